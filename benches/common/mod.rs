@@ -0,0 +1,88 @@
+//! Synthetic data for the benchmarks: deterministic, so runs compare like with
+//! like, and shaped like a clinical export (ids, dates, codes, numbers, notes).
+
+use std::fmt::Write;
+
+/// Column headers of the generated table
+pub const HEADERS: [&str; 8] = [
+    "subject_id",
+    "site",
+    "visit_date",
+    "arm",
+    "age",
+    "weight_kg",
+    "hb",
+    "comment",
+];
+
+/// Position of a column among `HEADERS`
+pub fn position(name: &str) -> usize {
+    HEADERS.iter().position(|h| *h == name).unwrap()
+}
+
+/// Small seedable generator (SplitMix64)
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in [0, n)
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n.max(1)
+    }
+}
+
+/// One row of the table; about one cell in twenty is missing
+pub fn row(rng: &mut Rng, index: u64) -> Vec<String> {
+    const COMMENTS: [&str; 4] = [
+        "Patient reports mild headache after dose",
+        "No adverse events",
+        "Follow-up call scheduled",
+        "Contact jane.doe@example.org about the next visit",
+    ];
+    let mut cells = vec![
+        format!("SUBJ-{:06}", index),
+        format!("S{:02}", rng.below(40)),
+        format!("2024-{:02}-{:02}", 1 + rng.below(12), 1 + rng.below(28)),
+        ["A", "B", "Placebo"][rng.below(3) as usize].to_string(),
+        (18 + rng.below(70)).to_string(),
+        format!("{}.{}", 45 + rng.below(80), rng.below(10)),
+        format!("{}.{}", 9 + rng.below(8), rng.below(10)),
+        COMMENTS[rng.below(COMMENTS.len() as u64) as usize].to_string(),
+    ];
+    for cell in cells.iter_mut().skip(2) {
+        if rng.below(20) == 0 {
+            cell.clear();
+        }
+    }
+    cells
+}
+
+/// A CSV file of `rows` rows
+pub fn csv(rows: u64) -> String {
+    let mut rng = Rng::new(rows);
+    let mut text = HEADERS.join(",");
+    text.push('\n');
+    for index in 0..rows {
+        let _ = writeln!(text, "{}", row(&mut rng, index).join(","));
+    }
+    text
+}
+
+/// `count` values of one column of the table
+pub fn column(index: usize, count: u64) -> Vec<String> {
+    let mut rng = Rng::new(index as u64);
+    (0..count)
+        .map(|i| std::mem::take(&mut row(&mut rng, i)[index]))
+        .collect()
+}
@@ -0,0 +1,128 @@
+//! Scanning throughput (rows per second through `extract_schema`) and the
+//! per-cell work of a scan, one piece at a time: type inference, PHI value
+//! patterns and unique value tracking.
+//!
+//! Run with `cargo bench`; `cargo bench -- inference` runs one group.
+
+mod common;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use ert_manifest::audit::NullAuditSink;
+use ert_manifest::inference::TypeInferencer;
+use ert_manifest::privacy::check_value_pattern;
+use ert_manifest::schema::extract_schema;
+use ert_manifest::stats::{CappedUniqueTracker, HyperLogLog};
+use ert_manifest::types::{ProcessingOptions, MAX_UNIQUE_VALUES};
+
+/// Values per column in the component benchmarks
+const VALUES: u64 = 10_000;
+
+fn csv_scan(c: &mut Criterion) {
+    let dir = tempfile::tempdir().unwrap();
+    let mut group = c.benchmark_group("csv_scan");
+    group.sample_size(10);
+    for rows in [10_000u64, 100_000] {
+        let path = dir.path().join(format!("visits_{}.csv", rows));
+        std::fs::write(&path, common::csv(rows)).unwrap();
+        group.throughput(Throughput::Elements(rows));
+        group.bench_with_input(BenchmarkId::from_parameter(rows), &path, |b, path| {
+            b.iter(|| {
+                extract_schema(path, ProcessingOptions::default(), &mut NullAuditSink).unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn csv_deep_scan(c: &mut Criterion) {
+    let dir = tempfile::tempdir().unwrap();
+    let rows = 10_000u64;
+    let path = dir.path().join("visits.csv");
+    std::fs::write(&path, common::csv(rows)).unwrap();
+    let options = ProcessingOptions {
+        deep_scan: true,
+        ..ProcessingOptions::default()
+    };
+    let mut group = c.benchmark_group("csv_deep_scan");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(rows));
+    group.bench_function(BenchmarkId::from_parameter(rows), |b| {
+        b.iter(|| extract_schema(&path, options.clone(), &mut NullAuditSink).unwrap())
+    });
+    group.finish();
+}
+
+fn inference(c: &mut Criterion) {
+    let mut group = c.benchmark_group("inference");
+    group.throughput(Throughput::Elements(VALUES));
+    for (index, name) in common::HEADERS.iter().enumerate() {
+        let values = common::column(index, VALUES);
+        group.bench_function(*name, |b| {
+            b.iter(|| {
+                let mut inferencer = TypeInferencer::new();
+                for value in &values {
+                    inferencer.observe(value);
+                }
+                inferencer.finalize_initial_inference();
+                inferencer.inferred_type()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn value_patterns(c: &mut Criterion) {
+    let mut group = c.benchmark_group("value_patterns");
+    group.throughput(Throughput::Elements(VALUES));
+    for name in ["subject_id", "visit_date", "comment"] {
+        let values = common::column(common::position(name), VALUES);
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                values
+                    .iter()
+                    .filter(|v| check_value_pattern(v).is_phi)
+                    .count()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn unique_tracking(c: &mut Criterion) {
+    let mut group = c.benchmark_group("unique_tracking");
+    group.throughput(Throughput::Elements(VALUES));
+    // The arms stay tracked; the subject ids go over the cap
+    for name in ["arm", "subject_id"] {
+        let values = common::column(common::position(name), VALUES);
+        group.bench_function(format!("capped/{}", name), |b| {
+            b.iter(|| {
+                let mut tracker = CappedUniqueTracker::new(MAX_UNIQUE_VALUES);
+                for value in &values {
+                    tracker.add(value);
+                }
+                black_box(tracker.unique_count())
+            })
+        });
+        group.bench_function(format!("hyperloglog/{}", name), |b| {
+            b.iter(|| {
+                let mut sketch = HyperLogLog::new(HyperLogLog::DEFAULT_PRECISION);
+                for value in &values {
+                    sketch.add(value);
+                }
+                sketch.estimate()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    csv_scan,
+    csv_deep_scan,
+    inference,
+    value_patterns,
+    unique_tracking
+);
+criterion_main!(benches);
@@ -0,0 +1,19 @@
+//! Delimited text: the same bytes read as CSV and as TSV
+
+#![no_main]
+
+use std::io::Write;
+
+use ert_manifest::audit::NullAuditSink;
+use ert_manifest::schema::extract_schema_as;
+use ert_manifest::types::{FileFormat, ProcessingOptions};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let mut file = tempfile::Builder::new().suffix(".csv").tempfile().unwrap();
+    file.write_all(data).unwrap();
+    for format in [FileFormat::Csv, FileFormat::Tsv] {
+        let options = ProcessingOptions::default();
+        let _ = extract_schema_as(file.path(), Some(format), options, &mut NullAuditSink);
+    }
+});
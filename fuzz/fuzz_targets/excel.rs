@@ -0,0 +1,25 @@
+//! Workbooks: the bytes of an .xlsx, .xls or .xlsb file.
+//!
+//! Start from a corpus of real workbooks; random bytes rarely get past the
+//! zip or OLE container.
+
+#![no_main]
+
+use std::io::Write;
+
+use ert_manifest::audit::NullAuditSink;
+use ert_manifest::schema::extract_schema_as;
+use ert_manifest::types::{FileFormat, ProcessingOptions};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let mut file = tempfile::Builder::new().suffix(".xlsx").tempfile().unwrap();
+    file.write_all(data).unwrap();
+    let options = ProcessingOptions::default();
+    let _ = extract_schema_as(
+        file.path(),
+        Some(FileFormat::Excel),
+        options,
+        &mut NullAuditSink,
+    );
+});
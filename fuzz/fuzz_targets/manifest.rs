@@ -0,0 +1,18 @@
+//! JSON manifests as `synth` and `redact` load them, of any version
+
+#![no_main]
+
+use ert_manifest::compat::{from_value, LoadedManifest};
+use ert_manifest::redact::{redact_manifest, redact_multi_manifest};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(value) = serde_json::from_slice(data) else {
+        return;
+    };
+    match from_value(value) {
+        Ok(LoadedManifest::Single(mut manifest)) => redact_manifest(&mut manifest),
+        Ok(LoadedManifest::Multi(mut manifest)) => redact_multi_manifest(&mut manifest),
+        Err(_) => {}
+    }
+});
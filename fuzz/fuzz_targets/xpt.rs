@@ -0,0 +1,22 @@
+//! SAS transport files (v5 and v8)
+
+#![no_main]
+
+use std::io::Write;
+
+use ert_manifest::audit::NullAuditSink;
+use ert_manifest::schema::extract_schema_as;
+use ert_manifest::types::{FileFormat, ProcessingOptions};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let mut file = tempfile::Builder::new().suffix(".xpt").tempfile().unwrap();
+    file.write_all(data).unwrap();
+    let options = ProcessingOptions::default();
+    let _ = extract_schema_as(
+        file.path(),
+        Some(FileFormat::Xpt),
+        options,
+        &mut NullAuditSink,
+    );
+});
@@ -0,0 +1,434 @@
+//! Privacy risk assessment (`assess`): which columns a scan would suppress,
+//! recode or flag, and how identifiable each sheet's data is, without writing
+//! a manifest. Meant for checks before an IRB or data-access submission.
+
+use std::collections::HashSet;
+
+use serde::Serialize;
+
+use crate::privacy::quasi_identifiers::quasi_identifier_kind;
+use crate::types::{Classification, ColumnSchema, ManifestSchema, SafeValue, SheetSchema};
+
+/// Fewest quasi-identifiers shared together that make a sheet high risk
+pub const HIGH_RISK_QUASI_IDENTIFIERS: usize = 3;
+
+/// Re-identification risk of a sheet's data
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RiskGrade {
+    Low,
+    Moderate,
+    High,
+}
+
+impl RiskGrade {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RiskGrade::Low => "low",
+            RiskGrade::Moderate => "moderate",
+            RiskGrade::High => "high",
+        }
+    }
+}
+
+/// A column the scan would withhold or flag
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ColumnFinding {
+    pub index: usize,
+    /// Column name (suppressed if it is PHI itself)
+    pub name: SafeValue,
+    /// First warning the scan gave the column
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// A shared column that could help single someone out in combination with others
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QuasiIdentifier {
+    pub index: usize,
+    pub name: SafeValue,
+    /// `age`, `sex`, `geography`, `date`, ...
+    pub kind: &'static str,
+}
+
+/// Assessment of one sheet
+#[derive(Debug, Clone, Serialize)]
+pub struct SheetAssessment {
+    pub sheet: String,
+    pub grade: RiskGrade,
+    /// What the grade is based on
+    pub signals: Vec<String>,
+    /// PHI and high-cardinality columns, whose values would be withheld
+    pub suppressed: Vec<ColumnFinding>,
+    /// Site-identifying columns, whose values would be replaced by labels
+    pub recoded: Vec<ColumnFinding>,
+    /// Columns that would be shared with a privacy warning
+    pub flagged: Vec<ColumnFinding>,
+    /// Shared columns that are quasi-identifiers
+    pub quasi_identifiers: Vec<QuasiIdentifier>,
+    /// Diversity of the sensitive columns within quasi-identifier groups
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sensitive_attributes: Vec<SensitiveAttribute>,
+}
+
+/// How well a sensitive column's values are mixed within the groups of a
+/// quasi-identifier combination
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SensitiveAttribute {
+    pub column: String,
+    pub quasi_identifiers: Vec<String>,
+    /// Groups with fewer than l distinct values of the column
+    pub groups_below_l: SafeValue,
+    pub percent_below_l: f64,
+}
+
+/// Privacy risk report for a file
+#[derive(Debug, Clone, Serialize)]
+pub struct Assessment {
+    pub file_name: String,
+    pub k_anonymity: u64,
+    /// Diversity threshold, when sensitive columns were given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub l_diversity: Option<u64>,
+    /// Highest grade of any sheet
+    pub grade: RiskGrade,
+    pub sheets: Vec<SheetAssessment>,
+    /// File-level warnings, such as names in document properties
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+/// Assess a scanned file
+pub fn assess(manifest: &ManifestSchema) -> Assessment {
+    let k = manifest.options.k_anonymity;
+    let l = manifest.options.l_diversity;
+    let sheets: Vec<SheetAssessment> =
+        manifest.sheets.iter().map(|sheet| assess_sheet(sheet, k, l)).collect();
+
+    // Sheet and column warnings are repeated at file level; keep only the file's own
+    let mut sheet_warnings: HashSet<String> = HashSet::new();
+    for sheet in &manifest.sheets {
+        for warning in &sheet.warnings {
+            sheet_warnings.insert(format!("Sheet '{}': {}", sheet.name, warning));
+        }
+        for col in &sheet.columns {
+            for warning in &col.warnings {
+                sheet_warnings.insert(format!(
+                    "Sheet '{}', Column {}: {}",
+                    sheet.name,
+                    col.index + 1,
+                    warning
+                ));
+            }
+        }
+    }
+
+    Assessment {
+        file_name: manifest.file_name.clone(),
+        k_anonymity: k,
+        l_diversity: (!manifest.options.sensitive_columns.is_empty()).then_some(l),
+        grade: sheets.iter().map(|s| s.grade).max().unwrap_or(RiskGrade::Low),
+        sheets,
+        warnings: manifest
+            .warnings
+            .iter()
+            .filter(|w| !sheet_warnings.contains(*w))
+            .cloned()
+            .collect(),
+    }
+}
+
+fn finding(column: &ColumnSchema) -> ColumnFinding {
+    ColumnFinding {
+        index: column.index,
+        name: column.name.clone(),
+        reason: column.warnings.first().cloned(),
+    }
+}
+
+fn assess_sheet(sheet: &SheetSchema, k: u64, l: u64) -> SheetAssessment {
+    let mut suppressed = Vec::new();
+    let mut recoded = Vec::new();
+    let mut flagged = Vec::new();
+    let mut quasi_identifiers = Vec::new();
+    let mut sensitive_attributes = Vec::new();
+    for column in &sheet.columns {
+        match column.classification {
+            Classification::Phi | Classification::HighCardinality => {
+                suppressed.push(finding(column));
+                continue;
+            }
+            Classification::Recode => {
+                recoded.push(finding(column));
+                continue;
+            }
+            Classification::Warning => flagged.push(finding(column)),
+            Classification::Safe => {}
+        }
+        if let SafeValue::ShortString(ref name) = column.name {
+            if let Some(kind) = quasi_identifier_kind(name, column.dtype) {
+                quasi_identifiers.push(QuasiIdentifier {
+                    index: column.index,
+                    name: column.name.clone(),
+                    kind,
+                });
+            }
+        }
+    }
+
+    let mut grade = RiskGrade::Low;
+    let mut signals = Vec::new();
+    let mut signal = |level: RiskGrade, text: String| {
+        grade = grade.max(level);
+        signals.push(text);
+    };
+    if !suppressed.is_empty() {
+        signal(
+            RiskGrade::High,
+            format!(
+                "{} column(s) hold direct identifiers or near-unique values and would be \
+                 suppressed",
+                suppressed.len()
+            ),
+        );
+    }
+    if quasi_identifiers.len() >= 2 {
+        let mut kinds: Vec<&str> = Vec::new();
+        for q in &quasi_identifiers {
+            if !kinds.contains(&q.kind) {
+                kinds.push(q.kind);
+            }
+        }
+        let level = if quasi_identifiers.len() >= HIGH_RISK_QUASI_IDENTIFIERS {
+            RiskGrade::High
+        } else {
+            RiskGrade::Moderate
+        };
+        signal(
+            level,
+            format!(
+                "{} quasi-identifiers would be shared together ({}); their combinations \
+                 can single out individuals",
+                quasi_identifiers.len(),
+                kinds.join(", ")
+            ),
+        );
+    }
+    for check in &sheet.quasi_identifier_checks {
+        if check.min_class_size < k {
+            signal(
+                RiskGrade::High,
+                format!(
+                    "Quasi-identifiers {}: {}% of rows are in groups of fewer than k={} rows \
+                     (smallest group: {})",
+                    check.columns.join(", "),
+                    check.percent_below_k,
+                    k,
+                    check.min_class_size
+                ),
+            );
+        }
+        for diversity in &check.l_diversity {
+            if diversity.percent_below_l > 0.0 {
+                signal(
+                    RiskGrade::High,
+                    format!(
+                        "Sensitive column {}: {}% of rows are in groups by {} with fewer than \
+                         l={} distinct values, so belonging to the group reveals it",
+                        diversity.column,
+                        diversity.percent_below_l,
+                        check.columns.join(", "),
+                        l
+                    ),
+                );
+            }
+            sensitive_attributes.push(SensitiveAttribute {
+                column: diversity.column.clone(),
+                quasi_identifiers: check.columns.clone(),
+                groups_below_l: diversity.groups_below_l.clone(),
+                percent_below_l: diversity.percent_below_l,
+            });
+        }
+    }
+    if !recoded.is_empty() {
+        signal(
+            RiskGrade::Moderate,
+            format!("{} column(s) identify sites and would be recoded", recoded.len()),
+        );
+    }
+    if !flagged.is_empty() {
+        signal(
+            RiskGrade::Moderate,
+            format!("{} column(s) would be shared with privacy warnings", flagged.len()),
+        );
+    }
+
+    SheetAssessment {
+        sheet: sheet.name.clone(),
+        grade,
+        signals,
+        suppressed,
+        recoded,
+        flagged,
+        quasi_identifiers,
+        sensitive_attributes,
+    }
+}
+
+/// Human-readable report
+pub fn format_assessment(assessment: &Assessment) -> String {
+    let thresholds = match assessment.l_diversity {
+        Some(l) => format!("k={}, l={}", assessment.k_anonymity, l),
+        None => format!("k={}", assessment.k_anonymity),
+    };
+    let mut lines = vec![
+        format!("Privacy assessment: {} ({})", assessment.file_name, thresholds),
+        format!("Overall risk: {}", assessment.grade.as_str()),
+    ];
+    for warning in &assessment.warnings {
+        lines.push(format!("  ! {}", warning));
+    }
+    for sheet in &assessment.sheets {
+        lines.push(String::new());
+        lines.push(format!("Sheet '{}': {} risk", sheet.sheet, sheet.grade.as_str()));
+        for signal in &sheet.signals {
+            lines.push(format!("  - {}", signal));
+        }
+        for (label, findings) in [
+            ("Suppressed", &sheet.suppressed),
+            ("Recoded", &sheet.recoded),
+            ("Flagged", &sheet.flagged),
+        ] {
+            if findings.is_empty() {
+                continue;
+            }
+            lines.push(format!("  {}:", label));
+            for f in findings {
+                let reason = f.reason.as_deref().map(|r| format!(": {}", r)).unwrap_or_default();
+                lines.push(format!("    column {} ({}){}", f.index + 1, f.name, reason));
+            }
+        }
+        if !sheet.quasi_identifiers.is_empty() {
+            lines.push("  Quasi-identifiers:".to_string());
+            for q in &sheet.quasi_identifiers {
+                lines.push(format!("    column {} ({}): {}", q.index + 1, q.name, q.kind));
+            }
+        }
+        if !sheet.sensitive_attributes.is_empty() {
+            lines.push("  Sensitive attributes:".to_string());
+            for s in &sheet.sensitive_attributes {
+                lines.push(format!(
+                    "    {} by {}: {} group(s), {}% of rows below l",
+                    s.column,
+                    s.quasi_identifiers.join(", "),
+                    s.groups_below_l,
+                    s.percent_below_l
+                ));
+            }
+        }
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{DType, DiversityCheck, FileFormat, QuasiIdentifierCheck};
+
+    fn column(name: &str, index: usize, dtype: DType, class: Classification) -> ColumnSchema {
+        let mut column = ColumnSchema::new(SafeValue::ShortString(name.to_string()), index, dtype);
+        column.classification = class;
+        column
+    }
+
+    #[test]
+    fn test_assess_grades() {
+        let mut manifest = ManifestSchema::new("trial.csv".to_string(), FileFormat::Csv);
+        let mut sheet = SheetSchema::new("trial.csv".to_string(), 0);
+        sheet.columns.push(column("arm", 0, DType::String, Classification::Safe));
+        sheet.columns.push(column("hba1c", 1, DType::Numeric, Classification::Safe));
+        manifest.sheets.push(sheet.clone());
+
+        let report = assess(&manifest);
+        assert_eq!(report.grade, RiskGrade::Low);
+        assert!(report.sheets[0].signals.is_empty());
+
+        sheet.columns.push(column("age", 2, DType::Integer, Classification::Safe));
+        sheet.columns.push(column("sex", 3, DType::String, Classification::Safe));
+        manifest.sheets[0] = sheet.clone();
+        let report = assess(&manifest);
+        assert_eq!(report.grade, RiskGrade::Moderate);
+        assert_eq!(report.sheets[0].quasi_identifiers.len(), 2);
+
+        // Measured groups smaller than k make it high
+        manifest.sheets[0].quasi_identifier_checks.push(QuasiIdentifierCheck {
+            columns: vec!["age".to_string(), "sex".to_string()],
+            suggested: true,
+            classes: SafeValue::Integer(12),
+            min_class_size: 1,
+            percent_below_k: 8.5,
+            l_diversity: Vec::new(),
+            set: None,
+        });
+        let report = assess(&manifest);
+        assert_eq!(report.grade, RiskGrade::High);
+        let signal = &report.sheets[0].signals[1];
+        assert!(signal.starts_with("Quasi-identifiers age, sex: 8.5% of rows"));
+
+        sheet.columns.push(column("visit_date", 4, DType::Date, Classification::Safe));
+        let mut mrn = column("mrn", 5, DType::String, Classification::Phi);
+        mrn.warnings.push("Column name matches PHI pattern 'mrn'".to_string());
+        sheet.columns.push(mrn);
+        manifest.sheets[0] = sheet;
+        manifest
+            .warnings
+            .push("Sheet 'trial.csv', Column 6: Column name matches PHI pattern 'mrn'".to_string());
+        manifest.warnings.push("File name looks like it holds PHI (name)".to_string());
+
+        let report = assess(&manifest);
+        assert_eq!(report.grade, RiskGrade::High);
+        let sheet = &report.sheets[0];
+        assert_eq!(sheet.suppressed.len(), 1);
+        assert_eq!(sheet.quasi_identifiers.len(), 3);
+        assert_eq!(sheet.signals.len(), 2);
+        assert_eq!(report.warnings, vec!["File name looks like it holds PHI (name)"]);
+
+        let text = format_assessment(&report);
+        assert!(text.contains("Overall risk: high"));
+        assert!(text.contains("column 6 (mrn): Column name matches PHI pattern 'mrn'"));
+        assert!(text.contains("column 5 (visit_date): date"));
+    }
+
+    #[test]
+    fn test_assess_sensitive_attributes() {
+        let mut manifest = ManifestSchema::new("trial.csv".to_string(), FileFormat::Csv);
+        manifest.options.sensitive_columns = vec!["hiv".to_string()];
+        let mut sheet = SheetSchema::new("trial.csv".to_string(), 0);
+        sheet.columns.push(column("arm", 0, DType::String, Classification::Safe));
+        sheet.quasi_identifier_checks.push(QuasiIdentifierCheck {
+            columns: vec!["age".to_string(), "sex".to_string()],
+            suggested: false,
+            classes: SafeValue::Integer(4),
+            min_class_size: 8,
+            percent_below_k: 0.0,
+            l_diversity: vec![DiversityCheck {
+                column: "hiv".to_string(),
+                groups_below_l: SafeValue::Integer(1),
+                percent_below_l: 25.0,
+                sensitive: 0,
+            }],
+            set: Some(0),
+        });
+        manifest.sheets.push(sheet);
+
+        let report = assess(&manifest);
+        assert_eq!(report.grade, RiskGrade::High);
+        assert_eq!(report.l_diversity, Some(2));
+        assert_eq!(report.sheets[0].sensitive_attributes.len(), 1);
+        assert!(report.sheets[0].signals[0].starts_with("Sensitive column hiv: 25% of rows"));
+
+        let text = format_assessment(&report);
+        assert!(text.contains("(k=5, l=2)"));
+        assert!(text.contains("hiv by age, sex: 1 group(s), 25% of rows below l"));
+    }
+}
@@ -0,0 +1,232 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::types::{Result, SafeValue};
+
+/// What was done to the data
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    /// Withheld from the manifest
+    Suppress,
+    /// Replaced with generic labels (originals go to the recode sidekick)
+    Recode,
+}
+
+/// Stable reason codes for privacy decisions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ReasonCode {
+    /// Column name matches a PHI pattern; its name and values are withheld
+    PhiColumnName,
+    /// Column name is too long to export
+    ColumnNameTooLong,
+    /// Column identifies a site
+    SiteIdentifier,
+    /// Value occurs fewer than k times
+    BelowK,
+    /// Value matches a PHI pattern
+    PhiValue,
+    /// Value is too long to export
+    ValueTooLong,
+    /// Too many distinct values to list
+    HighCardinality,
+    /// Values withheld by `--no-values-for` or `--no-values-columns`
+    ValuesWithheld,
+}
+
+/// One suppression or recode decision.
+///
+/// Never carries a raw value: value-level decisions identify the value by its
+/// SHA-256 hash only.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AuditDecision {
+    pub sheet: String,
+    /// Column index (0-based)
+    pub column_index: usize,
+    /// Column name as written to the manifest
+    pub column: SafeValue,
+    pub action: AuditAction,
+    /// Rule that fired (e.g. the matched PHI pattern)
+    pub rule: String,
+    pub reason: ReasonCode,
+    /// Number of values affected
+    pub count: u64,
+    /// SHA-256 of the affected value, for value-level decisions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value_hash: Option<String>,
+}
+
+/// Receives privacy decisions as files are processed
+pub trait AuditSink {
+    /// Subsequent decisions belong to this file
+    fn begin_file(&mut self, file_name: &str) -> Result<()>;
+
+    fn record(&mut self, decision: AuditDecision) -> Result<()>;
+
+    /// Called after each batch of rows with the rows read so far in this pass
+    /// (1: type inference, 2: statistics) of `sheet`. Returning an error stops
+    /// the scan (the GUI uses this to cancel).
+    fn progress(&mut self, _sheet: &str, _pass: u8, _rows: u64) -> Result<()> {
+        Ok(())
+    }
+
+    /// Make recorded decisions durable
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Discards all decisions (no `--audit-log`)
+pub struct NullAuditSink;
+
+impl AuditSink for NullAuditSink {
+    fn begin_file(&mut self, _file_name: &str) -> Result<()> {
+        Ok(())
+    }
+
+    fn record(&mut self, _decision: AuditDecision) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Appends one JSON object per decision to a log
+pub struct JsonlAuditSink<W: Write> {
+    writer: W,
+    file_name: String,
+}
+
+#[derive(Serialize)]
+struct AuditLine<'a> {
+    /// UTC timestamp (RFC 3339)
+    timestamp: String,
+    file_name: &'a str,
+    #[serde(flatten)]
+    decision: &'a AuditDecision,
+}
+
+impl JsonlAuditSink<BufWriter<File>> {
+    /// Open an audit log for appending, creating it (owner-only on Unix) if needed
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut open_options = OpenOptions::new();
+        open_options.create(true).append(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            open_options.mode(0o600);
+        }
+        Ok(Self::new(BufWriter::new(open_options.open(path)?)))
+    }
+}
+
+impl<W: Write> JsonlAuditSink<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            file_name: String::new(),
+        }
+    }
+}
+
+impl<W: Write> AuditSink for JsonlAuditSink<W> {
+    fn begin_file(&mut self, file_name: &str) -> Result<()> {
+        self.file_name = file_name.to_string();
+        Ok(())
+    }
+
+    fn record(&mut self, decision: AuditDecision) -> Result<()> {
+        let line = AuditLine {
+            timestamp: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            file_name: &self.file_name,
+            decision: &decision,
+        };
+        serde_json::to_writer(&mut self.writer, &line)?;
+        self.writer.write_all(b"\n")?;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+impl AuditSink for Vec<AuditDecision> {
+    fn begin_file(&mut self, _file_name: &str) -> Result<()> {
+        Ok(())
+    }
+
+    fn record(&mut self, decision: AuditDecision) -> Result<()> {
+        self.push(decision);
+        Ok(())
+    }
+}
+
+/// Hex SHA-256 of a value, so a decision can be matched to a value at the site
+/// without the value itself appearing in the log
+pub fn hash_value(value: &str) -> String {
+    format!("{:x}", Sha256::digest(value.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decision() -> AuditDecision {
+        AuditDecision {
+            sheet: "labs.csv".to_string(),
+            column_index: 2,
+            column: SafeValue::ShortString("mrn_note".to_string()),
+            action: AuditAction::Suppress,
+            rule: "mrn".to_string(),
+            reason: ReasonCode::PhiValue,
+            count: 7,
+            value_hash: Some(hash_value("MRN 1234567")),
+        }
+    }
+
+    #[test]
+    fn test_hash_value() {
+        assert_eq!(
+            hash_value("abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_jsonl_sink() {
+        let mut buffer = Vec::new();
+        let mut sink = JsonlAuditSink::new(&mut buffer);
+        sink.begin_file("labs.csv").unwrap();
+        sink.record(decision()).unwrap();
+        sink.record(decision()).unwrap();
+        let text = String::from_utf8(buffer).unwrap();
+
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let entry: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(entry["file_name"], "labs.csv");
+        assert_eq!(entry["reason"], "PHI_VALUE");
+        assert_eq!(entry["action"], "suppress");
+        assert_eq!(entry["count"], 7);
+        assert!(!text.contains("1234567"));
+    }
+
+    #[test]
+    fn test_open_appends() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+        for _ in 0..2 {
+            let mut sink = JsonlAuditSink::open(&path).unwrap();
+            sink.begin_file("labs.csv").unwrap();
+            sink.record(decision()).unwrap();
+            sink.flush().unwrap();
+        }
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 2);
+    }
+}
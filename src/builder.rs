@@ -0,0 +1,155 @@
+//! Building a manifest from rows pushed one at a time, for services that
+//! receive data over the network rather than as a file.
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use tempfile::TempDir;
+
+use crate::audit::{AuditSink, NullAuditSink};
+use crate::error::Error;
+use crate::schema::{extract_schema_as, ExtractionResult};
+use crate::tempfiles::{self, SecureTempFile};
+use crate::types::{FileFormat, HeaderMode, ManifestSchema, ProcessingOptions, Result};
+
+/// Profiles a table whose headers are known up front and whose rows arrive
+/// incrementally.
+///
+/// Profiling reads the data twice, so pushed rows are spooled as CSV to a
+/// [`SecureTempFile`] (owner-only, wiped when the builder is finished or
+/// dropped). The manifest is the one a scan of that CSV would give, reported
+/// under the builder's name.
+pub struct ManifestBuilder {
+    options: ProcessingOptions,
+    writer: csv::Writer<BufWriter<File>>,
+    rows: u64,
+    // Dropped in this order: the spool is wiped before its directory goes
+    spool: SecureTempFile,
+    _dir: TempDir,
+}
+
+impl ManifestBuilder {
+    /// Start a table with these column headers. `name` is the file name the
+    /// manifest reports (e.g. `visits.csv`).
+    pub fn new<S: AsRef<str>>(
+        name: &str,
+        headers: &[S],
+        options: ProcessingOptions,
+    ) -> Result<Self> {
+        let is_plain_name = Path::new(name).file_name().is_some_and(|n| n == name);
+        if !is_plain_name {
+            return Err(Error::InvalidInput(format!(
+                "manifest name must be a file name without folders: {}",
+                name
+            )));
+        }
+        if headers.is_empty() {
+            return Err(Error::InvalidInput(
+                "a table needs at least one column".to_string(),
+            ));
+        }
+
+        let dir = tempfiles::private_dir()?;
+        let spool = SecureTempFile::with_name_in(dir.path(), name)?;
+        let mut writer = csv::WriterBuilder::new()
+            .flexible(true)
+            .from_writer(BufWriter::new(spool.as_file().try_clone()?));
+        writer.write_record(headers.iter().map(|h| h.as_ref()))?;
+        Ok(Self {
+            // The headers are given, never detected
+            options: ProcessingOptions {
+                header: HeaderMode::Row(1),
+                header_rows: 1,
+                ..options
+            },
+            writer,
+            rows: 0,
+            spool,
+            _dir: dir,
+        })
+    }
+
+    /// Add one row. Rows shorter or longer than the headers are taken as they
+    /// are, like ragged lines in a CSV file.
+    pub fn push_row<S: AsRef<str>>(&mut self, row: &[S]) -> Result<()> {
+        self.writer.write_record(row.iter().map(|v| v.as_ref()))?;
+        self.rows += 1;
+        Ok(())
+    }
+
+    /// Add a batch of rows
+    pub fn push_batch<R: AsRef<[S]>, S: AsRef<str>>(&mut self, rows: &[R]) -> Result<()> {
+        for row in rows {
+            self.push_row(row.as_ref())?;
+        }
+        Ok(())
+    }
+
+    /// Rows pushed so far
+    pub fn row_count(&self) -> u64 {
+        self.rows
+    }
+
+    /// Profile the pushed rows into a manifest
+    pub fn finish(self) -> Result<ManifestSchema> {
+        Ok(self.finish_with_audit(&mut NullAuditSink)?.manifest)
+    }
+
+    /// Profile the pushed rows, reporting privacy decisions to `audit` and
+    /// keeping the recode mappings for the sidekick
+    pub fn finish_with_audit(mut self, audit: &mut dyn AuditSink) -> Result<ExtractionResult> {
+        self.writer.flush()?;
+        extract_schema_as(
+            self.spool.path(),
+            Some(FileFormat::Csv),
+            self.options.clone(),
+            audit,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Classification, SafeValue};
+
+    #[test]
+    fn test_manifest_builder() {
+        let options = ProcessingOptions {
+            bucket_counts: false,
+            ..ProcessingOptions::default()
+        };
+        let mut builder =
+            ManifestBuilder::new("visits.csv", &["patient_name", "arm", "age"], options).unwrap();
+        builder.push_row(&["Jane Doe", "A", "34"]).unwrap();
+        let batch: Vec<Vec<String>> = (0..20)
+            .map(|i| {
+                vec![
+                    format!("Person {}", i),
+                    "B".to_string(),
+                    (40 + i).to_string(),
+                ]
+            })
+            .collect();
+        builder.push_batch(&batch).unwrap();
+        // Commas and quotes survive the spool
+        builder.push_row(&["Doe, \"J\"", "A"]).unwrap();
+        assert_eq!(builder.row_count(), 22);
+
+        let manifest = builder.finish().unwrap();
+        assert_eq!(manifest.file_name, "visits.csv");
+        let sheet = &manifest.sheets[0];
+        assert_eq!(sheet.row_count, SafeValue::Integer(22));
+        assert_eq!(sheet.columns.len(), 3);
+        assert_eq!(sheet.columns[0].classification, Classification::Phi);
+        assert_eq!(sheet.columns[1].classification, Classification::Safe);
+    }
+
+    #[test]
+    fn test_manifest_builder_rejects_paths() {
+        let options = ProcessingOptions::default();
+        assert!(ManifestBuilder::new("../visits.csv", &["arm"], options.clone()).is_err());
+        assert!(ManifestBuilder::new("visits.csv", &[] as &[&str], options).is_err());
+    }
+}
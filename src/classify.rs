@@ -0,0 +1,83 @@
+//! The PHI checks on their own, for tools that classify column names or values
+//! without scanning a file (e.g. a form-design linter checking field names).
+
+use crate::privacy::cdisc;
+use crate::privacy::locales::{self, detect_language};
+use crate::privacy::value_patterns::ValuePatternResult;
+use crate::privacy::{check_column_name, check_value_pattern, ColumnNameResult};
+use crate::readers::encoding::repair_double_encoding;
+use crate::sanitize::sanitize_text;
+use crate::types::ProcessingOptions;
+
+/// Classify column names as a scan would: garbled names are repaired and
+/// control characters removed first, the locale packs in `options` apply (plus
+/// the language the names are in, with `auto_locales`), and with `cdisc`
+/// standard variables are classified by what the standard says they hold.
+///
+/// Reviewer overrides are not applied: they name a sheet, and these columns
+/// have none.
+pub fn classify_columns(headers: &[&str], options: &ProcessingOptions) -> Vec<ColumnNameResult> {
+    let headers: Vec<String> = headers
+        .iter()
+        .map(|h| {
+            let repaired = repair_double_encoding(h);
+            sanitize_text(repaired.as_deref().unwrap_or(h)).into_owned()
+        })
+        .collect();
+
+    let mut languages = options.locales.clone();
+    if options.auto_locales {
+        languages.extend(detect_language(&headers));
+    }
+    let packs: Vec<_> = languages.iter().copied().filter_map(locales::pack).collect();
+
+    headers
+        .iter()
+        .map(|h| {
+            let cdisc_match = options.cdisc.then(|| cdisc::match_variable(h, None)).flatten();
+            match cdisc_match {
+                Some(cdisc_match) => cdisc_match.name_check(),
+                None => check_column_name(h, &packs),
+            }
+        })
+        .collect()
+}
+
+/// Check a single value against the PHI value patterns (emails, phone numbers,
+/// national identifiers, ...) and any registered detectors and rule plugins
+pub fn classify_value(value: &str) -> ValuePatternResult {
+    check_value_pattern(&sanitize_text(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Classification, Language};
+
+    #[test]
+    fn test_classify_columns() {
+        let options = ProcessingOptions::default();
+        let names = ["patient_name", "age", "PrÃ©nom", "Geburtsname"];
+        let results = classify_columns(&names, &options);
+        let classes: Vec<_> = results.iter().map(|r| r.classification.clone()).collect();
+        assert_eq!(classes[0], Classification::Phi);
+        assert_eq!(classes[1], Classification::Safe);
+        // Repaired to "Prénom" before the patterns apply
+        assert_eq!(classes[2], Classification::Phi);
+        // Only the German pack knows it
+        assert_eq!(classes[3], Classification::Safe);
+
+        let options = ProcessingOptions {
+            locales: vec![Language::De],
+            ..ProcessingOptions::default()
+        };
+        let results = classify_columns(&["Geburtsname"], &options);
+        assert_eq!(results[0].classification, Classification::Phi);
+    }
+
+    #[test]
+    fn test_classify_value() {
+        assert!(classify_value("jane.doe@example.org").is_phi);
+        assert!(!classify_value("Arm A").is_phi);
+    }
+}
@@ -1,8 +1,9 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
 
-use crate::schema;
-use crate::types::{ProcessingOptions, DEFAULT_K_ANONYMITY};
+use crate::output::OutputFormat;
+use crate::privacy::RecodeFormat;
+use crate::types::{Classification, DatePrecision, DecimalSeparator, FileFormat};
 
 /// Privacy-preserving metadata extraction from data files
 #[derive(Parser, Debug)]
@@ -11,299 +12,449 @@ use crate::types::{ProcessingOptions, DEFAULT_K_ANONYMITY};
 pub struct Cli {
     #[command(subcommand)]
     pub command: Option<Commands>,
+
+    /// Scan history database (default: ~/.ert-manifest/history.sqlite)
+    #[arg(long, global = true)]
+    pub history_db: Option<PathBuf>,
+
+    /// Config file with default scan settings (default: ./ert-manifest.toml if present)
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+
+    /// Directory for temporary copies of input data (decompressed files, spooled
+    /// rows); default: the system temp directory
+    #[arg(long, global = true, value_name = "DIR")]
+    pub temp_dir: Option<PathBuf>,
+
+    /// Suppress progress and warning messages on stderr (errors are still reported)
+    #[arg(short, long, global = true)]
+    pub quiet: bool,
 }
 
 #[derive(Subcommand, Debug)]
 pub enum Commands {
-    /// Scan a data file and extract metadata
-    Scan {
-        /// Input file path
+    /// Scan a data file (or a directory of related files) and extract metadata
+    Scan(Box<ScanArgs>),
+
+    /// Report which columns a scan would suppress, recode or flag and each sheet's
+    /// re-identification risk, without writing a manifest
+    Assess {
+        /// Input data file
         #[arg(short, long)]
         input: PathBuf,
 
-        /// Output JSON file path (stdout if not specified)
+        /// K-anonymity threshold [default: 5]
+        #[arg(short, long)]
+        k: Option<u64>,
+
+        /// Extra locale pattern packs, as for `scan`
+        #[arg(long, value_enum, value_delimiter = ',')]
+        locales: Vec<LocaleChoice>,
+
+        /// Quasi-identifier combinations to measure, as for `scan`, besides the one
+        /// suggested from the column names
+        #[arg(long, value_name = "COLUMNS")]
+        quasi_identifiers: Vec<String>,
+
+        /// Sensitive columns to check the diversity of, as for `scan`
+        #[arg(long, value_name = "COLUMNS", value_delimiter = ',')]
+        sensitive_columns: Vec<String>,
+
+        /// Fewest distinct sensitive values per group, as for `scan` [default: 2]
+        #[arg(long, value_name = "L")]
+        l_diversity: Option<u64>,
+
+        /// Output the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Generate synthetic CSV data matching a manifest's schema and distributions
+    Synth {
+        /// Single-file JSON manifest to take columns and distributions from
+        #[arg(long)]
+        template: PathBuf,
+
+        /// Output CSV path (stdout if not specified)
         #[arg(short, long)]
         out: Option<PathBuf>,
 
-        /// K-anonymity threshold
-        #[arg(short, long, default_value_t = DEFAULT_K_ANONYMITY)]
-        k: u64,
+        /// Sheet to generate, by name or 0-based index [default: first sheet]
+        #[arg(long)]
+        sheet: Option<String>,
 
-        /// Bucket counts instead of exact values
-        #[arg(long, default_value_t = true)]
-        bucket_counts: bool,
+        /// Number of rows [default: the manifest's row count]
+        #[arg(long)]
+        rows: Option<usize>,
 
-        /// Use exact counts (requires --relaxed)
-        #[arg(long, default_value_t = false)]
-        exact_counts: bool,
+        /// Random seed; the same seed and manifest always give the same data
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
 
-        /// Use exact median (requires --relaxed)
-        #[arg(long, default_value_t = false)]
-        exact_median: bool,
+        /// Reproduce the correlations the manifest lists between numeric columns
+        #[arg(long)]
+        correlations: bool,
 
-        /// Compute file hash (SHA-256)
-        #[arg(long, default_value_t = true)]
-        hash_file: bool,
+        /// Overwrite an existing output file
+        #[arg(long)]
+        force: bool,
+    },
 
-        /// Enable relaxed mode (allows exact counts/median)
-        #[arg(long, default_value_t = false)]
-        relaxed: bool,
+    /// Derive an external-safe copy of a manifest without re-scanning the data
+    Redact {
+        /// JSON manifest (single- or multi-file) to redact
+        input: PathBuf,
+
+        /// Output path (stdout if not specified)
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+
+        /// Overwrite an existing output file
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Install and list privacy rule packs
+    Rules {
+        #[command(subcommand)]
+        command: RulesCommand,
     },
 
     /// Launch the GUI
     Gui,
-}
 
-/// GUI Application state
-#[derive(Debug, Clone, PartialEq)]
-pub enum GuiState {
-    Ready,
-    Processing,
-    Done,
-    Error(String),
-}
+    /// Show the log of previous scans
+    History {
+        /// Only show scans whose file name contains this text
+        #[arg(long)]
+        file: Option<String>,
 
-impl Default for GuiState {
-    fn default() -> Self {
-        GuiState::Ready
-    }
+        /// Maximum number of entries to show
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+
+        /// Output entries as JSON
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
 }
 
-/// GUI Application
-pub struct GuiApp {
-    state: GuiState,
-    dropped_file: Option<PathBuf>,
-    result: Option<String>,
-    warnings: Vec<String>,
-    options: ProcessingOptions,
+#[derive(Subcommand, Debug)]
+pub enum RulesCommand {
+    /// Install or update a signed rule pack
+    Update {
+        /// Rule pack file or http(s) URL
+        #[arg(long)]
+        from: String,
+
+        /// Detached signature file or URL [default: <from>.sig]
+        #[arg(long)]
+        signature: Option<String>,
+
+        /// Install even if an equal or newer version is installed
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// List the installed rule packs
+    List,
 }
 
-impl Default for GuiApp {
-    fn default() -> Self {
-        Self {
-            state: GuiState::Ready,
-            dropped_file: None,
-            result: None,
-            warnings: Vec::new(),
-            options: ProcessingOptions::default(),
-        }
-    }
+/// Options of the `scan` command
+#[derive(Args, Debug)]
+pub struct ScanArgs {
+    /// Input file path, a directory to scan every supported file in it,
+    /// or `-` to read CSV/TSV data from stdin
+    #[arg(short, long)]
+    pub input: PathBuf,
+
+    /// Read the input as this format instead of going by its extension
+    /// (stdin: default csv)
+    #[arg(long, value_enum)]
+    pub input_format: Option<InputFormat>,
+
+    /// Read the input as fixed-width text cut into columns by this layout CSV
+    /// (name, start, width, and optionally type, per column)
+    #[arg(long, conflicts_with = "input_format")]
+    pub layout: Option<PathBuf>,
+
+    /// Output file path (stdout if not specified)
+    #[arg(short, long)]
+    pub out: Option<PathBuf>,
+
+    /// Output format [default: json]
+    #[arg(long, value_enum)]
+    pub format: Option<OutputFormat>,
+
+    /// Render the report with this Handlebars template instead of a built-in format
+    #[arg(long, conflicts_with = "format")]
+    pub template: Option<PathBuf>,
+
+    /// Compare the columns with this codebook CSV (name, and optionally dtype
+    /// and sheet, columns); single-file scans
+    #[arg(long)]
+    pub codebook: Option<PathBuf>,
+
+    /// Check the columns against this list of variables approved for sharing
+    /// (one name or `*`/`?` pattern per line); single-file scans
+    #[arg(long)]
+    pub scope: Option<PathBuf>,
+
+    /// Count rows breaking the cross-column rules in this file (one rule per
+    /// line, e.g. `discharge_date >= admission_date` or `age between 0 and 120`)
+    #[arg(long)]
+    pub validation_rules: Option<PathBuf>,
+
+    /// Recode sidekick path (single-file scans; default: next to the input)
+    #[arg(long, visible_alias = "recode-out")]
+    pub sidekick_out: Option<PathBuf>,
+
+    /// Recode sidekick format
+    #[arg(long, value_enum, default_value_t = RecodeFormat::Txt)]
+    pub recode_format: RecodeFormat,
+
+    /// Do not write the recode sidekick; only report how many values were recoded
+    #[arg(long, conflicts_with_all = ["sidekick_out", "recode_format"])]
+    pub no_sidekick: bool,
+
+    /// Directory for generated files: sidekicks, dataset.json and a relative --out path
+    #[arg(long)]
+    pub out_dir: Option<PathBuf>,
+
+    /// Source text encoding for CSV/TSV (e.g. windows-1252); detected if not given
+    #[arg(long)]
+    pub encoding: Option<String>,
+
+    /// Header is on this row (1-based); rows above it are skipped
+    #[arg(long, value_name = "N", conflicts_with = "no_header")]
+    pub header_row: Option<usize>,
+
+    /// The file has no header row; columns are named Column1..N
+    #[arg(long)]
+    pub no_header: bool,
+
+    /// The header spans N stacked rows (e.g. a group row over the variable names),
+    /// joined into one name per column; merged header cells are spread over their span
+    #[arg(long, value_name = "N", conflicts_with = "no_header")]
+    pub header_rows: Option<usize>,
+
+    /// Append every suppression/recode decision to this JSONL audit log
+    #[arg(long, value_name = "PATH")]
+    pub audit_log: Option<PathBuf>,
+
+    /// Overwrite an existing output file
+    #[arg(long, conflicts_with = "versioned")]
+    pub force: bool,
+
+    /// Add a timestamp to the output file name instead of overwriting
+    /// (defaults to <input>.manifest.<timestamp>.<ext> when --out is not given)
+    #[arg(long)]
+    pub versioned: bool,
+
+    /// Exit with status 4 (after writing the output) if any column is of this
+    /// class or a more severe one, to block a data transfer in a pipeline
+    #[arg(long, value_enum, value_name = "SEVERITY", default_value_t = FailOn::None)]
+    pub fail_on: FailOn,
+
+    /// K-anonymity threshold [default: 5]
+    #[arg(short, long)]
+    pub k: Option<u64>,
+
+    /// Check every cell of string columns for PHI value patterns (emails, phone
+    /// numbers, ... also inside longer text) and report hit counts per column
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pub deep_scan: Option<bool>,
+
+    /// Cross-check PHI column names against the values: a column whose name matches a
+    /// PHI pattern but holds a few repeated categories that look like no PHI (e.g.
+    /// `name` holding "Treatment A/B/C") gets a warning instead of being suppressed
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pub content_aware: Option<bool>,
+
+    /// Recognize CDISC SDTM/ADaM variables (USUBJID, SITEID, BRTHDTC, ...), classify
+    /// them by the standard and name the matched variable in the manifest
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pub cdisc: Option<bool>,
+
+    /// Add up to three example values to each safe column (values seen at least
+    /// k times that match no PHI pattern)
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pub examples: Option<bool>,
+
+    /// Measure the k-anonymity of a combination of quasi-identifier columns, given as
+    /// comma-separated column names (repeat for several); `auto` combines the columns
+    /// whose names mark them as quasi-identifiers (age, sex, region, dates, ...)
+    #[arg(long, value_name = "COLUMNS")]
+    pub quasi_identifiers: Vec<String>,
+
+    /// Sensitive columns (e.g. HIV status), comma-separated: report quasi-identifier
+    /// groups whose rows hold fewer than --l-diversity distinct values of them
+    #[arg(long, value_name = "COLUMNS", value_delimiter = ',')]
+    pub sensitive_columns: Vec<String>,
+
+    /// Fewest distinct values of each sensitive column a quasi-identifier group
+    /// should hold [default: 2]
+    #[arg(long, value_name = "L")]
+    pub l_diversity: Option<u64>,
+
+    /// Leave hidden and very hidden workbook sheets out of the scan (they are
+    /// scanned by default, since they often hold the raw data)
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pub skip_hidden_sheets: Option<bool>,
+
+    /// Also check workbook cell comments and the text in formulas for PHI;
+    /// only counts of hits are reported
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pub scan_comments: Option<bool>,
+
+    /// Workbook sheets (or transport datasets) to scan, comma-separated names or
+    /// `*`/`?` patterns; the others are skipped
+    #[arg(long, value_name = "SHEETS", value_delimiter = ',')]
+    pub sheets: Vec<String>,
+
+    /// Sheets to skip, comma-separated names or `*`/`?` patterns
+    #[arg(long, value_name = "SHEETS", value_delimiter = ',')]
+    pub exclude_sheets: Vec<String>,
+
+    /// Columns to scan, comma-separated names or `*`/`?` patterns; the others
+    /// are left out of the manifest
+    #[arg(long, value_name = "COLUMNS", value_delimiter = ',')]
+    pub columns: Vec<String>,
+
+    /// Columns to leave out, comma-separated names or `*`/`?` patterns
+    #[arg(long, value_name = "COLUMNS", value_delimiter = ',')]
+    pub exclude_columns: Vec<String>,
+
+    /// Never export values of columns of these classes, comma-separated (e.g.
+    /// `warning,high_cardinality`); columns whose name puts them in one are
+    /// not even collected
+    #[arg(long, value_enum, value_name = "CLASSES", value_delimiter = ',')]
+    pub no_values_for: Vec<Classification>,
+
+    /// Never collect or export values of these columns, comma-separated names
+    /// or `*`/`?` patterns
+    #[arg(long, value_name = "COLUMNS", value_delimiter = ',')]
+    pub no_values_columns: Vec<String>,
+
+    /// Distinct values tracked per column before it is treated as high
+    /// cardinality; can only lower the default [default: 2000]
+    #[arg(long, value_name = "N")]
+    pub max_unique_values: Option<usize>,
+
+    /// Profile only a random sample of N rows of each table, for a quick first
+    /// look at a very large file; counts are the sample's
+    #[arg(long, value_name = "N", conflicts_with = "sample_fraction")]
+    pub sample_rows: Option<u64>,
+
+    /// Profile only this share of the rows of each table (e.g. 0.01), evenly
+    /// spread; counts are the sample's
+    #[arg(long, value_name = "P")]
+    pub sample_fraction: Option<f64>,
+
+    /// Column holding subject IDs (e.g. `USUBJID`); a directory scan counts
+    /// the distinct subjects across its files by salted hashes of its values
+    #[arg(long, value_name = "COLUMN")]
+    pub subject_column: Option<String>,
+
+    /// Replace file and sheet names that look like PHI (a person's name, an email
+    /// address, ...) with neutral ones in the manifest
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pub redact_names: Option<bool>,
+
+    /// Extra locale pattern packs for column names, comma-separated; `auto` enables
+    /// the pack matching the language of each table's column names
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub locales: Vec<LocaleChoice>,
+
+    /// Precision of earliest/latest dates in date columns [default: month]
+    #[arg(long, value_enum)]
+    pub date_precision: Option<DatePrecision>,
+
+    /// Decimal separator of numbers in text files [default: detected per column]
+    #[arg(long, value_enum)]
+    pub decimal_separator: Option<DecimalSeparator>,
+
+    /// Extra values counted as missing, comma-separated or repeated (e.g. -99,UNK)
+    #[arg(long, value_delimiter = ',', allow_hyphen_values = true)]
+    pub missing_tokens: Vec<String>,
+
+    /// Bucket counts instead of exact values [default: true]
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pub bucket_counts: Option<bool>,
+
+    /// Use exact counts (requires --relaxed) [default: false]
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pub exact_counts: Option<bool>,
+
+    /// Use exact median (requires --relaxed) [default: false]
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pub exact_median: Option<bool>,
+
+    /// Compute file hash (SHA-256) [default: true]
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pub hash_file: Option<bool>,
+
+    /// Enable relaxed mode (allows exact counts/median) [default: false]
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pub relaxed: Option<bool>,
 }
 
-impl eframe::App for GuiApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // Handle dropped files
-        ctx.input(|i| {
-            if !i.raw.dropped_files.is_empty() {
-                if let Some(path) = i.raw.dropped_files[0].path.clone() {
-                    self.dropped_file = Some(path);
-                    self.state = GuiState::Processing;
-                }
-            }
-        });
-
-        // Process file if needed
-        if self.state == GuiState::Processing {
-            if let Some(ref path) = self.dropped_file {
-                match schema::extract_schema(path, self.options.clone()) {
-                    Ok(extraction_result) => {
-                        let manifest = &extraction_result.manifest;
-                        self.warnings = manifest.warnings.clone();
-                        for sheet in &manifest.sheets {
-                            self.warnings.extend(sheet.warnings.clone());
-                            for col in &sheet.columns {
-                                self.warnings.extend(col.warnings.clone());
-                            }
-                        }
-
-                        // Write sidekick file if recoding was done
-                        if let Some(ref sidekick_content) = extraction_result.recode_sidekick {
-                            let sidekick_path = path.with_extension("recode.txt");
-                            if let Err(e) = std::fs::write(&sidekick_path, sidekick_content) {
-                                self.warnings.push(format!("Failed to write recode file: {}", e));
-                            } else {
-                                self.warnings.push(format!(
-                                    "Recode mapping saved to: {}",
-                                    sidekick_path.display()
-                                ));
-                            }
-                        }
-
-                        match crate::output::to_json_string(manifest) {
-                            Ok(json) => {
-                                self.result = Some(json);
-                                self.state = GuiState::Done;
-                            }
-                            Err(e) => {
-                                self.state = GuiState::Error(e.to_string());
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        self.state = GuiState::Error(e.to_string());
-                    }
-                }
-            }
-        }
+/// A locale pattern pack (English, French, and Portuguese are always on)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LocaleChoice {
+    /// The pack matching the detected column-name language
+    Auto,
+    /// Spanish
+    Es,
+    /// German
+    De,
+    /// English: UK postcodes in values (column names are always checked)
+    En,
+    /// Portuguese: Brazilian phone numbers and postal codes in values (column names are
+    /// always checked)
+    Pt,
+}
 
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.heading("ert-manifest v0.1.1");
-            ui.add_space(10.0);
-
-            match &self.state {
-                GuiState::Ready => {
-                    self.show_ready_state(ui);
-                }
-                GuiState::Processing => {
-                    ui.spinner();
-                    ui.label("Processing file...");
-                }
-                GuiState::Done => {
-                    self.show_done_state(ui, ctx);
-                }
-                GuiState::Error(msg) => {
-                    let msg = msg.clone();
-                    self.show_error_state(ui, msg);
-                }
-            }
-        });
-    }
+/// Column class at which `scan --fail-on` fails; each level includes the more
+/// severe ones after it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum FailOn {
+    /// Never fail
+    None,
+    /// Columns flagged with a privacy warning
+    Warning,
+    /// Columns with too many distinct values to list
+    HighCardinality,
+    /// Columns holding PHI
+    Phi,
 }
 
-impl GuiApp {
-    fn show_ready_state(&mut self, ui: &mut egui::Ui) {
-        // Drag and drop zone
-        let drop_zone = egui::Frame::none()
-            .fill(egui::Color32::from_gray(40))
-            .stroke(egui::Stroke::new(2.0, egui::Color32::from_gray(100)))
-            .rounding(10.0)
-            .inner_margin(40.0);
-
-        drop_zone.show(ui, |ui| {
-            ui.vertical_centered(|ui| {
-                ui.label("Drag and drop a data file here");
-                ui.label("(CSV, TSV, Excel)");
-                ui.add_space(20.0);
-                ui.label("or");
-                ui.add_space(10.0);
-                if ui.button("Browse...").clicked() {
-                    if let Some(path) = rfd::FileDialog::new()
-                        .add_filter("Data files", &["csv", "tsv", "xlsx", "xls"])
-                        .pick_file()
-                    {
-                        self.dropped_file = Some(path);
-                        self.state = GuiState::Processing;
-                    }
-                }
-            });
-        });
-
-        ui.add_space(20.0);
-
-        // Options
-        ui.collapsing("Options", |ui| {
-            ui.horizontal(|ui| {
-                ui.label("K-anonymity:");
-                let mut k = self.options.k_anonymity as i32;
-                if ui.add(egui::Slider::new(&mut k, 1..=20)).changed() {
-                    self.options.k_anonymity = k as u64;
-                }
-            });
-
-            ui.checkbox(&mut self.options.bucket_counts, "Bucket counts");
-            ui.checkbox(&mut self.options.hash_file, "Compute file hash");
-
-            ui.add_enabled_ui(self.options.relaxed, |ui| {
-                ui.checkbox(&mut self.options.exact_counts, "Exact counts");
-                ui.checkbox(&mut self.options.exact_median, "Exact median");
-            });
-
-            ui.checkbox(&mut self.options.relaxed, "Relaxed mode");
-        });
+impl FailOn {
+    /// Whether a column of this class fails the scan. Recoded and safe columns
+    /// never do: their values are safe to share as written to the manifest.
+    pub fn fails(self, classification: &Classification) -> bool {
+        let severity = match classification {
+            Classification::Safe | Classification::Recode => return false,
+            Classification::Warning => FailOn::Warning,
+            Classification::HighCardinality => FailOn::HighCardinality,
+            Classification::Phi => FailOn::Phi,
+        };
+        self != FailOn::None && severity >= self
     }
+}
 
-    fn show_done_state(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
-        // Show warnings if any
-        let warnings_count = self.warnings.len();
-        if warnings_count > 0 {
-            let warnings_clone = self.warnings.clone();
-            ui.collapsing(format!("Warnings ({})", warnings_count), |ui| {
-                for warning in &warnings_clone {
-                    ui.colored_label(egui::Color32::YELLOW, warning);
-                }
-            });
-            ui.add_space(10.0);
-        }
-
-        // Show result
-        let json_clone = self.result.clone();
-        if let Some(ref json) = json_clone {
-            ui.label("Manifest generated successfully!");
-            ui.add_space(10.0);
-
-            let mut should_reset = false;
-            let mut should_copy = false;
-            let mut save_path: Option<PathBuf> = None;
-
-            ui.horizontal(|ui| {
-                if ui.button("Copy to clipboard").clicked() {
-                    should_copy = true;
-                }
-
-                if ui.button("Save to file...").clicked() {
-                    save_path = rfd::FileDialog::new()
-                        .add_filter("JSON", &["json"])
-                        .save_file();
-                }
-
-                if ui.button("New file").clicked() {
-                    should_reset = true;
-                }
-            });
-
-            if should_copy {
-                ctx.copy_text(json.clone());
-            }
-
-            if let Some(path) = save_path {
-                if let Err(e) = std::fs::write(&path, json) {
-                    self.state = GuiState::Error(e.to_string());
-                    return;
-                }
-            }
-
-            if should_reset {
-                self.reset();
-                return;
-            }
-
-            ui.add_space(10.0);
-
-            egui::ScrollArea::vertical()
-                .max_height(400.0)
-                .show(ui, |ui| {
-                    let mut text = json.as_str();
-                    ui.add(
-                        egui::TextEdit::multiline(&mut text)
-                            .code_editor()
-                            .desired_width(f32::INFINITY),
-                    );
-                });
-        }
-    }
+/// Formats that can be read regardless of file extension (and from stdin)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum InputFormat {
+    Csv,
+    Tsv,
+}
 
-    fn show_error_state(&mut self, ui: &mut egui::Ui, msg: String) {
-        ui.colored_label(egui::Color32::RED, format!("Error: {}", msg));
-        ui.add_space(20.0);
-        if ui.button("Try again").clicked() {
-            self.reset();
+impl From<InputFormat> for FileFormat {
+    fn from(format: InputFormat) -> Self {
+        match format {
+            InputFormat::Csv => FileFormat::Csv,
+            InputFormat::Tsv => FileFormat::Tsv,
         }
     }
-
-    fn reset(&mut self) {
-        self.state = GuiState::Ready;
-        self.dropped_file = None;
-        self.result = None;
-        self.warnings.clear();
-    }
 }
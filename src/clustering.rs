@@ -0,0 +1,116 @@
+use std::collections::BTreeMap;
+
+use crate::privacy::locales::fold_accents;
+
+/// Key shared by spellings of the same category: lowercase, without accents
+/// or punctuation, with its words sorted ("Montréal " and "MONTREAL" give
+/// "montreal", "Smith, J." and "j smith" give "j smith")
+pub fn fingerprint(value: &str) -> String {
+    let folded = fold_accents(&value.to_lowercase());
+    let mut words: Vec<&str> = folded
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+    words.sort_unstable();
+    words.dedup();
+    words.join(" ")
+}
+
+/// Distinct values that share a fingerprint
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cluster<'a> {
+    /// Spellings with their counts, most frequent first
+    pub members: Vec<(&'a str, u64)>,
+}
+
+impl Cluster<'_> {
+    pub fn total(&self) -> u64 {
+        self.members.iter().map(|(_, n)| n).sum()
+    }
+}
+
+/// Group values with their counts into clusters of two or more spellings,
+/// largest cluster first
+pub fn near_duplicates<'a>(values: impl IntoIterator<Item = (&'a str, u64)>) -> Vec<Cluster<'a>> {
+    clusters_by(values, fingerprint)
+}
+
+/// Group values that differ only in letter case ("male", "Male", "MALE"),
+/// largest group first
+pub fn case_variants<'a>(values: impl IntoIterator<Item = (&'a str, u64)>) -> Vec<Cluster<'a>> {
+    clusters_by(values, str::to_lowercase)
+}
+
+/// Clusters of two or more values sharing a non-empty key, largest first
+fn clusters_by<'a>(
+    values: impl IntoIterator<Item = (&'a str, u64)>,
+    key_of: impl Fn(&str) -> String,
+) -> Vec<Cluster<'a>> {
+    let mut groups: BTreeMap<String, Vec<(&'a str, u64)>> = BTreeMap::new();
+    for (value, count) in values {
+        let key = key_of(value);
+        if !key.is_empty() {
+            groups.entry(key).or_default().push((value, count));
+        }
+    }
+
+    let mut clusters: Vec<Cluster> = groups
+        .into_values()
+        .filter(|members| members.len() > 1)
+        .map(|mut members| {
+            members.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+            Cluster { members }
+        })
+        .collect();
+    clusters.sort_by_key(|c| std::cmp::Reverse(c.total()));
+    clusters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint() {
+        assert_eq!(fingerprint("Montréal"), "montreal");
+        assert_eq!(fingerprint("MONTREAL "), "montreal");
+        assert_eq!(fingerprint("Smith, J."), "j smith");
+        assert_eq!(fingerprint("--"), "");
+    }
+
+    #[test]
+    fn test_near_duplicates() {
+        let values = [
+            ("Montréal", 40),
+            ("Montreal", 12),
+            ("MONTREAL ", 3),
+            ("Québec", 20),
+            ("Quebec City", 5),
+            ("Laval", 9),
+        ];
+        let clusters = near_duplicates(values);
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(
+            clusters[0].members,
+            vec![("Montréal", 40), ("Montreal", 12), ("MONTREAL ", 3)]
+        );
+        assert_eq!(clusters[0].total(), 55);
+    }
+
+    #[test]
+    fn test_case_variants() {
+        let values = [
+            ("male", 40),
+            ("Male", 12),
+            ("MALE", 3),
+            ("female", 30),
+            ("Female ", 2),
+            ("Montréal", 5),
+            ("MONTRÉAL", 4),
+        ];
+        let groups = case_variants(values);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].members, vec![("male", 40), ("Male", 12), ("MALE", 3)]);
+        assert_eq!(groups[1].members, vec![("Montréal", 5), ("MONTRÉAL", 4)]);
+    }
+}
@@ -0,0 +1,293 @@
+use std::path::Path;
+
+use crate::error::Error;
+use crate::paths;
+use crate::types::{
+    CodebookFindings, DType, DtypeMismatch, ManifestSchema, Result, SafeValue, UnexpectedColumn,
+};
+
+/// Lowest Jaro-Winkler similarity (0-1) for suggesting a missing codebook column
+const SIMILARITY_THRESHOLD: f64 = 0.85;
+
+/// One expected column from a codebook
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodebookEntry {
+    pub name: String,
+    pub dtype: Option<DType>,
+    /// Sheet the column is expected in; any sheet if not given
+    pub sheet: Option<String>,
+}
+
+/// A study codebook: the columns a data file is expected to have
+#[derive(Debug, Clone, PartialEq)]
+pub struct Codebook {
+    pub file_name: String,
+    pub entries: Vec<CodebookEntry>,
+}
+
+impl Codebook {
+    /// Read a codebook CSV with a `name` column and optional `dtype` and
+    /// `sheet` columns (other columns, e.g. labels, are ignored)
+    pub fn load(path: &Path) -> Result<Self> {
+        let invalid = |problem: String| {
+            Error::InvalidInput(format!(
+                "codebook {}: {}",
+                paths::display_path(path),
+                problem
+            ))
+        };
+
+        let mut reader = csv::ReaderBuilder::new()
+            .flexible(true)
+            .from_path(paths::for_fs_access(path))?;
+        let headers: Vec<String> = reader
+            .headers()?
+            .iter()
+            .map(|h| h.trim().trim_start_matches('\u{feff}').to_lowercase())
+            .collect();
+        let column = |names: &[&str]| headers.iter().position(|h| names.contains(&h.as_str()));
+        let name_col = column(&["name", "variable", "column"])
+            .ok_or_else(|| invalid("no 'name' column".to_string()))?;
+        let dtype_col = column(&["dtype", "type"]);
+        let sheet_col = column(&["sheet"]);
+
+        let mut entries = Vec::new();
+        for (i, record) in reader.records().enumerate() {
+            let record = record?;
+            let field = |col: Option<usize>| {
+                col.and_then(|c| record.get(c))
+                    .map(str::trim)
+                    .filter(|v| !v.is_empty())
+            };
+            let Some(name) = field(Some(name_col)) else {
+                continue;
+            };
+            let dtype =
+                match field(dtype_col) {
+                    Some(text) => Some(DType::from_name(text).ok_or_else(|| {
+                        invalid(format!("line {}: unknown dtype '{}'", i + 2, text))
+                    })?),
+                    None => None,
+                };
+            entries.push(CodebookEntry {
+                name: name.to_string(),
+                dtype,
+                sheet: field(sheet_col).map(str::to_string),
+            });
+        }
+
+        Ok(Self {
+            file_name: paths::file_name_lossy(path),
+            entries,
+        })
+    }
+
+    /// Compare a manifest's columns with the codebook
+    pub fn check(&self, manifest: &ManifestSchema) -> CodebookFindings {
+        let mut findings = CodebookFindings {
+            codebook: self.file_name.clone(),
+            ..Default::default()
+        };
+        let mut found = vec![false; self.entries.len()];
+
+        for sheet in &manifest.sheets {
+            for column in &sheet.columns {
+                let SafeValue::ShortString(ref name) = column.name else {
+                    findings.unchecked_columns += 1;
+                    continue;
+                };
+                let entry = self.entries.iter().position(|e| {
+                    e.name.eq_ignore_ascii_case(name) && e.sheet.iter().all(|s| *s == sheet.name)
+                });
+                let Some(entry) = entry else {
+                    findings.unexpected_columns.push(UnexpectedColumn {
+                        sheet: sheet.name.clone(),
+                        column_index: column.index,
+                        name: name.clone(),
+                        similar_to: None,
+                    });
+                    continue;
+                };
+
+                found[entry] = true;
+                if let Some(expected) = self.entries[entry].dtype {
+                    if !column.dtype.satisfies(expected) {
+                        findings.dtype_mismatches.push(DtypeMismatch {
+                            sheet: sheet.name.clone(),
+                            column_index: column.index,
+                            name: name.clone(),
+                            expected,
+                            found: column.dtype,
+                        });
+                    }
+                }
+            }
+        }
+
+        findings.missing_columns = self
+            .entries
+            .iter()
+            .zip(&found)
+            .filter(|(_, found)| !**found)
+            .map(|(e, _)| e.name.clone())
+            .collect();
+        for column in &mut findings.unexpected_columns {
+            column.similar_to = most_similar(&column.name, &findings.missing_columns);
+        }
+        findings
+    }
+}
+
+/// The candidate most similar to `name`, if similar enough
+fn most_similar(name: &str, candidates: &[String]) -> Option<String> {
+    let name = normalize(name);
+    candidates
+        .iter()
+        .map(|c| (similarity(&name, &normalize(c)), c))
+        .filter(|(score, _)| *score >= SIMILARITY_THRESHOLD)
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, c)| c.clone())
+}
+
+/// Lowercase alphanumerics, so `SBP_Baseline` and `sbp.baseline` compare equal
+fn normalize(name: &str) -> Vec<char> {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Jaro-Winkler similarity: rewards a shared prefix, so abbreviations
+/// (`sbp_bl` for `sbp_baseline`) score well
+fn similarity(a: &[char], b: &[char]) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let window = (a.len().max(b.len()) / 2).saturating_sub(1);
+    let mut a_matched = vec![false; a.len()];
+    let mut b_matched = vec![false; b.len()];
+    let mut matches = 0usize;
+    for (i, ca) in a.iter().enumerate() {
+        let end = (i + window + 1).min(b.len());
+        for j in i.saturating_sub(window)..end {
+            if !b_matched[j] && b[j] == *ca {
+                a_matched[i] = true;
+                b_matched[j] = true;
+                matches += 1;
+                break;
+            }
+        }
+    }
+    if matches == 0 {
+        return 0.0;
+    }
+
+    let a_order = a
+        .iter()
+        .zip(&a_matched)
+        .filter(|(_, m)| **m)
+        .map(|(c, _)| c);
+    let b_order = b
+        .iter()
+        .zip(&b_matched)
+        .filter(|(_, m)| **m)
+        .map(|(c, _)| c);
+    let transpositions = a_order.zip(b_order).filter(|(x, y)| x != y).count() / 2;
+
+    let m = matches as f64;
+    let jaro = (m / a.len() as f64 + m / b.len() as f64 + (m - transpositions as f64) / m) / 3.0;
+    let prefix = a.iter().zip(b).take(4).take_while(|(x, y)| x == y).count();
+    jaro + prefix as f64 * 0.1 * (1.0 - jaro)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ColumnSchema, FileFormat, SheetSchema};
+
+    fn manifest() -> ManifestSchema {
+        let mut sheet = SheetSchema::new("trial.csv".to_string(), 0);
+        let columns = [
+            ("id", DType::Integer),
+            ("age", DType::Integer),
+            ("sbp_bl", DType::Numeric),
+            ("visit_date", DType::String),
+        ];
+        for (i, (name, dtype)) in columns.into_iter().enumerate() {
+            sheet.columns.push(ColumnSchema::new(
+                SafeValue::ShortString(name.to_string()),
+                i,
+                dtype,
+            ));
+        }
+        sheet.columns.push(ColumnSchema::new(
+            SafeValue::Suppressed {
+                reason: "PHI".to_string(),
+            },
+            4,
+            DType::String,
+        ));
+        let mut manifest = ManifestSchema::new("trial.csv".to_string(), FileFormat::Csv);
+        manifest.sheets.push(sheet);
+        manifest
+    }
+
+    #[test]
+    fn test_check_codebook() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("codebook.csv");
+        std::fs::write(
+            &path,
+            "Variable,Label,Type\n\
+             ID,Participant ID,integer\n\
+             age,Age (years),numeric\n\
+             sbp_baseline,SBP at baseline,numeric\n\
+             visit_date,Visit date,date\n\
+             smoker,Smoking status,\n",
+        )
+        .unwrap();
+        let codebook = Codebook::load(&path).unwrap();
+        assert_eq!(codebook.entries.len(), 5);
+
+        let findings = codebook.check(&manifest());
+        assert_eq!(findings.codebook, "codebook.csv");
+        assert_eq!(findings.missing_columns, vec!["sbp_baseline", "smoker"]);
+        assert_eq!(findings.unexpected_columns.len(), 1);
+        assert_eq!(findings.unexpected_columns[0].name, "sbp_bl");
+        assert_eq!(
+            findings.unexpected_columns[0].similar_to.as_deref(),
+            Some("sbp_baseline")
+        );
+        assert_eq!(
+            findings.dtype_mismatches,
+            vec![DtypeMismatch {
+                sheet: "trial.csv".to_string(),
+                column_index: 3,
+                name: "visit_date".to_string(),
+                expected: DType::Date,
+                found: DType::String,
+            }]
+        );
+        assert_eq!(findings.unchecked_columns, 1);
+    }
+
+    #[test]
+    fn test_invalid_codebook() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("codebook.csv");
+        std::fs::write(&path, "label\nAge\n").unwrap();
+        assert!(Codebook::load(&path).is_err());
+        std::fs::write(&path, "name,dtype\nage,number\n").unwrap();
+        assert!(matches!(Codebook::load(&path), Err(Error::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_similarity() {
+        let s = |a: &str, b: &str| similarity(&normalize(a), &normalize(b));
+        assert_eq!(s("SBP_Baseline", "sbp.baseline"), 1.0);
+        assert!(s("sbp_bl", "sbp_baseline") >= SIMILARITY_THRESHOLD);
+        assert!(s("age", "smoker") < SIMILARITY_THRESHOLD);
+        assert!(s("visit_date", "visit_dt") > s("visit_date", "vital_signs"));
+        assert!((s("martha", "marhta") - 0.961).abs() < 0.001);
+    }
+}
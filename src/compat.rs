@@ -0,0 +1,182 @@
+//! Loading manifests written by earlier releases into the current model, for
+//! the subcommands that take a manifest as input (`synth`, `redact`).
+
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::error::Error;
+use crate::paths;
+use crate::types::{ManifestSchema, MultiFileManifest, Result, MANIFEST_VERSION};
+
+/// A manifest as read from disk: one file's, or a directory scan's
+#[derive(Debug, Clone)]
+pub enum LoadedManifest {
+    Single(Box<ManifestSchema>),
+    Multi(MultiFileManifest),
+}
+
+/// Read a JSON manifest of any version this release understands.
+///
+/// Manifests of an older minor version are brought up to the current one:
+/// fields added since are filled with their defaults (no checks, statistics or
+/// warnings that the scan did not make appear), and the version is stamped as
+/// current, since that is the model the result follows. A manifest of a newer
+/// major version is refused rather than misread.
+pub fn load_manifest(path: &Path) -> Result<LoadedManifest> {
+    let text = std::fs::read_to_string(paths::for_fs_access(path))?;
+    let invalid = |reason: String| {
+        Error::InvalidInput(format!(
+            "{} is not a JSON manifest: {}",
+            paths::display_path(path),
+            reason
+        ))
+    };
+    let value: Value = serde_json::from_str(&text).map_err(|e| invalid(e.to_string()))?;
+    from_value(value).map_err(invalid)
+}
+
+/// [`load_manifest`] for a manifest already parsed as JSON
+pub fn from_value(mut value: Value) -> std::result::Result<LoadedManifest, String> {
+    let version = match value.get("version") {
+        Some(Value::String(version)) => version.clone(),
+        _ => return Err("no version".to_string()),
+    };
+    let (major, _) = parse_version(&version).ok_or_else(|| format!("bad version {}", version))?;
+    let (current_major, _) = parse_version(MANIFEST_VERSION).unwrap_or((1, 0));
+    if major > current_major {
+        return Err(format!(
+            "version {} was written by a newer release; this one reads up to {}.x",
+            version, current_major
+        ));
+    }
+    if major < 1 {
+        return Err(format!("version {} predates the first release", version));
+    }
+
+    if value.get("files").is_some() {
+        for file in value["files"].as_array_mut().into_iter().flatten() {
+            stamp_current(file);
+        }
+        stamp_current(&mut value);
+        serde_json::from_value(value)
+            .map(LoadedManifest::Multi)
+            .map_err(|e| e.to_string())
+    } else {
+        stamp_current(&mut value);
+        serde_json::from_value(value)
+            .map(|manifest| LoadedManifest::Single(Box::new(manifest)))
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Major and minor number of a `major.minor[.patch]` version
+fn parse_version(version: &str) -> Option<(u64, u64)> {
+    let mut parts = version.trim().split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next().map_or(Some(0), |m| m.parse().ok())?;
+    Some((major, minor))
+}
+
+fn stamp_current(manifest: &mut Value) {
+    if let Some(object) = manifest.as_object_mut() {
+        object.insert("version".to_string(), Value::from(MANIFEST_VERSION));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{HeaderMode, SafeValue, DEFAULT_L_DIVERSITY};
+
+    /// Trimmed from a manifest written by release 1.0
+    const MANIFEST_1_0: &str = r#"{
+      "version": "1.0.0",
+      "file_name": "old.csv",
+      "file_hash": "b40b9516c0c278350c0a6797aa76a223e4f835239a9f7bbdabe84aeeef9d92a4",
+      "format": "csv",
+      "sheets": [{
+        "name": "old.csv",
+        "index": 0,
+        "row_count": {"type": "ShortString", "value": "2-5"},
+        "columns": [{
+          "name": {"type": "ShortString", "value": "age"},
+          "index": 1,
+          "dtype": "integer",
+          "classification": "safe",
+          "stats": {
+            "count": {"type": "ShortString", "value": "2-5"},
+            "missing_count": {"type": "ShortString", "value": "1"},
+            "min": {"type": "Float", "value": 34.0},
+            "max": {"type": "Float", "value": 41.0},
+            "mean": 37.5,
+            "std_dev": 4.949747468305833,
+            "median": 41.0,
+            "unique_count": {"type": "ShortString", "value": "2-5"}
+          }
+        }]
+      }],
+      "options": {
+        "k_anonymity": 5,
+        "bucket_counts": true,
+        "exact_counts": true,
+        "exact_median": false,
+        "hash_file": true,
+        "relaxed": true
+      }
+    }"#;
+
+    fn single(value: Value) -> ManifestSchema {
+        match from_value(value).unwrap() {
+            LoadedManifest::Single(manifest) => *manifest,
+            LoadedManifest::Multi(_) => panic!("expected a single-file manifest"),
+        }
+    }
+
+    #[test]
+    fn test_load_version_1_0() {
+        let manifest = single(serde_json::from_str(MANIFEST_1_0).unwrap());
+        assert_eq!(manifest.version, MANIFEST_VERSION);
+        assert_eq!(manifest.options.header, HeaderMode::Auto);
+        assert_eq!(manifest.options.l_diversity, DEFAULT_L_DIVERSITY);
+        let sheet = &manifest.sheets[0];
+        assert_eq!(sheet.row_count, SafeValue::ShortString("2-5".into()));
+        assert!(sheet.duplicate_rows.is_none() && sheet.sample.is_none());
+        let stats = sheet.columns[0].stats.as_ref().unwrap();
+        assert_eq!(stats.median, Some(41.0));
+        assert!(stats.top_values.is_none());
+
+        let multi = serde_json::json!({
+            "version": "1.0.0",
+            "files": [serde_json::from_str::<Value>(MANIFEST_1_0).unwrap()],
+            "join_keys": [],
+        });
+        match from_value(multi).unwrap() {
+            LoadedManifest::Multi(manifest) => {
+                assert_eq!(manifest.files[0].version, MANIFEST_VERSION);
+                assert!(manifest.join_keys.is_empty());
+            }
+            LoadedManifest::Single(_) => panic!("expected a multi-file manifest"),
+        }
+    }
+
+    #[test]
+    fn test_load_round_trip() {
+        let manifest = ManifestSchema::new("new.csv".into(), crate::types::FileFormat::Csv);
+        let loaded = single(serde_json::to_value(&manifest).unwrap());
+        assert_eq!(loaded.file_name, "new.csv");
+    }
+
+    #[test]
+    fn test_load_refuses_unknown_versions() {
+        let mut value: Value = serde_json::from_str(MANIFEST_1_0).unwrap();
+        value["version"] = Value::from("2.0.0");
+        assert!(from_value(value.clone())
+            .unwrap_err()
+            .contains("newer release"));
+        value["version"] = Value::from("one");
+        assert!(from_value(value.clone()).is_err());
+        value.as_object_mut().unwrap().remove("version");
+        assert!(from_value(value).is_err());
+    }
+}
@@ -0,0 +1,803 @@
+use std::path::{Path, PathBuf};
+
+use glob::Pattern;
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::error::Error;
+use crate::output::OutputFormat;
+use crate::paths;
+use crate::privacy::{CustomDetector, DylibRule, PrivacyRule};
+use crate::types::{
+    Classification, CodeListRule, ColumnSchema, CompletenessRule, DatePrecision, DecimalSeparator,
+    HeaderMode, ManifestSchema, MissingTokenRule, ProcessingOptions, Result, RetentionCategory,
+    SafeValue, ScanConfig,
+};
+
+/// Config file picked up from the current directory when `--config` is not given
+pub const CONFIG_FILE_NAME: &str = "ert-manifest.toml";
+
+/// Contents of an `ert-manifest.toml` file.
+///
+/// Every setting is optional; anything left out falls back to the built-in
+/// default, and command-line flags override the file.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigFile {
+    /// Default output format
+    pub format: Option<OutputFormat>,
+
+    /// Default report template (relative to the config file); `format` wins if both are set
+    pub template: Option<PathBuf>,
+
+    /// File-name glob patterns skipped when scanning a directory
+    #[serde(default)]
+    pub ignore: Vec<String>,
+
+    /// Directory for temporary copies of input data (relative to the config file)
+    pub temp_dir: Option<PathBuf>,
+
+    /// Default processing options
+    #[serde(default)]
+    pub options: OptionOverrides,
+
+    /// Site-specific value detectors, checked before the built-in patterns
+    #[serde(default)]
+    pub detectors: Vec<DetectorConfig>,
+
+    /// Tags and notes attached to columns in the manifest
+    #[serde(default)]
+    pub columns: Vec<ColumnAnnotation>,
+
+    /// Privacy rule plugins (shared libraries, relative to the config file)
+    #[serde(default)]
+    pub plugins: Vec<PathBuf>,
+
+    /// Rule pack settings
+    #[serde(default)]
+    pub rules: RulesSettings,
+}
+
+/// The `[rules]` table
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RulesSettings {
+    /// Hex-encoded Ed25519 public keys whose signatures `rules update` accepts
+    #[serde(default)]
+    pub trusted_keys: Vec<String>,
+}
+
+/// A `[[detectors]]` entry
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DetectorConfig {
+    pub name: String,
+    /// Regular expression matched against the trimmed value
+    pub regex: String,
+    /// `phi` (suppress matching values) or `warning` (keep them, flag the column)
+    #[serde(default = "default_detector_classification")]
+    pub classification: Classification,
+    pub description: Option<String>,
+}
+
+/// A `[[columns]]` entry
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ColumnAnnotation {
+    /// Column name as it appears in the manifest (case-insensitive)
+    pub name: String,
+    /// Sheet the column is in; any sheet if not given
+    pub sheet: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub note: Option<String>,
+    /// Minimum share of non-missing values (percent), checked during the scan
+    pub min_completeness: Option<f64>,
+    /// Code list the column's values must come from, checked during the scan
+    pub allowed_values: Option<Vec<String>>,
+    /// Tokens counted as missing in this column, replacing the built-in list
+    pub missing_tokens: Option<Vec<String>>,
+    /// `keep`, `destroy-after-study` or `never-transfer`
+    pub retention: Option<RetentionCategory>,
+}
+
+impl ColumnAnnotation {
+    fn matches(&self, sheet: &str, column: &ColumnSchema) -> bool {
+        let SafeValue::ShortString(ref name) = column.name else {
+            return false;
+        };
+        name.eq_ignore_ascii_case(&self.name) && self.sheet.iter().all(|s| s == sheet)
+    }
+}
+
+fn default_detector_classification() -> Classification {
+    Classification::Phi
+}
+
+/// Processing options that are set explicitly, by flags or a config file
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OptionOverrides {
+    pub k_anonymity: Option<u64>,
+    pub bucket_counts: Option<bool>,
+    pub exact_counts: Option<bool>,
+    pub exact_median: Option<bool>,
+    pub hash_file: Option<bool>,
+    pub relaxed: Option<bool>,
+    pub encoding: Option<String>,
+    pub header: Option<HeaderMode>,
+    pub header_rows: Option<usize>,
+    pub date_precision: Option<DatePrecision>,
+    pub decimal_separator: Option<DecimalSeparator>,
+    pub missing_tokens: Option<Vec<String>>,
+    pub deep_scan: Option<bool>,
+    pub content_aware: Option<bool>,
+    pub cdisc: Option<bool>,
+    pub examples: Option<bool>,
+    pub redact_names: Option<bool>,
+    pub skip_hidden_sheets: Option<bool>,
+    pub scan_comments: Option<bool>,
+    pub max_unique_values: Option<usize>,
+    pub sample_rows: Option<u64>,
+    pub sample_fraction: Option<f64>,
+    pub subject_column: Option<String>,
+}
+
+impl OptionOverrides {
+    /// Settings from `self` win; anything unset is taken from `lower`
+    pub fn or(self, lower: OptionOverrides) -> OptionOverrides {
+        let sampling = match (self.sample_rows, self.sample_fraction) {
+            (None, None) => (lower.sample_rows, lower.sample_fraction),
+            set => set,
+        };
+        OptionOverrides {
+            k_anonymity: self.k_anonymity.or(lower.k_anonymity),
+            bucket_counts: self.bucket_counts.or(lower.bucket_counts),
+            exact_counts: self.exact_counts.or(lower.exact_counts),
+            exact_median: self.exact_median.or(lower.exact_median),
+            hash_file: self.hash_file.or(lower.hash_file),
+            relaxed: self.relaxed.or(lower.relaxed),
+            encoding: self.encoding.or(lower.encoding),
+            header: self.header.or(lower.header),
+            header_rows: self.header_rows.or(lower.header_rows),
+            date_precision: self.date_precision.or(lower.date_precision),
+            decimal_separator: self.decimal_separator.or(lower.decimal_separator),
+            missing_tokens: self.missing_tokens.or(lower.missing_tokens),
+            deep_scan: self.deep_scan.or(lower.deep_scan),
+            content_aware: self.content_aware.or(lower.content_aware),
+            cdisc: self.cdisc.or(lower.cdisc),
+            examples: self.examples.or(lower.examples),
+            redact_names: self.redact_names.or(lower.redact_names),
+            skip_hidden_sheets: self.skip_hidden_sheets.or(lower.skip_hidden_sheets),
+            scan_comments: self.scan_comments.or(lower.scan_comments),
+            max_unique_values: self.max_unique_values.or(lower.max_unique_values),
+            // Either way of sampling replaces the other
+            sample_rows: sampling.0,
+            sample_fraction: sampling.1,
+            subject_column: self.subject_column.or(lower.subject_column),
+        }
+    }
+
+    /// Fill unset options from the defaults. Exact counts/median still require relaxed mode.
+    pub fn resolve(self) -> ProcessingOptions {
+        let defaults = ProcessingOptions::default();
+        let relaxed = self.relaxed.unwrap_or(defaults.relaxed);
+        ProcessingOptions {
+            k_anonymity: self.k_anonymity.unwrap_or(defaults.k_anonymity),
+            bucket_counts: self.bucket_counts.unwrap_or(defaults.bucket_counts),
+            exact_counts: self.exact_counts.unwrap_or(defaults.exact_counts) && relaxed,
+            exact_median: self.exact_median.unwrap_or(defaults.exact_median) && relaxed,
+            hash_file: self.hash_file.unwrap_or(defaults.hash_file),
+            relaxed,
+            encoding: self.encoding.or(defaults.encoding),
+            header: self.header.unwrap_or(defaults.header),
+            header_rows: self.header_rows.unwrap_or(defaults.header_rows),
+            date_precision: self.date_precision.unwrap_or(defaults.date_precision),
+            decimal_separator: self.decimal_separator.or(defaults.decimal_separator),
+            missing_tokens: self.missing_tokens.unwrap_or(defaults.missing_tokens),
+            deep_scan: self.deep_scan.unwrap_or(defaults.deep_scan),
+            content_aware: self.content_aware.unwrap_or(defaults.content_aware),
+            cdisc: self.cdisc.unwrap_or(defaults.cdisc),
+            examples: self.examples.unwrap_or(defaults.examples),
+            redact_names: self.redact_names.unwrap_or(defaults.redact_names),
+            skip_hidden_sheets: self.skip_hidden_sheets.unwrap_or(defaults.skip_hidden_sheets),
+            scan_comments: self.scan_comments.unwrap_or(defaults.scan_comments),
+            max_unique_values: self.max_unique_values.unwrap_or(defaults.max_unique_values),
+            sample_rows: self.sample_rows.or(defaults.sample_rows),
+            sample_fraction: self.sample_fraction.or(defaults.sample_fraction),
+            subject_column: self.subject_column.or(defaults.subject_column),
+            ..defaults
+        }
+    }
+}
+
+/// A config file and where it was read from
+#[derive(Debug, Clone)]
+pub struct LoadedConfig {
+    pub path: PathBuf,
+    pub file: ConfigFile,
+}
+
+impl LoadedConfig {
+    /// Compiled `ignore` patterns
+    pub fn ignore_patterns(&self) -> Result<Vec<Pattern>> {
+        self.file
+            .ignore
+            .iter()
+            .map(|p| {
+                Pattern::new(p).map_err(|e| {
+                    Error::Config(format!(
+                        "{}: invalid ignore pattern '{}': {}",
+                        paths::display_path(&self.path),
+                        p,
+                        e
+                    ))
+                })
+            })
+            .collect()
+    }
+
+    /// The `template` setting, resolved against the config file's directory
+    pub fn template_path(&self) -> Option<PathBuf> {
+        Some(self.resolve_path(self.file.template.as_ref()?))
+    }
+
+    /// The `temp_dir` setting
+    pub fn temp_dir(&self) -> Option<PathBuf> {
+        Some(self.resolve_path(self.file.temp_dir.as_ref()?))
+    }
+
+    /// Load the `plugins` libraries
+    pub fn plugin_rules(&self) -> Result<Vec<Box<dyn PrivacyRule>>> {
+        self.file
+            .plugins
+            .iter()
+            .map(|p| Ok(Box::new(DylibRule::load(&self.resolve_path(p))?) as Box<dyn PrivacyRule>))
+            .collect()
+    }
+
+    /// A path from the config file, relative to the file's directory
+    fn resolve_path(&self, path: &Path) -> PathBuf {
+        match self.path.parent() {
+            Some(dir) if path.is_relative() => dir.join(path),
+            _ => path.to_path_buf(),
+        }
+    }
+
+    /// Compiled `[[detectors]]`
+    pub fn custom_detectors(&self) -> Result<Vec<CustomDetector>> {
+        compile_detectors(&paths::display_path(&self.path), &self.file.detectors)
+    }
+
+    /// The `[[columns]]` completeness minimums
+    pub fn completeness_rules(&self) -> Result<Vec<CompletenessRule>> {
+        self.file
+            .columns
+            .iter()
+            .filter_map(|c| Some((c, c.min_completeness?)))
+            .map(|(c, min_percent)| {
+                if !(0.0..=100.0).contains(&min_percent) {
+                    return Err(Error::Config(format!(
+                        "{}: column '{}': min_completeness must be a percentage from 0 to 100",
+                        paths::display_path(&self.path),
+                        c.name
+                    )));
+                }
+                Ok(CompletenessRule {
+                    column: c.name.clone(),
+                    sheet: c.sheet.clone(),
+                    min_percent,
+                })
+            })
+            .collect()
+    }
+
+    /// The `[[columns]]` code lists
+    pub fn code_lists(&self) -> Result<Vec<CodeListRule>> {
+        self.file
+            .columns
+            .iter()
+            .filter_map(|c| Some((c, c.allowed_values.as_ref()?)))
+            .map(|(c, allowed)| {
+                if allowed.is_empty() {
+                    return Err(Error::Config(format!(
+                        "{}: column '{}': allowed_values is empty",
+                        paths::display_path(&self.path),
+                        c.name
+                    )));
+                }
+                Ok(CodeListRule {
+                    column: c.name.clone(),
+                    sheet: c.sheet.clone(),
+                    allowed: allowed.iter().map(|v| v.trim().to_string()).collect(),
+                })
+            })
+            .collect()
+    }
+
+    /// The `[[columns]]` missing-value tokens
+    pub fn missing_token_rules(&self) -> Vec<MissingTokenRule> {
+        self.file
+            .columns
+            .iter()
+            .filter_map(|c| {
+                Some(MissingTokenRule {
+                    column: c.name.clone(),
+                    sheet: c.sheet.clone(),
+                    tokens: c.missing_tokens.clone()?,
+                })
+            })
+            .collect()
+    }
+
+    /// Columns marked `never-transfer`, whose values the scan withholds. A
+    /// column named for one sheet is withheld in every sheet, erring on the
+    /// side of keeping values at the site.
+    pub fn never_transfer_columns(&self) -> Vec<String> {
+        self.file
+            .columns
+            .iter()
+            .filter(|c| c.retention == Some(RetentionCategory::NeverTransfer))
+            .map(|c| c.name.clone())
+            .collect()
+    }
+
+    /// Attach the `[[columns]]` tags, notes and retention categories to
+    /// matching columns. Columns whose name is suppressed cannot be matched.
+    pub fn annotate(&self, manifest: &mut ManifestSchema) {
+        for sheet in &mut manifest.sheets {
+            for column in &mut sheet.columns {
+                for annotation in &self.file.columns {
+                    if !annotation.matches(&sheet.name, column) {
+                        continue;
+                    }
+                    for tag in &annotation.tags {
+                        if !column.tags.contains(tag) {
+                            column.tags.push(tag.clone());
+                        }
+                    }
+                    if annotation.note.is_some() {
+                        column.note = annotation.note.clone();
+                    }
+                    if annotation.retention.is_some() {
+                        column.retention = annotation.retention;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Summary echoed into the manifest (the options themselves are echoed separately)
+    pub fn echo(&self, format: OutputFormat) -> ScanConfig {
+        ScanConfig {
+            source: paths::file_name_lossy(&self.path),
+            format,
+            ignore: self.file.ignore.clone(),
+            detectors: self.file.detectors.iter().map(|d| d.name.clone()).collect(),
+            plugins: self.file.plugins.iter().map(|p| paths::file_name_lossy(p)).collect(),
+        }
+    }
+}
+
+/// Compile detector entries; `source` names where they came from in errors
+pub fn compile_detectors(source: &str, detectors: &[DetectorConfig]) -> Result<Vec<CustomDetector>> {
+    let invalid = |d: &DetectorConfig, problem: String| {
+        Error::Config(format!("{}: detector '{}': {}", source, d.name, problem))
+    };
+
+    detectors
+        .iter()
+        .map(|d| {
+            if !matches!(d.classification, Classification::Phi | Classification::Warning) {
+                return Err(invalid(
+                    d,
+                    "classification must be \"phi\" or \"warning\"".to_string(),
+                ));
+            }
+            let regex = Regex::new(&d.regex).map_err(|e| invalid(d, e.to_string()))?;
+            Ok(CustomDetector {
+                name: d.name.clone(),
+                regex,
+                classification: d.classification.clone(),
+                description: d
+                    .description
+                    .clone()
+                    .unwrap_or_else(|| format!("Value matches custom detector '{}'", d.name)),
+            })
+        })
+        .collect()
+}
+
+/// Load the config file named by `--config`, or `ert-manifest.toml` in the
+/// current directory if there is one
+pub fn load(explicit: Option<&Path>) -> Result<Option<LoadedConfig>> {
+    match explicit {
+        Some(path) => load_file(path, true).map(Some),
+        None => {
+            let path = Path::new(CONFIG_FILE_NAME);
+            if !path.is_file() {
+                return Ok(None);
+            }
+            load_file(path, false).map(Some)
+        }
+    }
+}
+
+/// Read and parse a config file. `plugins` run code, so they are only taken
+/// from a file the user named: a config that merely sits in the current
+/// directory (e.g. a data folder from elsewhere) may not load them.
+fn load_file(path: &Path, explicit: bool) -> Result<LoadedConfig> {
+    let path = path.to_path_buf();
+    let text = std::fs::read_to_string(paths::for_fs_access(&path))
+        .map_err(|e| Error::Config(format!("{}: {}", paths::display_path(&path), e)))?;
+    let file = parse(&text)
+        .map_err(|e| Error::Config(format!("{}: {}", paths::display_path(&path), e)))?;
+    if !explicit && !file.plugins.is_empty() {
+        return Err(Error::Config(format!(
+            "{}: plugins are only loaded from a config file given with --config; this one was \
+             picked up from the current directory",
+            paths::display_path(&path)
+        )));
+    }
+    Ok(LoadedConfig { path, file })
+}
+
+fn parse(text: &str) -> std::result::Result<ConfigFile, toml::de::Error> {
+    toml::from_str(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{DType, FileFormat, SheetSchema};
+
+    #[test]
+    fn test_parse_full_config() {
+        let config = parse(
+            r#"
+format = "html"
+template = "templates/report.md.hbs"
+ignore = ["*.bak.csv", "~$*"]
+temp_dir = "scratch"
+
+[options]
+k_anonymity = 10
+bucket_counts = false
+encoding = "windows-1252"
+header = { row = 3 }
+date_precision = "year"
+decimal_separator = "comma"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.format, Some(OutputFormat::Html));
+        let loaded = LoadedConfig {
+            path: PathBuf::from("study").join(CONFIG_FILE_NAME),
+            file: config.clone(),
+        };
+        assert_eq!(
+            loaded.template_path(),
+            Some(PathBuf::from("study/templates/report.md.hbs"))
+        );
+        assert_eq!(loaded.temp_dir(), Some(PathBuf::from("study/scratch")));
+        assert_eq!(config.ignore, vec!["*.bak.csv", "~$*"]);
+        assert_eq!(config.options.k_anonymity, Some(10));
+        assert_eq!(config.options.bucket_counts, Some(false));
+        assert_eq!(config.options.header, Some(HeaderMode::Row(3)));
+        assert_eq!(config.options.date_precision, Some(DatePrecision::Year));
+        assert_eq!(config.options.decimal_separator, Some(DecimalSeparator::Comma));
+        assert_eq!(config.options.relaxed, None);
+    }
+
+    #[test]
+    fn test_unknown_keys_rejected() {
+        assert!(parse("k = 10").is_err());
+        assert!(parse("[options]\nk = 10").is_err());
+    }
+
+    #[test]
+    fn test_cli_overrides_config() {
+        let cli = OptionOverrides {
+            k_anonymity: Some(20),
+            ..Default::default()
+        };
+        let file = OptionOverrides {
+            k_anonymity: Some(10),
+            hash_file: Some(false),
+            deep_scan: Some(true),
+            examples: Some(true),
+            sample_rows: Some(1000),
+            ..Default::default()
+        };
+
+        let options = cli.clone().or(file.clone()).resolve();
+        assert_eq!(options.k_anonymity, 20);
+        assert!(!options.hash_file);
+        assert!(options.bucket_counts);
+        // Flags that were not given leave the config's settings alone
+        assert!(options.deep_scan && options.examples);
+        assert_eq!(options.sample_rows, Some(1000));
+
+        let cli = OptionOverrides {
+            examples: Some(false),
+            sample_fraction: Some(0.1),
+            ..cli
+        };
+        let options = cli.or(file).resolve();
+        assert!(options.deep_scan && !options.examples);
+        assert_eq!((options.sample_rows, options.sample_fraction), (None, Some(0.1)));
+    }
+
+    #[test]
+    fn test_exact_values_still_require_relaxed() {
+        let options = OptionOverrides {
+            exact_counts: Some(true),
+            exact_median: Some(true),
+            ..Default::default()
+        }
+        .resolve();
+        assert!(!options.exact_counts);
+        assert!(!options.exact_median);
+    }
+
+    #[test]
+    fn test_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("team.toml");
+        std::fs::write(&path, "ignore = [\"scratch_*\"]\n").unwrap();
+
+        let loaded = load(Some(&path)).unwrap().unwrap();
+        assert_eq!(loaded.template_path(), None);
+        let patterns = loaded.ignore_patterns().unwrap();
+        assert!(patterns[0].matches("scratch_labs.csv"));
+        assert_eq!(loaded.echo(OutputFormat::Json).source, "team.toml");
+
+        assert!(matches!(
+            load(Some(&dir.path().join("missing.toml"))),
+            Err(Error::Config(_))
+        ));
+    }
+
+    #[test]
+    fn test_plugins_need_explicit_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CONFIG_FILE_NAME);
+        std::fs::write(&path, "plugins = [\"libevil.so\"]\n").unwrap();
+
+        let err = load_file(&path, false).err().unwrap();
+        assert!(err.to_string().contains("--config"));
+        // Named with --config, the config is read (the plugins load later)
+        assert_eq!(load_file(&path, true).unwrap().file.plugins.len(), 1);
+    }
+
+    #[test]
+    fn test_custom_detectors() {
+        let file = parse(
+            r#"
+[[detectors]]
+name = "hsj_mrn"
+regex = '^HSJ-\d{6}$'
+description = "Sainte-Justine MRN"
+
+[[detectors]]
+name = "lab_kit"
+regex = '^KIT\d+$'
+classification = "warning"
+"#,
+        )
+        .unwrap();
+        let loaded = LoadedConfig {
+            path: PathBuf::from(CONFIG_FILE_NAME),
+            file,
+        };
+
+        let detectors = loaded.custom_detectors().unwrap();
+        assert_eq!(detectors[0].classification, Classification::Phi);
+        assert!(detectors[0].regex.is_match("HSJ-123456"));
+        assert_eq!(detectors[1].classification, Classification::Warning);
+        assert_eq!(
+            detectors[1].description,
+            "Value matches custom detector 'lab_kit'"
+        );
+        assert_eq!(
+            loaded.echo(OutputFormat::Json).detectors,
+            vec!["hsj_mrn", "lab_kit"]
+        );
+    }
+
+    #[test]
+    fn test_invalid_detectors() {
+        for entry in [
+            "name = \"x\"\nregex = \"(\"",
+            "name = \"x\"\nregex = \"x\"\nclassification = \"recode\"",
+        ] {
+            let loaded = LoadedConfig {
+                path: PathBuf::from(CONFIG_FILE_NAME),
+                file: parse(&format!("[[detectors]]\n{}", entry)).unwrap(),
+            };
+            assert!(matches!(loaded.custom_detectors(), Err(Error::Config(_))));
+        }
+    }
+
+    #[test]
+    fn test_completeness_rules() {
+        let loaded = |text: &str| LoadedConfig {
+            path: PathBuf::from(CONFIG_FILE_NAME),
+            file: parse(text).unwrap(),
+        };
+        let config = loaded(
+            r#"
+[[columns]]
+name = "primary_outcome"
+min_completeness = 95
+
+[[columns]]
+name = "sbp"
+sheet = "Visits"
+tags = ["baseline"]
+min_completeness = 80.5
+
+[[columns]]
+name = "bmi"
+tags = ["derived"]
+"#,
+        );
+        assert_eq!(
+            config.completeness_rules().unwrap(),
+            vec![
+                CompletenessRule {
+                    column: "primary_outcome".to_string(),
+                    sheet: None,
+                    min_percent: 95.0,
+                },
+                CompletenessRule {
+                    column: "sbp".to_string(),
+                    sheet: Some("Visits".to_string()),
+                    min_percent: 80.5,
+                },
+            ]
+        );
+
+        let invalid = loaded("[[columns]]\nname = \"sbp\"\nmin_completeness = 0.95e3\n");
+        assert!(matches!(invalid.completeness_rules(), Err(Error::Config(_))));
+    }
+
+    #[test]
+    fn test_code_lists() {
+        let loaded = |text: &str| LoadedConfig {
+            path: PathBuf::from(CONFIG_FILE_NAME),
+            file: parse(text).unwrap(),
+        };
+        let config = loaded(
+            r#"
+[[columns]]
+name = "sex"
+allowed_values = ["M", "F", "U"]
+
+[[columns]]
+name = "sbp"
+min_completeness = 80
+"#,
+        );
+        assert_eq!(
+            config.code_lists().unwrap(),
+            vec![CodeListRule {
+                column: "sex".to_string(),
+                sheet: None,
+                allowed: vec!["M".to_string(), "F".to_string(), "U".to_string()],
+            }]
+        );
+
+        let invalid = loaded("[[columns]]\nname = \"sex\"\nallowed_values = []\n");
+        assert!(matches!(invalid.code_lists(), Err(Error::Config(_))));
+    }
+
+    #[test]
+    fn test_missing_tokens() {
+        let config = parse(
+            r#"
+[options]
+missing_tokens = ["-99", "UNK"]
+
+[[columns]]
+name = "sbp"
+sheet = "Visits"
+missing_tokens = ["-9"]
+
+[[columns]]
+name = "sex"
+allowed_values = ["M", "F"]
+"#,
+        )
+        .unwrap();
+        let options = config.options.clone().resolve();
+        assert_eq!(options.missing_tokens, vec!["-99", "UNK"]);
+        let loaded = LoadedConfig {
+            path: PathBuf::from(CONFIG_FILE_NAME),
+            file: config,
+        };
+        assert_eq!(
+            loaded.missing_token_rules(),
+            vec![MissingTokenRule {
+                column: "sbp".to_string(),
+                sheet: Some("Visits".to_string()),
+                tokens: vec!["-9".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_annotate_columns() {
+        let file = parse(
+            r#"
+[[columns]]
+name = "SBP"
+tags = ["baseline", "outcome"]
+note = "Systolic BP at randomization"
+
+[[columns]]
+name = "sbp"
+sheet = "Visits"
+tags = ["outcome", "derived"]
+
+[[columns]]
+name = "mrn"
+tags = ["identifier"]
+retention = "never-transfer"
+
+[[columns]]
+name = "sbp"
+sheet = "Baseline"
+retention = "destroy-after-study"
+"#,
+        )
+        .unwrap();
+        let loaded = LoadedConfig {
+            path: PathBuf::from(CONFIG_FILE_NAME),
+            file,
+        };
+
+        let mut manifest = ManifestSchema::new("trial.xlsx".to_string(), FileFormat::Excel);
+        for name in ["Baseline", "Visits"] {
+            let mut sheet = SheetSchema::new(name.to_string(), 0);
+            sheet.columns.push(ColumnSchema::new(
+                SafeValue::ShortString("sbp".to_string()),
+                0,
+                DType::Integer,
+            ));
+            sheet.columns.push(ColumnSchema::new(
+                SafeValue::Suppressed {
+                    reason: "PHI".to_string(),
+                },
+                1,
+                DType::String,
+            ));
+            manifest.sheets.push(sheet);
+        }
+        loaded.annotate(&mut manifest);
+
+        let baseline = &manifest.sheets[0].columns[0];
+        assert_eq!(baseline.tags, vec!["baseline", "outcome"]);
+        assert_eq!(baseline.note.as_deref(), Some("Systolic BP at randomization"));
+        let visits = &manifest.sheets[1].columns[0];
+        assert_eq!(visits.tags, vec!["baseline", "outcome", "derived"]);
+        assert!(manifest.sheets[0].columns[1].tags.is_empty());
+        assert_eq!(baseline.retention, Some(RetentionCategory::DestroyAfterStudy));
+        assert_eq!(visits.retention, None);
+        assert_eq!(manifest.sheets[0].columns[1].retention, None);
+        assert_eq!(loaded.never_transfer_columns(), vec!["mrn"]);
+    }
+
+    #[test]
+    fn test_invalid_ignore_pattern() {
+        let loaded = LoadedConfig {
+            path: PathBuf::from(CONFIG_FILE_NAME),
+            file: ConfigFile {
+                ignore: vec!["[".to_string()],
+                ..Default::default()
+            },
+        };
+        assert!(loaded.ignore_patterns().is_err());
+    }
+}
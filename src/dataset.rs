@@ -0,0 +1,446 @@
+//! Dataset descriptor (`dataset.json`): a directory scan described as one
+//! dataset, with its files, the keys that link them, an estimate of how many
+//! subjects it covers and its re-identification risk as a whole.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::Serialize;
+
+use crate::assess::{assess, Assessment, RiskGrade, HIGH_RISK_QUASI_IDENTIFIERS};
+use crate::privacy::bucket_magnitude;
+use crate::stats::HyperLogLog;
+use crate::types::{
+    ColumnFingerprint, ColumnRef, FileFormat, JoinKeyCandidate, ManifestSchema, MultiFileManifest,
+    ProcessingOptions, SafeValue, MANIFEST_VERSION,
+};
+
+/// File name the descriptor is written under, next to the directory's manifest
+pub const DATASET_FILE_NAME: &str = "dataset.json";
+
+/// A directory of related files, described as one dataset
+#[derive(Debug, Clone, Serialize)]
+pub struct DatasetDescriptor {
+    /// Schema version
+    pub version: String,
+
+    /// Name of the scanned directory
+    pub name: String,
+
+    pub files: Vec<DatasetFile>,
+
+    /// Columns that appear to link files together (as the manifest's `join_keys`)
+    pub key_relationships: Vec<JoinKeyCandidate>,
+
+    /// Subjects across all files, if a key links them
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subjects: Option<SubjectEstimate>,
+
+    pub risk: DatasetRisk,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+/// One file of the dataset, summarized from its manifest
+#[derive(Debug, Clone, Serialize)]
+pub struct DatasetFile {
+    pub file_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_hash: Option<String>,
+    pub format: FileFormat,
+    pub sheets: Vec<DatasetSheet>,
+    /// Highest risk grade of the file's sheets, as `assess` gives it
+    pub risk: RiskGrade,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DatasetSheet {
+    pub name: String,
+    /// Row count (may be bucketed)
+    pub row_count: SafeValue,
+    pub columns: usize,
+}
+
+/// Distinct subjects across the files, estimated by merging the hashed-value
+/// sketches of the subject column (`--subject-column`) or, if none is given,
+/// of the key that links the most files
+#[derive(Debug, Clone, Serialize)]
+pub struct SubjectEstimate {
+    /// The subject key's column name (suppressed if PHI)
+    pub key: SafeValue,
+    /// Files the key was found in
+    pub files: usize,
+    /// Estimated distinct subjects, bucketed by order of magnitude
+    pub estimated_subjects: SafeValue,
+}
+
+/// Re-identification risk of the dataset as a whole
+#[derive(Debug, Clone, Serialize)]
+pub struct DatasetRisk {
+    /// Highest grade of any file, raised when linking the files combines
+    /// quasi-identifiers
+    pub grade: RiskGrade,
+    /// What the grade is based on
+    pub signals: Vec<String>,
+}
+
+/// Describe a directory scan as a dataset. Needs the column fingerprints, so
+/// it works on a freshly scanned manifest, not one read back from JSON.
+pub fn describe_dataset(
+    name: &str,
+    manifest: &MultiFileManifest,
+    options: &ProcessingOptions,
+) -> DatasetDescriptor {
+    let assessments: Vec<Assessment> = manifest.files.iter().map(assess).collect();
+    let files = manifest
+        .files
+        .iter()
+        .zip(&assessments)
+        .map(|(file, assessment)| DatasetFile {
+            file_name: file.file_name.clone(),
+            file_hash: file.file_hash.clone(),
+            format: file.format,
+            sheets: file
+                .sheets
+                .iter()
+                .map(|sheet| DatasetSheet {
+                    name: sheet.name.clone(),
+                    row_count: sheet.row_count.clone(),
+                    columns: sheet.columns.len(),
+                })
+                .collect(),
+            risk: assessment.grade,
+        })
+        .collect();
+
+    let mut warnings = Vec::new();
+    let subjects = match options.subject_column.as_deref() {
+        Some(column) => count_subjects(column, manifest, &mut warnings),
+        None => estimate_subjects(manifest),
+    };
+    if subjects.is_none() && options.subject_column.is_none() && manifest.files.len() > 1 {
+        warnings.push(
+            "No key column links the files, so subjects across them were not counted".to_string(),
+        );
+    }
+
+    DatasetDescriptor {
+        version: MANIFEST_VERSION.to_string(),
+        name: name.to_string(),
+        files,
+        key_relationships: manifest.join_keys.clone(),
+        subjects,
+        risk: dataset_risk(&manifest.join_keys, &assessments),
+        warnings,
+    }
+}
+
+/// Columns sharing one normalized key name across the join keys
+struct SubjectKey<'a> {
+    column: &'a SafeValue,
+    files: BTreeSet<&'a str>,
+    columns: BTreeSet<(&'a str, &'a str, usize)>,
+    sketch: HyperLogLog,
+}
+
+/// Subjects of every file holding the designated subject column, from the
+/// union of the column's salted-hash sketches
+fn count_subjects(
+    subject_column: &str,
+    manifest: &MultiFileManifest,
+    warnings: &mut Vec<String>,
+) -> Option<SubjectEstimate> {
+    let mut key = None;
+    let mut files = 0;
+    let mut missing = Vec::new();
+    let mut sketch = HyperLogLog::default();
+    for file in &manifest.files {
+        let mut found = false;
+        for column in file.sheets.iter().flat_map(|sheet| &sheet.columns) {
+            let Some(subjects) = column
+                .fingerprint
+                .as_ref()
+                .and_then(|f| f.subject_sketch.as_ref())
+            else {
+                continue;
+            };
+            key.get_or_insert_with(|| column.name.clone());
+            sketch.merge(subjects);
+            found = true;
+        }
+        if found {
+            files += 1;
+        } else {
+            missing.push(file.file_name.as_str());
+        }
+    }
+    if !missing.is_empty() {
+        warnings.push(format!(
+            "Subject column '{}' not found in {}; subjects seen only there are not counted",
+            subject_column,
+            missing.join(", ")
+        ));
+    }
+
+    Some(SubjectEstimate {
+        key: key?,
+        files,
+        estimated_subjects: estimated_count(&sketch),
+    })
+}
+
+/// A sketch's distinct count is only good to a few percent, so it is always
+/// bucketed
+fn estimated_count(sketch: &HyperLogLog) -> SafeValue {
+    SafeValue::ShortString(bucket_magnitude(sketch.estimate().round() as u64))
+}
+
+fn estimate_subjects(manifest: &MultiFileManifest) -> Option<SubjectEstimate> {
+    let mut keys: BTreeMap<&str, SubjectKey> = BTreeMap::new();
+    for candidate in &manifest.join_keys {
+        for side in [&candidate.left, &candidate.right] {
+            let Some(fingerprint) = fingerprint(&manifest.files, side) else {
+                continue;
+            };
+            let key = keys
+                .entry(fingerprint.key_name.as_str())
+                .or_insert_with(|| SubjectKey {
+                    column: &candidate.column_name,
+                    files: BTreeSet::new(),
+                    columns: BTreeSet::new(),
+                    sketch: HyperLogLog::default(),
+                });
+            key.files.insert(side.file_name.as_str());
+            let at = (
+                side.file_name.as_str(),
+                side.sheet.as_str(),
+                side.column_index,
+            );
+            if key.columns.insert(at) {
+                key.sketch.merge(&fingerprint.sketch);
+            }
+        }
+    }
+
+    let key = keys
+        .into_values()
+        .max_by_key(|key| (key.files.len(), key.columns.len()))?;
+    Some(SubjectEstimate {
+        key: key.column.clone(),
+        files: key.files.len(),
+        estimated_subjects: estimated_count(&key.sketch),
+    })
+}
+
+fn fingerprint<'a>(files: &'a [ManifestSchema], at: &ColumnRef) -> Option<&'a ColumnFingerprint> {
+    files
+        .iter()
+        .filter(|file| file.file_name == at.file_name)
+        .flat_map(|file| &file.sheets)
+        .filter(|sheet| sheet.name == at.sheet)
+        .flat_map(|sheet| &sheet.columns)
+        .find(|column| column.index == at.column_index)?
+        .fingerprint
+        .as_ref()
+}
+
+/// Each file's grade, raised to high when files linked by a key together hold
+/// more kinds of quasi-identifier than any one sheet, and enough of them to
+/// single people out
+fn dataset_risk(join_keys: &[JoinKeyCandidate], assessments: &[Assessment]) -> DatasetRisk {
+    let mut grade = RiskGrade::Low;
+    let mut signals = Vec::new();
+    for assessment in assessments {
+        grade = grade.max(assessment.grade);
+        if assessment.grade > RiskGrade::Low {
+            signals.push(format!(
+                "{}: {} risk on its own",
+                assessment.file_name,
+                assessment.grade.as_str()
+            ));
+        }
+    }
+
+    let linked: BTreeSet<&str> = join_keys
+        .iter()
+        .flat_map(|key| [key.left.file_name.as_str(), key.right.file_name.as_str()])
+        .collect();
+    let mut kinds: Vec<&str> = Vec::new();
+    let mut widest_sheet = 0;
+    for assessment in assessments
+        .iter()
+        .filter(|a| linked.contains(a.file_name.as_str()))
+    {
+        for sheet in &assessment.sheets {
+            let sheet_kinds: BTreeSet<&str> =
+                sheet.quasi_identifiers.iter().map(|q| q.kind).collect();
+            widest_sheet = widest_sheet.max(sheet_kinds.len());
+            for kind in sheet_kinds {
+                if !kinds.contains(&kind) {
+                    kinds.push(kind);
+                }
+            }
+        }
+    }
+    if kinds.len() >= HIGH_RISK_QUASI_IDENTIFIERS && kinds.len() > widest_sheet {
+        grade = RiskGrade::High;
+        signals.push(format!(
+            "Linked through shared keys, {} files together hold {} kinds of quasi-identifier \
+             ({}); their combinations can single out individuals",
+            linked.len(),
+            kinds.len(),
+            kinds.join(", ")
+        ));
+    }
+
+    DatasetRisk { grade, signals }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linkage::{detect_join_keys, normalize_key_name};
+    use crate::privacy::safe_count;
+    use crate::stats::HashSalt;
+    use crate::types::{ColumnSchema, DType, SheetSchema};
+
+    /// A key column; with a salt, also hashed as the subject column is
+    fn column(name: &str, values: &[String], salt: Option<&HashSalt>) -> ColumnSchema {
+        let mut column =
+            ColumnSchema::new(SafeValue::ShortString(name.to_string()), 0, DType::String);
+        let mut sketch = HyperLogLog::default();
+        let mut subject_sketch = salt.map(|_| HyperLogLog::default());
+        for value in values {
+            sketch.add(value);
+            if let (Some(subjects), Some(salt)) = (&mut subject_sketch, salt) {
+                subjects.add_salted(value, salt);
+            }
+        }
+        column.fingerprint = Some(ColumnFingerprint {
+            key_name: normalize_key_name(name),
+            value_count: values.len() as u64,
+            sketch,
+            subject_sketch,
+        });
+        column
+    }
+
+    fn file(name: &str, ids: std::ops::Range<u32>, others: &[(&str, DType)]) -> ManifestSchema {
+        keyed_file(name, "subject_id", ids, None, others)
+    }
+
+    fn keyed_file(
+        name: &str,
+        key: &str,
+        ids: std::ops::Range<u32>,
+        salt: Option<&HashSalt>,
+        others: &[(&str, DType)],
+    ) -> ManifestSchema {
+        let ids: Vec<String> = ids.map(|i| format!("S{:05}", i)).collect();
+        let mut sheet = SheetSchema::new(name.to_string(), 0);
+        sheet.row_count = safe_count(ids.len() as u64, true);
+        sheet.columns.push(column(key, &ids, salt));
+        for (i, (other, dtype)) in others.iter().enumerate() {
+            sheet.columns.push(ColumnSchema::new(
+                SafeValue::ShortString(other.to_string()),
+                i + 1,
+                *dtype,
+            ));
+        }
+        let mut manifest = ManifestSchema::new(name.to_string(), FileFormat::Csv);
+        manifest.sheets.push(sheet);
+        manifest
+    }
+
+    #[test]
+    fn test_describe_dataset() {
+        let files = vec![
+            file("demographics.csv", 0..600, &[("age", DType::Integer)]),
+            file(
+                "visits.csv",
+                200..800,
+                &[("sex", DType::String), ("zip3", DType::String)],
+            ),
+        ];
+        let options = ProcessingOptions {
+            bucket_counts: false,
+            ..ProcessingOptions::default()
+        };
+        let mut manifest = MultiFileManifest::new(files);
+        manifest.join_keys = detect_join_keys(&manifest.files, &options);
+
+        let dataset = describe_dataset("trial", &manifest, &options);
+        assert_eq!(dataset.name, "trial");
+        assert_eq!(dataset.files.len(), 2);
+        assert_eq!(dataset.files[1].sheets[0].columns, 3);
+        assert_eq!(dataset.key_relationships.len(), 1);
+        let subjects = dataset.subjects.unwrap();
+        assert_eq!(
+            subjects.key,
+            SafeValue::ShortString("subject_id".to_string())
+        );
+        assert_eq!(subjects.files, 2);
+        // About 800, bucketed even though the options ask for exact counts
+        assert_eq!(
+            subjects.estimated_subjects,
+            SafeValue::ShortString("101-1000".to_string())
+        );
+        assert!(dataset.warnings.is_empty());
+
+        // age, sex and geography only come together across the linked files
+        assert_eq!(dataset.files[0].risk, RiskGrade::Low);
+        assert_eq!(dataset.files[1].risk, RiskGrade::Moderate);
+        assert_eq!(dataset.risk.grade, RiskGrade::High);
+        let linked = dataset.risk.signals.last().unwrap();
+        assert!(linked.contains("(age, geography, sex)"), "{}", linked);
+    }
+
+    #[test]
+    fn test_subject_column() {
+        let salt = HashSalt::random();
+        let files = vec![
+            keyed_file("screening.csv", "USUBJID", 0..1500, Some(&salt), &[]),
+            keyed_file("visits.csv", "usubjid", 1000..2500, Some(&salt), &[]),
+            keyed_file("sites.csv", "site_id", 0..20, None, &[]),
+        ];
+        let options = ProcessingOptions {
+            subject_column: Some("usubjid".to_string()),
+            ..ProcessingOptions::default()
+        };
+        let manifest = MultiFileManifest::new(files);
+
+        // Counted from the subject column's sketches, without a detected join key
+        let dataset = describe_dataset("trial", &manifest, &options);
+        assert!(dataset.key_relationships.is_empty());
+        let subjects = dataset.subjects.unwrap();
+        assert_eq!(subjects.key, SafeValue::ShortString("USUBJID".to_string()));
+        assert_eq!(subjects.files, 2);
+        assert_eq!(
+            subjects.estimated_subjects,
+            SafeValue::ShortString("1001-10000".to_string())
+        );
+        assert_eq!(dataset.warnings.len(), 1);
+        assert!(dataset.warnings[0].contains("sites.csv"));
+
+        // No file has the column the scan was given
+        let files = vec![file("a.csv", 0..50, &[]), file("b.csv", 0..50, &[])];
+        let dataset = describe_dataset("trial", &MultiFileManifest::new(files), &options);
+        assert!(dataset.subjects.is_none());
+        assert_eq!(dataset.warnings.len(), 1);
+        assert!(dataset.warnings[0].contains("a.csv, b.csv"));
+    }
+
+    #[test]
+    fn test_unlinked_files() {
+        let files = vec![file("a.csv", 0..50, &[]), file("b.csv", 1000..1050, &[])];
+        let options = ProcessingOptions::default();
+        let mut manifest = MultiFileManifest::new(files);
+        manifest.join_keys = detect_join_keys(&manifest.files, &options);
+
+        let dataset = describe_dataset("trial", &manifest, &options);
+        assert!(dataset.key_relationships.is_empty());
+        assert!(dataset.subjects.is_none());
+        assert_eq!(dataset.warnings.len(), 1);
+        assert_eq!(dataset.risk.grade, RiskGrade::Low);
+    }
+}
@@ -12,12 +12,35 @@ pub enum Error {
     #[error("Excel error: {0}")]
     Excel(#[from] calamine::Error),
 
+    #[error("Database error: {0}")]
+    Database(#[from] rusqlite::Error),
+
+    #[error("Excel export error: {0}")]
+    XlsxExport(#[from] rust_xlsxwriter::XlsxError),
+
     #[error("JSON error: {0}")]
     Json(#[from] serde_json::Error),
 
     #[error("Unsupported file format: {0}")]
     UnsupportedFormat(String),
 
+    #[error("Output file already exists: {0} (use --force to overwrite or --versioned to keep both)")]
+    OutputExists(String),
+
+    #[error("Config error: {0}")]
+    Config(String),
+
+    #[error("Template error: {0}")]
+    Template(String),
+
+    #[error("Rule pack error: {0}")]
+    RulePack(String),
+
     #[error("Invalid input: {0}")]
     InvalidInput(String),
+
+    /// The user stopped a GUI scan
+    #[cfg(feature = "gui")]
+    #[error("Cancelled")]
+    Cancelled,
 }
@@ -0,0 +1,941 @@
+use std::collections::HashSet;
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::Arc;
+
+use zeroize::Zeroizing;
+
+use crate::audit::{AuditDecision, AuditSink};
+use crate::error::Error;
+use crate::history;
+use crate::output;
+use crate::paths;
+use crate::privacy::RecodeFormat;
+use crate::schema;
+use crate::types::{
+    Classification, ClassificationOverride, ManifestSchema, ProcessingOptions, Result,
+};
+
+/// Classifications a reviewer can assign
+const REVIEW_CLASSIFICATIONS: [Classification; 4] = [
+    Classification::Safe,
+    Classification::Warning,
+    Classification::Phi,
+    Classification::Recode,
+];
+
+/// GUI Application state
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum GuiState {
+    #[default]
+    Ready,
+    Processing,
+    Done,
+    Error(String),
+}
+
+/// One column in the review table
+#[derive(Debug, Clone, PartialEq)]
+struct ReviewRow {
+    sheet: String,
+    column_index: usize,
+    name: String,
+    dtype: &'static str,
+    /// Classification in the current manifest
+    current: Classification,
+    /// Classification chosen by the reviewer
+    chosen: Classification,
+}
+
+impl ReviewRow {
+    fn from_manifest(manifest: &ManifestSchema) -> Vec<Self> {
+        let mut rows = Vec::new();
+        for sheet in &manifest.sheets {
+            for col in &sheet.columns {
+                rows.push(Self {
+                    sheet: sheet.name.clone(),
+                    column_index: col.index,
+                    name: col.name.to_string(),
+                    dtype: col.dtype.as_str(),
+                    current: col.classification.clone(),
+                    chosen: col.classification.clone(),
+                });
+            }
+        }
+        rows
+    }
+}
+
+/// Outcome of one file in a multi-file queue
+#[derive(Debug, Clone, PartialEq)]
+enum QueueStatus {
+    Pending,
+    Done {
+        manifest: String,
+        sidekick: Option<Zeroizing<String>>,
+        warnings: usize,
+    },
+    Failed(String),
+}
+
+/// One file in a multi-file queue
+#[derive(Debug, Clone, PartialEq)]
+struct QueueItem {
+    path: PathBuf,
+    status: QueueStatus,
+}
+
+/// A finished scan of one file
+struct ScanOutput {
+    manifest: String,
+    review: Vec<ReviewRow>,
+    sidekick: Option<Zeroizing<String>>,
+    warnings: Vec<String>,
+}
+
+/// Scan a file, log it to the history database and serialize the manifest
+fn scan_file(
+    path: &Path,
+    options: &ProcessingOptions,
+    history_db: &Path,
+    audit: &mut dyn AuditSink,
+) -> Result<ScanOutput> {
+    let extraction_result = schema::extract_schema(path, options.clone(), audit)?;
+    let manifest = &extraction_result.manifest;
+
+    let mut warnings = manifest.warnings.clone();
+    for sheet in &manifest.sheets {
+        warnings.extend(sheet.warnings.clone());
+        for col in &sheet.columns {
+            warnings.extend(col.warnings.clone());
+        }
+    }
+
+    if let Err(e) = history::record_scan(history_db, manifest, "gui") {
+        warnings.push(format!("Failed to record scan history: {}", e));
+    }
+
+    Ok(ScanOutput {
+        manifest: output::to_json_string(manifest)?,
+        review: ReviewRow::from_manifest(manifest),
+        sidekick: extraction_result
+            .recodings
+            .map(|r| r.sidekick_content(RecodeFormat::Txt)),
+        warnings,
+    })
+}
+
+/// Messages from the worker thread to the UI
+enum WorkerMessage {
+    Progress(String),
+    Finished(Result<ScanOutput>),
+}
+
+/// Forwards scan progress to the UI and stops the scan once cancelled
+struct ProgressSink {
+    sender: Sender<WorkerMessage>,
+    cancel: Arc<AtomicBool>,
+    ctx: egui::Context,
+}
+
+impl AuditSink for ProgressSink {
+    fn begin_file(&mut self, _file_name: &str) -> Result<()> {
+        Ok(())
+    }
+
+    fn record(&mut self, _decision: AuditDecision) -> Result<()> {
+        Ok(())
+    }
+
+    fn progress(&mut self, sheet: &str, pass: u8, rows: u64) -> Result<()> {
+        if self.cancel.load(Ordering::Relaxed) {
+            return Err(Error::Cancelled);
+        }
+        let stage = if pass == 1 { "inferring types" } else { "collecting statistics" };
+        let _ = self.sender.send(WorkerMessage::Progress(format!(
+            "{}: {} ({} rows)",
+            sheet, stage, rows
+        )));
+        self.ctx.request_repaint();
+        Ok(())
+    }
+}
+
+/// A scan running on a background thread, so the window stays responsive
+struct Worker {
+    receiver: Receiver<WorkerMessage>,
+    cancel: Arc<AtomicBool>,
+    /// Queue item being scanned (`None` for a single file)
+    queue_index: Option<usize>,
+}
+
+impl Worker {
+    fn spawn(
+        ctx: &egui::Context,
+        path: PathBuf,
+        options: ProcessingOptions,
+        history_db: PathBuf,
+        queue_index: Option<usize>,
+    ) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let mut sink = ProgressSink {
+            sender: sender.clone(),
+            cancel: cancel.clone(),
+            ctx: ctx.clone(),
+        };
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let result = scan_file(&path, &options, &history_db, &mut sink);
+            let _ = sender.send(WorkerMessage::Finished(result));
+            ctx.request_repaint();
+        });
+
+        Self {
+            receiver,
+            cancel,
+            queue_index,
+        }
+    }
+
+    /// Latest progress text and the result once the scan has finished
+    fn poll(&self) -> (Option<String>, Option<Result<ScanOutput>>) {
+        let mut progress = None;
+        loop {
+            match self.receiver.try_recv() {
+                Ok(WorkerMessage::Progress(text)) => progress = Some(text),
+                Ok(WorkerMessage::Finished(result)) => return (progress, Some(result)),
+                Err(TryRecvError::Empty) => return (progress, None),
+                Err(TryRecvError::Disconnected) => {
+                    let lost = Error::InvalidInput("processing stopped unexpectedly".to_string());
+                    return (progress, Some(Err(lost)));
+                }
+            }
+        }
+    }
+}
+
+/// Expand dropped folders into the supported data files they contain
+fn expand_inputs(dropped: Vec<PathBuf>) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in dropped {
+        if path.is_dir() {
+            files.extend(schema::list_data_files(&path, &[])?);
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+/// ZIP archive of the manifests and recode files of every finished queue item.
+///
+/// Entries are named after the input file; inputs with the same name (from
+/// different folders) get a numeric prefix so nothing is overwritten.
+fn bundle_zip(items: &[QueueItem]) -> Result<Vec<u8>> {
+    let zip_error = |e: zip::result::ZipError| Error::InvalidInput(format!("ZIP error: {}", e));
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    let mut used = HashSet::new();
+
+    for (i, item) in items.iter().enumerate() {
+        let QueueStatus::Done {
+            manifest, sidekick, ..
+        } = &item.status
+        else {
+            continue;
+        };
+        let mut name = paths::file_name_lossy(&item.path);
+        if !used.insert(name.clone()) {
+            name = format!("{}_{}", i + 1, name);
+            used.insert(name.clone());
+        }
+        let stem = Path::new(&name)
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| name.clone());
+
+        zip.start_file(format!("{}.manifest.json", stem), options)
+            .map_err(zip_error)?;
+        zip.write_all(manifest.as_bytes())?;
+        if let Some(sidekick) = sidekick {
+            zip.start_file(format!("{}{}", name, RecodeFormat::Txt.suffix()), options)
+                .map_err(zip_error)?;
+            zip.write_all(sidekick.as_bytes())?;
+        }
+    }
+
+    Ok(zip.finish().map_err(zip_error)?.into_inner())
+}
+
+/// GUI Application
+pub struct GuiApp {
+    state: GuiState,
+    dropped_file: Option<PathBuf>,
+    /// Files dropped together, processed one after another
+    queue: Vec<QueueItem>,
+    worker: Option<Worker>,
+    /// Latest progress report from the worker
+    progress: Option<String>,
+    result: Option<String>,
+    review: Vec<ReviewRow>,
+    warnings: Vec<String>,
+    options: ProcessingOptions,
+    history_db: PathBuf,
+}
+
+impl Default for GuiApp {
+    fn default() -> Self {
+        Self {
+            state: GuiState::Ready,
+            dropped_file: None,
+            queue: Vec::new(),
+            worker: None,
+            progress: None,
+            result: None,
+            review: Vec::new(),
+            warnings: Vec::new(),
+            options: ProcessingOptions::default(),
+            history_db: history::default_db_path(),
+        }
+    }
+}
+
+impl eframe::App for GuiApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // Handle dropped files and folders
+        let dropped: Vec<PathBuf> = ctx.input(|i| {
+            i.raw
+                .dropped_files
+                .iter()
+                .filter_map(|f| f.path.clone())
+                .collect()
+        });
+        if !dropped.is_empty() && self.state != GuiState::Processing {
+            self.open(dropped);
+        }
+
+        // Collect worker results, then start the next scan if needed
+        self.poll_worker();
+        if self.state == GuiState::Processing && self.worker.is_none() {
+            self.start_next(ctx);
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("ert-manifest v0.1.1");
+            ui.add_space(10.0);
+
+            match &self.state {
+                GuiState::Ready => {
+                    self.show_ready_state(ui);
+                }
+                GuiState::Processing => {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        if ui.button("Cancel").clicked() {
+                            if let Some(ref worker) = self.worker {
+                                worker.cancel.store(true, Ordering::Relaxed);
+                            }
+                        }
+                    });
+                    if let Some(ref progress) = self.progress {
+                        ui.label(progress);
+                    }
+                    if self.queue.is_empty() {
+                        ui.label("Processing file...");
+                    } else {
+                        let finished = self
+                            .queue
+                            .iter()
+                            .filter(|item| item.status != QueueStatus::Pending)
+                            .count();
+                        ui.label(format!(
+                            "Processing file {} of {}...",
+                            (finished + 1).min(self.queue.len()),
+                            self.queue.len()
+                        ));
+                        self.show_queue(ui);
+                    }
+                }
+                GuiState::Done if !self.queue.is_empty() => {
+                    self.show_queue_done(ui);
+                }
+                GuiState::Done => {
+                    self.show_done_state(ui, ctx);
+                }
+                GuiState::Error(msg) => {
+                    let msg = msg.clone();
+                    self.show_error_state(ui, msg);
+                }
+            }
+        });
+    }
+}
+
+impl GuiApp {
+    /// Create the app, logging scans to the given history database
+    pub fn with_history_db(history_db: PathBuf) -> Self {
+        Self {
+            history_db,
+            ..Self::default()
+        }
+    }
+
+    /// Process dropped files with these options instead of the defaults
+    pub fn with_options(mut self, options: ProcessingOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Start processing dropped or picked paths: one file is scanned for
+    /// review, several files (or a folder) are queued
+    fn open(&mut self, dropped: Vec<PathBuf>) {
+        self.reset();
+        let files = match expand_inputs(dropped) {
+            Ok(files) => files,
+            Err(e) => {
+                self.state = GuiState::Error(e.to_string());
+                return;
+            }
+        };
+        match files.len() {
+            0 => {
+                self.state = GuiState::Error("No supported data files found".to_string());
+            }
+            1 => {
+                self.dropped_file = files.into_iter().next();
+                self.state = GuiState::Processing;
+            }
+            _ => {
+                self.queue = files
+                    .into_iter()
+                    .map(|path| QueueItem {
+                        path,
+                        status: QueueStatus::Pending,
+                    })
+                    .collect();
+                self.state = GuiState::Processing;
+            }
+        }
+    }
+
+    /// Scan the single file, or the next pending file in the queue; done
+    /// when none are left
+    fn start_next(&mut self, ctx: &egui::Context) {
+        let (path, queue_index) = if self.queue.is_empty() {
+            match self.dropped_file.clone() {
+                Some(path) => (path, None),
+                None => {
+                    self.state = GuiState::Ready;
+                    return;
+                }
+            }
+        } else {
+            match self
+                .queue
+                .iter()
+                .position(|item| item.status == QueueStatus::Pending)
+            {
+                Some(i) => (self.queue[i].path.clone(), Some(i)),
+                None => {
+                    self.state = GuiState::Done;
+                    return;
+                }
+            }
+        };
+        self.progress = None;
+        self.worker = Some(Worker::spawn(
+            ctx,
+            path,
+            self.options.clone(),
+            self.history_db.clone(),
+            queue_index,
+        ));
+    }
+
+    fn poll_worker(&mut self) {
+        let Some(ref worker) = self.worker else {
+            return;
+        };
+        let (progress, result) = worker.poll();
+        if progress.is_some() {
+            self.progress = progress;
+        }
+        let Some(result) = result else {
+            return;
+        };
+        let cancelled = worker.cancel.load(Ordering::Relaxed);
+        let queue_index = worker.queue_index;
+        self.worker = None;
+        self.progress = None;
+
+        match queue_index {
+            Some(i) => {
+                self.queue[i].status = match result {
+                    Ok(scan) => QueueStatus::Done {
+                        manifest: scan.manifest,
+                        sidekick: scan.sidekick,
+                        warnings: scan.warnings.len(),
+                    },
+                    Err(e) => QueueStatus::Failed(e.to_string()),
+                };
+                if cancelled {
+                    // Files already done can still be saved
+                    for item in &mut self.queue {
+                        if item.status == QueueStatus::Pending {
+                            item.status = QueueStatus::Failed(Error::Cancelled.to_string());
+                        }
+                    }
+                }
+            }
+            None if cancelled => self.reset(),
+            None => self.finish_single(result),
+        }
+    }
+
+    fn finish_single(&mut self, result: Result<ScanOutput>) {
+        match result {
+            Ok(scan) => {
+                self.warnings = scan.warnings;
+
+                // Write sidekick file if recoding was done
+                if let (Some(ref sidekick_content), Some(ref path)) =
+                    (scan.sidekick, &self.dropped_file)
+                {
+                    let sidekick_path = paths::sidekick_path(path, None, RecodeFormat::Txt.suffix());
+                    if let Err(e) = output::write_atomic(
+                        &paths::for_fs_access(&sidekick_path),
+                        sidekick_content.as_bytes(),
+                    ) {
+                        self.warnings.push(format!("Failed to write recode file: {}", e));
+                    } else {
+                        self.warnings.push(format!(
+                            "Recode mapping saved to: {}",
+                            paths::display_path(&sidekick_path)
+                        ));
+                    }
+                }
+
+                self.result = Some(scan.manifest);
+                self.review = scan.review;
+                self.state = GuiState::Done;
+            }
+            Err(e) => {
+                self.state = GuiState::Error(e.to_string());
+            }
+        }
+    }
+
+    /// Per-file status of the queue
+    fn show_queue(&self, ui: &mut egui::Ui) {
+        egui::ScrollArea::vertical()
+            .id_source("queue")
+            .max_height(400.0)
+            .show(ui, |ui| {
+                egui::Grid::new("queue_grid").striped(true).show(ui, |ui| {
+                    ui.strong("File");
+                    ui.strong("Status");
+                    ui.end_row();
+
+                    for item in &self.queue {
+                        ui.label(paths::display_path(&item.path));
+                        match &item.status {
+                            QueueStatus::Pending => {
+                                ui.label("Waiting");
+                            }
+                            QueueStatus::Done { warnings: 0, .. } => {
+                                ui.label("Done");
+                            }
+                            QueueStatus::Done { warnings, .. } => {
+                                ui.colored_label(
+                                    egui::Color32::YELLOW,
+                                    format!("Done ({} warnings)", warnings),
+                                );
+                            }
+                            QueueStatus::Failed(msg) => {
+                                ui.colored_label(egui::Color32::RED, format!("Error: {}", msg));
+                            }
+                        }
+                        ui.end_row();
+                    }
+                });
+            });
+    }
+
+    fn show_queue_done(&mut self, ui: &mut egui::Ui) {
+        let done = self
+            .queue
+            .iter()
+            .filter(|item| matches!(item.status, QueueStatus::Done { .. }))
+            .count();
+        ui.label(format!(
+            "{} of {} files processed successfully",
+            done,
+            self.queue.len()
+        ));
+        ui.add_space(10.0);
+
+        let mut should_reset = false;
+        let mut save_path: Option<PathBuf> = None;
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(done > 0, egui::Button::new("Save all as ZIP..."))
+                .clicked()
+            {
+                save_path = rfd::FileDialog::new()
+                    .add_filter("ZIP", &["zip"])
+                    .set_file_name("manifests.zip")
+                    .save_file();
+            }
+            if ui.button("New files").clicked() {
+                should_reset = true;
+            }
+        });
+
+        if let Some(path) = save_path {
+            let written = bundle_zip(&self.queue)
+                .and_then(|bytes| output::write_atomic(&paths::for_fs_access(&path), &bytes));
+            if let Err(e) = written {
+                self.state = GuiState::Error(e.to_string());
+                return;
+            }
+        }
+        if should_reset {
+            self.reset();
+            return;
+        }
+
+        ui.add_space(10.0);
+        self.show_queue(ui);
+    }
+
+    fn show_ready_state(&mut self, ui: &mut egui::Ui) {
+        // Drag and drop zone
+        let drop_zone = egui::Frame::none()
+            .fill(egui::Color32::from_gray(40))
+            .stroke(egui::Stroke::new(2.0, egui::Color32::from_gray(100)))
+            .rounding(10.0)
+            .inner_margin(40.0);
+
+        drop_zone.show(ui, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.label("Drag and drop data files or a folder here");
+                ui.label("(CSV, TSV, Excel)");
+                ui.add_space(20.0);
+                ui.label("or");
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    if ui.button("Browse...").clicked() {
+                        if let Some(paths) = rfd::FileDialog::new()
+                            .add_filter(
+                                "Data files",
+                                &["csv", "tsv", "xlsx", "xls", "xpt", "txt", "gz"],
+                            )
+                            .pick_files()
+                        {
+                            self.open(paths);
+                        }
+                    }
+                    if ui.button("Browse folder...").clicked() {
+                        if let Some(path) = rfd::FileDialog::new().pick_folder() {
+                            self.open(vec![path]);
+                        }
+                    }
+                });
+            });
+        });
+
+        ui.add_space(20.0);
+
+        // Options
+        ui.collapsing("Options", |ui| {
+            ui.horizontal(|ui| {
+                ui.label("K-anonymity:");
+                let mut k = self.options.k_anonymity as i32;
+                if ui.add(egui::Slider::new(&mut k, 1..=20)).changed() {
+                    self.options.k_anonymity = k as u64;
+                }
+            });
+
+            ui.checkbox(&mut self.options.bucket_counts, "Bucket counts");
+            ui.checkbox(&mut self.options.hash_file, "Compute file hash");
+
+            ui.add_enabled_ui(self.options.relaxed, |ui| {
+                ui.checkbox(&mut self.options.exact_counts, "Exact counts");
+                ui.checkbox(&mut self.options.exact_median, "Exact median");
+            });
+
+            ui.checkbox(&mut self.options.relaxed, "Relaxed mode");
+        });
+    }
+
+    fn show_done_state(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
+        // Show warnings if any
+        let warnings_count = self.warnings.len();
+        if warnings_count > 0 {
+            let warnings_clone = self.warnings.clone();
+            ui.collapsing(format!("Warnings ({})", warnings_count), |ui| {
+                for warning in &warnings_clone {
+                    ui.colored_label(egui::Color32::YELLOW, warning);
+                }
+            });
+            ui.add_space(10.0);
+        }
+
+        // Show result
+        let json_clone = self.result.clone();
+        if let Some(ref json) = json_clone {
+            ui.label("Manifest generated successfully!");
+            ui.add_space(10.0);
+
+            let mut should_reset = false;
+            let mut should_copy = false;
+            let mut save_path: Option<PathBuf> = None;
+
+            ui.horizontal(|ui| {
+                if ui.button("Copy to clipboard").clicked() {
+                    should_copy = true;
+                }
+
+                if ui.button("Save to file...").clicked() {
+                    save_path = rfd::FileDialog::new()
+                        .add_filter("JSON", &["json"])
+                        .save_file();
+                }
+
+                if ui.button("New file").clicked() {
+                    should_reset = true;
+                }
+            });
+
+            if should_copy {
+                ctx.copy_text(json.clone());
+            }
+
+            if let Some(path) = save_path {
+                if let Err(e) = output::write_atomic(&paths::for_fs_access(&path), json.as_bytes()) {
+                    self.state = GuiState::Error(e.to_string());
+                    return;
+                }
+            }
+
+            if should_reset {
+                self.reset();
+                return;
+            }
+
+            ui.add_space(10.0);
+            self.show_review(ui);
+            ui.add_space(10.0);
+
+            egui::ScrollArea::vertical()
+                .max_height(400.0)
+                .show(ui, |ui| {
+                    let mut text = json.as_str();
+                    ui.add(
+                        egui::TextEdit::multiline(&mut text)
+                            .code_editor()
+                            .desired_width(f32::INFINITY),
+                    );
+                });
+        }
+    }
+
+    /// Column table where the reviewer can change classifications and re-scan
+    fn show_review(&mut self, ui: &mut egui::Ui) {
+        let mut apply = false;
+        ui.collapsing("Review columns", |ui| {
+            egui::ScrollArea::vertical()
+                .id_source("review")
+                .max_height(250.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("review_grid").striped(true).show(ui, |ui| {
+                        ui.strong("Sheet");
+                        ui.strong("#");
+                        ui.strong("Column");
+                        ui.strong("Type");
+                        ui.strong("Classification");
+                        ui.end_row();
+
+                        for (i, row) in self.review.iter_mut().enumerate() {
+                            ui.label(&row.sheet);
+                            ui.label((row.column_index + 1).to_string());
+                            ui.label(&row.name);
+                            ui.label(row.dtype);
+                            egui::ComboBox::from_id_source(("classification", i))
+                                .selected_text(row.chosen.as_str())
+                                .show_ui(ui, |ui| {
+                                    for c in REVIEW_CLASSIFICATIONS {
+                                        let label = c.as_str();
+                                        ui.selectable_value(&mut row.chosen, c, label);
+                                    }
+                                });
+                            ui.end_row();
+                        }
+                    });
+                });
+
+            let changed = self.review.iter().any(|r| r.chosen != r.current);
+            ui.add_space(5.0);
+            if ui
+                .add_enabled(changed, egui::Button::new("Apply and re-scan"))
+                .clicked()
+            {
+                apply = true;
+            }
+        });
+
+        if apply {
+            self.apply_review();
+        }
+    }
+
+    /// Record the reviewer's changes as overrides and scan the file again
+    fn apply_review(&mut self) {
+        for row in self.review.iter().filter(|r| r.chosen != r.current) {
+            self.options
+                .classification_overrides
+                .retain(|o| !(o.sheet == row.sheet && o.column_index == row.column_index));
+            self.options
+                .classification_overrides
+                .push(ClassificationOverride {
+                    sheet: row.sheet.clone(),
+                    column_index: row.column_index,
+                    classification: row.chosen.clone(),
+                });
+        }
+        self.state = GuiState::Processing;
+    }
+
+    fn show_error_state(&mut self, ui: &mut egui::Ui, msg: String) {
+        ui.colored_label(egui::Color32::RED, format!("Error: {}", msg));
+        ui.add_space(20.0);
+        if ui.button("Try again").clicked() {
+            self.reset();
+        }
+    }
+
+    fn reset(&mut self) {
+        // A running scan stops at its next progress report
+        if let Some(worker) = self.worker.take() {
+            worker.cancel.store(true, Ordering::Relaxed);
+        }
+        self.progress = None;
+        self.state = GuiState::Ready;
+        self.dropped_file = None;
+        self.queue.clear();
+        self.result = None;
+        self.review.clear();
+        self.warnings.clear();
+        // Overrides belong to the file they were made for
+        self.options.classification_overrides.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn done(path: &str, sidekick: Option<&str>) -> QueueItem {
+        QueueItem {
+            path: PathBuf::from(path),
+            status: QueueStatus::Done {
+                manifest: format!("{{\"file_name\": \"{}\"}}", path),
+                sidekick: sidekick.map(|s| Zeroizing::new(s.to_string())),
+                warnings: 0,
+            },
+        }
+    }
+
+    #[test]
+    fn test_bundle_zip() {
+        let items = vec![
+            done("site1/labs.csv", Some("mapping")),
+            QueueItem {
+                path: PathBuf::from("broken.xlsx"),
+                status: QueueStatus::Failed("bad zip".to_string()),
+            },
+            done("site2/labs.csv", None),
+        ];
+
+        let bytes = bundle_zip(&items).unwrap();
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+        let mut names: Vec<&str> = archive.file_names().collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "3_labs.manifest.json",
+                "labs.csv.recode.txt",
+                "labs.manifest.json"
+            ]
+        );
+
+        let mut text = String::new();
+        archive
+            .by_name("3_labs.manifest.json")
+            .unwrap()
+            .read_to_string(&mut text)
+            .unwrap();
+        assert!(text.contains("site2/labs.csv"));
+    }
+
+    fn wait(worker: &Worker) -> Result<ScanOutput> {
+        loop {
+            if let (_, Some(result)) = worker.poll() {
+                return result;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn test_worker() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("trial.csv");
+        std::fs::write(&path, "id,age\n1,30\n2,41\n").unwrap();
+        let ctx = egui::Context::default();
+        let history_db = dir.path().join("history.sqlite");
+        let options = ProcessingOptions::default();
+
+        let worker = Worker::spawn(&ctx, path, options, history_db, None);
+        let scan = wait(&worker).unwrap();
+        assert!(scan.manifest.contains("trial.csv"));
+        assert_eq!(scan.review.len(), 2);
+    }
+
+    #[test]
+    fn test_progress_sink_cancel() {
+        let (sender, receiver) = mpsc::channel();
+        let mut sink = ProgressSink {
+            sender,
+            cancel: Arc::new(AtomicBool::new(false)),
+            ctx: egui::Context::default(),
+        };
+
+        sink.progress("trial.csv", 2, 1024).unwrap();
+        assert!(matches!(
+            receiver.try_recv(),
+            Ok(WorkerMessage::Progress(text)) if text == "trial.csv: collecting statistics (1024 rows)"
+        ));
+
+        sink.cancel.store(true, Ordering::Relaxed);
+        assert!(matches!(sink.progress("trial.csv", 2, 2048), Err(Error::Cancelled)));
+    }
+
+    #[test]
+    fn test_expand_inputs() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("b.csv"), "id\n1\n").unwrap();
+        std::fs::write(dir.path().join("a.tsv"), "id\n1\n").unwrap();
+        std::fs::write(dir.path().join("notes.docx"), "").unwrap();
+        let single = dir.path().join("b.csv");
+
+        let files = expand_inputs(vec![dir.path().to_path_buf(), single.clone()]).unwrap();
+        assert_eq!(files, vec![dir.path().join("a.tsv"), single.clone(), single]);
+    }
+}
@@ -0,0 +1,267 @@
+use std::path::{Path, PathBuf};
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::assess::assess;
+use crate::types::{Classification, ManifestSchema, Result};
+
+/// Default location of the scan history database (~/.ert-manifest/history.sqlite)
+pub fn default_db_path() -> PathBuf {
+    crate::paths::app_dir().join("history.sqlite")
+}
+
+/// A single recorded scan
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ScanRecord {
+    pub id: i64,
+    /// UTC timestamp (RFC 3339)
+    pub scanned_at: String,
+    /// Operating system user that ran the scan
+    pub user: String,
+    /// Interface used ("cli" or "gui")
+    pub interface: String,
+    pub file_name: String,
+    pub file_hash: Option<String>,
+    /// Processing options as JSON
+    pub options: String,
+    pub column_count: i64,
+    pub phi_columns: i64,
+    pub recode_columns: i64,
+    pub warning_columns: i64,
+    /// Re-identification risk grade of the file, as `assess` reports it
+    pub risk_grade: String,
+}
+
+impl ScanRecord {
+    /// Build a record from a finished manifest
+    pub fn from_manifest(manifest: &ManifestSchema, interface: &str) -> Self {
+        let columns = manifest.sheets.iter().flat_map(|s| s.columns.iter());
+        let count_class =
+            |class: Classification| columns.clone().filter(|c| c.classification == class).count();
+
+        Self {
+            id: 0,
+            scanned_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            user: current_user(),
+            interface: interface.to_string(),
+            file_name: manifest.file_name.clone(),
+            file_hash: manifest.file_hash.clone(),
+            options: serde_json::to_string(&manifest.options).unwrap_or_default(),
+            column_count: columns.clone().count() as i64,
+            phi_columns: count_class(Classification::Phi) as i64,
+            recode_columns: count_class(Classification::Recode) as i64,
+            warning_columns: count_class(Classification::Warning) as i64,
+            risk_grade: assess(manifest).grade.as_str().to_string(),
+        }
+    }
+}
+
+fn current_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+/// Local SQLite log of all scans performed on this machine
+pub struct HistoryDb {
+    conn: Connection,
+}
+
+impl HistoryDb {
+    /// Open (and create if needed) the history database at the given path
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+        Self::init(Connection::open(path)?)
+    }
+
+    #[cfg(test)]
+    fn open_in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS scans (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                scanned_at TEXT NOT NULL,
+                user TEXT NOT NULL,
+                interface TEXT NOT NULL,
+                file_name TEXT NOT NULL,
+                file_hash TEXT,
+                options TEXT NOT NULL,
+                column_count INTEGER NOT NULL,
+                phi_columns INTEGER NOT NULL,
+                recode_columns INTEGER NOT NULL,
+                warning_columns INTEGER NOT NULL,
+                risk_grade TEXT NOT NULL
+            );",
+        )?;
+        Ok(Self { conn })
+    }
+
+    /// Append a scan to the log, returning its id
+    pub fn record(&self, record: &ScanRecord) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO scans (scanned_at, user, interface, file_name, file_hash, options,
+                column_count, phi_columns, recode_columns, warning_columns, risk_grade)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                record.scanned_at,
+                record.user,
+                record.interface,
+                record.file_name,
+                record.file_hash,
+                record.options,
+                record.column_count,
+                record.phi_columns,
+                record.recode_columns,
+                record.warning_columns,
+                record.risk_grade,
+            ],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Most recent scans first, optionally filtered by file name substring
+    pub fn query(&self, file_filter: Option<&str>, limit: usize) -> Result<Vec<ScanRecord>> {
+        let pattern = format!("%{}%", file_filter.unwrap_or(""));
+        let mut stmt = self.conn.prepare(
+            "SELECT id, scanned_at, user, interface, file_name, file_hash, options,
+                column_count, phi_columns, recode_columns, warning_columns, risk_grade
+             FROM scans WHERE file_name LIKE ?1 ORDER BY id DESC LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(params![pattern, limit as i64], |row| {
+            Ok(ScanRecord {
+                id: row.get(0)?,
+                scanned_at: row.get(1)?,
+                user: row.get(2)?,
+                interface: row.get(3)?,
+                file_name: row.get(4)?,
+                file_hash: row.get(5)?,
+                options: row.get(6)?,
+                column_count: row.get(7)?,
+                phi_columns: row.get(8)?,
+                recode_columns: row.get(9)?,
+                warning_columns: row.get(10)?,
+                risk_grade: row.get(11)?,
+            })
+        })?;
+
+        let mut records = Vec::new();
+        for row in rows {
+            records.push(row?);
+        }
+        Ok(records)
+    }
+}
+
+/// Record a finished scan in the history database at `path`
+pub fn record_scan(path: &Path, manifest: &ManifestSchema, interface: &str) -> Result<()> {
+    let db = HistoryDb::open(path)?;
+    db.record(&ScanRecord::from_manifest(manifest, interface))?;
+    Ok(())
+}
+
+/// Format records as a plain-text table for the `history` command
+pub fn format_table(records: &[ScanRecord]) -> String {
+    let mut lines = vec![format!(
+        "{:>5}  {:<20}  {:<12}  {:<4}  {:<30}  {:>4}  {:>4}  {:>4}  {:<8}  {:<16}",
+        "ID", "SCANNED AT", "USER", "VIA", "FILE", "COLS", "PHI", "REC", "RISK", "SHA-256"
+    )];
+    for r in records {
+        let hash = r
+            .file_hash
+            .as_deref()
+            .map(|h| h.chars().take(16).collect::<String>())
+            .unwrap_or_else(|| "-".to_string());
+        lines.push(format!(
+            "{:>5}  {:<20}  {:<12}  {:<4}  {:<30}  {:>4}  {:>4}  {:>4}  {:<8}  {:<16}",
+            r.id,
+            r.scanned_at,
+            r.user,
+            r.interface,
+            r.file_name,
+            r.column_count,
+            r.phi_columns,
+            r.recode_columns,
+            r.risk_grade,
+            hash
+        ));
+    }
+    lines
+        .iter()
+        .map(|l| l.trim_end())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ColumnSchema, DType, FileFormat, SafeValue, SheetSchema};
+
+    fn sample_manifest(file_name: &str) -> ManifestSchema {
+        let mut manifest = ManifestSchema::new(file_name.to_string(), FileFormat::Csv);
+        manifest.file_hash = Some("ab".repeat(32));
+        let mut sheet = SheetSchema::new(file_name.to_string(), 0);
+        let mut phi = ColumnSchema::new(
+            SafeValue::Suppressed {
+                reason: "PHI".to_string(),
+            },
+            0,
+            DType::String,
+        );
+        phi.classification = Classification::Phi;
+        sheet.columns.push(phi);
+        sheet.columns.push(ColumnSchema::new(
+            SafeValue::ShortString("age".to_string()),
+            1,
+            DType::Integer,
+        ));
+        manifest.sheets.push(sheet);
+        manifest
+    }
+
+    #[test]
+    fn test_record_from_manifest() {
+        let record = ScanRecord::from_manifest(&sample_manifest("a.csv"), "cli");
+        assert_eq!(record.column_count, 2);
+        assert_eq!(record.phi_columns, 1);
+        assert_eq!(record.recode_columns, 0);
+        assert_eq!(record.risk_grade, "high");
+        assert!(record.options.contains("k_anonymity"));
+    }
+
+    #[test]
+    fn test_record_and_query() {
+        let db = HistoryDb::open_in_memory().unwrap();
+        db.record(&ScanRecord::from_manifest(&sample_manifest("labs.csv"), "cli"))
+            .unwrap();
+        db.record(&ScanRecord::from_manifest(&sample_manifest("demo.xlsx"), "gui"))
+            .unwrap();
+
+        let all = db.query(None, 10).unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].file_name, "demo.xlsx"); // Most recent first
+        assert!(format_table(&all).lines().nth(1).unwrap().contains("high"));
+
+        let labs = db.query(Some("labs"), 10).unwrap();
+        assert_eq!(labs.len(), 1);
+        assert_eq!(labs[0].interface, "cli");
+    }
+
+    #[test]
+    fn test_open_creates_parent_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("history.sqlite");
+        record_scan(&path, &sample_manifest("x.csv"), "cli").unwrap();
+
+        let db = HistoryDb::open(&path).unwrap();
+        assert_eq!(db.query(None, 10).unwrap().len(), 1);
+    }
+}
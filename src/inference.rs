@@ -1,8 +1,10 @@
-use chrono::NaiveDate;
+use chrono::{NaiveDate, NaiveDateTime};
 use once_cell::sync::Lazy;
 use regex::Regex;
 
-use crate::types::{DType, TYPE_INFERENCE_SAMPLE_SIZE};
+use std::borrow::Cow;
+
+use crate::types::{DType, DecimalSeparator, TYPE_INFERENCE_SAMPLE_SIZE};
 
 /// Boolean tokens (case-insensitive)
 const TRUE_TOKENS: &[&str] = &["true", "yes", "y", "1", "t"];
@@ -14,6 +16,9 @@ pub const MISSING_TOKENS: &[&str] = &[
     "MISSING", "None", "none", "#N/A", "#VALUE!", "#REF!", "#DIV/0!", "#NUM!", "#NAME?", "#NULL!",
 ];
 
+const COMPACT_YMD: &str = "%Y%m%d";
+const COMPACT_DMY: &str = "%d%m%Y";
+
 // Date format patterns
 static DATE_PATTERNS: Lazy<Vec<(Regex, &'static str)>> = Lazy::new(|| {
     vec![
@@ -38,6 +43,11 @@ static DATE_PATTERNS: Lazy<Vec<(Regex, &'static str)>> = Lazy::new(|| {
         ),
         // ISO with dots: 2024.01.15
         (Regex::new(r"^\d{4}\.\d{2}\.\d{2}$").unwrap(), "%Y.%m.%d"),
+        // Compact, as stored in integer columns: 20240115 or 15012024.
+        // Either reading of a compact ddmmyyyy value has month 19 or 20, so at
+        // most one of them parses.
+        (Regex::new(r"^(19|20)\d{6}$").unwrap(), COMPACT_YMD),
+        (Regex::new(r"^\d{4}(19|20)\d{2}$").unwrap(), COMPACT_DMY),
     ]
 });
 
@@ -77,10 +87,42 @@ pub struct TypeInferencer {
     initial_inference_done: bool,
     /// Count of detected free text (long strings)
     free_text_count: u64,
+    /// Layout of dates stored as 8-digit integers (`yyyymmdd` or `ddmmyyyy`)
+    compact_dates: Option<&'static str>,
+    /// Decimal separator set by `--decimal-separator`, or settled from the samples
+    decimal_separator: Option<DecimalSeparator>,
+    /// Values only readable with a decimal period (`3.14`)
+    period_decimal_values: u64,
+    /// Values only readable with a decimal comma (`3,14`)
+    comma_decimal_values: u64,
+    /// Numeric values containing a comma, when read with a decimal comma
+    comma_values: u64,
+    /// Values with grouped digits when read with a decimal period (`1,234`)
+    grouped_period_values: u64,
+    /// Values with grouped digits when read with a decimal comma (`1.234`)
+    grouped_comma_values: u64,
+    /// Values written as percentages (`45%`)
+    percent_values: u64,
+    /// Values skipped as missing
+    missing: MissingTokens,
+    /// Type inferred from the samples, before any widening
+    initial_type: Option<DType>,
+    /// Values the initial inference was made from
+    sample_size: u64,
+    /// Values after the samples that did not fit the initial type
+    conflicts: u64,
+    /// Types the column was widened to after the samples, in order
+    type_changes: Vec<DType>,
 }
 
 impl TypeInferencer {
     pub fn new() -> Self {
+        Self::with_decimal_separator(None)
+    }
+
+    /// Inferencer reading numbers with the given decimal separator, or detecting it from
+    /// the values when `None`
+    pub fn with_decimal_separator(decimal_separator: Option<DecimalSeparator>) -> Self {
         Self {
             current_type: None,
             samples: Vec::with_capacity(TYPE_INFERENCE_SAMPLE_SIZE),
@@ -88,17 +130,37 @@ impl TypeInferencer {
             values_seen: 0,
             initial_inference_done: false,
             free_text_count: 0,
+            compact_dates: None,
+            decimal_separator,
+            period_decimal_values: 0,
+            comma_decimal_values: 0,
+            comma_values: 0,
+            grouped_period_values: 0,
+            grouped_comma_values: 0,
+            percent_values: 0,
+            missing: MissingTokens::default(),
+            initial_type: None,
+            sample_size: 0,
+            conflicts: 0,
+            type_changes: Vec::new(),
         }
     }
 
+    /// Skip the given missing values instead of the built-in tokens alone
+    pub fn with_missing_tokens(mut self, missing: MissingTokens) -> Self {
+        self.missing = missing;
+        self
+    }
+
     /// Add a value for type inference
     pub fn observe(&mut self, value: &str) {
         // Skip missing values
-        if is_missing(value) {
+        if self.missing.is_missing(value) {
             return;
         }
 
         self.values_seen += 1;
+        self.observe_format(value);
 
         if !self.initial_inference_done {
             // Collect samples
@@ -128,6 +190,108 @@ impl TypeInferencer {
         self.current_type.unwrap_or(DType::String)
     }
 
+    /// Type the samples gave, before values later in the column widened it
+    pub fn initial_type(&self) -> DType {
+        self.initial_type.unwrap_or(DType::String)
+    }
+
+    /// Non-missing values the initial type was inferred from
+    pub fn sample_size(&self) -> u64 {
+        self.sample_size
+    }
+
+    /// Non-missing values after the samples that did not fit the initial type
+    pub fn conflicts(&self) -> u64 {
+        self.conflicts
+    }
+
+    /// Types the column was widened to after the samples, in order
+    pub fn type_changes(&self) -> &[DType] {
+        &self.type_changes
+    }
+
+    /// Layout of a date column whose values are 8-digit integers, such as `yyyymmdd`
+    pub fn compact_date_layout(&self) -> Option<&'static str> {
+        match self.current_type {
+            Some(DType::Date) => self.compact_dates,
+            _ => None,
+        }
+    }
+
+    /// Decimal separator of the column's numbers: the one set, else the one the values
+    /// so far need, a period when they don't say
+    pub fn decimal_separator(&self) -> DecimalSeparator {
+        self.decimal_separator.unwrap_or(if self.comma_decimal_values > self.period_decimal_values {
+            DecimalSeparator::Comma
+        } else {
+            DecimalSeparator::Period
+        })
+    }
+
+    /// Parse a number written with the column's decimal separator
+    pub fn parse_number(&self, value: &str) -> Option<f64> {
+        parse_number_as(value, self.decimal_separator())
+    }
+
+    /// Decimal separator a numeric column was read with, if its values use a decimal
+    /// comma or group their digits
+    pub fn numeric_locale(&self) -> Option<DecimalSeparator> {
+        let decimal = self.decimal_separator();
+        let marked = match decimal {
+            DecimalSeparator::Period => self.grouped_period_values > 0,
+            DecimalSeparator::Comma => self.comma_values > 0 || self.grouped_comma_values > 0,
+        };
+        match self.current_type {
+            Some(DType::Integer | DType::Numeric) if marked => Some(decimal),
+            _ => None,
+        }
+    }
+
+    /// How the column's values are written, when that matters for parsing them:
+    /// `decimal_comma` or `thousands_separator` for numbers, `percent` when every
+    /// value is a percentage
+    pub fn format_hint(&self) -> Option<&'static str> {
+        let grouped = match self.decimal_separator() {
+            DecimalSeparator::Period => self.grouped_period_values > 0,
+            DecimalSeparator::Comma => self.grouped_comma_values > 0,
+        };
+        let numeric = matches!(self.current_type, Some(DType::Integer | DType::Numeric));
+        if numeric && self.decimal_separator() == DecimalSeparator::Comma && self.comma_values > 0 {
+            Some("decimal_comma")
+        } else if numeric && grouped {
+            Some("thousands_separator")
+        } else if self.values_seen > 0 && self.percent_values == self.values_seen {
+            Some("percent")
+        } else {
+            None
+        }
+    }
+
+    fn observe_format(&mut self, value: &str) {
+        let trimmed = value.trim();
+        if let Some(number) = trimmed.strip_suffix('%') {
+            if parse_numeric(number).is_some() {
+                self.percent_values += 1;
+            }
+            return;
+        }
+        if !trimmed.contains([',', '.']) && !trimmed.contains(GROUP_SPACES) {
+            return;
+        }
+        // A value only one separator can read says which one the column uses
+        let period = normalize_number(trimmed, DecimalSeparator::Period);
+        let comma = normalize_number(trimmed, DecimalSeparator::Comma);
+        match (&period, &comma) {
+            (Some(_), None) => self.period_decimal_values += 1,
+            (None, Some(_)) => self.comma_decimal_values += 1,
+            _ => {}
+        }
+        let grouped = |number: &Option<(Cow<str>, bool)>| number.as_ref().is_some_and(|n| n.1);
+        self.grouped_period_values += u64::from(grouped(&period));
+        self.grouped_comma_values += u64::from(grouped(&comma));
+        self.comma_values += u64::from(comma.is_some() && trimmed.contains(','));
+    }
+
     /// Perform initial type inference on collected samples
     fn perform_initial_inference(&mut self) {
         if self.samples.is_empty() {
@@ -136,9 +300,16 @@ impl TypeInferencer {
             return;
         }
 
+        // The samples settle how the rest of the column's numbers are read
+        self.decimal_separator = Some(self.decimal_separator());
+
         // Try each type in order of specificity
+        self.compact_dates = self.common_compact_layout(&self.samples);
         let dtype = if self.all_boolean(&self.samples) {
             DType::Boolean
+        } else if self.compact_dates.is_some() {
+            // 20240115 is a date, not an integer whose mean means anything
+            DType::Date
         } else if self.all_integer(&self.samples) {
             DType::Integer
         } else if self.all_numeric(&self.samples) {
@@ -152,6 +323,8 @@ impl TypeInferencer {
         };
 
         self.current_type = Some(dtype);
+        self.initial_type = Some(dtype);
+        self.sample_size = self.samples.len() as u64;
         self.initial_inference_done = true;
 
         // Clear samples to free memory
@@ -162,20 +335,26 @@ impl TypeInferencer {
     /// Upgrade type during full scan if value doesn't fit current type
     fn upgrade_type_if_needed(&mut self, value: &str) {
         let current = self.current_type.unwrap_or(DType::String);
+        let initial = self.initial_type();
+        if current != initial && !self.fits(initial, value) {
+            self.conflicts += 1;
+        }
 
         // Check for free text (long strings)
         if value.len() > 100 || value.contains('\n') {
             self.free_text_count += 1;
             if self.free_text_count > 10 && current == DType::String {
                 self.current_type = Some(DType::FreeText);
+                self.type_changes.push(DType::FreeText);
                 return;
             }
         }
 
+        let decimal = self.decimal_separator();
         let new_type = match current {
             DType::Integer => {
-                if !is_integer(value) {
-                    if is_numeric(value) {
+                if !is_integer_as(value, decimal) {
+                    if parse_number_as(value, decimal).is_some() {
                         DType::Numeric
                     } else {
                         DType::String
@@ -185,7 +364,7 @@ impl TypeInferencer {
                 }
             }
             DType::Numeric => {
-                if !is_numeric(value) {
+                if parse_number_as(value, decimal).is_none() {
                     DType::String
                 } else {
                     return;
@@ -202,7 +381,11 @@ impl TypeInferencer {
                 if is_datetime(value) {
                     DType::Datetime
                 } else if !is_date(value) {
-                    DType::String
+                    if self.compact_dates.is_some() && is_integer_as(value, decimal) {
+                        DType::Integer
+                    } else {
+                        DType::String
+                    }
                 } else {
                     return;
                 }
@@ -219,19 +402,41 @@ impl TypeInferencer {
             }
         };
 
+        // Until the first widening, a value that widens is the one conflict
+        if current == initial {
+            self.conflicts += 1;
+        }
+        self.type_changes.push(new_type);
         self.current_type = Some(new_type);
     }
 
+    /// Whether a value reads as the given type, as the widening checks it
+    fn fits(&self, dtype: DType, value: &str) -> bool {
+        let decimal = self.decimal_separator();
+        match dtype {
+            DType::Integer => is_integer_as(value, decimal),
+            DType::Numeric => parse_number_as(value, decimal).is_some(),
+            DType::Boolean => is_boolean(value),
+            DType::Date => {
+                is_date(value)
+                    || (self.compact_dates.is_some()
+                        && compact_date_layout(value) == self.compact_dates)
+            }
+            DType::Datetime => is_datetime(value) || is_date(value),
+            DType::String | DType::FreeText => true,
+        }
+    }
+
     fn all_boolean(&self, values: &[String]) -> bool {
         values.iter().all(|v| is_boolean(v))
     }
 
     fn all_integer(&self, values: &[String]) -> bool {
-        values.iter().all(|v| is_integer(v))
+        values.iter().all(|v| is_integer_as(v, self.decimal_separator()))
     }
 
     fn all_numeric(&self, values: &[String]) -> bool {
-        values.iter().all(|v| is_numeric(v))
+        values.iter().all(|v| parse_number_as(v, self.decimal_separator()).is_some())
     }
 
     fn all_date(&self, values: &[String]) -> bool {
@@ -241,6 +446,37 @@ impl TypeInferencer {
     fn all_datetime(&self, values: &[String]) -> bool {
         values.iter().all(|v| is_datetime(v))
     }
+
+    /// Compact date layout shared by every value, if there is one
+    fn common_compact_layout(&self, values: &[String]) -> Option<&'static str> {
+        let first = compact_date_layout(values.first()?)?;
+        if values.iter().all(|v| compact_date_layout(v) == Some(first)) {
+            Some(first)
+        } else {
+            None
+        }
+    }
+}
+
+/// Layout of an 8-digit compact date (`yyyymmdd` or `ddmmyyyy`), if the value is one
+pub fn compact_date_layout(value: &str) -> Option<&'static str> {
+    let trimmed = value.trim();
+    if trimmed.len() != 8 || !trimmed.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    DATE_PATTERNS.iter().find_map(|(pattern, format)| {
+        let compact = *format == COMPACT_YMD || *format == COMPACT_DMY;
+        if compact && pattern.is_match(trimmed) && NaiveDate::parse_from_str(trimmed, format).is_ok()
+        {
+            Some(if *format == COMPACT_YMD {
+                "yyyymmdd"
+            } else {
+                "ddmmyyyy"
+            })
+        } else {
+            None
+        }
+    })
 }
 
 impl Default for TypeInferencer {
@@ -253,6 +489,53 @@ impl Default for TypeInferencer {
 pub fn is_missing(value: &str) -> bool {
     let trimmed = value.trim();
     MISSING_TOKENS.iter().any(|t| trimmed.eq_ignore_ascii_case(t))
+        || extended_missing_code(trimmed).is_some()
+}
+
+/// Values counted as missing in one column: the built-in tokens plus custom ones,
+/// or the custom ones alone. Empty cells and Stata extended missing codes always are.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MissingTokens {
+    custom: Vec<String>,
+    replace_builtin: bool,
+}
+
+impl MissingTokens {
+    /// The built-in tokens plus `custom`
+    pub fn extending(custom: &[String]) -> Self {
+        Self {
+            custom: custom.iter().map(|t| t.trim().to_string()).collect(),
+            replace_builtin: false,
+        }
+    }
+
+    /// `custom` instead of the built-in tokens
+    pub fn replacing(custom: &[String]) -> Self {
+        Self {
+            replace_builtin: true,
+            ..Self::extending(custom)
+        }
+    }
+
+    pub fn is_missing(&self, value: &str) -> bool {
+        let trimmed = value.trim();
+        let builtin = if self.replace_builtin {
+            trimmed.is_empty() || extended_missing_code(trimmed).is_some()
+        } else {
+            is_missing(trimmed)
+        };
+        builtin || self.custom.iter().any(|t| trimmed.eq_ignore_ascii_case(t))
+    }
+}
+
+/// Stata extended missing value (`.a` to `.z`), as written by Stata's
+/// exports; returns the code itself
+pub fn extended_missing_code(value: &str) -> Option<&str> {
+    let trimmed = value.trim();
+    match trimmed.as_bytes() {
+        [b'.', b'a'..=b'z'] => Some(trimmed),
+        _ => None,
+    }
 }
 
 /// Check if a value is a boolean
@@ -272,28 +555,172 @@ pub fn is_integer(value: &str) -> bool {
 
 /// Check if a value is numeric (integer or float)
 pub fn is_numeric(value: &str) -> bool {
-    let trimmed = value.trim();
-    if trimmed.is_empty() {
-        return false;
+    parse_numeric(value).is_some()
+}
+
+/// Check if a value is an integer written with the given decimal separator
+fn is_integer_as(value: &str, decimal: DecimalSeparator) -> bool {
+    normalize_number(value.trim(), decimal).is_some_and(|(number, _)| is_integer(&number))
+}
+
+/// Parse a number written with the given decimal separator (`1.234,5` with a comma)
+pub fn parse_number_as(value: &str, decimal: DecimalSeparator) -> Option<f64> {
+    normalize_number(value.trim(), decimal)?.0.parse().ok()
+}
+
+/// Spaces used to group digits (`1 234`)
+const GROUP_SPACES: [char; 3] = [' ', '\u{a0}', '\u{202f}'];
+
+/// Rewrite a number written with the given decimal separator, and possibly grouped
+/// digits, the way Rust parses it; also says whether the digits were grouped
+fn normalize_number(value: &str, decimal: DecimalSeparator) -> Option<(Cow<'_, str>, bool)> {
+    let (mark, grouping) = match decimal {
+        DecimalSeparator::Period => ('.', ','),
+        DecimalSeparator::Comma => (',', '.'),
+    };
+    if !value.contains(grouping) && value.parse::<f64>().is_ok() {
+        return Some((Cow::Borrowed(value), false));
+    }
+
+    let (sign, body) = match value.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", value.strip_prefix('+').unwrap_or(value)),
+    };
+    let (whole, fraction) = match body.split_once(mark) {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (body, None),
+    };
+
+    let is_digits = |part: &str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit());
+    let is_separator = |c: char| c == grouping || GROUP_SPACES.contains(&c);
+    let groups: Vec<&str> = whole.split(is_separator).collect();
+    let mut separators = whole.chars().filter(|&c| is_separator(c));
+    let first_separator = separators.next();
+    let groups_ok = groups.iter().all(|g| is_digits(g))
+        && (groups.len() == 1 || groups[0].len() <= 3)
+        && groups[1..].iter().all(|g| g.len() == 3)
+        && separators.all(|c| Some(c) == first_separator);
+    if !groups_ok || !fraction.iter().all(|f| is_digits(f)) {
+        return None;
+    }
+
+    let mut number = format!("{}{}", sign, groups.concat());
+    if let Some(fraction) = fraction {
+        number.push('.');
+        number.push_str(fraction);
+    }
+    Some((Cow::Owned(number), first_separator.is_some()))
+}
+
+/// Units recognized as the last word of a column name (`weight_kg`, `SBP (mmHg)`)
+const NAME_UNITS: &[(&str, &str)] = &[
+    ("mg", "mg"),
+    ("kg", "kg"),
+    ("mcg", "mcg"),
+    ("ug", "mcg"),
+    ("ng", "ng"),
+    ("lb", "lb"),
+    ("lbs", "lb"),
+    ("ml", "mL"),
+    ("dl", "dL"),
+    ("cm", "cm"),
+    ("mm", "mm"),
+    ("mmhg", "mmHg"),
+    ("bpm", "bpm"),
+    ("kcal", "kcal"),
+    ("mmol", "mmol"),
+    ("iu", "IU"),
+    ("mgdl", "mg/dL"),
+    ("mmoll", "mmol/L"),
+    ("kgm2", "kg/m2"),
+    ("pct", "%"),
+    ("percent", "%"),
+    ("yrs", "years"),
+    ("years", "years"),
+    ("months", "months"),
+    ("wks", "weeks"),
+    ("weeks", "weeks"),
+    ("days", "days"),
+    ("hrs", "hours"),
+    ("hours", "hours"),
+    ("mins", "minutes"),
+    ("minutes", "minutes"),
+    ("secs", "seconds"),
+    ("seconds", "seconds"),
+    ("degc", "°C"),
+];
+
+/// Units written as two words (`glucose_mg_dl`, `egfr_ml_min`)
+const NAME_UNIT_PAIRS: &[(&str, &str, &str)] = &[
+    ("mg", "dl", "mg/dL"),
+    ("g", "dl", "g/dL"),
+    ("g", "l", "g/L"),
+    ("mmol", "l", "mmol/L"),
+    ("umol", "l", "umol/L"),
+    ("u", "l", "U/L"),
+    ("kg", "m2", "kg/m2"),
+    ("ml", "min", "mL/min"),
+];
+
+/// Measurement unit named at the end of a column name, if any
+pub fn unit_hint(name: &str) -> Option<&'static str> {
+    if name.contains('%') {
+        return Some("%");
     }
-    trimmed.parse::<f64>().is_ok()
+    let words: Vec<String> = name
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_ascii_lowercase())
+        .filter(|w| w != "per")
+        .collect();
+    let last = words.last()?;
+    if let [.., first, second] = words.as_slice() {
+        let pair = NAME_UNIT_PAIRS.iter().find(|(a, b, _)| a == first && b == second);
+        if let Some((_, _, unit)) = pair {
+            return Some(unit);
+        }
+    }
+    // A name that is nothing but a unit ("days") says little about what was measured
+    if words.len() < 2 {
+        return None;
+    }
+    NAME_UNITS.iter().find(|(word, _)| word == last).map(|(_, unit)| *unit)
 }
 
 /// Check if a value is a date
 pub fn is_date(value: &str) -> bool {
+    parse_date(value).is_some()
+}
+
+/// Parse a value in one of the recognized date formats
+pub fn parse_date(value: &str) -> Option<NaiveDate> {
     let trimmed = value.trim();
     if trimmed.is_empty() {
-        return false;
+        return None;
     }
 
-    for (pattern, format) in DATE_PATTERNS.iter() {
+    DATE_PATTERNS.iter().find_map(|(pattern, format)| {
         if pattern.is_match(trimmed) {
-            if NaiveDate::parse_from_str(trimmed, format).is_ok() {
-                return true;
-            }
+            NaiveDate::parse_from_str(trimmed, format).ok()
+        } else {
+            None
         }
-    }
-    false
+    })
+}
+
+/// Parse a value in one of the recognized datetime formats
+pub fn parse_datetime(value: &str) -> Option<NaiveDateTime> {
+    let trimmed = value.trim();
+    // Patterns accept a space or 'T' between date and time; formats use 'T'
+    let normalized = trimmed.replacen(' ', "T", 1);
+
+    DATETIME_PATTERNS.iter().find_map(|(pattern, format)| {
+        if pattern.is_match(trimmed) {
+            NaiveDateTime::parse_from_str(&normalized, format).ok()
+        } else {
+            None
+        }
+    })
 }
 
 /// Check if a value is a datetime
@@ -311,15 +738,31 @@ pub fn is_datetime(value: &str) -> bool {
     false
 }
 
-/// Parse a numeric value
+/// Parse a numeric value, read with a decimal period unless only a decimal comma
+/// makes it a number
 pub fn parse_numeric(value: &str) -> Option<f64> {
-    value.trim().parse::<f64>().ok()
+    parse_number_as(value, DecimalSeparator::Period)
+        .or_else(|| parse_number_as(value, DecimalSeparator::Comma))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_date_and_datetime() {
+        assert_eq!(parse_date("2024-01-15"), NaiveDate::from_ymd_opt(2024, 1, 15));
+        assert_eq!(parse_date("1/15/2024"), NaiveDate::from_ymd_opt(2024, 1, 15));
+        assert_eq!(parse_date("2024-02-30"), None);
+        assert_eq!(
+            parse_datetime("2024-01-15 10:30:00").map(|d| d.date()),
+            NaiveDate::from_ymd_opt(2024, 1, 15)
+        );
+        assert!(parse_datetime("2024-01-15T10:30:00.250").is_some());
+        assert!(parse_datetime("2024-01-15T10:30:00Z").is_some());
+        assert_eq!(parse_datetime("2024-01-15T25:30:00"), None);
+    }
+
     #[test]
     fn test_is_missing() {
         assert!(is_missing(""));
@@ -331,6 +774,34 @@ mod tests {
         assert!(is_missing("#N/A"));
         assert!(!is_missing("0"));
         assert!(!is_missing("test"));
+        assert!(is_missing(".a"));
+        assert!(is_missing(" .z"));
+        assert!(!is_missing(".5"));
+        assert!(!is_missing(".ab"));
+    }
+
+    #[test]
+    fn test_missing_tokens() {
+        let custom = vec!["-99".to_string(), " unk ".to_string()];
+        let extending = MissingTokens::extending(&custom);
+        assert!(extending.is_missing("NA"));
+        assert!(extending.is_missing(" -99"));
+        assert!(extending.is_missing("UNK"));
+        assert!(!extending.is_missing("-9"));
+
+        let replacing = MissingTokens::replacing(&custom);
+        assert!(!replacing.is_missing("NA"));
+        assert!(replacing.is_missing("-99"));
+        assert!(replacing.is_missing(""));
+        assert!(replacing.is_missing(".b"));
+
+        let mut inferencer =
+            TypeInferencer::with_decimal_separator(None).with_missing_tokens(extending);
+        for value in ["12", "-99", "UNK", "40"] {
+            inferencer.observe(value);
+        }
+        inferencer.finalize_initial_inference();
+        assert_eq!(inferencer.inferred_type(), DType::Integer);
     }
 
     #[test]
@@ -369,6 +840,94 @@ mod tests {
         assert!(!is_numeric(""));
     }
 
+    #[test]
+    fn test_locale_numbers() {
+        assert_eq!(parse_numeric("1.234,56"), Some(1234.56));
+        assert_eq!(parse_numeric("1,234.56"), Some(1234.56));
+        assert_eq!(parse_numeric("-2,75"), Some(-2.75));
+        assert_eq!(parse_numeric("1 234 567"), Some(1234567.0));
+        assert_eq!(parse_numeric("1\u{a0}234,5"), Some(1234.5));
+        assert_eq!(parse_numeric("12345,6"), Some(12345.6));
+        // A lone comma before three digits groups thousands
+        assert_eq!(parse_numeric("1,234"), Some(1234.0));
+        assert!(is_integer_as("1.234.567", DecimalSeparator::Comma));
+        assert!(!is_integer_as("1,5", DecimalSeparator::Comma));
+        for value in ["1,2,3", "12,34,567", "1.234,567.8", "1-234", ",5", "1,", "555 1234"] {
+            assert_eq!(parse_numeric(value), None, "{}", value);
+        }
+    }
+
+    #[test]
+    fn test_format_hint() {
+        let hint = |values: &[&str]| {
+            let mut inferencer = TypeInferencer::new();
+            for value in values {
+                inferencer.observe(value);
+            }
+            inferencer.finalize_initial_inference();
+            (inferencer.inferred_type(), inferencer.format_hint())
+        };
+        assert_eq!(hint(&["3,14", "2,5", "10"]), (DType::Numeric, Some("decimal_comma")));
+        assert_eq!(
+            hint(&["1,234", "987", "12,000"]),
+            (DType::Integer, Some("thousands_separator"))
+        );
+        assert_eq!(hint(&["45%", "12.5%", "NA"]), (DType::String, Some("percent")));
+        assert_eq!(hint(&["45%", "n/k"]), (DType::String, None));
+        assert_eq!(hint(&["3.14", "2"]), (DType::Numeric, None));
+    }
+
+    #[test]
+    fn test_decimal_separator_detection() {
+        let infer = |decimal: Option<DecimalSeparator>, values: &[&str]| {
+            let mut inferencer = TypeInferencer::with_decimal_separator(decimal);
+            for value in values {
+                inferencer.observe(value);
+            }
+            inferencer.finalize_initial_inference();
+            inferencer
+        };
+
+        let comma = infer(None, &["1,5", "2,25", "1.234,5", "7"]);
+        assert_eq!(comma.inferred_type(), DType::Numeric);
+        assert_eq!(comma.numeric_locale(), Some(DecimalSeparator::Comma));
+        assert_eq!(comma.parse_number("1.234"), Some(1234.0));
+        assert_eq!(comma.parse_number("3.5"), None);
+
+        // "1,234" reads either way, so the one unambiguous value decides
+        let mixed = infer(None, &["1,234", "3,5"]);
+        assert_eq!(mixed.decimal_separator(), DecimalSeparator::Comma);
+        assert_eq!(mixed.parse_number("1,234"), Some(1.234));
+
+        let ambiguous = infer(None, &["1,234", "2,500"]);
+        assert_eq!(ambiguous.inferred_type(), DType::Integer);
+        assert_eq!(ambiguous.numeric_locale(), Some(DecimalSeparator::Period));
+
+        let forced = infer(Some(DecimalSeparator::Comma), &["1.234", "2.500"]);
+        assert_eq!(forced.inferred_type(), DType::Integer);
+        assert_eq!(forced.format_hint(), Some("thousands_separator"));
+
+        // Once the samples settle the separator, later values are read with it
+        let mut settled = infer(None, &["3.25", "1.5"]);
+        settled.observe("2,5");
+        assert_eq!(settled.inferred_type(), DType::String);
+
+        let plain = infer(None, &["3.25", "10"]);
+        assert_eq!(plain.numeric_locale(), None);
+    }
+
+    #[test]
+    fn test_unit_hint() {
+        assert_eq!(unit_hint("weight_kg"), Some("kg"));
+        assert_eq!(unit_hint("SBP (mmHg)"), Some("mmHg"));
+        assert_eq!(unit_hint("glucose_mg_per_dL"), Some("mg/dL"));
+        assert_eq!(unit_hint("body fat %"), Some("%"));
+        assert_eq!(unit_hint("age_years"), Some("years"));
+        assert_eq!(unit_hint("days"), None);
+        assert_eq!(unit_hint("mg_dose"), None);
+        assert_eq!(unit_hint("patient_id"), None);
+    }
+
     #[test]
     fn test_is_date() {
         assert!(is_date("2024-01-15"));
@@ -430,6 +989,37 @@ mod tests {
         assert_eq!(inf.inferred_type(), DType::Date);
     }
 
+    #[test]
+    fn test_compact_dates() {
+        assert_eq!(compact_date_layout("20240115"), Some("yyyymmdd"));
+        assert_eq!(compact_date_layout("15012024"), Some("ddmmyyyy"));
+        assert_eq!(compact_date_layout("20241315"), None);
+        assert_eq!(compact_date_layout("12345678"), None);
+        assert_eq!(parse_date("20240115"), NaiveDate::from_ymd_opt(2024, 1, 15));
+        assert_eq!(parse_date("15012024"), NaiveDate::from_ymd_opt(2024, 1, 15));
+        assert_eq!(parse_date("20121995"), NaiveDate::from_ymd_opt(1995, 12, 20));
+
+        let mut inf = TypeInferencer::new();
+        for value in ["20240115", "20231231", "19991001"] {
+            inf.observe(value);
+        }
+        inf.finalize_initial_inference();
+        assert_eq!(inf.inferred_type(), DType::Date);
+        assert_eq!(inf.compact_date_layout(), Some("yyyymmdd"));
+        // A number that is not a date makes it an integer column after all
+        inf.observe("99999999");
+        assert_eq!(inf.inferred_type(), DType::Integer);
+        assert_eq!(inf.compact_date_layout(), None);
+
+        // Layouts may not be mixed
+        let mut inf = TypeInferencer::new();
+        for value in ["20240115", "15012024"] {
+            inf.observe(value);
+        }
+        inf.finalize_initial_inference();
+        assert_eq!(inf.inferred_type(), DType::Integer);
+    }
+
     #[test]
     fn test_type_inferencer_upgrade_integer_to_numeric() {
         let mut inf = TypeInferencer::new();
@@ -456,6 +1046,23 @@ mod tests {
         assert_eq!(inf.inferred_type(), DType::String);
     }
 
+    #[test]
+    fn test_type_inferencer_trail() {
+        let mut inf = TypeInferencer::new();
+        for value in ["1", "2", "NA", "3"] {
+            inf.observe(value);
+        }
+        inf.finalize_initial_inference();
+        for value in ["4", "4.5", "5", "abc", "6", "n/k", "7.25"] {
+            inf.observe(value);
+        }
+        assert_eq!(inf.sample_size(), 3);
+        assert_eq!(inf.initial_type(), DType::Integer);
+        assert_eq!(inf.type_changes(), &[DType::Numeric, DType::String]);
+        // 4.5, abc, n/k and 7.25 are not integers
+        assert_eq!(inf.conflicts(), 4);
+    }
+
     #[test]
     fn test_type_inferencer_skips_missing() {
         let mut inf = TypeInferencer::new();
@@ -0,0 +1,40 @@
+//! Privacy-preserving metadata extraction from clinical trial data files.
+//!
+//! The `ert-manifest` binary is built on this library. Other tools that only
+//! need the PHI checks can call [`classify_columns`] and [`classify_value`]
+//! without scanning a file, and services that receive rows over the network
+//! can profile them with a [`ManifestBuilder`].
+
+pub mod assess;
+pub mod audit;
+pub mod builder;
+pub mod classify;
+pub mod clustering;
+pub mod codebook;
+pub mod compat;
+pub mod config;
+pub mod dataset;
+pub mod error;
+pub mod history;
+pub mod inference;
+pub mod linkage;
+pub mod output;
+pub mod paths;
+pub mod privacy;
+pub mod readers;
+pub mod redact;
+pub mod rules;
+pub mod sanitize;
+pub mod schema;
+pub mod scope;
+pub mod stats;
+pub mod synth;
+pub mod tempfiles;
+pub mod types;
+pub mod validation;
+
+pub use builder::ManifestBuilder;
+pub use classify::{classify_columns, classify_value};
+pub use privacy::value_patterns::ValuePatternResult;
+pub use privacy::ColumnNameResult;
+pub use types::{Classification, ManifestSchema, ProcessingOptions};
@@ -0,0 +1,163 @@
+use crate::privacy::safe_count;
+use crate::types::{
+    ColumnFingerprint, ColumnRef, ColumnSchema, JoinKeyCandidate, ManifestSchema,
+    ProcessingOptions,
+};
+
+/// Minimum share of the smaller column's distinct values that must also appear in the other
+pub const MIN_KEY_OVERLAP: f64 = 0.5;
+
+/// At least one side of a join key must be this close to one distinct value per row
+pub const MIN_KEY_UNIQUENESS: f64 = 0.9;
+
+/// Normalize a column header for cross-file matching ("Patient_ID" == "patient id")
+pub fn normalize_key_name(header: &str) -> String {
+    header
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+/// Find column pairs across different files that share a name and most of their values.
+///
+/// Value overlap is estimated from HyperLogLog sketches, so no raw values are
+/// compared or retained. Columns with fewer than k distinct values are ignored.
+pub fn detect_join_keys(
+    files: &[ManifestSchema],
+    options: &ProcessingOptions,
+) -> Vec<JoinKeyCandidate> {
+    let columns: Vec<(usize, &str, &ColumnSchema, &ColumnFingerprint)> = files
+        .iter()
+        .enumerate()
+        .flat_map(|(file_idx, manifest)| {
+            manifest.sheets.iter().flat_map(move |sheet| {
+                sheet.columns.iter().filter_map(move |col| {
+                    col.fingerprint
+                        .as_ref()
+                        .map(|fp| (file_idx, sheet.name.as_str(), col, fp))
+                })
+            })
+        })
+        .collect();
+
+    let mut candidates = Vec::new();
+    for (i, (file_a, sheet_a, col_a, fp_a)) in columns.iter().enumerate() {
+        for (file_b, sheet_b, col_b, fp_b) in &columns[i + 1..] {
+            if file_a == file_b || fp_a.key_name.is_empty() || fp_a.key_name != fp_b.key_name {
+                continue;
+            }
+
+            let distinct_a = fp_a.sketch.estimate();
+            let distinct_b = fp_b.sketch.estimate();
+            let smaller = distinct_a.min(distinct_b);
+            if smaller < options.k_anonymity as f64 {
+                continue;
+            }
+            if !is_key_like(fp_a, distinct_a) && !is_key_like(fp_b, distinct_b) {
+                continue;
+            }
+
+            let overlap = fp_a.sketch.intersection_estimate(&fp_b.sketch);
+            let ratio = (overlap / smaller).min(1.0);
+            if ratio < MIN_KEY_OVERLAP {
+                continue;
+            }
+
+            candidates.push(JoinKeyCandidate {
+                column_name: col_a.name.clone(),
+                left: ColumnRef {
+                    file_name: files[*file_a].file_name.clone(),
+                    sheet: sheet_a.to_string(),
+                    column_index: col_a.index,
+                },
+                right: ColumnRef {
+                    file_name: files[*file_b].file_name.clone(),
+                    sheet: sheet_b.to_string(),
+                    column_index: col_b.index,
+                },
+                estimated_overlap: safe_count(overlap.round() as u64, options.bucket_counts),
+                overlap_ratio: (ratio * 100.0).round() / 100.0,
+            });
+        }
+    }
+    candidates
+}
+
+fn is_key_like(fingerprint: &ColumnFingerprint, distinct: f64) -> bool {
+    fingerprint.value_count > 0
+        && distinct >= MIN_KEY_UNIQUENESS * fingerprint.value_count as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::HyperLogLog;
+    use crate::types::{DType, FileFormat, SafeValue, SheetSchema};
+
+    fn manifest_with_column(file_name: &str, header: &str, values: &[String]) -> ManifestSchema {
+        let mut sketch = HyperLogLog::default();
+        for v in values {
+            sketch.add(v);
+        }
+        let mut col = ColumnSchema::new(
+            SafeValue::ShortString(header.to_string()),
+            0,
+            DType::String,
+        );
+        col.fingerprint = Some(ColumnFingerprint {
+            key_name: normalize_key_name(header),
+            value_count: values.len() as u64,
+            sketch,
+            subject_sketch: None,
+        });
+
+        let mut sheet = SheetSchema::new(file_name.to_string(), 0);
+        sheet.columns.push(col);
+        let mut manifest = ManifestSchema::new(file_name.to_string(), FileFormat::Csv);
+        manifest.sheets.push(sheet);
+        manifest
+    }
+
+    fn ids(range: std::ops::Range<u32>) -> Vec<String> {
+        range.map(|i| format!("P{:05}", i)).collect()
+    }
+
+    #[test]
+    fn test_normalize_key_name() {
+        assert_eq!(normalize_key_name("Patient_ID"), "patientid");
+        assert_eq!(normalize_key_name("patient id"), "patientid");
+    }
+
+    #[test]
+    fn test_detect_shared_key() {
+        let demographics = manifest_with_column("demographics.csv", "study_id", &ids(0..500));
+        // Labs repeat each subject several times
+        let mut lab_ids = ids(0..400);
+        lab_ids.extend(ids(0..400));
+        let labs = manifest_with_column("labs.csv", "Study ID", &lab_ids);
+
+        let keys = detect_join_keys(&[demographics, labs], &ProcessingOptions::default());
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].left.file_name, "demographics.csv");
+        assert_eq!(keys[0].right.file_name, "labs.csv");
+        assert!(keys[0].overlap_ratio > 0.9);
+    }
+
+    #[test]
+    fn test_disjoint_values_not_a_key() {
+        let a = manifest_with_column("a.csv", "study_id", &ids(0..500));
+        let b = manifest_with_column("b.csv", "study_id", &ids(1000..1500));
+
+        assert!(detect_join_keys(&[a, b], &ProcessingOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn test_low_cardinality_not_a_key() {
+        let values: Vec<String> = ["M", "F"].iter().cycle().take(100).map(|s| s.to_string()).collect();
+        let a = manifest_with_column("a.csv", "sex", &values);
+        let b = manifest_with_column("b.csv", "sex", &values);
+
+        assert!(detect_join_keys(&[a, b], &ProcessingOptions::default()).is_empty());
+    }
+}
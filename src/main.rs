@@ -1,75 +1,778 @@
 mod cli;
-mod error;
-mod inference;
-mod output;
-mod privacy;
-mod readers;
-mod schema;
-mod stats;
-mod types;
+#[cfg(feature = "gui")]
+mod gui;
+
+use ert_manifest::{
+    assess, audit, codebook, config, dataset, error, history, output, paths, privacy, readers,
+    redact, rules, schema, scope, synth, tempfiles, types, validation,
+};
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use clap::Parser;
 use cli::{Cli, Commands};
 use error::Error;
 use types::Result;
 
+/// Set by --quiet; stdout only ever carries the requested output
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Progress/warning message on stderr, unless --quiet
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if !QUIET.load(Ordering::Relaxed) {
+            eprintln!($($arg)*);
+        }
+    };
+}
+
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    QUIET.store(cli.quiet, Ordering::Relaxed);
+    if let Some(ref dir) = cli.temp_dir {
+        tempfiles::set_temp_dir(dir.clone())?;
+    }
+    let history_db = cli
+        .history_db
+        .clone()
+        .unwrap_or_else(history::default_db_path);
 
     match cli.command {
-        Some(Commands::Scan {
-            input,
-            out,
-            k,
-            bucket_counts,
-            exact_counts,
-            exact_median,
-            hash_file,
-            relaxed,
-        }) => {
-            let options = types::ProcessingOptions {
+        Some(Commands::Scan(args)) => {
+            let cli::ScanArgs {
+                input,
+                input_format,
+                layout,
+                out,
+                format,
+                template,
+                codebook,
+                scope,
+                validation_rules,
+                sidekick_out,
+                recode_format,
+                no_sidekick,
+                out_dir,
+                encoding,
+                header_row,
+                header_rows,
+                no_header,
+                audit_log,
+                force,
+                versioned,
+                fail_on,
+                k,
+                deep_scan,
+                content_aware,
+                cdisc,
+                examples,
+                quasi_identifiers,
+                sensitive_columns,
+                l_diversity,
+                skip_hidden_sheets,
+                scan_comments,
+                sheets,
+                exclude_sheets,
+                columns,
+                exclude_columns,
+                no_values_for,
+                no_values_columns,
+                max_unique_values,
+                sample_rows,
+                sample_fraction,
+                subject_column,
+                redact_names,
+                locales,
+                date_precision,
+                decimal_separator,
+                missing_tokens,
+                bucket_counts,
+                exact_counts,
+                exact_median,
+                hash_file,
+                relaxed,
+            } = *args;
+            let loaded = load_config(cli.config.as_deref())?;
+            let file_config = loaded.as_ref().map(|c| c.file.clone()).unwrap_or_default();
+            let flags = config::OptionOverrides {
                 k_anonymity: k,
                 bucket_counts,
-                exact_counts: exact_counts && relaxed,
-                exact_median: exact_median && relaxed,
+                exact_counts,
+                exact_median,
                 hash_file,
                 relaxed,
+                encoding,
+                header: match (header_row, no_header) {
+                    (Some(row), _) => Some(types::HeaderMode::Row(row)),
+                    (None, true) => Some(types::HeaderMode::Absent),
+                    (None, false) => None,
+                },
+                header_rows,
+                date_precision,
+                decimal_separator,
+                missing_tokens: (!missing_tokens.is_empty()).then_some(missing_tokens),
+                deep_scan,
+                content_aware,
+                cdisc,
+                examples,
+                redact_names,
+                skip_hidden_sheets,
+                scan_comments,
+                max_unique_values,
+                sample_rows,
+                sample_fraction,
+                subject_column,
+            };
+            let mut options = flags.or(file_config.options).resolve();
+            if let Some(ref c) = loaded {
+                options.completeness_rules = c.completeness_rules()?;
+                options.code_lists = c.code_lists()?;
+                options.missing_token_rules = c.missing_token_rules();
+            }
+            options.sheets = sheets;
+            options.exclude_sheets = exclude_sheets;
+            options.columns = columns;
+            options.exclude_columns = exclude_columns;
+            if options.sample_fraction.is_some_and(|p| !(p > 0.0 && p <= 1.0)) {
+                return Err(Error::InvalidInput(
+                    "--sample-fraction must be above 0 and at most 1".to_string(),
+                ));
+            }
+            if options.sample_rows == Some(0) {
+                return Err(Error::InvalidInput(
+                    "--sample-rows must be at least 1".to_string(),
+                ));
+            }
+            if options.max_unique_values > types::MAX_UNIQUE_VALUES {
+                return Err(Error::InvalidInput(format!(
+                    "--max-unique-values can only lower the limit of {}",
+                    types::MAX_UNIQUE_VALUES
+                )));
+            }
+            options.no_values_for = no_values_for;
+            options.no_values_columns = no_values_columns;
+            if let Some(ref c) = loaded {
+                options.no_values_columns.extend(c.never_transfer_columns());
+            }
+            apply_quasi_identifiers(&mut options, &quasi_identifiers);
+            apply_sensitive_columns(&mut options, sensitive_columns, l_diversity);
+            apply_locales(&mut options, &locales);
+            if let Some(ref path) = layout {
+                options.fixed_width_layout =
+                    Some(readers::fixed_width::FixedWidthLayout::load(path)?);
+            }
+            if let Some(ref path) = validation_rules {
+                options.validation_rules = validation::load_rules(path)?;
+            }
+            // A template or format given on the command line beats either from the config file
+            let template = match (template, format) {
+                (Some(template), _) => Some(template),
+                (None, Some(_)) => None,
+                (None, None) if file_config.format.is_none() => {
+                    loaded.as_ref().and_then(|c| c.template_path())
+                }
+                (None, None) => None,
+            };
+            let template = template
+                .map(|t| output::template::ReportTemplate::load(&t))
+                .transpose()?;
+            let format = format.or(file_config.format).unwrap_or_default();
+            let codebook = codebook
+                .map(|c| codebook::Codebook::load(&c))
+                .transpose()?;
+            let scope = scope.map(|s| scope::Scope::load(&s)).transpose()?;
+            let extension = match template {
+                Some(ref t) => t.extension().to_string(),
+                None => format.extension().to_string(),
             };
+            let scan_config = loaded.as_ref().map(|c| c.echo(format));
 
-            let extraction_result = schema::extract_schema(&input, options)?;
+            // `-` reads stdin; generated file names then start with "stdin"
+            let stdin = input.as_os_str() == "-";
+            let name_base = if stdin {
+                PathBuf::from(schema::STDIN_NAME)
+            } else {
+                input.clone()
+            };
+
+            if let Some(ref dir) = out_dir {
+                std::fs::create_dir_all(paths::for_fs_access(dir))?;
+            }
+            // A relative --out is placed inside --out-dir
+            let out = out.map(|o| match out_dir {
+                Some(ref dir) if o.is_relative() => dir.join(o),
+                _ => o,
+            });
+            let out = if versioned {
+                let base = out.unwrap_or_else(|| {
+                    default_manifest_path(&name_base, &extension, out_dir.as_deref())
+                });
+                let timestamp = chrono::Local::now().format("%Y-%m-%dT%H-%M").to_string();
+                Some(paths::versioned_path(&base, &timestamp))
+            } else {
+                out
+            };
+            if let Some(ref out_path) = out {
+                if !force && paths::for_fs_access(out_path).exists() {
+                    return Err(Error::OutputExists(paths::display_path(out_path)));
+                }
+            }
+
+            let mut audit: Box<dyn audit::AuditSink> = match audit_log {
+                Some(ref path) => {
+                    Box::new(audit::JsonlAuditSink::open(&paths::for_fs_access(path))?)
+                }
+                None => Box::new(audit::NullAuditSink),
+            };
+
+            if !stdin && input.is_dir() {
+                if input_format.is_some() || layout.is_some() {
+                    return Err(Error::InvalidInput(
+                        "--input-format and --layout apply to single files and stdin".to_string(),
+                    ));
+                }
+                if codebook.is_some() || scope.is_some() {
+                    return Err(Error::InvalidInput(
+                        "--codebook and --scope apply to single-file scans".to_string(),
+                    ));
+                }
+                if sidekick_out.is_some() {
+                    return Err(Error::InvalidInput(
+                        "--sidekick-out applies to single-file scans; use --out-dir for directories"
+                            .to_string(),
+                    ));
+                }
+                if format != output::OutputFormat::Json || template.is_some() {
+                    return Err(Error::InvalidInput(
+                        "Directory scans only support --format json".to_string(),
+                    ));
+                }
+                let ignore = match loaded {
+                    Some(ref c) => c.ignore_patterns()?,
+                    None => Vec::new(),
+                };
+                let inputs = schema::list_data_files(&input, &ignore)?;
+                // dataset.json goes beside the manifest, or in --out-dir
+                let dataset = match (&out, &out_dir) {
+                    (Some(out), _) => Some(
+                        out.parent()
+                            .unwrap_or_else(|| Path::new(""))
+                            .join(dataset::DATASET_FILE_NAME),
+                    ),
+                    (None, Some(dir)) => Some(dir.join(dataset::DATASET_FILE_NAME)),
+                    (None, None) => None,
+                };
+                if let Some(ref path) = dataset {
+                    if !force && paths::for_fs_access(path).exists() {
+                        return Err(Error::OutputExists(paths::display_path(path)));
+                    }
+                }
+                let outputs = DirectoryOutputs {
+                    name: paths::file_name_lossy(&input),
+                    manifest: out,
+                    dataset,
+                };
+                let sidekicks = SidekickSettings {
+                    format: (!no_sidekick).then_some(recode_format),
+                    out_dir: out_dir.as_deref(),
+                };
+                let manifest = scan_directory(
+                    &inputs,
+                    outputs,
+                    options,
+                    loaded.as_ref(),
+                    &sidekicks,
+                    &history_db,
+                    audit.as_mut(),
+                )?;
+                exit_on_failed_checks(&manifest.files, fail_on);
+                return Ok(());
+            }
+
+            let input_format = match layout {
+                Some(_) => Some(types::FileFormat::FixedWidth),
+                None => input_format.map(types::FileFormat::from),
+            };
+            let mut extraction_result = if stdin {
+                let format = input_format.unwrap_or(types::FileFormat::Csv);
+                schema::extract_stdin(format, options, audit.as_mut())?
+            } else {
+                schema::extract_schema_as(&input, input_format, options, audit.as_mut())?
+            };
+            audit.flush()?;
+            extraction_result.manifest.config = scan_config;
+            if let Some(ref c) = loaded {
+                c.annotate(&mut extraction_result.manifest);
+            }
+            if let Some(ref codebook) = codebook {
+                let findings = codebook.check(&extraction_result.manifest);
+                status!(
+                    "Codebook: {} unexpected, {} missing, {} type mismatches",
+                    findings.unexpected_columns.len(),
+                    findings.missing_columns.len(),
+                    findings.dtype_mismatches.len()
+                );
+                extraction_result.manifest.codebook_findings = Some(findings);
+            }
+            if let Some(ref scope) = scope {
+                let findings = scope.check(&extraction_result.manifest);
+                for line in scope::format_findings(&findings) {
+                    status!("{}", line);
+                }
+                extraction_result.manifest.scope_findings = Some(findings);
+            }
+            for line in validation::format_results(&extraction_result.manifest.validation) {
+                status!("{}", line);
+            }
+
+            if let Err(e) = history::record_scan(&history_db, &extraction_result.manifest, "cli") {
+                status!("Warning: failed to record scan history: {}", e);
+            }
 
             // Write sidekick recode file if any recoding was done
-            if let Some(ref sidekick_content) = extraction_result.recode_sidekick {
-                let sidekick_path = input.with_extension("recode.txt");
-                std::fs::write(&sidekick_path, sidekick_content)?;
-                eprintln!("Recode mapping written to: {}", sidekick_path.display());
+            if let Some(ref recodings) = extraction_result.recodings {
+                let sidekicks = SidekickSettings {
+                    format: (!no_sidekick).then_some(recode_format),
+                    out_dir: out_dir.as_deref(),
+                };
+                sidekicks.write(&name_base, sidekick_out.as_deref(), recodings)?;
             }
 
-            if let Some(out_path) = out {
-                output::write_json_file(&extraction_result.manifest, &out_path)?;
-                eprintln!("Manifest written to: {}", out_path.display());
+            let manifest = &extraction_result.manifest;
+            match (out, template) {
+                (Some(out_path), Some(template)) => {
+                    output::write_atomic(
+                        &paths::for_fs_access(&out_path),
+                        template.render(manifest)?.as_bytes(),
+                    )?;
+                    status!("Report written to: {}", paths::display_path(&out_path));
+                }
+                (Some(out_path), None) => {
+                    output::write_file(manifest, format, &paths::for_fs_access(&out_path))?;
+                    status!("Manifest written to: {}", paths::display_path(&out_path));
+                }
+                (None, Some(template)) => println!("{}", template.render(manifest)?),
+                (None, None) => output::write_stdout(manifest, format)?,
+            }
+            exit_on_failed_checks(std::slice::from_ref(manifest), fail_on);
+        }
+        Some(Commands::Assess {
+            input,
+            k,
+            locales,
+            quasi_identifiers,
+            sensitive_columns,
+            l_diversity,
+            json,
+        }) => {
+            let loaded = load_config(cli.config.as_deref())?;
+            let file_config = loaded.map(|c| c.file.options).unwrap_or_default();
+            let flags = config::OptionOverrides {
+                k_anonymity: k,
+                ..Default::default()
+            };
+            let mut options = flags.or(file_config).resolve();
+            apply_locales(&mut options, &locales);
+            options.suggest_quasi_identifiers = true;
+            apply_quasi_identifiers(&mut options, &quasi_identifiers);
+            apply_sensitive_columns(&mut options, sensitive_columns, l_diversity);
+            let result = schema::extract_schema(&input, options, &mut audit::NullAuditSink)?;
+            let assessment = assess::assess(&result.manifest);
+            if let Err(e) = history::record_scan(&history_db, &result.manifest, "cli") {
+                status!("Warning: failed to record scan history: {}", e);
+            }
+            if json {
+                println!("{}", serde_json::to_string_pretty(&assessment)?);
             } else {
-                output::write_json_stdout(&extraction_result.manifest)?;
+                println!("{}", assess::format_assessment(&assessment));
             }
         }
-        Some(Commands::Gui) | None => {
-            #[cfg(not(target_arch = "wasm32"))]
-            {
-                run_gui()?;
+        Some(Commands::Synth {
+            template,
+            out,
+            sheet,
+            rows,
+            seed,
+            correlations,
+            force,
+        }) => {
+            let manifest = synth::load_template(&template)?;
+            let options = synth::SynthOptions {
+                sheet,
+                rows,
+                seed,
+                correlations,
+            };
+            match out {
+                Some(out_path) => {
+                    if !force && paths::for_fs_access(&out_path).exists() {
+                        return Err(Error::OutputExists(paths::display_path(&out_path)));
+                    }
+                    output::write_atomic_with(&paths::for_fs_access(&out_path), |writer| {
+                        synth::generate(&manifest, &options, writer)
+                    })?;
+                    status!("Synthetic data written to: {}", paths::display_path(&out_path));
+                }
+                None => synth::generate(&manifest, &options, &mut std::io::stdout().lock())?,
             }
-            #[cfg(target_arch = "wasm32")]
-            {
-                eprintln!("GUI not supported on this platform");
+        }
+        Some(Commands::Redact { input, out, force }) => {
+            let redacted = redact::redact_file(&input)?;
+            match out {
+                Some(out_path) => {
+                    if !force && paths::for_fs_access(&out_path).exists() {
+                        return Err(Error::OutputExists(paths::display_path(&out_path)));
+                    }
+                    output::write_atomic(&paths::for_fs_access(&out_path), redacted.as_bytes())?;
+                    status!("Redacted manifest written to: {}", paths::display_path(&out_path));
+                }
+                None => println!("{}", redacted),
+            }
+        }
+        Some(Commands::History { file, limit, json }) => {
+            let db = history::HistoryDb::open(&history_db)?;
+            let records = db.query(file.as_deref(), limit)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&records)?);
+            } else {
+                println!("{}", history::format_table(&records));
             }
         }
+        Some(Commands::Rules { command }) => {
+            let dir = rules::default_rules_dir();
+            match command {
+                cli::RulesCommand::Update {
+                    from,
+                    signature,
+                    force,
+                } => {
+                    let trusted_keys = config::load(cli.config.as_deref())?
+                        .map(|c| c.file.rules.trusted_keys)
+                        .unwrap_or_default();
+                    let (pack, outcome) =
+                        rules::update(&from, signature.as_deref(), &trusted_keys, &dir, force)?;
+                    match outcome {
+                        rules::UpdateOutcome::Installed { previous: None } => {
+                            status!("Installed rule pack {} {}", pack.name, pack.version)
+                        }
+                        rules::UpdateOutcome::Installed {
+                            previous: Some(previous),
+                        } => status!(
+                            "Updated rule pack {} from {} to {}",
+                            pack.name,
+                            previous,
+                            pack.version
+                        ),
+                        rules::UpdateOutcome::Unchanged => status!(
+                            "Rule pack {} {} is already installed",
+                            pack.name,
+                            pack.version
+                        ),
+                    }
+                }
+                cli::RulesCommand::List => {
+                    println!("{}", rules::format_table(&rules::installed(&dir)?))
+                }
+            }
+        }
+        Some(Commands::Gui) | None => launch_gui(cli.config.as_deref(), history_db)?,
     }
 
     Ok(())
 }
 
-#[cfg(not(target_arch = "wasm32"))]
-fn run_gui() -> Result<()> {
-    use crate::cli::GuiApp;
+/// Turn on the locale packs chosen with `--locales`
+fn apply_locales(options: &mut types::ProcessingOptions, locales: &[cli::LocaleChoice]) {
+    for locale in locales {
+        match locale {
+            cli::LocaleChoice::Auto => options.auto_locales = true,
+            cli::LocaleChoice::Es => options.locales.push(types::Language::Es),
+            cli::LocaleChoice::De => options.locales.push(types::Language::De),
+            cli::LocaleChoice::En => options.locales.push(types::Language::En),
+            cli::LocaleChoice::Pt => options.locales.push(types::Language::Pt),
+        }
+    }
+    options.locales.dedup();
+}
+
+/// Add the quasi-identifier combinations given with `--quasi-identifiers`
+fn apply_quasi_identifiers(options: &mut types::ProcessingOptions, specs: &[String]) {
+    for spec in specs {
+        if spec.trim().eq_ignore_ascii_case("auto") {
+            options.suggest_quasi_identifiers = true;
+            continue;
+        }
+        let columns: Vec<String> = spec
+            .split(',')
+            .map(str::trim)
+            .filter(|c| !c.is_empty())
+            .map(str::to_string)
+            .collect();
+        if !columns.is_empty() {
+            options.quasi_identifier_sets.push(columns);
+        }
+    }
+}
+
+/// Set the sensitive columns given with `--sensitive-columns`, and `--l-diversity`
+fn apply_sensitive_columns(
+    options: &mut types::ProcessingOptions,
+    columns: Vec<String>,
+    l_diversity: Option<u64>,
+) {
+    options.sensitive_columns = columns
+        .into_iter()
+        .map(|c| c.trim().to_string())
+        .filter(|c| !c.is_empty())
+        .collect();
+    if let Some(l) = l_diversity {
+        options.l_diversity = l;
+    }
+}
+
+/// Load the config file, set its temp directory and install its custom value
+/// detectors and plugins, then the detectors of the installed rule packs
+fn load_config(explicit: Option<&Path>) -> Result<Option<config::LoadedConfig>> {
+    let loaded = config::load(explicit)?;
+    let mut detectors = Vec::new();
+    if let Some(ref c) = loaded {
+        if let Some(dir) = c.temp_dir() {
+            // Ignored if --temp-dir was given, which was set first
+            tempfiles::set_temp_dir(dir)?;
+        }
+        detectors = c.custom_detectors()?;
+        privacy::register_rules(c.plugin_rules()?);
+    }
+    let packs = rules::installed(&rules::default_rules_dir())?;
+    for pack in &packs {
+        detectors.extend(pack.custom_detectors()?);
+    }
+    privacy::register_detectors(detectors);
+    rules::register_active(packs.iter().map(rules::RulePack::version).collect());
+    Ok(loaded)
+}
+
+/// Where a directory scan's results go
+struct DirectoryOutputs {
+    /// Name of the scanned directory
+    name: String,
+    /// Manifest path; stdout if not given
+    manifest: Option<PathBuf>,
+    /// Dataset descriptor path; not written if not given
+    dataset: Option<PathBuf>,
+}
+
+/// Scan every supported file in a directory into one multi-file manifest, and
+/// describe them as a dataset. Returns the manifest for the checks on its files.
+fn scan_directory(
+    inputs: &[PathBuf],
+    outputs: DirectoryOutputs,
+    options: types::ProcessingOptions,
+    loaded: Option<&config::LoadedConfig>,
+    sidekicks: &SidekickSettings,
+    history_db: &Path,
+    audit: &mut dyn audit::AuditSink,
+) -> Result<types::MultiFileManifest> {
+    let mut result = schema::extract_multi_schema(inputs, options.clone(), audit)?;
+    audit.flush()?;
+    for manifest in &mut result.manifest.files {
+        if let Some(c) = loaded {
+            manifest.config = Some(c.echo(output::OutputFormat::Json));
+            c.annotate(manifest);
+        }
+    }
+
+    for manifest in &result.manifest.files {
+        if let Err(e) = history::record_scan(history_db, manifest, "cli") {
+            status!("Warning: failed to record scan history: {}", e);
+        }
+    }
+
+    for (input, recodings) in &result.recodings {
+        sidekicks.write(input, None, recodings)?;
+    }
+
+    if let Some(out_path) = outputs.manifest {
+        output::write_json_file(&result.manifest, &paths::for_fs_access(&out_path))?;
+        status!("Manifest written to: {}", paths::display_path(&out_path));
+    } else {
+        println!("{}", output::to_json_string(&result.manifest)?);
+    }
+    let descriptor = dataset::describe_dataset(&outputs.name, &result.manifest, &options);
+    if let Some(ref subjects) = descriptor.subjects {
+        status!(
+            "Estimated subjects: {} (by {} across {} files)",
+            subjects.estimated_subjects,
+            subjects.key,
+            subjects.files
+        );
+    }
+    match outputs.dataset {
+        Some(path) => {
+            output::write_json_file(&descriptor, &paths::for_fs_access(&path))?;
+            status!("Dataset descriptor written to: {}", paths::display_path(&path));
+        }
+        None => status!("Dataset descriptor not written: give --out or --out-dir to write it"),
+    }
+    Ok(result.manifest)
+}
+
+/// Exit code when a completeness check fails (1 is an error, 2 a usage error)
+const EXIT_CHECKS_FAILED: i32 = 3;
+
+/// Exit code when `--fail-on` finds a column of the given class or above
+const EXIT_RISK_FOUND: i32 = 4;
+
+/// Print a manifest's completeness failures; returns whether there were any.
+/// Printed even with --quiet, like errors.
+fn report_completeness_failures(manifest: &types::ManifestSchema) -> bool {
+    for failure in &manifest.completeness_failures {
+        match (&failure.sheet, failure.column_index, failure.complete_percent) {
+            (Some(sheet), Some(index), Some(percent)) => eprintln!(
+                "Completeness check failed: {}, sheet '{}', column {} ({}): {}% complete, {}% required",
+                manifest.file_name,
+                sheet,
+                index + 1,
+                failure.column,
+                percent,
+                failure.required_percent
+            ),
+            _ => eprintln!(
+                "Completeness check failed: {}: column '{}' not found ({}% required)",
+                manifest.file_name, failure.column, failure.required_percent
+            ),
+        }
+    }
+    !manifest.completeness_failures.is_empty()
+}
+
+/// Print the columns that fail `--fail-on`; returns whether there were any.
+/// Printed even with --quiet, like errors.
+fn report_fail_on(manifest: &types::ManifestSchema, fail_on: cli::FailOn) -> bool {
+    let mut failed = false;
+    for sheet in &manifest.sheets {
+        for column in &sheet.columns {
+            if fail_on.fails(&column.classification) {
+                eprintln!(
+                    "Risk found: {}, sheet '{}', column {} ({}): {}",
+                    manifest.file_name,
+                    sheet.name,
+                    column.index + 1,
+                    column.name,
+                    column.classification.as_str()
+                );
+                failed = true;
+            }
+        }
+    }
+    failed
+}
+
+/// Once all output has been written, end the scan with [`EXIT_RISK_FOUND`] if
+/// a column fails `--fail-on`, or [`EXIT_CHECKS_FAILED`] if a completeness
+/// check failed
+fn exit_on_failed_checks(manifests: &[types::ManifestSchema], fail_on: cli::FailOn) {
+    use std::io::Write;
+    let mut incomplete = false;
+    let mut risky = false;
+    for manifest in manifests {
+        incomplete |= report_completeness_failures(manifest);
+        risky |= report_fail_on(manifest, fail_on);
+    }
+    let code = match (risky, incomplete) {
+        (true, _) => EXIT_RISK_FOUND,
+        (false, true) => EXIT_CHECKS_FAILED,
+        (false, false) => return,
+    };
+    let _ = std::io::stdout().flush();
+    std::process::exit(code)
+}
+
+/// `<input stem>.manifest.<extension>` in `out_dir` (or the current directory)
+fn default_manifest_path(input: &Path, extension: &str, out_dir: Option<&Path>) -> PathBuf {
+    let mut name = input.file_stem().unwrap_or_default().to_os_string();
+    name.push(format!(".manifest.{}", extension));
+    out_dir.unwrap_or_else(|| Path::new(".")).join(name)
+}
+
+/// How recode sidekicks are written
+struct SidekickSettings<'a> {
+    /// `None` skips the file and only reports the counts
+    format: Option<privacy::RecodeFormat>,
+    out_dir: Option<&'a Path>,
+}
+
+impl SidekickSettings<'_> {
+    /// Write the sidekick for `input` to `path`, or to the default path for the input
+    fn write(
+        &self,
+        input: &Path,
+        path: Option<&Path>,
+        recodings: &privacy::RecodeRegistry,
+    ) -> Result<()> {
+        let Some(format) = self.format else {
+            let (values, columns) = recodings.counts();
+            status!(
+                "Recode sidekick not written: {} values recoded in {} columns of {}",
+                values,
+                columns,
+                paths::file_name_lossy(input)
+            );
+            return Ok(());
+        };
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => paths::sidekick_path(input, self.out_dir, format.suffix()),
+        };
+        let content = recodings.sidekick_content(format);
+        output::write_atomic(&paths::for_fs_access(&path), content.as_bytes())?;
+        status!("Recode mapping written to: {}", paths::display_path(&path));
+        Ok(())
+    }
+}
+
+/// Start the GUI with defaults from the config file, if any
+#[cfg(feature = "gui")]
+fn launch_gui(config_path: Option<&Path>, history_db: PathBuf) -> Result<()> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let options = match load_config(config_path)? {
+            Some(loaded) => {
+                let completeness_rules = loaded.completeness_rules()?;
+                let code_lists = loaded.code_lists()?;
+                types::ProcessingOptions {
+                    completeness_rules,
+                    code_lists,
+                    missing_token_rules: loaded.missing_token_rules(),
+                    no_values_columns: loaded.never_transfer_columns(),
+                    ..loaded.file.options.resolve()
+                }
+            }
+            None => types::ProcessingOptions::default(),
+        };
+        run_gui(history_db, options)?;
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = (config_path, history_db);
+        eprintln!("GUI not supported on this platform");
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "gui"))]
+fn launch_gui(_config_path: Option<&Path>, _history_db: PathBuf) -> Result<()> {
+    Err(Error::InvalidInput(
+        "this build of ert-manifest has no GUI (rebuild with the `gui` feature); \
+         see `ert-manifest --help` for the command-line interface"
+            .to_string(),
+    ))
+}
+
+#[cfg(all(feature = "gui", not(target_arch = "wasm32")))]
+fn run_gui(history_db: PathBuf, processing_options: types::ProcessingOptions) -> Result<()> {
+    use crate::gui::GuiApp;
 
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
@@ -81,7 +784,9 @@ fn run_gui() -> Result<()> {
     eframe::run_native(
         "ert-manifest",
         options,
-        Box::new(|_cc| Box::new(GuiApp::default())),
+        Box::new(|_cc| {
+            Box::new(GuiApp::with_history_db(history_db).with_options(processing_options))
+        }),
     )
     .map_err(|e| Error::InvalidInput(format!("GUI error: {}", e)))?;
 
@@ -0,0 +1,243 @@
+use std::fmt::Write;
+
+use crate::types::{Classification, ColumnSchema, ManifestSchema, SafeValue, SheetSchema};
+
+const STYLE: &str = "
+body { font-family: -apple-system, 'Segoe UI', Helvetica, Arial, sans-serif; margin: 2em; color: #222; }
+h1 { font-size: 1.5em; }
+h2 { font-size: 1.2em; margin-top: 2em; }
+table { border-collapse: collapse; width: 100%; font-size: 0.9em; }
+th, td { border: 1px solid #ccc; padding: 4px 8px; text-align: left; vertical-align: top; }
+th { background: #f0f0f0; }
+.meta td:first-child { font-weight: bold; width: 12em; }
+.badge { display: inline-block; padding: 1px 6px; border-radius: 4px; font-size: 0.85em; color: #fff; }
+.badge-safe { background: #2e7d32; }
+.badge-warning { background: #f9a825; color: #222; }
+.badge-phi { background: #c62828; }
+.badge-recode { background: #6a1b9a; }
+.badge-high_cardinality { background: #1565c0; }
+.warnings li { color: #8a6d00; }
+.suppressed { color: #888; font-style: italic; }
+";
+
+/// Render the manifest as a self-contained HTML report
+pub fn render_html(manifest: &ManifestSchema) -> String {
+    let mut html = String::new();
+
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    let _ = writeln!(
+        html,
+        "<title>ert-manifest report: {}</title>",
+        escape_html(&manifest.file_name)
+    );
+    let _ = writeln!(html, "<style>{}</style>\n</head>\n<body>", STYLE);
+    let _ = writeln!(
+        html,
+        "<h1>ert-manifest report: {}</h1>",
+        escape_html(&manifest.file_name)
+    );
+
+    // File-level metadata
+    html.push_str("<table class=\"meta\">\n");
+    push_meta_row(&mut html, "Manifest version", &manifest.version);
+    push_meta_row(
+        &mut html,
+        "Format",
+        &format!("{:?}", manifest.format).to_lowercase(),
+    );
+    if let Some(ref hash) = manifest.file_hash {
+        push_meta_row(&mut html, "SHA-256", hash);
+    }
+    push_meta_row(
+        &mut html,
+        "K-anonymity",
+        &manifest.options.k_anonymity.to_string(),
+    );
+    push_meta_row(
+        &mut html,
+        "Counts",
+        if manifest.options.bucket_counts {
+            "bucketed"
+        } else {
+            "exact"
+        },
+    );
+    html.push_str("</table>\n");
+
+    // Global warnings
+    if !manifest.warnings.is_empty() {
+        let _ = writeln!(html, "<h2>Warnings ({})</h2>", manifest.warnings.len());
+        html.push_str("<ul class=\"warnings\">\n");
+        for warning in &manifest.warnings {
+            let _ = writeln!(html, "<li>{}</li>", escape_html(warning));
+        }
+        html.push_str("</ul>\n");
+    }
+
+    for sheet in &manifest.sheets {
+        render_sheet(&mut html, sheet);
+    }
+
+    html.push_str("</body>\n</html>");
+    html
+}
+
+fn push_meta_row(html: &mut String, label: &str, value: &str) {
+    let _ = writeln!(
+        html,
+        "<tr><td>{}</td><td>{}</td></tr>",
+        escape_html(label),
+        escape_html(value)
+    );
+}
+
+fn render_sheet(html: &mut String, sheet: &SheetSchema) {
+    let duplicates = match sheet.duplicate_rows {
+        Some(ref d) => format!(", duplicate rows: {}", safe_value_cell(d)),
+        None => String::new(),
+    };
+    let _ = writeln!(
+        html,
+        "<h2>Sheet: {} <small>(rows: {}{})</small></h2>",
+        escape_html(&sheet.name),
+        safe_value_cell(&sheet.row_count),
+        duplicates
+    );
+
+    if !sheet.warnings.is_empty() {
+        html.push_str("<ul class=\"warnings\">\n");
+        for warning in &sheet.warnings {
+            let _ = writeln!(html, "<li>{}</li>", escape_html(warning));
+        }
+        html.push_str("</ul>\n");
+    }
+
+    html.push_str(
+        "<table>\n<tr><th>#</th><th>Column</th><th>Type</th><th>Classification</th>\
+         <th>Count</th><th>Missing</th><th>Unique</th><th>Min</th><th>Max</th>\
+         <th>Mean</th><th>Median</th><th>Values</th></tr>\n",
+    );
+    for col in &sheet.columns {
+        render_column_row(html, col);
+    }
+    html.push_str("</table>\n");
+}
+
+fn render_column_row(html: &mut String, col: &ColumnSchema) {
+    let stats = col.stats.as_ref();
+    let opt_cell = |v: Option<&SafeValue>| v.map(safe_value_cell).unwrap_or_default();
+    let float_cell = |v: Option<f64>| v.map(|x| format!("{:.2}", x)).unwrap_or_default();
+
+    let values = col
+        .unique_values
+        .as_ref()
+        .map(|vals| {
+            vals.iter()
+                .map(safe_value_cell)
+                .collect::<Vec<_>>()
+                .join(", ")
+        })
+        .unwrap_or_default();
+
+    let _ = writeln!(
+        html,
+        "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td>\
+         <td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+        col.index + 1,
+        safe_value_cell(&col.name),
+        col.dtype.as_str(),
+        classification_badge(&col.classification),
+        opt_cell(stats.and_then(|s| s.count.as_ref())),
+        opt_cell(stats.and_then(|s| s.missing_count.as_ref())),
+        opt_cell(stats.and_then(|s| s.unique_count.as_ref())),
+        opt_cell(stats.and_then(|s| s.min.as_ref().or(s.earliest.as_ref()))),
+        opt_cell(stats.and_then(|s| s.max.as_ref().or(s.latest.as_ref()))),
+        float_cell(stats.and_then(|s| s.mean)),
+        float_cell(stats.and_then(|s| s.median)),
+        values
+    );
+}
+
+/// Render a SafeValue as escaped HTML, greying out suppressed values
+fn safe_value_cell(value: &SafeValue) -> String {
+    match value {
+        SafeValue::Suppressed { reason } => format!(
+            "<span class=\"suppressed\" title=\"{}\">suppressed</span>",
+            escape_html(reason)
+        ),
+        other => escape_html(&other.to_string()),
+    }
+}
+
+fn classification_badge(classification: &Classification) -> String {
+    let label = classification.as_str();
+    format!("<span class=\"badge badge-{}\">{}</span>", label, label)
+}
+
+/// Escape text for safe inclusion in HTML element content and attributes
+pub fn escape_html(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ColumnStats, DType, FileFormat};
+
+    #[test]
+    fn test_escape_html() {
+        assert_eq!(
+            escape_html("<script>alert('x')</script>"),
+            "&lt;script&gt;alert(&#39;x&#39;)&lt;/script&gt;"
+        );
+        assert_eq!(escape_html("a & b"), "a &amp; b");
+    }
+
+    #[test]
+    fn test_render_html_report() {
+        let mut manifest = ManifestSchema::new("trial.csv".to_string(), FileFormat::Csv);
+        manifest.warnings.push("Sheet 'trial.csv', Column 1: check <this>".to_string());
+
+        let mut sheet = SheetSchema::new("trial.csv".to_string(), 0);
+        let mut col = ColumnSchema::new(
+            SafeValue::ShortString("age".to_string()),
+            0,
+            DType::Integer,
+        );
+        col.stats = Some(ColumnStats {
+            mean: Some(42.0),
+            ..ColumnStats::default()
+        });
+        sheet.columns.push(col);
+
+        let mut phi = ColumnSchema::new(
+            SafeValue::Suppressed {
+                reason: "Column name matches PHI pattern".to_string(),
+            },
+            1,
+            DType::String,
+        );
+        phi.classification = Classification::Phi;
+        sheet.columns.push(phi);
+        manifest.sheets.push(sheet);
+
+        let html = render_html(&manifest);
+        assert!(html.contains("<h2>Sheet: trial.csv"));
+        assert!(html.contains("<td>age</td>"));
+        assert!(html.contains("42.00"));
+        assert!(html.contains("badge-phi"));
+        assert!(html.contains("check &lt;this&gt;"));
+        assert!(!html.contains("<this>"));
+    }
+}
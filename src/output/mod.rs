@@ -0,0 +1,169 @@
+pub mod html;
+pub mod table1;
+pub mod tabular;
+pub mod template;
+
+use crate::error::Error;
+use crate::tempfiles::SecureTempFile;
+use crate::types::{ManifestSchema, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Output format for the scan command
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// Machine-readable JSON manifest
+    #[default]
+    Json,
+    /// Self-contained HTML report
+    Html,
+    /// Column inventory, one CSV row per column
+    Csv,
+    /// Column inventory as an Excel workbook (requires --out)
+    Xlsx,
+    /// Clinical "Table 1" summary (Markdown)
+    Table1,
+}
+
+impl OutputFormat {
+    /// File extension for this format (without the dot)
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Json => "json",
+            OutputFormat::Html => "html",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Xlsx => "xlsx",
+            OutputFormat::Table1 => "md",
+        }
+    }
+}
+
+/// Render manifest in the requested format
+pub fn render(manifest: &ManifestSchema, format: OutputFormat) -> Result<String> {
+    match format {
+        OutputFormat::Json => to_json_string(manifest),
+        OutputFormat::Html => Ok(html::render_html(manifest)),
+        OutputFormat::Csv => tabular::render_csv(manifest),
+        OutputFormat::Table1 => Ok(table1::render_table1(manifest)),
+        OutputFormat::Xlsx => Err(Error::InvalidInput(
+            "xlsx output is binary; use --out to write it to a file".to_string(),
+        )),
+    }
+}
+
+/// Write manifest to a file in the requested format
+pub fn write_file(manifest: &ManifestSchema, format: OutputFormat, path: &Path) -> Result<()> {
+    match format {
+        OutputFormat::Json => write_json_file(manifest, path),
+        OutputFormat::Xlsx => tabular::write_xlsx(manifest, path),
+        _ => write_atomic(path, render(manifest, format)?.as_bytes()),
+    }
+}
+
+/// Write manifest to stdout in the requested format
+pub fn write_stdout(manifest: &ManifestSchema, format: OutputFormat) -> Result<()> {
+    let rendered = render(manifest, format)?;
+    let stdout = std::io::stdout();
+    let mut handle = stdout.lock();
+    writeln!(handle, "{}", rendered)?;
+    Ok(())
+}
+
+/// Write manifest to JSON file
+pub fn write_json_file<T: Serialize>(manifest: &T, path: &Path) -> Result<()> {
+    write_atomic_with(path, |writer| Ok(serde_json::to_writer_pretty(writer, manifest)?))
+}
+
+/// Write bytes to `path` atomically (see [`write_atomic_with`])
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    write_atomic_with(path, |writer| Ok(writer.write_all(contents)?))
+}
+
+/// Write to a temp file beside `path`, sync it, then rename it into place.
+///
+/// A crash or full disk mid-write leaves the previous file (or nothing) at
+/// `path`, never a truncated one. On failure the temp file is wiped and removed.
+pub fn write_atomic_with<F>(path: &Path, write: F) -> Result<()>
+where
+    F: FnOnce(&mut dyn Write) -> Result<()>,
+{
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let mut temp = SecureTempFile::new_in(dir)?;
+
+    {
+        let mut writer = BufWriter::new(temp.as_file_mut());
+        write(&mut writer)?;
+        writer.flush()?;
+    }
+    temp.as_file().sync_all()?;
+    temp.persist(path)
+}
+
+/// Write manifest to JSON string
+pub fn to_json_string<T: Serialize>(manifest: &T) -> Result<String> {
+    Ok(serde_json::to_string_pretty(manifest)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{FileFormat, SafeValue, SheetSchema};
+
+    #[test]
+    fn test_json_serialization() {
+        let mut manifest = ManifestSchema::new("test.csv".to_string(), FileFormat::Csv);
+        let mut sheet = SheetSchema::new("Sheet1".to_string(), 0);
+        sheet.row_count = SafeValue::Integer(100);
+        manifest.sheets.push(sheet);
+
+        let json = to_json_string(&manifest).unwrap();
+        assert!(json.contains("\"file_name\": \"test.csv\""));
+        assert!(json.contains("\"format\": \"csv\""));
+    }
+
+    #[test]
+    fn test_render_html_format() {
+        let manifest = ManifestSchema::new("test.csv".to_string(), FileFormat::Csv);
+        let rendered = render(&manifest, OutputFormat::Html).unwrap();
+        assert!(rendered.starts_with("<!DOCTYPE html>"));
+    }
+
+    #[test]
+    fn test_render_xlsx_requires_file() {
+        let manifest = ManifestSchema::new("test.csv".to_string(), FileFormat::Csv);
+        assert!(render(&manifest, OutputFormat::Xlsx).is_err());
+    }
+
+    #[test]
+    fn test_write_atomic_replaces_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("manifest.json");
+        std::fs::write(&path, "old").unwrap();
+
+        write_atomic(&path, b"new").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new");
+        // Only the target is left behind
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_failed_write_keeps_previous_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("manifest.json");
+        std::fs::write(&path, "old").unwrap();
+
+        let result = write_atomic_with(&path, |writer| {
+            writer.write_all(b"{\"trunc")?;
+            Err(Error::InvalidInput("disk full".to_string()))
+        });
+        assert!(result.is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "old");
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}
@@ -0,0 +1,194 @@
+use std::fmt::Write;
+
+use crate::types::{Classification, ColumnSchema, DType, ManifestSchema, SafeValue, SheetSchema};
+
+/// Render a clinical "Table 1" summary of each sheet as Markdown.
+///
+/// Continuous columns are shown as mean ± SD and median [IQR]; categorical
+/// columns as n (%) per listed value. Percentages need exact counts, so with
+/// bucketed counts only the bucket is shown. PHI, high-cardinality and
+/// free-text columns are left out.
+pub fn render_table1(manifest: &ManifestSchema) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# Table 1: {}", escape_cell(&manifest.file_name));
+    for sheet in &manifest.sheets {
+        render_sheet(&mut out, sheet);
+    }
+    out
+}
+
+fn render_sheet(out: &mut String, sheet: &SheetSchema) {
+    let _ = writeln!(
+        out,
+        "\n## {} (N = {})\n",
+        escape_cell(&sheet.name),
+        sheet.row_count
+    );
+    out.push_str("| Characteristic | Value | Missing |\n|---|---|---|\n");
+    for column in &sheet.columns {
+        if matches!(
+            column.classification,
+            Classification::Phi | Classification::HighCardinality
+        ) {
+            continue;
+        }
+        let Some(stats) = column.stats.as_ref() else {
+            continue;
+        };
+        let name = escape_cell(&column.name.to_string());
+        let missing = stats
+            .missing_count
+            .as_ref()
+            .map(|m| m.to_string())
+            .unwrap_or_default();
+
+        match (column.dtype, stats.mean) {
+            (DType::Integer | DType::Numeric, Some(mean)) => {
+                let sd = stats
+                    .std_dev
+                    .map(|sd| format!(" ± {:.1}", sd))
+                    .unwrap_or_default();
+                let _ = writeln!(
+                    out,
+                    "| {}, mean ± SD | {:.1}{} | {} |",
+                    name, mean, sd, missing
+                );
+                if let Some(median) = stats.median {
+                    let iqr = match (stats.q1, stats.q3) {
+                        (Some(q1), Some(q3)) => format!(" [{:.1}–{:.1}]", q1, q3),
+                        _ => String::new(),
+                    };
+                    let _ = writeln!(out, "| {}, median [IQR] | {:.1}{} | |", name, median, iqr);
+                }
+            }
+            _ => render_categorical(out, &name, column, &missing),
+        }
+    }
+}
+
+fn render_categorical(out: &mut String, name: &str, column: &ColumnSchema, missing: &str) {
+    let Some(frequencies) = column
+        .stats
+        .as_ref()
+        .and_then(|s| s.value_frequencies.as_ref())
+    else {
+        return;
+    };
+    if column.dtype == DType::FreeText {
+        return;
+    }
+    let total = match column.stats.as_ref().and_then(|s| s.count.as_ref()) {
+        Some(SafeValue::Integer(n)) if *n > 0 => Some(*n as f64),
+        _ => None,
+    };
+
+    let _ = writeln!(out, "| {}, n (%) | | {} |", name, missing);
+    for (value, count) in frequencies {
+        let cell = match (count, total) {
+            (SafeValue::Integer(n), Some(total)) => {
+                format!("{} ({:.1}%)", n, 100.0 * *n as f64 / total)
+            }
+            (SafeValue::Suppressed { .. }, _) => "<k".to_string(),
+            (other, _) => other.to_string(),
+        };
+        let _ = writeln!(out, "| &nbsp;&nbsp;{} | {} | |", escape_cell(value), cell);
+    }
+    if column.stats.as_ref().and_then(|s| s.suppressed_cells.as_ref()).is_some() {
+        let _ = writeln!(out, "| &nbsp;&nbsp;Other (suppressed) | <k | |");
+    }
+}
+
+/// Keep cell text from breaking the Markdown table
+fn escape_cell(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ColumnStats, FileFormat};
+    use std::collections::BTreeMap;
+
+    fn manifest(bucketed: bool) -> ManifestSchema {
+        let count = |n: i64| {
+            if bucketed {
+                SafeValue::ShortString(crate::privacy::bucket_count(n as u64).to_string())
+            } else {
+                SafeValue::Integer(n)
+            }
+        };
+
+        let mut age =
+            ColumnSchema::new(SafeValue::ShortString("age".to_string()), 0, DType::Integer);
+        age.stats = Some(ColumnStats {
+            missing_count: Some(count(2)),
+            mean: Some(54.25),
+            std_dev: Some(12.0),
+            median: Some(53.0),
+            q1: Some(45.0),
+            q3: Some(62.5),
+            ..Default::default()
+        });
+
+        let mut arm =
+            ColumnSchema::new(SafeValue::ShortString("arm".to_string()), 1, DType::String);
+        let mut frequencies = BTreeMap::new();
+        frequencies.insert("A".to_string(), count(30));
+        frequencies.insert("B|C".to_string(), count(10));
+        arm.stats = Some(ColumnStats {
+            count: Some(count(40)),
+            missing_count: Some(count(0)),
+            value_frequencies: Some(frequencies),
+            ..Default::default()
+        });
+
+        let mut mrn = ColumnSchema::new(
+            SafeValue::Suppressed {
+                reason: "PHI".to_string(),
+            },
+            2,
+            DType::String,
+        );
+        mrn.classification = Classification::Phi;
+        mrn.stats = Some(ColumnStats::default());
+
+        let mut sheet = SheetSchema::new("trial.csv".to_string(), 0);
+        sheet.row_count = count(42);
+        sheet.columns = vec![age, arm, mrn];
+        let mut manifest = ManifestSchema::new("trial.csv".to_string(), FileFormat::Csv);
+        manifest.sheets.push(sheet);
+        manifest
+    }
+
+    #[test]
+    fn test_render_table1_exact() {
+        let text = render_table1(&manifest(false));
+        assert!(text.contains("## trial.csv (N = 42)"));
+        assert!(text.contains("| age, mean ± SD | 54.2 ± 12.0 | 2 |"));
+        assert!(text.contains("| age, median [IQR] | 53.0 [45.0–62.5] | |"));
+        assert!(text.contains("| &nbsp;&nbsp;A | 30 (75.0%) | |"));
+        assert!(text.contains("B\\|C"));
+        assert!(!text.contains("suppressed"));
+    }
+
+    #[test]
+    fn test_render_table1_bucketed() {
+        let text = render_table1(&manifest(true));
+        assert!(text.contains("(N = 21-100)"));
+        assert!(text.contains("| &nbsp;&nbsp;A | 21-100 | |"));
+        assert!(text.contains("| &nbsp;&nbsp;B\\|C | 6-10 | |"));
+    }
+
+    #[test]
+    fn test_render_table1_suppressed_cells() {
+        let mut manifest = manifest(true);
+        let arm = &mut manifest.sheets[0].columns[1];
+        arm.stats.as_mut().unwrap().suppressed_cells = Some(SafeValue::ShortString("2-5".into()));
+
+        let text = render_table1(&manifest);
+        // Pooled after the listed values
+        let listed = text.find("B\\|C").unwrap();
+        let other = text.find("| &nbsp;&nbsp;Other (suppressed) | <k | |").unwrap();
+        assert!(other > listed);
+    }
+}
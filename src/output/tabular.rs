@@ -0,0 +1,203 @@
+use std::borrow::Cow;
+use std::path::Path;
+
+use rust_xlsxwriter::{Format, Workbook};
+
+use crate::types::{ColumnSchema, ManifestSchema, Result, SafeValue, SheetSchema};
+
+/// Header row of the column inventory
+pub const INVENTORY_HEADERS: &[&str] = &[
+    "file",
+    "sheet",
+    "column_index",
+    "column_name",
+    "dtype",
+    "classification",
+    "count",
+    "missing_count",
+    "unique_count",
+    "min",
+    "max",
+    "mean",
+    "std_dev",
+    "median",
+    "warnings",
+];
+
+/// One inventory row per column across all sheets
+pub fn inventory_rows(manifest: &ManifestSchema) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    for sheet in &manifest.sheets {
+        for col in &sheet.columns {
+            rows.push(column_row(manifest, sheet, col));
+        }
+    }
+    rows
+}
+
+fn column_row(manifest: &ManifestSchema, sheet: &SheetSchema, col: &ColumnSchema) -> Vec<String> {
+    let stats = col.stats.as_ref();
+    let safe = |v: Option<&SafeValue>| v.map(|v| v.to_string()).unwrap_or_default();
+    let float = |v: Option<f64>| v.map(|x| x.to_string()).unwrap_or_default();
+
+    vec![
+        manifest.file_name.clone(),
+        sheet.name.clone(),
+        (col.index + 1).to_string(),
+        col.name.to_string(),
+        col.dtype.as_str().to_string(),
+        col.classification.as_str().to_string(),
+        safe(stats.and_then(|s| s.count.as_ref())),
+        safe(stats.and_then(|s| s.missing_count.as_ref())),
+        safe(stats.and_then(|s| s.unique_count.as_ref())),
+        safe(stats.and_then(|s| s.min.as_ref().or(s.earliest.as_ref()))),
+        safe(stats.and_then(|s| s.max.as_ref().or(s.latest.as_ref()))),
+        float(stats.and_then(|s| s.mean)),
+        float(stats.and_then(|s| s.std_dev)),
+        float(stats.and_then(|s| s.median)),
+        col.warnings.join("; "),
+    ]
+}
+
+/// Whether a spreadsheet would read this cell as a formula (`=`, `+`, `-`, `@`,
+/// or a leading tab/carriage return). Plain numbers such as `-3.5` are not.
+pub fn is_formula_like(cell: &str) -> bool {
+    cell.starts_with(['=', '+', '-', '@', '\t', '\r']) && cell.trim().parse::<f64>().is_err()
+}
+
+/// Neutralize a cell that a spreadsheet would evaluate as a formula by
+/// prefixing it with a single quote
+pub fn escape_formula(cell: &str) -> Cow<'_, str> {
+    if is_formula_like(cell) {
+        Cow::Owned(format!("'{}", cell))
+    } else {
+        Cow::Borrowed(cell)
+    }
+}
+
+/// Render the column inventory as CSV
+pub fn render_csv(manifest: &ManifestSchema) -> Result<String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(INVENTORY_HEADERS)?;
+    for row in inventory_rows(manifest) {
+        writer.write_record(row.iter().map(|cell| escape_formula(cell).into_owned()))?;
+    }
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| crate::error::Error::InvalidInput(e.to_string()))?;
+    String::from_utf8(bytes).map_err(|e| crate::error::Error::InvalidInput(e.to_string()))
+}
+
+/// Write the column inventory as an XLSX workbook
+pub fn write_xlsx(manifest: &ManifestSchema, path: &Path) -> Result<()> {
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet();
+    worksheet.set_name("Columns")?;
+
+    for (col_idx, header) in INVENTORY_HEADERS.iter().enumerate() {
+        worksheet.write_string(0, col_idx as u16, *header)?;
+    }
+    // Cells are written as text, never formulas; the quote prefix also keeps
+    // formula-like text inert if the sheet is re-saved as CSV
+    let quoted = Format::new().set_quote_prefix();
+    for (row_idx, row) in inventory_rows(manifest).iter().enumerate() {
+        for (col_idx, cell) in row.iter().enumerate() {
+            let (row_num, col_num) = (row_idx as u32 + 1, col_idx as u16);
+            if is_formula_like(cell) {
+                worksheet.write_string_with_format(row_num, col_num, cell, &quoted)?;
+            } else {
+                worksheet.write_string(row_num, col_num, cell)?;
+            }
+        }
+    }
+
+    super::write_atomic(path, &workbook.save_to_buffer()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Classification, ColumnStats, DType, FileFormat};
+
+    fn sample_manifest() -> ManifestSchema {
+        let mut manifest = ManifestSchema::new("trial.csv".to_string(), FileFormat::Csv);
+        let mut sheet = SheetSchema::new("trial.csv".to_string(), 0);
+        let mut col = ColumnSchema::new(
+            SafeValue::ShortString("age".to_string()),
+            0,
+            DType::Integer,
+        );
+        col.stats = Some(ColumnStats {
+            count: Some(SafeValue::ShortString("21-100".to_string())),
+            mean: Some(41.5),
+            ..ColumnStats::default()
+        });
+        sheet.columns.push(col);
+
+        let mut phi = ColumnSchema::new(
+            SafeValue::Suppressed {
+                reason: "PHI".to_string(),
+            },
+            1,
+            DType::String,
+        );
+        phi.classification = Classification::Phi;
+        phi.warnings.push("Column name matches PHI pattern".to_string());
+        sheet.columns.push(phi);
+        manifest.sheets.push(sheet);
+        manifest
+    }
+
+    #[test]
+    fn test_inventory_rows() {
+        let rows = inventory_rows(&sample_manifest());
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].len(), INVENTORY_HEADERS.len());
+        assert_eq!(rows[0][3], "age");
+        assert_eq!(rows[0][6], "21-100");
+        assert_eq!(rows[0][11], "41.5");
+        assert_eq!(rows[1][3], "[suppressed]");
+        assert_eq!(rows[1][5], "phi");
+    }
+
+    #[test]
+    fn test_render_csv() {
+        let csv = render_csv(&sample_manifest()).unwrap();
+        let mut lines = csv.lines();
+        assert!(lines.next().unwrap().starts_with("file,sheet,column_index"));
+        assert!(lines.next().unwrap().starts_with("trial.csv,trial.csv,1,age,integer,safe"));
+    }
+
+    #[test]
+    fn test_escape_formula() {
+        assert_eq!(
+            escape_formula("=HYPERLINK(\"http://x\")"),
+            "'=HYPERLINK(\"http://x\")"
+        );
+        assert_eq!(escape_formula("+1+1"), "'+1+1");
+        assert_eq!(escape_formula("@SUM(A1)"), "'@SUM(A1)");
+        assert_eq!(escape_formula("\t=1"), "'\t=1");
+        assert_eq!(escape_formula("-3.5"), "-3.5");
+        assert_eq!(escape_formula("age"), "age");
+    }
+
+    #[test]
+    fn test_render_csv_escapes_formulas() {
+        let mut manifest = sample_manifest();
+        manifest.sheets[0].columns[0].name =
+            SafeValue::ShortString("=cmd|' /C calc'!A0".to_string());
+        manifest.sheets[0].columns[0].stats.as_mut().unwrap().min = Some(SafeValue::Float(-2.0));
+
+        let csv = render_csv(&manifest).unwrap();
+        assert!(csv.contains("'=cmd|' /C calc'!A0"));
+        assert!(csv.contains(",-2,"));
+    }
+
+    #[test]
+    fn test_write_xlsx() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("inventory.xlsx");
+        write_xlsx(&sample_manifest(), &path).unwrap();
+        assert!(std::fs::metadata(&path).unwrap().len() > 0);
+    }
+}
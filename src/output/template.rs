@@ -0,0 +1,160 @@
+use std::path::Path;
+
+use handlebars::{
+    no_escape, Context, Handlebars, Helper, HelperResult, Output, RenderContext, RenderError,
+    RenderErrorReason,
+};
+
+use crate::error::Error;
+use crate::paths;
+use crate::types::{ManifestSchema, Result, SafeValue};
+
+/// A user-supplied Handlebars template for the report.
+///
+/// The template sees the manifest exactly as in the JSON output. Templates
+/// whose name ends in `.html.hbs` (or `.htm.hbs`) escape HTML in `{{...}}`;
+/// all others are rendered verbatim, which suits Markdown and plain text.
+pub struct ReportTemplate {
+    registry: Handlebars<'static>,
+    extension: String,
+}
+
+const TEMPLATE_NAME: &str = "report";
+
+impl ReportTemplate {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(paths::for_fs_access(path)).map_err(|e| {
+            Error::Template(format!("{}: {}", paths::display_path(path), e))
+        })?;
+        Self::parse(&paths::file_name_lossy(path), &text)
+            .map_err(|e| Error::Template(format!("{}: {}", paths::display_path(path), e)))
+    }
+
+    fn parse(file_name: &str, text: &str) -> std::result::Result<Self, String> {
+        let extension = output_extension(file_name);
+        let mut registry = Handlebars::new();
+        if !matches!(extension.as_str(), "html" | "htm") {
+            registry.register_escape_fn(no_escape);
+        }
+        registry.register_helper("value", Box::new(value_helper));
+        registry
+            .register_template_string(TEMPLATE_NAME, text)
+            .map_err(|e| e.to_string())?;
+        Ok(Self {
+            registry,
+            extension,
+        })
+    }
+
+    /// Extension of the rendered report, taken from the template name
+    /// (`report.html.hbs` renders `.html`; `.md` if the name does not say)
+    pub fn extension(&self) -> &str {
+        &self.extension
+    }
+
+    pub fn render(&self, manifest: &ManifestSchema) -> Result<String> {
+        self.registry
+            .render(TEMPLATE_NAME, manifest)
+            .map_err(|e| Error::Template(e.to_string()))
+    }
+}
+
+fn output_extension(file_name: &str) -> String {
+    file_name
+        .strip_suffix(".hbs")
+        .or_else(|| file_name.strip_suffix(".handlebars"))
+        .and_then(|stem| Path::new(stem).extension())
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_else(|| "md".to_string())
+}
+
+/// `{{value name}}`: a SafeValue as plain text (as in the HTML report)
+fn value_helper(
+    h: &Helper,
+    _: &Handlebars,
+    _: &Context,
+    _: &mut RenderContext,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let param = h
+        .param(0)
+        .ok_or_else(|| RenderErrorReason::ParamNotFoundForIndex("value", 0))?;
+    let value: SafeValue = serde_json::from_value(param.value().clone()).map_err(|_| {
+        RenderError::from(RenderErrorReason::InvalidParamType("SafeValue"))
+    })?;
+    out.write(&value.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ColumnSchema, DType, FileFormat, SheetSchema};
+
+    fn manifest() -> ManifestSchema {
+        let mut sheet = SheetSchema::new("labs <2024>".to_string(), 0);
+        sheet.columns.push(ColumnSchema::new(
+            SafeValue::ShortString("age".to_string()),
+            0,
+            DType::Integer,
+        ));
+        sheet.columns.push(ColumnSchema::new(
+            SafeValue::Suppressed {
+                reason: "PHI".to_string(),
+            },
+            1,
+            DType::String,
+        ));
+        let mut manifest = ManifestSchema::new("labs.csv".to_string(), FileFormat::Csv);
+        manifest.sheets.push(sheet);
+        manifest
+    }
+
+    #[test]
+    fn test_render_markdown_template() {
+        let template = ReportTemplate::parse(
+            "site.md.hbs",
+            "# {{file_name}}\n\
+             {{#each sheets}}## {{name}}\n\
+             {{#each columns}}- {{value name}} ({{dtype}}, {{classification}})\n{{/each}}\
+             {{/each}}Approved for release by the Data Office.\n",
+        )
+        .unwrap();
+        assert_eq!(template.extension(), "md");
+        assert_eq!(
+            template.render(&manifest()).unwrap(),
+            "# labs.csv\n## labs <2024>\n- age (integer, safe)\n- [suppressed] (string, safe)\n\
+             Approved for release by the Data Office.\n"
+        );
+    }
+
+    #[test]
+    fn test_html_template_escapes() {
+        let template =
+            ReportTemplate::parse("report.html.hbs", "{{#each sheets}}<h2>{{name}}</h2>{{/each}}")
+                .unwrap();
+        assert_eq!(template.extension(), "html");
+        assert_eq!(
+            template.render(&manifest()).unwrap(),
+            "<h2>labs &lt;2024&gt;</h2>"
+        );
+    }
+
+    #[test]
+    fn test_output_extension() {
+        assert_eq!(output_extension("report.html.hbs"), "html");
+        assert_eq!(output_extension("Report.MD.handlebars"), "md");
+        assert_eq!(output_extension("report.hbs"), "md");
+        assert_eq!(output_extension("report.txt"), "md");
+    }
+
+    #[test]
+    fn test_invalid_template() {
+        assert!(ReportTemplate::parse("bad.md.hbs", "{{#each sheets}}").is_err());
+        let template = ReportTemplate::parse("bad.md.hbs", "{{value file_name}}").unwrap();
+        assert!(matches!(
+            template.render(&manifest()),
+            Err(Error::Template(_))
+        ));
+    }
+}
@@ -0,0 +1,223 @@
+use std::path::{Path, PathBuf};
+
+/// Windows verbatim prefix for extended-length paths
+const VERBATIM_PREFIX: &str = r"\\?\";
+
+/// Windows verbatim prefix for extended-length UNC paths
+const VERBATIM_UNC_PREFIX: &str = r"\\?\UNC\";
+
+/// Paths at or beyond this length need the verbatim prefix on Windows
+#[cfg(windows)]
+const MAX_PATH: usize = 260;
+
+/// File name of a path for display and manifests; non-UTF-8 bytes are replaced, never dropped
+pub fn file_name_lossy(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Extension of a path (without the dot); non-UTF-8 bytes are replaced
+pub fn extension_lossy(path: &Path) -> String {
+    path.extension()
+        .map(|e| e.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Human-readable form of a path with any `\\?\` / `\\?\UNC\` prefix removed
+pub fn display_path(path: &Path) -> String {
+    let s = path.to_string_lossy();
+    if let Some(rest) = s.strip_prefix(VERBATIM_UNC_PREFIX) {
+        format!(r"\\{}", rest)
+    } else if let Some(rest) = s.strip_prefix(VERBATIM_PREFIX) {
+        rest.to_string()
+    } else {
+        s.into_owned()
+    }
+}
+
+/// Per-user application directory (~/.ert-manifest)
+pub fn app_dir() -> PathBuf {
+    let home = std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("."));
+    home.join(".ert-manifest")
+}
+
+/// Append a suffix to the file name, keeping any existing extension
+/// (`export.v2.csv` + `.recode.txt` = `export.v2.csv.recode.txt`)
+pub fn append_extension(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Path of the recode sidekick for an input file: the input name plus
+/// `suffix`, next to the input or in `out_dir` if given
+pub fn sidekick_path(input: &Path, out_dir: Option<&Path>, suffix: &str) -> PathBuf {
+    match (out_dir, input.file_name()) {
+        (Some(dir), Some(name)) => append_extension(&dir.join(name), suffix),
+        _ => append_extension(input, suffix),
+    }
+}
+
+/// Insert a timestamp before the final extension
+/// (`data.manifest.json` -> `data.manifest.2024-06-01T12-00.json`).
+///
+/// If that name is already taken (two scans within the same minute), a counter
+/// is appended to the timestamp so nothing is overwritten.
+pub fn versioned_path(path: &Path, timestamp: &str) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default();
+    let candidate = |suffix: &str| {
+        let mut name = stem.to_os_string();
+        name.push(format!(".{}{}", timestamp, suffix));
+        if let Some(ext) = path.extension() {
+            name.push(".");
+            name.push(ext);
+        }
+        path.with_file_name(name)
+    };
+
+    let mut versioned = candidate("");
+    let mut n = 2;
+    while for_fs_access(&versioned).exists() {
+        versioned = candidate(&format!("-{}", n));
+        n += 1;
+    }
+    versioned
+}
+
+/// Prepare a user-supplied path for file system access.
+///
+/// On Windows, long absolute paths (including `\\server\share` UNC paths) are
+/// converted to their `\\?\` extended-length form so they open regardless of
+/// the MAX_PATH limit. Elsewhere the path is returned unchanged.
+pub fn for_fs_access(path: &Path) -> PathBuf {
+    #[cfg(windows)]
+    {
+        to_extended_length(path)
+    }
+    #[cfg(not(windows))]
+    {
+        path.to_path_buf()
+    }
+}
+
+#[cfg(windows)]
+fn to_extended_length(path: &Path) -> PathBuf {
+    let s = path.to_string_lossy();
+    if s.starts_with(VERBATIM_PREFIX) || s.len() < MAX_PATH {
+        return path.to_path_buf();
+    }
+
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        match std::env::current_dir() {
+            Ok(cwd) => cwd.join(path),
+            Err(_) => return path.to_path_buf(),
+        }
+    };
+
+    // Verbatim paths are not normalized by Windows, so separators must be backslashes
+    let absolute = absolute.to_string_lossy().replace('/', r"\");
+    if let Some(unc) = absolute.strip_prefix(r"\\") {
+        PathBuf::from(format!("{}{}", VERBATIM_UNC_PREFIX, unc))
+    } else {
+        PathBuf::from(format!("{}{}", VERBATIM_PREFIX, absolute))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_name_lossy() {
+        assert_eq!(file_name_lossy(Path::new("/data/trial.csv")), "trial.csv");
+        assert_eq!(file_name_lossy(Path::new("/")), "unknown");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_file_name_lossy_non_utf8() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = Path::new(OsStr::from_bytes(b"/data/donn\xe9es.csv"));
+        assert_eq!(file_name_lossy(path), "donn\u{FFFD}es.csv");
+        assert_eq!(extension_lossy(path), "csv");
+    }
+
+    #[test]
+    fn test_extension_lossy() {
+        assert_eq!(extension_lossy(Path::new("data.XLSX")), "XLSX");
+        assert_eq!(extension_lossy(Path::new("data")), "");
+    }
+
+    #[test]
+    fn test_display_path_strips_verbatim_prefix() {
+        assert_eq!(
+            display_path(Path::new(r"\\?\C:\data\trial.csv")),
+            r"C:\data\trial.csv"
+        );
+        assert_eq!(
+            display_path(Path::new(r"\\?\UNC\server\share\trial.csv")),
+            r"\\server\share\trial.csv"
+        );
+        assert_eq!(display_path(Path::new("/data/trial.csv")), "/data/trial.csv");
+    }
+
+    #[test]
+    fn test_sidekick_path_keeps_verbatim_prefix() {
+        assert_eq!(
+            sidekick_path(Path::new(r"\\?\C:\data\trial.csv"), None, ".recode.txt"),
+            PathBuf::from(r"\\?\C:\data\trial.csv.recode.txt")
+        );
+    }
+
+    #[test]
+    fn test_versioned_path() {
+        assert_eq!(
+            versioned_path(Path::new("/nonexistent/data.manifest.json"), "2024-06-01T12-00"),
+            PathBuf::from("/nonexistent/data.manifest.2024-06-01T12-00.json")
+        );
+    }
+
+    #[test]
+    fn test_versioned_path_avoids_existing() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("data.manifest.json");
+        std::fs::write(dir.path().join("data.manifest.2024-06-01T12-00.json"), "{}").unwrap();
+
+        assert_eq!(
+            versioned_path(&base, "2024-06-01T12-00"),
+            dir.path().join("data.manifest.2024-06-01T12-00-2.json")
+        );
+    }
+
+    #[test]
+    fn test_sidekick_path_appends_extension() {
+        assert_eq!(
+            sidekick_path(Path::new("/share/export.v2.csv"), None, ".recode.txt"),
+            PathBuf::from("/share/export.v2.csv.recode.txt")
+        );
+        assert_eq!(
+            sidekick_path(
+                Path::new("/share/export.v2.csv"),
+                Some(Path::new("/home/me/out")),
+                ".recode.json"
+            ),
+            PathBuf::from("/home/me/out/export.v2.csv.recode.json")
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_extended_length_unc() {
+        let long = format!(r"\\server\share\{}\trial.csv", "d".repeat(300));
+        let converted = for_fs_access(Path::new(&long));
+        assert!(converted.to_string_lossy().starts_with(r"\\?\UNC\server\share\"));
+    }
+}
@@ -14,6 +14,22 @@ pub fn bucket_count(n: u64) -> &'static str {
     }
 }
 
+/// Bucket a possibly large count by order of magnitude (for approximate counts)
+pub fn bucket_magnitude(n: u64) -> String {
+    if n <= 1000 {
+        return bucket_count(n).to_string();
+    }
+    let mut lower: u64 = 1000;
+    while lower < 1_000_000 {
+        let upper = lower * 10;
+        if n <= upper {
+            return format!("{}-{}", lower + 1, upper);
+        }
+        lower = upper;
+    }
+    ">1000000".to_string()
+}
+
 /// Convert a count to a SafeValue, bucketing if requested
 pub fn safe_count(n: u64, bucket: bool) -> SafeValue {
     if bucket {
@@ -27,6 +43,15 @@ pub fn safe_count(n: u64, bucket: bool) -> SafeValue {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_bucket_magnitude() {
+        assert_eq!(bucket_magnitude(50), "21-100");
+        assert_eq!(bucket_magnitude(2500), "1001-10000");
+        assert_eq!(bucket_magnitude(10_000), "1001-10000");
+        assert_eq!(bucket_magnitude(10_001), "10001-100000");
+        assert_eq!(bucket_magnitude(5_000_000), ">1000000");
+    }
+
     #[test]
     fn test_bucket_count_zero() {
         assert_eq!(bucket_count(0), "0");
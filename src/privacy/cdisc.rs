@@ -0,0 +1,323 @@
+use super::column_names::ColumnNameResult;
+use crate::types::Classification;
+
+/// How a standard variable is handled in the manifest
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Handling {
+    Safe,
+    /// Keep, but flag for review
+    Review,
+    /// Subject identifier: replace values with stable pseudonyms (recoded with this prefix)
+    Pseudonymize(&'static str),
+    /// Site-identifying: recode with this prefix
+    Recode(&'static str),
+    Phi,
+}
+
+struct Variable {
+    name: &'static str,
+    label: &'static str,
+    handling: Handling,
+}
+
+const fn var(name: &'static str, label: &'static str, handling: Handling) -> Variable {
+    Variable {
+        name,
+        label,
+        handling,
+    }
+}
+
+/// Named SDTM and ADaM variables
+const VARIABLES: &[Variable] = &[
+    // ===== IDENTIFIERS =====
+    var("STUDYID", "Study Identifier", Handling::Safe),
+    var("DOMAIN", "Domain Abbreviation", Handling::Safe),
+    var(
+        "USUBJID",
+        "Unique Subject Identifier",
+        Handling::Pseudonymize("Subject"),
+    ),
+    var(
+        "SUBJID",
+        "Subject Identifier for the Study",
+        Handling::Pseudonymize("Subject"),
+    ),
+    var("POOLID", "Pool Identifier", Handling::Pseudonymize("Pool")),
+    var("SITEID", "Study Site Identifier", Handling::Recode("Site")),
+    var("SITEGR1", "Pooled Site Group 1", Handling::Recode("Site")),
+    var(
+        "INVID",
+        "Investigator Identifier",
+        Handling::Recode("Investigator"),
+    ),
+    var("INVNAM", "Investigator Name", Handling::Phi),
+    var("SPDEVID", "Sponsor Device Identifier", Handling::Review),
+    // ===== DEMOGRAPHICS =====
+    var("BRTHDTC", "Date/Time of Birth", Handling::Phi),
+    var("BRTHDT", "Date of Birth", Handling::Phi),
+    var("AGE", "Age", Handling::Safe),
+    var("AGEU", "Age Units", Handling::Safe),
+    var("AGEGR1", "Pooled Age Group 1", Handling::Safe),
+    var("SEX", "Sex", Handling::Safe),
+    var("RACE", "Race", Handling::Safe),
+    var("ETHNIC", "Ethnicity", Handling::Safe),
+    var("COUNTRY", "Country", Handling::Review),
+    var("DTHDTC", "Date/Time of Death", Handling::Phi),
+    var("DTHDT", "Date of Death", Handling::Phi),
+    var("DTHFL", "Subject Death Flag", Handling::Safe),
+    // ===== REFERENCE DATES =====
+    var(
+        "RFSTDTC",
+        "Subject Reference Start Date/Time",
+        Handling::Review,
+    ),
+    var(
+        "RFENDTC",
+        "Subject Reference End Date/Time",
+        Handling::Review,
+    ),
+    var(
+        "RFXSTDTC",
+        "Date/Time of First Study Treatment",
+        Handling::Review,
+    ),
+    var(
+        "RFXENDTC",
+        "Date/Time of Last Study Treatment",
+        Handling::Review,
+    ),
+    var("RFICDTC", "Date/Time of Informed Consent", Handling::Review),
+    var(
+        "RFPENDTC",
+        "Date/Time of End of Participation",
+        Handling::Review,
+    ),
+    var(
+        "TRTSDT",
+        "Date of First Exposure to Treatment",
+        Handling::Review,
+    ),
+    var(
+        "TRTEDT",
+        "Date of Last Exposure to Treatment",
+        Handling::Review,
+    ),
+    var("ADT", "Analysis Date", Handling::Review),
+    // ===== TREATMENT AND POPULATIONS =====
+    var("ARMCD", "Planned Arm Code", Handling::Safe),
+    var("ARM", "Description of Planned Arm", Handling::Safe),
+    var("ACTARMCD", "Actual Arm Code", Handling::Safe),
+    var("ACTARM", "Description of Actual Arm", Handling::Safe),
+    var("TRT01P", "Planned Treatment for Period 01", Handling::Safe),
+    var("TRT01A", "Actual Treatment for Period 01", Handling::Safe),
+    var("SAFFL", "Safety Population Flag", Handling::Safe),
+    var("ITTFL", "Intent-To-Treat Population Flag", Handling::Safe),
+    var("FASFL", "Full Analysis Set Population Flag", Handling::Safe),
+    var("PPROTFL", "Per-Protocol Population Flag", Handling::Safe),
+    var("RANDFL", "Randomized Population Flag", Handling::Safe),
+    // ===== VISITS AND ANALYSIS VALUES =====
+    var("VISITNUM", "Visit Number", Handling::Safe),
+    var("VISIT", "Visit Name", Handling::Safe),
+    var("VISITDY", "Planned Study Day of Visit", Handling::Safe),
+    var("EPOCH", "Epoch", Handling::Safe),
+    var("AVISIT", "Analysis Visit", Handling::Safe),
+    var("AVISITN", "Analysis Visit (N)", Handling::Safe),
+    var("ADY", "Analysis Relative Day", Handling::Safe),
+    var("PARAMCD", "Parameter Code", Handling::Safe),
+    var("PARAM", "Parameter", Handling::Safe),
+    var("AVAL", "Analysis Value", Handling::Safe),
+    var("AVALC", "Analysis Value (C)", Handling::Safe),
+    var("BASE", "Baseline Value", Handling::Safe),
+    var("CHG", "Change from Baseline", Handling::Safe),
+    var("PCHG", "Percent Change from Baseline", Handling::Safe),
+    var("ABLFL", "Baseline Record Flag", Handling::Safe),
+];
+
+/// SDTM variables named with their domain prefix (`AESEQ`, `LBTESTCD`, ...);
+/// written `--SEQ`, `--TESTCD` in the standard
+const DOMAIN_VARIABLES: &[Variable] = &[
+    var("SEQ", "Sequence Number", Handling::Safe),
+    var("GRPID", "Group ID", Handling::Safe),
+    var("SPID", "Sponsor-Defined Identifier", Handling::Review),
+    var("TESTCD", "Short Name of Test", Handling::Safe),
+    var("TEST", "Name of Test", Handling::Safe),
+    var("CAT", "Category", Handling::Safe),
+    var("SCAT", "Subcategory", Handling::Safe),
+    var("ORRES", "Result in Original Units", Handling::Safe),
+    var("ORRESU", "Original Units", Handling::Safe),
+    var("STRESC", "Result in Standard Format", Handling::Safe),
+    var("STRESN", "Numeric Result in Standard Units", Handling::Safe),
+    var("STRESU", "Standard Units", Handling::Safe),
+    var("STAT", "Completion Status", Handling::Safe),
+    var("REASND", "Reason Not Done", Handling::Review),
+    var("BLFL", "Baseline Flag", Handling::Safe),
+    var("TERM", "Reported Term", Handling::Review),
+    var("DECOD", "Dictionary-Derived Term", Handling::Safe),
+    var("BODSYS", "Body System or Organ Class", Handling::Safe),
+    var("SEV", "Severity/Intensity", Handling::Safe),
+    var("SER", "Serious Event", Handling::Safe),
+    var("REL", "Causality", Handling::Safe),
+    var("OUT", "Outcome of Event", Handling::Safe),
+    var("TRT", "Name of Treatment", Handling::Safe),
+    var("DOSE", "Dose", Handling::Safe),
+    var("DOSU", "Dose Units", Handling::Safe),
+    var("ROUTE", "Route of Administration", Handling::Safe),
+    var("DTC", "Date/Time of Collection", Handling::Review),
+    var("STDTC", "Start Date/Time", Handling::Review),
+    var("ENDTC", "End Date/Time", Handling::Review),
+    var("DY", "Study Day", Handling::Safe),
+    var("STDY", "Study Day of Start", Handling::Safe),
+    var("ENDY", "Study Day of End", Handling::Safe),
+    var("VAL", "Comment", Handling::Review),
+];
+
+/// SDTM domain abbreviations
+const SDTM_DOMAINS: &[&str] = &[
+    "AE", "AG", "BE", "BS", "CE", "CM", "CO", "CV", "DA", "DD", "DM", "DS", "DV", "EC", "EG", "EX",
+    "FA", "HO", "IE", "IS", "LB", "MB", "MH", "MI", "MK", "ML", "MS", "NV", "OE", "PC", "PE", "PP",
+    "PR", "QS", "RE", "RP", "RS", "SC", "SE", "SM", "SR", "SS", "SU", "SV", "TA", "TD", "TE", "TI",
+    "TM", "TR", "TS", "TU", "TV", "UR", "VS",
+];
+
+/// A column recognized as a CDISC variable
+#[derive(Debug, Clone, PartialEq)]
+pub struct CdiscMatch {
+    /// Standard variable name; domain-prefixed variables are written `--SEQ`
+    pub variable: String,
+    label: &'static str,
+    handling: Handling,
+}
+
+impl CdiscMatch {
+    /// Classification of the column, in place of the name-pattern check
+    pub fn name_check(&self) -> ColumnNameResult {
+        let describe = |outcome: &str| {
+            Some(format!(
+                "CDISC variable {} ({}); {}",
+                self.variable, self.label, outcome
+            ))
+        };
+        let (classification, warning) = match self.handling {
+            Handling::Safe => (Classification::Safe, None),
+            Handling::Review => (Classification::Warning, describe("review recommended")),
+            Handling::Pseudonymize(_) => (
+                Classification::Recode,
+                describe("values will be replaced with pseudonyms"),
+            ),
+            Handling::Recode(_) => (Classification::Recode, describe("values will be recoded")),
+            Handling::Phi => (Classification::Phi, describe("values suppressed")),
+        };
+        ColumnNameResult {
+            classification,
+            matched_pattern: Some(format!("cdisc:{}", self.variable)),
+            warning,
+            matches: Vec::new(),
+        }
+    }
+
+    /// Label prefix for recoded values (`Subject_001`, `Site_A`, ...)
+    pub fn recode_prefix(&self) -> Option<&'static str> {
+        match self.handling {
+            Handling::Pseudonymize(prefix) | Handling::Recode(prefix) => Some(prefix),
+            _ => None,
+        }
+    }
+}
+
+/// The SDTM domain or ADaM dataset a table holds, from its dataset or file
+/// name (`DM`, `dm.xpt`, `adsl.csv`)
+pub fn dataset_domain(sheet_name: &str) -> Option<String> {
+    let stem = match sheet_name.rsplit_once('.') {
+        Some((stem, _)) => stem,
+        None => sheet_name,
+    };
+    let stem = stem.trim().to_ascii_uppercase();
+    let is_adam = stem.len() > 2
+        && stem.len() <= 8
+        && stem.starts_with("AD")
+        && stem.chars().all(|c| c.is_ascii_alphanumeric());
+    if is_adam || SDTM_DOMAINS.contains(&stem.as_str()) {
+        Some(stem)
+    } else {
+        None
+    }
+}
+
+/// Match a column name against the CDISC variables. In an SDTM domain table only
+/// that domain's prefix is recognized (`AESEQ` in AE); elsewhere any domain's is.
+pub fn match_variable(name: &str, domain: Option<&str>) -> Option<CdiscMatch> {
+    let name = name.trim().to_ascii_uppercase();
+    // SAS transport limits variable names to 8 characters
+    if name.is_empty() || name.len() > 8 || !name.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+
+    if let Some(v) = VARIABLES.iter().find(|v| v.name == name) {
+        return Some(CdiscMatch {
+            variable: v.name.to_string(),
+            label: v.label,
+            handling: v.handling,
+        });
+    }
+
+    let (prefix, rest) = name.split_at(2);
+    let prefix_ok = match domain {
+        Some(domain) if SDTM_DOMAINS.contains(&domain) => prefix == domain,
+        _ => SDTM_DOMAINS.contains(&prefix),
+    };
+    if !prefix_ok {
+        return None;
+    }
+    DOMAIN_VARIABLES
+        .iter()
+        .find(|v| v.name == rest)
+        .map(|v| CdiscMatch {
+            variable: format!("--{}", v.name),
+            label: v.label,
+            handling: v.handling,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dataset_domain() {
+        assert_eq!(dataset_domain("DM").as_deref(), Some("DM"));
+        assert_eq!(dataset_domain("ae.xpt").as_deref(), Some("AE"));
+        assert_eq!(dataset_domain("adsl.csv").as_deref(), Some("ADSL"));
+        assert_eq!(dataset_domain("patients.csv"), None);
+        assert_eq!(dataset_domain("AD"), None);
+    }
+
+    #[test]
+    fn test_match_variable() {
+        let usubjid = match_variable("usubjid", None).unwrap();
+        assert_eq!(usubjid.variable, "USUBJID");
+        assert_eq!(usubjid.name_check().classification, Classification::Recode);
+        assert_eq!(usubjid.recode_prefix(), Some("Subject"));
+
+        let siteid = match_variable("SITEID", Some("DM")).unwrap();
+        assert_eq!(siteid.recode_prefix(), Some("Site"));
+        assert_eq!(
+            match_variable("BRTHDTC", Some("DM"))
+                .unwrap()
+                .name_check()
+                .classification,
+            Classification::Phi
+        );
+
+        let aeseq = match_variable("AESEQ", Some("AE")).unwrap();
+        assert_eq!(aeseq.variable, "--SEQ");
+        assert_eq!(aeseq.name_check().classification, Classification::Safe);
+        assert!(aeseq.name_check().warning.is_none());
+        // Another domain's prefix in an SDTM domain table is not a match
+        assert_eq!(match_variable("LBSEQ", Some("AE")), None);
+        assert!(match_variable("LBSTDTC", Some("ADLB")).is_some());
+
+        assert_eq!(match_variable("weight_kg", None), None);
+        assert_eq!(match_variable("XXSEQ", None), None);
+    }
+}
@@ -0,0 +1,196 @@
+//! Clinical code systems recognized in column values.
+//!
+//! Diagnosis, drug and lab codes are not identifiers, but some look like them:
+//! a 9-digit SNOMED CT concept ID has the shape of an SSN. When most of a
+//! column's values follow one code system, the column is annotated with it and
+//! values of that shape are exempt from the PHI value patterns.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// A clinical coding system
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CodeSystem {
+    /// ICD-10 and ICD-10-CM diagnoses: `J18.9`, `E11`, `S72001A`
+    Icd10,
+    /// WHO ATC drug classes, from the pharmacological subgroup down: `N02B`, `N02BE01`
+    Atc,
+    /// LOINC lab and observation codes with their check digit: `2345-7`
+    Loinc,
+    /// SNOMED CT identifiers with a valid Verhoeff check digit: `22298006`
+    SnomedCt,
+}
+
+impl CodeSystem {
+    /// In the order columns are checked against them: full ATC codes are also
+    /// dotless ICD-10 shapes, so ATC comes first
+    const ALL: [CodeSystem; 4] = [
+        CodeSystem::Atc,
+        CodeSystem::Loinc,
+        CodeSystem::SnomedCt,
+        CodeSystem::Icd10,
+    ];
+
+    /// Whether a value has the shape of a code of this system
+    pub fn matches(&self, value: &str) -> bool {
+        let value = value.trim();
+        match self {
+            CodeSystem::Icd10 => {
+                ICD10_PATTERN.is_match(value) && !CANADA_POSTAL_SHAPE.is_match(value)
+            }
+            CodeSystem::Atc => ATC_PATTERN.is_match(value),
+            CodeSystem::Loinc => is_loinc(value),
+            CodeSystem::SnomedCt => is_snomed_ct(value),
+        }
+    }
+}
+
+/// Share of a column's distinct values that must follow one code system
+const MIN_CODE_SHARE: f64 = 0.9;
+
+/// Fewest distinct values a column needs for its code system to be told
+const MIN_CODED_VALUES: usize = 3;
+
+// Chapter letter, two characters of category, then up to four of subcategory
+// with or without the dot
+static ICD10_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^[A-Z]\d[0-9A-Z](\.?[0-9A-Z]{1,4})?$").unwrap());
+
+// A1A1A1 is a dotless ICD-10-CM code as much as a Canadian postal code; the
+// postal code wins
+static CANADA_POSTAL_SHAPE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^[A-Z]\d[A-Z]\d[A-Z]\d$").unwrap());
+
+// Anatomical group, therapeutic subgroup (2 digits), then the pharmacological
+// and chemical subgroups (a letter each) and the substance (2 digits)
+static ATC_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^[ABCDGHJLMNPRSV]\d{2}[A-Z]([A-Z](\d{2})?)?$").unwrap());
+
+static LOINC_PATTERN: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\d{1,5}-\d$").unwrap());
+
+static SNOMED_PATTERN: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[1-9]\d{5,17}$").unwrap());
+
+/// The code system followed by most of a column's distinct values, if any
+pub fn detect_code_system<'a>(values: impl IntoIterator<Item = &'a str>) -> Option<CodeSystem> {
+    let values: Vec<&str> = values
+        .into_iter()
+        .filter(|v| !v.trim().is_empty())
+        .collect();
+    if values.len() < MIN_CODED_VALUES {
+        return None;
+    }
+    CodeSystem::ALL.into_iter().find(|system| {
+        let coded = values.iter().filter(|v| system.matches(v)).count();
+        coded as f64 >= values.len() as f64 * MIN_CODE_SHARE
+    })
+}
+
+/// LOINC code: up to five digits, a dash and a mod-10 (Luhn) check digit
+fn is_loinc(value: &str) -> bool {
+    LOINC_PATTERN.is_match(value) && luhn_valid(&value.replace('-', ""))
+}
+
+/// SNOMED CT identifier: 6 to 18 digits whose second and third digits from the
+/// right name a core partition (concept, description, relationship) and whose
+/// last digit is a Verhoeff check digit
+fn is_snomed_ct(value: &str) -> bool {
+    if !SNOMED_PATTERN.is_match(value) {
+        return false;
+    }
+    let partition = &value[value.len() - 3..value.len() - 1];
+    matches!(partition, "00" | "01" | "02" | "10" | "11" | "12") && verhoeff_valid(value)
+}
+
+fn luhn_valid(digits: &str) -> bool {
+    let sum: i32 = digits
+        .bytes()
+        .rev()
+        .enumerate()
+        .map(|(i, b)| {
+            let d = i32::from(b - b'0');
+            if i % 2 == 1 {
+                let doubled = d * 2;
+                doubled / 10 + doubled % 10
+            } else {
+                d
+            }
+        })
+        .sum();
+    sum % 10 == 0
+}
+
+const VERHOEFF_D: [[u8; 10]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9],
+    [1, 2, 3, 4, 0, 6, 7, 8, 9, 5],
+    [2, 3, 4, 0, 1, 7, 8, 9, 5, 6],
+    [3, 4, 0, 1, 2, 8, 9, 5, 6, 7],
+    [4, 0, 1, 2, 3, 9, 5, 6, 7, 8],
+    [5, 9, 8, 7, 6, 0, 4, 3, 2, 1],
+    [6, 5, 9, 8, 7, 1, 0, 4, 3, 2],
+    [7, 6, 5, 9, 8, 2, 1, 0, 4, 3],
+    [8, 7, 6, 5, 9, 3, 2, 1, 0, 4],
+    [9, 8, 7, 6, 5, 4, 3, 2, 1, 0],
+];
+
+const VERHOEFF_P: [[u8; 10]; 8] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9],
+    [1, 5, 7, 6, 2, 8, 3, 0, 9, 4],
+    [5, 8, 0, 3, 7, 9, 6, 1, 4, 2],
+    [8, 9, 1, 6, 0, 4, 3, 5, 2, 7],
+    [9, 4, 5, 3, 1, 2, 6, 8, 7, 0],
+    [4, 2, 8, 6, 5, 7, 3, 9, 0, 1],
+    [2, 7, 9, 3, 8, 0, 6, 4, 1, 5],
+    [7, 0, 4, 6, 9, 1, 3, 2, 5, 8],
+];
+
+fn verhoeff_valid(digits: &str) -> bool {
+    let check = digits.bytes().rev().enumerate().fold(0, |c, (i, b)| {
+        let permuted = VERHOEFF_P[i % 8][usize::from(b - b'0')];
+        VERHOEFF_D[usize::from(c)][usize::from(permuted)]
+    });
+    check == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_code_shapes() {
+        for code in ["J18.9", "E11", "E11.65", "S72001A", "U07.1"] {
+            assert!(CodeSystem::Icd10.matches(code), "{}", code);
+        }
+        assert!(!CodeSystem::Icd10.matches("H3A1B2"));
+        assert!(!CodeSystem::Icd10.matches("J18.99999"));
+
+        for code in ["N02BE01", "C10AA05", "N02B"] {
+            assert!(CodeSystem::Atc.matches(code), "{}", code);
+        }
+        assert!(!CodeSystem::Atc.matches("X02BE01"));
+
+        assert!(CodeSystem::Loinc.matches("2345-7"));
+        assert!(CodeSystem::Loinc.matches("4548-4"));
+        assert!(!CodeSystem::Loinc.matches("2345-6"));
+
+        for code in ["22298006", "73211009", "38341003", "195967001"] {
+            assert!(CodeSystem::SnomedCt.matches(code), "{}", code);
+        }
+        assert!(!CodeSystem::SnomedCt.matches("22298007"));
+        assert!(!CodeSystem::SnomedCt.matches("5145551234"));
+    }
+
+    #[test]
+    fn test_detect_code_system() {
+        let diagnoses = ["J18.9", "E11.65", "I10", "N18.3"];
+        assert_eq!(detect_code_system(diagnoses), Some(CodeSystem::Icd10));
+        let drugs = ["N02BE01", "C10AA05", "A10BA02"];
+        assert_eq!(detect_code_system(drugs), Some(CodeSystem::Atc));
+        let concepts = ["22298006", "73211009", "38341003", "195967001"];
+        assert_eq!(detect_code_system(concepts), Some(CodeSystem::SnomedCt));
+
+        assert_eq!(detect_code_system(["J18.9", "E11"]), None);
+        assert_eq!(detect_code_system(["J18.9", "E11", "yes", "no"]), None);
+    }
+}
@@ -1,3 +1,4 @@
+use super::locales::{fold_accents, LocalePack};
 use crate::types::Classification;
 
 /// Patterns that indicate PHI in column names (suppress values)
@@ -213,12 +214,45 @@ const PHI_WARN_ONLY: &[&str] = &[
     "case",          // case number
 ];
 
+/// Short PHI patterns that are also everyday abbreviations (`uf` ultrafiltration,
+/// `md` mean difference, `mae` mean absolute error), with the confidence of a match
+/// inside a longer column name. A column named exactly like the pattern is still certain.
+const WEAK_PATTERNS: &[(&str, f64)] = &[
+    ("uf", 0.3),
+    ("rg", 0.3),
+    ("dn", 0.3),
+    ("md", 0.3),
+    ("mae", 0.3),
+    ("pai", 0.3),
+    ("cel", 0.3),
+    ("sus", 0.4),
+    ("kin", 0.4),
+    ("msp", 0.4),
+    ("vin", 0.4),
+    ("tel", 0.6),
+    ("sin", 0.6),
+];
+
+/// PHI matches below this confidence only warn
+const MIN_PHI_CONFIDENCE: f64 = 0.5;
+
+/// A pattern matched by a column name
+#[derive(Debug, Clone, PartialEq)]
+pub struct NameMatch {
+    pub pattern: String,
+    pub classification: Classification,
+    /// 1.0 unless the pattern is a short, ambiguous token inside a longer name
+    pub confidence: f64,
+}
+
 /// Result of checking a column name for PHI patterns
 #[derive(Debug, Clone, PartialEq)]
 pub struct ColumnNameResult {
     pub classification: Classification,
     pub matched_pattern: Option<String>,
     pub warning: Option<String>,
+    /// Every pattern the name matched, in pattern order
+    pub matches: Vec<NameMatch>,
 }
 
 impl ColumnNameResult {
@@ -227,6 +261,7 @@ impl ColumnNameResult {
             classification: Classification::Safe,
             matched_pattern: None,
             warning: None,
+            matches: Vec::new(),
         }
     }
 
@@ -238,6 +273,7 @@ impl ColumnNameResult {
                 "Column name matches PHI pattern '{}'; values suppressed",
                 pattern
             )),
+            matches: Vec::new(),
         }
     }
 
@@ -249,6 +285,7 @@ impl ColumnNameResult {
                 "Column name matches site-identifying pattern '{}'; values will be recoded",
                 pattern
             )),
+            matches: Vec::new(),
         }
     }
 
@@ -260,37 +297,104 @@ impl ColumnNameResult {
                 "Column name matches potentially sensitive pattern '{}'; review recommended",
                 pattern
             )),
+            matches: Vec::new(),
         }
     }
-}
-
-/// Check a column name for PHI patterns
-pub fn check_column_name(name: &str) -> ColumnNameResult {
-    let name_lower = name.to_lowercase();
-    let name_normalized = normalize_column_name(&name_lower);
 
-    // Check PHI patterns first (most restrictive - suppress)
-    for pattern in PHI_PATTERNS {
-        if matches_pattern(&name_normalized, pattern) {
-            return ColumnNameResult::phi(pattern);
+    /// A PHI pattern matched too weakly to suppress the column
+    fn weak_phi(pattern: &str, confidence: f64) -> Self {
+        Self {
+            classification: Classification::Warning,
+            matched_pattern: Some(pattern.to_string()),
+            warning: Some(format!(
+                "Column name may match PHI pattern '{}' (confidence {:.2}); review recommended",
+                pattern, confidence
+            )),
+            matches: Vec::new(),
         }
     }
+}
 
-    // Check recode patterns (anonymize but preserve)
-    for pattern in PHI_RECODE {
-        if matches_pattern(&name_normalized, pattern) {
-            return ColumnNameResult::recode(pattern);
-        }
+/// Check a column name for PHI patterns, including those of the given locale packs
+pub fn check_column_name(name: &str, packs: &[&LocalePack]) -> ColumnNameResult {
+    if let Some(result) = super::plugins::check_column_name_rules(name) {
+        return result;
     }
 
-    // Check warning-only patterns
-    for pattern in PHI_WARN_ONLY {
-        if matches_pattern(&name_normalized, pattern) {
-            return ColumnNameResult::warning(pattern);
+    // Accents are dropped so `prénom` and `dirección` match their patterns
+    let name_lower = fold_accents(&name.to_lowercase());
+    let name_normalized = normalize_column_name(&name_lower);
+
+    let mut matches = Vec::new();
+    let mut collect = |classification: Classification, patterns: &mut dyn Iterator<Item = &&str>| {
+        for pattern in patterns {
+            if let Some(confidence) = match_confidence(&name_normalized, pattern) {
+                matches.push(NameMatch {
+                    pattern: pattern.to_string(),
+                    classification: classification.clone(),
+                    confidence,
+                });
+            }
+        }
+    };
+    collect(
+        Classification::Phi,
+        &mut PHI_PATTERNS.iter().chain(packs.iter().flat_map(|p| p.phi)),
+    );
+    collect(
+        Classification::Recode,
+        &mut PHI_RECODE.iter().chain(packs.iter().flat_map(|p| p.recode)),
+    );
+    collect(
+        Classification::Warning,
+        &mut PHI_WARN_ONLY.iter().chain(packs.iter().flat_map(|p| p.warn)),
+    );
+
+    // PHI first (most restrictive - suppress), then recode (anonymize but preserve),
+    // then weak PHI and warning-only patterns
+    let first = |classification: Classification, min_confidence: f64| {
+        matches
+            .iter()
+            .find(|m| m.classification == classification && m.confidence >= min_confidence)
+    };
+    let mut result = if let Some(m) = first(Classification::Phi, MIN_PHI_CONFIDENCE) {
+        ColumnNameResult::phi(&m.pattern)
+    } else if let Some(m) = first(Classification::Recode, 0.0) {
+        ColumnNameResult::recode(&m.pattern)
+    } else if let Some(m) = first(Classification::Phi, 0.0) {
+        ColumnNameResult::weak_phi(&m.pattern, m.confidence)
+    } else if let Some(m) = first(Classification::Warning, 0.0) {
+        ColumnNameResult::warning(&m.pattern)
+    } else {
+        return ColumnNameResult::safe();
+    };
+
+    if matches.len() > 1 {
+        let listed: Vec<String> = matches
+            .iter()
+            .map(|m| {
+                let class = m.classification.as_str();
+                format!("'{}' ({}, {:.2})", m.pattern, class, m.confidence)
+            })
+            .collect();
+        if let Some(warning) = result.warning.as_mut() {
+            warning.push_str(&format!("; all matches: {}", listed.join(", ")));
         }
     }
+    result.matches = matches;
+    result
+}
 
-    ColumnNameResult::safe()
+/// Confidence that a normalized name refers to a pattern, if it matches at all
+fn match_confidence(normalized_name: &str, pattern: &str) -> Option<f64> {
+    if !matches_pattern(normalized_name, pattern) {
+        return None;
+    }
+    if normalized_name == pattern {
+        return Some(1.0);
+    }
+    let weight = WEAK_PATTERNS.iter().find(|(weak, _)| *weak == pattern).map(|(_, w)| *w);
+    Some(weight.unwrap_or(1.0))
 }
 
 /// Normalize a column name for pattern matching
@@ -316,7 +420,8 @@ fn matches_pattern(normalized_name: &str, pattern: &str) -> bool {
         return true;
     }
 
-    // Check for word boundary matches
+    // Check for word boundary matches, so short tokens such as `uf` never
+    // match inside a word (`fluffy`, `ufc`)
     // Pattern appears at start, end, or surrounded by underscores
     let parts: Vec<&str> = normalized_name.split('_').collect();
     for part in parts {
@@ -341,68 +446,68 @@ mod tests {
 
     #[test]
     fn test_exact_phi_match() {
-        let result = check_column_name("name");
+        let result = check_column_name("name", &[]);
         assert_eq!(result.classification, Classification::Phi);
         assert_eq!(result.matched_pattern, Some("name".to_string()));
     }
 
     #[test]
     fn test_phi_with_prefix() {
-        let result = check_column_name("patient_name");
+        let result = check_column_name("patient_name", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_with_suffix() {
-        let result = check_column_name("name_first");
+        let result = check_column_name("name_first", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_case_insensitive() {
-        let result = check_column_name("PATIENT_NAME");
+        let result = check_column_name("PATIENT_NAME", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_mrn() {
-        let result = check_column_name("mrn");
+        let result = check_column_name("mrn", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_ssn() {
-        let result = check_column_name("ssn");
+        let result = check_column_name("ssn", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_email() {
-        let result = check_column_name("email");
+        let result = check_column_name("email", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_phone() {
-        let result = check_column_name("phone_number");
+        let result = check_column_name("phone_number", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_address() {
-        let result = check_column_name("home_address");
+        let result = check_column_name("home_address", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_dob() {
-        let result = check_column_name("dob");
+        let result = check_column_name("dob", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_birth() {
-        let result = check_column_name("date_of_birth");
+        let result = check_column_name("date_of_birth", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
@@ -410,161 +515,161 @@ mod tests {
     fn test_warning_id() {
         // Note: "subject_id" now matches PHI because "subject" is a PHI pattern
         // Use a non-PHI column with "id" to test warning
-        let result = check_column_name("record_id");
+        let result = check_column_name("record_id", &[]);
         assert_eq!(result.classification, Classification::Warning);
         assert_eq!(result.matched_pattern, Some("id".to_string()));
     }
 
     #[test]
     fn test_safe_column() {
-        let result = check_column_name("age");
+        let result = check_column_name("age", &[]);
         assert_eq!(result.classification, Classification::Safe);
         assert!(result.matched_pattern.is_none());
     }
 
     #[test]
     fn test_safe_treatment() {
-        let result = check_column_name("treatment_group");
+        let result = check_column_name("treatment_group", &[]);
         assert_eq!(result.classification, Classification::Safe);
     }
 
     #[test]
     fn test_safe_dose() {
-        let result = check_column_name("dose_mg");
+        let result = check_column_name("dose_mg", &[]);
         assert_eq!(result.classification, Classification::Safe);
     }
 
     #[test]
     fn test_phi_with_dashes() {
-        let result = check_column_name("patient-name");
+        let result = check_column_name("patient-name", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_with_spaces() {
-        let result = check_column_name("patient name");
+        let result = check_column_name("patient name", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     // Canadian health identifiers
     #[test]
     fn test_phi_phn() {
-        let result = check_column_name("phn");
+        let result = check_column_name("phn", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_ohip() {
-        let result = check_column_name("ohip_number");
+        let result = check_column_name("ohip_number", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_sin() {
-        let result = check_column_name("sin");
+        let result = check_column_name("sin", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_healthcard() {
-        let result = check_column_name("health_card");
+        let result = check_column_name("health_card", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     // Name variants
     #[test]
     fn test_phi_first_name() {
-        let result = check_column_name("first_name");
+        let result = check_column_name("first_name", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_last_name() {
-        let result = check_column_name("last_name");
+        let result = check_column_name("last_name", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_fname() {
-        let result = check_column_name("fname");
+        let result = check_column_name("fname", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_surname() {
-        let result = check_column_name("surname");
+        let result = check_column_name("surname", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_initials() {
-        let result = check_column_name("patient_initials");
+        let result = check_column_name("patient_initials", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     // Abbreviated forms
     #[test]
     fn test_phi_pt_prefix() {
-        let result = check_column_name("pt_name");
+        let result = check_column_name("pt_name", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_subject_abbreviated() {
-        let result = check_column_name("subj_id");
+        let result = check_column_name("subj_id", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     // French forms
     #[test]
     fn test_phi_nom_patient() {
-        let result = check_column_name("nom_patient");
+        let result = check_column_name("nom_patient", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     // Emergency contacts
     #[test]
     fn test_phi_next_of_kin() {
-        let result = check_column_name("next_of_kin");
+        let result = check_column_name("next_of_kin", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_emergency_contact() {
-        let result = check_column_name("emergency_contact");
+        let result = check_column_name("emergency_contact", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_guarantor() {
-        let result = check_column_name("guarantor");
+        let result = check_column_name("guarantor", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     // Chart numbers
     #[test]
     fn test_phi_chart() {
-        let result = check_column_name("chart_number");
+        let result = check_column_name("chart_number", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     // Warning patterns
     #[test]
     fn test_warning_encounter() {
-        let result = check_column_name("encounter_id");
+        let result = check_column_name("encounter_id", &[]);
         assert_eq!(result.classification, Classification::Warning);
     }
 
     #[test]
     fn test_warning_visit() {
-        let result = check_column_name("visit_id");
+        let result = check_column_name("visit_id", &[]);
         assert_eq!(result.classification, Classification::Warning);
     }
 
     // Recode patterns (site should still be recoded, not PHI)
     #[test]
     fn test_recode_site() {
-        let result = check_column_name("site_code");
+        let result = check_column_name("site_code", &[]);
         assert_eq!(result.classification, Classification::Recode);
     }
 
@@ -572,85 +677,85 @@ mod tests {
 
     #[test]
     fn test_phi_nom_famille() {
-        let result = check_column_name("nom_famille");
+        let result = check_column_name("nom_famille", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_prenom() {
-        let result = check_column_name("prenom");
+        let result = check_column_name("prenom", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_adresse() {
-        let result = check_column_name("adresse");
+        let result = check_column_name("adresse", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_courriel() {
-        let result = check_column_name("courriel");
+        let result = check_column_name("courriel", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_telephone() {
-        let result = check_column_name("telephone");
+        let result = check_column_name("telephone", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_naissance() {
-        let result = check_column_name("date_naissance");
+        let result = check_column_name("date_naissance", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_ddn() {
-        let result = check_column_name("ddn");
+        let result = check_column_name("ddn", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_nas() {
-        let result = check_column_name("nas");
+        let result = check_column_name("nas", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_nam() {
-        let result = check_column_name("nam");
+        let result = check_column_name("nam", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_ramq() {
-        let result = check_column_name("numero_ramq");
+        let result = check_column_name("numero_ramq", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_medecin() {
-        let result = check_column_name("medecin_traitant");
+        let result = check_column_name("medecin_traitant", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_recode_hopital() {
-        let result = check_column_name("hopital");
+        let result = check_column_name("hopital", &[]);
         assert_eq!(result.classification, Classification::Recode);
     }
 
     #[test]
     fn test_recode_clinique() {
-        let result = check_column_name("clinique");
+        let result = check_column_name("clinique", &[]);
         assert_eq!(result.classification, Classification::Recode);
     }
 
     #[test]
     fn test_recode_centre() {
-        let result = check_column_name("centre_hospitalier");
+        let result = check_column_name("centre_hospitalier", &[]);
         assert_eq!(result.classification, Classification::Recode);
     }
 
@@ -658,106 +763,106 @@ mod tests {
 
     #[test]
     fn test_phi_nome() {
-        let result = check_column_name("nome_paciente");
+        let result = check_column_name("nome_paciente", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_sobrenome() {
-        let result = check_column_name("sobrenome");
+        let result = check_column_name("sobrenome", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_cpf() {
         // CPF is THE critical identifier in Brazil
-        let result = check_column_name("cpf");
+        let result = check_column_name("cpf", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_rg() {
-        let result = check_column_name("rg");
+        let result = check_column_name("rg", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_sus() {
-        let result = check_column_name("cartao_sus");
+        let result = check_column_name("cartao_sus", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_cns() {
-        let result = check_column_name("cns");
+        let result = check_column_name("cns", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_endereco() {
-        let result = check_column_name("endereco");
+        let result = check_column_name("endereco", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_cep() {
-        let result = check_column_name("cep");
+        let result = check_column_name("cep", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_telefone() {
-        let result = check_column_name("telefone");
+        let result = check_column_name("telefone", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_celular() {
-        let result = check_column_name("celular");
+        let result = check_column_name("celular", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_nascimento() {
-        let result = check_column_name("data_nascimento");
+        let result = check_column_name("data_nascimento", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_dt_nasc() {
-        let result = check_column_name("dt_nasc");
+        let result = check_column_name("dt_nasc", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_nome_mae() {
         // Mother's name is critical for ID in Brazil
-        let result = check_column_name("nome_mae");
+        let result = check_column_name("nome_mae", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_nome_pai() {
-        let result = check_column_name("nome_pai");
+        let result = check_column_name("nome_pai", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_medico() {
-        let result = check_column_name("medico");
+        let result = check_column_name("medico", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_prontuario() {
-        let result = check_column_name("prontuario");
+        let result = check_column_name("prontuario", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_recode_hospital_pt() {
         // "hospital" is same in Portuguese
-        let result = check_column_name("hospital");
+        let result = check_column_name("hospital", &[]);
         assert_eq!(result.classification, Classification::Recode);
     }
 
@@ -766,154 +871,231 @@ mod tests {
     // HIPAA #3: Additional dates
     #[test]
     fn test_phi_admission_date() {
-        let result = check_column_name("admission_date");
+        let result = check_column_name("admission_date", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_discharge_date() {
-        let result = check_column_name("discharge_date");
+        let result = check_column_name("discharge_date", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_death_date() {
-        let result = check_column_name("date_of_death");
+        let result = check_column_name("date_of_death", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     // HIPAA #9: Health plan beneficiary numbers
     #[test]
     fn test_phi_insurance() {
-        let result = check_column_name("insurance_id");
+        let result = check_column_name("insurance_id", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_policy_number() {
-        let result = check_column_name("policy_number");
+        let result = check_column_name("policy_number", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_beneficiary() {
-        let result = check_column_name("beneficiary_id");
+        let result = check_column_name("beneficiary_id", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     // HIPAA #10: Account numbers
     #[test]
     fn test_phi_account() {
-        let result = check_column_name("account_number");
+        let result = check_column_name("account_number", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_billing() {
-        let result = check_column_name("billing_id");
+        let result = check_column_name("billing_id", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     // HIPAA #11: Certificate/license numbers
     #[test]
     fn test_phi_license() {
-        let result = check_column_name("license_number");
+        let result = check_column_name("license_number", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_certificate() {
-        let result = check_column_name("certificate_id");
+        let result = check_column_name("certificate_id", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     // HIPAA #12: Vehicle identifiers
     #[test]
     fn test_phi_vin() {
-        let result = check_column_name("vin");
+        let result = check_column_name("vin", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_license_plate() {
-        let result = check_column_name("license_plate");
+        let result = check_column_name("license_plate", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     // HIPAA #13: Device identifiers
     #[test]
     fn test_phi_serial_number() {
-        let result = check_column_name("serial_number");
+        let result = check_column_name("serial_number", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_device_id() {
-        let result = check_column_name("device_id");
+        let result = check_column_name("device_id", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_imei() {
-        let result = check_column_name("imei");
+        let result = check_column_name("imei", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_mac_address() {
-        let result = check_column_name("mac_address");
+        let result = check_column_name("mac_address", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     // HIPAA #14: Web URLs
     #[test]
     fn test_phi_url() {
-        let result = check_column_name("profile_url");
+        let result = check_column_name("profile_url", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_website() {
-        let result = check_column_name("website");
+        let result = check_column_name("website", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     // HIPAA #15: IP addresses
     #[test]
     fn test_phi_ip_address() {
-        let result = check_column_name("ip_address");
+        let result = check_column_name("ip_address", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     // HIPAA #16: Biometric identifiers
     #[test]
     fn test_phi_fingerprint() {
-        let result = check_column_name("fingerprint");
+        let result = check_column_name("fingerprint", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_biometric() {
-        let result = check_column_name("biometric_data");
+        let result = check_column_name("biometric_data", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     // HIPAA #17: Photographs
     #[test]
     fn test_phi_photo() {
-        let result = check_column_name("patient_photo");
+        let result = check_column_name("patient_photo", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_photograph() {
-        let result = check_column_name("photograph");
+        let result = check_column_name("photograph", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
 
     #[test]
     fn test_phi_headshot() {
-        let result = check_column_name("headshot");
+        let result = check_column_name("headshot", &[]);
         assert_eq!(result.classification, Classification::Phi);
     }
+
+    #[test]
+    fn test_all_matches_reported() {
+        let result = check_column_name("patient_name", &[]);
+        assert_eq!(result.matched_pattern.as_deref(), Some("name"));
+        let patterns: Vec<&str> = result.matches.iter().map(|m| m.pattern.as_str()).collect();
+        assert_eq!(patterns, ["name", "patient"]);
+        assert!(result.warning.unwrap().contains("all matches: 'name' (phi, 1.00)"));
+
+        let result = check_column_name("site_id", &[]);
+        assert_eq!(result.classification, Classification::Recode);
+        assert_eq!(result.matches.len(), 2);
+        assert_eq!(result.matches[1].classification, Classification::Warning);
+
+        assert!(check_column_name("age", &[]).matches.is_empty());
+        assert_eq!(check_column_name("email", &[]).matches.len(), 1);
+    }
+
+    #[test]
+    fn test_short_token_confidence() {
+        // A whole-name match is certain
+        let result = check_column_name("UF", &[]);
+        assert_eq!(result.classification, Classification::Phi);
+        assert_eq!(result.matches[0].confidence, 1.0);
+
+        // Inside a longer name it only warns
+        let result = check_column_name("uf_volume_ml", &[]);
+        assert_eq!(result.classification, Classification::Warning);
+        assert_eq!(result.matched_pattern.as_deref(), Some("uf"));
+        assert!(result.matches[0].confidence < MIN_PHI_CONFIDENCE);
+        assert!(result.warning.unwrap().contains("confidence 0.30"));
+
+        // A stronger pattern in the same name still suppresses
+        let result = check_column_name("nome_mae", &[]);
+        assert_eq!(result.classification, Classification::Phi);
+        assert_eq!(result.matched_pattern.as_deref(), Some("nome"));
+
+        // Recode wins over a weak PHI match
+        let result = check_column_name("rg_site", &[]);
+        assert_eq!(result.classification, Classification::Recode);
+
+        // Short tokens need word boundaries
+        assert_eq!(check_column_name("rgb_value", &[]).classification, Classification::Safe);
+        assert_eq!(check_column_name("fluffy", &[]).classification, Classification::Safe);
+    }
+
+    #[test]
+    fn test_locale_packs() {
+        let spanish = [&super::super::locales::es::PACK];
+        assert_eq!(check_column_name("apellidos", &[]).classification, Classification::Safe);
+        assert_eq!(check_column_name("apellidos", &spanish).classification, Classification::Phi);
+        assert_eq!(
+            check_column_name("Dirección", &spanish).matched_pattern.as_deref(),
+            Some("direccion")
+        );
+        assert_eq!(check_column_name("clinica", &spanish).classification, Classification::Recode);
+        assert_eq!(check_column_name("prénom", &[]).classification, Classification::Phi);
+    }
+
+    #[test]
+    fn test_german_pack() {
+        let german = [&super::super::locales::de::PACK];
+        assert_eq!(check_column_name("Geburtsdatum", &[]).classification, Classification::Safe);
+        for name in ["Geburtsdatum", "Vorname", "Versichertennummer", "Straße", "PLZ", "Ärztin"] {
+            let result = check_column_name(name, &german);
+            assert_eq!(result.classification, Classification::Phi, "{}", name);
+        }
+        let result = check_column_name("Straße", &german);
+        assert_eq!(result.matched_pattern.as_deref(), Some("strasse"));
+        let result = check_column_name("Krankenhaus", &german);
+        assert_eq!(result.classification, Classification::Recode);
+        let result = check_column_name("Besuch", &german);
+        assert_eq!(result.classification, Classification::Warning);
+
+        let spanish = [&super::super::locales::es::PACK];
+        assert_eq!(check_column_name("RFC", &spanish).classification, Classification::Phi);
+    }
 }
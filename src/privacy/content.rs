@@ -0,0 +1,138 @@
+//! Second-stage check of name-based PHI classifications against column content
+//! (`--content-aware`).
+//!
+//! A column called `name` that only holds "Treatment A", "Treatment B" and
+//! "Treatment C" is a grouping variable, not a list of patients. When the values
+//! contradict the name, the column is downgraded from PHI to a warning.
+
+use std::collections::HashMap;
+
+use super::column_names::ColumnNameResult;
+use super::value_patterns::check_value_pattern_in;
+use crate::types::{Classification, DType, Language};
+
+/// Most distinct values a column may have for its content to outweigh its name
+pub const MAX_CONTENT_LEVELS: usize = 10;
+
+/// What was seen in a column, for checking its name-based classification
+pub struct ContentEvidence<'a> {
+    pub dtype: DType,
+    /// Distinct values with their counts; `None` once the column became high-cardinality
+    pub value_counts: Option<&'a HashMap<String, u64>>,
+    /// Cells that matched a PHI value pattern during a deep scan
+    pub pattern_hits: u64,
+    pub k_anonymity: u64,
+    pub languages: &'a [Language],
+}
+
+/// Why the content contradicts a PHI column name, if it does.
+///
+/// Only classifications from name patterns are questioned (not CDISC, plugins
+/// or reviewer overrides), and only when the column is a handful of categories,
+/// each seen at least k times, none of which looks like PHI. Dates and free
+/// text are always taken at their name's word.
+pub fn contradicts_name(
+    name_check: &ColumnNameResult,
+    evidence: &ContentEvidence,
+) -> Option<String> {
+    if name_check.classification != Classification::Phi || name_check.matches.is_empty() {
+        return None;
+    }
+    if matches!(
+        evidence.dtype,
+        DType::Date | DType::Datetime | DType::FreeText
+    ) || evidence.pattern_hits > 0
+    {
+        return None;
+    }
+    let counts = evidence.value_counts?;
+    if counts.is_empty() || counts.len() > MAX_CONTENT_LEVELS {
+        return None;
+    }
+    if counts.values().any(|&n| n < evidence.k_anonymity) {
+        return None;
+    }
+    if counts
+        .keys()
+        .any(|value| check_value_pattern_in(value, evidence.languages).is_phi)
+    {
+        return None;
+    }
+    Some(format!(
+        "Column name matches PHI pattern '{}' but its content does not look like PHI \
+         ({} distinct values, each seen at least {} times, none matching PHI value \
+         patterns); downgraded to warning",
+        name_check.matched_pattern.as_deref().unwrap_or_default(),
+        counts.len(),
+        evidence.k_anonymity
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::privacy::check_column_name;
+
+    fn counts(values: &[(&str, u64)]) -> HashMap<String, u64> {
+        values.iter().map(|&(v, n)| (v.to_string(), n)).collect()
+    }
+
+    fn evidence<'a>(dtype: DType, counts: &'a HashMap<String, u64>) -> ContentEvidence<'a> {
+        ContentEvidence {
+            dtype,
+            value_counts: Some(counts),
+            pattern_hits: 0,
+            k_anonymity: 5,
+            languages: &[],
+        }
+    }
+
+    #[test]
+    fn test_categories_contradict_name() {
+        let name = check_column_name("name", &[]);
+        let treatments = counts(&[("Treatment A", 12), ("Treatment B", 9), ("Treatment C", 7)]);
+        let reason = contradicts_name(&name, &evidence(DType::String, &treatments)).unwrap();
+        assert!(reason.contains("'name'"));
+        assert!(reason.contains("3 distinct values"));
+    }
+
+    #[test]
+    fn test_content_consistent_with_name() {
+        let name = check_column_name("name", &[]);
+
+        // Rare values could be anyone
+        let rare = counts(&[("Treatment A", 12), ("Treatment B", 2)]);
+        assert!(contradicts_name(&name, &evidence(DType::String, &rare)).is_none());
+
+        // Person names, even repeated across visits
+        let people = counts(&[("John Smith", 6), ("Maria Garcia", 6)]);
+        assert!(contradicts_name(&name, &evidence(DType::String, &people)).is_none());
+
+        // Dates are what a date-of-birth column should hold
+        let dob = check_column_name("dob", &[]);
+        let dates = counts(&[("2001-01-01", 8)]);
+        assert!(contradicts_name(&dob, &evidence(DType::Date, &dates)).is_none());
+
+        // Deep-scan hits and high cardinality
+        let levels = counts(&[("A", 8), ("B", 8)]);
+        let hits = ContentEvidence {
+            pattern_hits: 1,
+            ..evidence(DType::String, &levels)
+        };
+        assert!(contradicts_name(&name, &hits).is_none());
+        let many = ContentEvidence {
+            value_counts: None,
+            ..evidence(DType::String, &levels)
+        };
+        assert!(contradicts_name(&name, &many).is_none());
+
+        // Only PHI classifications from name patterns are questioned
+        let site = check_column_name("site", &[]);
+        assert!(contradicts_name(&site, &evidence(DType::String, &levels)).is_none());
+        let cdisc = ColumnNameResult {
+            matches: Vec::new(),
+            ..ColumnNameResult::phi("cdisc:BRTHDTC")
+        };
+        assert!(contradicts_name(&cdisc, &evidence(DType::String, &levels)).is_none());
+    }
+}
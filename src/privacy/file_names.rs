@@ -0,0 +1,76 @@
+//! PHI in the names of files and sheets.
+//!
+//! Exports are often named after whom they are about: `Smith_John_labs.xlsx`,
+//! a sheet called `Dr. Tremblay patients`. The manifest repeats those names, so
+//! they are checked against the value patterns and the name lists like any value.
+
+use super::name_lists::{is_likely_name, is_person_name_pair};
+use super::value_patterns::check_value_pattern_in;
+use crate::types::Language;
+
+/// Words that introduce a person's name
+const TITLES: &[&str] = &[
+    "dr", "dra", "doctor", "prof", "mr", "mrs", "ms", "mme", "mlle", "sr", "sra", "herr", "frau",
+];
+
+/// The PHI pattern a file or sheet name matches, if any.
+///
+/// The name is split into words at underscores, spaces and brackets; a word
+/// matching a value pattern (email, phone, SSN, ...) counts, as do two adjacent
+/// words forming a first name and a surname, and a title followed by a known
+/// name. A single first name or surname alone does not: too many ordinary
+/// words are also someone's name.
+pub fn find_phi_in_name(name: &str, languages: &[Language]) -> Option<&'static str> {
+    let words: Vec<&str> = name
+        .split(|c: char| c == '_' || c.is_whitespace() || "()[],".contains(c))
+        .filter(|w| !w.is_empty())
+        .collect();
+
+    let pattern = words.iter().find_map(|word| {
+        let result = check_value_pattern_in(word, languages);
+        result
+            .matched_pattern
+            .filter(|&p| result.is_phi && p != "name")
+    });
+    if pattern.is_some() {
+        return pattern;
+    }
+
+    let names: Vec<&str> = words.iter().map(|w| w.trim_end_matches('.')).collect();
+    let named = names.windows(2).any(|pair| {
+        let titled = TITLES.contains(&pair[0].to_lowercase().as_str()) && is_likely_name(pair[1]);
+        titled || is_person_name_pair(pair[0], pair[1]) || is_person_name_pair(pair[1], pair[0])
+    });
+    named.then_some("name")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names_with_phi() {
+        assert_eq!(find_phi_in_name("Smith_John_labs", &[]), Some("name"));
+        assert_eq!(find_phi_in_name("Dr. Tremblay patients", &[]), Some("name"));
+        assert_eq!(find_phi_in_name("labs (Maria Garcia)", &[]), Some("name"));
+        assert_eq!(find_phi_in_name("export_123-45-6789", &[]), Some("ssn"));
+        assert_eq!(
+            find_phi_in_name("jsmith@example.com notes", &[]),
+            Some("email")
+        );
+    }
+
+    #[test]
+    fn test_names_without_phi() {
+        for name in [
+            "trial_labs_2024",
+            "Baseline visits",
+            "Sheet1",
+            "ADSL",
+            "Smith",
+            "AE",
+        ] {
+            assert_eq!(find_phi_in_name(name, &[]), None, "{}", name);
+        }
+    }
+}
@@ -0,0 +1,74 @@
+//! German column names (Germany, Austria, Switzerland).
+//!
+//! Patterns are written the way names are matched: lowercase with umlauts
+//! folded (`ärztin` becomes `arztin`) and `ß` spelled `ss`.
+
+use super::LocalePack;
+
+pub const PACK: LocalePack = LocalePack {
+    phi: &[
+        // ===== NAMES =====
+        "vorname",      // first name
+        "nachname",     // last name
+        "familienname", // family name
+        "geburtsname",  // birth name
+        "patientenname",
+        // ===== IDENTIFIERS =====
+        "versichertennummer",        // health insurance member number
+        "versicherungsnummer",       // insurance number
+        "krankenversichertennummer", // statutory health insurance number
+        "kvnr",                      // Krankenversichertennummer
+        "sozialversicherungsnummer", // social insurance number
+        "svnr",                      // Sozialversicherungsnummer (Austria)
+        "ahv",                       // AHV number (Switzerland)
+        "steuer_id",                 // tax ID
+        "steuernummer",              // tax number
+        "personalausweis",           // ID card
+        "ausweisnummer",             // ID card number
+        "reisepass",                 // passport
+        "patientennummer",           // patient number
+        "fallnummer",                // case number
+        // ===== DATES =====
+        "geburtsdatum", // date of birth
+        "geb_datum",
+        "geburtstag",  // birthday
+        "sterbedatum", // date of death
+        "todesdatum",
+        "aufnahmedatum",    // admission date
+        "entlassdatum",     // discharge date
+        "entlassungsdatum", // discharge date
+        // ===== ADDRESS =====
+        "adresse",
+        "anschrift", // address
+        "strasse",   // street
+        "wohnort",   // place of residence
+        "plz",       // Postleitzahl
+        "postleitzahl",
+        // ===== CONTACT =====
+        "telefon",
+        "telefonnummer",
+        "handy", // mobile
+        "mobil",
+        "e_mail",
+        // ===== HEALTHCARE PROVIDERS =====
+        "arzt",     // physician
+        "arztin",   // physician (f)
+        "hausarzt", // family doctor
+    ],
+    recode: &[
+        "klinik",      // clinic
+        "krankenhaus", // hospital
+        "klinikum",    // hospital
+        "zentrum",     // center
+        "standort",    // location
+        "einrichtung", // facility
+        "praxis",      // practice
+    ],
+    warn: &[
+        "nummer",   // number
+        "kennung",  // identifier
+        "besuch",   // visit
+        "aufnahme", // admission
+        "fall",     // case
+    ],
+};
@@ -0,0 +1,51 @@
+//! Spanish column names (Spain, Mexico, and other Spanish-speaking sites)
+
+use super::LocalePack;
+
+pub const PACK: LocalePack = LocalePack {
+    phi: &[
+        // ===== NAMES =====
+        "nombre",
+        "nombres",
+        "apellido",
+        "apellidos",
+        "nombre_paciente",
+        // ===== IDENTIFIERS =====
+        "dni",              // Documento Nacional de Identidad (Spain)
+        "nie",              // Número de Identidad de Extranjero (Spain)
+        "curp",             // Clave Única de Registro de Población (Mexico)
+        "rfc",              // Registro Federal de Contribuyentes (Mexico)
+        "pasaporte",        // passport
+        "nss",              // Número de Seguridad Social
+        "historia_clinica", // medical record
+        "nhc",              // número de historia clínica
+        // ===== DATES =====
+        "nacimiento", // birth
+        "fecha_nacimiento",
+        "fecha_nac",
+        "fallecimiento", // death
+        "defuncion",     // death
+        // ===== ADDRESS =====
+        "direccion",
+        "domicilio",
+        "calle",     // street
+        "ciudad",    // city
+        "localidad", // town
+        "codigo_postal",
+        // ===== CONTACT =====
+        "telefono",
+        "movil",  // mobile
+        "correo", // email
+    ],
+    recode: &[
+        "clinica",         // clinic
+        "centro",          // center
+        "establecimiento", // facility
+    ],
+    warn: &[
+        "codigo",  // code
+        "numero",  // number
+        "visita",  // visit
+        "ingreso", // admission
+    ],
+};
@@ -0,0 +1,445 @@
+//! Column-name language detection and optional locale pattern packs.
+//!
+//! English, French, and Portuguese patterns are built into `column_names`
+//! and always checked; other languages are packs enabled with `--locales`.
+
+pub mod de;
+pub mod es;
+
+use crate::types::Language;
+
+/// Column name patterns for one language
+pub struct LocalePack {
+    /// Suppress values
+    pub phi: &'static [&'static str],
+    /// Recode values
+    pub recode: &'static [&'static str],
+    /// Warn only
+    pub warn: &'static [&'static str],
+}
+
+/// The optional pattern pack for a language; `None` for built-in languages
+pub fn pack(language: Language) -> Option<&'static LocalePack> {
+    match language {
+        Language::Es => Some(&es::PACK),
+        Language::De => Some(&de::PACK),
+        Language::En | Language::Fr | Language::Pt => None,
+    }
+}
+
+/// Common clinical-data words, used to tell the languages apart.
+/// Words shared by several languages (`data`, `sexo`) count for each.
+const VOCABULARY: &[(Language, &[&str])] = &[
+    (
+        Language::En,
+        &[
+            "age",
+            "sex",
+            "gender",
+            "weight",
+            "height",
+            "date",
+            "birth",
+            "death",
+            "name",
+            "first",
+            "last",
+            "patient",
+            "subject",
+            "visit",
+            "treatment",
+            "dose",
+            "result",
+            "status",
+            "baseline",
+            "follow",
+            "up",
+            "outcome",
+            "hospital",
+            "admission",
+            "discharge",
+            "address",
+            "city",
+            "phone",
+            "number",
+            "day",
+            "month",
+            "year",
+            "week",
+            "group",
+            "arm",
+            "score",
+            "blood",
+            "pressure",
+            "heart",
+            "rate",
+            "adverse",
+            "event",
+        ],
+    ),
+    (
+        Language::Fr,
+        &[
+            "sexe",
+            "poids",
+            "taille",
+            "naissance",
+            "deces",
+            "nom",
+            "prenom",
+            "patient",
+            "visite",
+            "traitement",
+            "dose",
+            "resultat",
+            "statut",
+            "suivi",
+            "hopital",
+            "adresse",
+            "ville",
+            "telephone",
+            "courriel",
+            "numero",
+            "jour",
+            "mois",
+            "annee",
+            "semaine",
+            "groupe",
+            "bras",
+            "tension",
+            "arterielle",
+            "frequence",
+            "cardiaque",
+            "evenement",
+            "indesirable",
+            "medecin",
+            "infirmiere",
+            "sortie",
+            "entree",
+            "de",
+            "du",
+            "des",
+            "le",
+            "la",
+        ],
+    ),
+    (
+        Language::Pt,
+        &[
+            "idade",
+            "sexo",
+            "peso",
+            "altura",
+            "data",
+            "nascimento",
+            "obito",
+            "nome",
+            "sobrenome",
+            "paciente",
+            "visita",
+            "tratamento",
+            "dose",
+            "resultado",
+            "situacao",
+            "seguimento",
+            "hospital",
+            "internacao",
+            "alta",
+            "endereco",
+            "cidade",
+            "telefone",
+            "numero",
+            "dia",
+            "mes",
+            "ano",
+            "semana",
+            "grupo",
+            "braco",
+            "pressao",
+            "arterial",
+            "frequencia",
+            "cardiaca",
+            "evento",
+            "adverso",
+            "medico",
+            "mae",
+            "de",
+            "do",
+            "da",
+        ],
+    ),
+    (
+        Language::Es,
+        &[
+            "edad",
+            "sexo",
+            "peso",
+            "altura",
+            "fecha",
+            "nacimiento",
+            "fallecimiento",
+            "nombre",
+            "apellido",
+            "paciente",
+            "visita",
+            "tratamiento",
+            "dosis",
+            "resultado",
+            "estado",
+            "seguimiento",
+            "hospital",
+            "ingreso",
+            "alta",
+            "direccion",
+            "ciudad",
+            "telefono",
+            "numero",
+            "dia",
+            "mes",
+            "ano",
+            "semana",
+            "grupo",
+            "brazo",
+            "presion",
+            "arterial",
+            "frecuencia",
+            "cardiaca",
+            "evento",
+            "adverso",
+            "medico",
+            "de",
+            "del",
+            "la",
+        ],
+    ),
+    (
+        Language::De,
+        &[
+            "alter",
+            "geschlecht",
+            "gewicht",
+            "grosse",
+            "datum",
+            "geburtsdatum",
+            "sterbedatum",
+            "name",
+            "vorname",
+            "nachname",
+            "patient",
+            "besuch",
+            "behandlung",
+            "dosis",
+            "ergebnis",
+            "verlauf",
+            "klinik",
+            "krankenhaus",
+            "aufnahme",
+            "entlassung",
+            "adresse",
+            "wohnort",
+            "telefon",
+            "nummer",
+            "tag",
+            "monat",
+            "jahr",
+            "woche",
+            "gruppe",
+            "blutdruck",
+            "herzfrequenz",
+            "ereignis",
+            "arzt",
+            "und",
+            "der",
+            "die",
+            "das",
+            "von",
+        ],
+    ),
+];
+
+/// Short function words of running text, used to tell the language of free-text cells
+const STOPWORDS: &[(Language, &[&str])] = &[
+    (
+        Language::En,
+        &[
+            "the", "and", "of", "to", "with", "was", "is", "for", "on", "in", "no", "not", "he",
+            "she", "his", "her", "at", "had", "has", "by", "from", "after", "were", "but",
+        ],
+    ),
+    (
+        Language::Fr,
+        &[
+            "le", "la", "les", "et", "des", "du", "de", "avec", "est", "pour", "dans", "sur",
+            "une", "un", "pas", "il", "elle", "au", "aux", "sans", "apres", "ete", "mais",
+        ],
+    ),
+    (
+        Language::Pt,
+        &[
+            "o", "a", "os", "as", "e", "com", "nao", "que", "do", "da", "dos", "das", "em", "no",
+            "na", "para", "um", "uma", "foi", "sem", "apos", "ao", "pelo", "pela", "mas",
+        ],
+    ),
+    (
+        Language::Es,
+        &[
+            "el", "los", "las", "y", "con", "que", "del", "de", "en", "para", "un", "una", "fue",
+            "sin", "por", "se", "al", "no", "es", "despues", "pero",
+        ],
+    ),
+    (
+        Language::De,
+        &[
+            "der", "die", "das", "und", "mit", "von", "ist", "war", "nicht", "ein", "eine", "im",
+            "zu", "auf", "fur", "bei", "nach", "wurde", "aber", "den", "dem", "sich", "keine",
+        ],
+    ),
+];
+
+/// Minimum number of words a language must be ahead of the next one
+const MIN_LEAD: usize = 2;
+
+/// The predominant language of a table's column names, if one clearly leads
+pub fn detect_language(headers: &[String]) -> Option<Language> {
+    leading_language(&words_of(headers), VOCABULARY)
+}
+
+/// The language of a free-text cell, if one clearly leads
+pub fn text_language(text: &str) -> Option<Language> {
+    leading_language(&words_of(&[text]), STOPWORDS)
+}
+
+/// Lowercase, accent-free words of the given texts
+fn words_of<S: AsRef<str>>(texts: &[S]) -> Vec<String> {
+    texts
+        .iter()
+        .flat_map(|t| {
+            fold_accents(&t.as_ref().to_lowercase())
+                .split(|c: char| !c.is_alphanumeric())
+                .filter(|w| !w.is_empty())
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// The language whose word list covers the most words, if it leads by `MIN_LEAD`
+fn leading_language(words: &[String], table: &[(Language, &[&str])]) -> Option<Language> {
+    let mut scores: Vec<(Language, usize)> = table
+        .iter()
+        .map(|(language, vocabulary)| {
+            let hits = words
+                .iter()
+                .filter(|w| vocabulary.contains(&w.as_str()))
+                .count();
+            (*language, hits)
+        })
+        .collect();
+    scores.sort_by_key(|&(_, hits)| std::cmp::Reverse(hits));
+    let (language, best) = scores[0];
+    (best >= scores[1].1 + MIN_LEAD).then_some(language)
+}
+
+/// Drop the accents of Latin letters (`prénom` becomes `prenom`) and spell `ß` as `ss`
+pub fn fold_accents(text: &str) -> String {
+    if text.contains('ß') {
+        return fold_accents(&text.replace('ß', "ss"));
+    }
+    text.chars()
+        .map(|c| match c {
+            'à' | 'á' | 'â' | 'ã' | 'ä' => 'a',
+            'ç' => 'c',
+            'è' | 'é' | 'ê' | 'ë' => 'e',
+            'ì' | 'í' | 'î' | 'ï' => 'i',
+            'ñ' => 'n',
+            'ò' | 'ó' | 'ô' | 'õ' | 'ö' => 'o',
+            'ù' | 'ú' | 'û' | 'ü' => 'u',
+            _ => c,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn test_detect_language() {
+        assert_eq!(
+            detect_language(&headers(&[
+                "patient_id",
+                "age",
+                "sex",
+                "visit_date",
+                "dose_mg"
+            ])),
+            Some(Language::En)
+        );
+        assert_eq!(
+            detect_language(&headers(&[
+                "No patient",
+                "Date de naissance",
+                "Poids",
+                "Sexe"
+            ])),
+            Some(Language::Fr)
+        );
+        assert_eq!(
+            detect_language(&headers(&["nome", "idade", "data_nascimento", "peso"])),
+            Some(Language::Pt)
+        );
+        assert_eq!(
+            detect_language(&headers(&[
+                "Nombre",
+                "Edad",
+                "Fecha de nacimiento",
+                "Dirección"
+            ])),
+            Some(Language::Es)
+        );
+        assert_eq!(
+            detect_language(&headers(&[
+                "Vorname",
+                "Nachname",
+                "Geburtsdatum",
+                "Größe",
+                "Gewicht"
+            ])),
+            Some(Language::De)
+        );
+    }
+
+    #[test]
+    fn test_fold_accents() {
+        assert_eq!(fold_accents("prénom"), "prenom");
+        assert_eq!(fold_accents("straße"), "strasse");
+        assert_eq!(fold_accents("größe"), "grosse");
+    }
+
+    #[test]
+    fn test_undetermined_language() {
+        assert_eq!(detect_language(&headers(&["x1", "x2", "var3"])), None);
+        // `sexo` and `peso` are both Portuguese and Spanish
+        assert_eq!(detect_language(&headers(&["sexo", "peso"])), None);
+        assert_eq!(detect_language(&[]), None);
+    }
+
+    #[test]
+    fn test_text_language() {
+        let en = "Patient was seen in the clinic and had no complaints";
+        assert_eq!(text_language(en), Some(Language::En));
+        let fr = "Le patient est revenu avec des douleurs dans la jambe";
+        assert_eq!(text_language(fr), Some(Language::Fr));
+        let pt = "Paciente nao compareceu, remarcado para a semana que vem";
+        assert_eq!(text_language(pt), Some(Language::Pt));
+        let es = "El paciente fue dado de alta sin complicaciones por la tarde";
+        assert_eq!(text_language(es), Some(Language::Es));
+        let de = "Der Patient wurde nach der Operation mit Schmerzen entlassen";
+        assert_eq!(text_language(de), Some(Language::De));
+        assert_eq!(text_language("stable"), None);
+    }
+}
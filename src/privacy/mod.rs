@@ -1,10 +1,21 @@
 pub mod bucketing;
+pub mod cdisc;
+pub mod code_systems;
 pub mod column_names;
+pub mod content;
+pub mod file_names;
+pub mod locales;
 pub mod name_lists;
+pub mod plugins;
+pub mod quasi_identifiers;
 pub mod recoding;
 pub mod value_patterns;
 
-pub use bucketing::{bucket_count, safe_count};
-pub use column_names::check_column_name;
-pub use recoding::RecodeRegistry;
-pub use value_patterns::check_value_pattern;
+pub use bucketing::{bucket_count, bucket_magnitude, safe_count};
+pub use column_names::{check_column_name, ColumnNameResult};
+pub use plugins::{register_rules, DylibRule, PrivacyRule};
+pub use recoding::{RecodeFormat, RecodeRegistry};
+pub use value_patterns::{
+    check_value_pattern, check_value_pattern_in, count_phi_in_text, find_phi_in_text,
+    register_detectors, CustomDetector,
+};
@@ -45,7 +45,7 @@ impl CombiningMark for char {
 // TOTAL NAMES: 10376
 
 // --- SURNAMES ---
-const SURNAMES: [&str; 1223] = [
+static SURNAMES: [&str; 1223] = [
     "abbott",
     "acevedo",
     "acosta",
@@ -1272,7 +1272,7 @@ const SURNAMES: [&str; 1223] = [
 ];
 
 // --- FIRST NAMES ---
-const FIRST_NAMES: [&str; 9153] = [
+static FIRST_NAMES: [&str; 9153] = [
     "aaden",
     "aakash",
     "aaliyah",
@@ -10490,18 +10490,24 @@ pub fn is_likely_name(value: &str) -> bool {
         if first.chars().all(|c| c.is_alphabetic() || c == '-')
             && last.chars().all(|c| c.is_alphabetic() || c == '-')
             && first.len() >= 2 && last.len() >= 2
+            && ((FIRST_NAME_SET.contains(first) && SURNAME_SET.contains(last))
+                || (SURNAME_SET.contains(first) && FIRST_NAME_SET.contains(last)))
         {
-            if (FIRST_NAME_SET.contains(first) && SURNAME_SET.contains(last))
-                || (SURNAME_SET.contains(first) && FIRST_NAME_SET.contains(last))
-            {
-                return true;
-            }
+            return true;
         }
     }
 
     false
 }
 
+/// Check if two words are a known first name followed by a known surname.
+/// Stricter than [`is_likely_name`], for text where single names are too common
+/// to count, such as file names.
+pub fn is_person_name_pair(first: &str, last: &str) -> bool {
+    FIRST_NAME_SET.contains(normalize_name(first).as_str())
+        && SURNAME_SET.contains(normalize_name(last).as_str())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,257 @@
+use std::ffi::{c_char, CStr};
+use std::path::Path;
+
+use libloading::Library;
+use once_cell::sync::OnceCell;
+
+use super::column_names::ColumnNameResult;
+use super::value_patterns::ValuePatternResult;
+use crate::error::Error;
+use crate::paths;
+use crate::types::Result;
+
+/// Plugin ABI version this build understands
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+/// What a privacy rule says about a column name or value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleVerdict {
+    /// No opinion; later rules and the built-in patterns decide
+    Pass,
+    /// Keep the values but flag the column for review
+    Warning,
+    /// Suppress (column names: the whole column; values: the value)
+    Phi,
+}
+
+impl RuleVerdict {
+    /// Plugin return codes: 1 = warning, 2 = PHI, anything else = pass
+    fn from_code(code: i32) -> Self {
+        match code {
+            1 => RuleVerdict::Warning,
+            2 => RuleVerdict::Phi,
+            _ => RuleVerdict::Pass,
+        }
+    }
+}
+
+/// Site-specific detection logic, checked before the built-in patterns
+pub trait PrivacyRule: Send + Sync {
+    /// Reported as the matched pattern (and as the audit log rule)
+    fn name(&self) -> &str;
+
+    fn check_column_name(&self, _name: &str) -> RuleVerdict {
+        RuleVerdict::Pass
+    }
+
+    /// Called with trimmed, non-empty values
+    fn check_value(&self, _value: &str) -> RuleVerdict {
+        RuleVerdict::Pass
+    }
+}
+
+static RULES: OnceCell<Vec<Box<dyn PrivacyRule>>> = OnceCell::new();
+
+/// Install plugin rules. Only the first call takes effect.
+pub fn register_rules(rules: Vec<Box<dyn PrivacyRule>>) {
+    let _ = RULES.set(rules);
+}
+
+/// Verdict of the first registered rule with an opinion on the column name
+pub(crate) fn check_column_name_rules(name: &str) -> Option<ColumnNameResult> {
+    column_name_verdict(RULES.get()?, name)
+}
+
+/// Verdict of the first registered rule with an opinion on the value
+pub(crate) fn check_value_rules(value: &str) -> Option<ValuePatternResult> {
+    value_verdict(RULES.get()?, value)
+}
+
+fn column_name_verdict(rules: &[Box<dyn PrivacyRule>], name: &str) -> Option<ColumnNameResult> {
+    rules
+        .iter()
+        .find_map(|rule| match rule.check_column_name(name) {
+            RuleVerdict::Pass => None,
+            RuleVerdict::Warning => Some(ColumnNameResult::warning(rule.name())),
+            RuleVerdict::Phi => Some(ColumnNameResult::phi(rule.name())),
+        })
+}
+
+fn value_verdict(
+    rules: &'static [Box<dyn PrivacyRule>],
+    value: &str,
+) -> Option<ValuePatternResult> {
+    rules.iter().find_map(|rule| match rule.check_value(value) {
+        RuleVerdict::Pass => None,
+        RuleVerdict::Warning => Some(ValuePatternResult::warning(
+            rule.name(),
+            "Value flagged by a plugin rule",
+        )),
+        RuleVerdict::Phi => Some(ValuePatternResult::phi(
+            rule.name(),
+            "Value flagged as PHI by a plugin rule",
+        )),
+    })
+}
+
+type AbiVersionFn = unsafe extern "C" fn() -> u32;
+type NameFn = unsafe extern "C" fn() -> *const c_char;
+type CheckFn = unsafe extern "C" fn(*const u8, usize) -> i32;
+
+/// A rule compiled into a shared library (`.so`, `.dylib`, `.dll`).
+///
+/// The library exports C functions (see the manual for the full contract):
+/// `ert_manifest_plugin_abi_version() -> u32` (must return
+/// [`PLUGIN_ABI_VERSION`]), `ert_manifest_plugin_name() -> *const c_char`, and
+/// optionally `ert_manifest_plugin_check_column` and
+/// `ert_manifest_plugin_check_value`, both `(ptr: *const u8, len: usize) -> i32`
+/// over UTF-8 text.
+pub struct DylibRule {
+    name: String,
+    check_column: Option<CheckFn>,
+    check_value: Option<CheckFn>,
+    /// Keeps the functions above loaded
+    _library: Library,
+}
+
+impl DylibRule {
+    pub fn load(path: &Path) -> Result<Self> {
+        let invalid =
+            |problem: String| Error::Config(format!("{}: {}", paths::display_path(path), problem));
+
+        // SAFETY: loading a library runs its initializers; plugins are trusted
+        // code, only taken from a config file the user passed with --config.
+        let library = unsafe { Library::new(paths::for_fs_access(path)) }
+            .map_err(|e| invalid(e.to_string()))?;
+
+        // SAFETY: the symbol types match the documented plugin ABI. The
+        // function pointers stay valid because `library` is stored alongside.
+        let (version, name, check_column, check_value) = unsafe {
+            let version = library
+                .get::<AbiVersionFn>(b"ert_manifest_plugin_abi_version\0")
+                .map_err(|e| invalid(e.to_string()))?;
+            let name = library
+                .get::<NameFn>(b"ert_manifest_plugin_name\0")
+                .map_err(|e| invalid(e.to_string()))?;
+            let check_column = library
+                .get::<CheckFn>(b"ert_manifest_plugin_check_column\0")
+                .ok()
+                .map(|f| *f);
+            let check_value = library
+                .get::<CheckFn>(b"ert_manifest_plugin_check_value\0")
+                .ok()
+                .map(|f| *f);
+
+            let name_ptr = name();
+            if name_ptr.is_null() {
+                return Err(invalid("plugin returned no name".to_string()));
+            }
+            let name = CStr::from_ptr(name_ptr).to_string_lossy().into_owned();
+            (version(), name, check_column, check_value)
+        };
+
+        if version != PLUGIN_ABI_VERSION {
+            return Err(invalid(format!(
+                "plugin ABI version {} is not supported (expected {})",
+                version, PLUGIN_ABI_VERSION
+            )));
+        }
+
+        Ok(Self {
+            name,
+            check_column,
+            check_value,
+            _library: library,
+        })
+    }
+
+    fn call(check: Option<CheckFn>, text: &str) -> RuleVerdict {
+        match check {
+            // SAFETY: the pointer and length describe a live UTF-8 buffer that
+            // the plugin only reads during the call.
+            Some(f) => RuleVerdict::from_code(unsafe { f(text.as_ptr(), text.len()) }),
+            None => RuleVerdict::Pass,
+        }
+    }
+}
+
+impl PrivacyRule for DylibRule {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn check_column_name(&self, name: &str) -> RuleVerdict {
+        Self::call(self.check_column, name)
+    }
+
+    fn check_value(&self, value: &str) -> RuleVerdict {
+        Self::call(self.check_value, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Classification;
+
+    /// Flags `study_code_*` columns and `SJ-` values
+    struct SiteRule;
+
+    impl PrivacyRule for SiteRule {
+        fn name(&self) -> &str {
+            "site_rule"
+        }
+
+        fn check_column_name(&self, name: &str) -> RuleVerdict {
+            if name.starts_with("study_code_") {
+                RuleVerdict::Phi
+            } else {
+                RuleVerdict::Pass
+            }
+        }
+
+        fn check_value(&self, value: &str) -> RuleVerdict {
+            if value.starts_with("SJ-") {
+                RuleVerdict::Warning
+            } else {
+                RuleVerdict::Pass
+            }
+        }
+    }
+
+    #[test]
+    fn test_rule_verdicts() {
+        let rules: &'static [Box<dyn PrivacyRule>] =
+            vec![Box::new(SiteRule) as Box<dyn PrivacyRule>].leak();
+
+        let column = column_name_verdict(rules, "study_code_a").unwrap();
+        assert_eq!(column.classification, Classification::Phi);
+        assert_eq!(column.matched_pattern.as_deref(), Some("site_rule"));
+        assert!(column_name_verdict(rules, "age").is_none());
+
+        let value = value_verdict(rules, "SJ-0042").unwrap();
+        assert!(value.is_warning && !value.is_phi);
+        assert_eq!(value.matched_pattern, Some("site_rule"));
+        assert!(value_verdict(rules, "42").is_none());
+    }
+
+    #[test]
+    fn test_verdict_codes() {
+        assert_eq!(RuleVerdict::from_code(0), RuleVerdict::Pass);
+        assert_eq!(RuleVerdict::from_code(1), RuleVerdict::Warning);
+        assert_eq!(RuleVerdict::from_code(2), RuleVerdict::Phi);
+        assert_eq!(RuleVerdict::from_code(-1), RuleVerdict::Pass);
+    }
+
+    #[test]
+    fn test_load_rejects_non_libraries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rules.so");
+        std::fs::write(&path, "not a library").unwrap();
+        assert!(matches!(DylibRule::load(&path), Err(Error::Config(_))));
+        assert!(matches!(
+            DylibRule::load(&dir.path().join("missing.so")),
+            Err(Error::Config(_))
+        ));
+    }
+}
@@ -0,0 +1,81 @@
+//! Quasi-identifiers: columns that identify no one alone but can in combination.
+//!
+//! Age, sex and a region are each shared by many people; together they can
+//! single out one. Columns are recognized by name, and dates by type.
+
+use super::locales::fold_accents;
+use crate::types::DType;
+
+/// Column name words that mark a quasi-identifier, with the kind they are
+const QUASI_IDENTIFIER_WORDS: &[(&str, &str)] = &[
+    ("age", "age"),
+    ("idade", "age"),
+    ("edad", "age"),
+    ("alter", "age"),
+    ("sex", "sex"),
+    ("gender", "sex"),
+    ("sexe", "sex"),
+    ("sexo", "sex"),
+    ("genero", "sex"),
+    ("geschlecht", "sex"),
+    ("race", "ethnicity"),
+    ("ethnicity", "ethnicity"),
+    ("ethnic", "ethnicity"),
+    ("raca", "ethnicity"),
+    ("etnia", "ethnicity"),
+    ("marital", "marital status"),
+    ("occupation", "occupation"),
+    ("profession", "occupation"),
+    ("job", "occupation"),
+    ("region", "geography"),
+    ("province", "geography"),
+    ("county", "geography"),
+    ("state", "geography"),
+    ("country", "geography"),
+    ("zip", "geography"),
+    ("zip3", "geography"),
+    ("postal", "geography"),
+    ("postcode", "geography"),
+    ("fsa", "geography"),
+];
+
+/// The kind of quasi-identifier a column is (`age`, `sex`, `geography`, `date`,
+/// ...), if it is one
+pub fn quasi_identifier_kind(name: &str, dtype: DType) -> Option<&'static str> {
+    let name = fold_accents(&name.to_lowercase());
+    let by_name = name.split(|c: char| !c.is_alphanumeric()).find_map(|word| {
+        QUASI_IDENTIFIER_WORDS
+            .iter()
+            .find(|(w, _)| *w == word)
+            .map(|(_, kind)| *kind)
+    });
+    by_name.or(matches!(dtype, DType::Date | DType::Datetime).then_some("date"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quasi_identifier_kind() {
+        assert_eq!(
+            quasi_identifier_kind("Age (years)", DType::Integer),
+            Some("age")
+        );
+        assert_eq!(
+            quasi_identifier_kind("patient_gender", DType::String),
+            Some("sex")
+        );
+        assert_eq!(
+            quasi_identifier_kind("Raça", DType::String),
+            Some("ethnicity")
+        );
+        assert_eq!(
+            quasi_identifier_kind("zip3", DType::String),
+            Some("geography")
+        );
+        assert_eq!(quasi_identifier_kind("visit_dt", DType::Date), Some("date"));
+        assert_eq!(quasi_identifier_kind("hba1c", DType::Numeric), None);
+        assert_eq!(quasi_identifier_kind("stage", DType::String), None);
+    }
+}
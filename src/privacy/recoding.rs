@@ -1,5 +1,44 @@
 use std::collections::HashMap;
 
+use clap::ValueEnum;
+use serde::Serialize;
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::output::tabular::escape_formula;
+
+/// File format of the recode sidekick
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum RecodeFormat {
+    /// Commented text, one section per column
+    #[default]
+    Txt,
+    /// One row per mapping: sheet, column, original, recoded
+    Csv,
+    /// Array of {sheet, column, original, recoded} objects
+    Json,
+}
+
+impl RecodeFormat {
+    /// Suffix appended to the input file name (`trial.csv.recode.json`)
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            RecodeFormat::Txt => ".recode.txt",
+            RecodeFormat::Csv => ".recode.csv",
+            RecodeFormat::Json => ".recode.json",
+        }
+    }
+}
+
+/// One mapping in the CSV and JSON sidekicks
+#[derive(Serialize)]
+struct MappingRow<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sheet: Option<&'a str>,
+    column: &'a str,
+    original: &'a str,
+    recoded: &'a str,
+}
+
 /// Recoder for anonymizing site-identifying values
 #[derive(Debug, Clone, Default)]
 pub struct ValueRecoder {
@@ -51,18 +90,35 @@ impl ValueRecoder {
         &self.mappings
     }
 
-    /// Get the reverse mapping (recoded -> original) for the sidekick file
-    pub fn get_reverse_mappings(&self) -> HashMap<String, String> {
-        self.mappings
+    /// Mappings as (recoded, original) pairs sorted by recoded value, for the sidekick file
+    pub fn sorted_reverse_mappings(&self) -> Vec<(&str, &str)> {
+        let mut pairs: Vec<(&str, &str)> = self
+            .mappings
             .iter()
-            .map(|(k, v)| (v.clone(), k.clone()))
-            .collect()
+            .map(|(original, recoded)| (recoded.as_str(), original.as_str()))
+            .collect();
+        pairs.sort();
+        pairs
     }
 
     /// Get mapping count
     pub fn count(&self) -> usize {
         self.mappings.len()
     }
+
+    /// Overwrite the original values before releasing them
+    fn wipe(&mut self) {
+        for (mut original, mut recoded) in self.mappings.drain() {
+            original.zeroize();
+            recoded.zeroize();
+        }
+    }
+}
+
+impl Drop for ValueRecoder {
+    fn drop(&mut self) {
+        self.wipe();
+    }
 }
 
 /// Convert a 0-based index to a letter label (0=A, 1=B, ..., 25=Z, 26=AA, ...)
@@ -82,6 +138,13 @@ fn index_to_label(index: usize) -> String {
     result
 }
 
+/// Join lines into wiped-on-drop content, wiping the lines themselves
+fn join_and_wipe(mut lines: Vec<String>, separator: &str) -> Zeroizing<String> {
+    let content = Zeroizing::new(lines.join(separator));
+    lines.zeroize();
+    content
+}
+
 /// Collection of recoders for multiple columns
 #[derive(Debug, Clone, Default)]
 pub struct RecodeRegistry {
@@ -89,6 +152,8 @@ pub struct RecodeRegistry {
     recoders: HashMap<usize, ValueRecoder>,
     /// Maps column index to column name (for sidekick file)
     column_names: HashMap<usize, String>,
+    /// Recoders merged in from other sheets, in merge order
+    sheets: Vec<(String, RecodeRegistry)>,
 }
 
 impl RecodeRegistry {
@@ -96,6 +161,14 @@ impl RecodeRegistry {
         Self {
             recoders: HashMap::new(),
             column_names: HashMap::new(),
+            sheets: Vec::new(),
+        }
+    }
+
+    /// Merge the registry of another sheet (used for multi-sheet workbooks)
+    pub fn merge_sheet(&mut self, sheet_name: &str, registry: RecodeRegistry) {
+        if registry.has_recodings() {
+            self.sheets.push((sheet_name.to_string(), registry));
         }
     }
 
@@ -105,6 +178,15 @@ impl RecodeRegistry {
         self.column_names.insert(column_index, column_name.to_string());
     }
 
+    /// Renumber the columns after profiling a selection of them: column `i`
+    /// becomes `file_columns[i]`
+    pub fn renumber_columns(&mut self, file_columns: &[usize]) {
+        let recoders = std::mem::take(&mut self.recoders);
+        self.recoders = recoders.into_iter().map(|(i, r)| (file_columns[i], r)).collect();
+        let names = std::mem::take(&mut self.column_names);
+        self.column_names = names.into_iter().map(|(i, n)| (file_columns[i], n)).collect();
+    }
+
     /// Recode a value for a specific column
     pub fn recode(&mut self, column_index: usize, original: &str) -> Option<String> {
         self.recoders.get_mut(&column_index).map(|r| r.recode(original))
@@ -124,43 +206,131 @@ impl RecodeRegistry {
         })
     }
 
-    /// Generate the sidekick file content
-    pub fn generate_sidekick_content(&self) -> String {
+    /// Sidekick file content in the given format (wiped from memory when dropped)
+    pub fn sidekick_content(&self, format: RecodeFormat) -> Zeroizing<String> {
+        match format {
+            RecodeFormat::Txt => self.generate_sidekick_content(),
+            RecodeFormat::Csv => {
+                // Originals that look like formulas are escaped, as in every CSV we write
+                let mut writer = csv::Writer::from_writer(Vec::new());
+                let mut write = |fields: [&str; 4]| {
+                    let mut record = fields.map(|f| escape_formula(f).into_owned());
+                    let written = writer.write_record(&record);
+                    record.zeroize();
+                    written.expect("writing to memory")
+                };
+                write(["sheet", "column", "original", "recoded"]);
+                for row in self.mapping_rows() {
+                    write([row.sheet.unwrap_or(""), row.column, row.original, row.recoded]);
+                }
+                let bytes = writer.into_inner().expect("writing to memory");
+                Zeroizing::new(String::from_utf8(bytes).expect("fields are UTF-8"))
+            }
+            RecodeFormat::Json => {
+                let rows = self.mapping_rows();
+                let mut lines = vec!["[".to_string()];
+                for (i, row) in rows.iter().enumerate() {
+                    let json =
+                        Zeroizing::new(serde_json::to_string(row).expect("string fields serialize"));
+                    let comma = if i + 1 < rows.len() { "," } else { "" };
+                    lines.push(format!("  {}{}", *json, comma));
+                }
+                lines.push("]\n".to_string());
+                join_and_wipe(lines, "\n")
+            }
+        }
+    }
+
+    /// Every mapping, by sheet and column index, then recoded value
+    fn mapping_rows(&self) -> Vec<MappingRow<'_>> {
+        let mut rows = Vec::new();
+        self.push_mapping_rows(&mut rows, None);
+        for (sheet_name, registry) in &self.sheets {
+            registry.push_mapping_rows(&mut rows, Some(sheet_name));
+        }
+        rows
+    }
+
+    fn push_mapping_rows<'a>(&'a self, rows: &mut Vec<MappingRow<'a>>, sheet: Option<&'a str>) {
+        let mut indices: Vec<_> = self.recoders.keys().collect();
+        indices.sort();
+        for col_idx in indices {
+            if let (Some(recoder), Some(column)) =
+                (self.recoders.get(col_idx), self.column_names.get(col_idx))
+            {
+                for (recoded, original) in recoder.sorted_reverse_mappings() {
+                    rows.push(MappingRow {
+                        sheet,
+                        column,
+                        original,
+                        recoded,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Number of recoded values and of recoded columns, over all sheets
+    pub fn counts(&self) -> (usize, usize) {
+        let (values, columns) = self.sheets.iter().fold((0, 0), |(v, c), (_, registry)| {
+            let (sv, sc) = registry.counts();
+            (v + sv, c + sc)
+        });
+        let own = self.recoders.values().filter(|r| r.count() > 0);
+        (
+            values + own.clone().map(ValueRecoder::count).sum::<usize>(),
+            columns + own.count(),
+        )
+    }
+
+    /// Generate the sidekick file content (wiped from memory when dropped)
+    pub fn generate_sidekick_content(&self) -> Zeroizing<String> {
         let mut lines = Vec::new();
         lines.push("# ERT-Manifest Recode Mapping".to_string());
         lines.push("# CONFIDENTIAL - Keep this file secure at your site".to_string());
         lines.push(format!("# Generated: {}", chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC")));
         lines.push(String::new());
 
+        self.push_column_sections(&mut lines, None);
+        for (sheet_name, registry) in &self.sheets {
+            registry.push_column_sections(&mut lines, Some(sheet_name));
+        }
+
+        join_and_wipe(lines, "\n")
+    }
+
+    /// Append one mapping section per recoded column
+    fn push_column_sections(&self, lines: &mut Vec<String>, sheet_name: Option<&str>) {
         // Sort by column index for consistent output
         let mut indices: Vec<_> = self.recoders.keys().collect();
         indices.sort();
 
         for &col_idx in &indices {
             if let (Some(recoder), Some(col_name)) = (self.recoders.get(col_idx), self.column_names.get(col_idx)) {
-                lines.push(format!("## Column {}: {}", col_idx + 1, col_name));
+                match sheet_name {
+                    Some(sheet) => lines.push(format!(
+                        "## Sheet '{}', Column {}: {}",
+                        sheet,
+                        col_idx + 1,
+                        col_name
+                    )),
+                    None => lines.push(format!("## Column {}: {}", col_idx + 1, col_name)),
+                }
                 lines.push(String::new());
 
                 // Sort mappings by recoded value for readability
-                let reverse = recoder.get_reverse_mappings();
-                let mut recoded_values: Vec<_> = reverse.keys().collect();
-                recoded_values.sort();
-
-                for recoded in recoded_values {
-                    if let Some(original) = reverse.get(recoded) {
-                        lines.push(format!("{} = {}", recoded, original));
-                    }
+                for (recoded, original) in recoder.sorted_reverse_mappings() {
+                    lines.push(format!("{} = {}", recoded, original));
                 }
                 lines.push(String::new());
             }
         }
-
-        lines.join("\n")
     }
 
     /// Check if any recoding was done
     pub fn has_recodings(&self) -> bool {
         self.recoders.values().any(|r| r.count() > 0)
+            || self.sheets.iter().any(|(_, r)| r.has_recodings())
     }
 }
 
@@ -191,6 +361,20 @@ mod tests {
         assert_eq!(recoder.count(), 3);
     }
 
+    #[test]
+    fn test_value_recoder_wipe() {
+        let mut recoder = ValueRecoder::for_sites();
+        recoder.recode("YVR-003");
+        recoder.recode("YYC-001");
+        assert_eq!(
+            recoder.sorted_reverse_mappings(),
+            vec![("Site_A", "YVR-003"), ("Site_B", "YYC-001")]
+        );
+
+        recoder.wipe();
+        assert_eq!(recoder.count(), 0);
+    }
+
     #[test]
     fn test_recode_registry() {
         let mut registry = RecodeRegistry::new();
@@ -216,4 +400,76 @@ mod tests {
         assert!(content.contains("Site_A = "));
         assert!(content.contains("Site_B = "));
     }
+
+    #[test]
+    fn test_merge_sheet_sidekick() {
+        let mut sheet = RecodeRegistry::new();
+        sheet.register_column(0, "hospital", "Hospital");
+        sheet.recode(0, "St. Paul's");
+
+        let mut registry = RecodeRegistry::new();
+        assert!(!registry.has_recodings());
+        registry.merge_sheet("Enrolment", sheet);
+        assert!(registry.has_recodings());
+
+        let content = registry.generate_sidekick_content();
+        assert!(content.contains("## Sheet 'Enrolment', Column 1: hospital"));
+        assert!(content.contains("Hospital_A = St. Paul's"));
+    }
+
+    #[test]
+    fn test_structured_sidekicks() {
+        let mut sheet = RecodeRegistry::new();
+        sheet.register_column(0, "hospital", "Hospital");
+        sheet.recode(0, "St. Paul's, Vancouver");
+
+        let mut registry = RecodeRegistry::new();
+        registry.register_column(1, "site_code", "Site");
+        registry.recode(1, "YVR-003");
+        registry.recode(1, "YYC-001");
+        registry.merge_sheet("Enrolment", sheet);
+        assert_eq!(registry.counts(), (3, 2));
+
+        assert_eq!(
+            *registry.sidekick_content(RecodeFormat::Csv),
+            "sheet,column,original,recoded\n\
+             ,site_code,YVR-003,Site_A\n\
+             ,site_code,YYC-001,Site_B\n\
+             Enrolment,hospital,\"St. Paul's, Vancouver\",Hospital_A\n"
+        );
+
+        let json: serde_json::Value =
+            serde_json::from_str(&registry.sidekick_content(RecodeFormat::Json)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([
+                {"column": "site_code", "original": "YVR-003", "recoded": "Site_A"},
+                {"column": "site_code", "original": "YYC-001", "recoded": "Site_B"},
+                {"sheet": "Enrolment", "column": "hospital", "original": "St. Paul's, Vancouver", "recoded": "Hospital_A"}
+            ])
+        );
+
+        let empty: serde_json::Value =
+            serde_json::from_str(&RecodeRegistry::new().sidekick_content(RecodeFormat::Json))
+                .unwrap();
+        assert_eq!(empty, serde_json::json!([]));
+    }
+
+    #[test]
+    fn test_csv_sidekick_escapes_formulas() {
+        let mut registry = RecodeRegistry::new();
+        registry.register_column(0, "site", "Site");
+        for original in ["=HYPERLINK(\"http://evil\")", "@SUM(1)", "+cmd", "-2+3"] {
+            registry.recode(0, original);
+        }
+
+        assert_eq!(
+            *registry.sidekick_content(RecodeFormat::Csv),
+            "sheet,column,original,recoded\n\
+             ,site,\"'=HYPERLINK(\"\"http://evil\"\")\",Site_A\n\
+             ,site,'@SUM(1),Site_B\n\
+             ,site,'+cmd,Site_C\n\
+             ,site,'-2+3,Site_D\n"
+        );
+    }
 }
@@ -1,12 +1,15 @@
-use once_cell::sync::Lazy;
+use once_cell::sync::{Lazy, OnceCell};
 use regex::Regex;
 
 use super::name_lists::is_likely_name;
+use crate::types::{Classification, Language};
 
 /// Result of checking a value for PHI patterns
 #[derive(Debug, Clone, PartialEq)]
 pub struct ValuePatternResult {
     pub is_phi: bool,
+    /// Matched a custom detector classified as a warning: the value is kept but flagged
+    pub is_warning: bool,
     pub matched_pattern: Option<&'static str>,
     pub description: Option<&'static str>,
 }
@@ -15,6 +18,7 @@ impl ValuePatternResult {
     pub fn safe() -> Self {
         Self {
             is_phi: false,
+            is_warning: false,
             matched_pattern: None,
             description: None,
         }
@@ -23,10 +27,52 @@ impl ValuePatternResult {
     pub fn phi(pattern: &'static str, description: &'static str) -> Self {
         Self {
             is_phi: true,
+            is_warning: false,
             matched_pattern: Some(pattern),
             description: Some(description),
         }
     }
+
+    pub fn warning(pattern: &'static str, description: &'static str) -> Self {
+        Self {
+            is_phi: false,
+            is_warning: true,
+            matched_pattern: Some(pattern),
+            description: Some(description),
+        }
+    }
+}
+
+/// A site-specific value detector (e.g. an internal MRN format) from the config file
+#[derive(Debug, Clone)]
+pub struct CustomDetector {
+    pub name: String,
+    /// Matched against the trimmed value
+    pub regex: Regex,
+    /// `Phi` suppresses matching values; `Warning` keeps them but flags the column
+    pub classification: Classification,
+    pub description: String,
+}
+
+/// Custom detectors, installed once at startup
+static CUSTOM_DETECTORS: OnceCell<Vec<CustomDetector>> = OnceCell::new();
+
+/// Install custom detectors; they are checked before the built-in patterns.
+/// Only the first call takes effect.
+pub fn register_detectors(detectors: Vec<CustomDetector>) {
+    let _ = CUSTOM_DETECTORS.set(detectors);
+}
+
+/// First custom detector matching the value
+fn check_custom_detectors(
+    detectors: &'static [CustomDetector],
+    value: &str,
+) -> Option<ValuePatternResult> {
+    let detector = detectors.iter().find(|d| d.regex.is_match(value))?;
+    Some(match detector.classification {
+        Classification::Phi => ValuePatternResult::phi(&detector.name, &detector.description),
+        _ => ValuePatternResult::warning(&detector.name, &detector.description),
+    })
 }
 
 // Compiled regex patterns for PHI detection
@@ -34,10 +80,43 @@ static EMAIL_PATTERN: Lazy<Regex> = Lazy::new(|| {
     Regex::new(r"^[a-zA-Z0-9._%+-]+@[a-zA-Z0-9.-]+\.[a-zA-Z]{2,}$").unwrap()
 });
 
+// North American numbers, with or without the country code: 1-514-555-1234
 static US_PHONE_PATTERN: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"^\(?\d{3}\)?[-.\s]?\d{3}[-.\s]?\d{4}$").unwrap()
+    Regex::new(r"^(\+?1[-.\s]?)?\(?\d{3}\)?[-.\s]?\d{3}[-.\s]?\d{4}$").unwrap()
 });
 
+// E.164 numbers, written with a leading + and optional separators: +55 11 98765-4321
+static INTERNATIONAL_PHONE_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\+[1-9][\d\s().-]{6,20}\d$").unwrap());
+
+// Patterns that only identify someone in one country and match ordinary values
+// elsewhere; checked when the language's locale is enabled (`--locales`)
+static LOCALE_PATTERNS: Lazy<Vec<(Language, &'static str, &'static str, Regex)>> =
+    Lazy::new(|| {
+        vec![
+            (
+                Language::Pt,
+                "phone",
+                "Value appears to be a Brazilian phone number",
+                // (11) 98765-4321, 11 3456-7890
+                Regex::new(r"^(\(\d{2}\)\s?|\d{2}[\s-])9?\d{4}-\d{4}$").unwrap(),
+            ),
+            (
+                Language::Pt,
+                "postal",
+                "Value appears to be a Brazilian postal code (CEP)",
+                Regex::new(r"^\d{5}-\d{3}$").unwrap(),
+            ),
+            (
+                Language::En,
+                "postal",
+                "Value appears to be a UK postcode",
+                // SW1A 1AA, M1 1AE, B33 8TH
+                Regex::new(r"(?i)^[A-PR-UWYZ][A-HK-Y]?\d[A-Z\d]? ?\d[ABD-HJLNP-UW-Z]{2}$").unwrap(),
+            ),
+        ]
+    });
+
 static US_ZIP_PATTERN: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"^\d{5}(-\d{4})?$").unwrap());
 
@@ -47,6 +126,10 @@ static CANADA_POSTAL_PATTERN: Lazy<Regex> =
 static SSN_PATTERN: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"^\d{3}-?\d{2}-?\d{4}$").unwrap());
 
+// Quebec health insurance number (RAMQ NAM): TREM 6512 3112
+static RAMQ_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^[A-Za-z]{4}\s?(\d{2})(\d{2})\s?(\d{2})\d{2}$").unwrap());
+
 static LONG_ID_PATTERN: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"^[A-Za-z0-9]{10,}$").unwrap());
 
@@ -67,22 +150,43 @@ static MAC_ADDRESS_PATTERN: Lazy<Regex> =
 
 /// Check if a value matches any PHI pattern
 pub fn check_value_pattern(value: &str) -> ValuePatternResult {
+    check_value_pattern_in(value, &[])
+}
+
+/// Check if a value matches any PHI pattern, including those of the given locales
+pub fn check_value_pattern_in(value: &str, languages: &[Language]) -> ValuePatternResult {
     let trimmed = value.trim();
 
     if trimmed.is_empty() {
         return ValuePatternResult::safe();
     }
 
+    if let Some(detectors) = CUSTOM_DETECTORS.get() {
+        if let Some(result) = check_custom_detectors(detectors, trimmed) {
+            return result;
+        }
+    }
+    if let Some(result) = super::plugins::check_value_rules(trimmed) {
+        return result;
+    }
+
     // Check email pattern
     if EMAIL_PATTERN.is_match(trimmed) {
         return ValuePatternResult::phi("email", "Value appears to be an email address");
     }
 
     // Check US phone pattern
-    if US_PHONE_PATTERN.is_match(trimmed) {
+    if US_PHONE_PATTERN.is_match(trimmed) || is_international_phone(trimmed) {
         return ValuePatternResult::phi("phone", "Value appears to be a phone number");
     }
 
+    let locale_match = LOCALE_PATTERNS
+        .iter()
+        .find(|(language, _, _, regex)| languages.contains(language) && regex.is_match(trimmed));
+    if let Some((_, pattern, description, _)) = locale_match {
+        return ValuePatternResult::phi(pattern, description);
+    }
+
     // Check SSN pattern
     if SSN_PATTERN.is_match(trimmed) {
         return ValuePatternResult::phi("ssn", "Value appears to be a Social Security Number");
@@ -98,6 +202,15 @@ pub fn check_value_pattern(value: &str) -> ValuePatternResult {
         return ValuePatternResult::phi("postal", "Value appears to be a Canadian postal code");
     }
 
+    // Check RAMQ numbers before long IDs, which they also are
+    if is_ramq_number(trimmed) {
+        return ValuePatternResult::phi(
+            "ramq",
+            "Value appears to be a Quebec health insurance number (RAMQ), which encodes \
+             the date of birth and sex",
+        );
+    }
+
     // Check for long alphanumeric IDs with mixed letters and digits
     if is_suspicious_long_id(trimmed) {
         return ValuePatternResult::phi(
@@ -134,6 +247,79 @@ pub fn check_value_pattern(value: &str) -> ValuePatternResult {
     ValuePatternResult::safe()
 }
 
+// Identifiers that can be told apart inside longer text; separators are
+// required so that plain numbers are not taken for phone numbers or SSNs
+static EMBEDDED_PATTERNS: Lazy<Vec<(&'static str, Regex)>> = Lazy::new(|| {
+    vec![
+        ("email", Regex::new(r"[a-zA-Z0-9._%+-]+@[a-zA-Z0-9.-]+\.[a-zA-Z]{2,}").unwrap()),
+        ("phone", Regex::new(r"(\(\d{3}\)\s?|\b\d{3}[-.\s])\d{3}[-.]\d{4}\b").unwrap()),
+        (
+            "phone",
+            Regex::new(r"\+[1-9]\d{0,2}[\s.-]?\(?\d{1,4}\)?([\s.-]?\d{2,5}){2,4}\b").unwrap(),
+        ),
+        ("ssn", Regex::new(r"\b\d{3}-\d{2}-\d{4}\b").unwrap()),
+        ("url", Regex::new(r"https?://\S+").unwrap()),
+        ("ipv4", Regex::new(r"\b\d{1,3}\.\d{1,3}\.\d{1,3}\.\d{1,3}\b").unwrap()),
+    ]
+});
+
+/// PHI pattern matched by a cell: by the whole value, or by an email address,
+/// phone number, SSN, URL or IP address inside longer text
+pub fn find_phi_in_text(value: &str, languages: &[Language]) -> Option<&'static str> {
+    let whole = check_value_pattern_in(value, languages);
+    if whole.is_phi {
+        return whole.matched_pattern;
+    }
+    EMBEDDED_PATTERNS
+        .iter()
+        .find(|(_, regex)| regex.is_match(value))
+        .map(|(name, _)| *name)
+}
+
+/// Number of PHI pattern matches in a cell: one for a value that is an identifier as
+/// a whole, otherwise each non-overlapping email, phone number, SSN, URL or IP address
+pub fn count_phi_in_text(value: &str, languages: &[Language]) -> usize {
+    if check_value_pattern_in(value, languages).is_phi {
+        return 1;
+    }
+    let mut spans: Vec<(usize, usize)> = EMBEDDED_PATTERNS
+        .iter()
+        .flat_map(|(_, regex)| regex.find_iter(value).map(|m| (m.start(), m.end())))
+        .collect();
+    spans.sort_unstable();
+    let mut count = 0;
+    let mut covered = 0;
+    for (start, end) in spans {
+        if start >= covered {
+            count += 1;
+        }
+        covered = covered.max(end);
+    }
+    count
+}
+
+/// Check if a value is a phone number with a country code (E.164 allows 8 to 15 digits)
+fn is_international_phone(value: &str) -> bool {
+    INTERNATIONAL_PHONE_PATTERN.is_match(value)
+        && (8..=15).contains(&value.bytes().filter(u8::is_ascii_digit).count())
+}
+
+/// Check if a value is a RAMQ health insurance number: three letters of the last name,
+/// one of the first name, the birth date as YYMMDD (women's months plus 50) and two
+/// more digits
+fn is_ramq_number(value: &str) -> bool {
+    let Some(parts) = RAMQ_PATTERN.captures(value) else {
+        return false;
+    };
+    let number = |i: usize| parts[i].parse::<u32>().unwrap_or(0);
+    let month = match number(2) {
+        m @ 51..=62 => m - 50,
+        m => m,
+    };
+    // 2000 was a leap year, so February 29 passes whatever the century
+    chrono::NaiveDate::from_ymd_opt(2000, month, number(3)).is_some()
+}
+
 /// Check if a value looks like a suspicious long alphanumeric ID
 fn is_suspicious_long_id(value: &str) -> bool {
     if !LONG_ID_PATTERN.is_match(value) {
@@ -151,6 +337,30 @@ fn is_suspicious_long_id(value: &str) -> bool {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_count_phi_in_text() {
+        assert_eq!(count_phi_in_text("j.doe@example.com", &[]), 1);
+        let note = "emailed j.doe@example.com and called +1 555-123-4567 twice";
+        assert_eq!(count_phi_in_text(note, &[]), 2);
+        assert_eq!(count_phi_in_text("no contact details recorded", &[]), 0);
+    }
+
+    #[test]
+    fn test_find_phi_in_text() {
+        assert_eq!(find_phi_in_text("555-123-4567", &[]), Some("phone"));
+        assert_eq!(
+            find_phi_in_text("called back at (555) 123-4567, no answer", &[]),
+            Some("phone")
+        );
+        assert_eq!(find_phi_in_text("see j.doe@example.com", &[]), Some("email"));
+        assert_eq!(find_phi_in_text("ssn 123-45-6789 on file", &[]), Some("ssn"));
+        assert_eq!(find_phi_in_text("upload to https://x.org/a", &[]), Some("url"));
+        assert_eq!(find_phi_in_text("dose 5551234567 units", &[]), None);
+        assert_eq!(find_phi_in_text("cel. +55 11 98765-4321", &[]), Some("phone"));
+        assert_eq!(find_phi_in_text("bolus +10 mg, then +2.5", &[]), None);
+        assert_eq!(find_phi_in_text("code 3 - stable", &[]), None);
+    }
+
     #[test]
     fn test_email_detection() {
         assert!(check_value_pattern("john.doe@example.com").is_phi);
@@ -166,6 +376,59 @@ mod tests {
         assert!(check_value_pattern("555.123.4567").is_phi);
     }
 
+    #[test]
+    fn test_international_phone_detection() {
+        assert!(check_value_pattern("+55 11 98765-4321").is_phi);
+        assert!(check_value_pattern("+5511987654321").is_phi);
+        assert!(check_value_pattern("+1-514-555-1234").is_phi);
+        assert!(check_value_pattern("1 (514) 555-1234").is_phi);
+        assert!(check_value_pattern("+44 20 7946 0958").is_phi);
+        assert!(!check_value_pattern("+12").is_phi);
+        assert!(!check_value_pattern("+1.5").is_phi);
+        assert!(!check_value_pattern("+1234567890123456").is_phi);
+    }
+
+    #[test]
+    fn test_locale_phone_detection() {
+        for value in ["(11) 98765-4321", "11 3456-7890", "(21)99876-5432"] {
+            assert!(!check_value_pattern(value).is_phi, "{}", value);
+            let result = check_value_pattern_in(value, &[Language::Pt]);
+            assert_eq!(result.matched_pattern, Some("phone"), "{}", value);
+        }
+        assert!(!check_value_pattern_in("(11) 987-65", &[Language::Pt]).is_phi);
+        assert!(!check_value_pattern_in("11 98765-4321", &[Language::Es]).is_phi);
+    }
+
+    #[test]
+    fn test_locale_postal_detection() {
+        for value in ["SW1A 1AA", "M1 1AE", "b33 8th", "EC1A1BB"] {
+            assert!(!check_value_pattern(value).is_phi, "{}", value);
+            let result = check_value_pattern_in(value, &[Language::En]);
+            assert_eq!(result.matched_pattern, Some("postal"), "{}", value);
+        }
+        assert_eq!(
+            check_value_pattern_in("01310-100", &[Language::Pt]).matched_pattern,
+            Some("postal")
+        );
+        assert!(!check_value_pattern("01310-100").is_phi);
+        assert!(!check_value_pattern_in("01310-100", &[Language::En]).is_phi);
+        // Visit and lab codes don't have a UK postcode's inward part
+        assert!(!check_value_pattern_in("V1 2", &[Language::En]).is_phi);
+        assert!(!check_value_pattern_in("AB12 3CI", &[Language::En]).is_phi);
+    }
+
+    #[test]
+    fn test_ramq_detection() {
+        for value in ["TREM 6512 3112", "GAGM55022914", "lapj 0402 2901"] {
+            let result = check_value_pattern(value);
+            assert_eq!(result.matched_pattern, Some("ramq"), "{}", value);
+        }
+        // Month 13 and day 32 are not birth dates; still long IDs
+        assert_eq!(check_value_pattern("TREM65133112").matched_pattern, Some("long_id"));
+        assert_eq!(check_value_pattern("TREM65123212").matched_pattern, Some("long_id"));
+        assert!(!check_value_pattern("TREM 6513 3112").is_phi);
+    }
+
     #[test]
     fn test_ssn_detection() {
         assert!(check_value_pattern("123-45-6789").is_phi);
@@ -270,6 +533,34 @@ mod tests {
         assert!(check_value_pattern("Aaliyah").is_phi);
     }
 
+    #[test]
+    fn test_custom_detectors() {
+        let detectors = vec![
+            CustomDetector {
+                name: "hsj_mrn".to_string(),
+                regex: Regex::new(r"^HSJ-\d{6}$").unwrap(),
+                classification: Classification::Phi,
+                description: "Sainte-Justine MRN".to_string(),
+            },
+            CustomDetector {
+                name: "lab_kit".to_string(),
+                regex: Regex::new(r"^KIT\d+$").unwrap(),
+                classification: Classification::Warning,
+                description: "Lab kit number".to_string(),
+            },
+        ]
+        .leak();
+
+        let mrn = check_custom_detectors(detectors, "HSJ-123456").unwrap();
+        assert!(mrn.is_phi);
+        assert_eq!(mrn.matched_pattern, Some("hsj_mrn"));
+
+        let kit = check_custom_detectors(detectors, "KIT42").unwrap();
+        assert!(!kit.is_phi && kit.is_warning);
+
+        assert!(check_custom_detectors(detectors, "HSJ-12").is_none());
+    }
+
     #[test]
     fn test_non_names() {
         // Clinical terms should not be detected as names
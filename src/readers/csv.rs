@@ -1,23 +1,46 @@
 use std::fs::File;
-use std::io::BufReader;
+use std::io::Read;
 use std::path::{Path, PathBuf};
 
-use csv::{Reader, ReaderBuilder};
+use csv::{Reader, ReaderBuilder, StringRecord};
+use encoding_rs::Encoding;
+use encoding_rs_io::{DecodeReaderBytes, DecodeReaderBytesBuilder};
 
-use crate::inference::{is_missing, parse_numeric, TypeInferencer};
-use crate::privacy::{bucket_count, check_column_name, safe_count, RecodeRegistry};
-use crate::stats::ColumnStatTracker;
-use crate::types::{
-    Classification, ColumnSchema, ColumnStats, DType, ProcessingOptions, Result, SafeValue,
-    SheetSchema, MAX_UNIQUE_VALUES,
+use crate::audit::{AuditSink, NullAuditSink};
+use crate::error::Error;
+use crate::privacy::RecodeRegistry;
+use crate::types::{FileDetails, HeaderMode, ProcessingOptions, Result, SheetSchema};
+
+use super::encoding::{decode_stream, detect_file_encoding, resolve_encoding, DecodedStream};
+use super::header::{
+    complete_headers, header_names, resolve_layout, sample_rows_needed, HeaderLayout,
 };
+use super::profile::profile_stream;
+use super::provenance::{sniff_source, PROVENANCE_SAMPLE_BYTES};
+use super::{DataReader, RowBatch, StreamingDataReader};
+
+/// CSV reader over a file transcoded to UTF-8
+type DecodedCsvReader = Reader<DecodeReaderBytes<File, Vec<u8>>>;
 
-use super::DataReader;
+/// Resolved header position and column names
+type ResolvedHeader = (HeaderLayout, Vec<String>);
 
 /// CSV/TSV file reader
 pub struct CsvReader {
     path: PathBuf,
     delimiter: u8,
+    /// Source encoding; detected on first open unless overridden
+    encoding: Option<&'static Encoding>,
+    header_mode: HeaderMode,
+    /// Stacked header rows joined into each column name
+    header_rows: usize,
+    /// Resolved header position and column names
+    layout: Option<ResolvedHeader>,
+    reader: Option<DecodedCsvReader>,
+    /// Likely exporting system, sniffed when the file is read
+    file_details: Option<FileDetails>,
+    /// The delimiter was recognized from the content rather than the extension
+    sniffed: bool,
 }
 
 impl CsvReader {
@@ -26,273 +49,343 @@ impl CsvReader {
         Ok(Self {
             path: path.to_path_buf(),
             delimiter: b',',
+            encoding: None,
+            header_mode: HeaderMode::Auto,
+            header_rows: 1,
+            layout: None,
+            reader: None,
+            file_details: None,
+            sniffed: false,
         })
     }
 
     /// Create a new TSV reader
     pub fn new_tsv(path: &Path) -> Result<Self> {
         Ok(Self {
-            path: path.to_path_buf(),
             delimiter: b'\t',
+            ..Self::new(path)?
+        })
+    }
+
+    /// Create a reader for delimited text whose delimiter was recognized from
+    /// the content
+    pub fn new_sniffed(path: &Path, delimiter: u8) -> Result<Self> {
+        Ok(Self {
+            delimiter,
+            sniffed: true,
+            ..Self::new(path)?
         })
     }
 
-    fn create_reader(&self) -> Result<Reader<BufReader<File>>> {
+    /// Force a source encoding instead of detecting it
+    pub fn set_encoding(&mut self, encoding: &'static Encoding) {
+        self.encoding = Some(encoding);
+        self.reader = None;
+    }
+
+    /// Choose how the header row is located
+    pub fn set_header_mode(&mut self, mode: HeaderMode) {
+        self.header_mode = mode;
+        self.layout = None;
+        self.reader = None;
+    }
+
+    /// Choose how many stacked header rows make up the column names
+    pub fn set_header_rows(&mut self, rows: usize) {
+        self.header_rows = rows;
+        self.layout = None;
+        self.reader = None;
+    }
+
+    /// Open a reader over all records, header included
+    fn create_reader(&mut self) -> Result<DecodedCsvReader> {
+        let encoding = match self.encoding {
+            Some(encoding) => encoding,
+            None => *self.encoding.insert(detect_file_encoding(&self.path)?),
+        };
+
         let file = File::open(&self.path)?;
-        let reader = BufReader::new(file);
+        // A BOM, if present, takes precedence and is stripped
+        let reader = DecodeReaderBytesBuilder::new()
+            .encoding(Some(encoding))
+            .bom_override(true)
+            .build(file);
         let csv_reader = ReaderBuilder::new()
             .delimiter(self.delimiter)
-            .has_headers(true)
+            .has_headers(false)
             .flexible(true)
             .from_reader(reader);
         Ok(csv_reader)
     }
-}
 
-impl DataReader for CsvReader {
-    fn read(&mut self, options: &ProcessingOptions) -> Result<Vec<SheetSchema>> {
-        let (sheets, _recode_registry) = self.read_with_recoding(options)?;
-        Ok(sheets)
+    /// Header layout and column names, resolved from the leading records on first use
+    fn layout(&mut self) -> Result<&ResolvedHeader> {
+        if self.layout.is_none() {
+            let mut reader = self.create_reader()?;
+            let (layout, _sample) = read_layout(&mut reader, self.header_mode, self.header_rows)?;
+            self.layout = Some(layout);
+        }
+        Ok(self.layout.as_ref().expect("layout was just resolved"))
     }
 
-    fn read_with_recoding(&mut self, options: &ProcessingOptions) -> Result<(Vec<SheetSchema>, RecodeRegistry)> {
-        let mut reader = self.create_reader()?;
-
-        // Get headers
-        let headers: Vec<String> = reader
-            .headers()?
-            .iter()
-            .map(|h| h.to_string())
-            .collect();
-
-        let num_cols = headers.len();
-
-        // Check column names and set up recoding registry
-        let mut recode_registry = RecodeRegistry::new();
-        let column_checks: Vec<_> = headers.iter().map(|h| check_column_name(h)).collect();
+    /// The first few kilobytes of the file, for spotting export banners
+    fn leading_text(&self) -> Result<String> {
+        let mut bytes = Vec::with_capacity(PROVENANCE_SAMPLE_BYTES);
+        File::open(&self.path)?
+            .take(PROVENANCE_SAMPLE_BYTES as u64)
+            .read_to_end(&mut bytes)?;
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
 
-        for (col_idx, check) in column_checks.iter().enumerate() {
-            if check.classification == Classification::Recode {
-                // Determine prefix based on column name
-                let prefix = determine_recode_prefix(&headers[col_idx]);
-                recode_registry.register_column(col_idx, &headers[col_idx], &prefix);
+    /// The underlying csv reader, positioned at the first data row on first use
+    fn reader(&mut self) -> Result<&mut DecodedCsvReader> {
+        if self.reader.is_none() {
+            let data_start = self.layout()?.0.data_start;
+            let mut reader = self.create_reader()?;
+            let mut record = StringRecord::new();
+            for _ in 0..data_start {
+                if !reader.read_record(&mut record)? {
+                    break;
+                }
             }
+            self.reader = Some(reader);
         }
+        Ok(self.reader.as_mut().expect("reader was just opened"))
+    }
+}
 
-        // Initialize trackers for each column
-        let mut type_inferencers: Vec<TypeInferencer> =
-            (0..num_cols).map(|_| TypeInferencer::new()).collect();
-        let mut stat_trackers: Vec<ColumnStatTracker> = (0..num_cols)
-            .map(|_| ColumnStatTracker::new(MAX_UNIQUE_VALUES))
-            .collect();
+/// Resolve the header layout and column names from the leading records. Also
+/// returns those records, header rows included.
+fn read_layout<R: Read>(
+    reader: &mut Reader<R>,
+    header_mode: HeaderMode,
+    header_rows: usize,
+) -> Result<(ResolvedHeader, Vec<Vec<String>>)> {
+    let mut sample = Vec::new();
+    let mut record = StringRecord::new();
+    while sample.len() < sample_rows_needed(header_mode, header_rows)
+        && reader.read_record(&mut record)?
+    {
+        sample.push(record.iter().map(|f| f.to_string()).collect::<Vec<_>>());
+    }
 
-        // First pass: collect samples for type inference
-        let mut row_count: u64 = 0;
+    let layout = resolve_layout(header_mode, header_rows, &sample);
+    // Plain text keeps no merged spans, so empty upper header cells are
+    // taken to continue the label to their left
+    let header = header_names(&layout, &sample, true);
+    let width = sample[layout.data_start.min(sample.len())..]
+        .iter()
+        .map(|r| r.len())
+        .max()
+        .unwrap_or(0);
+    Ok(((layout, complete_headers(header, width)), sample))
+}
 
-        for result in reader.records() {
-            let record = result?;
-            row_count += 1;
+impl StreamingDataReader for CsvReader {
+    fn headers(&mut self) -> Result<Vec<String>> {
+        Ok(self.layout()?.1.clone())
+    }
 
-            for (col_idx, field) in record.iter().enumerate() {
-                if col_idx >= num_cols {
-                    continue;
-                }
+    fn next_batch(&mut self, max_rows: usize) -> Result<Option<RowBatch>> {
+        let reader = self.reader()?;
+        let mut batch = Vec::new();
+        let mut record = StringRecord::new();
 
-                type_inferencers[col_idx].observe(field);
-            }
+        while batch.len() < max_rows && reader.read_record(&mut record)? {
+            batch.push(record.iter().map(|f| f.to_string()).collect());
         }
 
-        // Finalize type inference
-        for inf in &mut type_inferencers {
-            inf.finalize_initial_inference();
+        if batch.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(batch))
         }
+    }
 
-        // Second pass: collect statistics (with recoding)
-        let mut reader = self.create_reader()?;
-        for result in reader.records() {
-            let record = result?;
+    fn rewind(&mut self) -> Result<()> {
+        self.reader = None;
+        self.reader()?;
+        Ok(())
+    }
+}
 
-            for (col_idx, field) in record.iter().enumerate() {
-                if col_idx >= num_cols {
-                    continue;
-                }
+impl DataReader for CsvReader {
+    fn read(&mut self, options: &ProcessingOptions) -> Result<Vec<SheetSchema>> {
+        let (sheets, _recode_registry) = self.read_with_recoding(options, &mut NullAuditSink)?;
+        Ok(sheets)
+    }
 
-                let dtype = type_inferencers[col_idx].inferred_type();
-
-                if is_missing(field) {
-                    stat_trackers[col_idx].update_missing();
-                } else {
-                    // Recode values if this column is marked for recoding
-                    let value_to_track = if recode_registry.is_recoded(col_idx) {
-                        recode_registry.recode(col_idx, field).unwrap_or_else(|| field.to_string())
-                    } else {
-                        field.to_string()
-                    };
-
-                    match dtype {
-                        DType::Integer | DType::Numeric => {
-                            if let Some(num) = parse_numeric(field) {
-                                stat_trackers[col_idx].update_numeric(num, &value_to_track);
-                            } else {
-                                stat_trackers[col_idx].update_string(&value_to_track);
-                            }
-                        }
-                        _ => {
-                            stat_trackers[col_idx].update_string(&value_to_track);
-                        }
-                    }
-                }
-            }
+    fn read_with_recoding(
+        &mut self,
+        options: &ProcessingOptions,
+        audit: &mut dyn AuditSink,
+    ) -> Result<(Vec<SheetSchema>, RecodeRegistry)> {
+        let file_name = crate::paths::file_name_lossy(&self.path);
+
+        if let Some(label) = &options.encoding {
+            self.set_encoding(resolve_encoding(label)?);
         }
+        self.set_header_mode(options.header);
+        self.set_header_rows(options.header_rows);
 
-        // Build column schemas
-        let mut columns: Vec<ColumnSchema> = Vec::with_capacity(num_cols);
-
-        for (col_idx, header) in headers.iter().enumerate() {
-            let name_check = &column_checks[col_idx];
-            let dtype = type_inferencers[col_idx].inferred_type();
-            let tracker = &stat_trackers[col_idx];
-
-            // Determine classification
-            let mut classification = name_check.classification.clone();
-            if tracker.unique_tracker.is_high_cardinality()
-                && classification != Classification::Recode
-                && classification != Classification::Phi
-            {
-                classification = Classification::HighCardinality;
-            }
+        let headers = self.layout()?.1.clone();
+        self.file_details = sniff_source(&self.leading_text()?, &headers);
 
-            // Build column name SafeValue
-            let name_value = if classification == Classification::Phi {
-                SafeValue::Suppressed {
-                    reason: "Column name matches PHI pattern".to_string(),
-                }
-            } else {
-                SafeValue::from_string(header, "Column name too long")
-            };
+        self.rewind()?;
+        let (sheet, recode_registry) = profile_stream(self, &file_name, 0, options, audit)?;
 
-            let mut col_schema = ColumnSchema::new(name_value, col_idx, dtype);
-            col_schema.classification = classification.clone();
+        Ok((vec![sheet], recode_registry))
+    }
 
-            // Add warnings
-            if let Some(warning) = &name_check.warning {
-                col_schema.warnings.push(warning.clone());
-            }
+    fn encoding(&self) -> Option<String> {
+        self.encoding.map(|e| e.name().to_string())
+    }
 
-            // Build stats
-            let mut stats = ColumnStats::default();
-            let non_missing_count = tracker.welford.count();
-            stats.count = Some(safe_count(non_missing_count, options.bucket_counts));
-            stats.missing_count = Some(safe_count(tracker.missing_count, options.bucket_counts));
-
-            match dtype {
-                DType::Integer | DType::Numeric => {
-                    if let Some(min) = tracker.welford.min() {
-                        stats.min = Some(SafeValue::Float(min));
-                    }
-                    if let Some(max) = tracker.welford.max() {
-                        stats.max = Some(SafeValue::Float(max));
-                    }
-                    stats.mean = tracker.welford.mean();
-                    stats.std_dev = tracker.welford.std_dev();
-                    stats.median = tracker.p2_median.quantile();
-                }
-                _ => {}
-            }
+    fn file_details(&self) -> Option<FileDetails> {
+        let mut details = self.file_details.clone();
+        if self.sniffed {
+            details.get_or_insert_with(FileDetails::default).delimiter =
+                Some(char::from(self.delimiter).to_string());
+        }
+        details
+    }
+}
 
-            // Unique count
-            let unique_count = tracker.unique_tracker.unique_count() as u64;
-            if tracker.unique_tracker.is_high_cardinality() && classification != Classification::Recode {
-                stats.unique_count = Some(SafeValue::Suppressed {
-                    reason: "High cardinality; exact count suppressed".to_string(),
-                });
-            } else if options.bucket_counts {
-                stats.unique_count =
-                    Some(SafeValue::ShortString(bucket_count(unique_count).to_string()));
-            } else {
-                stats.unique_count = Some(SafeValue::Integer(unique_count as i64));
-            }
+/// CSV/TSV reader over input that can only be read once (stdin). The records
+/// are parsed as they arrive, so the data is profiled in a single pass and
+/// never written to disk.
+pub struct CsvStreamReader {
+    name: String,
+    delimiter: u8,
+    /// Consumed when the stream is opened
+    input: Option<Box<dyn Read>>,
+    encoding: Option<&'static Encoding>,
+    header_mode: HeaderMode,
+    header_rows: usize,
+    layout: Option<ResolvedHeader>,
+    reader: Option<Reader<DecodedStream>>,
+    /// Data records read ahead while resolving the header
+    pending: std::vec::IntoIter<Vec<String>>,
+    file_details: Option<FileDetails>,
+}
 
-            col_schema.stats = Some(stats);
-
-            // Build unique values list
-            if classification == Classification::Recode {
-                // For recoded columns, show the recoded values
-                if let Some(recoded_values) = recode_registry.get_recoded_values(col_idx) {
-                    let safe_values: Vec<SafeValue> = recoded_values
-                        .into_iter()
-                        .map(SafeValue::ShortString)
-                        .collect();
-                    if !safe_values.is_empty() {
-                        col_schema.unique_values = Some(safe_values);
-                    }
-                }
-            } else if classification == Classification::Safe || classification == Classification::Warning {
-                if let Some(values) = tracker.unique_tracker.values() {
-                    let mut safe_values: Vec<SafeValue> = Vec::new();
-                    let counts = tracker.unique_tracker.value_counts();
-
-                    for value in values {
-                        let count = counts
-                            .and_then(|c| c.get(value))
-                            .copied()
-                            .unwrap_or(1);
-
-                        if count >= options.k_anonymity {
-                            // Check value-level privacy
-                            let value_check = crate::privacy::check_value_pattern(value);
-                            if !value_check.is_phi && value.len() <= 32 {
-                                safe_values.push(SafeValue::ShortString(value.clone()));
-                            }
-                        }
-                    }
-
-                    if !safe_values.is_empty() {
-                        col_schema.unique_values = Some(safe_values);
-                    }
-                }
-            }
+impl CsvStreamReader {
+    /// Create a reader over `input`, reported under `name`
+    pub fn new(input: Box<dyn Read>, name: &str, delimiter: u8) -> Self {
+        Self {
+            name: name.to_string(),
+            delimiter,
+            input: Some(input),
+            encoding: None,
+            header_mode: HeaderMode::Auto,
+            header_rows: 1,
+            layout: None,
+            reader: None,
+            pending: Vec::new().into_iter(),
+            file_details: None,
+        }
+    }
 
-            columns.push(col_schema);
+    /// Start reading: detect the encoding and resolve the header
+    fn open(&mut self) -> Result<()> {
+        if self.layout.is_some() {
+            return Ok(());
         }
+        let input = self
+            .input
+            .take()
+            .ok_or_else(|| Error::InvalidInput("input was already read".to_string()))?;
+        let (encoding, leading, decoded) =
+            decode_stream(input, self.encoding, PROVENANCE_SAMPLE_BYTES)?;
+        self.encoding = Some(encoding);
+
+        let mut reader = ReaderBuilder::new()
+            .delimiter(self.delimiter)
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(decoded);
+        let (layout, sample) = read_layout(&mut reader, self.header_mode, self.header_rows)?;
+        self.file_details = sniff_source(&String::from_utf8_lossy(&leading), &layout.1);
+        self.pending = sample
+            .into_iter()
+            .skip(layout.0.data_start)
+            .collect::<Vec<_>>()
+            .into_iter();
+        self.layout = Some(layout);
+        self.reader = Some(reader);
+        Ok(())
+    }
+}
 
-        // Build sheet schema
-        let file_name = self
-            .path
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("unknown")
-            .to_string();
+impl StreamingDataReader for CsvStreamReader {
+    fn headers(&mut self) -> Result<Vec<String>> {
+        self.open()?;
+        Ok(self.layout.as_ref().expect("stream was just opened").1.clone())
+    }
 
-        let mut sheet = SheetSchema::new(file_name, 0);
-        sheet.row_count = safe_count(row_count, options.bucket_counts);
-        sheet.columns = columns;
+    fn next_batch(&mut self, max_rows: usize) -> Result<Option<RowBatch>> {
+        self.open()?;
+        let mut batch: Vec<Vec<String>> = self.pending.by_ref().take(max_rows).collect();
+        let reader = self.reader.as_mut().expect("stream was just opened");
+        let mut record = StringRecord::new();
+        while batch.len() < max_rows && reader.read_record(&mut record)? {
+            batch.push(record.iter().map(|f| f.to_string()).collect());
+        }
 
-        Ok((vec![sheet], recode_registry))
+        if batch.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(batch))
+        }
+    }
+
+    fn rewind(&mut self) -> Result<()> {
+        Err(Error::InvalidInput(
+            "input that can only be read once cannot be rewound".to_string(),
+        ))
+    }
+
+    fn rewindable(&self) -> bool {
+        false
     }
 }
 
-/// Determine the appropriate prefix for recoding based on column name
-fn determine_recode_prefix(column_name: &str) -> String {
-    let lower = column_name.to_lowercase();
-    if lower.contains("hospital") {
-        "Hospital".to_string()
-    } else if lower.contains("clinic") {
-        "Clinic".to_string()
-    } else if lower.contains("facility") {
-        "Facility".to_string()
-    } else if lower.contains("center") || lower.contains("centre") {
-        "Center".to_string()
-    } else if lower.contains("location") {
-        "Location".to_string()
-    } else {
-        "Site".to_string()
+impl DataReader for CsvStreamReader {
+    fn read(&mut self, options: &ProcessingOptions) -> Result<Vec<SheetSchema>> {
+        let (sheets, _recode_registry) = self.read_with_recoding(options, &mut NullAuditSink)?;
+        Ok(sheets)
+    }
+
+    fn read_with_recoding(
+        &mut self,
+        options: &ProcessingOptions,
+        audit: &mut dyn AuditSink,
+    ) -> Result<(Vec<SheetSchema>, RecodeRegistry)> {
+        if let Some(label) = &options.encoding {
+            self.encoding = Some(resolve_encoding(label)?);
+        }
+        self.header_mode = options.header;
+        self.header_rows = options.header_rows;
+
+        let name = self.name.clone();
+        let (sheet, recode_registry) = profile_stream(self, &name, 0, options, audit)?;
+        Ok((vec![sheet], recode_registry))
+    }
+
+    fn encoding(&self) -> Option<String> {
+        self.encoding.map(|e| e.name().to_string())
+    }
+
+    fn file_details(&self) -> Option<FileDetails> {
+        self.file_details.clone()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::readers::provenance::SourceSystem;
+    use crate::types::{Classification, DType, SafeValue};
     use std::io::Write;
     use tempfile::NamedTempFile;
 
@@ -359,4 +452,238 @@ mod tests {
         assert_eq!(stats.count, Some(SafeValue::Integer(3))); // 1, 2, 3
         assert_eq!(stats.missing_count, Some(SafeValue::Integer(2))); // NA and empty
     }
+
+    #[test]
+    fn test_value_frequencies_suppress_small_cells() {
+        let mut csv_content = String::from("arm,dose\n");
+        for _ in 0..6 {
+            csv_content.push_str("Treatment,10\n");
+        }
+        for _ in 0..5 {
+            csv_content.push_str("Placebo,0\n");
+            // A real category that happens to read like a placeholder
+            csv_content.push_str("(suppressed),0\n");
+        }
+        csv_content.push_str("Crossover,5\n");
+        let file = create_test_csv(&csv_content);
+
+        let mut reader = CsvReader::new(file.path()).unwrap();
+        let options = ProcessingOptions {
+            bucket_counts: false,
+            ..ProcessingOptions::default()
+        };
+        let sheets = reader.read(&options).unwrap();
+
+        let stats = sheets[0].columns[0].stats.as_ref().unwrap();
+        let freqs = stats.value_frequencies.as_ref().unwrap();
+        assert_eq!(freqs.get("Treatment"), Some(&SafeValue::Integer(6)));
+        assert_eq!(freqs.get("Placebo"), Some(&SafeValue::Integer(5)));
+        assert_eq!(freqs.get("(suppressed)"), Some(&SafeValue::Integer(5)));
+        assert!(!freqs.contains_key("Crossover"));
+        assert_eq!(stats.suppressed_cells, Some(SafeValue::Integer(1)));
+
+        // Numeric columns get summary statistics, not a frequency table
+        assert_eq!(sheets[0].columns[1].dtype, DType::Integer);
+        let stats = sheets[0].columns[1].stats.as_ref().unwrap();
+        assert!(stats.value_frequencies.is_none());
+    }
+
+    #[test]
+    fn test_streaming_batches() {
+        let file = create_test_csv("a,b\n1,x\n2,y\n3,z\n");
+
+        let mut reader = CsvReader::new(file.path()).unwrap();
+        assert_eq!(reader.headers().unwrap(), vec!["a", "b"]);
+        assert_eq!(reader.next_batch(2).unwrap().unwrap().len(), 2);
+        assert_eq!(reader.next_batch(2).unwrap().unwrap(), vec![vec!["3", "z"]]);
+        assert!(reader.next_batch(2).unwrap().is_none());
+
+        reader.rewind().unwrap();
+        assert_eq!(reader.next_batch(10).unwrap().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_stream_reader_single_pass() {
+        use crate::types::SINGLE_PASS_LEADING_ROWS;
+
+        // Past the rows held for the first pass, the code column turns to text
+        // and rows repeat both a held row and each other
+        let mut content = "id,code\n".to_string();
+        for i in 0..SINGLE_PASS_LEADING_ROWS {
+            content.push_str(&format!("{},7\n", i));
+        }
+        content.push_str("5,7\nx,A1\nx,A1\n");
+
+        let input = Box::new(std::io::Cursor::new(content));
+        let mut reader = CsvStreamReader::new(input, "s.csv", b',');
+        assert!(!reader.rewindable());
+        let options = ProcessingOptions {
+            bucket_counts: false,
+            ..ProcessingOptions::default()
+        };
+        let sheets = reader.read(&options).unwrap();
+
+        assert_eq!(sheets[0].name, "s.csv");
+        let rows = SINGLE_PASS_LEADING_ROWS as i64 + 3;
+        assert_eq!(sheets[0].row_count, SafeValue::Integer(rows));
+        assert_eq!(sheets[0].duplicate_rows, Some(SafeValue::Integer(2)));
+        assert_eq!(sheets[0].columns[0].dtype, DType::String);
+        assert_eq!(sheets[0].columns[1].dtype, DType::String);
+        assert!(reader.rewind().is_err());
+    }
+
+    #[test]
+    fn test_stream_reader_sampled() {
+        let content = (0..100).fold("id\n".to_string(), |acc, i| acc + &format!("{}\n", i));
+        let input = Box::new(std::io::Cursor::new(content));
+        let mut reader = CsvStreamReader::new(input, "s.csv", b',');
+        let options = ProcessingOptions {
+            bucket_counts: false,
+            sample_rows: Some(10),
+            ..ProcessingOptions::default()
+        };
+        let sheets = reader.read(&options).unwrap();
+
+        assert_eq!(sheets[0].row_count, SafeValue::Integer(10));
+        let sample = sheets[0].sample.as_ref().unwrap();
+        assert_eq!(sample.table_rows, SafeValue::Integer(100));
+    }
+
+    fn create_test_csv_bytes(content: &[u8]) -> NamedTempFile {
+        let mut file = NamedTempFile::with_suffix(".csv").unwrap();
+        file.write_all(content).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_windows_1252_transcoded() {
+        let mut content = b"ville\n".to_vec();
+        for _ in 0..5 {
+            content.extend_from_slice(b"Montr\xe9al\nQu\xe9bec\n");
+        }
+        let file = create_test_csv_bytes(&content);
+
+        let mut reader = CsvReader::new(file.path()).unwrap();
+        let sheets = reader.read(&ProcessingOptions::default()).unwrap();
+
+        assert_eq!(reader.encoding().as_deref(), Some("windows-1252"));
+        let values = sheets[0].columns[0].unique_values.as_ref().unwrap();
+        assert!(values.contains(&SafeValue::ShortString("Montréal".to_string())));
+    }
+
+    #[test]
+    fn test_encoding_override() {
+        let file = create_test_csv("ville\nLévis\n");
+
+        let mut reader = CsvReader::new(file.path()).unwrap();
+        let options = ProcessingOptions {
+            encoding: Some("latin1".to_string()),
+            ..ProcessingOptions::default()
+        };
+        reader.read(&options).unwrap();
+        assert_eq!(reader.encoding().as_deref(), Some("windows-1252"));
+
+        let options = ProcessingOptions {
+            encoding: Some("not-an-encoding".to_string()),
+            ..ProcessingOptions::default()
+        };
+        assert!(reader.read(&options).is_err());
+    }
+
+    #[test]
+    fn test_utf8_bom_stripped() {
+        let file = create_test_csv_bytes(b"\xEF\xBB\xBFage\n30\n");
+
+        let mut reader = CsvReader::new(file.path()).unwrap();
+        assert_eq!(reader.headers().unwrap(), vec!["age"]);
+        assert_eq!(reader.encoding().as_deref(), Some("UTF-8"));
+    }
+
+    #[test]
+    fn test_header_after_title_lines() {
+        let file = create_test_csv("Trial export,,\n,,\nid,age,arm\n1,30,A\n2,41,B\n");
+
+        let mut reader = CsvReader::new(file.path()).unwrap();
+        assert_eq!(reader.headers().unwrap(), vec!["id", "age", "arm"]);
+        assert_eq!(reader.next_batch(10).unwrap().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_likely_source_sniffed() {
+        let file = create_test_csv("record_id,age,baseline_complete\n1,30,2\n2,41,2\n");
+        let mut reader = CsvReader::new(file.path()).unwrap();
+        reader.read(&ProcessingOptions::default()).unwrap();
+        let details = reader.file_details().unwrap();
+        assert_eq!(details.likely_source, Some(SourceSystem::Redcap));
+
+        let file = create_test_csv("id,age\n1,30\n2,41\n");
+        let mut reader = CsvReader::new(file.path()).unwrap();
+        reader.read(&ProcessingOptions::default()).unwrap();
+        assert_eq!(reader.file_details(), None);
+    }
+
+    #[test]
+    fn test_headerless_csv() {
+        let file = create_test_csv("1,30\n2,41\n3,52\n");
+
+        let mut reader = CsvReader::new(file.path()).unwrap();
+        let sheets = reader.read(&ProcessingOptions::default()).unwrap();
+        let names: Vec<String> = sheets[0].columns.iter().map(|c| c.name.to_string()).collect();
+        assert_eq!(names, vec!["Column1", "Column2"]);
+        assert_eq!(sheets[0].row_count, SafeValue::ShortString("2-5".to_string()));
+    }
+
+    #[test]
+    fn test_explicit_header_modes() {
+        let file = create_test_csv("name,city\nAlice,Paris\n");
+
+        let mut reader = CsvReader::new(file.path()).unwrap();
+        reader.set_header_mode(HeaderMode::Absent);
+        assert_eq!(reader.headers().unwrap(), vec!["Column1", "Column2"]);
+        assert_eq!(reader.next_batch(10).unwrap().unwrap().len(), 2);
+
+        reader.set_header_mode(HeaderMode::Row(2));
+        assert_eq!(reader.headers().unwrap(), vec!["Alice", "Paris"]);
+        assert!(reader.next_batch(10).unwrap().is_none());
+    }
+
+    proptest::proptest! {
+        #![proptest_config(proptest::prelude::ProptestConfig::with_cases(64))]
+
+        /// Arbitrary bytes are read or refused with an error, never a panic
+        #[test]
+        fn prop_csv_arbitrary_bytes(bytes in proptest::collection::vec(0u8..=255, 0..2048)) {
+            let mut file = NamedTempFile::with_suffix(".csv").unwrap();
+            file.write_all(&bytes).unwrap();
+            if let Ok(mut reader) = CsvReader::new(file.path()) {
+                if let Err(e) = reader.read(&ProcessingOptions::default()) {
+                    proptest::prop_assert!(!e.to_string().is_empty());
+                }
+            }
+        }
+
+        /// Well-formed CSV of any cell text scans, with one column per field
+        #[test]
+        fn prop_csv_quoted_cells(
+            rows in proptest::collection::vec(
+                proptest::collection::vec("[ -~\n\r\té]{0,12}", 3),
+                1..30,
+            )
+        ) {
+            let mut writer = ::csv::Writer::from_writer(Vec::new());
+            writer.write_record(["arm", "visit", "notes"]).unwrap();
+            for row in &rows {
+                writer.write_record(row).unwrap();
+            }
+            let bytes = writer.into_inner().unwrap();
+            let mut file = NamedTempFile::with_suffix(".csv").unwrap();
+            file.write_all(&bytes).unwrap();
+            let options = ProcessingOptions {
+                header: HeaderMode::Row(1),
+                ..ProcessingOptions::default()
+            };
+            let sheets = CsvReader::new(file.path()).unwrap().read(&options).unwrap();
+            proptest::prop_assert_eq!(sheets[0].columns.len(), 3);
+        }
+    }
 }
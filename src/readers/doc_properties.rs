@@ -0,0 +1,180 @@
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use chrono::NaiveDate;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::types::DatePrecision;
+
+/// Largest properties part read; real ones are a few kilobytes
+const MAX_PART_SIZE: u64 = 1 << 20;
+
+/// Properties stored in an Office Open XML workbook's `docProps` parts.
+///
+/// Only whether the name fields are set is kept, never their contents: they
+/// typically hold a person's name or e-mail address.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DocumentProperties {
+    pub has_author: bool,
+    pub has_last_modified_by: bool,
+    pub has_company: bool,
+    pub has_manager: bool,
+    pub created: Option<NaiveDate>,
+    pub modified: Option<NaiveDate>,
+}
+
+static AUTHOR: Lazy<Regex> = Lazy::new(|| element("dc:creator"));
+static LAST_MODIFIED_BY: Lazy<Regex> = Lazy::new(|| element("cp:lastModifiedBy"));
+static CREATED: Lazy<Regex> = Lazy::new(|| element("dcterms:created"));
+static MODIFIED: Lazy<Regex> = Lazy::new(|| element("dcterms:modified"));
+static COMPANY: Lazy<Regex> = Lazy::new(|| element("Company"));
+static MANAGER: Lazy<Regex> = Lazy::new(|| element("Manager"));
+
+/// Text content of a simple XML element (attributes allowed, no nesting)
+fn element(name: &str) -> Regex {
+    Regex::new(&format!(
+        r"<{0}(?:\s[^>]*)?>([^<]*)</{0}>",
+        regex::escape(name)
+    ))
+    .unwrap()
+}
+
+impl DocumentProperties {
+    /// Read the properties of an .xlsx/.xlsm/.xlsb workbook.
+    ///
+    /// Returns `None` for files that are not zip packages (e.g. legacy .xls)
+    /// or have no properties parts.
+    pub fn read(path: &Path) -> Option<Self> {
+        let mut archive = zip::ZipArchive::new(BufReader::new(File::open(path).ok()?)).ok()?;
+        let core = read_part(&mut archive, "docProps/core.xml");
+        let app = read_part(&mut archive, "docProps/app.xml");
+        if core.is_none() && app.is_none() {
+            return None;
+        }
+        Some(Self::parse(
+            core.as_deref().unwrap_or(""),
+            app.as_deref().unwrap_or(""),
+        ))
+    }
+
+    fn parse(core: &str, app: &str) -> Self {
+        let is_set =
+            |re: &Regex, xml: &str| re.captures(xml).is_some_and(|c| !c[1].trim().is_empty());
+        let date = |re: &Regex| {
+            let captures = re.captures(core)?;
+            let text = captures[1].trim();
+            NaiveDate::parse_from_str(text.get(..10)?, "%Y-%m-%d").ok()
+        };
+
+        Self {
+            has_author: is_set(&AUTHOR, core),
+            has_last_modified_by: is_set(&LAST_MODIFIED_BY, core),
+            has_company: is_set(&COMPANY, app),
+            has_manager: is_set(&MANAGER, app),
+            created: date(&CREATED),
+            modified: date(&MODIFIED),
+        }
+    }
+
+    /// Privacy warnings for the manifest; name fields are reported by label only
+    pub fn warnings(&self, precision: DatePrecision) -> Vec<String> {
+        let mut warnings = Vec::new();
+
+        let names: Vec<&str> = [
+            (self.has_author, "author"),
+            (self.has_last_modified_by, "last saved by"),
+            (self.has_company, "company"),
+            (self.has_manager, "manager"),
+        ]
+        .iter()
+        .filter(|(set, _)| *set)
+        .map(|(_, label)| *label)
+        .collect();
+        if !names.is_empty() {
+            warnings.push(format!(
+                "Document properties identify people or organizations ({}); \
+                 remove them before sharing the workbook",
+                names.join(", ")
+            ));
+        }
+
+        let dates: Vec<String> = [("created", self.created), ("last modified", self.modified)]
+            .iter()
+            .filter_map(|(label, date)| date.map(|d| format!("{} {}", label, precision.format(d))))
+            .collect();
+        if !dates.is_empty() {
+            warnings.push(format!(
+                "Document properties: workbook {}",
+                dates.join(", ")
+            ));
+        }
+
+        warnings
+    }
+}
+
+fn read_part<R: std::io::Read + std::io::Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    name: &str,
+) -> Option<String> {
+    let part = archive.by_name(name).ok()?;
+    let mut text = String::new();
+    part.take(MAX_PART_SIZE).read_to_string(&mut text).ok()?;
+    Some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CORE: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<cp:coreProperties xmlns:cp="http://schemas.openxmlformats.org/package/2006/metadata/core-properties" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:dcterms="http://purl.org/dc/terms/" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+<dc:creator>Jane Smith</dc:creator>
+<cp:lastModifiedBy>jsmith@hospital.org</cp:lastModifiedBy>
+<dcterms:created xsi:type="dcterms:W3CDTF">2023-04-02T09:15:00Z</dcterms:created>
+<dcterms:modified xsi:type="dcterms:W3CDTF">2024-06-20T17:40:12Z</dcterms:modified>
+</cp:coreProperties>"#;
+
+    #[test]
+    fn test_parse_properties() {
+        let props = DocumentProperties::parse(CORE, "<Properties><Company></Company></Properties>");
+        assert!(props.has_author && props.has_last_modified_by);
+        assert!(!props.has_company && !props.has_manager);
+        assert_eq!(props.created, NaiveDate::from_ymd_opt(2023, 4, 2));
+
+        let warnings = props.warnings(DatePrecision::Month);
+        assert_eq!(
+            warnings,
+            vec![
+                "Document properties identify people or organizations (author, last saved by); \
+                 remove them before sharing the workbook",
+                "Document properties: workbook created 2023-04, last modified 2024-06",
+            ]
+        );
+        assert!(!warnings.concat().contains("smith"));
+    }
+
+    #[test]
+    fn test_read_workbook_properties() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("props.xlsx");
+        let mut workbook = rust_xlsxwriter::Workbook::new();
+        workbook.set_properties(
+            &rust_xlsxwriter::DocProperties::new()
+                .set_author("Jane Smith")
+                .set_company("Hospital"),
+        );
+        workbook.add_worksheet().write_string(0, 0, "id").unwrap();
+        workbook.save(&path).unwrap();
+
+        let props = DocumentProperties::read(&path).unwrap();
+        assert!(props.has_author && props.has_company);
+        assert!(props.created.is_some());
+
+        let csv = dir.path().join("data.csv");
+        std::fs::write(&csv, "id\n1\n").unwrap();
+        assert_eq!(DocumentProperties::read(&csv), None);
+    }
+}
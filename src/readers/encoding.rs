@@ -0,0 +1,178 @@
+use std::fs::File;
+use std::io::{Chain, Cursor, Read};
+use std::path::Path;
+
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
+use encoding_rs_io::{DecodeReaderBytes, DecodeReaderBytesBuilder};
+
+use crate::error::Error;
+use crate::types::Result;
+
+/// Bytes read from the start of a file to guess its encoding
+pub const SNIFF_BYTES: usize = 64 * 1024;
+
+/// Most rounds of wrong decoding undone by `repair_double_encoding`
+const MAX_REPAIR_ROUNDS: usize = 3;
+
+/// Look up an encoding by WHATWG label ("latin1", "windows-1252", "utf-8", ...)
+pub fn resolve_encoding(label: &str) -> Result<&'static Encoding> {
+    Encoding::for_label(label.trim().as_bytes())
+        .ok_or_else(|| Error::InvalidInput(format!("Unknown encoding: {}", label)))
+}
+
+/// Detect the text encoding of a file from its byte-order mark or leading bytes
+pub fn detect_file_encoding(path: &Path) -> Result<&'static Encoding> {
+    let mut sample = Vec::with_capacity(SNIFF_BYTES);
+    let file = File::open(path)?;
+    file.take(SNIFF_BYTES as u64).read_to_end(&mut sample)?;
+    let complete = sample.len() < SNIFF_BYTES;
+    Ok(detect_encoding(&sample, complete))
+}
+
+/// Input that can only be read once, transcoded to UTF-8
+pub type DecodedStream = DecodeReaderBytes<Chain<Cursor<Vec<u8>>, Box<dyn Read>>, Vec<u8>>;
+
+/// Transcode input that can only be read once (stdin) to UTF-8. Its leading
+/// bytes are read ahead to detect the encoding (unless `encoding` is given)
+/// and then replayed; a copy of up to `keep` of them is returned as well.
+pub fn decode_stream(
+    mut input: Box<dyn Read>,
+    encoding: Option<&'static Encoding>,
+    keep: usize,
+) -> Result<(&'static Encoding, Vec<u8>, DecodedStream)> {
+    let mut sample = Vec::with_capacity(SNIFF_BYTES);
+    input
+        .by_ref()
+        .take(SNIFF_BYTES as u64)
+        .read_to_end(&mut sample)?;
+    let encoding =
+        encoding.unwrap_or_else(|| detect_encoding(&sample, sample.len() < SNIFF_BYTES));
+    let leading = sample[..keep.min(sample.len())].to_vec();
+    // A BOM, if present, takes precedence and is stripped
+    let decoded = DecodeReaderBytesBuilder::new()
+        .encoding(Some(encoding))
+        .bom_override(true)
+        .build(Cursor::new(sample).chain(input));
+    Ok((encoding, leading, decoded))
+}
+
+/// Detect the encoding of a byte sample.
+///
+/// A BOM wins; otherwise valid UTF-8 is taken as UTF-8 and anything else is
+/// handed to chardetng (which recognizes Latin-1/Windows-1252 and friends).
+/// `complete` is false when the sample is a prefix of a longer file.
+pub fn detect_encoding(sample: &[u8], complete: bool) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(sample) {
+        return encoding;
+    }
+
+    match std::str::from_utf8(sample) {
+        Ok(_) => return UTF_8,
+        // A multi-byte character cut off at the end of a prefix is still UTF-8
+        Err(e) if e.error_len().is_none() && !complete => return UTF_8,
+        Err(_) => {}
+    }
+
+    let mut detector = EncodingDetector::new();
+    detector.feed(sample, complete);
+    detector.guess(None, true)
+}
+
+/// Repair text that was UTF-8, wrongly decoded as Windows-1252 or Latin-1 and
+/// stored again ("PrÃ©nom" for "Prénom"), undoing up to three such rounds.
+/// `None` if the text does not look double-encoded.
+///
+/// Each round maps the characters back to the single bytes they were decoded
+/// from, and is undone only if those bytes are valid UTF-8. Ordinary accented
+/// text ("Éric" is `C9 72 69 63`) never is.
+pub fn repair_double_encoding(text: &str) -> Option<String> {
+    let mut repaired = text.to_string();
+    for _ in 0..MAX_REPAIR_ROUNDS {
+        match undo_wrong_decoding(&repaired) {
+            Some(text) => repaired = text,
+            None => break,
+        }
+    }
+    (repaired != text).then_some(repaired)
+}
+
+fn undo_wrong_decoding(text: &str) -> Option<String> {
+    if text.is_ascii() {
+        return None;
+    }
+    let mut bytes = Vec::with_capacity(text.len());
+    for c in text.chars() {
+        // Latin-1 maps every byte to the code point of the same value;
+        // Windows-1252 puts other characters (€, ‚, ƒ, ...) on 0x80-0x9F
+        if let Ok(byte) = u8::try_from(u32::from(c)) {
+            bytes.push(byte);
+            continue;
+        }
+        let mut buf = [0; 4];
+        let (encoded, _, unmappable) = WINDOWS_1252.encode(c.encode_utf8(&mut buf));
+        if unmappable || encoded.len() != 1 {
+            return None;
+        }
+        bytes.push(encoded[0]);
+    }
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_utf8() {
+        assert_eq!(detect_encoding("nom,âge\nÉlise,30\n".as_bytes(), true), UTF_8);
+        assert_eq!(detect_encoding(b"plain ascii", true), UTF_8);
+    }
+
+    #[test]
+    fn test_detect_bom() {
+        assert_eq!(detect_encoding(b"\xEF\xBB\xBFa,b\n", true), UTF_8);
+        assert_eq!(
+            detect_encoding(b"\xFF\xFEa\x00", true),
+            encoding_rs::UTF_16LE
+        );
+    }
+
+    #[test]
+    fn test_detect_windows_1252() {
+        // "Hôpital Montréal,Québec" in Windows-1252
+        let sample = b"site,ville\nH\xf4pital Montr\xe9al,Qu\xe9bec\nH\xf4tel-Dieu,L\xe9vis\n";
+        assert_eq!(detect_encoding(sample, true), encoding_rs::WINDOWS_1252);
+    }
+
+    #[test]
+    fn test_truncated_utf8_prefix() {
+        let text = "é".as_bytes();
+        assert_eq!(detect_encoding(&text[..1], false), UTF_8);
+    }
+
+    #[test]
+    fn test_resolve_encoding() {
+        assert_eq!(resolve_encoding("latin1").unwrap(), encoding_rs::WINDOWS_1252);
+        assert_eq!(resolve_encoding("UTF-8").unwrap(), UTF_8);
+        assert!(resolve_encoding("klingon").is_err());
+    }
+
+    #[test]
+    fn test_repair_double_encoding() {
+        assert_eq!(repair_double_encoding("PrÃ©nom").as_deref(), Some("Prénom"));
+        assert_eq!(repair_double_encoding("Ã¢ge").as_deref(), Some("âge"));
+        // The Windows-1252 characters on 0x80-0x9F, and Latin-1's C1 controls
+        assert_eq!(repair_double_encoding("Stra\u{c3}\u{178}e").as_deref(), Some("Straße"));
+        let garbled = "N\u{c3}\u{bb}m\u{c3}\u{a9}ro";
+        assert_eq!(repair_double_encoding(garbled).as_deref(), Some("Nûméro"));
+        assert_eq!(repair_double_encoding("Ã\u{89}tat").as_deref(), Some("État"));
+        // Twice garbled
+        assert_eq!(repair_double_encoding("PrÃƒÂ©nom").as_deref(), Some("Prénom"));
+
+        assert_eq!(repair_double_encoding("Prénom"), None);
+        assert_eq!(repair_double_encoding("Éric"), None);
+        assert_eq!(repair_double_encoding("patient_id"), None);
+        assert_eq!(repair_double_encoding("名前"), None);
+    }
+}
@@ -1,30 +1,83 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::panic::{catch_unwind, AssertUnwindSafe};
 use std::path::{Path, PathBuf};
 
-use calamine::{open_workbook_auto, Data, Reader, Sheets};
-
-use crate::inference::{is_missing, TypeInferencer};
-use crate::privacy::{bucket_count, check_column_name, safe_count};
-use crate::stats::ColumnStatTracker;
+use calamine::{
+    open_workbook, open_workbook_auto, Data, ExcelDateTime, Range, Reader, SheetVisible, Sheets,
+    Xls, Xlsb, Xlsx,
+};
+use chrono::{DurationRound, NaiveTime};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::audit::{AuditSink, NullAuditSink};
+use crate::error::Error;
+use crate::inference::is_missing;
+use crate::paths;
+use crate::privacy::file_names::find_phi_in_name;
+use crate::privacy::{find_phi_in_text, safe_count, RecodeRegistry};
+use crate::sanitize::sanitize_text;
 use crate::types::{
-    Classification, ColumnSchema, ColumnStats, DType, ProcessingOptions, Result, SafeValue,
-    SheetSchema, MAX_UNIQUE_VALUES,
+    EmbeddedTextScan, FileFormat, HeaderMode, ProcessingOptions, Result, SafeValue, SheetSchema,
+    SheetVisibility,
 };
 
-use super::DataReader;
+use super::doc_properties::DocumentProperties;
+use super::header::{
+    complete_headers, header_names, resolve_layout, sample_rows_needed, HeaderLayout,
+};
+use super::profile::profile_stream;
+use super::signature::{detect_container, Container};
+use super::workbook_parts::{MergedCells, WorkbookParts};
+use super::{DataReader, RowBatch, StreamingDataReader};
 
 /// Excel file reader (supports .xlsx, .xls, .xlsm, .xlsb)
 pub struct ExcelReader {
     path: PathBuf,
+    /// Workbook-level warnings gathered while reading: skipped hidden sheets,
+    /// defined names and external links that belong to no scanned sheet
+    workbook_warnings: Vec<String>,
 }
 
 impl ExcelReader {
     pub fn new(path: &Path) -> Result<Self> {
         Ok(Self {
             path: path.to_path_buf(),
+            workbook_warnings: Vec::new(),
         })
     }
 
+    /// Open the workbook by its extension, or by its container when the
+    /// extension names no workbook format (a workbook saved as `.csv`)
+    fn open(&self) -> Result<Sheets<BufReader<File>>> {
+        let ext = paths::extension_lossy(&self.path);
+        if FileFormat::from_extension(&ext) == Some(FileFormat::Excel) {
+            return Ok(open_workbook_auto(&self.path)?);
+        }
+        if detect_container(&self.path) == Some(Container::Ole2) {
+            let workbook = open_workbook::<Xls<_>, _>(&self.path).map_err(calamine::Error::Xls)?;
+            return Ok(Sheets::Xls(workbook));
+        }
+        // Zip containers are xlsx, or else xlsb
+        match open_workbook::<Xlsx<_>, _>(&self.path) {
+            Ok(workbook) => Ok(Sheets::Xlsx(workbook)),
+            Err(_) => {
+                let workbook =
+                    open_workbook::<Xlsb<_>, _>(&self.path).map_err(calamine::Error::Xlsb)?;
+                Ok(Sheets::Xlsb(workbook))
+            }
+        }
+    }
+
     /// Convert Excel Data to string representation
+    fn malformed(&self) -> Error {
+        Error::InvalidInput(format!(
+            "{} is a damaged workbook: its contents could not be parsed",
+            paths::display_path(&self.path)
+        ))
+    }
+
     fn data_to_string(dt: &Data) -> String {
         match dt {
             Data::Empty => String::new(),
@@ -32,25 +85,39 @@ impl ExcelReader {
             Data::Float(f) => f.to_string(),
             Data::Int(i) => i.to_string(),
             Data::Bool(b) => b.to_string(),
-            Data::DateTime(d) => {
-                // Convert ExcelDateTime to string using its as_f64 representation
-                Self::excel_serial_to_date_string(d.as_f64())
-            }
+            Data::DateTime(d) => Self::excel_datetime_to_string(d),
             Data::DateTimeIso(s) => s.clone(),
             Data::DurationIso(s) => s.clone(),
-            Data::Error(e) => format!("#{:?}", e),
+            Data::Error(e) => e.to_string(),
         }
     }
 
-    /// Convert Excel serial date to ISO date string
-    fn excel_serial_to_date_string(serial: f64) -> String {
-        // Excel epoch is 1899-12-30 (with the 1900 leap year bug)
-        let days = serial as i64;
-        let base = chrono::NaiveDate::from_ymd_opt(1899, 12, 30).unwrap();
-        if let Some(date) = base.checked_add_signed(chrono::Duration::days(days)) {
-            date.format("%Y-%m-%d").to_string()
+    /// Convert an Excel date/time cell to ISO text: a date, a datetime when it
+    /// has a time of day, or a time alone. Serials count from 1899-12-30, or
+    /// from 1904-01-01 in workbooks using the 1904 date system.
+    fn excel_datetime_to_string(dt: &ExcelDateTime) -> String {
+        let serial = dt.as_f64();
+        if dt.is_duration() {
+            let seconds = (serial * 86_400.0).round() as i64;
+            return format!(
+                "{}:{:02}:{:02}",
+                seconds / 3600,
+                seconds % 3600 / 60,
+                seconds % 60
+            );
+        }
+        let Some(datetime) = dt
+            .as_datetime()
+            .and_then(|d| d.duration_round(chrono::Duration::seconds(1)).ok())
+        else {
+            return serial.to_string();
+        };
+        if (0.0..1.0).contains(&serial) {
+            datetime.format("%H:%M:%S").to_string()
+        } else if datetime.time() == NaiveTime::MIN {
+            datetime.format("%Y-%m-%d").to_string()
         } else {
-            serial.to_string()
+            datetime.format("%Y-%m-%dT%H:%M:%S").to_string()
         }
     }
 
@@ -64,27 +131,12 @@ impl ExcelReader {
         }
     }
 
-    /// Get numeric value from Data if applicable
-    fn get_numeric_value(dt: &Data) -> Option<f64> {
-        match dt {
-            Data::Float(f) => Some(*f),
-            Data::Int(i) => Some(*i as f64),
-            Data::DateTime(d) => Some(d.as_f64()),
-            _ => None,
-        }
-    }
-
-    /// Infer type from Data
-    fn infer_type_from_data(dt: &Data) -> Option<DType> {
-        match dt {
-            Data::Empty => None,
-            Data::String(_) => Some(DType::String),
-            Data::Float(_) => Some(DType::Numeric),
-            Data::Int(_) => Some(DType::Integer),
-            Data::Bool(_) => Some(DType::Boolean),
-            Data::DateTime(_) | Data::DateTimeIso(_) => Some(DType::Date),
-            Data::DurationIso(_) => Some(DType::Numeric),
-            Data::Error(_) => None,
+    /// Convert a cell for the profiler, mapping missing cells (including errors) to ""
+    fn cell_to_string(dt: &Data) -> String {
+        if Self::is_missing_data(dt) {
+            String::new()
+        } else {
+            Self::data_to_string(dt)
         }
     }
 
@@ -93,203 +145,415 @@ impl ExcelReader {
         workbook: &mut Sheets<std::io::BufReader<std::fs::File>>,
         sheet_name: &str,
         sheet_idx: usize,
+        parts: &WorkbookParts,
         options: &ProcessingOptions,
-    ) -> Result<SheetSchema> {
-        let range = workbook
-            .worksheet_range(sheet_name)
-            .map_err(crate::error::Error::Excel)?;
-
-        let mut sheet = SheetSchema::new(sheet_name.to_string(), sheet_idx);
-
-        if range.is_empty() {
+        audit: &mut dyn AuditSink,
+    ) -> Result<(SheetSchema, RecodeRegistry)> {
+        let range = guarded(|| workbook.worksheet_range(sheet_name))
+            .ok_or_else(|| self.malformed())?
+            .map_err(Error::Excel)?;
+
+        if range.is_empty() || range.get_size().0 == 0 {
+            let mut sheet = SheetSchema::new(sheet_name.to_string(), sheet_idx);
             sheet.row_count = SafeValue::Integer(0);
-            return Ok(sheet);
+            return Ok((sheet, RecodeRegistry::new()));
         }
 
-        let (row_count, col_count) = range.get_size();
+        // Merged cells only matter for naming columns from stacked header rows
+        let merges = (options.header_rows > 1)
+            .then(|| parts.merged_cells(&self.path, sheet_name))
+            .flatten();
+        let mut stream =
+            RangeStream::new(&range, options.header, options.header_rows, merges.as_deref());
+        let (mut sheet, registry) =
+            profile_stream(&mut stream, sheet_name, sheet_idx, options, audit)?;
+
+        // The range starts at the first used column, not necessarily column A
+        let first_column = range.start().map_or(0, |(_, col)| col);
+        for column in &mut sheet.columns {
+            let excel_column =
+                u32::try_from(column.index).map_or(u32::MAX, |i| first_column.saturating_add(i));
+            if parts.is_hidden(sheet_name, excel_column) {
+                column.hidden = true;
+                column.warnings.push(
+                    "Column is hidden in the workbook; check what it holds before sharing"
+                        .to_string(),
+                );
+            }
+        }
+        Ok((sheet, registry))
+    }
 
-        if row_count == 0 {
-            sheet.row_count = SafeValue::Integer(0);
-            return Ok(sheet);
+    /// External workbooks (1-based, as formulas number them) that a sheet's
+    /// formulas refer to
+    fn linked_workbooks(
+        workbook: &mut Sheets<std::io::BufReader<std::fs::File>>,
+        sheet_name: &str,
+    ) -> Vec<usize> {
+        let Some(Ok(formulas)) = guarded(|| workbook.worksheet_formula(sheet_name)) else {
+            return Vec::new();
+        };
+        let mut links: Vec<usize> = formulas
+            .used_cells()
+            .flat_map(|(_, _, formula)| EXTERNAL_REFERENCE.captures_iter(formula))
+            .filter_map(|c| c[1].parse().ok())
+            .collect();
+        links.sort_unstable();
+        links.dedup();
+        links
+    }
+
+    /// Check a sheet's cell comments and the text strings in its formulas for
+    /// PHI; `None` if it has neither
+    fn scan_embedded_text(
+        &self,
+        workbook: &mut Sheets<std::io::BufReader<std::fs::File>>,
+        sheet_name: &str,
+        parts: &WorkbookParts,
+        options: &ProcessingOptions,
+    ) -> Option<EmbeddedTextScan> {
+        let comments = parts.comments(&self.path, sheet_name).unwrap_or_default();
+        let formulas: Vec<String> = guarded(|| workbook.worksheet_formula(sheet_name))
+            .and_then(|range| range.ok())
+            .map(|range| range.used_cells().map(|(_, _, f)| f.clone()).collect())
+            .unwrap_or_default();
+        if comments.is_empty() && formulas.is_empty() {
+            return None;
         }
 
-        // First row is headers
-        let headers: Vec<String> = range
-            .rows()
-            .next()
-            .map(|row| {
-                row.iter()
-                    .map(|cell| Self::data_to_string(cell))
-                    .collect()
+        let mut patterns: Vec<String> = Vec::new();
+        let mut check = |text: &str| {
+            let text = sanitize_text(text);
+            let found = find_phi_in_text(&text, &options.locales)
+                .or_else(|| find_phi_in_name(&text, &options.locales));
+            if let Some(pattern) = found {
+                if !patterns.iter().any(|p| p == pattern) {
+                    patterns.push(pattern.to_string());
+                }
+            }
+            found.is_some()
+        };
+        let comments_with_phi = comments.iter().filter(|c| check(c)).count();
+        let formulas_with_phi = formulas
+            .iter()
+            .filter(|f| {
+                FORMULA_STRING
+                    .captures_iter(f)
+                    .any(|s| check(&s[1].replace("\"\"", "\"")))
             })
-            .unwrap_or_default();
+            .count();
+
+        let count = |n: usize| safe_count(n as u64, options.bucket_counts);
+        Some(EmbeddedTextScan {
+            comments: count(comments.len()),
+            comments_with_phi: count(comments_with_phi),
+            formulas: count(formulas.len()),
+            formulas_with_phi: count(formulas_with_phi),
+            patterns,
+        })
+    }
+}
 
-        let num_cols = headers.len().max(col_count);
-        let data_rows = row_count.saturating_sub(1);
+/// Run one of calamine's parsers, which can panic on a damaged workbook (a
+/// cell pointing past the shared strings); `None` if it did
+fn guarded<T>(parse: impl FnOnce() -> T) -> Option<T> {
+    catch_unwind(AssertUnwindSafe(parse)).ok()
+}
 
-        // Initialize trackers
-        let mut type_inferencers: Vec<TypeInferencer> =
-            (0..num_cols).map(|_| TypeInferencer::new()).collect();
-        let mut stat_trackers: Vec<ColumnStatTracker> = (0..num_cols)
-            .map(|_| ColumnStatTracker::new(MAX_UNIQUE_VALUES))
-            .collect();
+/// Text string in a formula, with `""` standing for a quote: `"Dr. Smith"`
+static FORMULA_STRING: Lazy<Regex> = Lazy::new(|| Regex::new(r#""((?:[^"]|"")*)""#).unwrap());
+
+/// Reference to an external workbook in a formula: `[1]Sheet1!A1`
+static EXTERNAL_REFERENCE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\[(\d+)\]").unwrap());
+
+/// Defined names that are Excel's own (print areas, filters, ...)
+const BUILT_IN_NAME_PREFIX: &str = "_xlnm.";
+
+/// Names of workbook items (defined names, linked files) for warnings, with
+/// any that look like PHI withheld
+fn listed_names<'a>(
+    names: impl IntoIterator<Item = &'a str>,
+    options: &ProcessingOptions,
+) -> String {
+    let mut shown = Vec::new();
+    let mut withheld = 0;
+    for name in names {
+        if find_phi_in_name(name, &options.locales).is_some() {
+            withheld += 1;
+        } else {
+            shown.push(sanitize_text(name).into_owned());
+        }
+    }
+    if withheld > 0 {
+        shown.push(format!("{} withheld (looks like PHI)", withheld));
+    }
+    shown.join(", ")
+}
 
-        // Process data rows
-        for row in range.rows().skip(1) {
-            for (col_idx, cell) in row.iter().enumerate() {
-                if col_idx >= num_cols {
-                    continue;
-                }
+/// Whether a defined name's formula refers to a sheet (`Data!$A$1`, `'Raw data'!A:A`)
+fn refers_to_sheet(formula: &str, sheet_name: &str) -> bool {
+    let quoted = format!("'{}'!", sheet_name.replace('\'', "''"));
+    if formula.contains(&quoted) {
+        return true;
+    }
+    // Unquoted, the name must not be the end of a longer one (`OldData!`)
+    let plain = format!("{}!", sheet_name);
+    formula.match_indices(&plain).any(|(at, _)| {
+        !formula[..at]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '.')
+    })
+}
 
-                // Type inference from Excel native type
-                if Self::infer_type_from_data(cell).is_some() {
-                    // Also use string inference for consistency
-                    let str_val = Self::data_to_string(cell);
-                    type_inferencers[col_idx].observe(&str_val);
-                }
+/// Streams the data rows of a worksheet range below its header row
+struct RangeStream<'a> {
+    range: &'a Range<Data>,
+    layout: HeaderLayout,
+    headers: Vec<String>,
+    /// Next row to read
+    position: usize,
+}
 
-                // Statistics collection
-                if Self::is_missing_data(cell) {
-                    stat_trackers[col_idx].update_missing();
-                } else if let Some(num) = Self::get_numeric_value(cell) {
-                    let str_val = Self::data_to_string(cell);
-                    stat_trackers[col_idx].update_numeric(num, &str_val);
-                } else {
-                    let str_val = Self::data_to_string(cell);
-                    stat_trackers[col_idx].update_string(&str_val);
-                }
-            }
+impl<'a> RangeStream<'a> {
+    /// `merges` are the sheet's merged cells when the workbook lists them;
+    /// without them, stacked header rows guess the spans from empty cells.
+    fn new(
+        range: &'a Range<Data>,
+        mode: HeaderMode,
+        header_rows: usize,
+        merges: Option<&[MergedCells]>,
+    ) -> Self {
+        let mut sample: Vec<Vec<String>> = range
+            .rows()
+            .take(sample_rows_needed(mode, header_rows))
+            .map(|row| row.iter().map(ExcelReader::data_to_string).collect())
+            .collect();
+        let layout = resolve_layout(mode, header_rows, &sample);
+        if let Some(merges) = merges {
+            spread_merged_cells(&mut sample, range.start().unwrap_or((0, 0)), merges);
         }
+        Self {
+            range,
+            layout,
+            headers: header_names(&layout, &sample, merges.is_none()),
+            position: layout.data_start,
+        }
+    }
+}
 
-        // Finalize type inference
-        for inf in &mut type_inferencers {
-            inf.finalize_initial_inference();
+/// Copy the value of each merged cell, which the workbook keeps in its
+/// top-left cell only, over the rest of its span. `start` is the sheet
+/// position of the sample's first cell.
+fn spread_merged_cells(sample: &mut [Vec<String>], start: (u32, u32), merges: &[MergedCells]) {
+    let offset = |position: u32, first: u32| position.checked_sub(first).map(|n| n as usize);
+    for merge in merges {
+        let (Some(top), Some(left)) =
+            (offset(merge.first_row, start.0), offset(merge.first_column, start.1))
+        else {
+            continue;
+        };
+        let Some(value) = sample.get(top).and_then(|row| row.get(left)).cloned() else {
+            continue;
+        };
+        let rows = top..=merge.last_row.saturating_sub(start.0) as usize;
+        for row in sample.iter_mut().take(*rows.end() + 1).skip(top) {
+            let columns = left..=merge.last_column.saturating_sub(start.1) as usize;
+            for cell in row.iter_mut().take(*columns.end() + 1).skip(left) {
+                cell.clone_from(&value);
+            }
         }
+    }
+}
 
-        // Build column schemas
-        let mut columns: Vec<ColumnSchema> = Vec::with_capacity(num_cols);
+impl StreamingDataReader for RangeStream<'_> {
+    fn headers(&mut self) -> Result<Vec<String>> {
+        let (_, col_count) = self.range.get_size();
+        // Name any columns beyond the header row
+        Ok(complete_headers(self.headers.clone(), col_count))
+    }
 
-        for col_idx in 0..num_cols {
-            let header = headers.get(col_idx).cloned().unwrap_or_else(|| format!("Column{}", col_idx + 1));
-            let name_check = check_column_name(&header);
-            let dtype = type_inferencers[col_idx].inferred_type();
-            let tracker = &stat_trackers[col_idx];
+    fn next_batch(&mut self, max_rows: usize) -> Result<Option<RowBatch>> {
+        let batch: RowBatch = self
+            .range
+            .rows()
+            .skip(self.position)
+            .take(max_rows)
+            .map(|row| row.iter().map(ExcelReader::cell_to_string).collect())
+            .collect();
 
-            // Determine classification
-            let mut classification = name_check.classification.clone();
-            if tracker.unique_tracker.is_high_cardinality() {
-                classification = Classification::HighCardinality;
-            }
+        if batch.is_empty() {
+            return Ok(None);
+        }
+        self.position += batch.len();
+        Ok(Some(batch))
+    }
 
-            // Build column name SafeValue
-            let name_value = if classification == Classification::Phi {
-                SafeValue::Suppressed {
-                    reason: "Column name matches PHI pattern".to_string(),
-                }
-            } else {
-                SafeValue::from_string(&header, "Column name too long")
-            };
+    fn rewind(&mut self) -> Result<()> {
+        self.position = self.layout.data_start;
+        Ok(())
+    }
+}
 
-            let mut col_schema = ColumnSchema::new(name_value, col_idx, dtype);
-            col_schema.classification = classification.clone();
+impl DataReader for ExcelReader {
+    fn read(&mut self, options: &ProcessingOptions) -> Result<Vec<SheetSchema>> {
+        let (sheets, _recode_registry) = self.read_with_recoding(options, &mut NullAuditSink)?;
+        Ok(sheets)
+    }
 
-            // Add warnings
-            if let Some(warning) = name_check.warning {
-                col_schema.warnings.push(warning);
+    fn read_with_recoding(
+        &mut self,
+        options: &ProcessingOptions,
+        audit: &mut dyn AuditSink,
+    ) -> Result<(Vec<SheetSchema>, RecodeRegistry)> {
+        let mut workbook = guarded(|| self.open()).ok_or_else(|| self.malformed())??;
+        let parts = WorkbookParts::read(&self.path).unwrap_or_default();
+        self.workbook_warnings.clear();
+
+        let metadata: Vec<(String, SheetVisibility)> = workbook
+            .sheets_metadata()
+            .iter()
+            .map(|sheet| {
+                let visibility = match sheet.visible {
+                    SheetVisible::Visible => SheetVisibility::Visible,
+                    SheetVisible::Hidden => SheetVisibility::Hidden,
+                    SheetVisible::VeryHidden => SheetVisibility::VeryHidden,
+                };
+                (sheet.name.clone(), visibility)
+            })
+            .collect();
+        let defined_names: Vec<(String, String)> = workbook
+            .defined_names()
+            .iter()
+            .filter(|(name, _)| !name.starts_with(BUILT_IN_NAME_PREFIX))
+            .cloned()
+            .collect();
+        let mut sheets: Vec<SheetSchema> = Vec::with_capacity(metadata.len());
+        let mut recode_registry = RecodeRegistry::new();
+        let mut skipped_hidden = 0;
+        let mut scanned: Vec<&str> = Vec::new();
+        let mut linked = vec![false; parts.external_links.len()];
+
+        for (idx, (sheet_name, visibility)) in metadata.iter().enumerate() {
+            if !options.sheet_selected(sheet_name) {
+                continue;
+            }
+            if !visibility.is_visible() && options.skip_hidden_sheets {
+                skipped_hidden += 1;
+                continue;
+            }
+            let (mut sheet, sheet_registry) =
+                self.process_sheet(&mut workbook, sheet_name, idx, &parts, options, audit)?;
+            sheet.visibility = *visibility;
+            match visibility {
+                SheetVisibility::Visible => {}
+                SheetVisibility::Hidden => sheet.warnings.push(
+                    "Sheet is hidden in the workbook; hidden sheets often hold raw or \
+                     identifiable data"
+                        .to_string(),
+                ),
+                SheetVisibility::VeryHidden => sheet.warnings.push(
+                    "Sheet is very hidden (only a macro can show it); hidden sheets often hold \
+                     raw or identifiable data"
+                        .to_string(),
+                ),
             }
 
-            // Build stats
-            let mut stats = ColumnStats::default();
-            let non_missing_count = tracker.count();
-            stats.count = Some(safe_count(non_missing_count, options.bucket_counts));
-            stats.missing_count = Some(safe_count(tracker.missing_count, options.bucket_counts));
-
-            match dtype {
-                DType::Integer | DType::Numeric => {
-                    if let Some(min) = tracker.welford.min() {
-                        stats.min = Some(SafeValue::Float(min));
-                    }
-                    if let Some(max) = tracker.welford.max() {
-                        stats.max = Some(SafeValue::Float(max));
-                    }
-                    stats.mean = tracker.welford.mean();
-                    stats.std_dev = tracker.welford.std_dev();
-                    stats.median = tracker.p2_median.quantile();
-                }
-                _ => {}
+            let names = defined_names
+                .iter()
+                .filter(|(_, formula)| refers_to_sheet(formula, sheet_name))
+                .map(|(name, _)| name.as_str());
+            let names = listed_names(names, options);
+            if !names.is_empty() {
+                sheet.warnings.push(format!("Defined names refer to this sheet: {}", names));
             }
 
-            // Unique count
-            let unique_count = tracker.unique_tracker.unique_count() as u64;
-            if tracker.unique_tracker.is_high_cardinality() {
-                stats.unique_count = Some(SafeValue::Suppressed {
-                    reason: "High cardinality; exact count suppressed".to_string(),
-                });
-            } else if options.bucket_counts {
-                stats.unique_count =
-                    Some(SafeValue::ShortString(bucket_count(unique_count).to_string()));
-            } else {
-                stats.unique_count = Some(SafeValue::Integer(unique_count as i64));
+            if options.scan_comments {
+                sheet.embedded_text =
+                    self.scan_embedded_text(&mut workbook, sheet_name, &parts, options);
+            }
+            if let Some(scan) = &sheet.embedded_text {
+                if !scan.patterns.is_empty() {
+                    sheet.warnings.push(format!(
+                        "Cell comments ({}) or formulas ({}) look like they hold PHI ({}); \
+                         they are not exported, but travel with the workbook",
+                        scan.comments_with_phi,
+                        scan.formulas_with_phi,
+                        scan.patterns.join(", ")
+                    ));
+                }
             }
 
-            col_schema.stats = Some(stats);
-
-            // Build unique values list (if safe)
-            if classification == Classification::Safe || classification == Classification::Warning {
-                if let Some(values) = tracker.unique_tracker.values() {
-                    let mut safe_values: Vec<SafeValue> = Vec::new();
-                    let counts = tracker.unique_tracker.value_counts();
-
-                    for value in values {
-                        let count = counts
-                            .and_then(|c| c.get(value))
-                            .copied()
-                            .unwrap_or(1);
-
-                        if count >= options.k_anonymity {
-                            let value_check = crate::privacy::check_value_pattern(value);
-                            if !value_check.is_phi && value.len() <= 32 {
-                                safe_values.push(SafeValue::ShortString(value.clone()));
-                            }
-                        }
-                    }
-
-                    if !safe_values.is_empty() {
-                        col_schema.unique_values = Some(safe_values);
-                    }
+            if !parts.external_links.is_empty() {
+                let links: Vec<usize> = Self::linked_workbooks(&mut workbook, sheet_name)
+                    .into_iter()
+                    .filter(|&n| n >= 1 && n <= linked.len())
+                    .collect();
+                for &n in &links {
+                    linked[n - 1] = true;
+                }
+                if !links.is_empty() {
+                    let files = links.iter().map(|&n| parts.external_links[n - 1].as_str());
+                    sheet.warnings.push(format!(
+                        "Formulas link to other workbooks: {}",
+                        listed_names(files, options)
+                    ));
                 }
             }
 
-            columns.push(col_schema);
+            recode_registry.merge_sheet(sheet_name, sheet_registry);
+            sheets.push(sheet);
+            scanned.push(sheet_name);
         }
 
-        sheet.row_count = safe_count(data_rows as u64, options.bucket_counts);
-        sheet.columns = columns;
+        if skipped_hidden > 0 {
+            self.workbook_warnings.push(format!(
+                "{} hidden sheet(s) not scanned (--skip-hidden-sheets); they may hold \
+                 identifiable data",
+                skipped_hidden
+            ));
+        }
+        let unattached = defined_names
+            .iter()
+            .filter(|(_, formula)| !scanned.iter().any(|s| refers_to_sheet(formula, s)))
+            .map(|(name, _)| name.as_str());
+        let unattached = listed_names(unattached, options);
+        if !unattached.is_empty() {
+            self.workbook_warnings.push(format!(
+                "Defined names refer to no scanned sheet: {}",
+                unattached
+            ));
+        }
+        let unlinked = parts
+            .external_links
+            .iter()
+            .zip(&linked)
+            .filter(|(_, &linked)| !linked)
+            .map(|(file, _)| file.as_str());
+        let unlinked = listed_names(unlinked, options);
+        if !unlinked.is_empty() {
+            self.workbook_warnings.push(format!(
+                "Workbook links to other workbooks: {}",
+                unlinked
+            ));
+        }
 
-        Ok(sheet)
+        Ok((sheets, recode_registry))
     }
-}
-
-impl DataReader for ExcelReader {
-    fn read(&mut self, options: &ProcessingOptions) -> Result<Vec<SheetSchema>> {
-        let mut workbook: Sheets<std::io::BufReader<std::fs::File>> =
-            open_workbook_auto(&self.path)?;
-
-        let sheet_names: Vec<String> = workbook.sheet_names().to_vec();
-        let mut sheets: Vec<SheetSchema> = Vec::with_capacity(sheet_names.len());
-
-        for (idx, sheet_name) in sheet_names.iter().enumerate() {
-            let sheet = self.process_sheet(&mut workbook, sheet_name, idx, options)?;
-            sheets.push(sheet);
-        }
 
-        Ok(sheets)
+    fn file_warnings(&self, options: &ProcessingOptions) -> Vec<String> {
+        let mut warnings = DocumentProperties::read(&self.path)
+            .map(|props| props.warnings(options.date_precision))
+            .unwrap_or_default();
+        warnings.extend(self.workbook_warnings.iter().cloned());
+        warnings
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use calamine::ExcelDateTimeType;
 
     #[test]
     fn test_data_to_string() {
@@ -303,8 +567,8 @@ mod tests {
             "42"
         );
         assert_eq!(
-            ExcelReader::data_to_string(&Data::Float(3.14)),
-            "3.14"
+            ExcelReader::data_to_string(&Data::Float(2.5)),
+            "2.5"
         );
         assert_eq!(
             ExcelReader::data_to_string(&Data::Bool(true)),
@@ -321,8 +585,222 @@ mod tests {
 
     #[test]
     fn test_excel_serial_to_date() {
+        let cell = |serial: f64, is_1904: bool| {
+            let dt = ExcelDateTime::new(serial, ExcelDateTimeType::DateTime, is_1904);
+            ExcelReader::excel_datetime_to_string(&dt)
+        };
         // Excel serial date 44927 should be 2023-01-01
-        let result = ExcelReader::excel_serial_to_date_string(44927.0);
-        assert_eq!(result, "2023-01-01");
+        assert_eq!(cell(44927.0, false), "2023-01-01");
+        // The same day in the 1904 date system
+        assert_eq!(cell(43465.0, true), "2023-01-01");
+        assert_eq!(cell(44927.4375, false), "2023-01-01T10:30:00");
+        assert_eq!(cell(0.75, false), "18:00:00");
+
+        let duration = ExcelDateTime::new(1.5, ExcelDateTimeType::TimeDelta, false);
+        assert_eq!(ExcelReader::excel_datetime_to_string(&duration), "36:00:00");
+    }
+
+    #[test]
+    fn test_range_stream() {
+        let mut range = Range::new((0, 0), (2, 1));
+        range.set_value((0, 0), Data::String("age".to_string()));
+        range.set_value((1, 0), Data::Int(30));
+        range.set_value((1, 1), Data::Error(calamine::CellErrorType::Div0));
+        range.set_value((2, 0), Data::Float(41.5));
+
+        let mut stream = RangeStream::new(&range, HeaderMode::Auto, 1, None);
+        assert_eq!(stream.headers().unwrap(), vec!["age", ""]);
+
+        let batch = stream.next_batch(10).unwrap().unwrap();
+        assert_eq!(batch, vec![vec!["30", ""], vec!["41.5", ""]]);
+        assert!(stream.next_batch(10).unwrap().is_none());
+
+        stream.rewind().unwrap();
+        assert_eq!(stream.next_batch(1).unwrap().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_range_stream_title_rows_and_headerless() {
+        let mut range = Range::new((0, 0), (3, 2));
+        range.set_value((0, 0), Data::String("Lab export".to_string()));
+        range.set_value((2, 0), Data::String("id".to_string()));
+        range.set_value((2, 1), Data::String("hb".to_string()));
+        range.set_value((2, 2), Data::String("unit".to_string()));
+        range.set_value((3, 0), Data::Int(1));
+        range.set_value((3, 1), Data::Float(13.2));
+        range.set_value((3, 2), Data::String("g/dL".to_string()));
+
+        let mut stream = RangeStream::new(&range, HeaderMode::Auto, 1, None);
+        assert_eq!(stream.headers().unwrap(), vec!["id", "hb", "unit"]);
+        assert_eq!(
+            stream.next_batch(10).unwrap().unwrap(),
+            vec![vec!["1", "13.2", "g/dL"]]
+        );
+
+        let mut stream = RangeStream::new(&range, HeaderMode::Absent, 1, None);
+        assert_eq!(stream.headers().unwrap(), vec!["Column1", "Column2", "Column3"]);
+        assert_eq!(stream.next_batch(10).unwrap().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_hidden_sheets_columns_and_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("trial.xlsx");
+        let mut workbook = rust_xlsxwriter::Workbook::new();
+        let visible = workbook.add_worksheet().set_name("Summary").unwrap();
+        visible.write(0, 0, "arm").unwrap();
+        visible.write(0, 1, "notes").unwrap();
+        for row in 1..=6 {
+            visible.write(row, 0, "A").unwrap();
+            visible.write(row, 1, "ok").unwrap();
+        }
+        visible.set_column_hidden(1).unwrap();
+        let raw = workbook.add_worksheet().set_name("Raw").unwrap();
+        raw.write(0, 0, "mrn").unwrap();
+        raw.write(1, 0, "12345678").unwrap();
+        raw.set_very_hidden(true);
+        workbook.define_name("Cohort", "=Summary!$A$1:$A$7").unwrap();
+        workbook.define_name("Jane_Smith", "=Summary!$B$1").unwrap();
+        workbook.define_name("Rate", "=0.5").unwrap();
+        workbook.save(&path).unwrap();
+
+        let mut reader = ExcelReader::new(&path).unwrap();
+        let options = ProcessingOptions::default();
+        let sheets = reader.read(&options).unwrap();
+        assert_eq!(sheets.len(), 2);
+        let summary = &sheets[0];
+        assert!(summary.visibility.is_visible());
+        assert!(!summary.columns[0].hidden);
+        assert!(summary.columns[1].hidden);
+        assert!(summary.warnings.contains(
+            &"Defined names refer to this sheet: Cohort, 1 withheld (looks like PHI)".to_string()
+        ));
+        assert_eq!(sheets[1].visibility, SheetVisibility::VeryHidden);
+        assert!(sheets[1].warnings[0].starts_with("Sheet is very hidden"));
+        let warnings = reader.file_warnings(&options);
+        assert!(warnings.contains(&"Defined names refer to no scanned sheet: Rate".to_string()));
+
+        let options = ProcessingOptions {
+            skip_hidden_sheets: true,
+            ..ProcessingOptions::default()
+        };
+        let sheets = reader.read(&options).unwrap();
+        assert_eq!(sheets.len(), 1);
+        let warnings = reader.file_warnings(&options);
+        assert!(warnings.iter().any(|w| w.starts_with("1 hidden sheet(s) not scanned")));
+
+        let options = ProcessingOptions {
+            exclude_sheets: vec!["sum*".to_string()],
+            ..ProcessingOptions::default()
+        };
+        let sheets = reader.read(&options).unwrap();
+        let names: Vec<&str> = sheets.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["Raw"]);
+    }
+
+    #[test]
+    fn test_scan_comments_and_formulas() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("visits.xlsx");
+        let mut workbook = rust_xlsxwriter::Workbook::new();
+        let sheet = workbook.add_worksheet();
+        sheet.write(0, 0, "arm").unwrap();
+        sheet.write(0, 1, "dose").unwrap();
+        for row in 1..=6 {
+            sheet.write(row, 0, "A").unwrap();
+            sheet.write(row, 1, 10).unwrap();
+        }
+        let note = rust_xlsxwriter::Note::new("checked with Dr. Smith").set_author("Ann Lee");
+        sheet.insert_note(1, 0, &note).unwrap();
+        let note = rust_xlsxwriter::Note::new("dose in mg");
+        sheet.insert_note(2, 1, &note).unwrap();
+        sheet.write_formula(1, 2, r#"=IF(A2="A","jane.doe@example.org","")"#).unwrap();
+        sheet.write_formula(2, 2, "=B2*2").unwrap();
+        workbook.save(&path).unwrap();
+
+        let mut reader = ExcelReader::new(&path).unwrap();
+        let sheets = reader.read(&ProcessingOptions::default()).unwrap();
+        assert!(sheets[0].embedded_text.is_none());
+
+        let options = ProcessingOptions {
+            scan_comments: true,
+            bucket_counts: false,
+            ..ProcessingOptions::default()
+        };
+        let sheets = reader.read(&options).unwrap();
+        let scan = sheets[0].embedded_text.as_ref().unwrap();
+        assert_eq!(scan.comments, SafeValue::Integer(2));
+        assert_eq!(scan.comments_with_phi, SafeValue::Integer(1));
+        assert_eq!(scan.formulas, SafeValue::Integer(2));
+        assert_eq!(scan.formulas_with_phi, SafeValue::Integer(1));
+        assert_eq!(scan.patterns, vec!["name", "email"]);
+        let warning = sheets[0].warnings.iter().find(|w| w.starts_with("Cell comments"));
+        assert!(!warning.unwrap().contains("Smith"));
+    }
+
+    #[test]
+    fn test_stacked_header_rows_with_merged_cells() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("vitals.xlsx");
+        let mut workbook = rust_xlsxwriter::Workbook::new();
+        let sheet = workbook.add_worksheet();
+        let format = rust_xlsxwriter::Format::new();
+        sheet.merge_range(0, 0, 1, 0, "arm", &format).unwrap();
+        sheet.merge_range(0, 1, 0, 3, "Vital signs", &format).unwrap();
+        for (col, name) in ["SBP", "DBP", "HR"].into_iter().enumerate() {
+            sheet.write(1, col as u16 + 1, name).unwrap();
+        }
+        for row in 2..8 {
+            sheet.write(row, 0, "A").unwrap();
+            sheet.write(row, 1, 120).unwrap();
+            sheet.write(row, 2, 80).unwrap();
+            sheet.write(row, 3, 70).unwrap();
+        }
+        workbook.save(&path).unwrap();
+
+        let options = ProcessingOptions {
+            header_rows: 2,
+            ..ProcessingOptions::default()
+        };
+        let sheets = ExcelReader::new(&path).unwrap().read(&options).unwrap();
+        let names: Vec<String> = sheets[0].columns.iter().map(|c| c.name.to_string()).collect();
+        assert_eq!(names, ["arm", "Vital signs SBP", "Vital signs DBP", "Vital signs HR"]);
+    }
+
+    proptest::proptest! {
+        #![proptest_config(proptest::prelude::ProptestConfig::with_cases(32))]
+
+        /// A workbook with bytes overwritten or cut short is read or refused
+        /// with an error, never a panic
+        #[test]
+        fn prop_excel_corrupted(
+            edits in proptest::collection::vec((0usize..8000, 0u8..=255), 0..8),
+            cut in 0usize..8000,
+        ) {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("trial.xlsx");
+            let mut workbook = rust_xlsxwriter::Workbook::new();
+            let sheet = workbook.add_worksheet();
+            sheet.write(0, 0, "arm").unwrap();
+            sheet.write(0, 1, "age").unwrap();
+            for row in 1..=20 {
+                sheet.write(row, 0, "A").unwrap();
+                sheet.write(row, 1, 30 + row).unwrap();
+            }
+            workbook.save(&path).unwrap();
+
+            let mut bytes = std::fs::read(&path).unwrap();
+            for (at, byte) in edits {
+                let len = bytes.len();
+                bytes[at % len] = byte;
+            }
+            bytes.truncate(cut.max(1) * 2);
+            std::fs::write(&path, &bytes).unwrap();
+            if let Ok(mut reader) = ExcelReader::new(&path) {
+                if let Err(e) = reader.read(&ProcessingOptions::default()) {
+                    proptest::prop_assert!(!e.to_string().is_empty());
+                }
+            }
+        }
     }
 }
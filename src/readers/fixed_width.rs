@@ -0,0 +1,433 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, Lines, Read};
+use std::path::{Path, PathBuf};
+
+use encoding_rs::Encoding;
+use encoding_rs_io::DecodeReaderBytesBuilder;
+
+use crate::audit::{AuditSink, NullAuditSink};
+use crate::error::Error;
+use crate::paths;
+use crate::privacy::RecodeRegistry;
+use crate::types::{DType, HeaderMode, ProcessingOptions, Result, SheetSchema};
+
+use super::encoding::{decode_stream, detect_file_encoding, resolve_encoding};
+use super::profile::profile_stream;
+use super::{DataReader, RowBatch, StreamingDataReader};
+
+/// One field of a fixed-width record
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayoutColumn {
+    pub name: String,
+    /// First character of the field (1-based)
+    pub start: usize,
+    /// Field width in characters
+    pub width: usize,
+    /// Type the layout declares, checked against the inferred type
+    pub dtype: Option<DType>,
+}
+
+/// Column layout of a fixed-width file
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixedWidthLayout {
+    pub columns: Vec<LayoutColumn>,
+}
+
+impl FixedWidthLayout {
+    /// Read a layout CSV with `name`, `start` (1-based) and `width` (or
+    /// inclusive `end`) columns, and an optional `type` column
+    pub fn load(path: &Path) -> Result<Self> {
+        let invalid = |problem: String| {
+            Error::InvalidInput(format!("layout {}: {}", paths::display_path(path), problem))
+        };
+
+        let mut reader = csv::ReaderBuilder::new()
+            .flexible(true)
+            .comment(Some(b'#'))
+            .from_path(paths::for_fs_access(path))?;
+        let headers: Vec<String> = reader
+            .headers()?
+            .iter()
+            .map(|h| h.trim().trim_start_matches('\u{feff}').to_lowercase())
+            .collect();
+        let column = |names: &[&str]| headers.iter().position(|h| names.contains(&h.as_str()));
+        let name_col = column(&["name", "variable", "column"])
+            .ok_or_else(|| invalid("no 'name' column".to_string()))?;
+        let start_col = column(&["start", "position"])
+            .ok_or_else(|| invalid("no 'start' column".to_string()))?;
+        let width_col = column(&["width", "length"]);
+        let end_col = column(&["end"]);
+        if width_col.is_none() && end_col.is_none() {
+            return Err(invalid("no 'width' or 'end' column".to_string()));
+        }
+        let dtype_col = column(&["type", "dtype"]);
+
+        let mut columns = Vec::new();
+        for (i, record) in reader.records().enumerate() {
+            let record = record?;
+            let line = i + 2;
+            let field = |col: Option<usize>| {
+                col.and_then(|c| record.get(c))
+                    .map(str::trim)
+                    .filter(|v| !v.is_empty())
+            };
+            let number = |col: Option<usize>, what: &str| -> Result<Option<usize>> {
+                field(col)
+                    .map(|text| {
+                        text.parse::<usize>()
+                            .ok()
+                            .filter(|n| *n > 0)
+                            .ok_or_else(|| {
+                                invalid(format!("line {}: invalid {} '{}'", line, what, text))
+                            })
+                    })
+                    .transpose()
+            };
+            let Some(name) = field(Some(name_col)) else {
+                continue;
+            };
+            let start = number(Some(start_col), "start")?
+                .ok_or_else(|| invalid(format!("line {}: no start for '{}'", line, name)))?;
+            let width = match (number(width_col, "width")?, number(end_col, "end")?) {
+                (Some(width), _) => width,
+                (None, Some(end)) if end >= start => end - start + 1,
+                _ => return Err(invalid(format!("line {}: no width for '{}'", line, name))),
+            };
+            let dtype =
+                match field(dtype_col) {
+                    Some(text) => Some(DType::from_name(text).ok_or_else(|| {
+                        invalid(format!("line {}: unknown type '{}'", line, text))
+                    })?),
+                    None => None,
+                };
+            columns.push(LayoutColumn {
+                name: name.to_string(),
+                start,
+                width,
+                dtype,
+            });
+        }
+
+        if columns.is_empty() {
+            return Err(invalid("no columns".to_string()));
+        }
+        let mut by_start: Vec<&LayoutColumn> = columns.iter().collect();
+        by_start.sort_by_key(|c| c.start);
+        for pair in by_start.windows(2) {
+            if pair[0].start + pair[0].width > pair[1].start {
+                return Err(invalid(format!(
+                    "columns '{}' and '{}' overlap",
+                    pair[0].name, pair[1].name
+                )));
+            }
+        }
+        Ok(Self { columns })
+    }
+
+    /// Cut one record into its fields; fields past the end of a short record are empty
+    fn split(&self, line: &str) -> Vec<String> {
+        let chars: Vec<char> = line.chars().collect();
+        self.columns
+            .iter()
+            .map(|c| {
+                let start = (c.start - 1).min(chars.len());
+                let end = (start + c.width).min(chars.len());
+                chars[start..end]
+                    .iter()
+                    .collect::<String>()
+                    .trim()
+                    .to_string()
+            })
+            .collect()
+    }
+}
+
+type DecodedLines = Lines<BufReader<Box<dyn Read>>>;
+
+/// Reader for fixed-width text files, cut into columns by a layout file
+pub struct FixedWidthReader {
+    path: PathBuf,
+    layout: Option<FixedWidthLayout>,
+    /// Source encoding; detected on first open unless overridden
+    encoding: Option<&'static Encoding>,
+    /// Header lines before the first record
+    skip_lines: usize,
+    lines: Option<DecodedLines>,
+    /// Input that can only be read once (stdin), read instead of `path`
+    stream: Option<Box<dyn Read>>,
+    streamed: bool,
+}
+
+impl FixedWidthReader {
+    pub fn new(path: &Path) -> Result<Self> {
+        Ok(Self {
+            path: path.to_path_buf(),
+            layout: None,
+            encoding: None,
+            skip_lines: 0,
+            lines: None,
+            stream: None,
+            streamed: false,
+        })
+    }
+
+    /// Create a reader over input that can only be read once, reported under
+    /// `name`; its records are profiled in a single pass
+    pub fn from_stream(input: Box<dyn Read>, name: &str) -> Self {
+        Self {
+            path: PathBuf::from(name),
+            layout: None,
+            encoding: None,
+            skip_lines: 0,
+            lines: None,
+            stream: Some(input),
+            streamed: true,
+        }
+    }
+
+    pub fn set_layout(&mut self, layout: FixedWidthLayout) {
+        self.layout = Some(layout);
+    }
+
+    fn layout(&self) -> Result<&FixedWidthLayout> {
+        self.layout.as_ref().ok_or_else(|| {
+            Error::InvalidInput("fixed-width files need a column layout (--layout)".to_string())
+        })
+    }
+
+    /// The decoded lines, positioned at the first record on first use
+    fn lines(&mut self) -> Result<&mut DecodedLines> {
+        if self.lines.is_none() {
+            let reader: Box<dyn Read> = if self.streamed {
+                let input = self.stream.take().ok_or_else(|| {
+                    Error::InvalidInput("input that can only be read once was rewound".to_string())
+                })?;
+                let (encoding, _, decoded) = decode_stream(input, self.encoding, 0)?;
+                self.encoding = Some(encoding);
+                Box::new(decoded)
+            } else {
+                let encoding = match self.encoding {
+                    Some(encoding) => encoding,
+                    None => *self.encoding.insert(detect_file_encoding(&self.path)?),
+                };
+                let file = File::open(&self.path)?;
+                Box::new(
+                    DecodeReaderBytesBuilder::new()
+                        .encoding(Some(encoding))
+                        .bom_override(true)
+                        .build(file),
+                )
+            };
+            let mut lines = BufReader::new(reader).lines();
+            for _ in 0..self.skip_lines {
+                if lines.next().transpose()?.is_none() {
+                    break;
+                }
+            }
+            self.lines = Some(lines);
+        }
+        Ok(self.lines.as_mut().expect("lines were just opened"))
+    }
+}
+
+impl StreamingDataReader for FixedWidthReader {
+    fn headers(&mut self) -> Result<Vec<String>> {
+        Ok(self
+            .layout()?
+            .columns
+            .iter()
+            .map(|c| c.name.clone())
+            .collect())
+    }
+
+    fn next_batch(&mut self, max_rows: usize) -> Result<Option<RowBatch>> {
+        let layout = self.layout()?.clone();
+        let lines = self.lines()?;
+        let mut batch = Vec::new();
+        while batch.len() < max_rows {
+            let Some(line) = lines.next().transpose()? else {
+                break;
+            };
+            // `lines` leaves the `\r` of CRLF line endings
+            let line = line.strip_suffix('\r').unwrap_or(&line);
+            if !line.trim().is_empty() {
+                batch.push(layout.split(line));
+            }
+        }
+
+        if batch.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(batch))
+        }
+    }
+
+    fn rewind(&mut self) -> Result<()> {
+        self.lines = None;
+        self.lines()?;
+        Ok(())
+    }
+
+    fn rewindable(&self) -> bool {
+        !self.streamed
+    }
+}
+
+impl DataReader for FixedWidthReader {
+    fn read(&mut self, options: &ProcessingOptions) -> Result<Vec<SheetSchema>> {
+        let (sheets, _recode_registry) = self.read_with_recoding(options, &mut NullAuditSink)?;
+        Ok(sheets)
+    }
+
+    fn read_with_recoding(
+        &mut self,
+        options: &ProcessingOptions,
+        audit: &mut dyn AuditSink,
+    ) -> Result<(Vec<SheetSchema>, RecodeRegistry)> {
+        if let Some(ref layout) = options.fixed_width_layout {
+            self.set_layout(layout.clone());
+        }
+        if let Some(label) = &options.encoding {
+            self.encoding = Some(resolve_encoding(label)?);
+        }
+        // Records carry no header; `--header-row N` skips the first N lines
+        self.skip_lines = match options.header {
+            HeaderMode::Row(row) => row,
+            HeaderMode::Auto | HeaderMode::Absent => 0,
+        };
+
+        self.rewind()?;
+        let file_name = paths::file_name_lossy(&self.path);
+        let (mut sheet, recode_registry) = profile_stream(self, &file_name, 0, options, audit)?;
+
+        for (column, declared) in sheet.columns.iter_mut().zip(&self.layout()?.columns) {
+            if let Some(dtype) = declared.dtype {
+                if !column.dtype.satisfies(dtype) {
+                    column.warnings.push(format!(
+                        "Layout declares type '{}' but the values look like '{}'",
+                        dtype.as_str(),
+                        column.dtype.as_str()
+                    ));
+                }
+            }
+        }
+
+        Ok((vec![sheet], recode_registry))
+    }
+
+    fn encoding(&self) -> Option<String> {
+        self.encoding.map(|e| e.name().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Classification, SafeValue};
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn write_file(suffix: &str, content: &str) -> NamedTempFile {
+        let mut file = NamedTempFile::with_suffix(suffix).unwrap();
+        write!(file, "{}", content).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_load_layout() {
+        let file = write_file(
+            ".csv",
+            "# Registry extract v3\nname,start,width,type\nrecord_id,1,6,integer\nsurname,7,12,\nvisit,19,10,date\n",
+        );
+        let layout = FixedWidthLayout::load(file.path()).unwrap();
+        assert_eq!(layout.columns.len(), 3);
+        assert_eq!(layout.columns[1].name, "surname");
+        assert_eq!(layout.columns[1].dtype, None);
+        assert_eq!(layout.columns[2].dtype, Some(DType::Date));
+
+        let file = write_file(".csv", "variable,start,end\nsex,1,1\nage,2,4\n");
+        let layout = FixedWidthLayout::load(file.path()).unwrap();
+        assert_eq!(layout.columns[1].width, 3);
+    }
+
+    #[test]
+    fn test_invalid_layouts() {
+        for content in [
+            "name,start\nage,1\n",
+            "name,start,width\nage,0,3\n",
+            "name,start,width\nage,1,3\nsex,3,1\n",
+            "name,start,width,type\nage,1,3,decimal\n",
+            "name,start,width\n",
+        ] {
+            let file = write_file(".csv", content);
+            assert!(FixedWidthLayout::load(file.path()).is_err(), "{}", content);
+        }
+    }
+
+    #[test]
+    fn test_fixed_width_read() {
+        let layout = FixedWidthLayout {
+            columns: vec![
+                LayoutColumn {
+                    name: "record_id".to_string(),
+                    start: 1,
+                    width: 4,
+                    dtype: Some(DType::Integer),
+                },
+                LayoutColumn {
+                    name: "last_name".to_string(),
+                    start: 5,
+                    width: 8,
+                    dtype: None,
+                },
+                LayoutColumn {
+                    name: "age".to_string(),
+                    start: 13,
+                    width: 3,
+                    dtype: Some(DType::Date),
+                },
+            ],
+        };
+        let file = write_file(
+            ".txt",
+            "0001Tremblay 34\r\n0002Gagnon   57\r\n\r\n0003Roy      \r\n",
+        );
+
+        let mut reader = FixedWidthReader::new(file.path()).unwrap();
+        reader.set_layout(layout.clone());
+        assert_eq!(
+            reader.next_batch(10).unwrap().unwrap(),
+            vec![
+                vec!["0001", "Tremblay", "34"],
+                vec!["0002", "Gagnon", "57"],
+                vec!["0003", "Roy", ""],
+            ]
+        );
+
+        let options = ProcessingOptions {
+            bucket_counts: false,
+            fixed_width_layout: Some(layout),
+            ..ProcessingOptions::default()
+        };
+        let sheets = reader.read(&options).unwrap();
+        let columns = &sheets[0].columns;
+        assert_eq!(sheets[0].row_count, SafeValue::Integer(3));
+        assert_eq!(columns[0].dtype, DType::Integer);
+        assert_eq!(columns[1].classification, Classification::Phi);
+        assert_eq!(
+            columns[2].stats.as_ref().unwrap().missing_count,
+            Some(SafeValue::Integer(1))
+        );
+        assert!(columns[2].warnings.contains(
+            &"Layout declares type 'date' but the values look like 'integer'".to_string()
+        ));
+        assert!(columns[0].warnings.iter().all(|w| !w.starts_with("Layout")));
+    }
+
+    #[test]
+    fn test_fixed_width_needs_layout() {
+        let file = write_file(".txt", "0001\n");
+        let mut reader = FixedWidthReader::new(file.path()).unwrap();
+        assert!(reader.read(&ProcessingOptions::default()).is_err());
+    }
+}
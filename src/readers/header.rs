@@ -0,0 +1,298 @@
+use std::collections::HashSet;
+
+use crate::inference::{is_date, is_datetime, is_numeric};
+use crate::types::HeaderMode;
+
+/// Rows examined when locating the header row
+pub const HEADER_SAMPLE_ROWS: usize = 20;
+
+/// Where the header and the data start within a table's rows (0-based)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeaderLayout {
+    /// (First) header row, or `None` when the table has no header
+    pub header_row: Option<usize>,
+    /// Stacked header rows from `header_row` on (`--header-rows`)
+    pub header_rows: usize,
+    /// First data row
+    pub data_start: usize,
+}
+
+/// Number of leading rows a reader should sample before resolving the layout
+pub fn sample_rows_needed(mode: HeaderMode, header_rows: usize) -> usize {
+    let stacked = header_rows.max(1) - 1;
+    match mode {
+        HeaderMode::Row(n) => n.max(1) + HEADER_SAMPLE_ROWS + stacked,
+        _ => HEADER_SAMPLE_ROWS + stacked,
+    }
+}
+
+/// Resolve the header layout from the requested mode, the number of stacked
+/// header rows and the leading rows of the table
+pub fn resolve_layout(
+    mode: HeaderMode,
+    header_rows: usize,
+    sample: &[Vec<String>],
+) -> HeaderLayout {
+    let header_rows = header_rows.max(1);
+    match mode {
+        HeaderMode::Row(n) => {
+            let row = n.max(1) - 1;
+            HeaderLayout {
+                header_row: Some(row),
+                header_rows,
+                data_start: row + header_rows,
+            }
+        }
+        HeaderMode::Absent => HeaderLayout {
+            header_row: None,
+            header_rows: 0,
+            data_start: 0,
+        },
+        // Upper header rows often only label groups of columns, so they would
+        // pass for title lines: the stack starts at the first non-blank row
+        HeaderMode::Auto if header_rows > 1 => {
+            let first = sample
+                .iter()
+                .position(|r| r.iter().any(|c| !c.trim().is_empty()))
+                .unwrap_or(0);
+            HeaderLayout {
+                header_row: Some(first),
+                header_rows,
+                data_start: first + header_rows,
+            }
+        }
+        HeaderMode::Auto => detect_layout(sample),
+    }
+}
+
+/// Guess the layout: skip title lines above the table, then decide whether
+/// the first full row is a header or already data.
+pub fn detect_layout(sample: &[Vec<String>]) -> HeaderLayout {
+    let filled = |row: &Vec<String>| row.iter().filter(|c| !c.trim().is_empty()).count();
+
+    let widest = sample.iter().map(filled).max().unwrap_or(0);
+    if widest == 0 {
+        return HeaderLayout {
+            header_row: Some(0),
+            header_rows: 1,
+            data_start: 1,
+        };
+    }
+
+    // Title lines ("Site export, June 2024") fill far fewer cells than the table.
+    // In one- and two-column tables a header may legitimately have a blank cell,
+    // so only blank rows are skipped there.
+    let threshold = if widest >= 3 { widest.div_ceil(2) } else { 1 };
+    let first = sample.iter().position(|r| filled(r) >= threshold).unwrap_or(0);
+
+    if looks_like_header(&sample[first], &sample[first + 1..]) {
+        HeaderLayout {
+            header_row: Some(first),
+            header_rows: 1,
+            data_start: first + 1,
+        }
+    } else {
+        HeaderLayout {
+            header_row: None,
+            header_rows: 0,
+            data_start: first,
+        }
+    }
+}
+
+fn looks_like_header(row: &[String], below: &[Vec<String>]) -> bool {
+    let is_value = |v: &str| is_numeric(v) || is_date(v) || is_datetime(v);
+    let cells: Vec<&str> = row.iter().map(|c| c.trim()).collect();
+
+    // Headers are labels, not numbers or dates
+    if cells.iter().any(|c| is_value(c)) {
+        return false;
+    }
+    if below.is_empty() {
+        return true;
+    }
+
+    // A text cell above a numeric/date column is a label
+    for (col_idx, cell) in cells.iter().enumerate() {
+        if cell.is_empty() {
+            continue;
+        }
+        let column: Vec<&str> = below
+            .iter()
+            .filter_map(|r| r.get(col_idx))
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+            .collect();
+        if !column.is_empty() && column.iter().all(|v| is_value(v)) {
+            return true;
+        }
+    }
+
+    // All-text table: header labels are distinct and don't recur below
+    let mut seen = HashSet::new();
+    let distinct = cells.iter().filter(|c| !c.is_empty()).all(|c| seen.insert(*c));
+    let recurs = cells.iter().enumerate().any(|(col_idx, cell)| {
+        !cell.is_empty()
+            && below
+                .iter()
+                .any(|r| r.get(col_idx).map(|v| v.trim()) == Some(*cell))
+    });
+    distinct && !recurs
+}
+
+/// Column names of a table: the header row, or the stacked header rows joined
+/// by `stack_headers`. Empty when the table has no header.
+pub fn header_names(
+    layout: &HeaderLayout,
+    sample: &[Vec<String>],
+    fill_merged: bool,
+) -> Vec<String> {
+    let Some(row) = layout.header_row else {
+        return Vec::new();
+    };
+    if layout.header_rows <= 1 {
+        return sample.get(row).cloned().unwrap_or_default();
+    }
+    let end = (row + layout.header_rows).min(sample.len());
+    stack_headers(sample.get(row..end).unwrap_or_default(), fill_merged)
+}
+
+/// Join stacked header rows into one name per column: the column's non-empty
+/// cells, top to bottom, separated by spaces, leaving out a cell that repeats
+/// the one above it (a label merged down).
+///
+/// With `fill_merged`, an empty cell in an upper row continues the label to
+/// its left, which is how a cell merged across columns ("Vital signs" over
+/// "SBP", "DBP" and "HR") comes out when exported without its span.
+pub fn stack_headers(rows: &[Vec<String>], fill_merged: bool) -> Vec<String> {
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let mut parts: Vec<Vec<&str>> = vec![Vec::new(); width];
+    for (row_idx, row) in rows.iter().enumerate() {
+        let upper = row_idx + 1 < rows.len();
+        let mut carried = "";
+        for (col_idx, column) in parts.iter_mut().enumerate() {
+            let cell = row.get(col_idx).map_or("", |c| c.trim());
+            if !cell.is_empty() {
+                carried = cell;
+            }
+            let label = if cell.is_empty() && fill_merged && upper {
+                carried
+            } else {
+                cell
+            };
+            if !label.is_empty() && column.last() != Some(&label) {
+                column.push(label);
+            }
+        }
+    }
+    parts.into_iter().map(|p| p.join(" ")).collect()
+}
+
+/// Pad headers with synthetic `Column{n}` names up to `width` columns
+pub fn complete_headers(mut headers: Vec<String>, width: usize) -> Vec<String> {
+    for col_idx in headers.len()..width {
+        headers.push(format!("Column{}", col_idx + 1));
+    }
+    headers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(lines: &[&str]) -> Vec<Vec<String>> {
+        lines
+            .iter()
+            .map(|l| {
+                if l.is_empty() {
+                    Vec::new()
+                } else {
+                    l.split(',').map(|c| c.to_string()).collect()
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_detect_standard_header() {
+        let layout = detect_layout(&rows(&["id,age", "1,30", "2,41"]));
+        assert_eq!(layout.header_row, Some(0));
+        assert_eq!(layout.data_start, 1);
+    }
+
+    #[test]
+    fn test_detect_headerless_numeric() {
+        let layout = detect_layout(&rows(&["1,30", "2,41"]));
+        assert_eq!(layout.header_row, None);
+        assert_eq!(layout.data_start, 0);
+    }
+
+    #[test]
+    fn test_detect_header_after_title_lines() {
+        let layout = detect_layout(&rows(&[
+            "Site export June 2024",
+            "",
+            "arm,age,visit",
+            "A,30,2024-01-03",
+            "B,41,2024-02-10",
+        ]));
+        assert_eq!(layout.header_row, Some(2));
+        assert_eq!(layout.data_start, 3);
+    }
+
+    #[test]
+    fn test_detect_text_table() {
+        let header = detect_layout(&rows(&["arm,sex", "A,M", "B,F", "A,F"]));
+        assert_eq!(header.header_row, Some(0));
+
+        // First row values recur below: it is data
+        let no_header = detect_layout(&rows(&["A,M", "B,F", "A,F"]));
+        assert_eq!(no_header.header_row, None);
+    }
+
+    #[test]
+    fn test_explicit_modes() {
+        let sample = rows(&["1,2"]);
+        let layout = resolve_layout(HeaderMode::Row(3), 1, &sample);
+        assert_eq!(layout.header_row, Some(2));
+        assert_eq!(layout.data_start, 3);
+
+        let layout = resolve_layout(HeaderMode::Row(3), 2, &sample);
+        assert_eq!(layout.header_row, Some(2));
+        assert_eq!(layout.data_start, 4);
+
+        let layout = resolve_layout(HeaderMode::Absent, 2, &sample);
+        assert_eq!(layout.header_row, None);
+        assert_eq!(layout.data_start, 0);
+    }
+
+    #[test]
+    fn test_stacked_headers() {
+        let sample = rows(&["", ",Vital signs,,,Labs", "id,SBP,DBP,HR,Hb", "1,120,80,70,13.5"]);
+        let layout = resolve_layout(HeaderMode::Auto, 2, &sample);
+        assert_eq!(layout.header_row, Some(1));
+        assert_eq!(layout.data_start, 3);
+        assert_eq!(
+            header_names(&layout, &sample, true),
+            vec!["id", "Vital signs SBP", "Vital signs DBP", "Vital signs HR", "Labs Hb"]
+        );
+        // With the merged spans already filled in, empty cells stay empty
+        assert_eq!(
+            header_names(&layout, &sample, false),
+            vec!["id", "Vital signs SBP", "DBP", "HR", "Labs Hb"]
+        );
+
+        // A label merged down is not repeated
+        let stacked = stack_headers(&rows(&["Subject,Visit 1", "Subject,Date"]), true);
+        assert_eq!(stacked, vec!["Subject", "Visit 1 Date"]);
+    }
+
+    #[test]
+    fn test_complete_headers() {
+        assert_eq!(
+            complete_headers(vec!["age".to_string()], 3),
+            vec!["age", "Column2", "Column3"]
+        );
+        assert_eq!(complete_headers(Vec::new(), 2), vec!["Column1", "Column2"]);
+    }
+}
@@ -1,41 +1,300 @@
 pub mod csv;
+pub mod doc_properties;
+pub mod encoding;
 pub mod excel;
+pub mod fixed_width;
+pub mod header;
+pub mod profile;
+pub mod provenance;
+pub mod signature;
+pub mod sniff;
+pub mod workbook_parts;
+pub mod xpt;
 
+use std::fs::File;
+use std::io::{Read, Write};
 use std::path::Path;
 
+use crate::audit::AuditSink;
+use crate::error::Error;
+use crate::paths;
 use crate::privacy::RecodeRegistry;
-use crate::types::{FileFormat, ProcessingOptions, Result, SheetSchema};
+use crate::tempfiles::{self, SecureTempFile};
+use crate::types::{FileDetails, FileFormat, ProcessingOptions, Result, SheetSchema};
+
+use signature::{detect_container, Container};
 
 /// Common trait for data file readers
 pub trait DataReader {
     /// Read the file and return sheet schemas
     fn read(&mut self, options: &ProcessingOptions) -> Result<Vec<SheetSchema>>;
 
-    /// Read the file with recoding support, returning both schemas and recode registry
-    fn read_with_recoding(&mut self, options: &ProcessingOptions) -> Result<(Vec<SheetSchema>, RecodeRegistry)> {
+    /// Read the file with recoding support, returning both schemas and recode registry.
+    /// Privacy decisions are reported to `audit`.
+    fn read_with_recoding(
+        &mut self,
+        options: &ProcessingOptions,
+        _audit: &mut dyn AuditSink,
+    ) -> Result<(Vec<SheetSchema>, RecodeRegistry)> {
         // Default implementation: no recoding
         let sheets = self.read(options)?;
         Ok((sheets, RecodeRegistry::new()))
     }
+
+    /// Text encoding the file was decoded from, for text formats
+    fn encoding(&self) -> Option<String> {
+        None
+    }
+
+    /// How the file was produced, if the reader can tell
+    fn file_details(&self) -> Option<FileDetails> {
+        None
+    }
+
+    /// File-level privacy warnings, such as names in document properties
+    fn file_warnings(&self, _options: &ProcessingOptions) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// A batch of rows, each holding one string per cell
+pub type RowBatch = Vec<Vec<String>>;
+
+/// Number of rows requested per batch by the built-in consumers
+pub const DEFAULT_BATCH_SIZE: usize = 1024;
+
+/// Row-batch interface over a single table, for processing large inputs with bounded memory
+pub trait StreamingDataReader {
+    /// Column headers of the table
+    fn headers(&mut self) -> Result<Vec<String>>;
+
+    /// Read up to `max_rows` data rows; returns `None` once the input is exhausted
+    fn next_batch(&mut self, max_rows: usize) -> Result<Option<RowBatch>>;
+
+    /// Restart from the first data row, for consumers that need more than one pass
+    fn rewind(&mut self) -> Result<()>;
+
+    /// Whether `rewind` works; input that can only be read once (stdin) is
+    /// profiled in a single pass
+    fn rewindable(&self) -> bool {
+        true
+    }
 }
 
-/// Create a reader for the given file path
-pub fn create_reader(path: &Path) -> Result<Box<dyn DataReader>> {
-    let ext = path
-        .extension()
-        .and_then(|e| e.to_str())
-        .unwrap_or("");
+/// Create a reader for a file whose extension names no format: a workbook
+/// container is read as Excel, gzip-compressed data is decompressed first, and
+/// delimited text is read as TSV (tab) or CSV (other delimiters). `None` if
+/// the content is none of these.
+pub fn create_sniffed_reader(path: &Path) -> Result<Option<(FileFormat, Box<dyn DataReader>)>> {
+    match detect_container(path) {
+        Some(Container::Zip | Container::Ole2) => {
+            return Ok(Some((FileFormat::Excel, Box::new(excel::ExcelReader::new(path)?))));
+        }
+        Some(Container::Gzip) => {
+            // Read as the extension under `.gz` says (`visits.csv.gz`), else by content
+            let spool = decompress(path)?;
+            let inner = spool.0.path();
+            let opened = match FileFormat::from_extension(&paths::extension_lossy(inner)) {
+                Some(format) => Some(create_reader(inner, format)?),
+                None => create_sniffed_reader(inner)?,
+            };
+            return Ok(opened.map(|(format, reader)| {
+                (format, Redirected::boxed(reader, None, Some(spool)))
+            }));
+        }
+        None => {}
+    }
+    let Some(delimiter) = sniff::sniff_delimiter(path)? else {
+        return Ok(None);
+    };
+    let format = if delimiter == b'\t' {
+        FileFormat::Tsv
+    } else {
+        FileFormat::Csv
+    };
+    Ok(Some((format, Box::new(csv::CsvReader::new_sniffed(path, delimiter)?))))
+}
 
-    let format = FileFormat::from_extension(ext).ok_or_else(|| {
-        crate::error::Error::UnsupportedFormat(format!(
-            "Unsupported file extension: .{}",
-            ext
-        ))
-    })?;
+/// Create a reader that reads the file as `format`, unless its first bytes say
+/// otherwise: a zip or OLE2 container is read as an Excel workbook,
+/// gzip-compressed data is decompressed first, and an Excel file that is no
+/// workbook is read as delimited text if it looks like it. Returns the format
+/// the file is read as; the reader's file warnings name any mismatch.
+pub fn create_reader(path: &Path, format: FileFormat) -> Result<(FileFormat, Box<dyn DataReader>)> {
+    match (detect_container(path), format) {
+        (Some(Container::Zip | Container::Ole2), FileFormat::Excel) => {}
+        (Some(container @ (Container::Zip | Container::Ole2)), _) => {
+            let warning = format!(
+                "File content is {}, not {} as its extension says; read as Excel",
+                container.description(),
+                format.name()
+            );
+            let reader = Box::new(excel::ExcelReader::new(path)?);
+            return Ok((FileFormat::Excel, Redirected::boxed(reader, Some(warning), None)));
+        }
+        (Some(Container::Gzip), _) => {
+            let warning = format!(
+                "File content is gzip-compressed, not {} as its extension says; \
+                 decompressed to read",
+                format.name()
+            );
+            let spool = decompress(path)?;
+            let (format, reader) = create_reader(spool.0.path(), format)?;
+            return Ok((format, Redirected::boxed(reader, Some(warning), Some(spool))));
+        }
+        (None, FileFormat::Excel) => {
+            if let Some((sniffed, reader)) = create_sniffed_reader(path)? {
+                let warning = format!(
+                    "File content is delimited text, not an Excel workbook as its extension \
+                     says; read as {}",
+                    sniffed.name()
+                );
+                return Ok((sniffed, Redirected::boxed(reader, Some(warning), None)));
+            }
+        }
+        (None, _) => {}
+    }
+    let reader: Box<dyn DataReader> = match format {
+        FileFormat::Csv => Box::new(csv::CsvReader::new(path)?),
+        FileFormat::Tsv => Box::new(csv::CsvReader::new_tsv(path)?),
+        FileFormat::Excel => Box::new(excel::ExcelReader::new(path)?),
+        FileFormat::FixedWidth => Box::new(fixed_width::FixedWidthReader::new(path)?),
+        FileFormat::Xpt => Box::new(xpt::XptReader::new(path)?),
+    };
+    Ok((format, reader))
+}
 
+/// Create a reader over input that can only be read once (stdin), reported
+/// under `name`. The data is parsed as it arrives and profiled in a single
+/// pass; only delimited and fixed-width text can be read this way.
+pub fn create_stream_reader(
+    input: Box<dyn Read>,
+    name: &str,
+    format: FileFormat,
+) -> Result<Box<dyn DataReader>> {
     match format {
-        FileFormat::Csv => Ok(Box::new(csv::CsvReader::new(path)?)),
-        FileFormat::Tsv => Ok(Box::new(csv::CsvReader::new_tsv(path)?)),
-        FileFormat::Excel => Ok(Box::new(excel::ExcelReader::new(path)?)),
+        FileFormat::Csv => Ok(Box::new(csv::CsvStreamReader::new(input, name, b','))),
+        FileFormat::Tsv => Ok(Box::new(csv::CsvStreamReader::new(input, name, b'\t'))),
+        FileFormat::FixedWidth => Ok(Box::new(fixed_width::FixedWidthReader::from_stream(
+            input, name,
+        ))),
+        FileFormat::Excel | FileFormat::Xpt => Err(Error::InvalidInput(format!(
+            "{} files cannot be read from stdin; only CSV, TSV and fixed-width text can",
+            format.name()
+        ))),
+    }
+}
+
+/// Decompressed copy of a file and its private directory. Dropped in order:
+/// the copy is wiped before its directory goes.
+type Spool = (SecureTempFile, tempfile::TempDir);
+
+/// Largest decompressed size of a gzip file (4 GiB); more is taken for a
+/// decompression bomb rather than filling the temp directory
+const MAX_DECOMPRESSED_BYTES: u64 = 4 << 30;
+
+/// Decompress a gzip file to a private temp file named like it without `.gz`
+fn decompress(path: &Path) -> Result<Spool> {
+    decompress_at_most(path, MAX_DECOMPRESSED_BYTES)
+}
+
+fn decompress_at_most(path: &Path, limit: u64) -> Result<Spool> {
+    let inner_name = match path.file_stem() {
+        Some(stem) if paths::extension_lossy(path).eq_ignore_ascii_case("gz") => {
+            stem.to_string_lossy().into_owned()
+        }
+        _ => paths::file_name_lossy(path),
+    };
+    let dir = tempfiles::private_dir()?;
+    let mut spool = SecureTempFile::with_name_in(dir.path(), &inner_name)?;
+    let decoder = flate2::read::MultiGzDecoder::new(File::open(path)?);
+    let copied = std::io::copy(&mut decoder.take(limit + 1), spool.as_file_mut())?;
+    if copied > limit {
+        // The partial copy is wiped as the spool is dropped
+        return Err(Error::InvalidInput(format!(
+            "{} decompresses to more than {} MiB; decompress it yourself to scan it",
+            paths::display_path(path),
+            limit >> 20
+        )));
+    }
+    spool.as_file_mut().flush()?;
+    Ok((spool, dir))
+}
+
+/// A reader for content other than the file's extension names, or for a
+/// decompressed copy of the file, that adds a warning saying so
+struct Redirected {
+    inner: Box<dyn DataReader>,
+    /// `None` when the extension gave nothing away (`.gz`)
+    warning: Option<String>,
+    /// Decompressed copy the inner reader reads, wiped when the reader is dropped
+    _spool: Option<Spool>,
+}
+
+impl Redirected {
+    fn boxed(
+        inner: Box<dyn DataReader>,
+        warning: Option<String>,
+        spool: Option<Spool>,
+    ) -> Box<dyn DataReader> {
+        Box::new(Self {
+            inner,
+            warning,
+            _spool: spool,
+        })
+    }
+}
+
+impl DataReader for Redirected {
+    fn read(&mut self, options: &ProcessingOptions) -> Result<Vec<SheetSchema>> {
+        self.inner.read(options)
+    }
+
+    fn read_with_recoding(
+        &mut self,
+        options: &ProcessingOptions,
+        audit: &mut dyn AuditSink,
+    ) -> Result<(Vec<SheetSchema>, RecodeRegistry)> {
+        self.inner.read_with_recoding(options, audit)
+    }
+
+    fn encoding(&self) -> Option<String> {
+        self.inner.encoding()
+    }
+
+    fn file_details(&self) -> Option<FileDetails> {
+        self.inner.file_details()
+    }
+
+    fn file_warnings(&self, options: &ProcessingOptions) -> Vec<String> {
+        let mut warnings = self.inner.file_warnings(options);
+        if let Some(ref warning) = self.warning {
+            warnings.insert(0, warning.clone());
+        }
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decompress_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("visits.csv.gz");
+        let mut encoder =
+            flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&b"id,arm\n1,A\n".repeat(1000)).unwrap();
+        std::fs::write(&path, encoder.finish().unwrap()).unwrap();
+
+        let (spool, _dir) = decompress_at_most(&path, 11_000).unwrap();
+        assert_eq!(spool.path().file_name().unwrap(), "visits.csv");
+        assert_eq!(spool.as_file().metadata().unwrap().len(), 11_000);
+
+        let Err(Error::InvalidInput(message)) = decompress_at_most(&path, 10_999) else {
+            panic!("expected the limit to be enforced");
+        };
+        assert!(message.contains("decompresses to more than"), "{}", message);
     }
 }
@@ -0,0 +1,2934 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use zeroize::Zeroize;
+
+use crate::audit::{hash_value, AuditAction, AuditDecision, AuditSink, ReasonCode};
+use crate::clustering::{case_variants, near_duplicates};
+use crate::error::Error;
+use crate::inference::{
+    extended_missing_code, parse_date, parse_datetime, unit_hint, MissingTokens, TypeInferencer,
+};
+use crate::linkage::normalize_key_name;
+use crate::privacy::cdisc::{self, CdiscMatch};
+use crate::privacy::code_systems::detect_code_system;
+use crate::privacy::content::{contradicts_name, ContentEvidence};
+use crate::privacy::locales::{self, detect_language, text_language};
+use crate::privacy::quasi_identifiers::quasi_identifier_kind;
+use crate::privacy::value_patterns::ValuePatternResult;
+use crate::privacy::{
+    bucket_count, bucket_magnitude, check_column_name, check_value_pattern_in, count_phi_in_text,
+    find_phi_in_text, safe_count, ColumnNameResult, RecodeRegistry,
+};
+use crate::sanitize::sanitize_text;
+use crate::stats::{
+    ColumnStatTracker, DuplicateRowDetector, EquivalenceClasses, HyperLogLog, PairwiseCorrelation,
+    SpreadEstimator,
+};
+use crate::types::{
+    CaseVariants, Classification, CodeListCheck, ColumnFingerprint, ColumnSchema, ColumnStats,
+    CompletenessCheck, Correlation, DType, DatePrecision, DiversityCheck, FrequentValue,
+    InferenceConfidence, InferenceDiagnostics, Language, ProcessingOptions, QuasiIdentifierCheck,
+    Result, RowSample, SafeValue, SheetSchema, TextSummary, ValueCluster,
+    CORRELATION_THRESHOLD, MAX_CORRELATION_COLUMNS, MAX_EXAMPLES, MAX_SHORT_STRING_LEN,
+    MIN_SENTINEL_COUNT, SINGLE_PASS_LEADING_ROWS, TOP_K_VALUES,
+};
+use crate::validation::{CodeListTracker, TableValidator};
+
+use super::encoding::repair_double_encoding;
+use super::{RowBatch, StreamingDataReader, DEFAULT_BATCH_SIZE};
+
+/// Profile one table from a streaming source into a sheet schema.
+///
+/// Makes two passes over the stream: the first infers column types, the second
+/// collects statistics (recoding site-identifying columns on the way). A stream
+/// that cannot be rewound is read once: the first pass sees only its leading
+/// rows, and the second widens the types over the rest as it goes.
+/// Every suppression and recode decision is reported to `audit`.
+pub fn profile_stream(
+    stream: &mut dyn StreamingDataReader,
+    sheet_name: &str,
+    sheet_index: usize,
+    options: &ProcessingOptions,
+    audit: &mut dyn AuditSink,
+) -> Result<(SheetSchema, RecodeRegistry)> {
+    // Headers garbled by a wrong decoding ("PrÃ©nom") are repaired first, then
+    // control and bidi characters are removed, before any checks: neither can
+    // hide a PHI column name from the patterns, nor the latter spoof the report
+    let mut raw_headers = stream.headers()?;
+    let repaired_headers: Vec<bool> = raw_headers
+        .iter_mut()
+        .map(|h| match repair_double_encoding(h) {
+            Some(repaired) => {
+                *h = repaired;
+                true
+            }
+            None => false,
+        })
+        .collect();
+    let altered_headers: Vec<bool> = raw_headers
+        .iter()
+        .map(|h| matches!(sanitize_text(h), Cow::Owned(_)))
+        .collect();
+    let headers: Vec<String> = raw_headers
+        .iter()
+        .map(|h| sanitize_text(h).into_owned())
+        .collect();
+    let sheet_name = &*sanitize_text(sheet_name);
+
+    // --columns/--exclude-columns narrow the table before anything is profiled;
+    // columns keep their index in the source
+    let file_columns: Vec<usize> = (0..headers.len())
+        .filter(|&i| !options.selects_columns() || options.column_selected(&headers[i]))
+        .collect();
+    let keep = |flags: &[bool]| -> Vec<bool> { file_columns.iter().map(|&i| flags[i]).collect() };
+    let repaired_headers = keep(&repaired_headers);
+    let altered_headers = keep(&altered_headers);
+    let narrowed = file_columns.len() < headers.len();
+    let headers: Vec<String> = file_columns.iter().map(|&i| headers[i].clone()).collect();
+    let mut selected;
+    let stream: &mut dyn StreamingDataReader = if narrowed {
+        selected = SelectedColumns {
+            inner: stream,
+            columns: &file_columns,
+        };
+        &mut selected
+    } else {
+        stream
+    };
+
+    // --sample-rows/--sample-fraction: the sample is drawn in one read of the
+    // table and profiled from memory
+    let mut sampled;
+    let mut sample = None;
+    let stream: &mut dyn StreamingDataReader = match draw_sample(stream, options)? {
+        Some(drawn) => {
+            sample = (drawn.fraction < 1.0).then_some((drawn.fraction, drawn.table_rows));
+            sampled = SampledRows {
+                inner: stream,
+                rows: drawn.rows,
+                position: 0,
+            };
+            &mut sampled
+        }
+        None => stream,
+    };
+    let single_pass = !stream.rewindable();
+    let mut leading;
+    let stream: &mut dyn StreamingDataReader = if single_pass {
+        leading = LeadingRows {
+            inner: stream,
+            capacity: SINGLE_PASS_LEADING_ROWS,
+            rows: Vec::new(),
+            replay: None,
+        };
+        &mut leading
+    } else {
+        stream
+    };
+    let num_cols = headers.len();
+
+    // Check column names and set up recoding registry
+    let mut recode_registry = RecodeRegistry::new();
+    let column_language = detect_language(&headers);
+    let mut languages = options.locales.clone();
+    if options.auto_locales {
+        languages.extend(column_language);
+    }
+    let packs: Vec<_> = languages.iter().copied().filter_map(locales::pack).collect();
+    let mut column_checks: Vec<_> = headers
+        .iter()
+        .map(|h| check_column_name(h, &packs))
+        .collect();
+
+    // Standard CDISC variables are classified by what the standard says they hold
+    let cdisc_domain = if options.cdisc {
+        cdisc::dataset_domain(sheet_name)
+    } else {
+        None
+    };
+    let cdisc_matches: Vec<Option<CdiscMatch>> = headers
+        .iter()
+        .map(|h| {
+            if options.cdisc {
+                cdisc::match_variable(h, cdisc_domain.as_deref())
+            } else {
+                None
+            }
+        })
+        .collect();
+    for (check, cdisc_match) in column_checks.iter_mut().zip(&cdisc_matches) {
+        if let Some(cdisc_match) = cdisc_match {
+            *check = cdisc_match.name_check();
+        }
+    }
+    for o in &options.classification_overrides {
+        if o.sheet != sheet_name {
+            continue;
+        }
+        let position = file_columns.iter().position(|&i| i == o.column_index);
+        if let Some(check) = position.and_then(|p| column_checks.get_mut(p)) {
+            *check = reviewer_override(&check.classification, &o.classification);
+        }
+    }
+
+    for (col_idx, check) in column_checks.iter().enumerate() {
+        if check.classification == Classification::Recode {
+            // Determine prefix based on the CDISC variable or the column name
+            let prefix = match cdisc_matches[col_idx].as_ref().and_then(|m| m.recode_prefix()) {
+                Some(prefix) => prefix.to_string(),
+                None => determine_recode_prefix(&headers[col_idx]),
+            };
+            recode_registry.register_column(col_idx, &headers[col_idx], &prefix);
+        }
+    }
+
+    // A column's own missing-value tokens from the config replace the scan-wide list
+    let missing_tokens: Vec<MissingTokens> = headers
+        .iter()
+        .map(|h| {
+            let rule = options.missing_token_rules.iter().find(|r| r.matches(sheet_name, h.trim()));
+            match rule {
+                Some(rule) => MissingTokens::replacing(&rule.tokens),
+                None => MissingTokens::extending(&options.missing_tokens),
+            }
+        })
+        .collect();
+
+    // Initialize trackers for each column
+    let mut type_inferencers: Vec<TypeInferencer> = missing_tokens
+        .iter()
+        .map(|missing| {
+            TypeInferencer::with_decimal_separator(options.decimal_separator)
+                .with_missing_tokens(missing.clone())
+        })
+        .collect();
+    // Values --no-values-for/--no-values-columns withhold are not even
+    // collected when the column name already decides the class
+    let mut stat_trackers: Vec<ColumnStatTracker> = column_checks
+        .iter()
+        .zip(&headers)
+        .map(|(check, header)| {
+            let mut tracker = ColumnStatTracker::new(options.max_unique_values);
+            if options
+                .subject_column
+                .as_deref()
+                .is_some_and(|subject| subject.trim().eq_ignore_ascii_case(header.trim()))
+            {
+                tracker.subject_sketch = Some(HyperLogLog::default());
+            }
+            if options.withholds_values(&check.classification, header) {
+                tracker.without_values()
+            } else {
+                tracker
+            }
+        })
+        .collect();
+
+    // Spread estimates from the first pass let the second count outliers
+    let mut spreads: Vec<SpreadEstimator> =
+        (0..num_cols).map(|_| SpreadEstimator::new()).collect();
+    let mut duplicates = DuplicateRowDetector::new();
+    let mut validator = TableValidator::new(&options.validation_rules, &headers)?
+        .with_missing_tokens(missing_tokens.clone());
+    let mut code_lists: Vec<Option<CodeListTracker>> = headers
+        .iter()
+        .map(|h| CodeListTracker::new(&options.code_lists, sheet_name, h))
+        .collect();
+
+    // First pass: type inference
+    let mut row_count: u64 = 0;
+
+    while let Some(batch) = stream.next_batch(DEFAULT_BATCH_SIZE)? {
+        for record in &batch {
+            row_count += 1;
+            duplicates.observe_first_pass(record);
+
+            for (col_idx, field) in record.iter().enumerate().take(num_cols) {
+                type_inferencers[col_idx].observe(field);
+                if missing_tokens[col_idx].is_missing(field) {
+                    continue;
+                }
+                if let Some(num) = type_inferencers[col_idx].parse_number(field) {
+                    spreads[col_idx].update(num);
+                }
+            }
+        }
+        audit.progress(sheet_name, 1, row_count)?;
+    }
+
+    // Finalize type inference
+    for inf in &mut type_inferencers {
+        inf.finalize_initial_inference();
+    }
+    let numeric = stat_trackers.iter_mut().zip(&type_inferencers).zip(&spreads);
+    for ((tracker, inf), spread) in numeric {
+        if matches!(inf.inferred_type(), DType::Integer | DType::Numeric) {
+            tracker.fences = spread.fences();
+        }
+    }
+
+    // Relaxed mode correlates the numeric columns pairwise
+    let numeric_columns: Vec<usize> = if options.relaxed {
+        (0..num_cols)
+            .filter(|&i| {
+                matches!(
+                    type_inferencers[i].inferred_type(),
+                    DType::Integer | DType::Numeric
+                )
+            })
+            .take(MAX_CORRELATION_COLUMNS)
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let mut correlation = PairwiseCorrelation::new(numeric_columns.len());
+    let mut numeric_values: Vec<Option<f64>> = vec![None; numeric_columns.len()];
+
+    // Quasi-identifier combinations, given or suggested from the column names
+    let dtypes: Vec<DType> = type_inferencers.iter().map(|inf| inf.inferred_type()).collect();
+    let sensitive = sensitive_columns(&headers, options);
+    let mut equivalence: Vec<(EquivalenceClasses, Option<usize>)> =
+        quasi_identifier_sets(&headers, &dtypes, options)
+            .into_iter()
+            .map(|(columns, set)| {
+                let measured: Vec<usize> =
+                    sensitive.iter().copied().filter(|i| !columns.contains(i)).collect();
+                let classes = EquivalenceClasses::new(columns)
+                    .with_sensitive(measured, options.l_diversity);
+                (classes, set)
+            })
+            .collect();
+
+    // Second pass: collect statistics (with recoding). Rows the first pass did
+    // not see (single-pass input only) get its bookkeeping here.
+    stream.rewind()?;
+    let first_pass_rows = row_count;
+    let mut rows_read: u64 = 0;
+    while let Some(batch) = stream.next_batch(DEFAULT_BATCH_SIZE)? {
+        for record in &batch {
+            rows_read += 1;
+            if rows_read > first_pass_rows {
+                row_count += 1;
+                duplicates.observe_single_pass(record);
+                for (inferencer, field) in type_inferencers.iter_mut().zip(record) {
+                    inferencer.observe(field);
+                }
+            } else {
+                duplicates.observe_second_pass(record);
+            }
+            validator.observe(record);
+            for (classes, _) in &mut equivalence {
+                classes.observe(record);
+            }
+            if !numeric_columns.is_empty() {
+                for (value, &col_idx) in numeric_values.iter_mut().zip(&numeric_columns) {
+                    *value = record
+                        .get(col_idx)
+                        .filter(|f| !missing_tokens[col_idx].is_missing(f))
+                        .and_then(|f| type_inferencers[col_idx].parse_number(f))
+                        .filter(|x| x.is_finite());
+                }
+                correlation.update(&numeric_values);
+            }
+            for (col_idx, field) in record.iter().enumerate().take(num_cols) {
+                let dtype = type_inferencers[col_idx].inferred_type();
+
+                if let Some(code) = extended_missing_code(field) {
+                    stat_trackers[col_idx].update_missing_code(code);
+                } else if missing_tokens[col_idx].is_missing(field) {
+                    stat_trackers[col_idx].update_missing();
+                } else {
+                    stat_trackers[col_idx].sketch.add(field.trim());
+                    if let Some(subjects) = &mut stat_trackers[col_idx].subject_sketch {
+                        subjects.add_salted(field.trim(), &options.subject_salt);
+                    }
+                    stat_trackers[col_idx].update_whitespace(field);
+                    if let Some(code_list) = &mut code_lists[col_idx] {
+                        code_list.observe(field);
+                    }
+                    if options.deep_scan && matches!(dtype, DType::String | DType::FreeText) {
+                        if let Some(pattern) = find_phi_in_text(field, &languages) {
+                            stat_trackers[col_idx].update_pattern_hit(pattern);
+                        }
+                    }
+                    if dtype == DType::FreeText {
+                        stat_trackers[col_idx].update_text(
+                            text_language(field),
+                            field.split_whitespace().count(),
+                            count_phi_in_text(field, &languages),
+                        );
+                    }
+
+                    // Recode values if this column is marked for recoding
+                    let value_to_track = if recode_registry.is_recoded(col_idx) {
+                        recode_registry
+                            .recode(col_idx, field)
+                            .unwrap_or_else(|| field.to_string())
+                    } else {
+                        field.to_string()
+                    };
+
+                    match dtype {
+                        DType::Integer | DType::Numeric => {
+                            if dtype == DType::Integer {
+                                stat_trackers[col_idx].update_digits(field);
+                            }
+                            if let Some(num) = type_inferencers[col_idx].parse_number(field) {
+                                stat_trackers[col_idx].update_numeric(num, &value_to_track);
+                            } else {
+                                stat_trackers[col_idx].update_string(&value_to_track);
+                            }
+                        }
+                        DType::Date | DType::Datetime => {
+                            let date = match dtype {
+                                DType::Date => parse_date(field),
+                                _ => parse_datetime(field).map(|dt| dt.date()),
+                            };
+                            if let Some(date) = date {
+                                stat_trackers[col_idx].update_date(date);
+                            }
+                            stat_trackers[col_idx].update_string(&value_to_track);
+                        }
+                        _ => {
+                            if matches!(dtype, DType::String | DType::FreeText) {
+                                stat_trackers[col_idx].update_length(field);
+                            }
+                            stat_trackers[col_idx].update_string(&value_to_track);
+                        }
+                    }
+                }
+            }
+        }
+        audit.progress(sheet_name, 2, rows_read)?;
+    }
+
+    // Build column schemas
+    let mut context = ColumnContext {
+        sheet: sheet_name,
+        file_columns: &file_columns,
+        options,
+        languages: &languages,
+        recode_registry: &recode_registry,
+        audit,
+    };
+    let mut columns: Vec<ColumnSchema> = Vec::with_capacity(num_cols);
+    for (col_idx, header) in headers.iter().enumerate() {
+        let mut column = build_column(
+            col_idx,
+            header,
+            &column_checks[col_idx],
+            type_inferencers[col_idx].inferred_type(),
+            &stat_trackers[col_idx],
+            &mut context,
+        )?;
+        if repaired_headers[col_idx] {
+            column.warnings.push(
+                "Column name repaired: it was UTF-8 text decoded with the wrong encoding \
+                 (e.g. \"PrÃ©nom\" for \"Prénom\")"
+                    .to_string(),
+            );
+        }
+        if altered_headers[col_idx] {
+            column.warnings.push(
+                "Control or bidirectional formatting characters removed from column name"
+                    .to_string(),
+            );
+        }
+        if let Some(layout) = type_inferencers[col_idx].compact_date_layout() {
+            column.warnings.push(format!(
+                "Dates stored as 8-digit numbers ({}); convert before analysis",
+                layout
+            ));
+        }
+        column.cdisc_variable = cdisc_matches[col_idx].as_ref().map(|m| m.variable.clone());
+        let format_hint = type_inferencers[col_idx].format_hint();
+        column.unit_hint = unit_hint(header)
+            .or(format_hint.filter(|&hint| hint == "percent").map(|_| "%"))
+            .map(str::to_string);
+        column.format_hint = format_hint.map(str::to_string);
+        column.diagnostics = Some(inference_diagnostics(&type_inferencers[col_idx], options));
+        column.decimal_separator = type_inferencers[col_idx].numeric_locale();
+        if let Some(code_list) = &code_lists[col_idx] {
+            let check = code_list_check(code_list, &column.classification, options, &languages);
+            if !check.passed {
+                column.warnings.push(format!(
+                    "{} value(s) outside the declared code list",
+                    check.out_of_list
+                ));
+            }
+            column.code_list = Some(check);
+        }
+        columns.push(column);
+    }
+
+    let mut sheet = SheetSchema::new(sheet_name.to_string(), sheet_index);
+    sheet.row_count = safe_count(row_count, options.bucket_counts);
+    sheet.duplicate_rows = Some(safe_count(
+        duplicates.duplicate_rows(),
+        options.bucket_counts,
+    ));
+    sheet.correlations = strong_correlations(&correlation, &numeric_columns, &columns, options);
+    for (classes, set) in &equivalence {
+        let Some(check) = quasi_identifier_check(classes, *set, &headers, &columns, options) else {
+            continue;
+        };
+        if check.min_class_size < options.k_anonymity {
+            sheet.warnings.push(format!(
+                "Quasi-identifiers {}: {}% of rows are in groups of fewer than k={} rows \
+                 sharing one combination of values (smallest group: {})",
+                check.columns.join(", "),
+                check.percent_below_k,
+                options.k_anonymity,
+                check.min_class_size
+            ));
+        }
+        for diversity in &check.l_diversity {
+            if diversity.percent_below_l > 0.0 {
+                sheet.warnings.push(format!(
+                    "Quasi-identifiers {}: {}% of rows are in groups with fewer than l={} \
+                     distinct values of sensitive column {}, which membership in the group \
+                     reveals",
+                    check.columns.join(", "),
+                    diversity.percent_below_l,
+                    options.l_diversity,
+                    diversity.column
+                ));
+            }
+        }
+        sheet.quasi_identifier_checks.push(check);
+    }
+    sheet.columns = columns;
+    sheet.column_language = column_language;
+    sheet.cdisc_domain = cdisc_domain;
+    sheet.validation = validator.results(sheet_name, options.bucket_counts);
+    if let Some((fraction, table_rows)) = sample {
+        let sample = RowSample {
+            table_rows: safe_count(table_rows, options.bucket_counts),
+            sampled_rows: safe_count(row_count, options.bucket_counts),
+            fraction,
+        };
+        sheet.warnings.push(format!(
+            "Only a sample of {} of {} rows was profiled: counts and statistics describe the \
+             sample, not the whole table",
+            sample.sampled_rows, sample.table_rows
+        ));
+        sheet.sample = Some(sample);
+    }
+    if narrowed {
+        recode_registry.renumber_columns(&file_columns);
+        if headers.is_empty() {
+            sheet
+                .warnings
+                .push("No column matches --columns/--exclude-columns".to_string());
+        }
+    }
+
+    Ok((sheet, recode_registry))
+}
+
+/// The columns of a stream that `--columns`/`--exclude-columns` select
+struct SelectedColumns<'a> {
+    inner: &'a mut dyn StreamingDataReader,
+    columns: &'a [usize],
+}
+
+impl StreamingDataReader for SelectedColumns<'_> {
+    fn headers(&mut self) -> Result<Vec<String>> {
+        let headers = self.inner.headers()?;
+        Ok(self.columns.iter().filter_map(|&i| headers.get(i).cloned()).collect())
+    }
+
+    fn next_batch(&mut self, max_rows: usize) -> Result<Option<RowBatch>> {
+        let Some(batch) = self.inner.next_batch(max_rows)? else {
+            return Ok(None);
+        };
+        let select = |mut row: Vec<String>| -> Vec<String> {
+            // A short row stays short: its missing fields are not made empty ones
+            let len = self.columns.iter().take_while(|&&i| i < row.len()).count();
+            self.columns[..len].iter().map(|&i| std::mem::take(&mut row[i])).collect()
+        };
+        Ok(Some(batch.into_iter().map(select).collect()))
+    }
+
+    fn rewind(&mut self) -> Result<()> {
+        self.inner.rewind()
+    }
+
+    fn rewindable(&self) -> bool {
+        self.inner.rewindable()
+    }
+}
+
+/// The leading rows of a stream that can only be read once, held in memory so
+/// that it still gets two passes: the first ends after `capacity` rows, and
+/// the second replays them, then reads on. The rows are wiped when dropped.
+struct LeadingRows<'a> {
+    inner: &'a mut dyn StreamingDataReader,
+    capacity: usize,
+    rows: Vec<Vec<String>>,
+    /// Leading rows not yet replayed, once rewound
+    replay: Option<std::vec::IntoIter<Vec<String>>>,
+}
+
+impl StreamingDataReader for LeadingRows<'_> {
+    fn headers(&mut self) -> Result<Vec<String>> {
+        self.inner.headers()
+    }
+
+    fn next_batch(&mut self, max_rows: usize) -> Result<Option<RowBatch>> {
+        let Some(replay) = &mut self.replay else {
+            let wanted = max_rows.min(self.capacity - self.rows.len());
+            if wanted == 0 {
+                return Ok(None);
+            }
+            let batch = self.inner.next_batch(wanted)?;
+            if let Some(ref rows) = batch {
+                self.rows.extend(rows.iter().cloned());
+            }
+            return Ok(batch);
+        };
+        let batch: RowBatch = replay.take(max_rows).collect();
+        if batch.is_empty() {
+            self.inner.next_batch(max_rows)
+        } else {
+            Ok(Some(batch))
+        }
+    }
+
+    fn rewind(&mut self) -> Result<()> {
+        if self.replay.is_some() {
+            return Err(Error::InvalidInput(
+                "input that can only be read once was rewound twice".to_string(),
+            ));
+        }
+        self.replay = Some(std::mem::take(&mut self.rows).into_iter());
+        Ok(())
+    }
+}
+
+impl Drop for LeadingRows<'_> {
+    fn drop(&mut self) {
+        self.rows.zeroize();
+        if let Some(replay) = &mut self.replay {
+            replay.for_each(|mut row| row.zeroize());
+        }
+    }
+}
+
+/// How a column's type was arrived at, from its inferencer after the first pass
+fn inference_diagnostics(
+    inferencer: &TypeInferencer,
+    options: &ProcessingOptions,
+) -> InferenceDiagnostics {
+    let sample_size = inferencer.sample_size();
+    let widened_to = inferencer.type_changes().to_vec();
+    InferenceDiagnostics {
+        confidence: InferenceConfidence::assess(sample_size, !widened_to.is_empty()),
+        sample_size: safe_count(sample_size, options.bucket_counts),
+        initial_type: inferencer.initial_type(),
+        conflicts: safe_count(inferencer.conflicts(), options.bucket_counts),
+        widened_to,
+    }
+}
+
+/// Rows drawn for `--sample-rows`/`--sample-fraction`, in table order
+struct DrawnSample {
+    rows: Vec<Vec<String>>,
+    /// Rows in the table the sample was drawn from
+    table_rows: u64,
+    /// Share of the table kept
+    fraction: f64,
+}
+
+/// The rows `--sample-rows`/`--sample-fraction` keep, read from the stream in
+/// one pass; `None` when all rows are profiled. `--sample-rows` keeps a uniform random
+/// sample (reservoir sampling), `--sample-fraction` every k-th row.
+fn draw_sample(
+    stream: &mut dyn StreamingDataReader,
+    options: &ProcessingOptions,
+) -> Result<Option<DrawnSample>> {
+    let mut table_rows: u64 = 0;
+    match (options.sample_rows, options.sample_fraction) {
+        (Some(size), _) => {
+            // Seeded alike for every table, so repeated scans draw the same rows
+            let mut random = SplitMix64(0x5EED);
+            let mut reservoir: Vec<(u64, Vec<String>)> = Vec::new();
+            while let Some(batch) = stream.next_batch(DEFAULT_BATCH_SIZE)? {
+                for row in batch {
+                    if (reservoir.len() as u64) < size {
+                        reservoir.push((table_rows, row));
+                    } else {
+                        let slot = random.below(table_rows + 1);
+                        if slot < size {
+                            let (_, mut old) = std::mem::replace(
+                                &mut reservoir[slot as usize],
+                                (table_rows, row),
+                            );
+                            old.zeroize();
+                        }
+                    }
+                    table_rows += 1;
+                }
+            }
+            reservoir.sort_unstable_by_key(|(position, _)| *position);
+            let fraction = reservoir.len() as f64 / table_rows.max(1) as f64;
+            let rows = reservoir.into_iter().map(|(_, row)| row).collect();
+            Ok(Some(DrawnSample {
+                rows,
+                table_rows,
+                fraction,
+            }))
+        }
+        (None, Some(fraction)) if fraction < 1.0 => {
+            // Row `i` is kept when `i * fraction` crosses a whole number, so the
+            // kept rows are evenly spread
+            let keeps =
+                |row: u64| ((row + 1) as f64 * fraction).floor() > (row as f64 * fraction).floor();
+            let mut rows = Vec::new();
+            while let Some(batch) = stream.next_batch(DEFAULT_BATCH_SIZE)? {
+                for row in batch {
+                    if keeps(table_rows) {
+                        rows.push(row);
+                    }
+                    table_rows += 1;
+                }
+            }
+            Ok(Some(DrawnSample {
+                rows,
+                table_rows,
+                fraction,
+            }))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Pseudo-random numbers for sampling (SplitMix64)
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A number below `n`
+    fn below(&mut self, n: u64) -> u64 {
+        ((self.next() as u128 * n as u128) >> 64) as u64
+    }
+}
+
+/// The sampled rows of a stream, held in memory so that the profiling passes
+/// do not read the table again. They are wiped when dropped.
+struct SampledRows<'a> {
+    inner: &'a mut dyn StreamingDataReader,
+    rows: Vec<Vec<String>>,
+    /// Rows returned in this pass
+    position: usize,
+}
+
+impl StreamingDataReader for SampledRows<'_> {
+    fn headers(&mut self) -> Result<Vec<String>> {
+        self.inner.headers()
+    }
+
+    fn next_batch(&mut self, max_rows: usize) -> Result<Option<RowBatch>> {
+        if self.position >= self.rows.len() {
+            return Ok(None);
+        }
+        let end = (self.position + max_rows).min(self.rows.len());
+        let batch = self.rows[self.position..end].to_vec();
+        self.position = end;
+        Ok(Some(batch))
+    }
+
+    fn rewind(&mut self) -> Result<()> {
+        self.position = 0;
+        Ok(())
+    }
+}
+
+impl Drop for SampledRows<'_> {
+    fn drop(&mut self) {
+        self.rows.zeroize();
+    }
+}
+
+/// Column sets to measure k-anonymity of, with the position of each among the
+/// given sets (`None` for the suggested one). A given set is skipped when the
+/// table lacks one of its columns (matched case-insensitively); the suggestion
+/// needs at least two quasi-identifiers.
+fn quasi_identifier_sets(
+    headers: &[String],
+    dtypes: &[DType],
+    options: &ProcessingOptions,
+) -> Vec<(Vec<usize>, Option<usize>)> {
+    let position = |name: &str| {
+        headers
+            .iter()
+            .position(|h| h.trim().eq_ignore_ascii_case(name.trim()))
+    };
+    let mut sets: Vec<(Vec<usize>, Option<usize>)> = Vec::new();
+    for (i, set) in options.quasi_identifier_sets.iter().enumerate() {
+        let columns: Option<Vec<usize>> = set.iter().map(|name| position(name)).collect();
+        if let Some(mut columns) = columns {
+            columns.sort_unstable();
+            columns.dedup();
+            sets.push((columns, Some(i)));
+        }
+    }
+    if options.suggest_quasi_identifiers {
+        let columns: Vec<usize> = (0..headers.len())
+            .filter(|&i| quasi_identifier_kind(&headers[i], dtypes[i]).is_some())
+            .collect();
+        if columns.len() >= 2 && !sets.iter().any(|(given, _)| *given == columns) {
+            sets.push((columns, None));
+        }
+    }
+    sets
+}
+
+/// The sensitive columns (`--sensitive-columns`) the table has, matched
+/// case-insensitively, with their position among the given ones
+fn sensitive_columns(headers: &[String], options: &ProcessingOptions) -> Vec<usize> {
+    let mut columns: Vec<usize> = options
+        .sensitive_columns
+        .iter()
+        .filter_map(|name| {
+            headers
+                .iter()
+                .position(|h| h.trim().eq_ignore_ascii_case(name.trim()))
+        })
+        .collect();
+    columns.sort_unstable();
+    columns.dedup();
+    columns
+}
+
+/// Equivalence-class sizes of a quasi-identifier combination, and the diversity
+/// of the sensitive columns within the classes; columns whose name is withheld
+/// are named by position
+fn quasi_identifier_check(
+    classes: &EquivalenceClasses,
+    set: Option<usize>,
+    headers: &[String],
+    columns: &[ColumnSchema],
+    options: &ProcessingOptions,
+) -> Option<QuasiIdentifierCheck> {
+    let min_class_size = classes.min_class_size()?;
+    let records = classes.records() as f64;
+    let percent = |rows: u64| (rows as f64 / records * 1000.0).round() / 10.0;
+    let name = |i: usize| match columns[i].name {
+        SafeValue::ShortString(ref name) => name.clone(),
+        _ => format!("column {}", i + 1),
+    };
+    let l_diversity = classes
+        .sensitive_columns()
+        .iter()
+        .enumerate()
+        .map(|(n, &i)| {
+            let (groups, rows) = classes.below_diversity(n, options.l_diversity);
+            DiversityCheck {
+                column: name(i),
+                groups_below_l: safe_count(groups, options.bucket_counts),
+                percent_below_l: percent(rows),
+                sensitive: options
+                    .sensitive_columns
+                    .iter()
+                    .position(|s| s.trim().eq_ignore_ascii_case(headers[i].trim()))
+                    .unwrap_or_default(),
+            }
+        })
+        .collect();
+    Some(QuasiIdentifierCheck {
+        columns: classes.columns().iter().map(|&i| name(i)).collect(),
+        suggested: set.is_none(),
+        classes: safe_count(classes.class_count(), options.bucket_counts),
+        min_class_size,
+        percent_below_k: percent(classes.records_below(options.k_anonymity)),
+        l_diversity,
+        set,
+    })
+}
+
+/// Pairs correlated at least `CORRELATION_THRESHOLD`, based on at least k rows.
+/// Columns whose name or values are withheld are left out.
+fn strong_correlations(
+    tracker: &PairwiseCorrelation,
+    numeric_columns: &[usize],
+    columns: &[ColumnSchema],
+    options: &ProcessingOptions,
+) -> Vec<Correlation> {
+    let shown = |i: usize| match &columns[numeric_columns[i]] {
+        ColumnSchema {
+            name: SafeValue::ShortString(name),
+            classification: Classification::Safe | Classification::Warning,
+            ..
+        } => Some(name.clone()),
+        _ => None,
+    };
+    let mut correlations: Vec<Correlation> = tracker
+        .correlations()
+        .into_iter()
+        .filter(|&(_, _, r, n)| r.abs() >= CORRELATION_THRESHOLD && n >= options.k_anonymity)
+        .filter_map(|(i, j, r, n)| {
+            Some(Correlation {
+                columns: [shown(i)?, shown(j)?],
+                r: (r * 1000.0).round() / 1000.0,
+                count: safe_count(n, options.bucket_counts),
+            })
+        })
+        .collect();
+    correlations.sort_by(|a, b| b.r.abs().total_cmp(&a.r.abs()));
+    correlations
+}
+
+/// Near-duplicate spellings among a column's values. Only spellings already
+/// reported in `exported` are named; the rest are only counted. Groups that
+/// differ only in letter case are left to `letter_case_variants`.
+fn value_clusters(
+    values: &HashSet<String>,
+    counts: Option<&HashMap<String, u64>>,
+    exported: &BTreeMap<String, SafeValue>,
+    options: &ProcessingOptions,
+) -> Vec<ValueCluster> {
+    let with_counts = values.iter().map(|v| {
+        let count = counts.and_then(|c| c.get(v)).copied().unwrap_or(1);
+        (v.as_str(), count)
+    });
+    near_duplicates(with_counts)
+        .into_iter()
+        .filter(|cluster| {
+            let first = cluster.members[0].0.to_lowercase();
+            cluster.members.iter().any(|(v, _)| v.to_lowercase() != first)
+        })
+        .map(|cluster| ValueCluster {
+            variants: safe_count(cluster.members.len() as u64, options.bucket_counts),
+            count: safe_count(cluster.total(), options.bucket_counts),
+            exemplars: cluster
+                .members
+                .iter()
+                .map(|(v, _)| sanitize_text(v).into_owned())
+                .filter(|v| exported.contains_key(v))
+                .map(SafeValue::ShortString)
+                .collect(),
+        })
+        .collect()
+}
+
+/// Values written in several letter cases, with the count of each variant.
+/// Only variants already reported in `exported` are named; the rest are counted.
+fn letter_case_variants(
+    values: &HashSet<String>,
+    counts: Option<&HashMap<String, u64>>,
+    exported: &BTreeMap<String, SafeValue>,
+    options: &ProcessingOptions,
+) -> Vec<CaseVariants> {
+    let with_counts = values.iter().map(|v| {
+        let count = counts.and_then(|c| c.get(v)).copied().unwrap_or(1);
+        (v.as_str(), count)
+    });
+    case_variants(with_counts)
+        .into_iter()
+        .map(|group| {
+            let mut variants = BTreeMap::new();
+            let mut withheld = 0;
+            for (value, count) in group.members {
+                let value = sanitize_text(value).into_owned();
+                if exported.contains_key(&value) {
+                    variants.insert(value, safe_count(count, options.bucket_counts));
+                } else {
+                    withheld += 1;
+                }
+            }
+            CaseVariants {
+                variants,
+                withheld: (withheld > 0).then(|| safe_count(withheld, options.bucket_counts)),
+            }
+        })
+        .collect()
+}
+
+/// Table-wide inputs shared by every column of a table
+struct ColumnContext<'a> {
+    sheet: &'a str,
+    /// Index in the source of each profiled column
+    file_columns: &'a [usize],
+    options: &'a ProcessingOptions,
+    /// Locales whose value patterns are checked
+    languages: &'a [Language],
+    recode_registry: &'a RecodeRegistry,
+    audit: &'a mut dyn AuditSink,
+}
+
+/// Build the privacy-safe schema for one column from its collected statistics
+fn build_column(
+    col_idx: usize,
+    header: &str,
+    name_check: &ColumnNameResult,
+    dtype: DType,
+    tracker: &ColumnStatTracker,
+    context: &mut ColumnContext,
+) -> Result<ColumnSchema> {
+    let options = context.options;
+    let recode_registry = context.recode_registry;
+    let file_index = context.file_columns[col_idx];
+
+    // Determine classification
+    let mut classification = name_check.classification.clone();
+    if tracker.unique_tracker.is_high_cardinality()
+        && classification != Classification::Recode
+        && classification != Classification::Phi
+    {
+        classification = Classification::HighCardinality;
+    }
+
+    // Columns of clinical codes (SNOMED CT concept IDs look like other long numbers)
+    let coded = classification != Classification::Recode
+        && matches!(dtype, DType::String | DType::Integer);
+    let code_system = if !coded {
+        None
+    } else if let Some(values) = tracker.unique_tracker.values() {
+        detect_code_system(values.iter().map(String::as_str))
+    } else {
+        let top = tracker.top_values.top(TOP_K_VALUES);
+        detect_code_system(top.iter().map(|hit| hit.value.as_str()))
+    };
+
+    // Long integers of one length, mostly distinct, are identifiers whatever the column is called
+    let numeric_id = numeric_id_digits(dtype, tracker).filter(|_| code_system.is_none());
+    if numeric_id.is_some() && classification == Classification::Safe {
+        classification = Classification::Warning;
+    }
+
+    // Values that plainly are not PHI outweigh the column name (`--content-aware`)
+    let content_override = if options.content_aware {
+        let evidence = ContentEvidence {
+            dtype,
+            value_counts: tracker.unique_tracker.value_counts(),
+            pattern_hits: tracker.pattern_hits.values().sum(),
+            k_anonymity: options.k_anonymity,
+            languages: context.languages,
+        };
+        contradicts_name(name_check, &evidence)
+    } else {
+        None
+    };
+    if content_override.is_some() {
+        classification = Classification::Warning;
+    }
+
+    // Build column name SafeValue
+    let name_value = if classification == Classification::Phi {
+        SafeValue::Suppressed {
+            reason: "Column name matches PHI pattern".to_string(),
+        }
+    } else {
+        SafeValue::from_string(header, "Column name too long")
+    };
+
+    // Decisions are logged against the column name as it appears in the manifest
+    let sheet = context.sheet;
+    let column_name = name_value.clone();
+    let decision = |action, reason, rule: &str, count| AuditDecision {
+        sheet: sheet.to_string(),
+        column_index: file_index,
+        column: column_name.clone(),
+        action,
+        rule: rule.to_string(),
+        reason,
+        count,
+        value_hash: None,
+    };
+    let rule_or = |fallback: &str| {
+        name_check
+            .matched_pattern
+            .clone()
+            .unwrap_or_else(|| fallback.to_string())
+    };
+
+    let mut col_schema = ColumnSchema::new(name_value, file_index, dtype);
+    col_schema.classification = classification.clone();
+    col_schema.code_system = code_system;
+
+    // Add warnings
+    if let Some(reason) = &content_override {
+        col_schema.warnings.push(reason.clone());
+    } else if let Some(warning) = &name_check.warning {
+        col_schema.warnings.push(warning.clone());
+    }
+    if let Some(digits) = numeric_id {
+        col_schema.warnings.push(format!(
+            "Values are distinct {}-digit numbers; likely an identifier (MRN, phone number)",
+            digits
+        ));
+    }
+
+    // Build stats
+    let mut stats = ColumnStats::default();
+    let non_missing_count = tracker.count();
+    stats.count = Some(safe_count(non_missing_count, options.bucket_counts));
+    stats.missing_count = Some(safe_count(tracker.missing_count, options.bucket_counts));
+    if !tracker.missing_codes.is_empty() {
+        stats.missing_codes = Some(
+            tracker
+                .missing_codes
+                .iter()
+                .map(|(code, &n)| (code.clone(), safe_count(n, options.bucket_counts)))
+                .collect(),
+        );
+    }
+    if !tracker.pattern_hits.is_empty() {
+        let cells: u64 = tracker.pattern_hits.values().sum();
+        let patterns: Vec<&str> = tracker.pattern_hits.keys().copied().collect();
+        col_schema.warnings.push(format!(
+            "Deep scan: {} cell(s) match PHI value patterns ({})",
+            bucket_count(cells),
+            patterns.join(", ")
+        ));
+        stats.pattern_hits = Some(
+            tracker
+                .pattern_hits
+                .iter()
+                .map(|(pattern, &n)| (pattern.to_string(), safe_count(n, options.bucket_counts)))
+                .collect(),
+        );
+    }
+    if !tracker.whitespace_anomalies.is_empty() {
+        let kinds: Vec<String> = tracker
+            .whitespace_anomalies
+            .iter()
+            .map(|(&kind, &n)| {
+                let described = match kind {
+                    "leading_trailing_whitespace" => "leading or trailing whitespace",
+                    "non_breaking_space" => "non-breaking spaces",
+                    _ => "zero-width characters",
+                };
+                format!("{} with {}", bucket_count(n), described)
+            })
+            .collect();
+        col_schema.warnings.push(format!(
+            "Value(s) with hidden whitespace, which can break joins: {}",
+            kinds.join(", ")
+        ));
+        stats.whitespace_anomalies = Some(
+            tracker
+                .whitespace_anomalies
+                .iter()
+                .map(|(kind, &n)| (kind.to_string(), safe_count(n, options.bucket_counts)))
+                .collect(),
+        );
+    }
+    if let Some((length, count)) = tracker.truncated_values() {
+        col_schema.warnings.push(format!(
+            "{} value(s) are exactly {} characters long, the column's maximum; \
+             they may have been truncated on export",
+            bucket_count(count),
+            length
+        ));
+    }
+    if tracker.text_words > 0 {
+        // Rounded so the density does not give away exact counts
+        let density = tracker.text_phi_hits as f64 * 1000.0 / tracker.text_words as f64;
+        let density = (density * 10.0).round() / 10.0;
+        if tracker.text_phi_hits > 0 {
+            col_schema.warnings.push(format!(
+                "Free text holds {} PHI pattern matches per 1000 words",
+                density
+            ));
+        }
+        stats.text_summary = Some(TextSummary {
+            languages: tracker
+                .text_languages
+                .iter()
+                .map(|(code, &n)| (code.to_string(), safe_count(n, options.bucket_counts)))
+                .collect(),
+            words: safe_count(tracker.text_words, options.bucket_counts),
+            phi_density: density,
+        });
+    }
+    col_schema.completeness =
+        completeness_check(options, sheet, header, non_missing_count, tracker.missing_count);
+    if let Some(ref check) = col_schema.completeness {
+        if !check.passed {
+            col_schema.warnings.push(format!(
+                "Completeness {}% is below the required {}%",
+                check.complete_percent, check.required_percent
+            ));
+        }
+    }
+
+    if classification == Classification::Phi {
+        context.audit.record(decision(
+            AuditAction::Suppress,
+            ReasonCode::PhiColumnName,
+            &rule_or("column_name"),
+            non_missing_count,
+        ))?;
+    } else if header.len() > MAX_SHORT_STRING_LEN {
+        context.audit.record(decision(
+            AuditAction::Suppress,
+            ReasonCode::ColumnNameTooLong,
+            "max_value_length",
+            1,
+        ))?;
+    }
+
+    if let DType::Integer | DType::Numeric = dtype {
+        // Range and quantiles are (close to) single values; withheld for PHI
+        // columns (e.g. record numbers), like the date range below
+        if classification != Classification::Phi {
+            if let Some(min) = tracker.welford.min() {
+                stats.min = Some(SafeValue::Float(min));
+            }
+            if let Some(max) = tracker.welford.max() {
+                stats.max = Some(SafeValue::Float(max));
+            }
+            stats.median = tracker.p2_median.quantile();
+            stats.q1 = tracker.p2_q1.quantile();
+            stats.q3 = tracker.p2_q3.quantile();
+        }
+        stats.mean = tracker.welford.mean();
+        stats.std_dev = tracker.welford.std_dev();
+        if tracker.non_finite_count > 0 {
+            stats.non_finite_count =
+                Some(safe_count(tracker.non_finite_count, options.bucket_counts));
+            col_schema.warnings.push(
+                "Some values are not finite numbers (NaN, inf or too large, like 1e999); \
+                 they are left out of the range, mean and quantiles"
+                    .to_string(),
+            );
+        }
+        if tracker.fences.is_some() {
+            stats.outliers_sd = Some(safe_count(tracker.outliers_sd, options.bucket_counts));
+            stats.outliers_iqr = Some(safe_count(tracker.outliers_iqr, options.bucket_counts));
+        }
+        let codes: BTreeMap<String, SafeValue> = tracker
+            .sentinel_counts
+            .iter()
+            .filter(|(_, &count)| count >= MIN_SENTINEL_COUNT)
+            .map(|(code, &count)| (code.to_string(), safe_count(count, options.bucket_counts)))
+            .collect();
+        if !codes.is_empty() {
+            let listed: Vec<&str> = codes.keys().map(String::as_str).collect();
+            col_schema.warnings.push(format!(
+                "Values {} look like missing-value codes; declare them with --missing-tokens",
+                listed.join(", ")
+            ));
+            stats.suspected_missing_codes = Some(codes);
+        }
+    }
+
+    // Date range; withheld for PHI columns (e.g. dates of birth)
+    if let Some((earliest, latest)) = tracker.date_range {
+        if classification != Classification::Phi {
+            let precision = options.date_precision;
+            stats.earliest = Some(SafeValue::ShortString(precision.format(earliest)));
+            stats.latest = Some(SafeValue::ShortString(precision.format(latest)));
+            // An exact span would undo a coarser precision
+            if matches!(precision, DatePrecision::Day) {
+                stats.span_days = Some((latest - earliest).num_days());
+            }
+        }
+    }
+
+    // Unique count
+    let unique_count = tracker.unique_tracker.unique_count() as u64;
+    let untracked = (tracker.unique_tracker.is_high_cardinality()
+        && classification != Classification::Recode)
+        || !tracker.collect_values;
+    if untracked {
+        // Exact tracking was abandoned at the cap; report the sketch estimate, always bucketed
+        stats.unique_count = Some(SafeValue::ShortString(bucket_magnitude(
+            tracker.approximate_unique_count(),
+        )));
+        stats.unique_count_approximate = true;
+        if classification == Classification::HighCardinality {
+            context.audit.record(decision(
+                AuditAction::Suppress,
+                ReasonCode::HighCardinality,
+                "max_unique_values",
+                tracker.approximate_unique_count(),
+            ))?;
+        }
+    } else if options.bucket_counts {
+        stats.unique_count = Some(SafeValue::ShortString(
+            bucket_count(unique_count).to_string(),
+        ));
+    } else {
+        stats.unique_count = Some(SafeValue::Integer(unique_count as i64));
+    }
+
+    // Descriptions of custom warning detectors matched by exported values
+    let mut flagged: Vec<&str> = Vec::new();
+    // RAMQ numbers were seen, whose digits are the holder's birth date
+    let mut leaks_birth_date = tracker.pattern_hits.contains_key("ramq");
+    // Exported values with their counts, the candidates for `--examples`
+    let mut examples: Vec<(u64, String)> = Vec::new();
+
+    // Clinical codes are exempt from the value patterns they happen to resemble
+    let languages = context.languages;
+    let check_value = |value: &str| match code_system {
+        Some(system) if system.matches(value) => ValuePatternResult::safe(),
+        _ => check_value_pattern_in(value, languages),
+    };
+
+    // Build unique values list
+    if options.withholds_values(&classification, header) {
+        context.audit.record(decision(
+            AuditAction::Suppress,
+            ReasonCode::ValuesWithheld,
+            if options.no_values_for.contains(&classification) {
+                "no_values_for"
+            } else {
+                "no_values_columns"
+            },
+            non_missing_count,
+        ))?;
+    } else if classification == Classification::Recode {
+        // For recoded columns, show the recoded values
+        if let Some(recoded_values) = recode_registry.get_recoded_values(col_idx) {
+            context.audit.record(decision(
+                AuditAction::Recode,
+                ReasonCode::SiteIdentifier,
+                &rule_or("site"),
+                recoded_values.len() as u64,
+            ))?;
+            let safe_values: Vec<SafeValue> = recoded_values
+                .into_iter()
+                .map(SafeValue::ShortString)
+                .collect();
+            if !safe_values.is_empty() {
+                col_schema.unique_values = Some(safe_values);
+            }
+        }
+    } else if classification == Classification::Safe || classification == Classification::Warning {
+        if let Some(values) = tracker.unique_tracker.values() {
+            let mut safe_values: Vec<SafeValue> = Vec::new();
+            let mut frequencies: BTreeMap<String, SafeValue> = BTreeMap::new();
+            let mut small_cells: u64 = 0;
+            let counts = tracker.unique_tracker.value_counts();
+
+            for raw_value in values {
+                let count = counts.and_then(|c| c.get(raw_value)).copied().unwrap_or(1);
+                let value = &*sanitize_text(raw_value);
+
+                if count >= options.k_anonymity {
+                    // Check value-level privacy
+                    let value_check = check_value(value);
+                    leaks_birth_date |= value_check.matched_pattern == Some("ramq");
+                    if value_check.is_phi {
+                        context.audit.record(AuditDecision {
+                            value_hash: Some(hash_value(raw_value)),
+                            ..decision(
+                                AuditAction::Suppress,
+                                ReasonCode::PhiValue,
+                                value_check.matched_pattern.unwrap_or("value_pattern"),
+                                count,
+                            )
+                        })?;
+                    } else if value.len() > MAX_SHORT_STRING_LEN {
+                        context.audit.record(AuditDecision {
+                            value_hash: Some(hash_value(raw_value)),
+                            ..decision(
+                                AuditAction::Suppress,
+                                ReasonCode::ValueTooLong,
+                                "max_value_length",
+                                count,
+                            )
+                        })?;
+                    } else {
+                        if value_check.is_warning {
+                            flagged.extend(value_check.description);
+                        }
+                        safe_values.push(SafeValue::ShortString(value.to_string()));
+                        frequencies
+                            .insert(value.to_string(), safe_count(count, options.bucket_counts));
+                        if options.examples && !value_check.is_warning {
+                            examples.push((count, value.to_string()));
+                        }
+                    }
+                } else {
+                    small_cells += 1;
+                    context.audit.record(AuditDecision {
+                        value_hash: Some(hash_value(raw_value)),
+                        ..decision(
+                            AuditAction::Suppress,
+                            ReasonCode::BelowK,
+                            "k_anonymity",
+                            count,
+                        )
+                    })?;
+                }
+            }
+
+            if !safe_values.is_empty() {
+                col_schema.unique_values = Some(safe_values);
+            }
+
+            if classification == Classification::Safe && dtype == DType::String {
+                let clusters = value_clusters(values, counts, &frequencies, options);
+                if !clusters.is_empty() {
+                    col_schema.warnings.push(format!(
+                        "{} group(s) of near-duplicate values; harmonize spellings before analysis",
+                        clusters.len()
+                    ));
+                    stats.value_clusters = Some(clusters);
+                }
+            }
+            if dtype == DType::String {
+                let groups = letter_case_variants(values, counts, &frequencies, options);
+                if !groups.is_empty() {
+                    col_schema.warnings.push(format!(
+                        "{} value(s) written in several letter cases; harmonize before analysis",
+                        groups.len()
+                    ));
+                    stats.case_variants = Some(groups);
+                }
+            }
+
+            // Frequency tables are for categories; numbers and dates get summary statistics
+            if matches!(dtype, DType::String | DType::Boolean) && !frequencies.is_empty() {
+                if small_cells > 0 {
+                    stats.suppressed_cells = Some(safe_count(small_cells, options.bucket_counts));
+                }
+                stats.value_frequencies = Some(frequencies);
+            }
+        }
+    } else if classification == Classification::HighCardinality {
+        // Full tracking was abandoned; list heavy hitters whose guaranteed count meets k
+        let mut top_values: Vec<FrequentValue> = Vec::new();
+        for hit in tracker.top_values.top(TOP_K_VALUES) {
+            let count = hit.guaranteed_count();
+            if count < options.k_anonymity {
+                continue;
+            }
+            let value = sanitize_text(&hit.value).into_owned();
+            let value_check = check_value(&value);
+            leaks_birth_date |= value_check.matched_pattern == Some("ramq");
+            let suppressed = if value_check.is_phi {
+                Some((
+                    ReasonCode::PhiValue,
+                    value_check.matched_pattern.unwrap_or("value_pattern"),
+                ))
+            } else if value.len() > MAX_SHORT_STRING_LEN {
+                Some((ReasonCode::ValueTooLong, "max_value_length"))
+            } else {
+                None
+            };
+            match suppressed {
+                Some((reason, rule)) => context.audit.record(AuditDecision {
+                    value_hash: Some(hash_value(&hit.value)),
+                    ..decision(AuditAction::Suppress, reason, rule, count)
+                })?,
+                None => {
+                    if value_check.is_warning {
+                        flagged.extend(value_check.description);
+                    }
+                    top_values.push(FrequentValue {
+                        count: safe_count(count, options.bucket_counts),
+                        value: SafeValue::ShortString(value),
+                    })
+                }
+            }
+        }
+        if !top_values.is_empty() {
+            stats.top_values = Some(top_values);
+        }
+    }
+
+    flagged.sort_unstable();
+    flagged.dedup();
+    if !flagged.is_empty() && col_schema.classification == Classification::Safe {
+        col_schema.classification = Classification::Warning;
+    }
+    for description in flagged {
+        col_schema.warnings.push(description.to_string());
+    }
+    if leaks_birth_date {
+        if col_schema.classification == Classification::Safe {
+            col_schema.classification = Classification::Warning;
+        }
+        col_schema.warnings.push(
+            "Quebec health insurance numbers (RAMQ) found; they also reveal date of birth and sex"
+                .to_string(),
+        );
+    }
+
+    if col_schema.classification == Classification::Safe {
+        examples.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+        col_schema.examples = examples
+            .into_iter()
+            .take(MAX_EXAMPLES)
+            .map(|(_, value)| SafeValue::ShortString(value))
+            .collect();
+    }
+
+    col_schema.stats = Some(stats);
+    col_schema.fingerprint = Some(ColumnFingerprint {
+        key_name: normalize_key_name(header),
+        value_count: non_missing_count,
+        sketch: tracker.sketch.clone(),
+        subject_sketch: tracker.subject_sketch.clone(),
+    });
+    Ok(col_schema)
+}
+
+/// Digit count of an integer column that looks like an identifier: every value
+/// has the same number of digits (7 to 12) and at least half are distinct
+fn numeric_id_digits(dtype: DType, tracker: &ColumnStatTracker) -> Option<usize> {
+    const ID_DIGITS: std::ops::RangeInclusive<usize> = 7..=12;
+    const MIN_VALUES: u64 = 10;
+
+    let (fewest, most) = tracker.digit_range?;
+    let count = tracker.count();
+    if dtype != DType::Integer || fewest != most || !ID_DIGITS.contains(&most) || count < MIN_VALUES
+    {
+        return None;
+    }
+    let distinct = if tracker.unique_tracker.is_high_cardinality() {
+        tracker.approximate_unique_count()
+    } else {
+        tracker.unique_tracker.unique_count() as u64
+    };
+    (distinct * 2 >= count).then_some(most)
+}
+
+/// Check a column against the strictest completeness rule that names it.
+/// Counts are exact here; only the whole-percent result reaches the manifest.
+fn completeness_check(
+    options: &ProcessingOptions,
+    sheet: &str,
+    header: &str,
+    present: u64,
+    missing: u64,
+) -> Option<CompletenessCheck> {
+    let rules = &options.completeness_rules;
+    let mut matching: Vec<usize> = (0..rules.len())
+        .filter(|&i| rules[i].matches(sheet, header))
+        .collect();
+    matching.sort_by(|&a, &b| rules[b].min_percent.total_cmp(&rules[a].min_percent));
+    let min_percent = rules[*matching.first()?].min_percent;
+    let total = present + missing;
+    let percent = if total == 0 {
+        0.0
+    } else {
+        present as f64 * 100.0 / total as f64
+    };
+    Some(CompletenessCheck {
+        required_percent: min_percent,
+        complete_percent: percent.floor(),
+        passed: percent >= min_percent,
+        rules: matching,
+    })
+}
+
+/// Counts of out-of-list values. The values themselves are named only for
+/// columns whose values may be shown, when seen at least k times and not PHI.
+fn code_list_check(
+    tracker: &CodeListTracker,
+    classification: &Classification,
+    options: &ProcessingOptions,
+    languages: &[Language],
+) -> CodeListCheck {
+    let mut values = BTreeMap::new();
+    if matches!(
+        classification,
+        Classification::Safe | Classification::Warning | Classification::HighCardinality
+    ) {
+        for (raw_value, &count) in &tracker.values {
+            let value = sanitize_text(raw_value);
+            if count >= options.k_anonymity
+                && value.len() <= MAX_SHORT_STRING_LEN
+                && !check_value_pattern_in(&value, languages).is_phi
+            {
+                values.insert(value.into_owned(), safe_count(count, options.bucket_counts));
+            }
+        }
+    }
+    CodeListCheck {
+        checked: safe_count(tracker.checked, options.bucket_counts),
+        out_of_list: safe_count(tracker.out_of_list, options.bucket_counts),
+        passed: tracker.out_of_list == 0,
+        values,
+    }
+}
+
+/// Name check result for a column whose classification a reviewer has set
+fn reviewer_override(detected: &Classification, chosen: &Classification) -> ColumnNameResult {
+    ColumnNameResult {
+        classification: chosen.clone(),
+        matched_pattern: Some("reviewer_override".to_string()),
+        warning: Some(format!(
+            "Classification set to '{}' by reviewer (detected: '{}')",
+            chosen.as_str(),
+            detected.as_str()
+        )),
+        matches: Vec::new(),
+    }
+}
+
+/// Determine the appropriate prefix for recoding based on column name
+fn determine_recode_prefix(column_name: &str) -> String {
+    let lower = column_name.to_lowercase();
+    if lower.contains("hospital") {
+        "Hospital".to_string()
+    } else if lower.contains("clinic") {
+        "Clinic".to_string()
+    } else if lower.contains("facility") {
+        "Facility".to_string()
+    } else if lower.contains("center") || lower.contains("centre") {
+        "Center".to_string()
+    } else if lower.contains("location") {
+        "Location".to_string()
+    } else {
+        "Site".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::NullAuditSink;
+    use crate::privacy::code_systems::CodeSystem;
+    use crate::readers::RowBatch;
+    use crate::types::{
+        ClassificationOverride, CodeListRule, CompletenessRule, DatePrecision, DecimalSeparator,
+        MissingTokenRule,
+    };
+
+    /// In-memory stream for exercising the profiler without files
+    struct VecStream {
+        headers: Vec<String>,
+        rows: Vec<Vec<String>>,
+        pos: usize,
+    }
+
+    impl VecStream {
+        fn new(headers: &[&str], rows: &[&[&str]]) -> Self {
+            Self {
+                headers: headers.iter().map(|h| h.to_string()).collect(),
+                rows: rows
+                    .iter()
+                    .map(|r| r.iter().map(|c| c.to_string()).collect())
+                    .collect(),
+                pos: 0,
+            }
+        }
+
+        fn from_rows(headers: &[&str], rows: Vec<Vec<String>>) -> Self {
+            Self {
+                headers: headers.iter().map(|h| h.to_string()).collect(),
+                rows,
+                pos: 0,
+            }
+        }
+    }
+
+    impl StreamingDataReader for VecStream {
+        fn headers(&mut self) -> Result<Vec<String>> {
+            Ok(self.headers.clone())
+        }
+
+        fn next_batch(&mut self, max_rows: usize) -> Result<Option<RowBatch>> {
+            if self.pos >= self.rows.len() {
+                return Ok(None);
+            }
+            let end = (self.pos + max_rows).min(self.rows.len());
+            let batch = self.rows[self.pos..end].to_vec();
+            self.pos = end;
+            Ok(Some(batch))
+        }
+
+        fn rewind(&mut self) -> Result<()> {
+            self.pos = 0;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_profile_stream() {
+        let mut stream = VecStream::new(
+            &["age", "site"],
+            &[&["30", "VAN"], &["40", "CAL"], &["50", "VAN"], &["40", "CAL"]],
+        );
+        let options = ProcessingOptions {
+            bucket_counts: false,
+            ..ProcessingOptions::default()
+        };
+
+        let (sheet, registry) =
+            profile_stream(&mut stream, "table", 2, &options, &mut NullAuditSink).unwrap();
+
+        assert_eq!(sheet.name, "table");
+        assert_eq!(sheet.index, 2);
+        assert_eq!(sheet.row_count, SafeValue::Integer(4));
+        assert_eq!(sheet.duplicate_rows, Some(SafeValue::Integer(1)));
+        assert_eq!(sheet.columns[0].dtype, DType::Integer);
+        assert_eq!(sheet.columns[1].classification, Classification::Recode);
+        assert!(registry.has_recodings());
+    }
+
+    #[test]
+    fn test_classification_overrides() {
+        let mut stream = VecStream::new(
+            &["visit_dob", "age"],
+            &[&["2024-01-01", "30"], &["2024-01-01", "40"]],
+        );
+        let options = ProcessingOptions {
+            classification_overrides: vec![
+                ClassificationOverride {
+                    sheet: "t".to_string(),
+                    column_index: 0,
+                    classification: Classification::Safe,
+                },
+                ClassificationOverride {
+                    sheet: "t".to_string(),
+                    column_index: 1,
+                    classification: Classification::Phi,
+                },
+                ClassificationOverride {
+                    sheet: "other".to_string(),
+                    column_index: 1,
+                    classification: Classification::Safe,
+                },
+            ],
+            ..ProcessingOptions::default()
+        };
+
+        let (sheet, _) =
+            profile_stream(&mut stream, "t", 0, &options, &mut NullAuditSink).unwrap();
+
+        let dob = &sheet.columns[0];
+        assert_eq!(dob.classification, Classification::Safe);
+        assert_eq!(dob.name, SafeValue::ShortString("visit_dob".to_string()));
+        assert!(dob.warnings[0].contains("by reviewer (detected: 'phi')"));
+
+        let age = &sheet.columns[1];
+        assert_eq!(age.classification, Classification::Phi);
+        assert!(matches!(age.name, SafeValue::Suppressed { .. }));
+    }
+
+    #[test]
+    fn test_completeness_check() {
+        let mut rows: Vec<Vec<String>> = (0..20)
+            .map(|i| vec![format!("{}", 100 + i), format!("{}", i)])
+            .collect();
+        rows[0][0].clear();
+        rows[1][0].clear();
+        let mut stream = VecStream::from_rows(&["SBP", "dob"], rows);
+        let rule = |column: &str, min_percent| CompletenessRule {
+            column: column.to_string(),
+            sheet: None,
+            min_percent,
+        };
+        let options = ProcessingOptions {
+            completeness_rules: vec![
+                rule("sbp", 80.0),
+                rule("sbp", 95.0),
+                rule("dob", 100.0),
+                rule("age", 50.0),
+            ],
+            ..ProcessingOptions::default()
+        };
+
+        let (sheet, _) =
+            profile_stream(&mut stream, "t", 0, &options, &mut NullAuditSink).unwrap();
+
+        // 18 of 20 present: 90%, below the stricter rule
+        let sbp = sheet.columns[0].completeness.as_ref().unwrap();
+        assert_eq!(sbp.required_percent, 95.0);
+        assert_eq!(sbp.complete_percent, 90.0);
+        assert!(!sbp.passed);
+        assert_eq!(sbp.rules, vec![1, 0]);
+        assert!(sheet.columns[0]
+            .warnings
+            .contains(&"Completeness 90% is below the required 95%".to_string()));
+
+        // Checked by its real name even though the manifest suppresses it
+        let dob = sheet.columns[1].completeness.as_ref().unwrap();
+        assert!(dob.passed);
+        assert!(matches!(sheet.columns[1].name, SafeValue::Suppressed { .. }));
+    }
+
+    #[test]
+    fn test_profile_stream_audit() {
+        let mut rows: Vec<Vec<String>> = Vec::new();
+        for _ in 0..5 {
+            rows.push(vec!["Ana".into(), "VAN".into(), "flu".into()]);
+            rows.push(vec!["Bo".into(), "CAL".into(), "ana@example.com".into()]);
+        }
+        rows.push(vec!["Cy".into(), "VAN".into(), "rare".into()]);
+        let mut stream = VecStream::from_rows(&["patient_name", "site", "dx"], rows);
+        let mut audit: Vec<AuditDecision> = Vec::new();
+
+        profile_stream(
+            &mut stream,
+            "t",
+            0,
+            &ProcessingOptions::default(),
+            &mut audit,
+        )
+        .unwrap();
+
+        let find = |reason| audit.iter().find(|d| d.reason == reason).unwrap();
+        let name = find(ReasonCode::PhiColumnName);
+        assert_eq!((name.column_index, name.count), (0, 11));
+        assert!(matches!(name.column, SafeValue::Suppressed { .. }));
+
+        let site = find(ReasonCode::SiteIdentifier);
+        assert_eq!((site.action, site.count), (AuditAction::Recode, 2));
+
+        let email = find(ReasonCode::PhiValue);
+        assert_eq!((email.rule.as_str(), email.count), ("email", 5));
+        assert_eq!(email.value_hash, Some(hash_value("ana@example.com")));
+
+        let rare = find(ReasonCode::BelowK);
+        assert_eq!((rare.column_index, rare.count), (2, 1));
+
+        // Raw values never reach the audit trail
+        let log = format!("{:?}", audit);
+        assert!(!log.contains("example.com") && !log.contains("rare"));
+    }
+
+    /// Records progress reports and stops the scan once pass 2 starts
+    struct CancelInPassTwo(Vec<(u8, u64)>);
+
+    impl AuditSink for CancelInPassTwo {
+        fn begin_file(&mut self, _file_name: &str) -> Result<()> {
+            Ok(())
+        }
+
+        fn record(&mut self, _decision: AuditDecision) -> Result<()> {
+            Ok(())
+        }
+
+        fn progress(&mut self, _sheet: &str, pass: u8, rows: u64) -> Result<()> {
+            self.0.push((pass, rows));
+            if pass == 2 {
+                return Err(crate::error::Error::InvalidInput("stop".to_string()));
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_progress_and_cancel() {
+        let rows: Vec<Vec<String>> = (0..DEFAULT_BATCH_SIZE + 5)
+            .map(|i| vec![i.to_string()])
+            .collect();
+        let mut stream = VecStream::from_rows(&["n"], rows);
+        let mut sink = CancelInPassTwo(Vec::new());
+
+        let result = profile_stream(
+            &mut stream,
+            "t",
+            0,
+            &ProcessingOptions::default(),
+            &mut sink,
+        );
+        assert!(matches!(result, Err(crate::error::Error::InvalidInput(_))));
+        let total = DEFAULT_BATCH_SIZE as u64 + 5;
+        assert_eq!(
+            sink.0,
+            vec![(1, DEFAULT_BATCH_SIZE as u64), (1, total), (2, DEFAULT_BATCH_SIZE as u64)]
+        );
+    }
+
+    #[test]
+    fn test_profile_stream_sanitizes_text() {
+        let rows: Vec<Vec<String>> = (0..6)
+            .map(|_| vec!["\u{1b}[31mA\u{1b}[0m".to_string()])
+            .collect();
+        let mut stream = VecStream::from_rows(&["arm\u{202E}mra"], rows);
+
+        let (sheet, _) = profile_stream(
+            &mut stream,
+            "t\u{7}",
+            0,
+            &ProcessingOptions::default(),
+            &mut NullAuditSink,
+        )
+        .unwrap();
+        let col = &sheet.columns[0];
+
+        assert_eq!(sheet.name, "t");
+        assert_eq!(col.name, SafeValue::ShortString("armmra".to_string()));
+        assert_eq!(
+            col.unique_values,
+            Some(vec![SafeValue::ShortString("A".to_string())])
+        );
+        assert!(col.warnings.iter().any(|w| w.contains("bidirectional")));
+    }
+
+    #[test]
+    fn test_profile_stream_repairs_garbled_headers() {
+        let rows: Vec<Vec<String>> = (0..6)
+            .map(|i| vec![format!("Marie{}", i), "A".to_string()])
+            .collect();
+        let mut stream = VecStream::from_rows(&["PrÃ©nom", "bras"], rows);
+        let options = ProcessingOptions {
+            locales: vec![Language::Fr],
+            ..ProcessingOptions::default()
+        };
+
+        let (sheet, _) = profile_stream(&mut stream, "t", 0, &options, &mut NullAuditSink).unwrap();
+        let col = &sheet.columns[0];
+        assert_eq!(col.classification, Classification::Phi);
+        assert!(col.warnings.iter().any(|w| w.starts_with("Column name repaired")));
+        assert!(sheet.columns[1].warnings.is_empty());
+    }
+
+    #[test]
+    fn test_profile_stream_selects_columns() {
+        let rows: Vec<Vec<String>> = (0..6)
+            .map(|i| {
+                let row = [format!("{}", i), "S01".into(), "A".into(), "x".into(), "1".into()];
+                // The last row is short
+                row[..if i == 5 { 3 } else { 5 }].to_vec()
+            })
+            .collect();
+        let headers = ["id", "site", "arm", "notes_1", "lab_hb"];
+        let mut stream = VecStream::from_rows(&headers, rows);
+        let options = ProcessingOptions {
+            columns: vec!["site".into(), "ARM".into(), "lab_*".into()],
+            exclude_columns: vec!["notes_*".into()],
+            ..ProcessingOptions::default()
+        };
+
+        let (sheet, registry) =
+            profile_stream(&mut stream, "t", 0, &options, &mut NullAuditSink).unwrap();
+        let indices: Vec<usize> = sheet.columns.iter().map(|c| c.index).collect();
+        assert_eq!(indices, vec![1, 2, 4]);
+        assert_eq!(sheet.columns[1].name, SafeValue::ShortString("arm".into()));
+        assert_eq!(sheet.columns[0].classification, Classification::Recode);
+        // The site column keeps its place in the file for the sidekick
+        assert!(registry.is_recoded(1));
+        assert!(!registry.is_recoded(0));
+
+        let options = ProcessingOptions {
+            columns: vec!["visit_*".into()],
+            ..ProcessingOptions::default()
+        };
+        let mut stream = VecStream::from_rows(&headers, Vec::new());
+        let (sheet, _) = profile_stream(&mut stream, "t", 0, &options, &mut NullAuditSink).unwrap();
+        assert!(sheet.columns.is_empty());
+        assert_eq!(sheet.warnings, vec!["No column matches --columns/--exclude-columns"]);
+    }
+
+    #[test]
+    fn test_profile_stream_withholds_values() {
+        let rows: Vec<Vec<String>> = (0..60)
+            .map(|i| {
+                let arm = ["A", "B"][i % 2].to_string();
+                vec![arm.clone(), arm, format!("K{}", i % 12), (i % 3).to_string()]
+            })
+            .collect();
+        let options = ProcessingOptions {
+            no_values_for: vec![Classification::HighCardinality],
+            no_values_columns: vec!["ARM_*".into()],
+            max_unique_values: 10,
+            ..ProcessingOptions::default()
+        };
+        let mut stream = VecStream::from_rows(&["arm", "arm_group", "kit", "dose_level"], rows);
+        let (sheet, _) = profile_stream(&mut stream, "t", 0, &options, &mut NullAuditSink).unwrap();
+        assert!(sheet.columns[0].unique_values.is_some());
+
+        // Not collected: no values, and the unique count comes from the sketch
+        let arm_group = &sheet.columns[1];
+        assert_eq!(arm_group.classification, Classification::Safe);
+        assert!(arm_group.unique_values.is_none());
+        let stats = arm_group.stats.as_ref().unwrap();
+        assert!(stats.value_frequencies.is_none() && stats.unique_count_approximate);
+
+        // Over the lowered limit, and its class withheld: no frequent values either
+        let kit = &sheet.columns[2];
+        assert_eq!(kit.classification, Classification::HighCardinality);
+        assert!(kit.stats.as_ref().unwrap().top_values.is_none());
+        assert!(sheet.columns[3].unique_values.is_some());
+    }
+
+    #[test]
+    fn test_profile_stream_inference_diagnostics() {
+        let rows: Vec<Vec<String>> = (0..2500)
+            .map(|i| {
+                let late = if i == 2400 { "unknown".to_string() } else { i.to_string() };
+                let few = if i < 12 { i.to_string() } else { String::new() };
+                vec![i.to_string(), late, few]
+            })
+            .collect();
+        let options = ProcessingOptions {
+            bucket_counts: false,
+            ..ProcessingOptions::default()
+        };
+        let mut stream = VecStream::from_rows(&["id", "dose", "score"], rows);
+        let (sheet, _) = profile_stream(&mut stream, "t", 0, &options, &mut NullAuditSink).unwrap();
+        let diagnostics: Vec<&InferenceDiagnostics> =
+            sheet.columns.iter().map(|c| c.diagnostics.as_ref().unwrap()).collect();
+        assert_eq!(diagnostics[0].confidence, InferenceConfidence::High);
+        assert_eq!(diagnostics[0].sample_size, SafeValue::Integer(2000));
+
+        // Integer for the first 2000 values, then one that is not
+        assert_eq!(sheet.columns[1].dtype, DType::String);
+        assert_eq!(diagnostics[1].confidence, InferenceConfidence::Medium);
+        assert_eq!(diagnostics[1].initial_type, DType::Integer);
+        assert_eq!(diagnostics[1].widened_to, vec![DType::String]);
+        assert_eq!(diagnostics[1].conflicts, SafeValue::Integer(1));
+
+        assert_eq!(sheet.columns[2].dtype, DType::Integer);
+        assert_eq!(diagnostics[2].confidence, InferenceConfidence::Low);
+        assert_eq!(diagnostics[2].sample_size, SafeValue::Integer(12));
+    }
+
+    #[test]
+    fn test_profile_stream_samples_rows() {
+        let rows: Vec<Vec<String>> = (0..3000).map(|i| vec![(i % 7).to_string()]).collect();
+        let options = ProcessingOptions {
+            bucket_counts: false,
+            sample_fraction: Some(0.01),
+            ..ProcessingOptions::default()
+        };
+        let mut stream = VecStream::from_rows(&["score"], rows.clone());
+        let (sheet, _) = profile_stream(&mut stream, "t", 0, &options, &mut NullAuditSink).unwrap();
+        assert_eq!(sheet.row_count, SafeValue::Integer(30));
+        let sample = sheet.sample.as_ref().unwrap();
+        assert_eq!(sample.table_rows, SafeValue::Integer(3000));
+        assert_eq!(sample.fraction, 0.01);
+        assert_eq!(sheet.columns[0].dtype, DType::Integer);
+        assert!(sheet.warnings[0].starts_with("Only a sample of 30 of 3000 rows"));
+
+        let options = ProcessingOptions {
+            bucket_counts: false,
+            sample_rows: Some(500),
+            ..ProcessingOptions::default()
+        };
+        let mut stream = VecStream::from_rows(&["score"], rows.clone());
+        let (sheet, _) = profile_stream(&mut stream, "t", 0, &options, &mut NullAuditSink).unwrap();
+        assert_eq!(sheet.row_count, SafeValue::Integer(500));
+        let sample = sheet.sample.as_ref().unwrap();
+        assert_eq!(sample.table_rows, SafeValue::Integer(3000));
+        assert_eq!(sample.fraction, 500.0 / 3000.0);
+
+        // The reservoir is a random draw, kept in table order and the same on every scan
+        let ids: Vec<Vec<String>> = (0..3000).map(|i| vec![i.to_string()]).collect();
+        let mut stream = VecStream::from_rows(&["id"], ids);
+        let drawn = draw_sample(&mut stream, &options).unwrap().unwrap();
+        assert_eq!((drawn.rows.len(), drawn.table_rows), (500, 3000));
+        stream.rewind().unwrap();
+        assert_eq!(draw_sample(&mut stream, &options).unwrap().unwrap().rows, drawn.rows);
+        let positions: Vec<u32> = drawn.rows.iter().map(|row| row[0].parse().unwrap()).collect();
+        assert!(positions.windows(2).all(|w| w[0] < w[1]));
+        assert!(positions.iter().any(|&p| p >= 2500));
+
+        // A table within the sample size is profiled whole
+        let options = ProcessingOptions {
+            sample_rows: Some(5000),
+            ..options
+        };
+        let mut stream = VecStream::from_rows(&["score"], rows);
+        let (sheet, _) = profile_stream(&mut stream, "t", 0, &options, &mut NullAuditSink).unwrap();
+        assert!(sheet.sample.is_none() && sheet.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_profile_stream_date_range() {
+        let mut stream = VecStream::new(
+            &["visit_date", "collected_at"],
+            &[
+                &["2024-03-15", "2023-12-31 08:00:00"],
+                &["2024-01-02", "2024-01-01T09:30:00"],
+                &["2024-06-30", ""],
+            ],
+        );
+
+        let (sheet, _) = profile_stream(
+            &mut stream,
+            "t",
+            0,
+            &ProcessingOptions::default(),
+            &mut NullAuditSink,
+        )
+        .unwrap();
+        let visits = sheet.columns[0].stats.as_ref().unwrap();
+        assert_eq!(sheet.columns[0].dtype, DType::Date);
+        assert_eq!(
+            visits.earliest,
+            Some(SafeValue::ShortString("2024-01".to_string()))
+        );
+        assert_eq!(
+            visits.latest,
+            Some(SafeValue::ShortString("2024-06".to_string()))
+        );
+        assert_eq!(visits.span_days, None);
+
+        stream.rewind().unwrap();
+        let options = ProcessingOptions {
+            date_precision: DatePrecision::Day,
+            ..ProcessingOptions::default()
+        };
+        let (sheet, _) = profile_stream(&mut stream, "t", 0, &options, &mut NullAuditSink).unwrap();
+        let visits = sheet.columns[0].stats.as_ref().unwrap();
+        assert_eq!(
+            visits.earliest,
+            Some(SafeValue::ShortString("2024-01-02".to_string()))
+        );
+        assert_eq!(visits.span_days, Some(180));
+
+        let collected = sheet.columns[1].stats.as_ref().unwrap();
+        assert_eq!(sheet.columns[1].dtype, DType::Datetime);
+        assert_eq!(collected.span_days, Some(1));
+    }
+
+    #[test]
+    fn test_span_days_follows_date_precision() {
+        for (precision, span) in [
+            (DatePrecision::Day, Some(364)),
+            (DatePrecision::Month, None),
+            (DatePrecision::Year, None),
+        ] {
+            let mut stream = VecStream::new(&["visit_date"], &[&["2023-01-01"], &["2023-12-31"]]);
+            let options = ProcessingOptions {
+                date_precision: precision,
+                ..ProcessingOptions::default()
+            };
+            let (sheet, _) =
+                profile_stream(&mut stream, "t", 0, &options, &mut NullAuditSink).unwrap();
+            assert_eq!(sheet.columns[0].stats.as_ref().unwrap().span_days, span);
+        }
+    }
+
+    #[test]
+    fn test_profile_stream_ragged_rows() {
+        let mut stream = VecStream::new(&["a", "b"], &[&["1"], &["2", "x", "extra"]]);
+        let options = ProcessingOptions::default();
+
+        let (sheet, _) = profile_stream(&mut stream, "t", 0, &options, &mut NullAuditSink).unwrap();
+        assert_eq!(sheet.columns.len(), 2);
+    }
+
+    #[test]
+    fn test_value_clusters() {
+        let mut rows = Vec::new();
+        for (city, n) in [("Montréal", 6), ("Montreal", 5), ("MONTREAL ", 1), ("Laval", 7)] {
+            rows.extend((0..n).map(|_| vec![city.to_string()]));
+        }
+        let mut stream = VecStream::from_rows(&["region"], rows);
+        let options = ProcessingOptions {
+            bucket_counts: false,
+            ..ProcessingOptions::default()
+        };
+
+        let (sheet, _) = profile_stream(&mut stream, "t", 0, &options, &mut NullAuditSink).unwrap();
+        let column = &sheet.columns[0];
+        let clusters = column.stats.as_ref().unwrap().value_clusters.as_ref().unwrap();
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].variants, SafeValue::Integer(3));
+        assert_eq!(clusters[0].count, SafeValue::Integer(12));
+        // The below-k spelling is counted but not named
+        assert_eq!(
+            clusters[0].exemplars,
+            vec![
+                SafeValue::ShortString("Montréal".to_string()),
+                SafeValue::ShortString("Montreal".to_string())
+            ]
+        );
+        assert!(column.warnings.iter().any(|w| w.contains("near-duplicate")));
+    }
+
+    #[test]
+    fn test_case_only_clusters_warn_once() {
+        let rows = (0..20).map(|i| vec![if i % 2 == 0 { "M" } else { "m" }.to_string()]);
+        let mut stream = VecStream::from_rows(&["sex"], rows.collect());
+        let options = ProcessingOptions::default();
+
+        let (sheet, _) = profile_stream(&mut stream, "t", 0, &options, &mut NullAuditSink).unwrap();
+        let column = &sheet.columns[0];
+        let stats = column.stats.as_ref().unwrap();
+        assert!(stats.value_clusters.is_none());
+        assert_eq!(stats.case_variants.as_ref().unwrap().len(), 1);
+        assert_eq!(column.warnings.len(), 1);
+        assert!(column.warnings[0].contains("letter cases"));
+    }
+
+    #[test]
+    fn test_clinical_code_columns() {
+        let rows: Vec<Vec<String>> = (0..24)
+            .map(|i| {
+                // 9-digit concept IDs have the shape of an SSN
+                let concept = ["195967001", "254637007", "399211009", "22298006"][i % 4];
+                let diagnosis = ["J18.9", "E11.65", "I10"][i % 3];
+                vec![concept.to_string(), diagnosis.to_string()]
+            })
+            .collect();
+        let mut stream = VecStream::from_rows(&["condition", "diagnosis"], rows);
+        let options = ProcessingOptions::default();
+        let (sheet, _) = profile_stream(&mut stream, "t", 0, &options, &mut NullAuditSink).unwrap();
+
+        let concepts = &sheet.columns[0];
+        assert_eq!(concepts.code_system, Some(CodeSystem::SnomedCt));
+        assert_eq!(concepts.unique_values.as_ref().unwrap().len(), 4);
+        let diagnoses = &sheet.columns[1];
+        assert_eq!(diagnoses.code_system, Some(CodeSystem::Icd10));
+        assert_eq!(diagnoses.unique_values.as_ref().unwrap().len(), 3);
+
+        // Without the code system the SSN-shaped IDs would be withheld
+        assert!(check_value_pattern_in("195967001", &[]).is_phi);
+    }
+
+    #[test]
+    fn test_case_variants() {
+        let mut rows = Vec::new();
+        for (sex, n) in [("male", 9), ("Male", 6), ("MALE", 2), ("female", 12)] {
+            rows.extend((0..n).map(|_| vec![sex.to_string()]));
+        }
+        let mut stream = VecStream::from_rows(&["sex"], rows);
+        let options = ProcessingOptions {
+            bucket_counts: false,
+            ..ProcessingOptions::default()
+        };
+
+        let (sheet, _) = profile_stream(&mut stream, "t", 0, &options, &mut NullAuditSink).unwrap();
+        let column = &sheet.columns[0];
+        let groups = column.stats.as_ref().unwrap().case_variants.as_ref().unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(
+            groups[0].variants,
+            BTreeMap::from([
+                ("Male".to_string(), SafeValue::Integer(6)),
+                ("male".to_string(), SafeValue::Integer(9)),
+            ])
+        );
+        // The below-k variant is counted but not named
+        assert_eq!(groups[0].withheld, Some(SafeValue::Integer(1)));
+        assert!(column.warnings.iter().any(|w| w.contains("several letter cases")));
+    }
+
+    #[test]
+    fn test_quasi_identifier_combinations() {
+        // 16 rows share one age and sex; 4 are alone in their group
+        let mut rows: Vec<Vec<String>> = (0..16)
+            .map(|i| vec!["40".to_string(), "M".to_string(), format!("{}.5", i)])
+            .collect();
+        for age in ["71", "72", "73", "74"] {
+            rows.push(vec![age.to_string(), "F".to_string(), "6.1".to_string()]);
+        }
+        let mut stream = VecStream::from_rows(&["Age", "Sex", "hba1c"], rows);
+        let options = ProcessingOptions {
+            bucket_counts: false,
+            quasi_identifier_sets: vec![
+                vec!["sex".to_string(), "age".to_string()],
+                vec!["sex".to_string(), "zip3".to_string()],
+            ],
+            suggest_quasi_identifiers: true,
+            ..ProcessingOptions::default()
+        };
+
+        let (sheet, _) = profile_stream(&mut stream, "t", 0, &options, &mut NullAuditSink).unwrap();
+        // The set naming a missing column is skipped, and the suggestion is the given set
+        assert_eq!(sheet.quasi_identifier_checks.len(), 1);
+        let check = &sheet.quasi_identifier_checks[0];
+        assert_eq!(check.columns, vec!["Age", "Sex"]);
+        assert!(!check.suggested);
+        assert_eq!(check.set, Some(0));
+        assert_eq!(check.classes, SafeValue::Integer(5));
+        assert_eq!(check.min_class_size, 1);
+        assert_eq!(check.percent_below_k, 20.0);
+        assert!(sheet.warnings[0].starts_with("Quasi-identifiers Age, Sex: 20% of rows"));
+
+        let mut stream = VecStream::from_rows(&["Age", "Sex", "hba1c"], Vec::new());
+        let options = ProcessingOptions {
+            suggest_quasi_identifiers: true,
+            ..ProcessingOptions::default()
+        };
+        let (sheet, _) = profile_stream(&mut stream, "t", 0, &options, &mut NullAuditSink).unwrap();
+        assert!(sheet.quasi_identifier_checks.is_empty());
+    }
+
+    #[test]
+    fn test_sensitive_column_diversity() {
+        // Everyone in the 40/M group tested positive; the 55/F group is mixed
+        let mut rows: Vec<Vec<String>> = (0..6)
+            .map(|_| vec!["40".to_string(), "M".to_string(), "positive".to_string()])
+            .collect();
+        for status in ["positive", "negative", "negative", "negative"] {
+            rows.push(vec!["55".to_string(), "F".to_string(), status.to_string()]);
+        }
+        let mut stream = VecStream::from_rows(&["age", "sex", "HIV_status"], rows);
+        let options = ProcessingOptions {
+            bucket_counts: false,
+            k_anonymity: 3,
+            quasi_identifier_sets: vec![vec!["age".to_string(), "sex".to_string()]],
+            sensitive_columns: vec!["hiv_status".to_string(), "sex".to_string()],
+            ..ProcessingOptions::default()
+        };
+
+        let (sheet, _) = profile_stream(&mut stream, "t", 0, &options, &mut NullAuditSink).unwrap();
+        let check = &sheet.quasi_identifier_checks[0];
+        // A sensitive column that is itself a quasi-identifier is not measured
+        assert_eq!(check.l_diversity.len(), 1);
+        let diversity = &check.l_diversity[0];
+        assert_eq!(diversity.column, "HIV_status");
+        assert_eq!(diversity.groups_below_l, SafeValue::Integer(1));
+        assert_eq!(diversity.percent_below_l, 60.0);
+        assert_eq!(diversity.sensitive, 0);
+        assert_eq!(sheet.warnings.len(), 1);
+        assert!(sheet.warnings[0].contains("fewer than l=2 distinct values of sensitive column"));
+    }
+
+    #[test]
+    fn test_ramq_numbers_leak_birth_date() {
+        let rows: Vec<Vec<String>> = (0..8)
+            .map(|i| vec![format!("TREM 65{:02} 3112", i + 1), "stable".to_string()])
+            .collect();
+        let options = ProcessingOptions {
+            deep_scan: true,
+            ..ProcessingOptions::default()
+        };
+        let mut stream = VecStream::from_rows(&["ref_code", "status"], rows);
+        let (sheet, _) = profile_stream(&mut stream, "t", 0, &options, &mut NullAuditSink).unwrap();
+        let column = &sheet.columns[0];
+        let hits = column.stats.as_ref().unwrap().pattern_hits.as_ref().unwrap();
+        assert!(hits.contains_key("ramq"));
+        assert_eq!(column.classification, Classification::Warning);
+        assert!(column.warnings.iter().any(|w| w.contains("reveal date of birth")));
+        assert!(sheet.columns[1].warnings.is_empty());
+    }
+
+    #[test]
+    fn test_deep_scan() {
+        let rows: Vec<Vec<String>> = (0..6)
+            .map(|i| {
+                let note = match i {
+                    0 => "called pt at 555-123-4567".to_string(),
+                    1 => "jdoe@example.com".to_string(),
+                    _ => "stable".to_string(),
+                };
+                vec![note, format!("{}", i)]
+            })
+            .collect();
+        let options = ProcessingOptions {
+            bucket_counts: false,
+            deep_scan: true,
+            ..ProcessingOptions::default()
+        };
+
+        let mut stream = VecStream::from_rows(&["comments_coded", "score"], rows.clone());
+        let (sheet, _) = profile_stream(&mut stream, "t", 0, &options, &mut NullAuditSink).unwrap();
+        let comments = &sheet.columns[0];
+        let hits = comments.stats.as_ref().unwrap().pattern_hits.as_ref().unwrap();
+        assert_eq!(hits["phone"], SafeValue::Integer(1));
+        assert_eq!(hits["email"], SafeValue::Integer(1));
+        assert!(comments.warnings.iter().any(|w| w.starts_with("Deep scan: 2-5 cell(s)")));
+        assert!(sheet.columns[1].stats.as_ref().unwrap().pattern_hits.is_none());
+
+        let mut stream = VecStream::from_rows(&["comments_coded", "score"], rows);
+        let (sheet, _) = profile_stream(
+            &mut stream,
+            "t",
+            0,
+            &ProcessingOptions::default(),
+            &mut NullAuditSink,
+        )
+        .unwrap();
+        assert!(sheet.columns[0].stats.as_ref().unwrap().pattern_hits.is_none());
+    }
+
+    #[test]
+    fn test_content_aware_downgrade() {
+        let rows: Vec<Vec<String>> = (0..30)
+            .map(|i| {
+                let arm = ["Treatment A", "Treatment B", "Treatment C"][i % 3];
+                vec![arm.to_string(), i.to_string()]
+            })
+            .collect();
+
+        let mut stream = VecStream::from_rows(&["name", "score"], rows.clone());
+        let options = ProcessingOptions::default();
+        let (sheet, _) = profile_stream(&mut stream, "t", 0, &options, &mut NullAuditSink).unwrap();
+        assert_eq!(sheet.columns[0].classification, Classification::Phi);
+
+        let mut stream = VecStream::from_rows(&["name", "score"], rows);
+        let options = ProcessingOptions {
+            content_aware: true,
+            ..ProcessingOptions::default()
+        };
+        let (sheet, _) = profile_stream(&mut stream, "t", 0, &options, &mut NullAuditSink).unwrap();
+        let column = &sheet.columns[0];
+        assert_eq!(column.classification, Classification::Warning);
+        assert_eq!(column.name, SafeValue::ShortString("name".to_string()));
+        assert!(column.warnings[0].contains("downgraded to warning"));
+        let values = column.stats.as_ref().unwrap().value_frequencies.as_ref().unwrap();
+        assert!(values.contains_key("Treatment A"));
+    }
+
+    #[test]
+    fn test_whitespace_anomalies() {
+        let rows: Vec<Vec<String>> = (0..20)
+            .map(|i| {
+                let code = match i {
+                    0..=2 => "A12 ",
+                    3 => "A\u{00A0}12",
+                    4 => "\u{200B}A12",
+                    _ => "A12",
+                };
+                vec![code.to_string()]
+            })
+            .collect();
+        let mut stream = VecStream::from_rows(&["lab_code"], rows);
+        let options = ProcessingOptions {
+            bucket_counts: false,
+            ..ProcessingOptions::default()
+        };
+        let (sheet, _) = profile_stream(&mut stream, "t", 0, &options, &mut NullAuditSink).unwrap();
+        let column = &sheet.columns[0];
+        let anomalies = column.stats.as_ref().unwrap().whitespace_anomalies.as_ref().unwrap();
+        assert_eq!(anomalies["leading_trailing_whitespace"], SafeValue::Integer(3));
+        assert_eq!(anomalies["non_breaking_space"], SafeValue::Integer(1));
+        assert_eq!(anomalies["zero_width"], SafeValue::Integer(1));
+        assert!(column.warnings.iter().any(|w| w.ends_with(
+            "2-5 with leading or trailing whitespace, 1 with non-breaking spaces, \
+             1 with zero-width characters"
+        )));
+    }
+
+    #[test]
+    fn test_truncated_export_warning() {
+        let rows: Vec<Vec<String>> = (0..60)
+            .map(|i| {
+                let comment = if i % 4 == 0 {
+                    "c".repeat(255)
+                } else {
+                    "c".repeat(30 + i % 50)
+                };
+                vec![comment]
+            })
+            .collect();
+        let mut stream = VecStream::from_rows(&["comment"], rows);
+        let options = ProcessingOptions::default();
+        let (sheet, _) = profile_stream(&mut stream, "t", 0, &options, &mut NullAuditSink).unwrap();
+        let warning = "11-20 value(s) are exactly 255 characters long";
+        assert!(sheet.columns[0].warnings.iter().any(|w| w.starts_with(warning)));
+
+        let rows = (0..60).map(|i| vec!["c".repeat(30 + i % 50)]).collect();
+        let mut stream = VecStream::from_rows(&["comment"], rows);
+        let (sheet, _) = profile_stream(&mut stream, "t", 0, &options, &mut NullAuditSink).unwrap();
+        assert!(!sheet.columns[0].warnings.iter().any(|w| w.contains("truncated")));
+    }
+
+    #[test]
+    fn test_free_text_summary() {
+        // Free text is told apart from strings only after the inference sample
+        let rows: Vec<Vec<String>> = (0..2100)
+            .map(|i| {
+                let note = match i % 4 {
+                    0 => "Le patient est revenu avec des douleurs\ndans la jambe".to_string(),
+                    1 => "Patient was seen in the clinic\nand had no complaints".to_string(),
+                    2 => "Follow up by email with j.doe@example.com\nafter the visit".to_string(),
+                    _ => "Stable\nunchanged".to_string(),
+                };
+                vec![note, i.to_string()]
+            })
+            .collect();
+        let options = ProcessingOptions {
+            bucket_counts: false,
+            ..ProcessingOptions::default()
+        };
+        let mut stream = VecStream::from_rows(&["notes", "score"], rows);
+        let (sheet, _) = profile_stream(&mut stream, "t", 0, &options, &mut NullAuditSink).unwrap();
+        let notes = &sheet.columns[0];
+        assert_eq!(notes.dtype, DType::FreeText);
+        let summary = notes.stats.as_ref().unwrap().text_summary.as_ref().unwrap();
+        assert_eq!(summary.languages["fr"], SafeValue::Integer(525));
+        assert_eq!(summary.languages["en"], SafeValue::Integer(1050));
+        assert_eq!(summary.languages["unknown"], SafeValue::Integer(525));
+        assert_eq!(summary.words, SafeValue::Integer(16275));
+        // 525 email addresses in 16275 words
+        assert_eq!(summary.phi_density, 32.3);
+        assert!(notes.warnings.iter().any(|w| w.contains("32.3 PHI pattern matches")));
+        assert!(sheet.columns[1].stats.as_ref().unwrap().text_summary.is_none());
+    }
+
+    #[test]
+    fn test_stata_extended_missing_codes() {
+        let mut stream = VecStream::new(
+            &["sbp"],
+            &[&["120"], &[".a"], &["131"], &[".b"], &[".a"], &["."], &["118"]],
+        );
+        let options = ProcessingOptions {
+            bucket_counts: false,
+            ..ProcessingOptions::default()
+        };
+
+        let (sheet, _) = profile_stream(&mut stream, "t", 0, &options, &mut NullAuditSink).unwrap();
+        let column = &sheet.columns[0];
+        assert_eq!(column.dtype, DType::Integer);
+        let stats = column.stats.as_ref().unwrap();
+        assert_eq!(stats.missing_count, Some(SafeValue::Integer(4)));
+        let codes = stats.missing_codes.as_ref().unwrap();
+        assert_eq!(codes.len(), 2);
+        assert_eq!(codes[".a"], SafeValue::Integer(2));
+        assert_eq!(codes[".b"], SafeValue::Integer(1));
+    }
+
+    #[test]
+    fn test_high_cardinality_approximate_unique_count() {
+        let rows: Vec<Vec<String>> = (0..3000).map(|i| vec![format!("code-{}", i)]).collect();
+        let mut stream = VecStream::from_rows(&["dx_code"], rows);
+
+        let (sheet, _) = profile_stream(
+            &mut stream,
+            "t",
+            0,
+            &ProcessingOptions::default(),
+            &mut NullAuditSink,
+        )
+        .unwrap();
+        let col = &sheet.columns[0];
+        let stats = col.stats.as_ref().unwrap();
+
+        assert_eq!(col.classification, Classification::HighCardinality);
+        assert_eq!(
+            stats.unique_count,
+            Some(SafeValue::ShortString("1001-10000".to_string()))
+        );
+        assert!(stats.unique_count_approximate);
+        // All values are distinct, so none reach k
+        assert!(stats.top_values.is_none());
+    }
+
+    #[test]
+    fn test_high_cardinality_top_values() {
+        let rows: Vec<Vec<String>> = (0..3000)
+            .map(|i| {
+                if i % 4 == 0 {
+                    vec!["I10".to_string()]
+                } else {
+                    vec![format!("Z{:05}", i)]
+                }
+            })
+            .collect();
+        let mut stream = VecStream::from_rows(&["dx_code"], rows);
+
+        let (sheet, _) = profile_stream(
+            &mut stream,
+            "t",
+            0,
+            &ProcessingOptions::default(),
+            &mut NullAuditSink,
+        )
+        .unwrap();
+        let top = sheet.columns[0]
+            .stats
+            .as_ref()
+            .unwrap()
+            .top_values
+            .clone()
+            .unwrap();
+
+        assert_eq!(top.len(), 1);
+        assert_eq!(top[0].value, SafeValue::ShortString("I10".to_string()));
+    }
+
+    #[test]
+    fn test_cdisc_variables() {
+        let rows: Vec<Vec<String>> = (0..6)
+            .map(|i| {
+                vec![
+                    format!("STUDY-{:03}", i),
+                    format!("{}", 100 + i % 2),
+                    "1960-05-01".to_string(),
+                    format!("{}", i + 1),
+                ]
+            })
+            .collect();
+        let headers = ["USUBJID", "SITEID", "BRTHDTC", "DMSEQ"];
+        let options = ProcessingOptions {
+            cdisc: true,
+            ..ProcessingOptions::default()
+        };
+
+        let mut stream = VecStream::from_rows(&headers, rows.clone());
+        let (sheet, registry) =
+            profile_stream(&mut stream, "dm.xpt", 0, &options, &mut NullAuditSink).unwrap();
+        assert_eq!(sheet.cdisc_domain.as_deref(), Some("DM"));
+        let classes: Vec<_> = sheet.columns.iter().map(|c| c.classification.clone()).collect();
+        assert_eq!(
+            classes,
+            vec![
+                Classification::Recode,
+                Classification::Recode,
+                Classification::Phi,
+                Classification::Safe
+            ]
+        );
+        assert_eq!(sheet.columns[0].cdisc_variable.as_deref(), Some("USUBJID"));
+        assert_eq!(sheet.columns[3].cdisc_variable.as_deref(), Some("--SEQ"));
+        let pseudonyms = registry.get_recoded_values(0).unwrap();
+        assert!(pseudonyms.iter().all(|v| v.starts_with("Subject")));
+
+        // Without --cdisc the subject identifier is not recognized
+        let mut stream = VecStream::from_rows(&headers, rows);
+        let (sheet, _) = profile_stream(
+            &mut stream,
+            "dm.xpt",
+            0,
+            &ProcessingOptions::default(),
+            &mut NullAuditSink,
+        )
+        .unwrap();
+        assert_ne!(sheet.columns[0].classification, Classification::Recode);
+        assert_eq!(sheet.columns[0].cdisc_variable, None);
+        assert_eq!(sheet.cdisc_domain, None);
+    }
+
+    #[test]
+    fn test_code_list_check() {
+        let mut rows: Vec<Vec<String>> = Vec::new();
+        for (sex, n) in [("M", 8), ("F", 7), ("Male", 5), ("X", 1)] {
+            rows.extend((0..n).map(|_| vec![sex.to_string()]));
+        }
+        let options = ProcessingOptions {
+            bucket_counts: false,
+            code_lists: vec![CodeListRule {
+                column: "sex".to_string(),
+                sheet: None,
+                allowed: vec!["M".to_string(), "F".to_string(), "U".to_string()],
+            }],
+            ..ProcessingOptions::default()
+        };
+
+        let mut stream = VecStream::from_rows(&["Sex"], rows);
+        let (sheet, _) = profile_stream(&mut stream, "t", 0, &options, &mut NullAuditSink).unwrap();
+        let column = &sheet.columns[0];
+        let check = column.code_list.as_ref().unwrap();
+        assert_eq!(check.checked, SafeValue::Integer(21));
+        assert_eq!(check.out_of_list, SafeValue::Integer(6));
+        assert!(!check.passed);
+        // "X" is below k, so it is counted but not named
+        assert_eq!(
+            check.values.keys().collect::<Vec<_>>(),
+            vec![&"Male".to_string()]
+        );
+        assert!(column.warnings.iter().any(|w| w.contains("code list")));
+    }
+
+    #[test]
+    fn test_compact_date_column() {
+        let rows: Vec<Vec<String>> = (1..=9)
+            .map(|day| vec![format!("202401{:02}", day)])
+            .collect();
+        let mut stream = VecStream::from_rows(&["visit_dt"], rows);
+        let (sheet, _) = profile_stream(
+            &mut stream,
+            "t",
+            0,
+            &ProcessingOptions::default(),
+            &mut NullAuditSink,
+        )
+        .unwrap();
+        let column = &sheet.columns[0];
+        assert_eq!(column.dtype, DType::Date);
+        let stats = column.stats.as_ref().unwrap();
+        assert_eq!(stats.mean, None);
+        assert_eq!(stats.earliest, Some(SafeValue::ShortString("2024-01".to_string())));
+        assert_eq!(stats.span_days, None);
+        assert!(column.warnings.iter().any(|w| w.contains("yyyymmdd")));
+    }
+
+    #[test]
+    fn test_correlations() {
+        let rows: Vec<Vec<String>> = (0..20)
+            .map(|i| {
+                let weight = 50.0 + i as f64 * 2.5;
+                vec![
+                    format!("{}", weight),
+                    format!("{:.2}", weight * 2.20462),
+                    format!("{}", (i * 7) % 5),
+                    format!("{}", 60 + i),
+                ]
+            })
+            .collect();
+        let headers = ["weight_kg", "weight_lb", "score", "dob_year"];
+
+        let mut stream = VecStream::from_rows(&headers, rows.clone());
+        let options = ProcessingOptions {
+            relaxed: true,
+            ..ProcessingOptions::default()
+        };
+        let (sheet, _) = profile_stream(&mut stream, "t", 0, &options, &mut NullAuditSink).unwrap();
+        // dob_year is just as correlated but its name is withheld
+        assert_eq!(sheet.correlations.len(), 1);
+        let pair = &sheet.correlations[0];
+        assert_eq!(pair.columns, ["weight_kg".to_string(), "weight_lb".to_string()]);
+        assert_eq!(pair.r, 1.0);
+        assert_eq!(pair.count, SafeValue::ShortString("11-20".to_string()));
+
+        let mut stream = VecStream::from_rows(&headers, rows);
+        let (sheet, _) = profile_stream(
+            &mut stream,
+            "t",
+            0,
+            &ProcessingOptions::default(),
+            &mut NullAuditSink,
+        )
+        .unwrap();
+        assert!(sheet.correlations.is_empty());
+    }
+
+    #[test]
+    fn test_outliers() {
+        let mut rows: Vec<Vec<String>> =
+            (0..30).map(|i| vec![format!("{}", 40 + i % 25)]).collect();
+        rows.push(vec!["999".to_string()]);
+        rows.push(vec!["NA".to_string()]);
+        let mut stream = VecStream::from_rows(&["age"], rows);
+        let options = ProcessingOptions {
+            bucket_counts: false,
+            ..ProcessingOptions::default()
+        };
+        let (sheet, _) = profile_stream(&mut stream, "t", 0, &options, &mut NullAuditSink).unwrap();
+        let stats = sheet.columns[0].stats.as_ref().unwrap();
+        assert_eq!(stats.outliers_sd, Some(SafeValue::Integer(1)));
+        assert_eq!(stats.outliers_iqr, Some(SafeValue::Integer(1)));
+    }
+
+    #[test]
+    fn test_phi_numbers_keep_no_range() {
+        let rows: Vec<Vec<String>> =
+            (0..30).map(|i| vec![format!("{}", 4_000_000 + i * 37)]).collect();
+        let mut stream = VecStream::from_rows(&["mrn"], rows);
+        let options = ProcessingOptions {
+            bucket_counts: false,
+            ..ProcessingOptions::default()
+        };
+        let (sheet, _) = profile_stream(&mut stream, "t", 0, &options, &mut NullAuditSink).unwrap();
+        assert_eq!(sheet.columns[0].classification, Classification::Phi);
+        let stats = sheet.columns[0].stats.as_ref().unwrap();
+        assert_eq!((stats.min.as_ref(), stats.max.as_ref()), (None, None));
+        assert_eq!((stats.q1, stats.median, stats.q3), (None, None, None));
+        assert_eq!(stats.outliers_iqr, Some(SafeValue::Integer(0)));
+    }
+
+    #[test]
+    fn test_non_finite_values() {
+        let mut rows: Vec<Vec<String>> = (0..20).map(|i| vec![format!("{}.5", i)]).collect();
+        for value in ["inf", "-inf", "1e999", "Infinity"] {
+            rows.push(vec![value.to_string()]);
+        }
+        let mut stream = VecStream::from_rows(&["dose"], rows);
+        let options = ProcessingOptions {
+            bucket_counts: false,
+            ..ProcessingOptions::default()
+        };
+        let (sheet, _) = profile_stream(&mut stream, "t", 0, &options, &mut NullAuditSink).unwrap();
+        let column = &sheet.columns[0];
+        assert_eq!(column.dtype, DType::Numeric);
+        let stats = column.stats.as_ref().unwrap();
+        assert_eq!(stats.non_finite_count, Some(SafeValue::Integer(4)));
+        assert_eq!(stats.count, Some(SafeValue::Integer(24)));
+        assert_eq!(stats.max, Some(SafeValue::Float(19.5)));
+        assert_eq!(stats.mean, Some(10.0));
+        assert!(stats.median.is_some_and(|m| (0.5..=19.5).contains(&m)));
+        assert!(column.warnings.iter().any(|w| w.contains("not finite")));
+        let json = serde_json::to_string(&sheet).unwrap();
+        assert!(!json.contains("null"));
+    }
+
+    #[test]
+    fn test_subject_column_sketch() {
+        let rows: Vec<Vec<String>> = (0..30)
+            .map(|i| vec![format!("S{:03}", i % 10), "A".to_string()])
+            .collect();
+        let mut stream = VecStream::from_rows(&["Subject ID", "arm"], rows);
+        let options = ProcessingOptions {
+            subject_column: Some("subject id".to_string()),
+            ..ProcessingOptions::default()
+        };
+        let (sheet, _) = profile_stream(&mut stream, "t", 0, &options, &mut NullAuditSink).unwrap();
+        let subjects = |i: usize| {
+            let fingerprint = sheet.columns[i].fingerprint.as_ref().unwrap();
+            fingerprint.subject_sketch.as_ref().map(|s| s.estimate().round())
+        };
+        assert_eq!(subjects(0), Some(10.0));
+        assert_eq!(subjects(1), None);
+    }
+
+    #[test]
+    fn test_custom_missing_tokens() {
+        let rows: Vec<Vec<String>> = ["120", "-99", "135", "NA", "UNK"]
+            .iter()
+            .map(|v| vec![v.to_string(), v.to_string()])
+            .collect();
+        let options = ProcessingOptions {
+            bucket_counts: false,
+            missing_tokens: vec!["-99".to_string(), "UNK".to_string()],
+            missing_token_rules: vec![MissingTokenRule {
+                column: "DBP".to_string(),
+                sheet: None,
+                tokens: vec!["UNK".to_string()],
+            }],
+            ..ProcessingOptions::default()
+        };
+        let mut stream = VecStream::from_rows(&["sbp", "dbp"], rows);
+        let (sheet, _) = profile_stream(&mut stream, "t", 0, &options, &mut NullAuditSink).unwrap();
+
+        let sbp = sheet.columns[0].stats.as_ref().unwrap();
+        assert_eq!(sheet.columns[0].dtype, DType::Integer);
+        assert_eq!(sbp.missing_count, Some(SafeValue::Integer(3)));
+        assert_eq!(sbp.min, Some(SafeValue::Float(120.0)));
+
+        // The column's own list replaces both the scan-wide and built-in tokens
+        let dbp = sheet.columns[1].stats.as_ref().unwrap();
+        assert_eq!(sheet.columns[1].dtype, DType::String);
+        assert_eq!(dbp.missing_count, Some(SafeValue::Integer(1)));
+    }
+
+    #[test]
+    fn test_locale_value_patterns() {
+        let rows: Vec<Vec<String>> = (0..12)
+            .map(|i| vec![if i % 2 == 0 { "(11) 98765-4321" } else { "sim" }.to_string()])
+            .collect();
+        let listed = |locales: Vec<Language>| {
+            let options = ProcessingOptions {
+                locales,
+                ..ProcessingOptions::default()
+            };
+            let mut stream = VecStream::from_rows(&["obs"], rows.clone());
+            let (sheet, _) =
+                profile_stream(&mut stream, "t", 0, &options, &mut NullAuditSink).unwrap();
+            let values = sheet.columns[0].unique_values.clone().unwrap_or_default();
+            values.iter().any(|v| v.to_string() == "(11) 98765-4321")
+        };
+        assert!(listed(Vec::new()));
+        assert!(!listed(vec![Language::Pt]));
+    }
+
+    #[test]
+    fn test_decimal_comma_column() {
+        let rows = ["1,5", "2,5", "1.000,5", "NA"].iter().map(|v| vec![v.to_string()]).collect();
+        let mut stream = VecStream::from_rows(&["dose_mg"], rows);
+        let (sheet, _) = profile_stream(
+            &mut stream,
+            "t",
+            0,
+            &ProcessingOptions::default(),
+            &mut NullAuditSink,
+        )
+        .unwrap();
+        let column = &sheet.columns[0];
+        assert_eq!(column.dtype, DType::Numeric);
+        assert_eq!(column.decimal_separator, Some(DecimalSeparator::Comma));
+        assert_eq!(column.format_hint.as_deref(), Some("decimal_comma"));
+        assert_eq!(column.unit_hint.as_deref(), Some("mg"));
+        let stats = column.stats.as_ref().unwrap();
+        assert_eq!(stats.max, Some(SafeValue::Float(1000.5)));
+    }
+
+    #[test]
+    fn test_suspected_missing_codes() {
+        let mut rows: Vec<Vec<String>> =
+            (0..40).map(|i| vec![format!("{}", 20 + i % 15)]).collect();
+        rows.extend(["-99", "-99", "-99", "-99", "999"].iter().map(|v| vec![v.to_string()]));
+        let mut stream = VecStream::from_rows(&["age"], rows.clone());
+        let (sheet, _) = profile_stream(
+            &mut stream,
+            "t",
+            0,
+            &ProcessingOptions::default(),
+            &mut NullAuditSink,
+        )
+        .unwrap();
+        let column = &sheet.columns[0];
+        let codes = column.stats.as_ref().unwrap().suspected_missing_codes.as_ref().unwrap();
+        assert_eq!(codes.keys().collect::<Vec<_>>(), vec!["-99"]);
+        assert_eq!(codes["-99"], SafeValue::ShortString("2-5".to_string()));
+        assert!(column.warnings.iter().any(|w| w.starts_with("Values -99 look like")));
+
+        // Declared as missing, the code no longer shows up
+        let options = ProcessingOptions {
+            missing_tokens: vec!["-99".to_string()],
+            ..ProcessingOptions::default()
+        };
+        let mut stream = VecStream::from_rows(&["age"], rows);
+        let (sheet, _) = profile_stream(&mut stream, "t", 0, &options, &mut NullAuditSink).unwrap();
+        assert!(sheet.columns[0].stats.as_ref().unwrap().suspected_missing_codes.is_none());
+    }
+
+    #[test]
+    fn test_examples() {
+        let mut rows: Vec<Vec<String>> = Vec::new();
+        let arms = [("placebo", 9), ("low dose", 6), ("high dose", 7), ("open", 5), ("x", 1)];
+        for (arm, n) in arms {
+            rows.extend((0..n).map(|_| vec![arm.to_string(), "jdoe@example.com".to_string()]));
+        }
+        let headers = ["arm", "contact_note"];
+        let options = ProcessingOptions {
+            examples: true,
+            ..ProcessingOptions::default()
+        };
+
+        let mut stream = VecStream::from_rows(&headers, rows.clone());
+        let (sheet, _) = profile_stream(&mut stream, "t", 0, &options, &mut NullAuditSink).unwrap();
+        let examples: Vec<String> =
+            sheet.columns[0].examples.iter().map(|v| v.to_string()).collect();
+        assert_eq!(examples, vec!["placebo", "high dose", "low dose"]);
+        assert!(sheet.columns[1].examples.is_empty());
+
+        let mut stream = VecStream::from_rows(&headers, rows);
+        let (sheet, _) = profile_stream(
+            &mut stream,
+            "t",
+            0,
+            &ProcessingOptions::default(),
+            &mut NullAuditSink,
+        )
+        .unwrap();
+        assert!(sheet.columns[0].examples.is_empty());
+    }
+
+    #[test]
+    fn test_numeric_id_detection() {
+        let rows: Vec<Vec<String>> = (0..30)
+            .map(|i| {
+                vec![
+                    format!("{}", 5_145_550_100_u64 + i * 37),
+                    format!("{:07}", 4200 + i),
+                    format!("{}", 1_000_000 + (i % 3) * 1_000_000),
+                    format!("{}", 10_i64.pow(6 + (i % 2) as u32) + i as i64),
+                ]
+            })
+            .collect();
+        let headers = ["line_a", "ref", "dose_ug", "count"];
+        let mut stream = VecStream::from_rows(&headers, rows);
+        let (sheet, _) = profile_stream(
+            &mut stream,
+            "t",
+            0,
+            &ProcessingOptions::default(),
+            &mut NullAuditSink,
+        )
+        .unwrap();
+        let is_id = |i: usize| {
+            let column = &sheet.columns[i];
+            column.classification == Classification::Warning
+                && column.warnings.iter().any(|w| w.contains("likely an identifier"))
+        };
+        // 10-digit phone-like numbers, and 7-digit numbers with leading zeros
+        assert!(is_id(0));
+        assert!(is_id(1));
+        // Few distinct values, or lengths that vary
+        assert!(!is_id(2));
+        assert!(!is_id(3));
+    }
+
+    #[test]
+    fn test_determine_recode_prefix() {
+        assert_eq!(determine_recode_prefix("hospital_name"), "Hospital");
+        assert_eq!(determine_recode_prefix("centre"), "Center");
+        assert_eq!(determine_recode_prefix("site_id"), "Site");
+    }
+}
@@ -0,0 +1,134 @@
+use serde::{Deserialize, Serialize};
+
+use crate::types::FileDetails;
+
+/// Bytes read from the start of a file when looking for banners
+pub const PROVENANCE_SAMPLE_BYTES: usize = 4096;
+
+/// System that probably generated a text export
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceSystem {
+    Redcap,
+    Spss,
+    Sas,
+    Excel,
+}
+
+/// REDCap system columns (the record ID column name is configurable, so it is not used)
+const REDCAP_COLUMNS: &[&str] = &[
+    "redcap_event_name",
+    "redcap_repeat_instrument",
+    "redcap_repeat_instance",
+    "redcap_data_access_group",
+    "redcap_survey_identifier",
+];
+
+/// Automatic variables SAS procedures add to output data sets
+const SAS_COLUMNS: &[&str] = &["_TYPE_", "_FREQ_", "_NAME_", "_LABEL_"];
+
+/// Guess which system exported a CSV/TSV from its first lines and headers
+pub fn sniff_source(leading_text: &str, headers: &[String]) -> Option<FileDetails> {
+    let found = |source, evidence: &str| {
+        Some(FileDetails {
+            likely_source: Some(source),
+            source_evidence: Some(evidence.to_string()),
+            delimiter: None,
+        })
+    };
+    let first_line = leading_text
+        .trim_start_matches('\u{feff}')
+        .lines()
+        .next()
+        .unwrap_or("")
+        .trim();
+    let headers: Vec<&str> = headers.iter().map(|h| h.trim()).collect();
+
+    if first_line.len() == 5 && first_line.to_ascii_lowercase().starts_with("sep=") {
+        return found(
+            SourceSystem::Excel,
+            "\"sep=\" delimiter hint on the first line",
+        );
+    }
+
+    if headers
+        .iter()
+        .any(|h| REDCAP_COLUMNS.contains(&h.to_ascii_lowercase().as_str()))
+    {
+        return found(SourceSystem::Redcap, "redcap_* system columns");
+    }
+    let completion_columns = headers.iter().filter(|h| h.ends_with("_complete")).count();
+    if headers.first() == Some(&"record_id") && completion_columns > 0 {
+        return found(
+            SourceSystem::Redcap,
+            "record_id first column and *_complete form status columns",
+        );
+    }
+
+    if leading_text
+        .lines()
+        .take(5)
+        .any(|l| l.trim().trim_matches('"') == "The SAS System")
+    {
+        return found(SourceSystem::Sas, "\"The SAS System\" title banner");
+    }
+    if headers.iter().any(|h| SAS_COLUMNS.contains(h)) {
+        return found(
+            SourceSystem::Sas,
+            "SAS automatic variables (_TYPE_, _FREQ_, ...)",
+        );
+    }
+
+    let default_names = !headers.is_empty()
+        && headers
+            .iter()
+            .enumerate()
+            .all(|(i, h)| *h == format!("V{}", i + 1));
+    if default_names && headers.len() > 1 {
+        return found(SourceSystem::Spss, "default variable names V1, V2, ...");
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sniff(text: &str, headers: &[&str]) -> Option<SourceSystem> {
+        let headers: Vec<String> = headers.iter().map(|h| h.to_string()).collect();
+        sniff_source(text, &headers).and_then(|d| d.likely_source)
+    }
+
+    #[test]
+    fn test_sniff_sources() {
+        assert_eq!(
+            sniff("sep=;\nid;age\n", &["id", "age"]),
+            Some(SourceSystem::Excel)
+        );
+        assert_eq!(
+            sniff("", &["study_id", "redcap_event_name", "age"]),
+            Some(SourceSystem::Redcap)
+        );
+        assert_eq!(
+            sniff("", &["record_id", "age", "demographics_complete"]),
+            Some(SourceSystem::Redcap)
+        );
+        assert_eq!(
+            sniff("\"The SAS System\"\n\nid,age\n", &["id", "age"]),
+            Some(SourceSystem::Sas)
+        );
+        assert_eq!(
+            sniff("", &["arm", "_TYPE_", "_FREQ_"]),
+            Some(SourceSystem::Sas)
+        );
+        assert_eq!(sniff("", &["V1", "V2", "V3"]), Some(SourceSystem::Spss));
+    }
+
+    #[test]
+    fn test_no_signature() {
+        assert_eq!(sniff("id,age\n1,30\n", &["id", "age"]), None);
+        assert_eq!(sniff("", &["V1", "V3"]), None);
+        assert_eq!(sniff("", &["record_id", "age"]), None);
+    }
+}
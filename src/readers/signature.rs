@@ -0,0 +1,70 @@
+//! Container signatures at the start of a file, for files whose extension names
+//! the wrong format: a workbook saved as `.csv`, a CSV renamed `.xlsx`, a
+//! compressed export.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Kind of container a file's first bytes mark it as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Container {
+    /// Zip archive, as xlsx and xlsb workbooks are
+    Zip,
+    /// OLE2 compound document, as xls workbooks are
+    Ole2,
+    Gzip,
+}
+
+impl Container {
+    /// Description for warnings
+    pub fn description(&self) -> &'static str {
+        match self {
+            Container::Zip => "a zip container (xlsx workbook)",
+            Container::Ole2 => "an OLE2 container (xls workbook)",
+            Container::Gzip => "gzip-compressed",
+        }
+    }
+}
+
+const ZIP_SIGNATURE: &[u8] = b"PK\x03\x04";
+const OLE2_SIGNATURE: &[u8] = &[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+const GZIP_SIGNATURE: &[u8] = &[0x1F, 0x8B];
+
+/// The container a file is, if its first bytes carry a known signature.
+/// Unreadable files have none; opening them fails later with a clearer error.
+pub fn detect_container(path: &Path) -> Option<Container> {
+    let mut head = Vec::with_capacity(OLE2_SIGNATURE.len());
+    File::open(path)
+        .ok()?
+        .take(OLE2_SIGNATURE.len() as u64)
+        .read_to_end(&mut head)
+        .ok()?;
+    container_of(&head)
+}
+
+fn container_of(head: &[u8]) -> Option<Container> {
+    [
+        (ZIP_SIGNATURE, Container::Zip),
+        (OLE2_SIGNATURE, Container::Ole2),
+        (GZIP_SIGNATURE, Container::Gzip),
+    ]
+    .into_iter()
+    .find(|(signature, _)| head.starts_with(signature))
+    .map(|(_, container)| container)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_container_of() {
+        assert_eq!(container_of(b"PK\x03\x04\x14\x00"), Some(Container::Zip));
+        assert_eq!(container_of(OLE2_SIGNATURE), Some(Container::Ole2));
+        assert_eq!(container_of(&[0x1F, 0x8B, 0x08, 0x00]), Some(Container::Gzip));
+        assert_eq!(container_of(b"id,arm\n1,A\n"), None);
+        assert_eq!(container_of(b"PK"), None);
+        assert_eq!(container_of(b""), None);
+    }
+}
@@ -0,0 +1,108 @@
+//! Recognizing delimited text by its content, for files whose extension names
+//! no format (`data.txt`, `export`).
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use super::encoding::{detect_encoding, SNIFF_BYTES};
+use crate::types::Result;
+
+/// Delimiters tried; ties go to the earlier one. Commas come last since they
+/// also turn up inside values (decimal commas, free text).
+const DELIMITERS: [u8; 4] = [b'\t', b';', b'|', b','];
+
+/// Lines compared to find the delimiter
+const SNIFF_LINES: usize = 20;
+
+/// Share of lines that must have the same number of delimiters; title lines
+/// above the header may differ
+const MIN_CONSISTENT_SHARE: f64 = 0.8;
+
+/// The delimiter of a file's content, if it looks like delimited text
+pub fn sniff_delimiter(path: &Path) -> Result<Option<u8>> {
+    let mut sample = Vec::with_capacity(SNIFF_BYTES);
+    File::open(path)?
+        .take(SNIFF_BYTES as u64)
+        .read_to_end(&mut sample)?;
+    let complete = sample.len() < SNIFF_BYTES;
+    Ok(delimiter_of(&sample, complete))
+}
+
+/// The delimiter that splits most lines of the sample into the same number of
+/// fields, at least two. Binary content (control characters other than tabs
+/// and line breaks) is not text.
+fn delimiter_of(sample: &[u8], complete: bool) -> Option<u8> {
+    let (text, _, _) = detect_encoding(sample, complete).decode(sample);
+    if text
+        .chars()
+        .any(|c| c.is_control() && !matches!(c, '\t' | '\n' | '\r'))
+    {
+        return None;
+    }
+    let mut lines: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).collect();
+    if !complete {
+        // The last line of a prefix may be cut off
+        lines.pop();
+    }
+    lines.truncate(SNIFF_LINES);
+    if lines.len() < 2 {
+        return None;
+    }
+
+    let mut best: Option<(u8, usize)> = None;
+    for delimiter in DELIMITERS {
+        let counts: Vec<usize> = lines
+            .iter()
+            .map(|line| delimiters_outside_quotes(line, delimiter))
+            .collect();
+        // The most common count, with the number of lines that have it
+        let (lines_with_count, count) = counts
+            .iter()
+            .map(|&count| (counts.iter().filter(|&&c| c == count).count(), count))
+            .max()
+            .unwrap_or_default();
+        let consistent = lines_with_count as f64 >= lines.len() as f64 * MIN_CONSISTENT_SHARE;
+        let beaten = matches!(best, Some((_, most)) if most >= count);
+        if count > 0 && consistent && !beaten {
+            best = Some((delimiter, count));
+        }
+    }
+    best.map(|(delimiter, _)| delimiter)
+}
+
+fn delimiters_outside_quotes(line: &str, delimiter: u8) -> usize {
+    let mut quoted = false;
+    let mut count = 0;
+    for byte in line.bytes() {
+        if byte == b'"' {
+            quoted = !quoted;
+        } else if byte == delimiter && !quoted {
+            count += 1;
+        }
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delimiter_of() {
+        let text = "id;name;visit\n1;\"Smith; J\";2\n2;Doe;3\n";
+        assert_eq!(delimiter_of(text.as_bytes(), true), Some(b';'));
+        let text = "id\tvalue, mg\n1\t2,5\n2\t3,1\n";
+        assert_eq!(delimiter_of(text.as_bytes(), true), Some(b'\t'));
+        let text = "Lab export 2024-01-02\nid|sodium|potassium\n1|140|4.1\n2|138|3.9\n3|141|4.0\n";
+        assert_eq!(delimiter_of(text.as_bytes(), true), Some(b'|'));
+    }
+
+    #[test]
+    fn test_not_delimited() {
+        let prose = "These notes describe the study.\nThey are not a table.\n";
+        assert_eq!(delimiter_of(prose.as_bytes(), true), None);
+        assert_eq!(delimiter_of(b"a,b\n", true), None);
+        assert_eq!(delimiter_of(b"a,b\x00\x01\n1,2\n3,4\n", true), None);
+    }
+}
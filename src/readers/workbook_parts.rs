@@ -0,0 +1,378 @@
+//! Parts of an xlsx workbook that calamine does not expose: hidden columns,
+//! merged cells, cell comments and links to other workbooks, read from the
+//! package's XML.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek};
+use std::path::Path;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// Most of a worksheet part read for its column settings, which come before
+/// the cell data
+const MAX_SHEET_PREFIX: u64 = 1 << 20;
+
+/// Largest workbook or relationships part read
+const MAX_PART_SIZE: u64 = 4 << 20;
+
+static SHEET_TAG: Lazy<Regex> = Lazy::new(|| tag("sheet"));
+static EXTERNAL_REFERENCE_TAG: Lazy<Regex> = Lazy::new(|| tag("externalReference"));
+static RELATIONSHIP_TAG: Lazy<Regex> = Lazy::new(|| tag("Relationship"));
+static COL_TAG: Lazy<Regex> = Lazy::new(|| tag("col"));
+static MERGE_CELL_TAG: Lazy<Regex> = Lazy::new(|| tag("mergeCell"));
+static AUTHOR: Lazy<Regex> = Lazy::new(|| element("author"));
+static COMMENT: Lazy<Regex> = Lazy::new(|| element("comment"));
+static TEXT: Lazy<Regex> = Lazy::new(|| element("t"));
+
+/// Relationship type of a sheet's comments part
+const COMMENTS_RELATIONSHIP: &str = "/relationships/comments";
+
+/// Start or empty tag of an element, with any namespace prefix
+fn tag(name: &str) -> Regex {
+    Regex::new(&format!(r"<(?:\w+:)?{}\b[^>]*>", regex::escape(name))).unwrap()
+}
+
+/// Element with content, capturing its start tag and its content
+fn element(name: &str) -> Regex {
+    let name = regex::escape(name);
+    Regex::new(&format!(r"(?s)(<(?:\w+:)?{0}\b[^>]*>)(.*?)</(?:\w+:)?{0}>", name)).unwrap()
+}
+
+/// A rectangle of cells merged into one, 0-based and inclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MergedCells {
+    pub first_row: u32,
+    pub first_column: u32,
+    pub last_row: u32,
+    pub last_column: u32,
+}
+
+/// What a workbook's XML says beyond its cells
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WorkbookParts {
+    /// Package part of each worksheet, by sheet name
+    sheet_parts: HashMap<String, String>,
+    /// Hidden columns by sheet name, as 0-based inclusive ranges
+    pub hidden_columns: HashMap<String, Vec<(u32, u32)>>,
+    /// File names of the linked external workbooks, in the order formulas
+    /// number them (`[1]`, `[2]`, ...)
+    pub external_links: Vec<String>,
+}
+
+impl WorkbookParts {
+    /// Read the parts of an .xlsx/.xlsm workbook; `None` for files that are not
+    /// zip packages with a workbook part (e.g. legacy .xls)
+    pub fn read(path: &Path) -> Option<Self> {
+        let mut archive = zip::ZipArchive::new(BufReader::new(File::open(path).ok()?)).ok()?;
+        let workbook = read_part(&mut archive, "xl/workbook.xml", MAX_PART_SIZE)?;
+        let rels = read_part(&mut archive, "xl/_rels/workbook.xml.rels", MAX_PART_SIZE)
+            .unwrap_or_default();
+        let targets = relationship_targets(&rels);
+        let part_of = |tag: &str| {
+            let target = targets.get(attribute(tag, "r:id")?)?;
+            Some(part_path("xl", &target.0))
+        };
+
+        let mut parts = Self::default();
+        for tag in SHEET_TAG.find_iter(&workbook).map(|m| m.as_str()) {
+            let (Some(name), Some(part)) = (attribute(tag, "name"), part_of(tag)) else {
+                continue;
+            };
+            let name = unescape(name);
+            if let Some(xml) = read_part(&mut archive, &part, MAX_SHEET_PREFIX) {
+                let hidden = hidden_columns(&xml);
+                if !hidden.is_empty() {
+                    parts.hidden_columns.insert(name.clone(), hidden);
+                }
+            }
+            parts.sheet_parts.insert(name, part);
+        }
+        for tag in EXTERNAL_REFERENCE_TAG.find_iter(&workbook).map(|m| m.as_str()) {
+            let target = part_of(tag).and_then(|part| {
+                let xml = read_part(&mut archive, &rels_path(&part)?, MAX_PART_SIZE)?;
+                let external = relationship_targets(&xml)
+                    .into_values()
+                    .find(|(_, external)| *external)?;
+                Some(external.0)
+            });
+            parts.external_links.push(match target {
+                Some(target) => linked_file_name(&target),
+                None => "unknown workbook".to_string(),
+            });
+        }
+        Some(parts)
+    }
+
+    /// Whether a column (0-based, counted from column A) is hidden in a sheet
+    pub fn is_hidden(&self, sheet: &str, column: u32) -> bool {
+        self.hidden_columns
+            .get(sheet)
+            .is_some_and(|ranges| ranges.iter().any(|&(min, max)| (min..=max).contains(&column)))
+    }
+
+    /// Merged cells of a sheet. They are listed after the cell data, so the
+    /// whole worksheet part is read through. `None` when the sheet's part
+    /// cannot be read (e.g. the workbook is not an xlsx package).
+    pub fn merged_cells(&self, path: &Path, sheet: &str) -> Option<Vec<MergedCells>> {
+        let part = self.sheet_parts.get(sheet)?;
+        let mut archive = zip::ZipArchive::new(BufReader::new(File::open(path).ok()?)).ok()?;
+        let xml = part_tail(&mut archive, part, "</sheetData>")?;
+        Some(
+            MERGE_CELL_TAG
+                .find_iter(&xml)
+                .filter_map(|m| merged_range(attribute(m.as_str(), "ref")?))
+                .collect(),
+        )
+    }
+
+    /// Text of a sheet's cell comments, without the author's name that Excel
+    /// puts before it. `None` when the sheet's part cannot be read (e.g. the
+    /// workbook is not an xlsx package).
+    pub fn comments(&self, path: &Path, sheet: &str) -> Option<Vec<String>> {
+        let part = self.sheet_parts.get(sheet)?;
+        let mut archive = zip::ZipArchive::new(BufReader::new(File::open(path).ok()?)).ok()?;
+        archive.by_name(part).ok()?;
+        let rels = rels_path(part)
+            .and_then(|rels| read_part(&mut archive, &rels, MAX_PART_SIZE))
+            .unwrap_or_default();
+        let target = RELATIONSHIP_TAG.find_iter(&rels).find_map(|m| {
+            let tag = m.as_str();
+            attribute(tag, "Type")
+                .filter(|t| t.ends_with(COMMENTS_RELATIONSHIP))
+                .and(attribute(tag, "Target"))
+        });
+        let Some(target) = target else {
+            return Some(Vec::new());
+        };
+        let dir = part.rsplit_once('/').map_or("", |(dir, _)| dir);
+        let xml = read_part(&mut archive, &part_path(dir, &unescape(target)), MAX_PART_SIZE)?;
+        Some(comment_texts(&xml))
+    }
+}
+
+/// Text of each `<comment>` in a comments part
+fn comment_texts(xml: &str) -> Vec<String> {
+    let authors: Vec<String> = AUTHOR.captures_iter(xml).map(|c| unescape(&c[2])).collect();
+    COMMENT
+        .captures_iter(xml)
+        .map(|comment| {
+            let text: String = TEXT.captures_iter(&comment[2]).map(|t| unescape(&t[2])).collect();
+            let author = attribute(&comment[1], "authorId")
+                .and_then(|id| id.parse::<usize>().ok())
+                .and_then(|id| authors.get(id));
+            match author.and_then(|a| text.strip_prefix(a.as_str())?.strip_prefix(':')) {
+                Some(rest) => rest.trim_start().to_string(),
+                None => text,
+            }
+        })
+        .collect()
+}
+
+/// A merged range reference such as `B1:D1`
+fn merged_range(reference: &str) -> Option<MergedCells> {
+    let (first, last) = reference.split_once(':')?;
+    let (first_row, first_column) = cell_position(first)?;
+    let (last_row, last_column) = cell_position(last)?;
+    Some(MergedCells {
+        first_row,
+        first_column,
+        last_row,
+        last_column,
+    })
+}
+
+/// 0-based row and column of an A1-style cell reference
+fn cell_position(reference: &str) -> Option<(u32, u32)> {
+    let digits = reference.find(|c: char| c.is_ascii_digit())?;
+    let (letters, row) = reference.split_at(digits);
+    if letters.is_empty() || !letters.chars().all(|c| c.is_ascii_uppercase()) {
+        return None;
+    }
+    let column = letters
+        .bytes()
+        .try_fold(0u32, |n, b| n.checked_mul(26)?.checked_add(u32::from(b - b'A') + 1))?;
+    let row: u32 = row.parse().ok()?;
+    Some((row.checked_sub(1)?, column - 1))
+}
+
+/// Relationship targets by id, each with whether it is outside the package
+fn relationship_targets(xml: &str) -> HashMap<String, (String, bool)> {
+    RELATIONSHIP_TAG
+        .find_iter(xml)
+        .filter_map(|m| {
+            let tag = m.as_str();
+            let external = attribute(tag, "TargetMode") == Some("External");
+            Some((
+                attribute(tag, "Id")?.to_string(),
+                (unescape(attribute(tag, "Target")?), external),
+            ))
+        })
+        .collect()
+}
+
+/// Package path of a relationship target, which is relative to `base` unless
+/// it starts with `/`
+fn part_path(base: &str, target: &str) -> String {
+    let (mut segments, target): (Vec<&str>, &str) = match target.strip_prefix('/') {
+        Some(absolute) => (Vec::new(), absolute),
+        None => (base.split('/').filter(|s| !s.is_empty()).collect(), target),
+    };
+    for segment in target.split('/') {
+        match segment {
+            ".." => {
+                segments.pop();
+            }
+            "." | "" => {}
+            segment => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
+
+/// Relationships part of a package part (`xl/_rels/workbook.xml.rels`)
+fn rels_path(part: &str) -> Option<String> {
+    let (dir, file) = part.rsplit_once('/')?;
+    Some(format!("{}/_rels/{}.rels", dir, file))
+}
+
+/// Hidden column ranges of a worksheet's `<cols>` (1-based in the XML)
+fn hidden_columns(xml: &str) -> Vec<(u32, u32)> {
+    let cols_end = xml.find("<sheetData").unwrap_or(xml.len());
+    COL_TAG
+        .find_iter(&xml[..cols_end])
+        .map(|m| m.as_str())
+        .filter(|tag| matches!(attribute(tag, "hidden"), Some("1" | "true")))
+        .filter_map(|tag| {
+            let min: u32 = attribute(tag, "min")?.parse().ok()?;
+            let max: u32 = attribute(tag, "max")?.parse().ok()?;
+            Some((min.checked_sub(1)?, max.checked_sub(1)?))
+        })
+        .collect()
+}
+
+/// File name of a linked workbook, without the folders (which often name a
+/// person: `C:\Users\jsmith\...`)
+fn linked_file_name(target: &str) -> String {
+    let name = target.rsplit(['/', '\\']).next().unwrap_or(target);
+    name.replace("%20", " ")
+}
+
+/// Value of an attribute in a tag
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let pattern = format!("{}=\"", name);
+    let mut rest = tag;
+    while let Some(at) = rest.find(&pattern) {
+        let preceded_by_space = rest[..at].ends_with(char::is_whitespace);
+        let value = &rest[at + pattern.len()..];
+        if preceded_by_space {
+            return value.find('"').map(|end| &value[..end]);
+        }
+        rest = value;
+    }
+    None
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// What follows `marker` in a part, read through in chunks so that only the
+/// tail is kept; `None` if the part or the marker is missing
+fn part_tail<R: Read + Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    name: &str,
+    marker: &str,
+) -> Option<String> {
+    let mut part = archive.by_name(name).ok()?;
+    let marker = marker.as_bytes();
+    let mut window: Vec<u8> = Vec::new();
+    let mut chunk = vec![0; 64 * 1024];
+    loop {
+        let read = part.read(&mut chunk).ok()?;
+        if read == 0 {
+            return None;
+        }
+        window.extend_from_slice(&chunk[..read]);
+        if let Some(at) = window.windows(marker.len()).position(|w| w == marker) {
+            let mut tail = window.split_off(at + marker.len());
+            part.take(MAX_PART_SIZE).read_to_end(&mut tail).ok()?;
+            return Some(String::from_utf8_lossy(&tail).into_owned());
+        }
+        // Keep enough to find a marker split across chunks
+        let keep = window.len().min(marker.len() - 1);
+        window.drain(..window.len() - keep);
+    }
+}
+
+/// Up to `limit` bytes of a part, as text
+fn read_part<R: Read + Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    name: &str,
+    limit: u64,
+) -> Option<String> {
+    let part = archive.by_name(name).ok()?;
+    let mut bytes = Vec::new();
+    part.take(limit).read_to_end(&mut bytes).ok()?;
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hidden_columns() {
+        let xml = r#"<worksheet><cols><col min="1" max="1" width="9"/>
+<col min="2" max="3" width="0" hidden="1" customWidth="1"/><col min="6" max="6" hidden="true"/>
+</cols><sheetData><row r="1"><c r="A1"><v>1</v></c></row></sheetData></worksheet>"#;
+        assert_eq!(hidden_columns(xml), vec![(1, 2), (5, 5)]);
+        assert!(hidden_columns("<worksheet><sheetData/></worksheet>").is_empty());
+    }
+
+    #[test]
+    fn test_attributes_and_links() {
+        let tag = r#"<sheet name="R&amp;D" sheetId="2" state="hidden" r:id="rId2"/>"#;
+        assert_eq!(attribute(tag, "name"), Some("R&amp;D"));
+        assert_eq!(attribute(tag, "id"), None);
+        assert_eq!(attribute(tag, "r:id"), Some("rId2"));
+        assert_eq!(unescape("R&amp;D"), "R&D");
+
+        let rels = r#"<Relationships><Relationship Id="rId1" Type="x"
+Target="file:///C:\Users\jsmith\Patient%20list.xlsx" TargetMode="External"/></Relationships>"#;
+        let (target, external) = &relationship_targets(rels)["rId1"];
+        assert!(external);
+        assert_eq!(linked_file_name(target), "Patient list.xlsx");
+        assert_eq!(part_path("xl", "worksheets/sheet1.xml"), "xl/worksheets/sheet1.xml");
+        assert_eq!(part_path("xl", "/xl/worksheets/sheet1.xml"), "xl/worksheets/sheet1.xml");
+        assert_eq!(part_path("xl/worksheets", "../comments1.xml"), "xl/comments1.xml");
+        assert_eq!(rels_path("xl/workbook.xml").unwrap(), "xl/_rels/workbook.xml.rels");
+    }
+
+    #[test]
+    fn test_comment_texts() {
+        let xml = r#"<comments><authors><author>Ann Lee</author><author>QA</author></authors>
+<commentList><comment ref="B2" authorId="0"><text><r><rPr><b/></rPr><t>Ann Lee:</t></r>
+<r><t xml:space="preserve">
+checked with Dr. Smith</t></r></text></comment>
+<comment ref="C3" authorId="1"><text><t>Range 0&lt;x</t></text></comment>
+</commentList></comments>"#;
+        assert_eq!(comment_texts(xml), vec!["checked with Dr. Smith", "Range 0<x"]);
+    }
+
+    #[test]
+    fn test_merged_range() {
+        let range = merged_range("B1:D2").unwrap();
+        assert_eq!((range.first_row, range.first_column), (0, 1));
+        assert_eq!((range.last_row, range.last_column), (1, 3));
+        assert_eq!(cell_position("AA10"), Some((9, 26)));
+        assert_eq!(cell_position("A0"), None);
+        assert_eq!(cell_position("10"), None);
+        assert!(merged_range("B1").is_none());
+    }
+}
@@ -0,0 +1,738 @@
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use chrono::{Duration, NaiveDate};
+
+use crate::audit::{AuditSink, NullAuditSink};
+use crate::error::Error;
+use crate::privacy::{check_value_pattern, RecodeRegistry};
+use crate::sanitize::sanitize_text;
+use crate::types::{ProcessingOptions, Result, SafeValue, SheetSchema};
+
+use super::profile::profile_stream;
+use super::{DataReader, RowBatch, StreamingDataReader};
+
+/// Transport files are laid out in 80-byte records
+const RECORD_LEN: usize = 80;
+
+/// Start of every header record
+const HEADER_PREFIX: &[u8] = b"HEADER RECORD*******";
+
+/// Length of a variable descriptor unless the member header says otherwise
+/// (VAX/VMS files use 136)
+const DEFAULT_NAMESTR_LEN: usize = 140;
+
+/// SAS formats whose values are days since 1960-01-01
+const DATE_FORMATS: &[&str] = &[
+    "DATE", "YYMMDD", "MMDDYY", "DDMMYY", "E8601DA", "B8601DA", "IS8601DA", "WEEKDATE", "WORDDATE",
+    "NLDATE",
+];
+
+/// SAS formats whose values are seconds since 1960-01-01 00:00:00
+const DATETIME_FORMATS: &[&str] = &["DATETIME", "E8601DT", "B8601DT", "IS8601DT", "NLDATM"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum XptVersion {
+    V5,
+    V8,
+}
+
+impl XptVersion {
+    /// Name of a header record in this version (`MEMBER` is `MEMBV8` in v8, ...)
+    fn record_name(self, v5: &'static str, v8: &'static str) -> &'static str {
+        match self {
+            XptVersion::V5 => v5,
+            XptVersion::V8 => v8,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ValueKind {
+    Number,
+    Date,
+    Datetime,
+    Text,
+}
+
+/// One variable of a dataset, from its descriptor ("namestr") record
+#[derive(Debug, Clone, PartialEq)]
+struct Variable {
+    name: String,
+    label: String,
+    kind: ValueKind,
+    /// Offset of the value within an observation
+    position: usize,
+    length: usize,
+}
+
+/// One dataset (member) of a transport file
+#[derive(Debug, Clone, PartialEq)]
+struct Member {
+    name: String,
+    variables: Vec<Variable>,
+    obs_length: usize,
+    data_start: u64,
+    rows: u64,
+}
+
+/// Reader for SAS transport (XPORT v5 and v8/v9) files; each dataset becomes a sheet
+pub struct XptReader {
+    path: PathBuf,
+}
+
+impl XptReader {
+    pub fn new(path: &Path) -> Result<Self> {
+        Ok(Self {
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+fn invalid(problem: &str) -> Error {
+    Error::InvalidInput(format!("not a valid SAS transport file: {}", problem))
+}
+
+fn is_header(record: &[u8], name: &str) -> bool {
+    record.starts_with(HEADER_PREFIX)
+        && record[HEADER_PREFIX.len()..].starts_with(name.as_bytes())
+        && record[HEADER_PREFIX.len() + name.len()..].starts_with(b" ")
+}
+
+/// Text of a fixed-width field, without trailing blanks or NULs
+fn field_text(bytes: &[u8]) -> String {
+    let end = bytes
+        .iter()
+        .rposition(|&b| b != b' ' && b != 0)
+        .map_or(0, |i| i + 1);
+    let bytes = &bytes[..end];
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        // Older files are in a single-byte Latin encoding
+        Err(_) => encoding_rs::WINDOWS_1252.decode(bytes).0.into_owned(),
+    }
+}
+
+fn be_u16(bytes: &[u8]) -> usize {
+    u16::from_be_bytes([bytes[0], bytes[1]]) as usize
+}
+
+/// Sequential 80-byte records with their offsets
+struct Records {
+    reader: BufReader<File>,
+    offset: u64,
+}
+
+impl Records {
+    fn next(&mut self) -> Result<Option<[u8; RECORD_LEN]>> {
+        let mut record = [0u8; RECORD_LEN];
+        let mut filled = 0;
+        while filled < RECORD_LEN {
+            match self.reader.read(&mut record[filled..])? {
+                0 if filled == 0 => return Ok(None),
+                0 => return Err(invalid("truncated record")),
+                n => filled += n,
+            }
+        }
+        self.offset += RECORD_LEN as u64;
+        Ok(Some(record))
+    }
+
+    fn expect(&mut self) -> Result<[u8; RECORD_LEN]> {
+        self.next()?
+            .ok_or_else(|| invalid("unexpected end of file"))
+    }
+
+    /// Records up to the next header record, joined, and that header
+    fn until_header(&mut self) -> Result<(Vec<u8>, Option<[u8; RECORD_LEN]>)> {
+        let mut bytes = Vec::new();
+        while let Some(record) = self.next()? {
+            if record.starts_with(HEADER_PREFIX) {
+                return Ok((bytes, Some(record)));
+            }
+            bytes.extend_from_slice(&record);
+        }
+        Ok((bytes, None))
+    }
+}
+
+/// Parse the variable descriptors of a member
+fn parse_namestrs(bytes: &[u8], namestr_len: usize, version: XptVersion) -> Result<Vec<Variable>> {
+    let mut variables = Vec::new();
+    for namestr in bytes.chunks_exact(namestr_len) {
+        let kind = match be_u16(&namestr[0..2]) {
+            1 => ValueKind::Number,
+            2 => ValueKind::Text,
+            _ => return Err(invalid("unknown variable type")),
+        };
+        let length = be_u16(&namestr[4..6]);
+        let short_name = field_text(&namestr[8..16]);
+        let long_name = match version {
+            XptVersion::V8 if namestr_len >= 120 => field_text(&namestr[88..120]),
+            _ => String::new(),
+        };
+        let format = field_text(&namestr[56..64]).to_uppercase();
+        let position = u32::from_be_bytes([namestr[84], namestr[85], namestr[86], namestr[87]]);
+        variables.push(Variable {
+            name: if long_name.is_empty() {
+                short_name
+            } else {
+                long_name
+            },
+            label: field_text(&namestr[16..56]),
+            kind: match kind {
+                ValueKind::Number if DATE_FORMATS.iter().any(|f| *f == format) => ValueKind::Date,
+                ValueKind::Number if DATETIME_FORMATS.iter().any(|f| *f == format) => {
+                    ValueKind::Datetime
+                }
+                kind => kind,
+            },
+            position: position as usize,
+            length,
+        });
+    }
+    Ok(variables)
+}
+
+/// Apply the long labels of a v8 `LABELV8`/`LABELV9` section
+fn apply_long_labels(bytes: &[u8], with_formats: bool, variables: &mut [Variable]) {
+    let mut rest = bytes;
+    let fixed = if with_formats { 10 } else { 6 };
+    while rest.len() >= fixed {
+        let number = be_u16(&rest[0..2]);
+        let name_len = be_u16(&rest[2..4]);
+        let label_len = be_u16(&rest[4..6]);
+        let (format_len, informat_len) = if with_formats {
+            (be_u16(&rest[6..8]), be_u16(&rest[8..10]))
+        } else {
+            (0, 0)
+        };
+        let total = fixed + name_len + label_len + format_len + informat_len;
+        if number == 0 || rest.len() < total {
+            break;
+        }
+        let label = &rest[fixed + name_len..fixed + name_len + label_len];
+        if let Some(variable) = variables.get_mut(number - 1) {
+            variable.label = field_text(label);
+        }
+        rest = &rest[total..];
+    }
+}
+
+/// Read the headers of every member, noting where each one's observations are
+fn parse_members(path: &Path) -> Result<Vec<Member>> {
+    let file = File::open(path)?;
+    let file_len = file.metadata()?.len();
+    let mut records = Records {
+        reader: BufReader::new(file),
+        offset: 0,
+    };
+
+    let first = records.next()?.ok_or_else(|| invalid("empty file"))?;
+    let version = if is_header(&first, "LIBRARY") {
+        XptVersion::V5
+    } else if is_header(&first, "LIBV8") {
+        XptVersion::V8
+    } else {
+        return Err(invalid("missing library header"));
+    };
+    // Library "real" header and modification date
+    records.expect()?;
+    records.expect()?;
+
+    let mut members = Vec::new();
+    let mut header = records.next()?;
+    while let Some(member_header) = header {
+        if !is_header(&member_header, version.record_name("MEMBER", "MEMBV8")) {
+            return Err(invalid("missing member header"));
+        }
+        let namestr_len = std::str::from_utf8(&member_header[74..78])
+            .ok()
+            .and_then(|n| n.trim().parse::<usize>().ok())
+            .filter(|n| *n >= 88)
+            .unwrap_or(DEFAULT_NAMESTR_LEN);
+        if !is_header(
+            &records.expect()?,
+            version.record_name("DSCRPTR", "DSCPTV8"),
+        ) {
+            return Err(invalid("missing descriptor header"));
+        }
+        let descriptor = records.expect()?;
+        let name = match version {
+            XptVersion::V5 => field_text(&descriptor[8..16]),
+            XptVersion::V8 => field_text(&descriptor[8..40]),
+        };
+        records.expect()?;
+        if !is_header(
+            &records.expect()?,
+            version.record_name("NAMESTR", "NAMSTV8"),
+        ) {
+            return Err(invalid("missing variable header"));
+        }
+
+        let (namestrs, mut next) = records.until_header()?;
+        let mut variables = parse_namestrs(&namestrs, namestr_len, version)?;
+        while let Some(record) = next {
+            let with_formats = is_header(&record, "LABELV9");
+            if !with_formats && !is_header(&record, "LABELV8") {
+                break;
+            }
+            let (labels, after) = records.until_header()?;
+            apply_long_labels(&labels, with_formats, &mut variables);
+            next = after;
+        }
+        match next {
+            Some(record) if is_header(&record, version.record_name("OBS", "OBSV8")) => {}
+            _ => return Err(invalid("missing observation header")),
+        }
+
+        // Observations run until the next member header or the end of the file
+        let data_start = records.offset;
+        let member_tag = version.record_name("MEMBER", "MEMBV8");
+        header = None;
+        while let Some(record) = records.next()? {
+            if is_header(&record, member_tag) {
+                header = Some(record);
+                break;
+            }
+        }
+        let data_end = match header {
+            Some(_) => records.offset - RECORD_LEN as u64,
+            None => file_len,
+        };
+
+        let obs_length = variables
+            .iter()
+            .map(|v| v.position + v.length)
+            .max()
+            .unwrap_or(0);
+        if variables
+            .iter()
+            .any(|v| v.kind != ValueKind::Text && !(2..=8).contains(&v.length))
+        {
+            return Err(invalid("numeric variable longer than 8 bytes"));
+        }
+        let mut member = Member {
+            name,
+            variables,
+            obs_length,
+            data_start,
+            rows: 0,
+        };
+        member.rows = count_rows(path, &member, data_end)?;
+        members.push(member);
+    }
+    Ok(members)
+}
+
+/// Number of observations in a member's data. The last record is padded with
+/// blanks, which can look like whole observations when they are short.
+fn count_rows(path: &Path, member: &Member, data_end: u64) -> Result<u64> {
+    let obs_length = member.obs_length as u64;
+    if obs_length == 0 {
+        return Ok(0);
+    }
+    let data_len = data_end - member.data_start;
+    let mut rows = data_len / obs_length;
+
+    let mut file = File::open(path)?;
+    let mut row = vec![0u8; member.obs_length];
+    while rows > 0 && (rows - 1) * obs_length + RECORD_LEN as u64 >= data_len {
+        file.seek(SeekFrom::Start(member.data_start + (rows - 1) * obs_length))?;
+        file.read_exact(&mut row)?;
+        if row.iter().any(|&b| b != b' ') {
+            break;
+        }
+        rows -= 1;
+    }
+    Ok(rows)
+}
+
+/// Convert an IBM System/360 floating-point number (big-endian, 2 to 8 bytes)
+fn ibm_to_f64(bytes: &[u8]) -> f64 {
+    let mut b = [0u8; 8];
+    b[..bytes.len()].copy_from_slice(bytes);
+    let sign = if b[0] & 0x80 != 0 { -1.0 } else { 1.0 };
+    let exponent = (b[0] & 0x7f) as i32 - 64;
+    let mantissa = u64::from_be_bytes([0, b[1], b[2], b[3], b[4], b[5], b[6], b[7]]);
+    sign * mantissa as f64 / 2f64.powi(56) * 16f64.powi(exponent)
+}
+
+/// Text for a numeric value; SAS missing values (`.`, `._`, `.A` to `.Z`) become
+/// "" or the matching lowercase code
+fn number_text(bytes: &[u8], kind: ValueKind) -> String {
+    if bytes[1..].iter().all(|&b| b == 0) {
+        match bytes[0] {
+            b'.' | b'_' => return String::new(),
+            code @ b'A'..=b'Z' => return format!(".{}", code.to_ascii_lowercase() as char),
+            _ => {}
+        }
+    }
+
+    let value = ibm_to_f64(bytes);
+    let epoch = NaiveDate::from_ymd_opt(1960, 1, 1).expect("valid date");
+    // Out-of-range offsets (a corrupt file) fall back to the plain number
+    let formatted = match kind {
+        ValueKind::Date => Duration::try_days(value.floor() as i64)
+            .and_then(|days| epoch.checked_add_signed(days))
+            .map(|d| d.format("%Y-%m-%d").to_string()),
+        ValueKind::Datetime => Duration::try_seconds(value.floor() as i64)
+            .and_then(|seconds| epoch.and_hms_opt(0, 0, 0)?.checked_add_signed(seconds))
+            .map(|t| t.format("%Y-%m-%dT%H:%M:%S").to_string()),
+        _ => None,
+    };
+    formatted.unwrap_or_else(|| {
+        // IBM floats carry about 15 significant digits; print 0.1 rather than 0.09999999999999999
+        let rounded: f64 = format!("{:.14e}", value).parse().unwrap_or(value);
+        if rounded.fract() == 0.0 && rounded.abs() < 1e15 {
+            format!("{}", rounded as i64)
+        } else {
+            format!("{}", rounded)
+        }
+    })
+}
+
+/// Streams the observations of one member
+struct MemberStream<'a> {
+    member: &'a Member,
+    file: BufReader<File>,
+    next_row: u64,
+}
+
+impl<'a> MemberStream<'a> {
+    fn open(path: &Path, member: &'a Member) -> Result<Self> {
+        let mut stream = Self {
+            member,
+            file: BufReader::new(File::open(path)?),
+            next_row: 0,
+        };
+        stream.rewind()?;
+        Ok(stream)
+    }
+}
+
+impl StreamingDataReader for MemberStream<'_> {
+    fn headers(&mut self) -> Result<Vec<String>> {
+        Ok(self
+            .member
+            .variables
+            .iter()
+            .map(|v| v.name.clone())
+            .collect())
+    }
+
+    fn next_batch(&mut self, max_rows: usize) -> Result<Option<RowBatch>> {
+        let mut batch = Vec::new();
+        let mut row = vec![0u8; self.member.obs_length];
+        while batch.len() < max_rows && self.next_row < self.member.rows {
+            self.file.read_exact(&mut row)?;
+            self.next_row += 1;
+            batch.push(
+                self.member
+                    .variables
+                    .iter()
+                    .map(|v| {
+                        let bytes = &row[v.position..v.position + v.length];
+                        match v.kind {
+                            ValueKind::Text => field_text(bytes),
+                            kind => number_text(bytes, kind),
+                        }
+                    })
+                    .collect(),
+            );
+        }
+
+        if batch.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(batch))
+        }
+    }
+
+    fn rewind(&mut self) -> Result<()> {
+        self.file.seek(SeekFrom::Start(self.member.data_start))?;
+        self.next_row = 0;
+        Ok(())
+    }
+}
+
+impl DataReader for XptReader {
+    fn read(&mut self, options: &ProcessingOptions) -> Result<Vec<SheetSchema>> {
+        let (sheets, _recode_registry) = self.read_with_recoding(options, &mut NullAuditSink)?;
+        Ok(sheets)
+    }
+
+    fn read_with_recoding(
+        &mut self,
+        options: &ProcessingOptions,
+        audit: &mut dyn AuditSink,
+    ) -> Result<(Vec<SheetSchema>, RecodeRegistry)> {
+        let members = parse_members(&self.path)?;
+        let mut sheets = Vec::with_capacity(members.len());
+        let mut recode_registry = RecodeRegistry::new();
+
+        for (idx, member) in members.iter().enumerate() {
+            if !options.sheet_selected(&member.name) {
+                continue;
+            }
+            let mut stream = MemberStream::open(&self.path, member)?;
+            let (mut sheet, sheet_registry) =
+                profile_stream(&mut stream, &member.name, idx, options, audit)?;
+
+            // Labels are shown only for columns whose name is shown, and only if
+            // they do not look like PHI themselves
+            for column in &mut sheet.columns {
+                let Some(variable) = member.variables.get(column.index) else {
+                    continue;
+                };
+                let label = sanitize_text(&variable.label);
+                if matches!(column.name, SafeValue::ShortString(_))
+                    && !label.is_empty()
+                    && !check_value_pattern(&label).is_phi
+                {
+                    column.label = Some(label.into_owned());
+                }
+            }
+            recode_registry.merge_sheet(&member.name, sheet_registry);
+            sheets.push(sheet);
+        }
+
+        Ok((sheets, recode_registry))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Classification, DType};
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn f64_to_ibm(value: f64) -> [u8; 8] {
+        if value == 0.0 {
+            return [0; 8];
+        }
+        let sign = if value < 0.0 { 0x80 } else { 0 };
+        let mut fraction = value.abs();
+        let mut exponent = 64;
+        while fraction >= 1.0 {
+            fraction /= 16.0;
+            exponent += 1;
+        }
+        while fraction < 1.0 / 16.0 {
+            fraction *= 16.0;
+            exponent -= 1;
+        }
+        let mantissa = (fraction * 2f64.powi(56)).round() as u64;
+        let mut bytes = mantissa.to_be_bytes();
+        bytes[0] = sign | exponent as u8;
+        bytes
+    }
+
+    fn padded(text: &str, len: usize) -> Vec<u8> {
+        let mut bytes = text.as_bytes().to_vec();
+        bytes.resize(len, b' ');
+        bytes
+    }
+
+    fn header(name: &str, tail: &str) -> Vec<u8> {
+        padded(
+            &format!(
+                "HEADER RECORD*******{:<8}HEADER RECORD!!!!!!!{}",
+                name, tail
+            ),
+            RECORD_LEN,
+        )
+    }
+
+    fn pad_record(bytes: &mut Vec<u8>) {
+        let len = bytes.len().div_ceil(RECORD_LEN) * RECORD_LEN;
+        bytes.resize(len, b' ');
+    }
+
+    /// (name, label, numeric, length, format)
+    type Var<'a> = (&'a str, &'a str, bool, usize, &'a str);
+
+    fn member(out: &mut Vec<u8>, name: &str, vars: &[Var], rows: &[Vec<Vec<u8>>]) {
+        out.extend(header("MEMBER", "000000000000000001600000000140"));
+        out.extend(header("DSCRPTR", "000000000000000000000000000000"));
+        out.extend(padded(
+            &format!("SAS     {:<8}SASDATA 9.4", name),
+            RECORD_LEN,
+        ));
+        out.extend(padded("", RECORD_LEN));
+        out.extend(header(
+            "NAMESTR",
+            &format!("000000{:04}00000000000000000000", vars.len()),
+        ));
+        let mut namestrs = Vec::new();
+        let mut position = 0;
+        for (i, (name, label, numeric, length, format)) in vars.iter().enumerate() {
+            let mut n = Vec::new();
+            n.extend((if *numeric { 1u16 } else { 2 }).to_be_bytes());
+            n.extend(0u16.to_be_bytes());
+            n.extend((*length as u16).to_be_bytes());
+            n.extend((i as u16 + 1).to_be_bytes());
+            n.extend(padded(name, 8));
+            n.extend(padded(label, 40));
+            n.extend(padded(format, 8));
+            n.extend([0u8; 8]);
+            n.extend(padded("", 8));
+            n.extend([0u8; 4]);
+            n.extend((position as u32).to_be_bytes());
+            n.resize(140, 0);
+            namestrs.extend(n);
+            position += length;
+        }
+        pad_record(&mut namestrs);
+        out.extend(namestrs);
+        out.extend(header("OBS", "000000000000000000000000000000"));
+        let mut data = Vec::new();
+        for row in rows {
+            for value in row {
+                data.extend(value);
+            }
+        }
+        pad_record(&mut data);
+        out.extend(data);
+    }
+
+    fn sample_file() -> NamedTempFile {
+        let mut bytes = header("LIBRARY", "000000000000000000000000000000");
+        bytes.extend(padded("SAS     SAS     SASLIB  9.4", RECORD_LEN));
+        bytes.extend(padded("", RECORD_LEN));
+
+        let vars: [Var; 4] = [
+            ("USUBJID", "Unique Subject Identifier", false, 10, ""),
+            ("AGE", "Age", true, 8, ""),
+            ("RFSTDTC", "Reference Start Date", true, 8, "DATE"),
+            ("WEIGHT", "Weight (kg)", true, 8, ""),
+        ];
+        let rows: Vec<Vec<Vec<u8>>> = (0..7)
+            .map(|i| {
+                let age = if i == 3 {
+                    let mut missing = [0u8; 8];
+                    missing[0] = b'A';
+                    missing
+                } else {
+                    f64_to_ibm(30.0 + i as f64)
+                };
+                vec![
+                    padded(&format!("STUDY-{:03}", i), 10),
+                    age.to_vec(),
+                    // 2024-01-15 is day 23390 since 1960-01-01
+                    f64_to_ibm(23390.0 + i as f64).to_vec(),
+                    f64_to_ibm(70.1).to_vec(),
+                ]
+            })
+            .collect();
+        member(&mut bytes, "DM", &vars, &rows);
+
+        let vars: [Var; 1] = [("AETERM", "Reported Term", false, 5, "")];
+        let rows: Vec<Vec<Vec<u8>>> = ["HEAD", "RASH", "HEAD"]
+            .iter()
+            .map(|t| vec![padded(t, 5)])
+            .collect();
+        member(&mut bytes, "AE", &vars, &rows);
+
+        let mut file = NamedTempFile::with_suffix(".xpt").unwrap();
+        file.write_all(&bytes).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_ibm_float() {
+        for value in [0.0, 1.0, -2.5, 70.1, 23390.0, 1e-5, 123456789.0] {
+            assert!((ibm_to_f64(&f64_to_ibm(value)) - value).abs() <= value.abs() * 1e-15);
+        }
+        assert_eq!(number_text(&f64_to_ibm(70.1), ValueKind::Number), "70.1");
+        assert_eq!(number_text(&f64_to_ibm(34.0), ValueKind::Number), "34");
+        assert_eq!(number_text(&f64_to_ibm(0.0), ValueKind::Date), "1960-01-01");
+        assert_eq!(
+            number_text(&f64_to_ibm(86400.0 + 3661.0), ValueKind::Datetime),
+            "1960-01-02T01:01:01"
+        );
+        assert_eq!(number_text(b".\0\0\0\0\0\0\0", ValueKind::Number), "");
+        assert_eq!(number_text(b"Z\0\0\0\0\0\0\0", ValueKind::Number), ".z");
+    }
+
+    #[test]
+    fn test_parse_members() {
+        let file = sample_file();
+        let members = parse_members(file.path()).unwrap();
+        assert_eq!(members.len(), 2);
+        assert_eq!(members[0].name, "DM");
+        assert_eq!(members[0].rows, 7);
+        assert_eq!(members[0].obs_length, 34);
+        assert_eq!(members[0].variables[2].kind, ValueKind::Date);
+        assert_eq!(members[1].name, "AE");
+        // Three 5-byte rows padded to 80 bytes: the blank padding is not data
+        assert_eq!(members[1].rows, 3);
+    }
+
+    #[test]
+    fn test_xpt_read() {
+        let file = sample_file();
+        let mut reader = XptReader::new(file.path()).unwrap();
+        let options = ProcessingOptions {
+            bucket_counts: false,
+            ..ProcessingOptions::default()
+        };
+        let sheets = reader.read(&options).unwrap();
+
+        assert_eq!(sheets.len(), 2);
+        let dm = &sheets[0];
+        assert_eq!(dm.name, "DM");
+        assert_eq!(dm.row_count, SafeValue::Integer(7));
+        assert_eq!(
+            dm.columns[0].label.as_deref(),
+            Some("Unique Subject Identifier")
+        );
+        assert_eq!(dm.columns[1].dtype, DType::Integer);
+        let age_stats = dm.columns[1].stats.as_ref().unwrap();
+        assert_eq!(age_stats.missing_count, Some(SafeValue::Integer(1)));
+        assert!(age_stats.missing_codes.as_ref().unwrap().contains_key(".a"));
+        assert_eq!(dm.columns[2].dtype, DType::Date);
+        assert_eq!(dm.columns[3].dtype, DType::Numeric);
+        assert_eq!(dm.columns[3].stats.as_ref().unwrap().mean, Some(70.1));
+
+        let ae = &sheets[1];
+        assert_eq!(ae.row_count, SafeValue::Integer(3));
+        assert_eq!(ae.columns[0].classification, Classification::Safe);
+    }
+
+    #[test]
+    fn test_not_xpt() {
+        let mut file = NamedTempFile::with_suffix(".xpt").unwrap();
+        file.write_all(b"id,name\n1,x\n").unwrap();
+        let mut reader = XptReader::new(file.path()).unwrap();
+        assert!(reader.read(&ProcessingOptions::default()).is_err());
+    }
+
+    proptest::proptest! {
+        #![proptest_config(proptest::prelude::ProptestConfig::with_cases(64))]
+
+        /// A transport file with bytes overwritten or cut short is read or
+        /// refused with an error, never a panic
+        #[test]
+        fn prop_xpt_corrupted(
+            edits in proptest::collection::vec((0usize..4000, 0u8..=255), 0..16),
+            cut in 0usize..4000,
+        ) {
+            let mut bytes = std::fs::read(sample_file().path()).unwrap();
+            for (at, byte) in edits {
+                let len = bytes.len();
+                bytes[at % len] = byte;
+            }
+            bytes.truncate(cut.max(1) * 2);
+            let mut file = NamedTempFile::with_suffix(".xpt").unwrap();
+            file.write_all(&bytes).unwrap();
+            if let Ok(mut reader) = XptReader::new(file.path()) {
+                if let Err(e) = reader.read(&ProcessingOptions::default()) {
+                    proptest::prop_assert!(!e.to_string().is_empty());
+                }
+            }
+        }
+    }
+}
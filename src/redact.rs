@@ -0,0 +1,197 @@
+//! Stricter derivatives of existing manifests, for sharing beyond the team that
+//! ran the scan without going back to the source data.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::compat::{self, LoadedManifest};
+use crate::privacy::bucket_count;
+use crate::types::{
+    ColumnSchema, ManifestSchema, MultiFileManifest, Result, SafeValue, SheetSchema,
+};
+
+/// Remove every list of data values from a manifest and bucket every count.
+///
+/// Gone are unique values, examples, value frequencies, frequent values, case
+/// variants, the exemplars of value clusters and out-of-list code values. The
+/// structure, types, classifications, summary statistics and warnings stay.
+pub fn redact_manifest(manifest: &mut ManifestSchema) {
+    for sheet in &mut manifest.sheets {
+        redact_sheet(sheet);
+    }
+    for result in &mut manifest.validation {
+        result.checked.iter_mut().for_each(bucket);
+        result.violations.iter_mut().for_each(bucket);
+    }
+    manifest.options.bucket_counts = true;
+    manifest.options.exact_counts = false;
+    manifest.options.examples = false;
+    manifest.redacted = true;
+}
+
+/// [`redact_manifest`] for each file of a multi-file manifest, with the join
+/// key overlaps bucketed
+pub fn redact_multi_manifest(manifest: &mut MultiFileManifest) {
+    for file in &mut manifest.files {
+        redact_manifest(file);
+    }
+    for key in &mut manifest.join_keys {
+        bucket(&mut key.estimated_overlap);
+    }
+}
+
+/// Read a single- or multi-file JSON manifest of any supported version and
+/// return its redacted form as JSON
+pub fn redact_file(path: &Path) -> Result<String> {
+    match compat::load_manifest(path)? {
+        LoadedManifest::Single(mut manifest) => {
+            redact_manifest(&mut manifest);
+            Ok(serde_json::to_string_pretty(&manifest)?)
+        }
+        LoadedManifest::Multi(mut manifest) => {
+            redact_multi_manifest(&mut manifest);
+            Ok(serde_json::to_string_pretty(&manifest)?)
+        }
+    }
+}
+
+fn redact_sheet(sheet: &mut SheetSchema) {
+    bucket(&mut sheet.row_count);
+    sheet.duplicate_rows.iter_mut().for_each(bucket);
+    for correlation in &mut sheet.correlations {
+        bucket(&mut correlation.count);
+    }
+    for check in &mut sheet.quasi_identifier_checks {
+        bucket(&mut check.classes);
+        for diversity in &mut check.l_diversity {
+            bucket(&mut diversity.groups_below_l);
+        }
+    }
+    if let Some(scan) = &mut sheet.embedded_text {
+        bucket(&mut scan.comments);
+        bucket(&mut scan.comments_with_phi);
+        bucket(&mut scan.formulas);
+        bucket(&mut scan.formulas_with_phi);
+    }
+    if let Some(sample) = &mut sheet.sample {
+        bucket(&mut sample.table_rows);
+        bucket(&mut sample.sampled_rows);
+    }
+    for result in &mut sheet.validation {
+        result.checked.iter_mut().for_each(bucket);
+        result.violations.iter_mut().for_each(bucket);
+    }
+    for column in &mut sheet.columns {
+        redact_column(column);
+    }
+}
+
+fn redact_column(column: &mut ColumnSchema) {
+    column.unique_values = None;
+    column.examples.clear();
+    if let Some(diagnostics) = &mut column.diagnostics {
+        bucket(&mut diagnostics.sample_size);
+        bucket(&mut diagnostics.conflicts);
+    }
+    if let Some(code_list) = &mut column.code_list {
+        code_list.values.clear();
+        bucket(&mut code_list.checked);
+        bucket(&mut code_list.out_of_list);
+    }
+
+    let Some(stats) = &mut column.stats else {
+        return;
+    };
+    stats.value_frequencies = None;
+    stats.suppressed_cells = None;
+    stats.top_values = None;
+    stats.case_variants = None;
+    for value in [
+        &mut stats.count,
+        &mut stats.missing_count,
+        &mut stats.non_finite_count,
+        &mut stats.outliers_sd,
+        &mut stats.outliers_iqr,
+        &mut stats.unique_count,
+    ] {
+        value.iter_mut().for_each(bucket);
+    }
+    for counts in [
+        &mut stats.missing_codes,
+        &mut stats.pattern_hits,
+        &mut stats.whitespace_anomalies,
+        &mut stats.suspected_missing_codes,
+    ] {
+        counts.iter_mut().for_each(bucket_all);
+    }
+    if let Some(summary) = &mut stats.text_summary {
+        bucket_all(&mut summary.languages);
+        bucket(&mut summary.words);
+    }
+    for cluster in stats.value_clusters.iter_mut().flatten() {
+        cluster.exemplars.clear();
+        bucket(&mut cluster.variants);
+        bucket(&mut cluster.count);
+    }
+}
+
+/// Bucket an exact count; bucketed and suppressed counts are left as they are
+fn bucket(count: &mut SafeValue) {
+    if let SafeValue::Integer(n) = *count {
+        *count = SafeValue::ShortString(bucket_count(n.max(0) as u64).to_string());
+    }
+}
+
+fn bucket_all(counts: &mut BTreeMap<String, SafeValue>) {
+    counts.values_mut().for_each(bucket);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ColumnStats, DType, FileFormat, FrequentValue};
+
+    #[test]
+    fn test_redact_manifest() {
+        let mut column = ColumnSchema::new(SafeValue::ShortString("arm".into()), 0, DType::String);
+        column.unique_values = Some(vec![SafeValue::ShortString("A".into())]);
+        column.examples = vec![SafeValue::ShortString("A".into())];
+        column.stats = Some(ColumnStats {
+            count: Some(SafeValue::Integer(412)),
+            missing_count: Some(SafeValue::ShortString("2-5".into())),
+            min: Some(SafeValue::Integer(18)),
+            top_values: Some(vec![FrequentValue {
+                value: SafeValue::ShortString("A".into()),
+                count: SafeValue::Integer(200),
+            }]),
+            value_frequencies: Some(BTreeMap::from([("A".into(), SafeValue::Integer(200))])),
+            pattern_hits: Some(BTreeMap::from([("email".into(), SafeValue::Integer(3))])),
+            ..ColumnStats::default()
+        });
+        let mut sheet = SheetSchema::new("Visits".into(), 0);
+        sheet.row_count = SafeValue::Integer(415);
+        sheet.columns.push(column);
+        let mut manifest = ManifestSchema::new("visits.csv".into(), FileFormat::Csv);
+        manifest.options.bucket_counts = false;
+        manifest.sheets.push(sheet);
+
+        redact_manifest(&mut manifest);
+        assert!(manifest.redacted);
+        assert!(manifest.options.bucket_counts);
+        let sheet = &manifest.sheets[0];
+        assert_eq!(sheet.row_count, SafeValue::ShortString("101-1000".into()));
+        let column = &sheet.columns[0];
+        assert!(column.unique_values.is_none() && column.examples.is_empty());
+        let stats = column.stats.as_ref().unwrap();
+        assert_eq!(stats.count, Some(SafeValue::ShortString("101-1000".into())));
+        assert_eq!(
+            stats.missing_count,
+            Some(SafeValue::ShortString("2-5".into()))
+        );
+        // Values are not counts
+        assert_eq!(stats.min, Some(SafeValue::Integer(18)));
+        assert!(stats.top_values.is_none() && stats.value_frequencies.is_none());
+        let hits = stats.pattern_hits.as_ref().unwrap();
+        assert_eq!(hits["email"], SafeValue::ShortString("2-5".into()));
+    }
+}
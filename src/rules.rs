@@ -0,0 +1,464 @@
+use std::cmp::Ordering;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use ed25519_dalek::{Signature, VerifyingKey};
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+
+use crate::config::{self, DetectorConfig};
+use crate::error::Error;
+use crate::output::write_atomic;
+use crate::paths;
+use crate::privacy::CustomDetector;
+use crate::types::{Result, RulePackVersion};
+
+/// Extension of installed rule packs
+const PACK_EXTENSION: &str = "toml";
+
+/// Suffix of detached signature files (`pack.toml.sig`)
+pub const SIGNATURE_SUFFIX: &str = ".sig";
+
+/// Largest pack or signature accepted from a URL or file
+const MAX_DOWNLOAD_BYTES: u64 = 4 * 1024 * 1024;
+
+/// A versioned bundle of value detectors, distributed by a privacy office
+/// and installed with `ert-manifest rules update`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RulePack {
+    /// Also the installed file name, so letters, digits, `-`, `_` and `.` only
+    pub name: String,
+    /// Dotted version (`2024.06.1`); updates never go to a lower version unless forced
+    pub version: String,
+    pub description: Option<String>,
+    /// Same format as `[[detectors]]` in the config file
+    #[serde(default)]
+    pub detectors: Vec<DetectorConfig>,
+}
+
+impl RulePack {
+    fn parse(text: &str) -> Result<Self> {
+        let pack: RulePack =
+            toml::from_str(text).map_err(|e| Error::RulePack(format!("invalid pack: {}", e)))?;
+        let valid_name = !pack.name.is_empty()
+            && !pack.name.starts_with('.')
+            && pack
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        if !valid_name {
+            return Err(Error::RulePack(format!(
+                "invalid pack name '{}': use letters, digits, '-', '_' and '.'",
+                pack.name
+            )));
+        }
+        if pack.version.trim().is_empty() {
+            return Err(Error::RulePack(format!(
+                "pack '{}' has no version",
+                pack.name
+            )));
+        }
+        // Reject bad patterns at install time, not at the next scan
+        pack.custom_detectors()?;
+        Ok(pack)
+    }
+
+    /// Compiled detectors
+    pub fn custom_detectors(&self) -> Result<Vec<CustomDetector>> {
+        config::compile_detectors(&format!("rule pack '{}'", self.name), &self.detectors)
+    }
+
+    pub fn version(&self) -> RulePackVersion {
+        RulePackVersion {
+            name: self.name.clone(),
+            version: self.version.clone(),
+        }
+    }
+}
+
+/// Outcome of `rules update`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UpdateOutcome {
+    /// Installed; `previous` is the version it replaced
+    Installed { previous: Option<String> },
+    /// The same version was already installed
+    Unchanged,
+}
+
+/// Where installed rule packs live (~/.ert-manifest/rules)
+pub fn default_rules_dir() -> PathBuf {
+    paths::app_dir().join("rules")
+}
+
+/// Rule packs installed in `dir`, by name. A missing directory has none.
+pub fn installed(dir: &Path) -> Result<Vec<RulePack>> {
+    let entries = match std::fs::read_dir(paths::for_fs_access(dir)) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut packs = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if !path.is_file() || paths::extension_lossy(&path) != PACK_EXTENSION {
+            continue;
+        }
+        let text = std::fs::read_to_string(&path)?;
+        let pack = RulePack::parse(&text)
+            .map_err(|e| Error::RulePack(format!("{}: {}", paths::display_path(&path), e)))?;
+        packs.push(pack);
+    }
+    packs.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(packs)
+}
+
+/// Fetch a pack and its signature (default: `<source>.sig`) from a file or
+/// an http(s) URL, verify it against the trusted keys and install it in `dir`
+pub fn update(
+    source: &str,
+    signature: Option<&str>,
+    trusted_keys: &[String],
+    dir: &Path,
+    force: bool,
+) -> Result<(RulePack, UpdateOutcome)> {
+    let signature_source = match signature {
+        Some(s) => s.to_string(),
+        None => format!("{}{}", source, SIGNATURE_SUFFIX),
+    };
+    let text = fetch(source)?;
+    let signature = fetch(&signature_source)?;
+    install(&text, &signature, trusted_keys, dir, force)
+}
+
+/// Verify and install pack text. An older version than the installed one is
+/// refused unless `force` is set.
+fn install(
+    text: &[u8],
+    signature: &[u8],
+    trusted_keys: &[String],
+    dir: &Path,
+    force: bool,
+) -> Result<(RulePack, UpdateOutcome)> {
+    verify(text, signature, trusted_keys)?;
+    let pack = RulePack::parse(
+        std::str::from_utf8(text)
+            .map_err(|_| Error::RulePack("pack is not valid UTF-8".to_string()))?,
+    )?;
+
+    let previous = installed(dir)?
+        .into_iter()
+        .find(|p| p.name == pack.name)
+        .map(|p| p.version);
+    if let Some(ref previous) = previous {
+        match compare_versions(&pack.version, previous) {
+            Ordering::Equal if !force => return Ok((pack, UpdateOutcome::Unchanged)),
+            Ordering::Less if !force => {
+                return Err(Error::RulePack(format!(
+                    "'{}' {} is older than the installed {} (use --force to downgrade)",
+                    pack.name, pack.version, previous
+                )))
+            }
+            _ => {}
+        }
+    }
+
+    std::fs::create_dir_all(paths::for_fs_access(dir))?;
+    let path = dir.join(format!("{}.{}", pack.name, PACK_EXTENSION));
+    // Signature first: an installed pack always has the signature it was verified with
+    write_atomic(
+        &paths::for_fs_access(&paths::append_extension(&path, SIGNATURE_SUFFIX)),
+        signature,
+    )?;
+    write_atomic(&paths::for_fs_access(&path), text)?;
+    Ok((pack, UpdateOutcome::Installed { previous }))
+}
+
+fn fetch(source: &str) -> Result<Vec<u8>> {
+    let failed = |problem: String| Error::RulePack(format!("{}: {}", source, problem));
+
+    let mut bytes = Vec::new();
+    if source.starts_with("https://") || source.starts_with("http://") {
+        let response = ureq::get(source)
+            .call()
+            .map_err(|e| failed(e.to_string()))?;
+        response
+            .into_reader()
+            .take(MAX_DOWNLOAD_BYTES + 1)
+            .read_to_end(&mut bytes)
+            .map_err(|e| failed(e.to_string()))?;
+    } else {
+        std::fs::File::open(paths::for_fs_access(Path::new(source)))
+            .and_then(|f| f.take(MAX_DOWNLOAD_BYTES + 1).read_to_end(&mut bytes))
+            .map_err(|e| failed(e.to_string()))?;
+    }
+    if bytes.len() as u64 > MAX_DOWNLOAD_BYTES {
+        return Err(failed("larger than 4 MiB".to_string()));
+    }
+    Ok(bytes)
+}
+
+/// Check a detached Ed25519 signature (64 raw bytes, or 128 hex digits)
+/// against the trusted public keys (64 hex digits each)
+fn verify(text: &[u8], signature: &[u8], trusted_keys: &[String]) -> Result<()> {
+    if trusted_keys.is_empty() {
+        return Err(Error::RulePack(
+            "no trusted keys: add the privacy office's public key to trusted_keys under [rules] \
+             in the config file"
+                .to_string(),
+        ));
+    }
+
+    let signature: [u8; 64] = if signature.len() == 64 {
+        signature.try_into().expect("length checked")
+    } else {
+        std::str::from_utf8(signature)
+            .ok()
+            .and_then(|s| decode_hex(s.trim()))
+            .and_then(|b| b.try_into().ok())
+            .ok_or_else(|| Error::RulePack("malformed signature".to_string()))?
+    };
+    let signature = Signature::from_bytes(&signature);
+
+    for key in trusted_keys {
+        let key = decode_hex(key.trim())
+            .and_then(|b| <[u8; 32]>::try_from(b).ok())
+            .and_then(|b| VerifyingKey::from_bytes(&b).ok())
+            .ok_or_else(|| Error::Config(format!("invalid trusted key '{}'", key)))?;
+        if key.verify_strict(text, &signature).is_ok() {
+            return Ok(());
+        }
+    }
+    Err(Error::RulePack(
+        "signature does not match any trusted key".to_string(),
+    ))
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    let digits: Vec<u32> = text
+        .chars()
+        .map(|c| c.to_digit(16))
+        .collect::<Option<_>>()?;
+    let pairs = digits.chunks_exact(2);
+    if !pairs.remainder().is_empty() {
+        return None;
+    }
+    Some(pairs.map(|p| (p[0] * 16 + p[1]) as u8).collect())
+}
+
+/// Compare dotted versions part by part, numerically where both parts are numbers
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parts = |v: &str| -> Vec<String> { v.split(['.', '-']).map(str::to_string).collect() };
+    let (a, b) = (parts(a), parts(b));
+    for (x, y) in a.iter().zip(&b) {
+        let order = match (x.parse::<u64>(), y.parse::<u64>()) {
+            (Ok(x), Ok(y)) => x.cmp(&y),
+            _ => x.cmp(y),
+        };
+        if order != Ordering::Equal {
+            return order;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+/// Rule packs active in this process, recorded in every manifest
+static ACTIVE_PACKS: OnceCell<Vec<RulePackVersion>> = OnceCell::new();
+
+/// Record the packs whose detectors were registered. Only the first call takes effect.
+pub fn register_active(packs: Vec<RulePackVersion>) {
+    let _ = ACTIVE_PACKS.set(packs);
+}
+
+pub fn active() -> Vec<RulePackVersion> {
+    ACTIVE_PACKS.get().cloned().unwrap_or_default()
+}
+
+/// Installed packs as a text table
+pub fn format_table(packs: &[RulePack]) -> String {
+    if packs.is_empty() {
+        return "No rule packs installed.".to_string();
+    }
+    let mut lines = vec![format!(
+        "{:<24} {:<12} {:>9}  DESCRIPTION",
+        "NAME", "VERSION", "DETECTORS"
+    )];
+    for pack in packs {
+        lines.push(format!(
+            "{:<24} {:<12} {:>9}  {}",
+            pack.name,
+            pack.version,
+            pack.detectors.len(),
+            pack.description.as_deref().unwrap_or("")
+        ));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    const PACK_V1: &str = r#"
+name = "hsj-identifiers"
+version = "2024.6.1"
+description = "Hospital identifiers"
+
+[[detectors]]
+name = "hsj_mrn"
+regex = '^HSJ-\d{6}$'
+"#;
+
+    fn key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    fn public_hex(key: &SigningKey) -> String {
+        key.verifying_key()
+            .as_bytes()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    fn sign(key: &SigningKey, text: &str) -> Vec<u8> {
+        key.sign(text.as_bytes()).to_bytes().to_vec()
+    }
+
+    #[test]
+    fn test_install_and_update() {
+        let dir = tempfile::tempdir().unwrap();
+        let office = key(1);
+        let keys = vec![public_hex(&key(9)), public_hex(&office)];
+
+        let (pack, outcome) = install(
+            PACK_V1.as_bytes(),
+            &sign(&office, PACK_V1),
+            &keys,
+            dir.path(),
+            false,
+        )
+        .unwrap();
+        assert_eq!(outcome, UpdateOutcome::Installed { previous: None });
+        assert!(pack.custom_detectors().unwrap()[0]
+            .regex
+            .is_match("HSJ-123456"));
+        assert!(dir.path().join("hsj-identifiers.toml.sig").is_file());
+
+        // Reinstalling the same version is a no-op; hex signatures work too
+        let hex_signature: String = sign(&office, PACK_V1)
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        let (_, outcome) = install(
+            PACK_V1.as_bytes(),
+            hex_signature.as_bytes(),
+            &keys,
+            dir.path(),
+            false,
+        )
+        .unwrap();
+        assert_eq!(outcome, UpdateOutcome::Unchanged);
+
+        let v2 = PACK_V1.replace("2024.6.1", "2024.10.0");
+        let (_, outcome) =
+            install(v2.as_bytes(), &sign(&office, &v2), &keys, dir.path(), false).unwrap();
+        assert_eq!(
+            outcome,
+            UpdateOutcome::Installed {
+                previous: Some("2024.6.1".to_string())
+            }
+        );
+
+        // No silent downgrades
+        let result = install(
+            PACK_V1.as_bytes(),
+            &sign(&office, PACK_V1),
+            &keys,
+            dir.path(),
+            false,
+        );
+        assert!(matches!(result, Err(Error::RulePack(_))));
+        install(
+            PACK_V1.as_bytes(),
+            &sign(&office, PACK_V1),
+            &keys,
+            dir.path(),
+            true,
+        )
+        .unwrap();
+
+        let packs = installed(dir.path()).unwrap();
+        assert_eq!(packs.len(), 1);
+        assert_eq!(
+            packs[0].version(),
+            RulePackVersion {
+                name: "hsj-identifiers".to_string(),
+                version: "2024.6.1".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_rejects_untrusted_packs() {
+        let dir = tempfile::tempdir().unwrap();
+        let office = key(1);
+        let keys = vec![public_hex(&office)];
+        let reject = |text: &str, signature: &[u8], keys: &[String]| {
+            install(text.as_bytes(), signature, keys, dir.path(), false).is_err()
+        };
+
+        assert!(reject(PACK_V1, &sign(&key(2), PACK_V1), &keys));
+        let tampered = PACK_V1.replace("HSJ", "XYZ");
+        assert!(reject(&tampered, &sign(&office, PACK_V1), &keys));
+        assert!(reject(PACK_V1, b"not a signature", &keys));
+        assert!(reject(PACK_V1, &sign(&office, PACK_V1), &[]));
+
+        // Signed but unusable
+        let bad_name = PACK_V1.replace("hsj-identifiers", "../etc/passwd");
+        assert!(reject(&bad_name, &sign(&office, &bad_name), &keys));
+        let bad_regex = PACK_V1.replace(r"\d{6}", "[");
+        assert!(reject(&bad_regex, &sign(&office, &bad_regex), &keys));
+
+        assert!(installed(dir.path()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_fetch_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let office = key(1);
+        let path = dir.path().join("pack.toml");
+        std::fs::write(&path, PACK_V1).unwrap();
+        std::fs::write(
+            paths::append_extension(&path, SIGNATURE_SUFFIX),
+            sign(&office, PACK_V1),
+        )
+        .unwrap();
+
+        let rules_dir = dir.path().join("rules");
+        let (pack, _) = update(
+            path.to_str().unwrap(),
+            None,
+            &[public_hex(&office)],
+            &rules_dir,
+            false,
+        )
+        .unwrap();
+        assert_eq!(pack.name, "hsj-identifiers");
+        assert!(rules_dir.join("hsj-identifiers.toml").is_file());
+        assert!(matches!(
+            fetch(dir.path().join("missing.toml").to_str().unwrap()),
+            Err(Error::RulePack(_))
+        ));
+    }
+
+    #[test]
+    fn test_compare_versions() {
+        assert_eq!(compare_versions("2024.10.0", "2024.9.3"), Ordering::Greater);
+        assert_eq!(compare_versions("1.2", "1.2.0"), Ordering::Less);
+        assert_eq!(compare_versions("1.2.0", "1.2.0"), Ordering::Equal);
+        assert_eq!(compare_versions("1.0-rc1", "1.0-rc2"), Ordering::Less);
+    }
+}
@@ -0,0 +1,99 @@
+use std::borrow::Cow;
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// Unicode bidirectional formatting characters, which can make text render in
+/// a different order than it is stored (e.g. "txt.exe" shown as "exe.txt")
+fn is_bidi_control(c: char) -> bool {
+    matches!(
+        c,
+        '\u{061C}' | '\u{200E}' | '\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}'
+    )
+}
+
+fn needs_sanitizing(c: char) -> bool {
+    c.is_control() || is_bidi_control(c)
+}
+
+/// Make text from a source file safe to show in the manifest, reports and terminals.
+///
+/// ANSI escape sequences and bidi formatting characters are removed, tabs and
+/// line breaks become spaces, and any other control character is dropped.
+pub fn sanitize_text(text: &str) -> Cow<'_, str> {
+    if !text.chars().any(needs_sanitizing) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\u{1b}' => skip_escape_sequence(&mut chars),
+            '\t' | '\n' | '\r' => out.push(' '),
+            c if needs_sanitizing(c) => {}
+            c => out.push(c),
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// Skip the rest of an escape sequence whose ESC has just been read
+fn skip_escape_sequence(chars: &mut Peekable<Chars>) {
+    match chars.next() {
+        // CSI (colors, cursor movement): parameters up to a final byte in @..~
+        Some('[') => {
+            for c in chars.by_ref() {
+                if ('\u{40}'..='\u{7e}').contains(&c) {
+                    break;
+                }
+            }
+        }
+        // OSC (window titles, hyperlinks): up to BEL or ESC \
+        Some(']') => {
+            while let Some(c) = chars.next() {
+                if c == '\u{7}' {
+                    break;
+                }
+                if c == '\u{1b}' {
+                    chars.next_if_eq(&'\\');
+                    break;
+                }
+            }
+        }
+        // Other escapes are two characters long
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clean_text_is_borrowed() {
+        assert!(matches!(sanitize_text("Hôpital Montréal"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_strips_ansi_sequences() {
+        assert_eq!(sanitize_text("\u{1b}[31;1mred\u{1b}[0m"), "red");
+        assert_eq!(
+            sanitize_text("\u{1b}]8;;http://x\u{7}link\u{1b}]8;;\u{1b}\\"),
+            "link"
+        );
+        assert_eq!(sanitize_text("a\u{1b}cb"), "ab");
+    }
+
+    #[test]
+    fn test_strips_bidi_overrides() {
+        assert_eq!(sanitize_text("patient\u{202E}eman"), "patienteman");
+        assert_eq!(sanitize_text("\u{2066}age\u{2069}"), "age");
+    }
+
+    #[test]
+    fn test_control_characters() {
+        assert_eq!(sanitize_text("line1\r\nline2\tx"), "line1  line2 x");
+        assert_eq!(sanitize_text("nul\u{0}bell\u{7}del\u{7f}"), "nulbelldel");
+        assert_eq!(sanitize_text("c1\u{9b}31m"), "c131m");
+    }
+}
@@ -1,63 +1,292 @@
+use std::cell::RefCell;
 use std::fs::File;
 use std::io::{BufReader, Read};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
+use glob::Pattern;
+use once_cell::sync::Lazy;
 use sha2::{Digest, Sha256};
 
-use crate::readers::create_reader;
-use crate::types::{FileFormat, ManifestSchema, ProcessingOptions, Result};
+use crate::audit::AuditSink;
+use crate::linkage::detect_join_keys;
+use crate::paths;
+use crate::privacy::file_names::find_phi_in_name;
+use crate::privacy::RecodeRegistry;
+use crate::readers::{create_reader, create_sniffed_reader, create_stream_reader, DataReader};
+use crate::sanitize::sanitize_text;
+use crate::stats::HashSalt;
+use crate::types::{
+    CompletenessFailure, FileFormat, ManifestSchema, MultiFileManifest, ProcessingOptions, Result,
+    SheetSchema,
+};
 
-/// Result of schema extraction, including optional recode sidekick content
+/// Result of schema extraction, including the recode mappings for the sidekick
 pub struct ExtractionResult {
     pub manifest: ManifestSchema,
-    pub recode_sidekick: Option<String>,
+    /// Present if any value was recoded
+    pub recodings: Option<RecodeRegistry>,
 }
 
-/// Extract schema from a data file
-pub fn extract_schema(path: &Path, options: ProcessingOptions) -> Result<ExtractionResult> {
+/// Result of a multi-file extraction; recodings are keyed by the input they belong to
+pub struct MultiExtractionResult {
+    pub manifest: MultiFileManifest,
+    pub recodings: Vec<(PathBuf, RecodeRegistry)>,
+}
+
+/// Supported data files directly inside a directory, sorted by name, skipping
+/// file names that match an `ignore` pattern
+pub fn list_data_files(dir: &Path, ignore: &[Pattern]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(paths::for_fs_access(dir))? {
+        let path = entry?.path();
+        let name = paths::file_name_lossy(&path);
+        if path.is_file()
+            && FileFormat::from_extension(&paths::extension_lossy(&path)).is_some()
+            && !ignore.iter().any(|p| p.matches(&name))
+        {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Extract schemas from several related files and detect shared join keys
+pub fn extract_multi_schema(
+    inputs: &[PathBuf],
+    options: ProcessingOptions,
+    audit: &mut dyn AuditSink,
+) -> Result<MultiExtractionResult> {
+    if inputs.is_empty() {
+        return Err(crate::error::Error::InvalidInput(
+            "No supported data files to scan".to_string(),
+        ));
+    }
+
+    let mut files = Vec::new();
+    let mut recodings = Vec::new();
+    for input in inputs {
+        let result = extract_schema(input, options.clone(), audit)?;
+        if let Some(registry) = result.recodings {
+            recodings.push((input.clone(), registry));
+        }
+        files.push(result.manifest);
+    }
+
+    let join_keys = detect_join_keys(&files, &options);
+    let mut manifest = MultiFileManifest::new(files);
+    manifest.join_keys = join_keys;
+    for file in &manifest.files {
+        for warning in &file.warnings {
+            manifest.warnings.push(format!("{}: {}", file.file_name, warning));
+        }
+    }
+
+    Ok(MultiExtractionResult {
+        manifest,
+        recodings,
+    })
+}
+
+/// Extract schema from a data file, reporting privacy decisions to `audit`
+pub fn extract_schema(
+    path: &Path,
+    options: ProcessingOptions,
+    audit: &mut dyn AuditSink,
+) -> Result<ExtractionResult> {
+    extract_schema_as(path, None, options, audit)
+}
+
+/// Name stdin data is reported under (plus the format's extension)
+pub const STDIN_NAME: &str = "stdin";
+
+/// Extract schema from CSV/TSV or fixed-width data read from stdin.
+///
+/// The data is parsed as it arrives and profiled in a single pass; nothing is
+/// written to disk. The file hash, if requested, is computed along the way.
+pub fn extract_stdin(
+    format: FileFormat,
+    options: ProcessingOptions,
+    audit: &mut dyn AuditSink,
+) -> Result<ExtractionResult> {
+    extract_from_reader(Box::new(std::io::stdin().lock()), format, options, audit)
+}
+
+fn extract_from_reader(
+    input: Box<dyn Read>,
+    format: FileFormat,
+    options: ProcessingOptions,
+    audit: &mut dyn AuditSink,
+) -> Result<ExtractionResult> {
+    let extension = match format {
+        FileFormat::Csv => "csv",
+        FileFormat::Tsv => "tsv",
+        FileFormat::FixedWidth => "txt",
+        FileFormat::Excel | FileFormat::Xpt => "",
+    };
+    let name = format!("{}.{}", STDIN_NAME, extension);
+
+    let hasher = Rc::new(RefCell::new(Sha256::new()));
+    let input = HashingReader {
+        inner: input,
+        hasher: Rc::clone(&hasher),
+    };
+    let reader = create_stream_reader(Box::new(input), &name, format)?;
+    let file_hash = move || Ok(format!("{:x}", hasher.take().finalize()));
+
+    extract_with_reader(reader, format, &name, file_hash, options, audit)
+}
+
+/// Passes input through while hashing it, for inputs that cannot be read twice
+struct HashingReader {
+    inner: Box<dyn Read>,
+    hasher: Rc<RefCell<Sha256>>,
+}
+
+impl Read for HashingReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.borrow_mut().update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+/// Extract schema from a data file, reading it as `format` if given instead of
+/// going by the file extension
+pub fn extract_schema_as(
+    path: &Path,
+    format: Option<FileFormat>,
+    options: ProcessingOptions,
+    audit: &mut dyn AuditSink,
+) -> Result<ExtractionResult> {
+    // Long and UNC paths on Windows need the extended-length form to open
+    let path = &paths::for_fs_access(path);
+
     // Determine file format
-    let ext = path
-        .extension()
-        .and_then(|e| e.to_str())
-        .unwrap_or("");
-
-    let format = FileFormat::from_extension(ext).ok_or_else(|| {
-        crate::error::Error::UnsupportedFormat(format!(
-            "Unsupported file extension: .{}",
-            ext
-        ))
-    })?;
-
-    // Get file name
-    let file_name = path
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("unknown")
-        .to_string();
+    let ext = paths::extension_lossy(path);
+
+    // An unknown extension is read as delimited text if the content looks like it
+    let (format, reader) = match format.or_else(|| FileFormat::from_extension(&ext)) {
+        Some(format) => create_reader(path, format)?,
+        None => create_sniffed_reader(path)?.ok_or_else(|| {
+            crate::error::Error::UnsupportedFormat(format!(
+                "Unsupported file extension: .{} (and the content is not delimited text)",
+                ext
+            ))
+        })?,
+    };
+
+    let file_name = paths::file_name_lossy(path);
+    let file_hash = || compute_file_hash(path);
+    extract_with_reader(reader, format, &file_name, file_hash, options, audit)
+}
+
+/// Profile the data behind `reader` into a manifest for the file `file_name`.
+/// `file_hash` is only called if a hash is requested, once the data is read.
+fn extract_with_reader(
+    mut reader: Box<dyn DataReader>,
+    format: FileFormat,
+    file_name: &str,
+    file_hash: impl FnOnce() -> Result<String>,
+    options: ProcessingOptions,
+    audit: &mut dyn AuditSink,
+) -> Result<ExtractionResult> {
+    // Get file name, replaced by a neutral one if it looks like PHI and redaction is on
+    let file_name = sanitize_text(file_name).into_owned();
+    let stem = Path::new(&file_name).file_stem().map(|s| s.to_string_lossy());
+    let file_name_phi = stem.and_then(|stem| find_phi_in_name(&stem, &options.locales));
+    let reported_name = match file_name_phi {
+        Some(_) if options.redact_names => redacted_file_name(&file_name),
+        _ => file_name.clone(),
+    };
+
+    audit.begin_file(&reported_name)?;
 
     // Create manifest
-    let mut manifest = ManifestSchema::new(file_name, format);
+    let mut manifest = ManifestSchema::new(reported_name, format);
     manifest.options = options.clone();
+    manifest.rule_packs = crate::rules::active();
+
+    // Extract sheets with recoding
+    let (sheets, recode_registry) = reader.read_with_recoding(&options, audit)?;
 
     // Compute file hash if requested
     if options.hash_file {
-        manifest.file_hash = Some(compute_file_hash(path)?);
+        manifest.file_hash = Some(file_hash()?);
     }
-
-    // Create reader and extract sheets with recoding
-    let mut reader = create_reader(path)?;
-    let (sheets, recode_registry) = reader.read_with_recoding(&options)?;
     manifest.sheets = sheets;
+    if let Some(pattern) = file_name_phi {
+        manifest.warnings.push(format!(
+            "File name looks like it holds PHI ({}); {}rename the file before sharing",
+            pattern,
+            if options.redact_names { "it is replaced in this manifest, " } else { "" }
+        ));
+    }
+    if !options.sheets.is_empty() || !options.exclude_sheets.is_empty() {
+        if !matches!(format, FileFormat::Excel | FileFormat::Xpt) {
+            manifest.warnings.push(format!(
+                "--sheets and --exclude-sheets apply to workbooks and SAS transport files; \
+                 the whole {} file was scanned",
+                format.name()
+            ));
+        } else if manifest.sheets.is_empty() {
+            manifest.warnings.push(
+                "No sheet matches --sheets/--exclude-sheets; nothing was scanned".to_string(),
+            );
+        }
+    }
+    let sheet_warnings =
+        check_sheet_names(&mut manifest.sheets, &file_name, &manifest.file_name, &options);
+    manifest.warnings.extend(sheet_warnings);
+    manifest.encoding = reader.encoding();
+    manifest.file_details = reader.file_details();
+    manifest.completeness_failures = completeness_failures(&manifest, &options);
+    manifest.validation = manifest
+        .sheets
+        .iter_mut()
+        .flat_map(|sheet| std::mem::take(&mut sheet.validation))
+        .collect();
+    let unmatched = crate::validation::unmatched_rules(&options.validation_rules, &manifest.validation);
+    manifest.validation.extend(unmatched);
+    manifest.warnings.extend(reader.file_warnings(&options));
 
-    // Generate recode sidekick content if any recoding was done
-    let recode_sidekick = if recode_registry.has_recodings() {
-        Some(recode_registry.generate_sidekick_content())
+    // Keep the mappings for the sidekick if any recoding was done
+    let recodings = if recode_registry.has_recodings() {
+        Some(recode_registry)
     } else {
         None
     };
 
+    for (i, set) in options.quasi_identifier_sets.iter().enumerate() {
+        let mut checks = manifest.sheets.iter().flat_map(|s| &s.quasi_identifier_checks);
+        if !checks.any(|c| c.set == Some(i)) {
+            manifest.warnings.push(format!(
+                "Quasi-identifiers {}: no sheet has all of these columns",
+                set.join(", ")
+            ));
+        }
+    }
+    for (i, column) in options.sensitive_columns.iter().enumerate() {
+        let mut checks = manifest
+            .sheets
+            .iter()
+            .flat_map(|s| &s.quasi_identifier_checks)
+            .flat_map(|c| &c.l_diversity);
+        if !checks.any(|d| d.sensitive == i) {
+            manifest.warnings.push(format!(
+                "Sensitive column {}: not measured (no sheet has it alongside a \
+                 quasi-identifier combination)",
+                column
+            ));
+        }
+    }
+
     // Collect global warnings
     for sheet in &manifest.sheets {
+        for warning in &sheet.warnings {
+            manifest.warnings.push(format!("Sheet '{}': {}", sheet.name, warning));
+        }
         for col in &sheet.columns {
             if !col.warnings.is_empty() {
                 for warning in &col.warnings {
@@ -77,10 +306,102 @@ pub fn extract_schema(path: &Path, options: ProcessingOptions) -> Result<Extract
 
     Ok(ExtractionResult {
         manifest,
-        recode_sidekick,
+        recodings,
     })
 }
 
+/// Key for the stand-ins of redacted file names, drawn once per run and never
+/// written out
+static FILE_NAME_SALT: Lazy<HashSalt> = Lazy::new(HashSalt::random);
+
+/// Neutral stand-in for a file name, with the extension kept: a digest keyed
+/// with a per-run secret, so that files stay distinct within a run but the name
+/// cannot be recovered by hashing candidate names
+fn redacted_file_name(file_name: &str) -> String {
+    let token = format!("{:016x}", FILE_NAME_SALT.hash(file_name));
+    match Path::new(file_name).extension() {
+        Some(ext) => format!("file-{}.{}", &token[..8], ext.to_string_lossy()),
+        None => format!("file-{}", &token[..8]),
+    }
+}
+
+/// Warnings for sheet names that look like PHI, renaming them `Sheet N` when
+/// `redact_names` is set. Sheets named after the file (CSV, TSV, ...) take the
+/// file's reported name instead.
+fn check_sheet_names(
+    sheets: &mut [SheetSchema],
+    file_name: &str,
+    reported_name: &str,
+    options: &ProcessingOptions,
+) -> Vec<String> {
+    let mut warnings = Vec::new();
+    for sheet in sheets {
+        let renamed = if sheet.name == file_name {
+            reported_name.to_string()
+        } else if let Some(pattern) = find_phi_in_name(&sheet.name, &options.locales) {
+            warnings.push(format!(
+                "Sheet {} name looks like it holds PHI ({}); {}rename the sheet before sharing",
+                sheet.index + 1,
+                pattern,
+                if options.redact_names { "it is replaced in this manifest, " } else { "" }
+            ));
+            if !options.redact_names {
+                continue;
+            }
+            format!("Sheet {}", sheet.index + 1)
+        } else {
+            continue;
+        };
+        for result in &mut sheet.validation {
+            if let Some(ref mut name) = result.sheet {
+                name.clone_from(&renamed);
+            }
+        }
+        sheet.name = renamed;
+    }
+    warnings
+}
+
+/// Columns that failed their completeness check, and rules that matched no column
+fn completeness_failures(
+    manifest: &ManifestSchema,
+    options: &ProcessingOptions,
+) -> Vec<CompletenessFailure> {
+    let mut failures = Vec::new();
+    let mut matched = vec![false; options.completeness_rules.len()];
+    for sheet in &manifest.sheets {
+        for column in &sheet.columns {
+            let Some(ref check) = column.completeness else {
+                continue;
+            };
+            for &rule in &check.rules {
+                matched[rule] = true;
+            }
+            if !check.passed {
+                failures.push(CompletenessFailure {
+                    column: options.completeness_rules[check.rules[0]].column.clone(),
+                    sheet: Some(sheet.name.clone()),
+                    column_index: Some(column.index),
+                    required_percent: check.required_percent,
+                    complete_percent: Some(check.complete_percent),
+                });
+            }
+        }
+    }
+    for (rule, matched) in options.completeness_rules.iter().zip(matched) {
+        if !matched {
+            failures.push(CompletenessFailure {
+                column: rule.column.clone(),
+                sheet: rule.sheet.clone(),
+                column_index: None,
+                required_percent: rule.min_percent,
+                complete_percent: None,
+            });
+        }
+    }
+    failures
+}
+
 /// Compute SHA-256 hash of a file (streaming to handle large files)
 fn compute_file_hash(path: &Path) -> Result<String> {
     let file = File::open(path)?;
@@ -103,6 +424,9 @@ fn compute_file_hash(path: &Path) -> Result<String> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::audit::NullAuditSink;
+    use crate::privacy::RecodeFormat;
+    use crate::types::{CompletenessRule, SafeValue};
     use std::io::Write;
     use tempfile::NamedTempFile;
 
@@ -116,13 +440,63 @@ mod tests {
         assert_eq!(hash.len(), 64); // SHA-256 produces 64 hex chars
     }
 
+    #[test]
+    fn test_extract_from_reader() {
+        let input: &[u8] = b"id\tarm\n1\tA\n2\tB\n3\tA\n";
+        let options = ProcessingOptions {
+            hash_file: true,
+            ..Default::default()
+        };
+        let result = extract_from_reader(
+            Box::new(input),
+            FileFormat::Tsv,
+            options,
+            &mut NullAuditSink,
+        )
+        .unwrap();
+
+        assert_eq!(result.manifest.file_name, "stdin.tsv");
+        assert_eq!(
+            result.manifest.file_hash.as_deref(),
+            Some(format!("{:x}", Sha256::digest(input)).as_str())
+        );
+        assert_eq!(result.manifest.format, FileFormat::Tsv);
+        assert_eq!(result.manifest.sheets[0].columns.len(), 2);
+
+        assert!(extract_from_reader(
+            Box::new(std::io::empty()),
+            FileFormat::Excel,
+            ProcessingOptions::default(),
+            &mut NullAuditSink
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_extract_schema_as() {
+        let mut file = NamedTempFile::with_suffix(".txt").unwrap();
+        write!(file, "Study notes\nNot a table\n").unwrap();
+        assert!(extract_schema(file.path(), ProcessingOptions::default(), &mut NullAuditSink).is_err());
+
+        let mut file = NamedTempFile::with_suffix(".txt").unwrap();
+        write!(file, "id\tarm\n1\tA\n2\tB\n").unwrap();
+        let result = extract_schema_as(
+            file.path(),
+            Some(FileFormat::Tsv),
+            ProcessingOptions::default(),
+            &mut NullAuditSink,
+        )
+        .unwrap();
+        assert_eq!(result.manifest.sheets[0].columns.len(), 2);
+    }
+
     #[test]
     fn test_extract_schema_csv() {
         let mut file = NamedTempFile::with_suffix(".csv").unwrap();
         write!(file, "col1,col2\n1,a\n2,b\n3,c\n").unwrap();
 
         let options = ProcessingOptions::default();
-        let result = extract_schema(file.path(), options).unwrap();
+        let result = extract_schema(file.path(), options, &mut NullAuditSink).unwrap();
 
         assert_eq!(result.manifest.format, FileFormat::Csv);
         assert_eq!(result.manifest.sheets.len(), 1);
@@ -130,12 +504,127 @@ mod tests {
         assert!(result.manifest.file_hash.is_some());
     }
 
+    #[test]
+    fn test_file_name_with_phi() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Smith_John_labs.csv");
+        std::fs::write(&path, "arm\nA\nB\n").unwrap();
+
+        let result = extract_schema(&path, ProcessingOptions::default(), &mut NullAuditSink);
+        let manifest = result.unwrap().manifest;
+        assert_eq!(manifest.file_name, "Smith_John_labs.csv");
+        assert!(manifest.warnings[0].starts_with("File name looks like it holds PHI (name)"));
+
+        let options = ProcessingOptions {
+            redact_names: true,
+            ..Default::default()
+        };
+        let manifest = extract_schema(&path, options, &mut NullAuditSink).unwrap().manifest;
+        assert!(manifest.file_name.starts_with("file-"));
+        assert!(manifest.file_name.ends_with(".csv"));
+        // Not the unkeyed digest of the name, which a list of names would reveal
+        let unkeyed = format!("{:x}", Sha256::digest(b"Smith_John_labs.csv"));
+        assert_ne!(manifest.file_name, format!("file-{}.csv", &unkeyed[..8]));
+        assert_eq!(redacted_file_name("Smith_John_labs.csv"), manifest.file_name);
+        assert_ne!(redacted_file_name("Smith_Jane_labs.csv"), manifest.file_name);
+        assert_eq!(manifest.sheets[0].name, manifest.file_name);
+        assert!(manifest.warnings[0].contains("it is replaced in this manifest"));
+    }
+
+    #[test]
+    fn test_sheet_names_with_phi() {
+        let mut sheets = vec![
+            SheetSchema::new("Baseline".to_string(), 0),
+            SheetSchema::new("Dr. Tremblay patients".to_string(), 1),
+        ];
+        let warnings =
+            check_sheet_names(&mut sheets, "trial.xlsx", "trial.xlsx", &Default::default());
+        assert_eq!(
+            warnings,
+            vec!["Sheet 2 name looks like it holds PHI (name); rename the sheet before sharing"]
+        );
+        assert_eq!(sheets[1].name, "Dr. Tremblay patients");
+
+        let options = ProcessingOptions {
+            redact_names: true,
+            ..Default::default()
+        };
+        check_sheet_names(&mut sheets, "trial.xlsx", "trial.xlsx", &options);
+        assert_eq!(sheets[0].name, "Baseline");
+        assert_eq!(sheets[1].name, "Sheet 2");
+    }
+
+    #[test]
+    fn test_extract_schema_sniffed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.txt");
+        std::fs::write(&path, "id;arm;dose\n1;A;2,5\n2;B;3,0\n3;A;2,5\n").unwrap();
+
+        let result = extract_schema(&path, ProcessingOptions::default(), &mut NullAuditSink);
+        let manifest = result.unwrap().manifest;
+        assert_eq!(manifest.format, FileFormat::Csv);
+        assert_eq!(manifest.sheets[0].columns.len(), 3);
+        let details = manifest.file_details.unwrap();
+        assert_eq!(details.delimiter.as_deref(), Some(";"));
+
+        let path = dir.path().join("export");
+        std::fs::write(&path, "id\tarm\n1\tA\n2\tB\n").unwrap();
+        let result = extract_schema(&path, ProcessingOptions::default(), &mut NullAuditSink);
+        assert_eq!(result.unwrap().manifest.format, FileFormat::Tsv);
+    }
+
+    #[test]
+    fn test_extract_schema_misleading_extension() {
+        let dir = tempfile::tempdir().unwrap();
+
+        // A workbook saved as .csv
+        let path = dir.path().join("visits.csv");
+        let mut workbook = rust_xlsxwriter::Workbook::new();
+        let sheet = workbook.add_worksheet();
+        sheet.write(0, 0, "arm").unwrap();
+        sheet.write(1, 0, "A").unwrap();
+        workbook.save(&path).unwrap();
+        let manifest = extract_schema(&path, ProcessingOptions::default(), &mut NullAuditSink)
+            .unwrap()
+            .manifest;
+        assert_eq!(manifest.format, FileFormat::Excel);
+        assert_eq!(manifest.sheets[0].columns.len(), 1);
+        assert!(manifest.warnings[0]
+            .starts_with("File content is a zip container (xlsx workbook), not CSV as"));
+
+        // A CSV renamed .xlsx
+        let path = dir.path().join("visits.xlsx");
+        std::fs::write(&path, "id,arm\n1,A\n2,B\n").unwrap();
+        let manifest = extract_schema(&path, ProcessingOptions::default(), &mut NullAuditSink)
+            .unwrap()
+            .manifest;
+        assert_eq!(manifest.format, FileFormat::Csv);
+        assert!(manifest.warnings[0].contains("not an Excel workbook"));
+
+        // Compressed CSV, with and without the extension saying so
+        let mut encoder =
+            flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(b"id,arm\n1,A\n2,B\n").unwrap();
+        let compressed = encoder.finish().unwrap();
+        for (name, warned) in [("visits.csv.gz", false), ("export.csv", true)] {
+            let path = dir.path().join(name);
+            std::fs::write(&path, &compressed).unwrap();
+            let manifest = extract_schema(&path, ProcessingOptions::default(), &mut NullAuditSink)
+                .unwrap()
+                .manifest;
+            assert_eq!(manifest.file_name, name);
+            assert_eq!(manifest.format, FileFormat::Csv);
+            assert_eq!(manifest.sheets[0].columns.len(), 2);
+            assert_eq!(manifest.warnings.iter().any(|w| w.contains("gzip")), warned);
+        }
+    }
+
     #[test]
     fn test_extract_schema_unsupported() {
         let file = NamedTempFile::with_suffix(".xyz").unwrap();
 
         let options = ProcessingOptions::default();
-        let result = extract_schema(file.path(), options);
+        let result = extract_schema(file.path(), options, &mut NullAuditSink);
 
         assert!(result.is_err());
     }
@@ -146,12 +635,102 @@ mod tests {
         write!(file, "site_code,age\nVAN-001,30\nCAL-002,25\nVAN-001,35\n").unwrap();
 
         let options = ProcessingOptions::default();
-        let result = extract_schema(file.path(), options).unwrap();
+        let result = extract_schema(file.path(), options, &mut NullAuditSink).unwrap();
 
         // Check that recode sidekick was generated
-        assert!(result.recode_sidekick.is_some());
-        let sidekick = result.recode_sidekick.unwrap();
+        assert!(result.recodings.is_some());
+        let sidekick = result.recodings.unwrap().sidekick_content(RecodeFormat::Txt);
         assert!(sidekick.contains("Site_A"));
         assert!(sidekick.contains("Site_B"));
     }
+
+    #[test]
+    fn test_extract_multi_schema_from_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut demographics = String::from("study_id,age\n");
+        let mut labs = String::from("Study_ID,value\n");
+        for i in 0..50 {
+            demographics.push_str(&format!("S{:03},{}\n", i, 20 + i));
+            labs.push_str(&format!("S{:03},{}\n", i, i % 7));
+            labs.push_str(&format!("S{:03},{}\n", i, i % 5));
+        }
+        std::fs::write(dir.path().join("demographics.csv"), demographics).unwrap();
+        std::fs::write(dir.path().join("labs.csv"), labs).unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+
+        let inputs = list_data_files(dir.path(), &[]).unwrap();
+        assert_eq!(inputs.len(), 2);
+
+        let ignore = [Pattern::new("labs*").unwrap()];
+        assert_eq!(list_data_files(dir.path(), &ignore).unwrap().len(), 1);
+
+        let result = extract_multi_schema(&inputs, ProcessingOptions::default(), &mut NullAuditSink).unwrap();
+        assert_eq!(result.manifest.files.len(), 2);
+        assert_eq!(result.manifest.join_keys.len(), 1);
+        assert_eq!(result.manifest.join_keys[0].left.file_name, "demographics.csv");
+        assert_eq!(result.manifest.join_keys[0].right.column_index, 0);
+    }
+
+    #[test]
+    fn test_completeness_failures() {
+        let mut file = NamedTempFile::with_suffix(".csv").unwrap();
+        write!(file, "outcome,age\n1,30\n,40\n0,50\n1,60\n").unwrap();
+        let rule = |column: &str, min_percent| CompletenessRule {
+            column: column.to_string(),
+            sheet: None,
+            min_percent,
+        };
+        let options = ProcessingOptions {
+            completeness_rules: vec![rule("outcome", 95.0), rule("age", 100.0), rule("sbp", 50.0)],
+            ..ProcessingOptions::default()
+        };
+        let manifest = extract_schema(file.path(), options, &mut NullAuditSink)
+            .unwrap()
+            .manifest;
+
+        let sheet = manifest.sheets[0].name.clone();
+        assert_eq!(
+            manifest.completeness_failures,
+            vec![
+                CompletenessFailure {
+                    column: "outcome".to_string(),
+                    sheet: Some(sheet),
+                    column_index: Some(0),
+                    required_percent: 95.0,
+                    complete_percent: Some(75.0),
+                },
+                CompletenessFailure {
+                    column: "sbp".to_string(),
+                    sheet: None,
+                    column_index: None,
+                    required_percent: 50.0,
+                    complete_percent: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validation_results() {
+        let mut file = NamedTempFile::with_suffix(".csv").unwrap();
+        write!(file, "admitted,discharged\n2024-01-02,2024-01-05\n2024-02-10,2024-02-01\n").unwrap();
+        let options = ProcessingOptions {
+            validation_rules: vec![
+                "discharged >= admitted".to_string(),
+                "age between 0 and 120".to_string(),
+            ],
+            ..ProcessingOptions::default()
+        };
+        let manifest = extract_schema(file.path(), options, &mut NullAuditSink)
+            .unwrap()
+            .manifest;
+
+        assert_eq!(manifest.validation.len(), 2);
+        assert_eq!(manifest.validation[0].sheet.as_deref(), Some(manifest.sheets[0].name.as_str()));
+        assert_eq!(manifest.validation[0].checked, Some(SafeValue::ShortString("2-5".to_string())));
+        assert_eq!(manifest.validation[0].violations, Some(SafeValue::ShortString("1".to_string())));
+        assert_eq!(manifest.validation[1].rule, "age between 0 and 120");
+        assert!(manifest.validation[1].error.is_some());
+        assert!(manifest.sheets[0].validation.is_empty());
+    }
 }
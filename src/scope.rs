@@ -0,0 +1,223 @@
+use std::path::Path;
+
+use glob::{MatchOptions, Pattern};
+
+use crate::error::Error;
+use crate::paths;
+use crate::types::{ManifestSchema, Result, SafeValue, ScopeFindings, ScopeGap};
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: false,
+    require_literal_separator: false,
+    require_literal_leading_dot: false,
+};
+
+/// One line of a scope file: a variable name, or a glob pattern such as `lab_*`
+#[derive(Debug, Clone)]
+struct ApprovedVariable {
+    text: String,
+    pattern: Pattern,
+    is_glob: bool,
+}
+
+/// The variables approved for sharing under a consent or data-sharing agreement
+#[derive(Debug, Clone)]
+pub struct Scope {
+    file_name: String,
+    variables: Vec<ApprovedVariable>,
+}
+
+impl Scope {
+    /// Read a scope file: one approved variable per line; blank lines and
+    /// lines starting with `#` are ignored
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(paths::for_fs_access(path)).map_err(|e| {
+            Error::InvalidInput(format!("scope {}: {}", paths::display_path(path), e))
+        })?;
+        Self::parse(&paths::file_name_lossy(path), &text).map_err(|problem| {
+            Error::InvalidInput(format!("scope {}: {}", paths::display_path(path), problem))
+        })
+    }
+
+    fn parse(file_name: &str, text: &str) -> std::result::Result<Self, String> {
+        let mut variables = Vec::new();
+        for line in text.lines() {
+            let line = line.trim().trim_start_matches('\u{feff}');
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            // Only `*` and `?` are wildcards, so `weight [kg]` stays a plain name
+            let is_glob = line.contains(['*', '?']);
+            let glob = Pattern::escape(line)
+                .replace("[*]", "*")
+                .replace("[?]", "?");
+            let pattern = Pattern::new(&glob).expect("escaped pattern is valid");
+            variables.push(ApprovedVariable {
+                text: line.to_string(),
+                pattern,
+                is_glob,
+            });
+        }
+        if variables.is_empty() {
+            return Err("no approved variables".to_string());
+        }
+        Ok(Self {
+            file_name: file_name.to_string(),
+            variables,
+        })
+    }
+
+    /// Compare a manifest's columns with the approved variables
+    pub fn check(&self, manifest: &ManifestSchema) -> ScopeFindings {
+        let mut used = vec![false; self.variables.len()];
+        let mut out_of_scope_columns = Vec::new();
+
+        for sheet in &manifest.sheets {
+            for column in &sheet.columns {
+                let approved = match column.name {
+                    SafeValue::ShortString(ref name) => {
+                        let mut approved = false;
+                        for (variable, used) in self.variables.iter().zip(&mut used) {
+                            if variable.pattern.matches_with(name, MATCH_OPTIONS) {
+                                *used = true;
+                                approved = true;
+                            }
+                        }
+                        approved
+                    }
+                    // A suppressed name cannot be shown to be approved
+                    _ => false,
+                };
+                if !approved {
+                    out_of_scope_columns.push(ScopeGap {
+                        sheet: sheet.name.clone(),
+                        column_index: column.index,
+                        name: column.name.clone(),
+                    });
+                }
+            }
+        }
+
+        ScopeFindings {
+            scope: self.file_name.clone(),
+            compliant: out_of_scope_columns.is_empty(),
+            out_of_scope_columns,
+            missing_variables: self
+                .variables
+                .iter()
+                .zip(&used)
+                .filter(|(v, used)| !v.is_glob && !**used)
+                .map(|(v, _)| v.text.clone())
+                .collect(),
+        }
+    }
+}
+
+/// Gap report lines for stderr
+pub fn format_findings(findings: &ScopeFindings) -> Vec<String> {
+    let mut lines = vec![format!(
+        "Scope ({}): {} columns outside the approved scope, {} approved variables missing",
+        findings.scope,
+        findings.out_of_scope_columns.len(),
+        findings.missing_variables.len()
+    )];
+    for gap in &findings.out_of_scope_columns {
+        lines.push(format!(
+            "  not approved: sheet '{}', column {}: {}",
+            gap.sheet,
+            gap.column_index + 1,
+            gap.name
+        ));
+    }
+    for variable in &findings.missing_variables {
+        lines.push(format!("  missing: {}", variable));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ColumnSchema, DType, FileFormat, SheetSchema};
+
+    fn manifest() -> ManifestSchema {
+        let mut sheet = SheetSchema::new("trial.csv".to_string(), 0);
+        for (i, name) in ["study_id", "Age", "lab_hba1c", "lab_ldl", "postal_code"]
+            .into_iter()
+            .enumerate()
+        {
+            sheet.columns.push(ColumnSchema::new(
+                SafeValue::ShortString(name.to_string()),
+                i,
+                DType::String,
+            ));
+        }
+        sheet.columns.push(ColumnSchema::new(
+            SafeValue::Suppressed {
+                reason: "PHI".to_string(),
+            },
+            5,
+            DType::String,
+        ));
+        let mut manifest = ManifestSchema::new("trial.csv".to_string(), FileFormat::Csv);
+        manifest.sheets.push(sheet);
+        manifest
+    }
+
+    #[test]
+    fn test_check_scope() {
+        let scope = Scope::parse(
+            "approved.txt",
+            "# Approved under DSA 2024-017\nstudy_id\nage\nlab_*\nsbp\n\n",
+        )
+        .unwrap();
+        let findings = scope.check(&manifest());
+
+        assert!(!findings.compliant);
+        assert_eq!(findings.scope, "approved.txt");
+        let gaps: Vec<(usize, String)> = findings
+            .out_of_scope_columns
+            .iter()
+            .map(|g| (g.column_index, g.name.to_string()))
+            .collect();
+        assert_eq!(
+            gaps,
+            vec![
+                (4, "postal_code".to_string()),
+                (5, "[suppressed]".to_string())
+            ]
+        );
+        assert_eq!(findings.missing_variables, vec!["sbp"]);
+
+        let lines = format_findings(&findings);
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[3], "  missing: sbp");
+    }
+
+    #[test]
+    fn test_compliant_scope() {
+        let scope = Scope::parse("approved.txt", "*_id\nage\nlab_*\npostal_code\n").unwrap();
+        let mut manifest = manifest();
+        manifest.sheets[0].columns.pop();
+        let findings = scope.check(&manifest);
+        assert!(findings.compliant);
+        assert!(findings.out_of_scope_columns.is_empty());
+        assert!(findings.missing_variables.is_empty());
+    }
+
+    #[test]
+    fn test_empty_scope() {
+        assert!(Scope::parse("empty.txt", "# nothing approved yet\n").is_err());
+    }
+
+    #[test]
+    fn test_only_star_and_question_mark_are_wildcards() {
+        let scope = Scope::parse("units.txt", "weight [kg]\nvisit_?\n").unwrap();
+        let matches =
+            |i: usize, name: &str| scope.variables[i].pattern.matches_with(name, MATCH_OPTIONS);
+        assert!(matches(0, "Weight [KG]"));
+        assert!(!matches(0, "weight k"));
+        assert!(matches(1, "visit_2"));
+        assert!(!matches(1, "visit_10"));
+    }
+}
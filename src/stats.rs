@@ -1,4 +1,13 @@
-use std::collections::HashSet;
+use std::collections::hash_map::{DefaultHasher, RandomState};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::hash::{BuildHasher, Hash, Hasher};
+
+use chrono::NaiveDate;
+use sha2::{Digest, Sha256};
+use zeroize::Zeroize;
+
+use crate::types::{Language, MIN_TRUNCATED_VALUES, MIN_TRUNCATION_LENGTH, TRUNCATION_RATIO};
 
 /// Welford's online algorithm for computing mean and variance in O(1) memory
 #[derive(Debug, Clone)]
@@ -21,8 +30,12 @@ impl WelfordStats {
         }
     }
 
-    /// Add a new value to the running statistics
+    /// Add a new value to the running statistics; NaN and infinities are
+    /// ignored (callers count them as `non_finite_count`)
     pub fn update(&mut self, value: f64) {
+        if !value.is_finite() {
+            return;
+        }
         self.count += 1;
         let delta = value - self.mean;
         self.mean += delta / self.count as f64;
@@ -34,12 +47,15 @@ impl WelfordStats {
         self.max = Some(self.max.map_or(value, |m| m.max(value)));
     }
 
+    #[cfg(test)]
     pub fn count(&self) -> u64 {
         self.count
     }
 
+    /// Mean, if there are values and it is finite (values near `f64::MAX`
+    /// can overflow the running sums)
     pub fn mean(&self) -> Option<f64> {
-        if self.count > 0 {
+        if self.count > 0 && self.mean.is_finite() {
             Some(self.mean)
         } else {
             None
@@ -47,8 +63,9 @@ impl WelfordStats {
     }
 
     pub fn variance(&self) -> Option<f64> {
-        if self.count > 1 {
-            Some(self.m2 / (self.count - 1) as f64)
+        let variance = self.m2 / self.count.saturating_sub(1) as f64;
+        if self.count > 1 && variance.is_finite() {
+            Some(variance)
         } else {
             None
         }
@@ -118,8 +135,12 @@ impl P2Quantile {
         Self::new(0.5)
     }
 
-    /// Add a new observation
+    /// Add a new observation; NaN and infinities are ignored, since they have
+    /// no place among the markers and would poison every estimate after them
     pub fn update(&mut self, x: f64) {
+        if !x.is_finite() {
+            return;
+        }
         self.count += 1;
 
         if !self.initialized {
@@ -169,7 +190,12 @@ impl P2Quantile {
                 if self.q[i - 1] < q_new && q_new < self.q[i + 1] {
                     self.q[i] = q_new;
                 } else {
-                    self.q[i] = self.linear(i, d_sign);
+                    // Heights near f64::MAX can overflow even the linear step;
+                    // the marker then stays where it is, between its neighbours
+                    let q_linear = self.linear(i, d_sign);
+                    if q_linear.is_finite() {
+                        self.q[i] = q_linear;
+                    }
                 }
                 self.n[i] += d_sign;
             }
@@ -178,7 +204,7 @@ impl P2Quantile {
 
     /// Initialize the estimator with the first 5 observations
     fn initialize(&mut self) {
-        self.initial_values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        self.initial_values.sort_by(|a, b| a.total_cmp(b));
         for (i, &v) in self.initial_values.iter().enumerate() {
             self.q[i] = v;
         }
@@ -225,7 +251,7 @@ impl P2Quantile {
             }
             // For fewer than 5 observations, compute exact quantile
             let mut sorted = self.initial_values.clone();
-            sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            sorted.sort_by(|a, b| a.total_cmp(b));
             let idx = ((sorted.len() - 1) as f64 * self.p).round() as usize;
             return Some(sorted[idx]);
         }
@@ -245,8 +271,49 @@ impl Default for P2Quantile {
 pub struct ColumnStatTracker {
     pub welford: WelfordStats,
     pub p2_median: P2Quantile,
+    /// First and third quartiles (P² estimates)
+    pub p2_q1: P2Quantile,
+    pub p2_q3: P2Quantile,
     pub missing_count: u64,
+    /// Missing values by explicit missing code (Stata `.a` to `.z`)
+    pub missing_codes: BTreeMap<String, u64>,
+    /// Cells matching a PHI value pattern, by pattern (`--deep-scan`)
+    pub pattern_hits: BTreeMap<&'static str, u64>,
     pub unique_tracker: CappedUniqueTracker,
+    /// Distinct-value sketch of the raw values, used for cross-file key detection
+    pub sketch: HyperLogLog,
+    /// Sketch of the salted value hashes, kept for the subject ID column only
+    pub subject_sketch: Option<HyperLogLog>,
+    /// Most frequent values; keeps working past the unique-value cap
+    pub top_values: SpaceSaving,
+    /// Earliest and latest date seen (date/datetime columns)
+    pub date_range: Option<(NaiveDate, NaiveDate)>,
+    /// Outlier limits from the first pass, if the column is numeric
+    pub fences: Option<OutlierFences>,
+    /// Values beyond mean ± 3 SD
+    pub outliers_sd: u64,
+    /// Values beyond the quartiles ± 1.5 IQR
+    pub outliers_iqr: u64,
+    /// Fewest and most digits of the integer values, leading zeros included
+    pub digit_range: Option<(usize, usize)>,
+    /// Values shaped like missing-value codes beyond Tukey's fences, with their counts
+    pub sentinel_counts: BTreeMap<i64, u64>,
+    /// Values with hidden whitespace, by kind
+    pub whitespace_anomalies: BTreeMap<&'static str, u64>,
+    /// Number of values of each length in characters (string and free-text columns)
+    pub value_lengths: BTreeMap<usize, u64>,
+    /// Free-text cells by detected language code (`unknown` when none leads)
+    pub text_languages: BTreeMap<&'static str, u64>,
+    /// Words across free-text cells
+    pub text_words: u64,
+    /// PHI value pattern matches across free-text cells
+    pub text_phi_hits: u64,
+    /// Whether values are kept for the unique and frequent value lists
+    pub collect_values: bool,
+    /// Numeric values that are NaN or infinite (`inf`, `1e999`), left out of
+    /// the summary statistics
+    pub non_finite_count: u64,
+    value_count: u64,
 }
 
 impl ColumnStatTracker {
@@ -254,27 +321,161 @@ impl ColumnStatTracker {
         Self {
             welford: WelfordStats::new(),
             p2_median: P2Quantile::median(),
+            p2_q1: P2Quantile::new(0.25),
+            p2_q3: P2Quantile::new(0.75),
             missing_count: 0,
+            missing_codes: BTreeMap::new(),
+            pattern_hits: BTreeMap::new(),
             unique_tracker: CappedUniqueTracker::new(max_unique),
+            sketch: HyperLogLog::default(),
+            subject_sketch: None,
+            top_values: SpaceSaving::default(),
+            date_range: None,
+            fences: None,
+            outliers_sd: 0,
+            outliers_iqr: 0,
+            digit_range: None,
+            sentinel_counts: BTreeMap::new(),
+            whitespace_anomalies: BTreeMap::new(),
+            value_lengths: BTreeMap::new(),
+            text_languages: BTreeMap::new(),
+            text_words: 0,
+            text_phi_hits: 0,
+            collect_values: true,
+            non_finite_count: 0,
+            value_count: 0,
         }
     }
 
+    /// Tracker that keeps no values, only counts and summary statistics
+    pub fn without_values(mut self) -> Self {
+        self.collect_values = false;
+        self
+    }
+
     pub fn update_numeric(&mut self, value: f64, raw_value: &str) {
+        if !value.is_finite() {
+            self.non_finite_count += 1;
+            self.update_string(raw_value);
+            return;
+        }
+        self.value_count += 1;
+        if let Some(fences) = &self.fences {
+            if value < fences.sd.0 || value > fences.sd.1 {
+                self.outliers_sd += 1;
+            }
+            if value < fences.iqr.0 || value > fences.iqr.1 {
+                self.outliers_iqr += 1;
+                if let Some(code) = sentinel_code(value) {
+                    *self.sentinel_counts.entry(code).or_insert(0) += 1;
+                }
+            }
+        }
         self.welford.update(value);
         self.p2_median.update(value);
-        self.unique_tracker.add(raw_value);
+        self.p2_q1.update(value);
+        self.p2_q3.update(value);
+        if self.collect_values {
+            self.unique_tracker.add(raw_value);
+            self.top_values.add(raw_value);
+        }
     }
 
     pub fn update_string(&mut self, value: &str) {
-        self.unique_tracker.add(value);
+        self.value_count += 1;
+        if self.collect_values {
+            self.unique_tracker.add(value);
+            self.top_values.add(value);
+        }
+    }
+
+    /// Record a parsed date; the value itself is counted via `update_string`
+    pub fn update_date(&mut self, date: NaiveDate) {
+        self.date_range = Some(match self.date_range {
+            Some((earliest, latest)) => (earliest.min(date), latest.max(date)),
+            None => (date, date),
+        });
+    }
+
+    /// Record the length of an integer as written (`-0042` has 4 digits)
+    pub fn update_digits(&mut self, raw_value: &str) {
+        let digits = raw_value.bytes().filter(u8::is_ascii_digit).count();
+        self.digit_range = Some(match self.digit_range {
+            Some((fewest, most)) => (fewest.min(digits), most.max(digits)),
+            None => (digits, digits),
+        });
+    }
+
+    /// Count the kinds of whitespace a value hides: spaces around it, non-breaking
+    /// spaces, and zero-width characters (including a stray byte order mark)
+    pub fn update_whitespace(&mut self, value: &str) {
+        let mut kinds = Vec::new();
+        if value.starts_with(char::is_whitespace) || value.ends_with(char::is_whitespace) {
+            kinds.push("leading_trailing_whitespace");
+        }
+        if value.contains(['\u{00A0}', '\u{2007}', '\u{202F}']) {
+            kinds.push("non_breaking_space");
+        }
+        if value.contains(['\u{200B}', '\u{200C}', '\u{200D}', '\u{2060}', '\u{FEFF}']) {
+            kinds.push("zero_width");
+        }
+        for kind in kinds {
+            *self.whitespace_anomalies.entry(kind).or_insert(0) += 1;
+        }
+    }
+
+    /// Record the length of a text value in characters
+    pub fn update_length(&mut self, value: &str) {
+        *self.value_lengths.entry(value.chars().count()).or_insert(0) += 1;
+    }
+
+    /// Maximum length and the number of values cut off at it, when the column looks
+    /// truncated on export: many values of exactly the maximum length (255, 4000, ...),
+    /// far more than just below it
+    pub fn truncated_values(&self) -> Option<(usize, u64)> {
+        let (&longest, &count) = self.value_lengths.iter().next_back()?;
+        let shorter = longest
+            .checked_sub(1)
+            .and_then(|len| self.value_lengths.get(&len))
+            .copied()
+            .unwrap_or(0);
+        let truncated = longest >= MIN_TRUNCATION_LENGTH
+            && count >= MIN_TRUNCATED_VALUES
+            && count > shorter * TRUNCATION_RATIO
+            && self.value_lengths.len() > 1;
+        truncated.then_some((longest, count))
     }
 
     pub fn update_missing(&mut self) {
         self.missing_count += 1;
     }
 
+    pub fn update_pattern_hit(&mut self, pattern: &'static str) {
+        *self.pattern_hits.entry(pattern).or_insert(0) += 1;
+    }
+
+    /// Count one free-text cell: its language, words and PHI pattern matches
+    pub fn update_text(&mut self, language: Option<Language>, words: usize, phi_hits: usize) {
+        let code = language.map_or("unknown", |l| l.as_str());
+        *self.text_languages.entry(code).or_insert(0) += 1;
+        self.text_words += words as u64;
+        self.text_phi_hits += phi_hits as u64;
+    }
+
+    /// Count a missing value written as an explicit missing code
+    pub fn update_missing_code(&mut self, code: &str) {
+        self.missing_count += 1;
+        *self.missing_codes.entry(code.to_string()).or_insert(0) += 1;
+    }
+
+    /// Estimated distinct values from the sketch; keeps working past the unique-value cap
+    pub fn approximate_unique_count(&self) -> u64 {
+        self.sketch.estimate().round() as u64
+    }
+
+    /// Number of non-missing values seen (numeric or not)
     pub fn count(&self) -> u64 {
-        self.welford.count()
+        self.value_count
     }
 }
 
@@ -284,6 +485,71 @@ impl Default for ColumnStatTracker {
     }
 }
 
+/// The value as a whole number if it is shaped like a missing-value code: two or more
+/// repeated 7s, 8s or 9s (77, 888, 9999), or the negative of one (-99)
+fn sentinel_code(value: f64) -> Option<i64> {
+    if value.fract() != 0.0 || !(10.0..1e12).contains(&value.abs()) {
+        return None;
+    }
+    let code = value as i64;
+    let digits = code.unsigned_abs().to_string();
+    let first = digits.as_bytes()[0];
+    let repeated = matches!(first, b'7'..=b'9') && digits.bytes().all(|b| b == first);
+    repeated.then_some(code)
+}
+
+/// Limits beyond which a value counts as an outlier
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OutlierFences {
+    /// Mean ± 3 standard deviations
+    pub sd: (f64, f64),
+    /// Tukey's fences: Q1 - 1.5 IQR and Q3 + 1.5 IQR
+    pub iqr: (f64, f64),
+}
+
+/// First-pass estimates of a column's spread, so the second pass can count
+/// outliers without keeping the values
+#[derive(Debug, Clone)]
+pub struct SpreadEstimator {
+    welford: WelfordStats,
+    q1: P2Quantile,
+    q3: P2Quantile,
+}
+
+impl SpreadEstimator {
+    pub fn new() -> Self {
+        Self {
+            welford: WelfordStats::new(),
+            q1: P2Quantile::new(0.25),
+            q3: P2Quantile::new(0.75),
+        }
+    }
+
+    pub fn update(&mut self, value: f64) {
+        self.welford.update(value);
+        self.q1.update(value);
+        self.q3.update(value);
+    }
+
+    /// Outlier limits, once there are enough values to estimate them
+    pub fn fences(&self) -> Option<OutlierFences> {
+        let mean = self.welford.mean()?;
+        let sd = self.welford.std_dev()?;
+        let (q1, q3) = (self.q1.quantile()?, self.q3.quantile()?);
+        let iqr = q3 - q1;
+        Some(OutlierFences {
+            sd: (mean - 3.0 * sd, mean + 3.0 * sd),
+            iqr: (q1 - 1.5 * iqr, q3 + 1.5 * iqr),
+        })
+    }
+}
+
+impl Default for SpreadEstimator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Capped unique value tracker that stops tracking after hitting a limit
 #[derive(Debug, Clone)]
 pub struct CappedUniqueTracker {
@@ -313,8 +579,17 @@ impl CappedUniqueTracker {
 
         if self.values.len() > self.max_values {
             self.high_cardinality = true;
-            self.values.clear();
-            self.value_counts.clear();
+            self.wipe();
+        }
+    }
+
+    /// Overwrite the retained raw values before releasing them
+    fn wipe(&mut self) {
+        for mut value in self.values.drain() {
+            value.zeroize();
+        }
+        for (mut value, _) in self.value_counts.drain() {
+            value.zeroize();
         }
     }
 
@@ -354,6 +629,524 @@ impl Default for CappedUniqueTracker {
     }
 }
 
+impl Drop for CappedUniqueTracker {
+    fn drop(&mut self) {
+        self.wipe();
+    }
+}
+
+/// HyperLogLog distinct-count sketch.
+///
+/// Values are hashed before they touch the registers, so a sketch holds no raw
+/// data; sketches of the same precision can be merged to estimate set overlap.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HyperLogLog {
+    precision: u8,
+    registers: Vec<u8>,
+}
+
+impl HyperLogLog {
+    /// Default precision: 4096 registers, ~1.6% standard error
+    pub const DEFAULT_PRECISION: u8 = 12;
+
+    pub fn new(precision: u8) -> Self {
+        let precision = precision.clamp(4, 16);
+        Self {
+            precision,
+            registers: vec![0; 1 << precision],
+        }
+    }
+
+    pub fn add(&mut self, value: &str) {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        self.add_hash(hasher.finish());
+    }
+
+    /// Add a value by its salted hash, so the registers cannot be checked
+    /// against a list of known values without the salt
+    pub fn add_salted(&mut self, value: &str, salt: &HashSalt) {
+        self.add_hash(salt.hash(value));
+    }
+
+    fn add_hash(&mut self, hash: u64) {
+        let p = self.precision as u32;
+        let index = (hash >> (64 - p)) as usize;
+        // Sentinel bit bounds the rank when the remaining bits are all zero
+        let rest = (hash << p) | (1 << (p - 1));
+        let rank = rest.leading_zeros() as u8 + 1;
+        if rank > self.registers[index] {
+            self.registers[index] = rank;
+        }
+    }
+
+    /// Estimated number of distinct values added
+    pub fn estimate(&self) -> f64 {
+        let m = self.registers.len() as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let sum: f64 = self
+            .registers
+            .iter()
+            .map(|&r| 2f64.powi(-(r as i32)))
+            .sum();
+        let raw = alpha * m * m / sum;
+
+        // Linear counting is more accurate for small cardinalities
+        let zeros = self.registers.iter().filter(|&&r| r == 0).count();
+        if raw <= 2.5 * m && zeros > 0 {
+            m * (m / zeros as f64).ln()
+        } else {
+            raw
+        }
+    }
+
+    /// Fold another sketch into this one (union). Precisions must match.
+    pub fn merge(&mut self, other: &HyperLogLog) {
+        if self.precision != other.precision {
+            return;
+        }
+        for (r, &o) in self.registers.iter_mut().zip(&other.registers) {
+            *r = (*r).max(o);
+        }
+    }
+
+    /// Estimated size of the intersection with another sketch (inclusion-exclusion)
+    pub fn intersection_estimate(&self, other: &HyperLogLog) -> f64 {
+        let mut union = self.clone();
+        union.merge(other);
+        (self.estimate() + other.estimate() - union.estimate()).max(0.0)
+    }
+}
+
+impl Default for HyperLogLog {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_PRECISION)
+    }
+}
+
+/// Random salt for hashing identifiers, drawn once per run and never stored.
+/// Sketches built with the same salt can be merged; a new run gets a new salt.
+#[derive(Clone)]
+pub struct HashSalt([u8; 16]);
+
+impl HashSalt {
+    pub fn random() -> Self {
+        // Each RandomState is keyed from the OS random source
+        let mut salt = [0; 16];
+        for half in salt.chunks_mut(8) {
+            let key = RandomState::new().build_hasher().finish();
+            half.copy_from_slice(&key.to_le_bytes());
+        }
+        Self(salt)
+    }
+
+    pub fn hash(&self, value: &str) -> u64 {
+        let mut hasher = Sha256::new();
+        hasher.update(self.0);
+        hasher.update(value.as_bytes());
+        u64::from_le_bytes(hasher.finalize()[..8].try_into().expect("8 bytes"))
+    }
+}
+
+impl Default for HashSalt {
+    fn default() -> Self {
+        Self::random()
+    }
+}
+
+impl fmt::Debug for HashSalt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("HashSalt(..)")
+    }
+}
+
+impl Drop for HashSalt {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+/// Space-saving top-K tracker (Metwally et al., 2005).
+///
+/// Keeps a fixed number of counters; when a new value arrives and all are in
+/// use, the smallest counter is reassigned to it. Each counter's `error` bounds
+/// how much its count may be overestimated, so `count - error` is a guaranteed
+/// lower bound on the true frequency.
+#[derive(Debug, Clone)]
+pub struct SpaceSaving {
+    capacity: usize,
+    counters: HashMap<String, (u64, u64)>,
+}
+
+/// A frequent value reported by [`SpaceSaving`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeavyHitter {
+    pub value: String,
+    /// Estimated count (never an underestimate)
+    pub count: u64,
+    /// Maximum overestimation of `count`
+    pub error: u64,
+}
+
+impl HeavyHitter {
+    /// Count the value is guaranteed to have reached
+    pub fn guaranteed_count(&self) -> u64 {
+        self.count - self.error
+    }
+}
+
+impl SpaceSaving {
+    /// Default number of counters
+    pub const DEFAULT_CAPACITY: usize = 64;
+
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            counters: HashMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, value: &str) {
+        if let Some((count, _)) = self.counters.get_mut(value) {
+            *count += 1;
+            return;
+        }
+
+        if self.counters.len() < self.capacity {
+            self.counters.insert(value.to_string(), (1, 0));
+            return;
+        }
+
+        // Evict the smallest counter and let the new value inherit its count
+        let (mut evicted, min_count) = self
+            .counters
+            .iter()
+            .min_by(|a, b| a.1 .0.cmp(&b.1 .0).then_with(|| b.0.cmp(a.0)))
+            .map(|(v, (c, _))| (v.clone(), *c))
+            .expect("capacity is at least 1");
+        if let Some((mut key, _)) = self.counters.remove_entry(&evicted) {
+            key.zeroize();
+        }
+        evicted.zeroize();
+        self.counters
+            .insert(value.to_string(), (min_count + 1, min_count));
+    }
+
+    /// Up to `n` most frequent values, highest estimated count first
+    pub fn top(&self, n: usize) -> Vec<HeavyHitter> {
+        let mut hitters: Vec<HeavyHitter> = self
+            .counters
+            .iter()
+            .map(|(value, &(count, error))| HeavyHitter {
+                value: value.clone(),
+                count,
+                error,
+            })
+            .collect();
+        hitters.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+        hitters.truncate(n);
+        hitters
+    }
+}
+
+impl Default for SpaceSaving {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CAPACITY)
+    }
+}
+
+impl Drop for SpaceSaving {
+    fn drop(&mut self) {
+        for (mut value, _) in self.counters.drain() {
+            value.zeroize();
+        }
+    }
+}
+
+/// Counts exact duplicate rows over two passes of a table.
+///
+/// The first pass adds a SHA-256 digest of every row to a Bloom filter; rows
+/// the filter may already have seen become candidates. The second pass counts
+/// candidate rows exactly, so filter false positives never inflate the result.
+/// Only digests are kept, never row contents.
+pub struct DuplicateRowDetector {
+    bloom: Vec<u64>,
+    candidates: HashMap<[u8; 32], u64>,
+}
+
+impl DuplicateRowDetector {
+    /// Bloom filter size in bits (1 MiB)
+    pub const BLOOM_BITS: usize = 1 << 23;
+
+    /// Bit positions set per row
+    const BLOOM_HASHES: u64 = 4;
+
+    pub fn new() -> Self {
+        Self {
+            bloom: vec![0; Self::BLOOM_BITS / 64],
+            candidates: HashMap::new(),
+        }
+    }
+
+    fn digest(record: &[String]) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        for field in record {
+            // Length prefix keeps ["ab", "c"] and ["a", "bc"] apart
+            hasher.update((field.len() as u64).to_le_bytes());
+            hasher.update(field.as_bytes());
+        }
+        hasher.finalize().into()
+    }
+
+    /// First pass: note rows that may have been seen before
+    pub fn observe_first_pass(&mut self, record: &[String]) {
+        let digest = Self::digest(record);
+        if self.mark(&digest) {
+            self.candidates.insert(digest, 0);
+        }
+    }
+
+    /// Rows the first pass never saw (input read only once): a row the filter
+    /// may have seen is counted as a repeat straight away, so a false positive
+    /// can count one duplicate too many
+    pub fn observe_single_pass(&mut self, record: &[String]) {
+        let digest = Self::digest(record);
+        if self.mark(&digest) {
+            *self.candidates.entry(digest).or_insert(1) += 1;
+        }
+    }
+
+    /// Add a row digest to the Bloom filter; whether it may have been added before
+    fn mark(&mut self, digest: &[u8; 32]) -> bool {
+        let h1 = u64::from_le_bytes(digest[0..8].try_into().expect("8 bytes"));
+        let h2 = u64::from_le_bytes(digest[8..16].try_into().expect("8 bytes"));
+
+        let mut seen = true;
+        for i in 0..Self::BLOOM_HASHES {
+            let bit = (h1.wrapping_add(i.wrapping_mul(h2)) % Self::BLOOM_BITS as u64) as usize;
+            let (word, mask) = (bit / 64, 1u64 << (bit % 64));
+            if self.bloom[word] & mask == 0 {
+                seen = false;
+                self.bloom[word] |= mask;
+            }
+        }
+        seen
+    }
+
+    /// Second pass: count occurrences of candidate rows
+    pub fn observe_second_pass(&mut self, record: &[String]) {
+        if self.candidates.is_empty() {
+            return;
+        }
+        if let Some(count) = self.candidates.get_mut(&Self::digest(record)) {
+            *count += 1;
+        }
+    }
+
+    /// Rows that repeat an earlier row exactly
+    pub fn duplicate_rows(&self) -> u64 {
+        self.candidates.values().map(|&c| c.saturating_sub(1)).sum()
+    }
+}
+
+impl Default for DuplicateRowDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Equivalence classes of a combination of quasi-identifier columns: rows with
+/// the same values in all of them. Rows are grouped by a digest of those values,
+/// so the values themselves are never kept.
+#[derive(Debug, Clone)]
+pub struct EquivalenceClasses {
+    columns: Vec<usize>,
+    /// Sensitive columns whose diversity within each class is measured
+    sensitive: Vec<usize>,
+    /// Distinct sensitive values kept per class, enough to tell whether it reaches l
+    max_distinct: usize,
+    classes: HashMap<[u8; 16], EquivalenceClass>,
+    records: u64,
+}
+
+/// Rows of one equivalence class, and hashes of the first distinct values of
+/// each sensitive column seen in it
+#[derive(Debug, Clone)]
+struct EquivalenceClass {
+    rows: u64,
+    sensitive: Vec<Vec<u64>>,
+}
+
+impl EquivalenceClasses {
+    pub fn new(columns: Vec<usize>) -> Self {
+        Self {
+            columns,
+            sensitive: Vec::new(),
+            max_distinct: 0,
+            classes: HashMap::new(),
+            records: 0,
+        }
+    }
+
+    /// Also measure how many distinct values of each sensitive column the
+    /// classes hold, up to `l`
+    pub fn with_sensitive(mut self, sensitive: Vec<usize>, l: u64) -> Self {
+        self.sensitive = sensitive;
+        self.max_distinct = usize::try_from(l).unwrap_or(usize::MAX);
+        self
+    }
+
+    pub fn columns(&self) -> &[usize] {
+        &self.columns
+    }
+
+    pub fn sensitive_columns(&self) -> &[usize] {
+        &self.sensitive
+    }
+
+    pub fn observe(&mut self, record: &[String]) {
+        let mut hasher = Sha256::new();
+        for &col in &self.columns {
+            let field = record.get(col).map_or("", |f| f.trim());
+            hasher.update((field.len() as u64).to_le_bytes());
+            hasher.update(field.as_bytes());
+        }
+        let digest = hasher.finalize();
+        let key: [u8; 16] = digest[..16].try_into().expect("16 bytes");
+        let sensitive_count = self.sensitive.len();
+        let class = self.classes.entry(key).or_insert_with(|| EquivalenceClass {
+            rows: 0,
+            sensitive: vec![Vec::new(); sensitive_count],
+        });
+        class.rows += 1;
+        for (seen, &col) in class.sensitive.iter_mut().zip(&self.sensitive) {
+            if seen.len() >= self.max_distinct {
+                continue;
+            }
+            let mut hasher = DefaultHasher::new();
+            record.get(col).map_or("", |f| f.trim()).hash(&mut hasher);
+            let value = hasher.finish();
+            if !seen.contains(&value) {
+                seen.push(value);
+            }
+        }
+        self.records += 1;
+    }
+
+    pub fn records(&self) -> u64 {
+        self.records
+    }
+
+    /// Number of distinct value combinations
+    pub fn class_count(&self) -> u64 {
+        self.classes.len() as u64
+    }
+
+    pub fn min_class_size(&self) -> Option<u64> {
+        self.classes.values().map(|c| c.rows).min()
+    }
+
+    /// Rows in classes smaller than `k`
+    pub fn records_below(&self, k: u64) -> u64 {
+        self.classes.values().map(|c| c.rows).filter(|&n| n < k).sum()
+    }
+
+    /// Classes holding fewer than `l` distinct values of the `i`th sensitive
+    /// column, and the rows in them
+    pub fn below_diversity(&self, i: usize, l: u64) -> (u64, u64) {
+        self.classes
+            .values()
+            .filter(|c| (c.sensitive[i].len() as u64) < l)
+            .fold((0, 0), |(classes, rows), c| (classes + 1, rows + c.rows))
+    }
+}
+
+/// Running co-moments of one pair of columns, over rows where both have a value
+#[derive(Debug, Clone, Copy, Default)]
+struct PairMoments {
+    count: u64,
+    mean_x: f64,
+    mean_y: f64,
+    m2_x: f64,
+    m2_y: f64,
+    c_xy: f64,
+}
+
+impl PairMoments {
+    fn update(&mut self, x: f64, y: f64) {
+        self.count += 1;
+        let n = self.count as f64;
+        let dx = x - self.mean_x;
+        self.mean_x += dx / n;
+        let dy = y - self.mean_y;
+        self.mean_y += dy / n;
+        self.m2_x += dx * (x - self.mean_x);
+        self.m2_y += dy * (y - self.mean_y);
+        self.c_xy += dx * (y - self.mean_y);
+    }
+
+    fn pearson(&self) -> Option<f64> {
+        if self.count < 2 || self.m2_x <= 0.0 || self.m2_y <= 0.0 {
+            return None;
+        }
+        Some((self.c_xy / (self.m2_x * self.m2_y).sqrt()).clamp(-1.0, 1.0))
+    }
+}
+
+/// Streaming Pearson correlations between every pair of a set of columns.
+///
+/// Each pair keeps its own online covariance, so a row missing a value in one
+/// column still counts for the other pairs.
+#[derive(Debug, Clone)]
+pub struct PairwiseCorrelation {
+    columns: usize,
+    /// Upper triangle, row by row: (0,1), (0,2), ..., (1,2), ...
+    pairs: Vec<PairMoments>,
+}
+
+impl PairwiseCorrelation {
+    pub fn new(columns: usize) -> Self {
+        Self {
+            columns,
+            pairs: vec![PairMoments::default(); columns * columns.saturating_sub(1) / 2],
+        }
+    }
+
+    /// Add one row; `values[i]` is the value of column `i`, if it has one
+    pub fn update(&mut self, values: &[Option<f64>]) {
+        let mut pair = 0;
+        for i in 0..self.columns {
+            let Some(x) = values[i] else {
+                pair += self.columns - i - 1;
+                continue;
+            };
+            for y in &values[i + 1..self.columns] {
+                if let Some(y) = y {
+                    self.pairs[pair].update(x, *y);
+                }
+                pair += 1;
+            }
+        }
+    }
+
+    /// Correlation of every pair `(i, j)` with `i < j` that has one, with the
+    /// number of rows it is based on
+    pub fn correlations(&self) -> Vec<(usize, usize, f64, u64)> {
+        let mut result = Vec::new();
+        let mut pair = 0;
+        for i in 0..self.columns {
+            for j in i + 1..self.columns {
+                let moments = &self.pairs[pair];
+                if let Some(r) = moments.pearson() {
+                    result.push((i, j, r, moments.count));
+                }
+                pair += 1;
+            }
+        }
+        result
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -479,4 +1272,389 @@ mod tests {
         assert_eq!(tracker.missing_count, 1);
         assert!((tracker.welford.mean().unwrap() - 2.0).abs() < 1e-10);
     }
+
+    #[test]
+    fn test_column_stat_tracker_counts_strings() {
+        let mut tracker = ColumnStatTracker::new(100);
+        tracker.update_string("a");
+        tracker.update_string("b");
+        tracker.update_missing();
+        tracker.update_missing_code(".a");
+        tracker.update_missing_code(".a");
+
+        assert_eq!(tracker.count(), 2);
+        assert_eq!(tracker.welford.count(), 0);
+        assert_eq!(tracker.missing_count, 3);
+        assert_eq!(tracker.missing_codes.get(".a"), Some(&2));
+    }
+
+    #[test]
+    fn test_column_stat_tracker_date_range() {
+        let mut tracker = ColumnStatTracker::new(100);
+        assert_eq!(tracker.date_range, None);
+
+        let date = |d| NaiveDate::from_ymd_opt(2024, 3, d).unwrap();
+        tracker.update_date(date(10));
+        tracker.update_date(date(2));
+        tracker.update_date(date(25));
+        assert_eq!(tracker.date_range, Some((date(2), date(25))));
+    }
+
+    #[test]
+    fn test_hyperloglog_estimate() {
+        let mut hll = HyperLogLog::default();
+        for i in 0..10_000 {
+            hll.add(&format!("id-{}", i));
+        }
+        // Duplicates don't change the estimate
+        for i in 0..5_000 {
+            hll.add(&format!("id-{}", i));
+        }
+
+        let estimate = hll.estimate();
+        assert!(
+            (estimate - 10_000.0).abs() < 500.0,
+            "Estimate {} should be close to 10000",
+            estimate
+        );
+    }
+
+    #[test]
+    fn test_hyperloglog_small_cardinality() {
+        let mut hll = HyperLogLog::default();
+        for v in ["a", "b", "c", "a"] {
+            hll.add(v);
+        }
+        assert!((hll.estimate() - 3.0).abs() < 0.5);
+        assert_eq!(HyperLogLog::default().estimate(), 0.0);
+    }
+
+    #[test]
+    fn test_hyperloglog_intersection() {
+        let mut a = HyperLogLog::default();
+        let mut b = HyperLogLog::default();
+        for i in 0..4_000 {
+            a.add(&i.to_string());
+        }
+        for i in 2_000..6_000 {
+            b.add(&i.to_string());
+        }
+
+        let overlap = a.intersection_estimate(&b);
+        assert!(
+            (overlap - 2_000.0).abs() < 300.0,
+            "Overlap {} should be close to 2000",
+            overlap
+        );
+    }
+
+    #[test]
+    fn test_hyperloglog_salted_union() {
+        let salt = HashSalt::random();
+        let mut a = HyperLogLog::default();
+        let mut b = HyperLogLog::default();
+        for i in 0..3_000 {
+            a.add_salted(&format!("S{}", i), &salt);
+        }
+        for i in 1_000..5_000 {
+            b.add_salted(&format!("S{}", i), &salt);
+        }
+        a.merge(&b);
+        let union = a.estimate();
+        assert!((union - 5_000.0).abs() < 400.0, "Union {}", union);
+
+        // A different salt scatters the same values into other registers
+        let other = HashSalt::random();
+        assert_ne!(salt.hash("S1"), other.hash("S1"));
+        assert_eq!(salt.hash("S1"), salt.clone().hash("S1"));
+        assert_eq!(format!("{:?}", salt), "HashSalt(..)");
+    }
+
+    #[test]
+    fn test_space_saving_exact_below_capacity() {
+        let mut ss = SpaceSaving::new(10);
+        for v in ["a", "b", "a", "c", "a", "b"] {
+            ss.add(v);
+        }
+
+        let top = ss.top(2);
+        assert_eq!(top[0].value, "a");
+        assert_eq!(top[0].count, 3);
+        assert_eq!(top[0].error, 0);
+        assert_eq!(top[1].value, "b");
+    }
+
+    #[test]
+    fn test_space_saving_skewed_stream() {
+        let mut ss = SpaceSaving::new(16);
+        for i in 0..10_000 {
+            // Every fifth value is "I10"; the rest are all distinct
+            if i % 5 == 0 {
+                ss.add("I10");
+            } else {
+                ss.add(&format!("rare-{}", i));
+            }
+        }
+
+        let top = ss.top(1);
+        assert_eq!(top[0].value, "I10");
+        assert!(top[0].guaranteed_count() <= 2_000);
+        assert!(top[0].count >= 2_000);
+    }
+
+    fn rows(lines: &[&str]) -> Vec<Vec<String>> {
+        lines
+            .iter()
+            .map(|l| l.split(',').map(|c| c.to_string()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_duplicate_rows() {
+        let table = rows(&["1,A", "2,B", "1,A", "3,C", "1,A", "2,B", "1,a", "12,", "1,2"]);
+        let mut detector = DuplicateRowDetector::new();
+        for row in &table {
+            detector.observe_first_pass(row);
+        }
+        for row in &table {
+            detector.observe_second_pass(row);
+        }
+        assert_eq!(detector.duplicate_rows(), 3);
+    }
+
+    #[test]
+    fn test_duplicate_rows_single_pass() {
+        // Two passes over the first four rows, then one over the rest
+        let table = rows(&["1,A", "2,B", "1,A", "3,C", "1,A", "2,B", "1,a", "12,", "1,2"]);
+        let mut detector = DuplicateRowDetector::new();
+        for row in &table[..4] {
+            detector.observe_first_pass(row);
+        }
+        for row in &table[..4] {
+            detector.observe_second_pass(row);
+        }
+        for row in &table[4..] {
+            detector.observe_single_pass(row);
+        }
+        assert_eq!(detector.duplicate_rows(), 3);
+    }
+
+    #[test]
+    fn test_duplicate_rows_none() {
+        let table: Vec<Vec<String>> = (0..50_000).map(|i| vec![i.to_string()]).collect();
+        let mut detector = DuplicateRowDetector::new();
+        for row in &table {
+            detector.observe_first_pass(row);
+        }
+        for row in &table {
+            detector.observe_second_pass(row);
+        }
+        assert_eq!(detector.duplicate_rows(), 0);
+    }
+
+    #[test]
+    fn test_equivalence_classes() {
+        let table = rows(&[
+            "34,M,H3A", "34,M,H3A ", "34,F,H3A", "51,M,K1A", "51,M,K1A", "51,M,K1A",
+        ]);
+        let mut classes = EquivalenceClasses::new(vec![0, 1]);
+        for row in &table {
+            classes.observe(row);
+        }
+        assert_eq!(classes.records(), 6);
+        assert_eq!(classes.class_count(), 3);
+        assert_eq!(classes.min_class_size(), Some(1));
+        assert_eq!(classes.records_below(3), 3);
+        assert_eq!(classes.records_below(2), 1);
+    }
+
+    #[test]
+    fn test_equivalence_class_diversity() {
+        let table = rows(&["34,M,pos", "34,M,pos", "34,M,pos", "51,F,pos", "51,F,neg", "51,F,neg"]);
+        let mut classes = EquivalenceClasses::new(vec![0, 1]).with_sensitive(vec![2], 2);
+        for row in &table {
+            classes.observe(row);
+        }
+        // Everyone aged 34 and male is positive
+        assert_eq!(classes.below_diversity(0, 2), (1, 3));
+        assert_eq!(classes.below_diversity(0, 3), (2, 6));
+    }
+
+    #[test]
+    fn test_pairwise_correlation() {
+        let mut tracker = PairwiseCorrelation::new(3);
+        for i in 0..10 {
+            let x = i as f64;
+            // Column 1 is derived from column 0; column 2 is noise, missing once
+            let noise = [3.0, 1.0, 4.0, 1.0, 5.0, 9.0, 2.0, 6.0, 5.0, 3.0][i];
+            let noise = if i == 4 { None } else { Some(noise) };
+            tracker.update(&[Some(x), Some(2.0 * x + 1.0), noise]);
+        }
+        let correlations = tracker.correlations();
+        assert_eq!(correlations.len(), 3);
+        let (i, j, r, n) = correlations[0];
+        assert_eq!((i, j, n), (0, 1, 10));
+        assert!((r - 1.0).abs() < 1e-12);
+        let (_, _, r, n) = correlations[1];
+        assert_eq!(n, 9);
+        assert!(r.abs() < 0.8);
+
+        // A constant column has no correlation
+        let mut constant = PairwiseCorrelation::new(2);
+        constant.update(&[Some(1.0), Some(5.0)]);
+        constant.update(&[Some(2.0), Some(5.0)]);
+        assert!(constant.correlations().is_empty());
+    }
+
+    #[test]
+    fn test_outlier_counts() {
+        let mut values: Vec<f64> = (0..40).map(|i| 40.0 + (i % 20) as f64).collect();
+        values.push(999.0);
+        let mut spread = SpreadEstimator::new();
+        for &v in &values {
+            spread.update(v);
+        }
+        let mut tracker = ColumnStatTracker {
+            fences: spread.fences(),
+            ..ColumnStatTracker::default()
+        };
+        for &v in &values {
+            tracker.update_numeric(v, &v.to_string());
+        }
+        assert_eq!(tracker.outliers_sd, 1);
+        assert_eq!(tracker.outliers_iqr, 1);
+
+        let mut single = SpreadEstimator::new();
+        single.update(1.0);
+        assert!(single.fences().is_none());
+    }
+
+    #[test]
+    fn test_sentinel_counts() {
+        let mut values: Vec<f64> = (0..40).map(|i| 20.0 + (i % 15) as f64).collect();
+        values.extend([-99.0, -99.0, -99.0, 999.0, 123.0, 30.0]);
+        let mut spread = SpreadEstimator::new();
+        for &v in &values {
+            spread.update(v);
+        }
+        let mut tracker = ColumnStatTracker {
+            fences: spread.fences(),
+            ..ColumnStatTracker::default()
+        };
+        for &v in &values {
+            tracker.update_numeric(v, &v.to_string());
+        }
+        // 123 is an outlier but not shaped like a code
+        assert_eq!(tracker.sentinel_counts, BTreeMap::from([(-99, 3), (999, 1)]));
+
+        assert_eq!(sentinel_code(77.0), Some(77));
+        assert_eq!(sentinel_code(-9999.0), Some(-9999));
+        assert_eq!(sentinel_code(9.0), None);
+        assert_eq!(sentinel_code(99.5), None);
+        assert_eq!(sentinel_code(989.0), None);
+    }
+
+    #[test]
+    fn test_whitespace_anomalies() {
+        let mut tracker = ColumnStatTracker::default();
+        let values = ["ok", " lead", "trail ", "a\u{00A0}b", "\u{00A0}x"];
+        for value in values.into_iter().chain(["z\u{200B}w", "\u{FEFF}id"]) {
+            tracker.update_whitespace(value);
+        }
+        assert_eq!(
+            tracker.whitespace_anomalies,
+            BTreeMap::from([
+                ("leading_trailing_whitespace", 3),
+                ("non_breaking_space", 2),
+                ("zero_width", 2),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_truncated_values() {
+        let mut tracker = ColumnStatTracker::default();
+        for i in 0..40 {
+            tracker.update_length(&"x".repeat(20 + i % 30));
+        }
+        assert_eq!(tracker.truncated_values(), None);
+
+        for _ in 0..12 {
+            tracker.update_length(&"é".repeat(255));
+        }
+        assert_eq!(tracker.truncated_values(), Some((255, 12)));
+
+        // Fixed-width codes are all the same length
+        let mut codes = ColumnStatTracker::default();
+        for _ in 0..40 {
+            codes.update_length("ABCDEFGHIJKLMNOPQRSTUVWXYZ");
+        }
+        assert_eq!(codes.truncated_values(), None);
+    }
+
+    #[test]
+    fn test_welford_ignores_non_finite() {
+        let mut stats = WelfordStats::new();
+        for x in [1.0, f64::NAN, 2.0, f64::INFINITY, 3.0, f64::NEG_INFINITY] {
+            stats.update(x);
+        }
+        assert_eq!(stats.count(), 3);
+        assert_eq!(stats.mean(), Some(2.0));
+        assert_eq!((stats.min(), stats.max()), (Some(1.0), Some(3.0)));
+
+        let mut stats = WelfordStats::new();
+        stats.update(f64::MAX);
+        stats.update(-f64::MAX);
+        // The running sums overflow: no mean rather than an infinite one
+        assert_eq!(stats.mean(), None);
+        assert_eq!(stats.variance(), None);
+    }
+
+    #[test]
+    fn test_tracker_counts_non_finite() {
+        let mut tracker = ColumnStatTracker::new(100);
+        tracker.update_numeric(1.0, "1");
+        tracker.update_numeric(f64::INFINITY, "inf");
+        tracker.update_numeric(f64::INFINITY, "1e999");
+        tracker.update_numeric(3.0, "3");
+        assert_eq!(tracker.non_finite_count, 2);
+        assert_eq!(tracker.count(), 4);
+        assert_eq!(tracker.welford.max(), Some(3.0));
+        assert_eq!(tracker.p2_median.quantile(), Some(3.0));
+        assert!(tracker.unique_tracker.values().unwrap().contains("inf"));
+    }
+
+    #[test]
+    fn test_p2_ignores_non_finite() {
+        let mut p2 = P2Quantile::median();
+        for x in [f64::NAN, 1.0, f64::INFINITY, 2.0, 3.0, f64::NEG_INFINITY, 4.0, 5.0, 6.0] {
+            p2.update(x);
+        }
+        let median = p2.quantile().unwrap();
+        assert!((1.0..=6.0).contains(&median));
+    }
+
+    proptest::proptest! {
+        /// Any stream of values, NaN and infinities included, gives a quantile
+        /// within the range of its finite values
+        #[test]
+        fn prop_p2_within_finite_range(
+            values in proptest::collection::vec(proptest::num::f64::ANY, 0..200),
+            p in 0.0f64..=1.0,
+        ) {
+            let mut p2 = P2Quantile::new(p);
+            for &x in &values {
+                p2.update(x);
+            }
+            let finite: Vec<f64> = values.iter().copied().filter(|x| x.is_finite()).collect();
+            match p2.quantile() {
+                None => proptest::prop_assert!(finite.is_empty()),
+                Some(q) => {
+                    let min = finite.iter().copied().fold(f64::INFINITY, f64::min);
+                    let max = finite.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+                    proptest::prop_assert!(q >= min && q <= max, "{} outside {}..{}", q, min, max);
+                }
+            }
+        }
+    }
 }
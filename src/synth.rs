@@ -0,0 +1,685 @@
+use std::io::Write;
+use std::path::Path;
+
+use chrono::{Datelike, Duration, NaiveDate};
+
+use crate::compat::{self, LoadedManifest};
+use crate::error::Error;
+use crate::output::tabular::escape_formula;
+use crate::paths;
+use crate::types::{
+    Classification, ColumnSchema, Correlation, DType, ManifestSchema, Result, SafeValue,
+};
+
+/// Rows generated when the manifest's row count is unknown
+pub const DEFAULT_SYNTH_ROWS: usize = 100;
+
+/// What to generate from a manifest
+#[derive(Debug, Clone, Default)]
+pub struct SynthOptions {
+    /// Sheet name or 0-based index (first sheet if not given)
+    pub sheet: Option<String>,
+    /// Number of rows (the manifest's row count if not given)
+    pub rows: Option<usize>,
+    /// Random seed; the same seed and manifest always give the same data
+    pub seed: u64,
+    /// Reproduce the correlations the manifest lists between numeric columns
+    pub correlations: bool,
+}
+
+/// Read a single-file JSON manifest to use as a template
+pub fn load_template(path: &Path) -> Result<ManifestSchema> {
+    match compat::load_manifest(path)? {
+        LoadedManifest::Single(manifest) => Ok(*manifest),
+        LoadedManifest::Multi(_) => Err(Error::InvalidInput(format!(
+            "{} is not a single-file JSON manifest",
+            paths::display_path(path)
+        ))),
+    }
+}
+
+/// Write synthetic CSV data with the columns, types and distributions of one
+/// sheet of the manifest.
+///
+/// Only what the manifest itself reports is used, so no value appears that is
+/// not already in the manifest. PHI columns get placeholder tokens.
+pub fn generate(
+    manifest: &ManifestSchema,
+    options: &SynthOptions,
+    out: &mut dyn Write,
+) -> Result<()> {
+    let sheet = match options.sheet.as_deref() {
+        None => manifest.sheets.first(),
+        Some(wanted) => manifest
+            .sheets
+            .iter()
+            .find(|s| s.name == wanted)
+            .or_else(|| {
+                wanted
+                    .parse()
+                    .ok()
+                    .and_then(|i: usize| manifest.sheets.get(i))
+            }),
+    }
+    .ok_or_else(|| {
+        let names: Vec<&str> = manifest.sheets.iter().map(|s| s.name.as_str()).collect();
+        Error::InvalidInput(format!(
+            "no such sheet in the manifest (available: {})",
+            names.join(", ")
+        ))
+    })?;
+
+    let rows = options
+        .rows
+        .or_else(|| estimate_count(&sheet.row_count).map(|n| n.round() as usize))
+        .unwrap_or(DEFAULT_SYNTH_ROWS);
+    let plans: Vec<ColumnPlan> = sheet.columns.iter().map(ColumnPlan::new).collect();
+
+    // Numeric columns drawn together, and the factor that correlates their draws
+    let numbers: Vec<usize> = (0..plans.len())
+        .filter(|&i| options.correlations && matches!(plans[i].generator, Generator::Number(_)))
+        .collect();
+    let factor = correlation_factor(&plans, &numbers, &sheet.correlations);
+
+    let mut rng = SplitMix64::new(options.seed);
+    let mut writer = csv::Writer::from_writer(out);
+    writer.write_record(plans.iter().map(|p| p.name.as_str()))?;
+    for row in 0..rows {
+        let draws: Vec<f64> = numbers.iter().map(|_| rng.normal()).collect();
+        let record: Vec<String> = plans
+            .iter()
+            .enumerate()
+            .map(|(i, plan)| {
+                if rng.next_f64() < plan.missing_rate {
+                    return String::new();
+                }
+                match (numbers.iter().position(|&n| n == i), &plan.generator) {
+                    (Some(k), Generator::Number(number)) => {
+                        let z = factor[k].iter().zip(&draws).map(|(l, d)| l * d).sum();
+                        number.value(z)
+                    }
+                    _ => plan.generator.sample(&mut rng, row),
+                }
+            })
+            .collect();
+        writer.write_record(&record)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Lower-triangular factor `L` of the correlation matrix of the `numbers`
+/// columns, so that `L z` for independent standard normals `z` has those
+/// correlations. Pairs the manifest does not list are taken as uncorrelated.
+/// Listed correlations that are not jointly possible (they are rounded, and
+/// only strong pairs are listed) are shrunk towards zero until they are.
+fn correlation_factor(
+    plans: &[ColumnPlan],
+    numbers: &[usize],
+    correlations: &[Correlation],
+) -> Vec<Vec<f64>> {
+    let n = numbers.len();
+    let position = |name: &str| numbers.iter().position(|&i| plans[i].name == name);
+    let mut listed = vec![vec![0.0; n]; n];
+    for correlation in correlations {
+        let [a, b] = &correlation.columns;
+        if let (Some(a), Some(b)) = (position(a), position(b)) {
+            if a != b {
+                listed[a][b] = correlation.r.clamp(-1.0, 1.0);
+                listed[b][a] = listed[a][b];
+            }
+        }
+    }
+
+    let mut shrink = 1.0;
+    loop {
+        let matrix: Vec<Vec<f64>> = (0..n)
+            .map(|i| (0..n).map(|j| if i == j { 1.0 } else { listed[i][j] * shrink }).collect())
+            .collect();
+        if let Some(factor) = cholesky(&matrix) {
+            return factor;
+        }
+        shrink *= 0.9;
+    }
+}
+
+/// Cholesky factor of a symmetric matrix; `None` unless it is positive definite
+fn cholesky(matrix: &[Vec<f64>]) -> Option<Vec<Vec<f64>>> {
+    let n = matrix.len();
+    let mut factor = vec![vec![0.0; n]; n];
+    for i in 0..n {
+        for j in 0..=i {
+            let sum: f64 = (0..j).map(|k| factor[i][k] * factor[j][k]).sum();
+            if i == j {
+                let pivot = matrix[i][i] - sum;
+                if pivot <= 1e-9 {
+                    return None;
+                }
+                factor[i][j] = pivot.sqrt();
+            } else {
+                factor[i][j] = (matrix[i][j] - sum) / factor[j][j];
+            }
+        }
+    }
+    Some(factor)
+}
+
+/// How one column is generated
+struct ColumnPlan {
+    name: String,
+    missing_rate: f64,
+    generator: Generator,
+}
+
+enum Generator {
+    /// Values drawn with the given cumulative weights
+    Categorical {
+        values: Vec<String>,
+        cumulative: Vec<f64>,
+    },
+    /// Normal around the mean, kept within min/max
+    Number(Normal),
+    /// Uniform over a date range
+    Date {
+        start: NaiveDate,
+        days: i64,
+        with_time: bool,
+    },
+    /// Made-up tokens for columns whose values are not in the manifest
+    Placeholder { prefix: String },
+}
+
+/// A normal distribution clamped to the reported range
+struct Normal {
+    mean: f64,
+    std_dev: f64,
+    min: f64,
+    max: f64,
+    integer: bool,
+}
+
+impl Normal {
+    /// The value `z` standard deviations from the mean
+    fn value(&self, z: f64) -> String {
+        let x = (self.mean + self.std_dev * z).clamp(self.min, self.max);
+        if self.integer {
+            format!("{}", x.round() as i64)
+        } else {
+            format!("{:.2}", x)
+        }
+    }
+}
+
+impl ColumnPlan {
+    fn new(column: &ColumnSchema) -> Self {
+        let name = match column.name {
+            SafeValue::ShortString(ref s) => s.clone(),
+            _ => format!("Column{}", column.index + 1),
+        };
+        let stats = column.stats.clone().unwrap_or_default();
+
+        let present = stats.count.as_ref().and_then(estimate_count);
+        let missing = stats.missing_count.as_ref().and_then(estimate_count);
+        let missing_rate = match (present, missing) {
+            (Some(p), Some(m)) if p + m > 0.0 => m / (p + m),
+            _ => 0.0,
+        };
+
+        let placeholder = Generator::Placeholder {
+            prefix: format!("SYNTH{}", column.index + 1),
+        };
+        let generator = if column.classification == Classification::Phi {
+            placeholder
+        } else {
+            match column.dtype {
+                DType::Integer | DType::Numeric => match stats.mean {
+                    Some(mean) => {
+                        let min = stats.min.as_ref().and_then(as_f64).unwrap_or(f64::MIN);
+                        let max = stats.max.as_ref().and_then(as_f64).unwrap_or(f64::MAX);
+                        Generator::Number(Normal {
+                            mean,
+                            std_dev: stats.std_dev.unwrap_or(0.0),
+                            min,
+                            max,
+                            integer: column.dtype == DType::Integer,
+                        })
+                    }
+                    None => categorical(column).unwrap_or(placeholder),
+                },
+                DType::Date | DType::Datetime => {
+                    let start = stats.earliest.as_ref().and_then(period_bounds);
+                    let end = stats.latest.as_ref().and_then(period_bounds);
+                    match (start, end) {
+                        (Some((start, _)), Some((_, end))) if end >= start => Generator::Date {
+                            start,
+                            days: (end - start).num_days(),
+                            with_time: column.dtype == DType::Datetime,
+                        },
+                        _ => placeholder,
+                    }
+                }
+                DType::Boolean => categorical(column).unwrap_or_else(|| Generator::Categorical {
+                    values: vec!["true".to_string(), "false".to_string()],
+                    cumulative: vec![1.0, 2.0],
+                }),
+                DType::String | DType::FreeText => categorical(column).unwrap_or(placeholder),
+            }
+        };
+
+        Self {
+            name,
+            missing_rate,
+            generator,
+        }
+    }
+}
+
+/// Categories from the value frequencies, unique values or top values
+fn categorical(column: &ColumnSchema) -> Option<Generator> {
+    let stats = column.stats.as_ref();
+    let mut weighted: Vec<(String, f64)> = Vec::new();
+
+    if let Some(frequencies) = stats.and_then(|s| s.value_frequencies.as_ref()) {
+        for (value, count) in frequencies {
+            weighted.push((value.clone(), estimate_count(count).unwrap_or(1.0)));
+        }
+    }
+    if weighted.is_empty() {
+        if let Some(values) = column.unique_values.as_ref() {
+            weighted = values
+                .iter()
+                .filter(|v| !matches!(v, SafeValue::Suppressed { .. }))
+                .map(|v| (v.to_string(), 1.0))
+                .collect();
+        }
+    }
+    if weighted.is_empty() {
+        if let Some(top) = stats.and_then(|s| s.top_values.as_ref()) {
+            weighted = top
+                .iter()
+                .filter(|t| !matches!(t.value, SafeValue::Suppressed { .. }))
+                .map(|t| (t.value.to_string(), estimate_count(&t.count).unwrap_or(1.0)))
+                .collect();
+        }
+    }
+    if weighted.is_empty() {
+        return None;
+    }
+
+    let mut total = 0.0;
+    let mut values = Vec::with_capacity(weighted.len());
+    let mut cumulative = Vec::with_capacity(weighted.len());
+    for (value, weight) in weighted {
+        total += weight.max(1.0);
+        values.push(escape_formula(&value).into_owned());
+        cumulative.push(total);
+    }
+    Some(Generator::Categorical { values, cumulative })
+}
+
+impl Generator {
+    fn sample(&self, rng: &mut SplitMix64, row: usize) -> String {
+        match self {
+            Generator::Categorical { values, cumulative } => {
+                let total = cumulative.last().copied().unwrap_or(0.0);
+                let target = rng.next_f64() * total;
+                let idx = cumulative.partition_point(|&c| c <= target);
+                values[idx.min(values.len() - 1)].clone()
+            }
+            Generator::Number(number) => number.value(rng.normal()),
+            Generator::Date {
+                start,
+                days,
+                with_time,
+            } => {
+                let date = *start + Duration::days(rng.below(*days as u64 + 1) as i64);
+                if *with_time {
+                    let seconds = rng.below(86_400);
+                    format!(
+                        "{} {:02}:{:02}:{:02}",
+                        date.format("%Y-%m-%d"),
+                        seconds / 3600,
+                        seconds / 60 % 60,
+                        seconds % 60
+                    )
+                } else {
+                    date.format("%Y-%m-%d").to_string()
+                }
+            }
+            Generator::Placeholder { prefix } => format!("{}-{:06}", prefix, row + 1),
+        }
+    }
+}
+
+/// A count from the manifest; bucketed counts ("21-100") give their midpoint
+fn estimate_count(value: &SafeValue) -> Option<f64> {
+    match value {
+        SafeValue::Integer(n) => Some(*n as f64),
+        SafeValue::Float(x) => Some(*x),
+        SafeValue::ShortString(s) => {
+            let s = s.trim();
+            if let Some(lower) = s.strip_prefix('>') {
+                return lower.parse().ok();
+            }
+            match s.split_once('-') {
+                Some((lo, hi)) => {
+                    let lo: f64 = lo.parse().ok()?;
+                    let hi: f64 = hi.parse().ok()?;
+                    Some((lo + hi) / 2.0)
+                }
+                None => s.parse().ok(),
+            }
+        }
+        _ => None,
+    }
+}
+
+fn as_f64(value: &SafeValue) -> Option<f64> {
+    match value {
+        SafeValue::Integer(n) => Some(*n as f64),
+        SafeValue::Float(x) => Some(*x),
+        _ => None,
+    }
+}
+
+/// First and last day of a generalized date ("2024", "2024-03" or "2024-03-15")
+fn period_bounds(value: &SafeValue) -> Option<(NaiveDate, NaiveDate)> {
+    let SafeValue::ShortString(s) = value else {
+        return None;
+    };
+    let parts: Vec<&str> = s.split('-').collect();
+    match parts.as_slice() {
+        [y] => {
+            let y: i32 = y.parse().ok()?;
+            Some((
+                NaiveDate::from_ymd_opt(y, 1, 1)?,
+                NaiveDate::from_ymd_opt(y, 12, 31)?,
+            ))
+        }
+        [y, m] => {
+            let first = NaiveDate::from_ymd_opt(y.parse().ok()?, m.parse().ok()?, 1)?;
+            let next = if first.month() == 12 {
+                NaiveDate::from_ymd_opt(first.year() + 1, 1, 1)?
+            } else {
+                NaiveDate::from_ymd_opt(first.year(), first.month() + 1, 1)?
+            };
+            Some((first, next - Duration::days(1)))
+        }
+        [..] => {
+            let date = NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()?;
+            Some((date, date))
+        }
+    }
+}
+
+/// Small seedable generator (SplitMix64), so output is reproducible across
+/// platforms and releases
+struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in [0, 1)
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform in [0, n)
+    fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n.max(1)
+    }
+
+    /// Standard normal (Box-Muller)
+    fn normal(&mut self) -> f64 {
+        let u1 = 1.0 - self.next_f64();
+        let u2 = self.next_f64();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ColumnStats, FileFormat, SheetSchema};
+    use std::collections::BTreeMap;
+
+    fn template() -> ManifestSchema {
+        let mut arm =
+            ColumnSchema::new(SafeValue::ShortString("arm".to_string()), 0, DType::String);
+        let mut frequencies = BTreeMap::new();
+        frequencies.insert(
+            "A".to_string(),
+            SafeValue::ShortString("21-100".to_string()),
+        );
+        frequencies.insert("B".to_string(), SafeValue::ShortString("6-10".to_string()));
+        arm.stats = Some(ColumnStats {
+            value_frequencies: Some(frequencies),
+            suppressed_cells: Some(SafeValue::ShortString("1".to_string())),
+            ..Default::default()
+        });
+
+        let mut age =
+            ColumnSchema::new(SafeValue::ShortString("age".to_string()), 1, DType::Integer);
+        age.stats = Some(ColumnStats {
+            count: Some(SafeValue::Integer(90)),
+            missing_count: Some(SafeValue::Integer(10)),
+            min: Some(SafeValue::Float(18.0)),
+            max: Some(SafeValue::Float(90.0)),
+            mean: Some(54.0),
+            std_dev: Some(12.0),
+            ..Default::default()
+        });
+
+        let mut visit =
+            ColumnSchema::new(SafeValue::ShortString("visit".to_string()), 2, DType::Date);
+        visit.stats = Some(ColumnStats {
+            earliest: Some(SafeValue::ShortString("2024-01".to_string())),
+            latest: Some(SafeValue::ShortString("2024-03".to_string())),
+            ..Default::default()
+        });
+
+        let mut mrn = ColumnSchema::new(
+            SafeValue::Suppressed {
+                reason: "PHI column name".to_string(),
+            },
+            3,
+            DType::String,
+        );
+        mrn.classification = Classification::Phi;
+
+        let mut sheet = SheetSchema::new("labs.csv".to_string(), 0);
+        sheet.row_count = SafeValue::ShortString("101-1000".to_string());
+        sheet.columns = vec![arm, age, visit, mrn];
+        let mut manifest = ManifestSchema::new("labs.csv".to_string(), FileFormat::Csv);
+        manifest.sheets.push(sheet);
+        manifest
+    }
+
+    fn synth(options: &SynthOptions) -> Vec<Vec<String>> {
+        let mut buffer = Vec::new();
+        generate(&template(), options, &mut buffer).unwrap();
+        csv::Reader::from_reader(buffer.as_slice())
+            .records()
+            .map(|r| r.unwrap().iter().map(|c| c.to_string()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_reproducible() {
+        let options = SynthOptions {
+            seed: 42,
+            ..Default::default()
+        };
+        assert_eq!(synth(&options), synth(&options));
+        assert_ne!(
+            synth(&options),
+            synth(&SynthOptions {
+                seed: 7,
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn test_values_follow_manifest() {
+        let rows = synth(&SynthOptions::default());
+        // Midpoint of the 101-1000 bucket
+        assert_eq!(rows.len(), 551);
+
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let end = NaiveDate::from_ymd_opt(2024, 3, 31).unwrap();
+        let mut missing_ages = 0;
+        for row in &rows {
+            assert!(row[0] == "A" || row[0] == "B");
+            if row[1].is_empty() {
+                missing_ages += 1;
+            } else {
+                let age: i64 = row[1].parse().unwrap();
+                assert!((18..=90).contains(&age));
+            }
+            let visit = NaiveDate::parse_from_str(&row[2], "%Y-%m-%d").unwrap();
+            assert!(visit >= start && visit <= end);
+            assert!(row[3].starts_with("SYNTH4-"));
+        }
+        assert!((20..=100).contains(&missing_ages));
+    }
+
+    #[test]
+    fn test_sheet_and_rows() {
+        let mut buffer = Vec::new();
+        let options = SynthOptions {
+            sheet: Some("0".to_string()),
+            rows: Some(3),
+            seed: 1,
+            ..Default::default()
+        };
+        generate(&template(), &options, &mut buffer).unwrap();
+        let text = String::from_utf8(buffer).unwrap();
+        assert!(text.starts_with("arm,age,visit,Column4\n"));
+        assert_eq!(text.lines().count(), 4);
+
+        let missing = SynthOptions {
+            sheet: Some("visits".to_string()),
+            ..Default::default()
+        };
+        assert!(generate(&template(), &missing, &mut Vec::new()).is_err());
+    }
+
+    fn pearson(rows: &[Vec<String>], a: usize, b: usize) -> f64 {
+        let pairs: Vec<(f64, f64)> = rows
+            .iter()
+            .filter_map(|r| Some((r[a].parse().ok()?, r[b].parse().ok()?)))
+            .collect();
+        let n = pairs.len() as f64;
+        let (ma, mb) = pairs.iter().fold((0.0, 0.0), |(x, y), (a, b)| (x + a / n, y + b / n));
+        let cov: f64 = pairs.iter().map(|(a, b)| (a - ma) * (b - mb)).sum();
+        let va: f64 = pairs.iter().map(|(a, _)| (a - ma).powi(2)).sum();
+        let vb: f64 = pairs.iter().map(|(_, b)| (b - mb).powi(2)).sum();
+        cov / (va * vb).sqrt()
+    }
+
+    #[test]
+    fn test_correlations() {
+        let mut manifest = template();
+        let sheet = &mut manifest.sheets[0];
+        let mut weight =
+            ColumnSchema::new(SafeValue::ShortString("weight".to_string()), 4, DType::Numeric);
+        weight.stats = Some(ColumnStats {
+            min: Some(SafeValue::Float(40.0)),
+            max: Some(SafeValue::Float(160.0)),
+            mean: Some(80.0),
+            std_dev: Some(15.0),
+            ..Default::default()
+        });
+        sheet.columns.push(weight);
+        sheet.correlations.push(Correlation {
+            columns: ["age".to_string(), "weight".to_string()],
+            r: 0.9,
+            count: SafeValue::Integer(500),
+        });
+
+        let synth = |correlations| {
+            let options = SynthOptions {
+                rows: Some(2000),
+                correlations,
+                ..Default::default()
+            };
+            let mut buffer = Vec::new();
+            generate(&manifest, &options, &mut buffer).unwrap();
+            let rows: Vec<Vec<String>> = csv::Reader::from_reader(buffer.as_slice())
+                .records()
+                .map(|r| r.unwrap().iter().map(|c| c.to_string()).collect())
+                .collect();
+            pearson(&rows, 1, 4)
+        };
+        assert!(synth(true) > 0.8);
+        assert!(synth(false).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_impossible_correlations_are_shrunk() {
+        let plans: Vec<ColumnPlan> = ["a", "b", "c"]
+            .iter()
+            .map(|name| ColumnPlan {
+                name: name.to_string(),
+                missing_rate: 0.0,
+                generator: Generator::Placeholder {
+                    prefix: String::new(),
+                },
+            })
+            .collect();
+        // Three columns cannot all be strongly negatively correlated
+        let correlations: Vec<Correlation> = [("a", "b"), ("a", "c"), ("b", "c")]
+            .iter()
+            .map(|(a, b)| Correlation {
+                columns: [a.to_string(), b.to_string()],
+                r: -0.9,
+                count: SafeValue::Integer(100),
+            })
+            .collect();
+        let factor = correlation_factor(&plans, &[0, 1, 2], &correlations);
+        let r = factor[1][0] * factor[0][0];
+        assert!(r < 0.0 && r > -0.5);
+    }
+
+    #[test]
+    fn test_load_template() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("labs.manifest.json");
+        std::fs::write(&path, serde_json::to_string(&template()).unwrap()).unwrap();
+        let manifest = load_template(&path).unwrap();
+        assert_eq!(manifest.sheets[0].columns.len(), 4);
+
+        std::fs::write(&path, "{}").unwrap();
+        assert!(matches!(load_template(&path), Err(Error::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_estimate_count() {
+        assert_eq!(estimate_count(&SafeValue::Integer(12)), Some(12.0));
+        assert_eq!(
+            estimate_count(&SafeValue::ShortString("2-5".to_string())),
+            Some(3.5)
+        );
+        assert_eq!(
+            estimate_count(&SafeValue::ShortString(">1000".to_string())),
+            Some(1000.0)
+        );
+        assert_eq!(
+            estimate_count(&SafeValue::ShortString("1".to_string())),
+            Some(1.0)
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use std::fs::File;
+use std::io::{Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use once_cell::sync::OnceCell;
+use tempfile::{NamedTempFile, TempDir};
+
+use crate::error::Error;
+use crate::paths;
+use crate::types::Result;
+
+/// Directory spools are created in (`--temp-dir`), set once at startup
+static TEMP_DIR: OnceCell<PathBuf> = OnceCell::new();
+
+/// Create spools under `dir` instead of the system temp directory. Only the
+/// first call takes effect, so a command-line setting wins over the config file.
+pub fn set_temp_dir(dir: PathBuf) -> Result<()> {
+    if TEMP_DIR.get().is_some() {
+        return Ok(());
+    }
+    if !dir.is_dir() {
+        return Err(Error::InvalidInput(format!(
+            "temp directory {} does not exist",
+            paths::display_path(&dir)
+        )));
+    }
+    let _ = TEMP_DIR.set(dir);
+    Ok(())
+}
+
+/// A fresh private directory for spooled copies of input data, in the
+/// configured temp directory (the system one, from `TMPDIR`, if none is set)
+pub fn private_dir() -> Result<TempDir> {
+    let mut builder = tempfile::Builder::new();
+    builder.prefix("ert-manifest-");
+    Ok(match TEMP_DIR.get() {
+        Some(dir) => builder.tempdir_in(dir)?,
+        None => builder.tempdir()?,
+    })
+}
+
+/// Temp file for data that may contain PHI.
+///
+/// Created owner-only (0600 on Unix). Unless persisted, its contents are
+/// overwritten with zeros before it is removed, including when dropped on an
+/// error path. Overwriting cannot defeat copy-on-write file systems or SSD
+/// wear levelling, so temp files should still only live on trusted storage.
+pub struct SecureTempFile {
+    file: Option<NamedTempFile>,
+}
+
+impl SecureTempFile {
+    /// Create a temp file in `dir` (e.g. next to a file it will replace)
+    pub fn new_in(dir: &Path) -> Result<Self> {
+        let mut builder = tempfile::Builder::new();
+        builder.prefix(".ert-manifest-").suffix(".tmp");
+        Self::create(builder, dir)
+    }
+
+    /// Create a temp file with exactly this name in `dir` (a fresh private
+    /// directory, so the name cannot be taken)
+    pub fn with_name_in(dir: &Path, name: &str) -> Result<Self> {
+        let mut builder = tempfile::Builder::new();
+        builder.prefix(name).rand_bytes(0);
+        Self::create(builder, dir)
+    }
+
+    fn create(mut builder: tempfile::Builder<'_, '_>, dir: &Path) -> Result<Self> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            builder.permissions(std::fs::Permissions::from_mode(0o600));
+        }
+        Ok(Self {
+            file: Some(builder.tempfile_in(dir)?),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        self.inner().path()
+    }
+
+    pub fn as_file(&self) -> &File {
+        self.inner().as_file()
+    }
+
+    pub fn as_file_mut(&mut self) -> &mut File {
+        self.file
+            .as_mut()
+            .expect("temp file is present until persisted")
+            .as_file_mut()
+    }
+
+    /// Atomically move the temp file to `path`; it is no longer wiped afterwards
+    pub fn persist(mut self, path: &Path) -> Result<()> {
+        let file = self.file.take().expect("temp file is present until persisted");
+        match file.persist(path) {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                // Put it back so Drop still wipes it
+                self.file = Some(e.file);
+                Err(Error::Io(e.error))
+            }
+        }
+    }
+
+    fn inner(&self) -> &NamedTempFile {
+        self.file
+            .as_ref()
+            .expect("temp file is present until persisted")
+    }
+}
+
+/// Overwrite a file's contents with zeros and flush them to disk
+fn wipe(file: &mut File) -> std::io::Result<()> {
+    let len = file.metadata()?.len();
+    file.seek(SeekFrom::Start(0))?;
+    let zeros = [0u8; 8192];
+    let mut remaining = len;
+    while remaining > 0 {
+        let chunk = remaining.min(zeros.len() as u64) as usize;
+        file.write_all(&zeros[..chunk])?;
+        remaining -= chunk as u64;
+    }
+    file.sync_all()
+}
+
+impl Drop for SecureTempFile {
+    fn drop(&mut self) {
+        if let Some(mut file) = self.file.take() {
+            // Best effort: removal below happens even if wiping fails
+            let _ = wipe(file.as_file_mut());
+            let _ = file.close();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_removed_on_drop() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = {
+            let mut temp = SecureTempFile::new_in(dir.path()).unwrap();
+            temp.as_file_mut().write_all(b"MRN 1234567").unwrap();
+            temp.path().to_path_buf()
+        };
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_wipe_zeroes_contents() {
+        let mut file = tempfile::tempfile().unwrap();
+        file.write_all(b"Vancouver General").unwrap();
+        wipe(&mut file).unwrap();
+
+        let mut contents = Vec::new();
+        file.seek(SeekFrom::Start(0)).unwrap();
+        file.read_to_end(&mut contents).unwrap();
+        assert_eq!(contents.len(), 17);
+        assert!(contents.iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn test_persist() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("out.txt");
+        let mut temp = SecureTempFile::new_in(dir.path()).unwrap();
+        temp.as_file_mut().write_all(b"kept").unwrap();
+        temp.persist(&target).unwrap();
+
+        assert_eq!(std::fs::read_to_string(&target).unwrap(), "kept");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_owner_only_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let temp = SecureTempFile::new_in(dir.path()).unwrap();
+        let mode = temp.as_file().metadata().unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
@@ -1,17 +1,67 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
 
+use crate::output::OutputFormat;
+use crate::privacy::code_systems::CodeSystem;
+use crate::readers::fixed_width::FixedWidthLayout;
+use crate::readers::provenance::SourceSystem;
+use crate::stats::{HashSalt, HyperLogLog};
+
 /// Maximum length for short strings that can be safely exported
 pub const MAX_SHORT_STRING_LEN: usize = 32;
 
 /// Maximum unique values to track before marking as high cardinality
 pub const MAX_UNIQUE_VALUES: usize = 2000;
 
+/// Most frequent values reported for high-cardinality columns
+pub const TOP_K_VALUES: usize = 10;
+
+/// Example values shown per safe column (`--examples`)
+pub const MAX_EXAMPLES: usize = 3;
+
+/// A number shaped like a missing-value code (-99, 999) must be seen this often,
+/// outside the column's usual range, to be reported as one
+pub const MIN_SENTINEL_COUNT: u64 = 3;
+
+/// Values at a text column's maximum length are taken for truncated ones when there
+/// are at least this many, the length is at least `MIN_TRUNCATION_LENGTH` characters,
+/// and they outnumber the values one character shorter `TRUNCATION_RATIO` times over
+pub const MIN_TRUNCATED_VALUES: u64 = 5;
+pub const MIN_TRUNCATION_LENGTH: usize = 20;
+pub const TRUNCATION_RATIO: u64 = 3;
+
+/// Version of the manifest model written by this release; `compat` reads
+/// older ones
+pub const MANIFEST_VERSION: &str = "1.1.0";
+
 /// Default k-anonymity threshold
 pub const DEFAULT_K_ANONYMITY: u64 = 5;
 
+/// Default number of distinct sensitive values each quasi-identifier group should hold
+pub const DEFAULT_L_DIVERSITY: u64 = 2;
+
 /// Sample size for type inference
 pub const TYPE_INFERENCE_SAMPLE_SIZE: usize = 2000;
 
+/// Leading rows held in memory for the first pass over input that can only be
+/// read once (stdin)
+pub const SINGLE_PASS_LEADING_ROWS: usize = 10_000;
+
+/// Values below which an inferred type has low confidence
+pub const MIN_INFERENCE_SAMPLE: u64 = 30;
+
+/// Values from which an inferred type that held up has high confidence
+pub const SOLID_INFERENCE_SAMPLE: u64 = 100;
+
+/// Pairs of numeric columns correlated at least this strongly (|r|) are reported in relaxed mode
+pub const CORRELATION_THRESHOLD: f64 = 0.8;
+
+/// Numeric columns past this many are left out of the correlations
+pub const MAX_CORRELATION_COLUMNS: usize = 100;
+
 /// A value that is safe to export (privacy-preserving)
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type", content = "value")]
@@ -36,6 +86,18 @@ impl SafeValue {
     }
 }
 
+impl std::fmt::Display for SafeValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SafeValue::Integer(i) => write!(f, "{}", i),
+            SafeValue::Float(x) => write!(f, "{}", x),
+            SafeValue::Boolean(b) => write!(f, "{}", b),
+            SafeValue::ShortString(s) => write!(f, "{}", s),
+            SafeValue::Suppressed { .. } => write!(f, "[suppressed]"),
+        }
+    }
+}
+
 /// Data type classification for columns
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -49,10 +111,56 @@ pub enum DType {
     FreeText,
 }
 
+impl DType {
+    /// Name as it appears in the manifest
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DType::Integer => "integer",
+            DType::Numeric => "numeric",
+            DType::String => "string",
+            DType::Date => "date",
+            DType::Datetime => "datetime",
+            DType::Boolean => "boolean",
+            DType::FreeText => "free_text",
+        }
+    }
+
+    /// Parse a type name as written in a codebook or layout file (`free text`
+    /// and `free-text` are accepted for `free_text`)
+    pub fn from_name(text: &str) -> Option<Self> {
+        let text = text.to_lowercase().replace([' ', '-'], "_");
+        [
+            DType::Integer,
+            DType::Numeric,
+            DType::String,
+            DType::Date,
+            DType::Datetime,
+            DType::Boolean,
+            DType::FreeText,
+        ]
+        .into_iter()
+        .find(|d| d.as_str() == text)
+    }
+
+    /// Whether this inferred type satisfies a declared type. Inference picks
+    /// the narrowest type that fits, so e.g. a numeric column of whole numbers
+    /// is found as integer.
+    pub fn satisfies(self, declared: DType) -> bool {
+        matches!(
+            (declared, self),
+            (DType::Numeric, DType::Integer)
+                | (DType::Integer | DType::Numeric, DType::Boolean)
+                | (DType::Datetime, DType::Date)
+                | (DType::String, DType::FreeText | DType::Boolean)
+                | (DType::FreeText, DType::String)
+        ) || declared == self
+    }
+}
 
 /// Classification of a column's privacy sensitivity
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
+#[value(rename_all = "snake_case")]
 pub enum Classification {
     /// Safe to export
     Safe,
@@ -66,6 +174,19 @@ pub enum Classification {
     HighCardinality,
 }
 
+impl Classification {
+    /// Name as it appears in the manifest
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Classification::Safe => "safe",
+            Classification::Warning => "warning",
+            Classification::Phi => "phi",
+            Classification::Recode => "recode",
+            Classification::HighCardinality => "high_cardinality",
+        }
+    }
+}
+
 /// Statistics for a column (all privacy-safe)
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ColumnStats {
@@ -77,6 +198,31 @@ pub struct ColumnStats {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub missing_count: Option<SafeValue>,
 
+    /// Missing values by explicit missing code, e.g. Stata `.a` to `.z` (may be bucketed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub missing_codes: Option<BTreeMap<String, SafeValue>>,
+
+    /// Cells matching a PHI value pattern, by pattern (`--deep-scan`; may be bucketed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern_hits: Option<BTreeMap<String, SafeValue>>,
+
+    /// Values with hidden whitespace, by kind: `leading_trailing_whitespace`,
+    /// `non_breaking_space` and `zero_width` (may be bucketed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub whitespace_anomalies: Option<BTreeMap<String, SafeValue>>,
+
+    /// Language mix and PHI density of a free-text column
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text_summary: Option<TextSummary>,
+
+    /// Groups of near-duplicate spellings ("Montréal", "MONTREAL ") in categorical columns
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value_clusters: Option<Vec<ValueCluster>>,
+
+    /// Groups of values that differ only in letter case ("male", "Male", "MALE")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub case_variants: Option<Vec<CaseVariants>>,
+
     /// Minimum value (for numeric/date types)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub min: Option<SafeValue>,
@@ -97,9 +243,118 @@ pub struct ColumnStats {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub median: Option<f64>,
 
+    /// First quartile (for numeric types, estimated via P²)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub q1: Option<f64>,
+
+    /// Third quartile (for numeric types, estimated via P²)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub q3: Option<f64>,
+
+    /// Numeric values that are NaN or infinite (`inf`, `1e999`), left out of
+    /// min, max, mean, median and quartiles (may be bucketed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub non_finite_count: Option<SafeValue>,
+
+    /// Values beyond mean ± 3 SD (numeric types; may be bucketed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outliers_sd: Option<SafeValue>,
+
+    /// Values beyond Q1 - 1.5 IQR or Q3 + 1.5 IQR (numeric types; may be bucketed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outliers_iqr: Option<SafeValue>,
+
+    /// Numbers shaped like missing-value codes (-99, 999) seen repeatedly outside the
+    /// column's usual range, with their counts (may be bucketed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suspected_missing_codes: Option<BTreeMap<String, SafeValue>>,
+
     /// Number of unique values (may be bucketed or marked high cardinality)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub unique_count: Option<SafeValue>,
+
+    /// Most frequent values of a high-cardinality column (each seen at least k times)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_values: Option<Vec<FrequentValue>>,
+
+    /// True when unique_count is a sketch estimate (column exceeded the unique-value cap)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub unique_count_approximate: bool,
+
+    /// Per-value counts for string and boolean columns (bucketed; values below k are left out)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value_frequencies: Option<BTreeMap<String, SafeValue>>,
+
+    /// Number of values left out of value_frequencies for being seen fewer than k times
+    /// (may be bucketed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suppressed_cells: Option<SafeValue>,
+
+    /// Earliest date (date/datetime columns), generalized to the configured precision
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub earliest: Option<SafeValue>,
+
+    /// Latest date (date/datetime columns), generalized to the configured precision
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latest: Option<SafeValue>,
+
+    /// Days between the earliest and latest date; only at day precision
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub span_days: Option<i64>,
+}
+
+/// What a free-text column is written in and how much PHI it carries
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TextSummary {
+    /// Cells by language (`en`, `fr`, `pt`, `es`, `de`, or `unknown`; may be bucketed)
+    pub languages: BTreeMap<String, SafeValue>,
+    /// Words across all cells (may be bucketed)
+    pub words: SafeValue,
+    /// Emails, phone numbers, SSNs, URLs, IP addresses and other PHI value patterns
+    /// found per 1000 words
+    pub phi_density: f64,
+}
+
+/// Spellings of one category that differ only in case, accents, punctuation
+/// or word order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValueCluster {
+    /// Distinct spellings in the cluster (may be bucketed)
+    pub variants: SafeValue,
+    /// Rows holding any of the spellings (may be bucketed)
+    pub count: SafeValue,
+    /// Spellings that are also in the column's reported values
+    pub exemplars: Vec<SafeValue>,
+}
+
+/// One category written in several letter cases
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CaseVariants {
+    /// Exported variants with their counts (may be bucketed)
+    pub variants: BTreeMap<String, SafeValue>,
+    /// Variants not named because they are rarer than k or look like PHI
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub withheld: Option<SafeValue>,
+}
+
+/// A frequent value and its (lower-bound) count
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FrequentValue {
+    pub value: SafeValue,
+    /// Guaranteed minimum count (may be bucketed)
+    pub count: SafeValue,
+}
+
+/// What a site's data-retention plan says happens to a column's data
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RetentionCategory {
+    /// Kept after the study
+    Keep,
+    /// Destroyed once the study closes
+    DestroyAfterStudy,
+    /// Never leaves the site; its values are withheld from the manifest
+    NeverTransfer,
 }
 
 /// Schema for a single column
@@ -108,6 +363,10 @@ pub struct ColumnSchema {
     /// Column name (may be suppressed if PHI)
     pub name: SafeValue,
 
+    /// Variable label stored in the file (SAS transport)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+
     /// Column index (0-based)
     pub index: usize,
 
@@ -125,21 +384,179 @@ pub struct ColumnSchema {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub unique_values: Option<Vec<SafeValue>>,
 
+    /// A few of the column's most frequent values, shown as examples (`--examples`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub examples: Vec<SafeValue>,
+
+    /// Hidden in the workbook (xlsx)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub hidden: bool,
+
+    /// Matched CDISC SDTM/ADaM variable (`--cdisc`); domain-prefixed variables
+    /// are written `--SEQ`, `--STDTC`, ...
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cdisc_variable: Option<String>,
+
+    /// Clinical code system most values follow (`icd10`, `atc`, `loinc`, `snomed_ct`);
+    /// values of its shape are not checked against the PHI value patterns
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code_system: Option<CodeSystem>,
+
+    /// Measurement unit suggested by the column name (`weight_kg`) or values (`45%`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit_hint: Option<String>,
+
+    /// How the values are written when it matters for parsing them:
+    /// `decimal_comma`, `thousands_separator` or `percent`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format_hint: Option<String>,
+
+    /// Decimal separator the column's numbers were read with, when they use a
+    /// decimal comma or group their digits
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decimal_separator: Option<DecimalSeparator>,
+
+    /// Tags from the config file (e.g. `outcome`, `baseline`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+
+    /// Note from the config file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+
+    /// Retention category from the config file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retention: Option<RetentionCategory>,
+
+    /// Result of the column's minimum-completeness check, if the config sets one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completeness: Option<CompletenessCheck>,
+
+    /// Values outside the column's code list, if the config declares one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code_list: Option<CodeListCheck>,
+
     /// Warnings about this column
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub warnings: Vec<String>,
+
+    /// How the type was inferred, for judging how far to trust it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diagnostics: Option<InferenceDiagnostics>,
+
+    /// Hashed value fingerprint for cross-file linkage (never serialized)
+    #[serde(skip)]
+    pub fingerprint: Option<ColumnFingerprint>,
+}
+
+/// A column's completeness compared with its required minimum
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompletenessCheck {
+    /// Required share of non-missing values (percent)
+    pub required_percent: f64,
+    /// Actual share of non-missing values, rounded down to a whole percent
+    pub complete_percent: f64,
+    pub passed: bool,
+    /// Indexes of the matching `ProcessingOptions::completeness_rules`, strictest first
+    #[serde(skip)]
+    pub rules: Vec<usize>,
+}
+
+/// A column's values compared with its declared code list
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CodeListCheck {
+    /// Number of non-missing values checked
+    pub checked: SafeValue,
+    /// Number of values not in the code list
+    pub out_of_list: SafeValue,
+    pub passed: bool,
+    /// Out-of-list values seen at least k times, with their counts
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub values: BTreeMap<String, SafeValue>,
+}
+
+/// Sample size, conflicts and widenings behind a column's inferred type
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InferenceDiagnostics {
+    /// How solid the type is
+    pub confidence: InferenceConfidence,
+    /// Non-missing values the type was first inferred from (may be bucketed)
+    pub sample_size: SafeValue,
+    /// Type inferred from those values
+    pub initial_type: DType,
+    /// Later values that did not fit the initial type (may be bucketed)
+    pub conflicts: SafeValue,
+    /// Types the column was widened to after the sample, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub widened_to: Vec<DType>,
+}
+
+/// Confidence in an inferred column type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InferenceConfidence {
+    /// Inferred from fewer than `MIN_INFERENCE_SAMPLE` values
+    Low,
+    /// Widened by values after the sample, or inferred from fewer than
+    /// `SOLID_INFERENCE_SAMPLE` values
+    Medium,
+    High,
+}
+
+impl InferenceConfidence {
+    pub fn assess(sample_size: u64, widened: bool) -> Self {
+        if sample_size < MIN_INFERENCE_SAMPLE {
+            InferenceConfidence::Low
+        } else if widened || sample_size < SOLID_INFERENCE_SAMPLE {
+            InferenceConfidence::Medium
+        } else {
+            InferenceConfidence::High
+        }
+    }
+}
+
+/// In-memory fingerprint of a column's values, used to spot shared join keys
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnFingerprint {
+    /// Column header normalized for matching (lowercase alphanumerics)
+    pub key_name: String,
+
+    /// Number of non-missing values
+    pub value_count: u64,
+
+    /// Distinct-value sketch of the raw values
+    pub sketch: HyperLogLog,
+
+    /// Sketch of the salted value hashes, for the subject ID column
+    /// (`--subject-column`) only
+    pub subject_sketch: Option<HyperLogLog>,
 }
 
 impl ColumnSchema {
     pub fn new(name: SafeValue, index: usize, dtype: DType) -> Self {
         Self {
             name,
+            label: None,
             index,
             dtype,
             classification: Classification::Safe,
             stats: None,
             unique_values: None,
+            examples: Vec::new(),
+            cdisc_variable: None,
+            code_system: None,
+            hidden: false,
+            unit_hint: None,
+            format_hint: None,
+            decimal_separator: None,
+            tags: Vec::new(),
+            note: None,
+            retention: None,
+            completeness: None,
+            code_list: None,
             warnings: Vec::new(),
+            diagnostics: None,
+            fingerprint: None,
         }
     }
 }
@@ -153,15 +570,53 @@ pub struct SheetSchema {
     /// Sheet index (0-based)
     pub index: usize,
 
+    /// Whether the sheet is hidden in the workbook
+    #[serde(default, skip_serializing_if = "SheetVisibility::is_visible")]
+    pub visibility: SheetVisibility,
+
     /// Row count (may be bucketed)
     pub row_count: SafeValue,
 
+    /// Rows that exactly repeat an earlier row (may be bucketed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duplicate_rows: Option<SafeValue>,
+
     /// Column schemas
     pub columns: Vec<ColumnSchema>,
 
+    /// Strongly correlated pairs of numeric columns (relaxed mode only)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub correlations: Vec<Correlation>,
+
     /// Sheet-level warnings
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub warnings: Vec<String>,
+
+    /// K-anonymity of quasi-identifier combinations (`--quasi-identifiers`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub quasi_identifier_checks: Vec<QuasiIdentifierCheck>,
+
+    /// Predominant language of the column names, if one clearly leads
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub column_language: Option<Language>,
+
+    /// SDTM domain or ADaM dataset recognized from the sheet or file name (`--cdisc`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cdisc_domain: Option<String>,
+
+    /// Cell comments and formulas checked for PHI (`--scan-comments`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedded_text: Option<EmbeddedTextScan>,
+
+    /// Rows profiled when only a sample was (`--sample-rows`,
+    /// `--sample-fraction`); every other count of the sheet is then the
+    /// sample's
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample: Option<RowSample>,
+
+    /// Validation rule counts for this sheet, gathered into `ManifestSchema::validation`
+    #[serde(skip)]
+    pub validation: Vec<ValidationResult>,
 }
 
 impl SheetSchema {
@@ -169,13 +624,119 @@ impl SheetSchema {
         Self {
             name,
             index,
+            visibility: SheetVisibility::Visible,
             row_count: SafeValue::Integer(0),
+            duplicate_rows: None,
             columns: Vec::new(),
+            correlations: Vec::new(),
             warnings: Vec::new(),
+            quasi_identifier_checks: Vec::new(),
+            column_language: None,
+            cdisc_domain: None,
+            embedded_text: None,
+            sample: None,
+            validation: Vec::new(),
         }
     }
 }
 
+/// Text a sheet carries outside its cells' values, checked for PHI without
+/// exporting it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EmbeddedTextScan {
+    /// Cell comments (may be bucketed)
+    pub comments: SafeValue,
+    /// Comments that look like they hold PHI (may be bucketed)
+    pub comments_with_phi: SafeValue,
+    /// Formula cells (may be bucketed)
+    pub formulas: SafeValue,
+    /// Formulas whose text strings look like PHI (may be bucketed)
+    pub formulas_with_phi: SafeValue,
+    /// PHI patterns matched (`name`, `email`, ...)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub patterns: Vec<String>,
+}
+
+/// A sample of a table's rows: a random draw of `--sample-rows` rows, or every
+/// k-th row under `--sample-fraction`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RowSample {
+    /// Rows of the whole table (may be bucketed)
+    pub table_rows: SafeValue,
+    /// Rows profiled (may be bucketed)
+    pub sampled_rows: SafeValue,
+    /// Share of the rows profiled
+    pub fraction: f64,
+}
+
+/// Whether a workbook sheet is shown in its tabs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SheetVisibility {
+    #[default]
+    Visible,
+    /// Hidden, but can be unhidden from the workbook's tabs
+    Hidden,
+    /// Hidden so that only a macro can show it again
+    VeryHidden,
+}
+
+impl SheetVisibility {
+    pub fn is_visible(&self) -> bool {
+        *self == SheetVisibility::Visible
+    }
+}
+
+/// Pearson correlation between two numeric columns, an entry of the sparse
+/// correlation matrix
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Correlation {
+    /// Names of the two columns, in column order
+    pub columns: [String; 2],
+    /// Pearson's r, rounded to 3 decimals
+    pub r: f64,
+    /// Rows where both columns have a value (may be bucketed)
+    pub count: SafeValue,
+}
+
+/// K-anonymity of a combination of quasi-identifier columns (`--quasi-identifiers`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuasiIdentifierCheck {
+    /// The columns, in column order
+    pub columns: Vec<String>,
+    /// Suggested from the column names rather than given
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub suggested: bool,
+    /// Distinct combinations of values (may be bucketed)
+    pub classes: SafeValue,
+    /// Rows in the smallest group sharing one combination
+    pub min_class_size: u64,
+    /// Share of rows in groups smaller than k, rounded to 0.1
+    pub percent_below_k: f64,
+    /// Diversity of each sensitive column within the groups (`--sensitive-columns`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub l_diversity: Vec<DiversityCheck>,
+    /// Position of the set in `ProcessingOptions::quasi_identifier_sets`, if given
+    #[serde(skip)]
+    pub set: Option<usize>,
+}
+
+/// How many quasi-identifier groups hold too few distinct values of a sensitive
+/// column: everyone in such a group shares (nearly) the same sensitive value, so
+/// knowing someone is in the group reveals it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiversityCheck {
+    /// The sensitive column
+    pub column: String,
+    /// Groups with fewer than l distinct values of the column (may be bucketed)
+    pub groups_below_l: SafeValue,
+    /// Share of rows in those groups, rounded to 0.1
+    pub percent_below_l: f64,
+    /// Position of the column in `ProcessingOptions::sensitive_columns`
+    #[serde(skip)]
+    pub sensitive: usize,
+}
+
 /// Complete manifest schema for a file
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ManifestSchema {
@@ -192,27 +753,284 @@ pub struct ManifestSchema {
     /// File format
     pub format: FileFormat,
 
+    /// Text encoding the file was decoded from (CSV/TSV only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+
+    /// Details about how the file was produced
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_details: Option<FileDetails>,
+
     /// Sheets in the file
     pub sheets: Vec<SheetSchema>,
 
     /// Global warnings
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub warnings: Vec<String>,
 
     /// Processing options used
     pub options: ProcessingOptions,
+
+    /// Config file the scan's defaults came from, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<ScanConfig>,
+
+    /// Differences from the study codebook (`--codebook`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub codebook_findings: Option<CodebookFindings>,
+
+    /// Columns outside the approved data-sharing scope (`--scope`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope_findings: Option<ScopeFindings>,
+
+    /// Critical columns below their required completeness, or not found
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub completeness_failures: Vec<CompletenessFailure>,
+
+    /// Cross-column validation rule results (`--validation-rules`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub validation: Vec<ValidationResult>,
+
+    /// Installed rule packs whose detectors were active for the scan
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rule_packs: Vec<RulePackVersion>,
+
+    /// Derived from a scan's manifest by `redact`: value lists removed and all
+    /// counts bucketed
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub redacted: bool,
 }
 
 impl ManifestSchema {
     pub fn new(file_name: String, format: FileFormat) -> Self {
         Self {
-            version: "1.0.0".to_string(),
+            version: MANIFEST_VERSION.to_string(),
             file_name,
             file_hash: None,
             format,
+            encoding: None,
+            file_details: None,
             sheets: Vec::new(),
             warnings: Vec::new(),
             options: ProcessingOptions::default(),
+            config: None,
+            codebook_findings: None,
+            scope_findings: None,
+            completeness_failures: Vec::new(),
+            validation: Vec::new(),
+            rule_packs: Vec::new(),
+            redacted: false,
+        }
+    }
+}
+
+/// A failed completeness check
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompletenessFailure {
+    /// Column name from the rule
+    pub column: String,
+    /// Sheet the column was found in (or the rule's sheet, if it was not found)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sheet: Option<String>,
+    /// Index of the column; absent if no column matched the rule
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub column_index: Option<usize>,
+    pub required_percent: f64,
+    /// Absent if no column matched the rule
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub complete_percent: Option<f64>,
+}
+
+/// Outcome of one validation rule. Only counts are reported, never the
+/// values that broke the rule.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValidationResult {
+    /// The rule as written in the rules file
+    pub rule: String,
+    /// Sheet the rule was evaluated on; absent if no sheet had its columns
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sheet: Option<String>,
+    /// Rows with every value present and comparable (may be bucketed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checked: Option<SafeValue>,
+    /// Rows that broke the rule (may be bucketed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub violations: Option<SafeValue>,
+    /// Why the rule could not be evaluated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Name and version of a rule pack
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RulePackVersion {
+    pub name: String,
+    pub version: String,
+}
+
+/// Comparison of the scanned columns with a study codebook
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CodebookFindings {
+    /// Codebook file name
+    pub codebook: String,
+
+    /// Columns in the file that the codebook does not list
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unexpected_columns: Vec<UnexpectedColumn>,
+
+    /// Codebook columns not found in the file
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub missing_columns: Vec<String>,
+
+    /// Columns whose inferred type differs from the codebook
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dtype_mismatches: Vec<DtypeMismatch>,
+
+    /// Columns with suppressed (PHI) names, which cannot be compared
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub unchecked_columns: usize,
+}
+
+/// Comparison of the scanned columns with the variables approved for sharing
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScopeFindings {
+    /// Scope file name
+    pub scope: String,
+
+    /// True if every column is within the approved scope
+    pub compliant: bool,
+
+    /// Columns not covered by the scope, including columns whose name is
+    /// suppressed and so cannot be shown to be approved
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub out_of_scope_columns: Vec<ScopeGap>,
+
+    /// Approved variables not found in the file
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub missing_variables: Vec<String>,
+}
+
+/// A column outside the approved scope
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScopeGap {
+    pub sheet: String,
+    pub column_index: usize,
+    pub name: SafeValue,
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+/// A column the codebook does not list
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnexpectedColumn {
+    pub sheet: String,
+    pub column_index: usize,
+    pub name: String,
+
+    /// Missing codebook column with a similar name (e.g. a renamed column)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub similar_to: Option<String>,
+}
+
+/// A column whose inferred type differs from the codebook
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DtypeMismatch {
+    pub sheet: String,
+    pub column_index: usize,
+    pub name: String,
+    pub expected: DType,
+    pub found: DType,
+}
+
+/// How a file was produced, as far as it can be told from the file itself
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FileDetails {
+    /// System that probably exported the file (CSV/TSV only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub likely_source: Option<SourceSystem>,
+
+    /// Signature the guess is based on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_evidence: Option<String>,
+
+    /// Delimiter recognized from the content, for files whose extension names no format
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delimiter: Option<String>,
+}
+
+/// Settings a scan took from a config file, beyond the processing options
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanConfig {
+    /// Config file name
+    pub source: String,
+
+    /// Effective output format
+    pub format: OutputFormat,
+
+    /// File-name patterns skipped in directory scans
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignore: Vec<String>,
+
+    /// Names of the custom value detectors applied
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub detectors: Vec<String>,
+
+    /// File names of the privacy rule plugins loaded
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub plugins: Vec<String>,
+}
+
+/// Location of a column within a multi-file scan
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColumnRef {
+    pub file_name: String,
+    pub sheet: String,
+    /// Column index (0-based)
+    pub column_index: usize,
+}
+
+/// A pair of columns in different files that look like the same join key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JoinKeyCandidate {
+    /// Shared column name (suppressed if PHI)
+    pub column_name: SafeValue,
+
+    pub left: ColumnRef,
+    pub right: ColumnRef,
+
+    /// Estimated number of distinct values present in both columns (may be bucketed)
+    pub estimated_overlap: SafeValue,
+
+    /// Estimated overlap as a fraction of the smaller column's distinct values
+    pub overlap_ratio: f64,
+}
+
+/// Manifest covering several related files scanned together
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultiFileManifest {
+    /// Schema version
+    pub version: String,
+
+    /// Per-file manifests
+    pub files: Vec<ManifestSchema>,
+
+    /// Columns that appear to link files together
+    pub join_keys: Vec<JoinKeyCandidate>,
+
+    /// Global warnings
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+impl MultiFileManifest {
+    pub fn new(files: Vec<ManifestSchema>) -> Self {
+        Self {
+            version: MANIFEST_VERSION.to_string(),
+            files,
+            join_keys: Vec::new(),
+            warnings: Vec::new(),
         }
     }
 }
@@ -224,6 +1042,11 @@ pub enum FileFormat {
     Csv,
     Tsv,
     Excel,
+    /// Fixed-width text, read with a column layout (`--layout`)
+    #[serde(rename = "fixed_width")]
+    FixedWidth,
+    /// SAS transport (XPORT v5 or v8)
+    Xpt,
 }
 
 impl FileFormat {
@@ -232,9 +1055,92 @@ impl FileFormat {
             "csv" => Some(FileFormat::Csv),
             "tsv" | "tab" => Some(FileFormat::Tsv),
             "xlsx" | "xls" | "xlsm" | "xlsb" => Some(FileFormat::Excel),
+            "xpt" => Some(FileFormat::Xpt),
             _ => None,
         }
     }
+
+    /// Name for messages
+    pub fn name(&self) -> &'static str {
+        match self {
+            FileFormat::Csv => "CSV",
+            FileFormat::Tsv => "TSV",
+            FileFormat::Excel => "Excel",
+            FileFormat::FixedWidth => "fixed-width text",
+            FileFormat::Xpt => "SAS transport",
+        }
+    }
+}
+
+/// How the header row of a table is located
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HeaderMode {
+    /// Detect title lines and whether the first table row is a header
+    #[default]
+    Auto,
+    /// Header on this row (1-based); rows above it are skipped
+    Row(usize),
+    /// No header row; columns are named Column1..N
+    Absent,
+}
+
+/// Language of column names; also names the optional locale pattern packs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    En,
+    Fr,
+    Pt,
+    Es,
+    De,
+}
+
+impl Language {
+    /// Code as it appears in the manifest
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Language::En => "en",
+            Language::Fr => "fr",
+            Language::Pt => "pt",
+            Language::Es => "es",
+            Language::De => "de",
+        }
+    }
+}
+
+/// Granularity at which earliest/latest dates are reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DatePrecision {
+    /// Full date (2024-03-15)
+    Day,
+    /// Year and month (2024-03)
+    #[default]
+    Month,
+    /// Year only (2024)
+    Year,
+}
+
+/// Decimal separator of numbers in text files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DecimalSeparator {
+    /// 3.14, with commas or spaces grouping thousands (1,234.5)
+    Period,
+    /// 3,14, with periods or spaces grouping thousands (1.234,5)
+    Comma,
+}
+
+impl DatePrecision {
+    /// Format a date at this precision
+    pub fn format(&self, date: NaiveDate) -> String {
+        match self {
+            DatePrecision::Day => date.format("%Y-%m-%d").to_string(),
+            DatePrecision::Month => date.format("%Y-%m").to_string(),
+            DatePrecision::Year => date.format("%Y").to_string(),
+        }
+    }
 }
 
 /// Processing options
@@ -257,6 +1163,278 @@ pub struct ProcessingOptions {
 
     /// Relaxed mode (allows exact counts/median)
     pub relaxed: bool,
+
+    /// Source text encoding override (detected when not set)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+
+    /// Header row handling
+    #[serde(default)]
+    pub header: HeaderMode,
+
+    /// Stacked header rows joined into each column name (case report form
+    /// exports often have a group row above the variable names)
+    #[serde(default = "default_header_rows")]
+    pub header_rows: usize,
+
+    /// Precision of reported earliest/latest dates
+    #[serde(default)]
+    pub date_precision: DatePrecision,
+
+    /// Decimal separator of numbers; detected per column if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decimal_separator: Option<DecimalSeparator>,
+
+    /// Classifications set by a reviewer, used instead of the column name checks
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub classification_overrides: Vec<ClassificationOverride>,
+
+    /// Minimum completeness of critical columns, from the config file
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub completeness_rules: Vec<CompletenessRule>,
+
+    /// Allowed values of categorical columns, from the config file
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub code_lists: Vec<CodeListRule>,
+
+    /// Extra tokens counted as missing in every column (`--missing-tokens`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub missing_tokens: Vec<String>,
+
+    /// Missing-value tokens of single columns, from the config file
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub missing_token_rules: Vec<MissingTokenRule>,
+
+    /// Cross-column validation rules (`--validation-rules`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub validation_rules: Vec<String>,
+
+    /// Locale pattern packs checked in addition to the built-in patterns (`--locales`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub locales: Vec<Language>,
+
+    /// Also enable the pack matching each table's column language (`--locales auto`)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub auto_locales: bool,
+
+    /// Column layout for fixed-width files (`--layout`)
+    #[serde(skip)]
+    pub fixed_width_layout: Option<FixedWidthLayout>,
+
+    /// Check every cell of string columns for PHI value patterns
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub deep_scan: bool,
+
+    /// Downgrade PHI column names to warnings when the values contradict them
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub content_aware: bool,
+
+    /// Recognize CDISC SDTM/ADaM variables and classify them by the standard
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cdisc: bool,
+
+    /// Show a few k-anonymous example values for each safe column
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub examples: bool,
+
+    /// Replace file and sheet names that look like PHI in the manifest
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub redact_names: bool,
+
+    /// Combinations of quasi-identifier columns to measure k-anonymity of
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub quasi_identifier_sets: Vec<Vec<String>>,
+
+    /// Also measure the combination of the columns whose names mark them as
+    /// quasi-identifiers (`--quasi-identifiers auto`)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub suggest_quasi_identifiers: bool,
+
+    /// Sensitive columns (diagnoses, test results, ...) whose diversity within each
+    /// quasi-identifier group is measured
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sensitive_columns: Vec<String>,
+
+    /// Fewest distinct sensitive values a quasi-identifier group should hold
+    #[serde(default = "default_l_diversity")]
+    pub l_diversity: u64,
+
+    /// Leave hidden workbook sheets out of the scan
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub skip_hidden_sheets: bool,
+
+    /// Check workbook cell comments and formula text for PHI
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub scan_comments: bool,
+
+    /// Workbook sheets (or transport datasets) to scan, by name or glob
+    /// pattern; all when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sheets: Vec<String>,
+
+    /// Sheets left out of the scan, by name or glob pattern
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude_sheets: Vec<String>,
+
+    /// Columns to scan, by name or glob pattern; all when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub columns: Vec<String>,
+
+    /// Columns left out of the scan, by name or glob pattern
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude_columns: Vec<String>,
+
+    /// Column classes whose values are neither exported nor, where the column
+    /// name already decides the class, collected
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub no_values_for: Vec<Classification>,
+
+    /// Columns whose values are neither collected nor exported, by name or
+    /// glob pattern
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub no_values_columns: Vec<String>,
+
+    /// Distinct values tracked per column before it counts as high
+    /// cardinality; at most `MAX_UNIQUE_VALUES`
+    #[serde(default = "default_max_unique_values")]
+    pub max_unique_values: usize,
+
+    /// Profile a random sample of this many rows of each table
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample_rows: Option<u64>,
+
+    /// Profile this share (0 to 1) of the rows of each table, evenly spread
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample_fraction: Option<f64>,
+
+    /// Column holding subject IDs, counted across the files of a batch by
+    /// their salted hashes (`--subject-column`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subject_column: Option<String>,
+
+    /// Salt of the subject ID hashes, shared by the files of one run
+    #[serde(skip)]
+    pub subject_salt: HashSalt,
+}
+
+impl ProcessingOptions {
+    /// Whether a sheet is scanned under `sheets` and `exclude_sheets`
+    pub fn sheet_selected(&self, name: &str) -> bool {
+        is_selected(name, &self.sheets, &self.exclude_sheets)
+    }
+
+    /// Whether a column is scanned under `columns` and `exclude_columns`
+    pub fn column_selected(&self, name: &str) -> bool {
+        is_selected(name, &self.columns, &self.exclude_columns)
+    }
+
+    /// Whether a column's values are withheld under `no_values_for` and
+    /// `no_values_columns`
+    pub fn withholds_values(&self, classification: &Classification, column: &str) -> bool {
+        self.no_values_for.contains(classification)
+            || self.no_values_columns.iter().any(|pattern| name_matches(pattern, column))
+    }
+
+    /// Whether `columns` or `exclude_columns` narrow the scan
+    pub fn selects_columns(&self) -> bool {
+        !self.columns.is_empty() || !self.exclude_columns.is_empty()
+    }
+}
+
+/// Included by one of `include` (or `include` is empty) and by none of
+/// `exclude`. Patterns are globs matched ignoring case; one that is not a
+/// valid glob matches its exact text.
+fn is_selected(name: &str, include: &[String], exclude: &[String]) -> bool {
+    let matches = |pattern: &String| name_matches(pattern, name);
+    (include.is_empty() || include.iter().any(matches)) && !exclude.iter().any(matches)
+}
+
+/// Whether a `--sheets`/`--columns` style pattern matches a name
+pub fn name_matches(pattern: &str, name: &str) -> bool {
+    const OPTIONS: glob::MatchOptions = glob::MatchOptions {
+        case_sensitive: false,
+        require_literal_separator: false,
+        require_literal_leading_dot: false,
+    };
+    match glob::Pattern::new(pattern) {
+        Ok(glob) => glob.matches_with(name.trim(), OPTIONS),
+        Err(_) => pattern.eq_ignore_ascii_case(name.trim()),
+    }
+}
+
+fn default_l_diversity() -> u64 {
+    DEFAULT_L_DIVERSITY
+}
+
+fn default_max_unique_values() -> usize {
+    MAX_UNIQUE_VALUES
+}
+
+fn default_header_rows() -> usize {
+    1
+}
+
+/// Minimum share of non-missing values required of a column
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompletenessRule {
+    /// Column name (case-insensitive)
+    pub column: String,
+    /// Sheet (or CSV file name) the column is in; every sheet if not given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sheet: Option<String>,
+    /// Required share of non-missing values (percent)
+    pub min_percent: f64,
+}
+
+impl CompletenessRule {
+    pub fn matches(&self, sheet: &str, column: &str) -> bool {
+        column.eq_ignore_ascii_case(&self.column) && self.sheet.iter().all(|s| s == sheet)
+    }
+}
+
+/// Values a categorical column may take
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CodeListRule {
+    /// Column name (case-insensitive)
+    pub column: String,
+    /// Sheet (or CSV file name) the column is in; every sheet if not given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sheet: Option<String>,
+    /// Allowed values, compared exactly after trimming
+    pub allowed: Vec<String>,
+}
+
+impl CodeListRule {
+    pub fn matches(&self, sheet: &str, column: &str) -> bool {
+        column.eq_ignore_ascii_case(&self.column) && self.sheet.iter().all(|s| s == sheet)
+    }
+}
+
+/// Tokens counted as missing in one column, instead of the built-in and scan-wide ones
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MissingTokenRule {
+    /// Column name (case-insensitive)
+    pub column: String,
+    /// Sheet (or CSV file name) the column is in; every sheet if not given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sheet: Option<String>,
+    /// Missing-value tokens, compared case-insensitively after trimming
+    pub tokens: Vec<String>,
+}
+
+impl MissingTokenRule {
+    pub fn matches(&self, sheet: &str, column: &str) -> bool {
+        column.eq_ignore_ascii_case(&self.column) && self.sheet.iter().all(|s| s == sheet)
+    }
+}
+
+/// A reviewer's classification for one column
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClassificationOverride {
+    pub sheet: String,
+    /// Column index (0-based)
+    pub column_index: usize,
+    pub classification: Classification,
 }
 
 impl Default for ProcessingOptions {
@@ -268,6 +1446,42 @@ impl Default for ProcessingOptions {
             exact_median: false,
             hash_file: true,
             relaxed: false,
+            encoding: None,
+            header: HeaderMode::Auto,
+            header_rows: 1,
+            date_precision: DatePrecision::Month,
+            decimal_separator: None,
+            classification_overrides: Vec::new(),
+            completeness_rules: Vec::new(),
+            code_lists: Vec::new(),
+            missing_tokens: Vec::new(),
+            missing_token_rules: Vec::new(),
+            validation_rules: Vec::new(),
+            locales: Vec::new(),
+            auto_locales: false,
+            fixed_width_layout: None,
+            deep_scan: false,
+            content_aware: false,
+            cdisc: false,
+            examples: false,
+            redact_names: false,
+            quasi_identifier_sets: Vec::new(),
+            suggest_quasi_identifiers: false,
+            sensitive_columns: Vec::new(),
+            l_diversity: DEFAULT_L_DIVERSITY,
+            skip_hidden_sheets: false,
+            scan_comments: false,
+            sheets: Vec::new(),
+            exclude_sheets: Vec::new(),
+            columns: Vec::new(),
+            exclude_columns: Vec::new(),
+            no_values_for: Vec::new(),
+            no_values_columns: Vec::new(),
+            max_unique_values: MAX_UNIQUE_VALUES,
+            sample_rows: None,
+            sample_fraction: None,
+            subject_column: None,
+            subject_salt: HashSalt::random(),
         }
     }
 }
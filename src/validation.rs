@@ -0,0 +1,581 @@
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use chrono::NaiveDateTime;
+
+use crate::error::Error;
+use crate::inference::{is_missing, parse_date, parse_datetime, parse_numeric, MissingTokens};
+use crate::paths;
+use crate::privacy::safe_count;
+use crate::types::{CodeListRule, Result, ValidationResult, MAX_UNIQUE_VALUES};
+
+/// Read a validation rules file: one rule per line; blank lines and lines
+/// starting with `#` are ignored. Every rule is parsed so mistakes are
+/// reported before the scan starts.
+pub fn load_rules(path: &Path) -> Result<Vec<String>> {
+    let invalid = |problem: String| {
+        Error::InvalidInput(format!(
+            "validation rules {}: {}",
+            paths::display_path(path),
+            problem
+        ))
+    };
+    let text =
+        std::fs::read_to_string(paths::for_fs_access(path)).map_err(|e| invalid(e.to_string()))?;
+
+    let mut rules = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim().trim_start_matches('\u{feff}');
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        Rule::parse(line).map_err(|e| invalid(format!("line {}: {}", i + 1, e)))?;
+        rules.push(line.to_string());
+    }
+    Ok(rules)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompareOp {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+impl CompareOp {
+    fn holds(self, order: Ordering) -> bool {
+        match self {
+            CompareOp::Lt => order == Ordering::Less,
+            CompareOp::Le => order != Ordering::Greater,
+            CompareOp::Gt => order == Ordering::Greater,
+            CompareOp::Ge => order != Ordering::Less,
+            CompareOp::Eq => order == Ordering::Equal,
+            CompareOp::Ne => order != Ordering::Equal,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    Column(String),
+    /// A number or quoted text (dates are written as text: `'2020-01-01'`)
+    Literal(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Rule {
+    Compare(Operand, CompareOp, Operand),
+    Between(Operand, Operand, Operand),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    Text(String),
+    Number(String),
+    Op(CompareOp),
+}
+
+fn tokenize(text: &str) -> std::result::Result<Vec<Token>, String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '"' || c == '\'' {
+            let end = chars[i + 1..]
+                .iter()
+                .position(|&d| d == c)
+                .ok_or_else(|| format!("unterminated {} quote", c))?;
+            let inner: String = chars[i + 1..i + 1 + end].iter().collect();
+            tokens.push(if c == '"' {
+                Token::Quoted(inner)
+            } else {
+                Token::Text(inner)
+            });
+            i += end + 2;
+        } else if c.is_ascii_digit()
+            || (c == '-' && chars.get(i + 1).is_some_and(char::is_ascii_digit))
+        {
+            let start = i;
+            i += 1;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let number: String = chars[start..i].iter().collect();
+            if parse_numeric(&number).is_none() {
+                return Err(format!("invalid number '{}'", number));
+            }
+            tokens.push(Token::Number(number));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || matches!(chars[i], '_' | '.')) {
+                i += 1;
+            }
+            tokens.push(Token::Word(chars[start..i].iter().collect()));
+        } else {
+            let two: String = chars[i..(i + 2).min(chars.len())].iter().collect();
+            let (op, len) = match two.as_str() {
+                ">=" => (CompareOp::Ge, 2),
+                "<=" => (CompareOp::Le, 2),
+                "==" => (CompareOp::Eq, 2),
+                "!=" | "<>" => (CompareOp::Ne, 2),
+                _ => match c {
+                    '>' => (CompareOp::Gt, 1),
+                    '<' => (CompareOp::Lt, 1),
+                    '=' => (CompareOp::Eq, 1),
+                    _ => return Err(format!("unexpected '{}'", c)),
+                },
+            };
+            tokens.push(Token::Op(op));
+            i += len;
+        }
+    }
+    Ok(tokens)
+}
+
+fn is_keyword(token: &Token, keyword: &str) -> bool {
+    matches!(token, Token::Word(w) if w.eq_ignore_ascii_case(keyword))
+}
+
+impl Rule {
+    /// `a >= b` (also `<=`, `>`, `<`, `=`/`==`, `!=`/`<>`) or `a between x and y`.
+    /// Operands are column names (bare, or in double quotes), numbers, or
+    /// text in single quotes.
+    fn parse(text: &str) -> std::result::Result<Self, String> {
+        let tokens = tokenize(text)?;
+        let operand = |token: Option<&Token>| match token {
+            Some(Token::Word(w))
+                if !w.eq_ignore_ascii_case("between") && !w.eq_ignore_ascii_case("and") =>
+            {
+                Ok(Operand::Column(w.clone()))
+            }
+            Some(Token::Quoted(q)) => Ok(Operand::Column(q.clone())),
+            Some(Token::Text(t)) | Some(Token::Number(t)) => Ok(Operand::Literal(t.clone())),
+            _ => Err("expected a column name or a value".to_string()),
+        };
+
+        let left = operand(tokens.first())?;
+        let rule = match tokens.get(1) {
+            Some(Token::Op(op)) if tokens.len() == 3 => {
+                Rule::Compare(left, *op, operand(tokens.get(2))?)
+            }
+            Some(t)
+                if is_keyword(t, "between")
+                    && tokens.len() == 5
+                    && is_keyword(&tokens[3], "and") =>
+            {
+                Rule::Between(left, operand(tokens.get(2))?, operand(tokens.get(4))?)
+            }
+            _ => {
+                return Err("expected '<a> <op> <b>' or '<a> between <low> and <high>'".to_string())
+            }
+        };
+        if rule
+            .operands()
+            .iter()
+            .all(|o| matches!(o, Operand::Literal(_)))
+        {
+            return Err("a rule must name at least one column".to_string());
+        }
+        Ok(rule)
+    }
+
+    fn operands(&self) -> Vec<&Operand> {
+        match self {
+            Rule::Compare(a, _, b) => vec![a, b],
+            Rule::Between(a, low, high) => vec![a, low, high],
+        }
+    }
+}
+
+/// Compare two values as numbers, else as dates/datetimes. Other text can
+/// only be tested for equality; `None` if the values cannot be compared.
+fn compare_values(a: &str, b: &str, op: CompareOp) -> Option<bool> {
+    if let (Some(x), Some(y)) = (parse_numeric(a), parse_numeric(b)) {
+        return x.partial_cmp(&y).map(|o| op.holds(o));
+    }
+    let as_datetime = |v: &str| -> Option<NaiveDateTime> {
+        parse_datetime(v).or_else(|| parse_date(v).and_then(|d| d.and_hms_opt(0, 0, 0)))
+    };
+    if let (Some(x), Some(y)) = (as_datetime(a), as_datetime(b)) {
+        return Some(op.holds(x.cmp(&y)));
+    }
+    match op {
+        CompareOp::Eq | CompareOp::Ne => Some(op.holds(a.trim().cmp(b.trim()))),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone)]
+enum BoundOperand {
+    Column(usize),
+    Literal(String),
+}
+
+/// Result of checking one row
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Pass,
+    Violation,
+    /// A value is missing or the values cannot be compared
+    Skipped,
+}
+
+/// A rule resolved against one table's columns, counting outcomes over its rows
+#[derive(Debug, Clone)]
+struct BoundRule {
+    text: String,
+    rule: Rule,
+    operands: Vec<BoundOperand>,
+    checked: u64,
+    violations: u64,
+}
+
+impl BoundRule {
+    fn check(&self, record: &[String], missing: &[MissingTokens]) -> Outcome {
+        let mut values = Vec::with_capacity(self.operands.len());
+        for operand in &self.operands {
+            let (value, missing) = match operand {
+                BoundOperand::Column(i) => {
+                    (record.get(*i).map(String::as_str).unwrap_or(""), missing.get(*i))
+                }
+                BoundOperand::Literal(v) => (v.as_str(), None),
+            };
+            let is_missing = match missing {
+                Some(missing) => missing.is_missing(value),
+                None => is_missing(value),
+            };
+            if value.trim().is_empty() || is_missing {
+                return Outcome::Skipped;
+            }
+            values.push(value);
+        }
+
+        let holds = match self.rule {
+            Rule::Compare(_, op, _) => compare_values(values[0], values[1], op),
+            Rule::Between(..) => compare_values(values[0], values[1], CompareOp::Ge)
+                .zip(compare_values(values[0], values[2], CompareOp::Le))
+                .map(|(low, high)| low && high),
+        };
+        match holds {
+            Some(true) => Outcome::Pass,
+            Some(false) => Outcome::Violation,
+            None => Outcome::Skipped,
+        }
+    }
+}
+
+/// The validation rules that apply to one table, with their running counts
+#[derive(Debug, Clone, Default)]
+pub struct TableValidator {
+    bound: Vec<BoundRule>,
+    /// Missing values of each column; the built-in tokens when not given
+    missing: Vec<MissingTokens>,
+}
+
+impl TableValidator {
+    /// Bind every rule whose columns all exist in `headers` (case-insensitive)
+    pub fn new(rules: &[String], headers: &[String]) -> Result<Self> {
+        let mut bound = Vec::new();
+        for text in rules {
+            let rule = Rule::parse(text)
+                .map_err(|e| Error::InvalidInput(format!("validation rule '{}': {}", text, e)))?;
+            let operands: Option<Vec<BoundOperand>> = rule
+                .operands()
+                .into_iter()
+                .map(|o| match o {
+                    Operand::Column(name) => headers
+                        .iter()
+                        .position(|h| h.trim().eq_ignore_ascii_case(name))
+                        .map(BoundOperand::Column),
+                    Operand::Literal(v) => Some(BoundOperand::Literal(v.clone())),
+                })
+                .collect();
+            if let Some(operands) = operands {
+                bound.push(BoundRule {
+                    text: text.clone(),
+                    rule,
+                    operands,
+                    checked: 0,
+                    violations: 0,
+                });
+            }
+        }
+        Ok(Self {
+            bound,
+            missing: Vec::new(),
+        })
+    }
+
+    /// Skip values that are missing by each column's own tokens
+    pub fn with_missing_tokens(mut self, missing: Vec<MissingTokens>) -> Self {
+        self.missing = missing;
+        self
+    }
+
+    pub fn observe(&mut self, record: &[String]) {
+        for rule in &mut self.bound {
+            match rule.check(record, &self.missing) {
+                Outcome::Pass => rule.checked += 1,
+                Outcome::Violation => {
+                    rule.checked += 1;
+                    rule.violations += 1;
+                }
+                Outcome::Skipped => {}
+            }
+        }
+    }
+
+    /// Counts per rule; only the counts leave the scan, never the values
+    pub fn results(&self, sheet: &str, bucket_counts: bool) -> Vec<ValidationResult> {
+        self.bound
+            .iter()
+            .map(|rule| ValidationResult {
+                rule: rule.text.clone(),
+                sheet: Some(sheet.to_string()),
+                checked: Some(safe_count(rule.checked, bucket_counts)),
+                violations: Some(safe_count(rule.violations, bucket_counts)),
+                error: None,
+            })
+            .collect()
+    }
+}
+
+/// A column's values counted against its code lists
+#[derive(Debug, Clone, Default)]
+pub struct CodeListTracker {
+    /// Values allowed by every code list that names the column
+    allowed: HashSet<String>,
+    pub checked: u64,
+    pub out_of_list: u64,
+    /// Out-of-list values with their counts; new values stop being kept after
+    /// `MAX_UNIQUE_VALUES` but are still counted
+    pub values: HashMap<String, u64>,
+}
+
+impl CodeListTracker {
+    /// Tracker for a column, if any code list names it
+    pub fn new(rules: &[CodeListRule], sheet: &str, header: &str) -> Option<Self> {
+        let mut matching = rules.iter().filter(|r| r.matches(sheet, header.trim()));
+        let mut allowed: HashSet<String> = matching.next()?.allowed.iter().cloned().collect();
+        for rule in matching {
+            allowed.retain(|v| rule.allowed.contains(v));
+        }
+        Some(Self {
+            allowed,
+            ..Self::default()
+        })
+    }
+
+    /// Count one non-missing value
+    pub fn observe(&mut self, value: &str) {
+        let value = value.trim();
+        self.checked += 1;
+        if self.allowed.contains(value) {
+            return;
+        }
+        self.out_of_list += 1;
+        if let Some(count) = self.values.get_mut(value) {
+            *count += 1;
+        } else if self.values.len() < MAX_UNIQUE_VALUES {
+            self.values.insert(value.to_string(), 1);
+        }
+    }
+}
+
+/// Entries for rules that no table in the file could evaluate
+pub fn unmatched_rules(rules: &[String], results: &[ValidationResult]) -> Vec<ValidationResult> {
+    rules
+        .iter()
+        .filter(|text| !results.iter().any(|r| &r.rule == *text))
+        .map(|text| ValidationResult {
+            rule: text.clone(),
+            sheet: None,
+            checked: None,
+            violations: None,
+            error: Some("a column in the rule was not found".to_string()),
+        })
+        .collect()
+}
+
+/// Summary lines for stderr
+pub fn format_results(results: &[ValidationResult]) -> Vec<String> {
+    results
+        .iter()
+        .map(|r| match (&r.checked, &r.violations, &r.sheet) {
+            (Some(checked), Some(violations), Some(sheet)) => format!(
+                "Validation: {} violations in {} rows checked: {} (sheet '{}')",
+                violations, checked, r.rule, sheet
+            ),
+            _ => format!(
+                "Validation: not evaluated: {} ({})",
+                r.rule,
+                r.error.as_deref().unwrap_or("no result")
+            ),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::SafeValue;
+
+    fn row(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_rules() {
+        assert_eq!(
+            Rule::parse("discharge_date >= admission_date").unwrap(),
+            Rule::Compare(
+                Operand::Column("discharge_date".to_string()),
+                CompareOp::Ge,
+                Operand::Column("admission_date".to_string())
+            )
+        );
+        assert_eq!(
+            Rule::parse("age BETWEEN 0 and 120").unwrap(),
+            Rule::Between(
+                Operand::Column("age".to_string()),
+                Operand::Literal("0".to_string()),
+                Operand::Literal("120".to_string())
+            )
+        );
+        assert_eq!(
+            Rule::parse("\"visit date\" < '2030-01-01'").unwrap(),
+            Rule::Compare(
+                Operand::Column("visit date".to_string()),
+                CompareOp::Lt,
+                Operand::Literal("2030-01-01".to_string())
+            )
+        );
+        assert_eq!(
+            Rule::parse("temp != -1.5").unwrap(),
+            Rule::Compare(
+                Operand::Column("temp".to_string()),
+                CompareOp::Ne,
+                Operand::Literal("-1.5".to_string())
+            )
+        );
+
+        for bad in [
+            "age",
+            "age >=",
+            "age between 0",
+            "1 < 2",
+            "age ~ 3",
+            "name = 'x",
+            "age >= 1.2.3",
+        ] {
+            assert!(Rule::parse(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_validate_rows() {
+        let rules = vec![
+            "discharge_date >= admission_date".to_string(),
+            "age between 0 and 120".to_string(),
+            "sex = 'F'".to_string(),
+            "bmi > 10".to_string(),
+        ];
+        let headers = row(&["Age", "admission_date", "discharge_date", "sex"]);
+        let mut validator = TableValidator::new(&rules, &headers).unwrap();
+        assert_eq!(validator.bound.len(), 3);
+
+        for record in [
+            ["34", "2024-01-10", "2024-01-12", "F"],
+            ["130", "2024-02-01", "2024-01-28", "F"],
+            ["-1", "2024-03-01", "NA", "M"],
+            ["", "2024-03-01T08:00:00", "2024-03-01", "F"],
+            ["fifty", "unknown", "2024-03-05", "F"],
+        ] {
+            validator.observe(&row(&record));
+        }
+
+        let results = validator.results("t.csv", false);
+        let counts: Vec<(i64, i64)> = results
+            .iter()
+            .map(|r| match (&r.checked, &r.violations) {
+                (Some(SafeValue::Integer(c)), Some(SafeValue::Integer(v))) => (*c, *v),
+                other => panic!("unexpected counts {:?}", other),
+            })
+            .collect();
+        // Missing values and text that is neither a number nor a date are skipped
+        assert_eq!(counts, vec![(3, 2), (3, 2), (5, 1)]);
+
+        let unmatched = unmatched_rules(&rules, &results);
+        assert_eq!(unmatched.len(), 1);
+        assert_eq!(unmatched[0].rule, "bmi > 10");
+        assert!(unmatched[0].error.is_some());
+
+        let lines = format_results(&[results[0].clone(), unmatched[0].clone()]);
+        assert_eq!(
+            lines[0],
+            "Validation: 2 violations in 3 rows checked: discharge_date >= admission_date (sheet 't.csv')"
+        );
+        assert!(lines[1].starts_with("Validation: not evaluated: bmi > 10"));
+    }
+
+    #[test]
+    fn test_bucketed_counts() {
+        let rules = vec!["a < b".to_string()];
+        let mut validator = TableValidator::new(&rules, &row(&["a", "b"])).unwrap();
+        validator.observe(&row(&["2", "1"]));
+        let results = validator.results("t.csv", true);
+        assert_eq!(results[0].violations, Some(safe_count(1, true)));
+    }
+
+    #[test]
+    fn test_load_rules() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rules.txt");
+        std::fs::write(
+            &path,
+            "# Visit logic\nage between 0 and 120\n\nend >= start\n",
+        )
+        .unwrap();
+        assert_eq!(
+            load_rules(&path).unwrap(),
+            vec!["age between 0 and 120", "end >= start"]
+        );
+
+        std::fs::write(&path, "age between 0 and 120\nage >>= 3\n").unwrap();
+        let error = load_rules(&path).unwrap_err().to_string();
+        assert!(error.contains("line 2"), "{}", error);
+    }
+
+    #[test]
+    fn test_code_list_tracker() {
+        let rule = |sheet: Option<&str>, allowed: &[&str]| CodeListRule {
+            column: "sex".to_string(),
+            sheet: sheet.map(str::to_string),
+            allowed: allowed.iter().map(|v| v.to_string()).collect(),
+        };
+        let rules = vec![rule(None, &["M", "F", "U"]), rule(Some("dm.csv"), &["M", "F"])];
+        assert!(CodeListTracker::new(&rules, "dm.csv", "age").is_none());
+
+        let mut tracker = CodeListTracker::new(&rules, "dm.csv", "SEX").unwrap();
+        for value in ["M", "F ", "U", "male", "male"] {
+            tracker.observe(value);
+        }
+        assert_eq!(tracker.checked, 5);
+        // Every matching list applies, so U is out of list in dm.csv
+        assert_eq!(tracker.out_of_list, 3);
+        assert_eq!(tracker.values.get("male"), Some(&2));
+
+        let mut other = CodeListTracker::new(&rules, "ae.csv", "sex").unwrap();
+        other.observe("U");
+        assert_eq!(other.out_of_list, 0);
+    }
+}